@@ -1,11 +1,80 @@
+//! Audio server, devices and latency.
+//!
+//! Latency is worked out from the server's buffer: PipeWire's quantum and
+//! rate from its `settings` metadata (a forced quantum or rate wins over
+//! the default), or JACK's buffer size and sample rate. Audio is buffered
+//! once on capture and once on playback, so the round trip is about twice
+//! the quantum; converters and the interface itself add a millisecond or
+//! two on top. A loopback measurement with `jack_iodelay` gives the real
+//! figure, but needs an output cabled back to an input, so it only runs
+//! when asked for.
+
+use super::{Priority, Recommendation, RecommendationCategory};
 use serde::{Deserialize, Serialize};
-use std::process::Command;
+use std::process::{Command, Stdio};
+use std::thread;
+use std::time::Duration;
+
+/// Round trip above which recording through monitoring is noticeably late
+pub const RECORDING_ROUND_TRIP_MS: f64 = 12.0;
+
+/// How long `jack_iodelay` gets to lock onto its test signal
+const LOOPBACK_MEASURE_TIME: Duration = Duration::from_secs(3);
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct AudioInfo {
     pub server: AudioServer,
     pub devices: Vec<AudioDevice>,
+    /// Realtime scheduling is available, through the audio or realtime
+    /// group or rtkit
     pub latency_capable: bool,
+    /// `None` without PipeWire or JACK, or when they can't be queried
+    #[serde(default)]
+    pub latency: Option<AudioLatency>,
+}
+
+#[derive(Debug, Clone, Copy, Serialize, Deserialize, PartialEq)]
+pub struct AudioLatency {
+    /// Frames per buffer
+    pub quantum: u32,
+    pub rate: u32,
+    /// Capture plus playback buffering
+    pub round_trip_ms: f64,
+    /// From a loopback measurement, when one was made and found its signal
+    pub measured_round_trip_ms: Option<f64>,
+}
+
+impl AudioLatency {
+    fn new(quantum: u32, rate: u32) -> Option<Self> {
+        if quantum == 0 || rate == 0 {
+            return None;
+        }
+        Some(Self {
+            quantum,
+            rate,
+            round_trip_ms: buffer_round_trip_ms(quantum, rate),
+            measured_round_trip_ms: None,
+        })
+    }
+
+    /// The measured round trip when there is one
+    pub fn effective_ms(&self) -> f64 {
+        self.measured_round_trip_ms.unwrap_or(self.round_trip_ms)
+    }
+
+    /// Largest power-of-two quantum that keeps buffering within
+    /// [`RECORDING_ROUND_TRIP_MS`] at this rate
+    pub fn recording_quantum(&self) -> u32 {
+        let mut quantum = 32;
+        while buffer_round_trip_ms(quantum * 2, self.rate) <= RECORDING_ROUND_TRIP_MS {
+            quantum *= 2;
+        }
+        quantum
+    }
+}
+
+fn buffer_round_trip_ms(quantum: u32, rate: u32) -> f64 {
+    2.0 * quantum as f64 / rate as f64 * 1000.0
 }
 
 #[derive(Debug, Clone, Copy, Serialize, Deserialize, PartialEq, Eq)]
@@ -34,14 +103,160 @@ pub fn detect() -> AudioInfo {
     let server = detect_server();
     let devices = detect_devices(&server);
     let latency_capable = check_realtime_capable();
+    let latency = detect_latency(server);
     
     AudioInfo {
         server,
         devices,
         latency_capable,
+        latency,
+    }
+}
+
+/// [`detect`], then a loopback measurement through the first physical
+/// output and input. Play nothing else meanwhile; without a loopback cable
+/// the measurement finds no signal and only the estimate is reported.
+pub fn detect_measuring_latency() -> AudioInfo {
+    let mut info = detect();
+    if let Some(ref mut latency) = info.latency {
+        latency.measured_round_trip_ms = measure_round_trip(info.server);
+    }
+    info
+}
+
+fn detect_latency(server: AudioServer) -> Option<AudioLatency> {
+    match server {
+        AudioServer::PipeWire => {
+            let output = Command::new("pw-metadata").args(["-n", "settings"]).output().ok()?;
+            let settings = parse_pw_metadata(&String::from_utf8_lossy(&output.stdout));
+            let setting = |key: &str| {
+                settings.iter()
+                    .find(|(k, _)| k == key)
+                    .and_then(|(_, v)| v.parse::<u32>().ok())
+            };
+            // A forced value of 0 means not forced
+            let forced = |key: &str| setting(key).filter(|&v| v > 0);
+            let quantum = forced("clock.force-quantum").or_else(|| setting("clock.quantum"))?;
+            let rate = forced("clock.force-rate").or_else(|| setting("clock.rate"))?;
+            AudioLatency::new(quantum, rate)
+        }
+        AudioServer::Jack => {
+            let quantum = last_number(&command_output("jack_bufsize", &[])?)?;
+            let rate = last_number(&command_output("jack_samplerate", &[])?)?;
+            AudioLatency::new(quantum, rate)
+        }
+        _ => None,
     }
 }
 
+/// `key:'clock.quantum' value:'1024'` pairs from `pw-metadata` output
+fn parse_pw_metadata(output: &str) -> Vec<(String, String)> {
+    let quoted = |line: &str, field: &str| {
+        let start = line.find(field)? + field.len();
+        let rest = &line[start..];
+        Some(rest[..rest.find('\'')?].to_string())
+    };
+    output.lines()
+        .filter_map(|line| Some((quoted(line, "key:'")?, quoted(line, "value:'")?)))
+        .collect()
+}
+
+fn last_number(text: &str) -> Option<u32> {
+    text.split(|c: char| !c.is_ascii_digit())
+        .rfind(|s| !s.is_empty())?
+        .parse()
+        .ok()
+}
+
+fn command_output(program: &str, args: &[&str]) -> Option<String> {
+    let output = Command::new(program).args(args).output().ok()?;
+    output.status.success().then(|| String::from_utf8_lossy(&output.stdout).into_owned())
+}
+
+/// JACK tools run against PipeWire through `pw-jack`
+fn jack_tool(server: AudioServer, program: &str) -> Command {
+    if server == AudioServer::PipeWire {
+        let mut command = Command::new("pw-jack");
+        command.arg(program);
+        command
+    } else {
+        Command::new(program)
+    }
+}
+
+/// Round trip through a loopback cable, timed by `jack_iodelay` sending a
+/// test signal out of the first physical playback port and listening for
+/// it on the first physical capture port
+pub fn measure_round_trip(server: AudioServer) -> Option<f64> {
+    if !matches!(server, AudioServer::PipeWire | AudioServer::Jack) {
+        return None;
+    }
+
+    let ports = jack_tool(server, "jack_lsp").arg("-p").output().ok()?;
+    let (capture, playback) = physical_ports(&String::from_utf8_lossy(&ports.stdout))?;
+
+    let mut iodelay = jack_tool(server, "jack_iodelay")
+        .stdout(Stdio::piped())
+        .stderr(Stdio::null())
+        .spawn()
+        .ok()?;
+    // The client needs a moment to register its ports
+    thread::sleep(Duration::from_millis(500));
+    let connected = [("jack_delay:out", playback.as_str()), (capture.as_str(), "jack_delay:in")]
+        .iter()
+        .all(|(from, to)| {
+            jack_tool(server, "jack_connect").args([from, to])
+                .stdout(Stdio::null())
+                .stderr(Stdio::null())
+                .status()
+                .map(|s| s.success())
+                .unwrap_or(false)
+        });
+    if connected {
+        thread::sleep(LOOPBACK_MEASURE_TIME);
+    }
+    let _ = iodelay.kill();
+    let output = iodelay.wait_with_output().ok()?;
+    if !connected {
+        return None;
+    }
+    parse_iodelay(&String::from_utf8_lossy(&output.stdout))
+}
+
+/// First physical capture and playback ports from `jack_lsp -p`. Capture
+/// ports are outputs to the JACK graph and playback ports inputs.
+fn physical_ports(listing: &str) -> Option<(String, String)> {
+    let mut capture = None;
+    let mut playback = None;
+    let mut port = None;
+    for line in listing.lines() {
+        match line.trim().strip_prefix("properties: ") {
+            Some(properties) if properties.contains("physical") => {
+                let slot = if properties.starts_with("output") { &mut capture } else { &mut playback };
+                if slot.is_none() {
+                    *slot = port.take();
+                }
+            }
+            Some(_) => {}
+            None => port = Some(line.trim().to_string()),
+        }
+    }
+    Some((capture?, playback?))
+}
+
+/// The last `… ms total roundtrip latency` line; `jack_iodelay` prints one
+/// a second once it hears its signal and nothing usable before
+fn parse_iodelay(output: &str) -> Option<f64> {
+    output.lines()
+        .rev()
+        .filter(|line| line.contains("total roundtrip latency"))
+        .find_map(|line| {
+            let words: Vec<&str> = line.split_whitespace().collect();
+            let ms = words.iter().position(|w| *w == "ms")?;
+            words.get(ms.checked_sub(1)?)?.parse().ok()
+        })
+}
+
 fn detect_server() -> AudioServer {
     // Check PipeWire first
     if Command::new("pgrep").arg("pipewire").output()
@@ -141,3 +356,46 @@ fn check_realtime_capable() -> bool {
     Command::new("pgrep").arg("rtkit-daemon").output()
         .map(|o| o.status.success()).unwrap_or(false)
 }
+
+/// Realtime scheduling and a smaller quantum when the audio workflow is
+/// suggested but the system isn't set up for tracking
+pub fn get_recommendations(audio: &AudioInfo, workflows: &[&str]) -> Vec<Recommendation> {
+    let mut recs = Vec::new();
+    if !workflows.contains(&"audio") {
+        return recs;
+    }
+
+    if !audio.latency_capable {
+        recs.push(Recommendation {
+            category: RecommendationCategory::Configuration,
+            title: "Realtime Audio Scheduling".to_string(),
+            description: "Without realtime priority the audio thread competes with everything else and drops out under load. realtime-privileges grants it to the realtime group; rtkit hands it out to PipeWire on request.".to_string(),
+            action: Some("sudo pacman -S realtime-privileges rtkit && sudo usermod -aG realtime $USER".to_string()),
+            priority: Priority::High,
+        });
+    }
+
+    if let Some(latency) = audio.latency.filter(|l| l.effective_ms() > RECORDING_ROUND_TRIP_MS) {
+        let quantum = latency.recording_quantum();
+        let measured = if latency.measured_round_trip_ms.is_some() { "measured" } else { "estimated" };
+        let description = format!(
+            "{}/{} gives a {:.1} ms {} round trip; above {:.0} ms, monitoring while recording is audibly late. A quantum of {} brings buffering down to {:.1} ms.",
+            latency.quantum, latency.rate, latency.effective_ms(), measured,
+            RECORDING_ROUND_TRIP_MS, quantum, buffer_round_trip_ms(quantum, latency.rate)
+        );
+        let action = match audio.server {
+            AudioServer::PipeWire => Some(format!("pw-metadata -n settings 0 clock.force-quantum {}", quantum)),
+            // jackd takes the buffer size at startup
+            _ => None,
+        };
+        recs.push(Recommendation {
+            category: RecommendationCategory::Performance,
+            title: "High Audio Latency".to_string(),
+            description,
+            action,
+            priority: Priority::Medium,
+        });
+    }
+
+    recs
+}
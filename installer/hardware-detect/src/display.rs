@@ -1,6 +1,42 @@
+pub mod session;
+
 use serde::{Deserialize, Serialize};
 use std::fs;
 use std::path::Path;
+use std::process::Command;
+
+use crate::{Priority, Recommendation, RecommendationCategory};
+
+/// Panels this much larger than sRGB in CIE xy are treated as wide gamut;
+/// "99% sRGB" panels land just above 1.0, Adobe RGB and P3 panels near 1.4
+const WIDE_GAMUT_AREA: f32 = 1.15;
+
+const SRGB_PRIMARIES: [(f32, f32); 3] = [(0.64, 0.33), (0.30, 0.60), (0.15, 0.06)];
+
+/// PNP vendor ID, model series and the product line it belongs to.
+/// A series matches a word of the EDID monitor name that starts with it
+/// and continues with a digit, so `SW` matches `BenQ SW271C` but not `SWIFT`
+const PROFESSIONAL_MODELS: &[(&str, &str, &str)] = &[
+    ("ENC", "CG", "EIZO ColorEdge"),
+    ("ENC", "CS", "EIZO ColorEdge"),
+    ("ENC", "CX", "EIZO ColorEdge"),
+    ("BNQ", "SW", "BenQ SW"),
+    ("DEL", "UP", "Dell UltraSharp PremierColor"),
+    ("AUS", "PA", "ASUS ProArt"),
+    ("NEC", "PA", "NEC MultiSync PA"),
+    ("SHP", "PA", "Sharp NEC MultiSync PA"),
+    ("HWP", "Z24x", "HP DreamColor"),
+    ("HWP", "Z27x", "HP DreamColor"),
+    ("HWP", "Z31x", "HP DreamColor"),
+    ("VSC", "VP", "ViewSonic ColorPro"),
+];
+
+/// Name fragments for lines without a usable series prefix
+const PROFESSIONAL_NAMES: &[(&str, &str, &str)] = &[
+    ("HWP", "DreamColor", "HP DreamColor"),
+    ("APP", "Pro Display XDR", "Apple Pro Display XDR"),
+    ("GSM", "EP950", "LG UltraFine OLED Pro"),
+];
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct DisplayInfo {
@@ -10,10 +46,57 @@ pub struct DisplayInfo {
     pub refresh_rate: Option<f32>,
     pub hdr_capable: bool,
     pub wide_gamut: bool,
+    /// Three-letter PNP manufacturer ID from the EDID, e.g. `ENC` for EIZO
+    #[serde(default)]
+    pub vendor: Option<String>,
+    /// Monitor name from the EDID
+    #[serde(default)]
+    pub model: Option<String>,
+    /// Gamut area of the EDID primaries in CIE xy, relative to sRGB
+    #[serde(default)]
+    pub gamut_area: Option<f32>,
+    /// Professional line the model belongs to, e.g. "EIZO ColorEdge"
+    #[serde(default)]
+    pub professional: Option<String>,
+    /// Whether colord has a profile assigned to it; `None` when colord
+    /// isn't running or doesn't know the display
+    #[serde(default)]
+    pub profiled: Option<bool>,
+}
+
+impl DisplayInfo {
+    /// Output name the compositor and `rururu-colorcal --display` use:
+    /// `card0-DP-1` in sysfs is `DP-1`
+    pub fn output(&self) -> &str {
+        self.name.split_once('-').map(|(_, o)| o).unwrap_or(&self.name)
+    }
+    
+    pub fn label(&self) -> String {
+        match &self.model {
+            Some(model) => format!("{} ({})", model, self.output()),
+            None => self.output().to_string(),
+        }
+    }
+    
+    /// Worth calibrating with a colorimeter: a professional model, or a
+    /// panel whose gamut sRGB content can't be shown on without a profile
+    pub fn color_critical(&self) -> bool {
+        self.professional.is_some() || self.wide_gamut
+    }
+}
+
+/// What the EDID says about a display
+#[derive(Debug, Clone, Default)]
+struct Edid {
+    vendor: Option<String>,
+    model: Option<String>,
+    primaries: Option<[(f32, f32); 3]>,
+    hdr_metadata: bool,
 }
 
 pub fn detect() -> Vec<DisplayInfo> {
     let mut displays = Vec::new();
+    let colord = colord_devices();
     
     let drm_path = Path::new("/sys/class/drm");
     if let Ok(entries) = fs::read_dir(drm_path) {
@@ -50,22 +133,31 @@ pub fn detect() -> Vec<DisplayInfo> {
             };
             
             // Check EDID for HDR/wide gamut
-            let (hdr_capable, wide_gamut) = if let Ok(edid) = 
-                fs::read(connector_path.join("edid")) 
-            {
-                parse_edid_capabilities(&edid)
-            } else {
-                (false, false)
+            let edid = fs::read(connector_path.join("edid"))
+                .ok()
+                .and_then(|edid| parse_edid(&edid))
+                .unwrap_or_default();
+            let gamut_area = edid.primaries.map(|p| triangle_area(&p) / triangle_area(&SRGB_PRIMARIES));
+            let professional = match (&edid.vendor, &edid.model) {
+                (Some(vendor), Some(model)) => professional_line(vendor, model).map(str::to_string),
+                _ => None,
             };
             
-            displays.push(DisplayInfo {
+            let mut display = DisplayInfo {
                 name,
                 connector,
                 resolution,
                 refresh_rate,
-                hdr_capable,
-                wide_gamut,
-            });
+                hdr_capable: edid.hdr_metadata,
+                wide_gamut: gamut_area.is_some_and(|a| a >= WIDE_GAMUT_AREA),
+                vendor: edid.vendor,
+                model: edid.model,
+                gamut_area,
+                professional,
+                profiled: None,
+            };
+            display.profiled = colord.as_deref().and_then(|devices| profiled(devices, &display));
+            displays.push(display);
         }
     }
     
@@ -92,12 +184,173 @@ fn parse_mode(mode: &str) -> (Option<(u32, u32)>, Option<f32>) {
     (resolution, refresh)
 }
 
-fn parse_edid_capabilities(edid: &[u8]) -> (bool, bool) {
-    // Simplified EDID parsing
-    // Real implementation would parse extension blocks for HDR metadata
+fn parse_edid(edid: &[u8]) -> Option<Edid> {
+    const HEADER: [u8; 8] = [0x00, 0xff, 0xff, 0xff, 0xff, 0xff, 0xff, 0x00];
+    if edid.len() < 128 || edid[..8] != HEADER {
+        return None;
+    }
+    
+    // Bytes 8-9: three 5-bit letters, 1 = 'A'
+    let id = u16::from_be_bytes([edid[8], edid[9]]);
+    let vendor: String = [10, 5, 0]
+        .iter()
+        .map(|shift| (b'@' + ((id >> shift) & 0x1f) as u8) as char)
+        .collect();
+    let vendor = vendor.chars().all(|c| c.is_ascii_uppercase()).then_some(vendor);
+    
+    // Four 18-byte descriptors; 0xfc holds the monitor name
+    let model = (0..4)
+        .map(|i| &edid[54 + i * 18..72 + i * 18])
+        .find(|d| d[..3] == [0, 0, 0] && d[3] == 0xfc)
+        .map(|d| {
+            let name = &d[5..];
+            let end = name.iter().position(|&b| b == 0x0a).unwrap_or(name.len());
+            String::from_utf8_lossy(&name[..end]).trim().to_string()
+        })
+        .filter(|name| !name.is_empty());
+    
+    // Bytes 25-34: 10-bit chromaticity, low bits packed into 25 and 26
+    let chroma = |high: usize, low_byte: usize, shift: u8| {
+        let value = (u16::from(edid[high]) << 2) | u16::from((edid[low_byte] >> shift) & 0x03);
+        f32::from(value) / 1024.0
+    };
+    let primaries = [
+        (chroma(27, 25, 6), chroma(28, 25, 4)),
+        (chroma(29, 25, 2), chroma(30, 25, 0)),
+        (chroma(31, 26, 6), chroma(32, 26, 4)),
+    ];
+    let primaries = (triangle_area(&primaries) > 0.01).then_some(primaries);
     
-    let hdr = edid.len() > 128; // Has extension blocks (might contain HDR info)
-    let wide_gamut = edid.len() > 128; // Placeholder
+    // CTA-861 extensions: an HDR static metadata data block means the
+    // display takes PQ/HLG signals
+    let hdr_metadata = edid[128..].chunks_exact(128).any(|block| {
+        if block[0] != 0x02 {
+            return false;
+        }
+        let end = usize::from(block[2]).min(127);
+        let mut offset = 4;
+        while offset < end {
+            let tag = block[offset] >> 5;
+            let len = usize::from(block[offset] & 0x1f);
+            // Extended tag 6: HDR static metadata
+            if tag == 7 && len > 0 && block.get(offset + 1) == Some(&6) {
+                return true;
+            }
+            offset += len + 1;
+        }
+        false
+    });
+    
+    Some(Edid { vendor, model, primaries, hdr_metadata })
+}
+
+fn triangle_area(p: &[(f32, f32); 3]) -> f32 {
+    let [(rx, ry), (gx, gy), (bx, by)] = *p;
+    ((gx - rx) * (by - ry) - (bx - rx) * (gy - ry)).abs() / 2.0
+}
+
+fn professional_line(vendor: &str, model: &str) -> Option<&'static str> {
+    let series = PROFESSIONAL_MODELS.iter().find(|(v, prefix, _)| {
+        *v == vendor && model.split_whitespace().any(|word| {
+            word.strip_prefix(prefix)
+                .is_some_and(|rest| rest.is_empty() || rest.starts_with(|c: char| c.is_ascii_digit()))
+        })
+    });
+    series
+        .or_else(|| PROFESSIONAL_NAMES.iter().find(|(v, name, _)| *v == vendor && model.contains(name)))
+        .map(|(_, _, line)| *line)
+}
+
+/// A display device colord knows about
+struct ColordDevice {
+    output: Option<String>,
+    model: Option<String>,
+    profiled: bool,
+}
+
+/// Display devices from `colormgr`; `None` when colord isn't available.
+/// Devices are blank-line separated blocks of `Key: value` lines
+fn colord_devices() -> Option<Vec<ColordDevice>> {
+    let output = Command::new("colormgr")
+        .args(["get-devices-by-kind", "display"])
+        .output()
+        .ok()
+        .filter(|o| o.status.success())?;
+    let stdout = String::from_utf8_lossy(&output.stdout);
+    
+    let devices = stdout
+        .split("\n\n")
+        .filter(|block| !block.trim().is_empty())
+        .map(|block| {
+            let value = |key: &str| {
+                block.lines()
+                    .find_map(|line| line.trim().strip_prefix(key))
+                    .map(|v| v.trim().to_string())
+            };
+            ColordDevice {
+                // Set by GNOME and KDE as `Metadata: XRANDR_name=DP-1`,
+                // possibly on a continuation line
+                output: block.split_whitespace()
+                    .find_map(|word| word.strip_prefix("XRANDR_name="))
+                    .map(str::to_string),
+                model: value("Model:"),
+                profiled: block.lines().any(|line| line.trim_start().starts_with("Profile ")),
+            }
+        })
+        .collect();
+    Some(devices)
+}
+
+fn profiled(devices: &[ColordDevice], display: &DisplayInfo) -> Option<bool> {
+    devices.iter()
+        .find(|d| d.output.as_deref() == Some(display.output()))
+        .or_else(|| devices.iter().find(|d| d.model.is_some() && d.model == display.model))
+        .map(|d| d.profiled)
+}
+
+pub fn get_recommendations(displays: &[DisplayInfo]) -> Vec<Recommendation> {
+    let mut recs = Vec::new();
+    
+    for display in displays.iter().filter(|d| d.color_critical()) {
+        let action = Some(format!("rururu-colorcal --display {}", display.output()));
+        let gamut = display.gamut_area
+            .filter(|_| display.wide_gamut)
+            .map(|area| format!("a gamut {:.0}% the size of sRGB", area * 100.0));
+        
+        // Native mode maps sRGB values straight onto the wider primaries
+        if display.wide_gamut && display.profiled == Some(false) {
+            recs.push(Recommendation {
+                category: RecommendationCategory::Configuration,
+                title: format!("No Color Profile for {}", display.label()),
+                description: format!(
+                    "{} has {} but no profile, so sRGB content (web pages, photos, UI) shows over-saturated. Calibrate it, or switch the monitor to its sRGB mode.",
+                    display.label(),
+                    gamut.as_deref().unwrap_or("a wide gamut"),
+                ),
+                action,
+                priority: Priority::High,
+            });
+            continue;
+        }
+        
+        let reason = match (&display.professional, &gamut) {
+            (Some(line), Some(gamut)) => format!("a {} display with {}", line, gamut),
+            (Some(line), None) => format!("a {} display", line),
+            (None, Some(gamut)) => format!("a display with {}", gamut),
+            (None, None) => continue,
+        };
+        recs.push(Recommendation {
+            category: RecommendationCategory::Workflow,
+            title: format!("Calibrate {}", display.label()),
+            description: format!(
+                "{} is {}. Calibrate it with a colorimeter so color grading, photo and print work on it can be trusted.",
+                display.label(),
+                reason,
+            ),
+            action,
+            priority: if display.profiled == Some(true) { Priority::Low } else { Priority::Medium },
+        });
+    }
     
-    (hdr, wide_gamut)
+    recs
 }
@@ -0,0 +1,238 @@
+use serde::{Deserialize, Serialize};
+use std::env;
+use std::process::Command;
+
+use crate::{Priority, Recommendation, RecommendationCategory};
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct SessionInfo {
+    pub session_type: SessionType,
+    pub compositor: Compositor,
+    pub desktop: Option<String>,
+    pub protocols: ProtocolSupport,
+}
+
+#[derive(Debug, Clone, Copy, Serialize, Deserialize, PartialEq, Eq)]
+pub enum SessionType {
+    Wayland,
+    X11,
+    Tty,
+    Unknown,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq, Eq)]
+pub enum Compositor {
+    KWin,
+    Mutter,
+    Hyprland,
+    Sway,
+    Xorg,
+    Other(String),
+    Unknown,
+}
+
+/// Display protocols the rururu color tools rely on.
+#[derive(Debug, Clone, Copy, Default, Serialize, Deserialize, PartialEq, Eq)]
+pub struct ProtocolSupport {
+    /// Per-output gamma ramps (wlr-gamma-control, XRandR, or compositor-managed ICC)
+    pub gamma_control: bool,
+    /// Changing modes/scale/position of outputs
+    pub output_management: bool,
+    /// HDR / wide-gamut output
+    pub hdr: bool,
+}
+
+impl Compositor {
+    pub fn name(&self) -> &str {
+        match self {
+            Compositor::KWin => "KWin",
+            Compositor::Mutter => "Mutter",
+            Compositor::Hyprland => "Hyprland",
+            Compositor::Sway => "sway",
+            Compositor::Xorg => "Xorg",
+            Compositor::Other(name) => name,
+            Compositor::Unknown => "unknown",
+        }
+    }
+}
+
+pub fn detect() -> SessionInfo {
+    let session_type = detect_session_type();
+    let desktop = env::var("XDG_CURRENT_DESKTOP").ok().filter(|d| !d.is_empty());
+    let compositor = detect_compositor(session_type, desktop.as_deref());
+    let protocols = protocol_support(session_type, &compositor);
+    
+    SessionInfo {
+        session_type,
+        compositor,
+        desktop,
+        protocols,
+    }
+}
+
+fn detect_session_type() -> SessionType {
+    match env::var("XDG_SESSION_TYPE").unwrap_or_default().as_str() {
+        "wayland" => return SessionType::Wayland,
+        "x11" => return SessionType::X11,
+        "tty" => return SessionType::Tty,
+        _ => {}
+    }
+    
+    // Fall back to the display sockets for sessions started without logind
+    if env::var_os("WAYLAND_DISPLAY").is_some() {
+        SessionType::Wayland
+    } else if env::var_os("DISPLAY").is_some() {
+        SessionType::X11
+    } else {
+        SessionType::Unknown
+    }
+}
+
+fn detect_compositor(session_type: SessionType, desktop: Option<&str>) -> Compositor {
+    if env::var_os("HYPRLAND_INSTANCE_SIGNATURE").is_some() {
+        return Compositor::Hyprland;
+    }
+    if env::var_os("SWAYSOCK").is_some() {
+        return Compositor::Sway;
+    }
+    
+    if let Some(desktop) = desktop {
+        let desktop = desktop.to_lowercase();
+        if desktop.contains("kde") {
+            return if session_type == SessionType::X11 { Compositor::Xorg } else { Compositor::KWin };
+        }
+        if desktop.contains("gnome") {
+            return if session_type == SessionType::X11 { Compositor::Xorg } else { Compositor::Mutter };
+        }
+    }
+    
+    let processes = [
+        ("kwin_wayland", Compositor::KWin),
+        ("gnome-shell", Compositor::Mutter),
+        ("Hyprland", Compositor::Hyprland),
+        ("sway", Compositor::Sway),
+    ];
+    for (process, compositor) in processes {
+        if Command::new("pgrep").args(["-x", process]).output()
+            .map(|o| o.status.success()).unwrap_or(false)
+        {
+            return compositor;
+        }
+    }
+    
+    match (session_type, desktop) {
+        (SessionType::X11, _) => Compositor::Xorg,
+        (_, Some(desktop)) => Compositor::Other(desktop.to_string()),
+        _ => Compositor::Unknown,
+    }
+}
+
+pub fn protocol_support(session_type: SessionType, compositor: &Compositor) -> ProtocolSupport {
+    match (session_type, compositor) {
+        // XRandR covers gamma ramps and output config; no HDR on X11
+        (SessionType::X11, _) | (_, Compositor::Xorg) => ProtocolSupport {
+            gamma_control: true,
+            output_management: true,
+            hdr: false,
+        },
+        // Gamma is applied by KWin from the output's ICC profile
+        (_, Compositor::KWin) => ProtocolSupport {
+            gamma_control: true,
+            output_management: true,
+            hdr: true,
+        },
+        // Mutter applies colord profiles itself; HDR is still experimental
+        (_, Compositor::Mutter) => ProtocolSupport {
+            gamma_control: true,
+            output_management: true,
+            hdr: false,
+        },
+        (_, Compositor::Hyprland) => ProtocolSupport {
+            gamma_control: true,
+            output_management: true,
+            hdr: true,
+        },
+        (_, Compositor::Sway) => ProtocolSupport {
+            gamma_control: true,
+            output_management: true,
+            hdr: false,
+        },
+        _ => ProtocolSupport::default(),
+    }
+}
+
+/// Protocols a workflow (as named by `rururu-workflow activate`) relies on.
+/// Of the workflows `suggest_workflows` recommends, only video needs the
+/// display session; audio needs nothing from it.
+pub fn required_protocols(workflow: &str) -> ProtocolSupport {
+    match workflow {
+        "video" => ProtocolSupport { gamma_control: true, output_management: false, hdr: true },
+        _ => ProtocolSupport::default(),
+    }
+}
+
+impl SessionInfo {
+    pub fn missing_protocols(&self, required: &ProtocolSupport) -> Vec<&'static str> {
+        let mut missing = Vec::new();
+        
+        if required.gamma_control && !self.protocols.gamma_control {
+            missing.push("gamma control");
+        }
+        if required.output_management && !self.protocols.output_management {
+            missing.push("output management");
+        }
+        if required.hdr && !self.protocols.hdr {
+            missing.push("HDR");
+        }
+        
+        missing
+    }
+}
+
+pub fn get_recommendations(
+    session: &SessionInfo,
+    displays: &[super::DisplayInfo],
+    workflows: &[&str],
+) -> Vec<Recommendation> {
+    let mut recs = Vec::new();
+    
+    if session.session_type == SessionType::Tty || session.session_type == SessionType::Unknown {
+        return recs;
+    }
+    
+    for workflow in workflows {
+        let missing = session.missing_protocols(&required_protocols(workflow));
+        if missing.is_empty() {
+            continue;
+        }
+        
+        recs.push(Recommendation {
+            category: RecommendationCategory::Configuration,
+            title: format!("Display Session Lacks {}", missing.join(", ")),
+            description: format!(
+                "The {} workflow relies on {}, which {} does not provide. Color tools will fall back to reduced functionality.",
+                workflow,
+                missing.join(" and "),
+                session.compositor.name()
+            ),
+            action: Some("Use a Plasma (KWin) Wayland session for full color management".to_string()),
+            priority: Priority::Medium,
+        });
+    }
+    
+    if displays.iter().any(|d| d.hdr_capable) && !session.protocols.hdr {
+        recs.push(Recommendation {
+            category: RecommendationCategory::Configuration,
+            title: "HDR Display Without HDR Session".to_string(),
+            description: format!(
+                "An HDR-capable display is connected, but {} ({:?}) cannot drive it in HDR mode.",
+                session.compositor.name(),
+                session.session_type
+            ),
+            action: None,
+            priority: Priority::Low,
+        });
+    }
+    
+    recs
+}
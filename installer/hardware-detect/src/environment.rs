@@ -0,0 +1,277 @@
+//! Whether the system runs on bare metal, in a virtual machine, in a
+//! container or inside a Flatpak sandbox.
+//!
+//! `systemd-detect-virt` answers when it's installed. Without it (as in
+//! most containers) the same markers it checks are read directly:
+//! `/.dockerenv`, `/run/.containerenv`, the `container=` variable init
+//! systems hand PID 1, a Microsoft kernel for WSL, then the DMI vendor
+//! strings and CPUID hypervisor flag virtual machines expose. Flatpak sets
+//! `FLATPAK_ID` and mounts `/.flatpak-info` in every sandbox.
+//!
+//! Off bare metal, microcode, device firmware and CPU frequency tuning
+//! belong to the host, and a container or sandbox shares the host's kernel
+//! too, so [`EnvironmentInfo::adjust`] drops advice that can't be acted on.
+
+use super::{Priority, Recommendation, RecommendationCategory};
+use serde::{Deserialize, Serialize};
+use std::env;
+use std::fs;
+use std::path::Path;
+use std::process::Command;
+
+/// DMI vendor and product strings of hypervisors, with the name
+/// `systemd-detect-virt` reports for each
+const HYPERVISORS: &[(&str, &str)] = &[
+    ("QEMU", "qemu"),
+    ("KVM", "kvm"),
+    ("VMware", "vmware"),
+    ("VirtualBox", "oracle"),
+    ("innotek", "oracle"),
+    ("Xen", "xen"),
+    ("Parallels", "parallels"),
+    ("Microsoft Corporation Virtual Machine", "microsoft"),
+    ("Amazon EC2", "amazon"),
+    ("Google Compute Engine", "google"),
+    ("BHYVE", "bhyve"),
+];
+
+#[derive(Debug, Clone, Copy, Default, Serialize, Deserialize, PartialEq, Eq)]
+pub enum EnvironmentKind {
+    #[default]
+    BareMetal,
+    VirtualMachine,
+    Container,
+    Flatpak,
+}
+
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct EnvironmentInfo {
+    pub kind: EnvironmentKind,
+    /// Hypervisor or container engine as `systemd-detect-virt` names it,
+    /// e.g. `kvm`, `oracle`, `docker` or `wsl`
+    pub technology: Option<String>,
+    /// App ID of the Flatpak this runs in
+    pub flatpak_id: Option<String>,
+}
+
+impl EnvironmentInfo {
+    pub fn is_virtualized(&self) -> bool {
+        self.kind != EnvironmentKind::BareMetal
+    }
+
+    /// Microcode, firmware and CPU frequency can be changed from here
+    pub fn owns_hardware(&self) -> bool {
+        self.kind == EnvironmentKind::BareMetal
+    }
+
+    /// Kernel drivers and sysctls are this system's own, not a host's
+    pub fn has_own_kernel(&self) -> bool {
+        matches!(
+            self.kind,
+            EnvironmentKind::BareMetal | EnvironmentKind::VirtualMachine
+        )
+    }
+
+    /// e.g. "KVM virtual machine" or "Docker container"
+    pub fn label(&self) -> String {
+        let technology = self.technology.as_deref().map(technology_name);
+        match (self.kind, technology) {
+            (EnvironmentKind::BareMetal, _) => "bare metal".to_string(),
+            (EnvironmentKind::VirtualMachine, Some(name)) => format!("{} virtual machine", name),
+            (EnvironmentKind::VirtualMachine, None) => "virtual machine".to_string(),
+            (EnvironmentKind::Container, Some(name)) => format!("{} container", name),
+            (EnvironmentKind::Container, None) => "container".to_string(),
+            (EnvironmentKind::Flatpak, _) => match self.flatpak_id {
+                Some(ref id) => format!("Flatpak sandbox ({})", id),
+                None => "Flatpak sandbox".to_string(),
+            },
+        }
+    }
+
+    /// Drop the recommendations this environment can't act on, and in a
+    /// Flatpak turn root commands into instructions for the host
+    pub fn adjust(&self, recs: &mut Vec<Recommendation>) {
+        if !self.owns_hardware() {
+            recs.retain(|r| r.category != RecommendationCategory::Firmware);
+        }
+        if !self.has_own_kernel() {
+            recs.retain(|r| {
+                r.category != RecommendationCategory::Driver
+                    && !r
+                        .action
+                        .as_deref()
+                        .is_some_and(|a| a.starts_with("sudo sysctl"))
+            });
+        }
+        if self.kind == EnvironmentKind::Flatpak {
+            for rec in recs.iter_mut() {
+                if rec.action.as_deref().is_some_and(|a| a.starts_with("sudo ")) {
+                    let action = rec.action.take().unwrap_or_default();
+                    rec.description
+                        .push_str(&format!(" Run `{}` on the host.", action));
+                }
+            }
+        }
+    }
+}
+
+pub fn detect(cpu: &super::cpu::CpuInfo) -> EnvironmentInfo {
+    if let Some(id) = flatpak_id() {
+        return EnvironmentInfo {
+            kind: EnvironmentKind::Flatpak,
+            technology: Some("flatpak".to_string()),
+            flatpak_id: Some(id),
+        };
+    }
+    if let Some(technology) = detect_container() {
+        return EnvironmentInfo {
+            kind: EnvironmentKind::Container,
+            technology: Some(technology),
+            flatpak_id: None,
+        };
+    }
+    if let Some(technology) = detect_vm(cpu) {
+        return EnvironmentInfo {
+            kind: EnvironmentKind::VirtualMachine,
+            technology: Some(technology),
+            flatpak_id: None,
+        };
+    }
+    EnvironmentInfo::default()
+}
+
+fn flatpak_id() -> Option<String> {
+    if let Some(id) = env::var("FLATPAK_ID").ok().filter(|id| !id.is_empty()) {
+        return Some(id);
+    }
+    // Processes started without the sandbox's environment still see this
+    let info = fs::read_to_string("/.flatpak-info").ok()?;
+    let id = info
+        .lines()
+        .find_map(|line| line.strip_prefix("name="))
+        .unwrap_or("unknown");
+    Some(id.trim().to_string())
+}
+
+fn detect_container() -> Option<String> {
+    if let Some(answer) = systemd_detect_virt("--container") {
+        return answer;
+    }
+
+    if Path::new("/.dockerenv").exists() {
+        return Some("docker".to_string());
+    }
+    if Path::new("/run/.containerenv").exists() {
+        return Some("podman".to_string());
+    }
+
+    // systemd-nspawn, LXC and other container managers tell PID 1
+    if let Some(name) = env::var("container").ok().filter(|v| !v.is_empty()) {
+        return Some(name);
+    }
+    if let Ok(environ) = fs::read("/proc/1/environ") {
+        let name = environ
+            .split(|&b| b == 0)
+            .find_map(|var| var.strip_prefix(b"container="))
+            .filter(|v| !v.is_empty());
+        if let Some(name) = name {
+            return Some(String::from_utf8_lossy(name).into_owned());
+        }
+    }
+
+    let release = fs::read_to_string("/proc/sys/kernel/osrelease").unwrap_or_default();
+    if release.to_lowercase().contains("microsoft") {
+        return Some("wsl".to_string());
+    }
+    None
+}
+
+fn detect_vm(cpu: &super::cpu::CpuInfo) -> Option<String> {
+    if let Some(answer) = systemd_detect_virt("--vm") {
+        return answer;
+    }
+
+    let dmi = |field: &str| {
+        fs::read_to_string(format!("/sys/class/dmi/id/{}", field))
+            .map(|s| s.trim().to_string())
+            .unwrap_or_default()
+    };
+    let vendor = [dmi("sys_vendor"), dmi("product_name"), dmi("board_vendor")].join(" ");
+    if let Some(&(_, name)) = HYPERVISORS.iter().find(|(marker, _)| vendor.contains(marker)) {
+        return Some(name.to_string());
+    }
+
+    // Every hypervisor sets the CPUID bit, even ones with generic DMI data
+    cpu.features
+        .iter()
+        .any(|f| f == "hypervisor")
+        .then(|| "unknown".to_string())
+}
+
+/// What `systemd-detect-virt` found: `None` when it isn't installed or
+/// gave no answer, `Some(None)` when it found nothing
+fn systemd_detect_virt(flag: &str) -> Option<Option<String>> {
+    let output = Command::new("systemd-detect-virt").arg(flag).output().ok()?;
+    let name = String::from_utf8_lossy(&output.stdout).trim().to_string();
+    if name.is_empty() {
+        return None;
+    }
+    // Prints "none" and exits 1 on bare metal
+    Some((output.status.success() && name != "none").then_some(name))
+}
+
+fn technology_name(technology: &str) -> &str {
+    match technology {
+        "kvm" => "KVM",
+        "qemu" => "QEMU",
+        "vmware" => "VMware",
+        "oracle" => "VirtualBox",
+        "microsoft" => "Hyper-V",
+        "xen" => "Xen",
+        "parallels" => "Parallels",
+        "amazon" => "Amazon EC2",
+        "google" => "Google Compute Engine",
+        "docker" => "Docker",
+        "podman" => "Podman",
+        "lxc" | "lxc-libvirt" => "LXC",
+        "wsl" => "WSL",
+        other => other,
+    }
+}
+
+/// A note that some advice was left out, so its absence isn't mistaken
+/// for nothing to improve
+pub fn get_recommendations(environment: &EnvironmentInfo) -> Vec<Recommendation> {
+    let (title, description) = match environment.kind {
+        EnvironmentKind::BareMetal => return Vec::new(),
+        EnvironmentKind::VirtualMachine => (
+            "Running in a Virtual Machine",
+            format!(
+                "Detected a {}. Microcode, firmware updates and CPU frequency tuning belong to the host, so they are left out; GPU acceleration depends on what the hypervisor passes through.",
+                environment.label()
+            ),
+        ),
+        EnvironmentKind::Container => (
+            "Running in a Container",
+            format!(
+                "Detected a {}. It shares the host's kernel, so drivers, firmware, microcode, sysctls and CPU frequency tuning are left out; make those changes on the host.",
+                environment.label()
+            ),
+        ),
+        EnvironmentKind::Flatpak => (
+            "Running in a Flatpak Sandbox",
+            format!(
+                "Running in a {}, which can't install packages or change system settings. Commands are shown for running on the host instead.",
+                environment.label()
+            ),
+        ),
+    };
+
+    vec![Recommendation {
+        category: RecommendationCategory::Configuration,
+        title: title.to_string(),
+        description,
+        action: None,
+        priority: Priority::Low,
+    }]
+}
@@ -0,0 +1,263 @@
+//! CPU microcode and device firmware.
+//!
+//! The loaded microcode revision comes from sysfs (or `/proc/cpuinfo` on
+//! kernels without the sysfs node); whether `intel-ucode`/`amd-ucode` is
+//! installed and current comes from pacman's local and sync databases, so
+//! "current" is only as fresh as the last `pacman -Sy`. Updatable device
+//! firmware (UEFI, SSDs, docks, some GPUs) is whatever fwupd reports from
+//! its LVFS metadata.
+
+use super::{cpu, Priority, Recommendation, RecommendationCategory};
+use serde::{Deserialize, Serialize};
+use std::fs;
+use std::path::Path;
+use std::process::Command;
+
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct FirmwareInfo {
+    pub microcode: Option<Microcode>,
+    pub vbios: Vec<Vbios>,
+    /// `None` when fwupd isn't installed
+    pub updates: Option<Vec<FirmwareUpdate>>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct Microcode {
+    /// Revision the CPU is running, e.g. `0xf4`
+    pub revision: Option<String>,
+    pub package: String,
+    /// Installed version of `package`
+    pub installed: Option<String>,
+    /// Newer version in the sync database, when there is one
+    pub available: Option<String>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct Vbios {
+    pub gpu: String,
+    pub version: String,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct FirmwareUpdate {
+    pub device: String,
+    pub device_id: String,
+    pub current: String,
+    pub available: String,
+}
+
+pub fn detect(cpu: &cpu::CpuInfo) -> FirmwareInfo {
+    FirmwareInfo {
+        microcode: detect_microcode(cpu),
+        vbios: detect_vbios(),
+        updates: detect_fwupd_updates(),
+    }
+}
+
+/// The package carrying microcode for `vendor`'s CPUs
+pub fn microcode_package(vendor: cpu::CpuVendor) -> Option<&'static str> {
+    match vendor {
+        cpu::CpuVendor::Intel => Some("intel-ucode"),
+        cpu::CpuVendor::Amd => Some("amd-ucode"),
+        _ => None,
+    }
+}
+
+fn detect_microcode(cpu: &cpu::CpuInfo) -> Option<Microcode> {
+    let package = microcode_package(cpu.vendor)?;
+
+    let revision = fs::read_to_string("/sys/devices/system/cpu/cpu0/microcode/version")
+        .ok()
+        .map(|v| v.trim().to_string())
+        .or_else(|| {
+            let cpuinfo = fs::read_to_string("/proc/cpuinfo").ok()?;
+            cpuinfo
+                .lines()
+                .find(|line| line.starts_with("microcode"))
+                .and_then(|line| line.split(':').nth(1))
+                .map(|v| v.trim().to_string())
+        })
+        .filter(|v| !v.is_empty());
+
+    // `pacman -Q name` prints "name version"; `-Qu` "name old -> new"
+    let installed = pacman(&["-Q", package])
+        .and_then(|out| out.split_whitespace().nth(1).map(String::from));
+    let available = installed.as_ref().and_then(|_| {
+        pacman(&["-Qu", package])
+            .and_then(|out| out.split_whitespace().nth(3).map(String::from))
+    });
+
+    Some(Microcode {
+        revision,
+        package: package.to_string(),
+        installed,
+        available,
+    })
+}
+
+/// Stdout of a successful pacman query
+fn pacman(args: &[&str]) -> Option<String> {
+    let output = Command::new("pacman").args(args).env("LC_ALL", "C").output().ok()?;
+    output
+        .status
+        .success()
+        .then(|| String::from_utf8_lossy(&output.stdout).trim().to_string())
+}
+
+/// VBIOS versions amdgpu exposes in sysfs and the NVIDIA driver in procfs
+fn detect_vbios() -> Vec<Vbios> {
+    let mut vbios = Vec::new();
+
+    if let Ok(entries) = fs::read_dir("/sys/class/drm") {
+        let mut cards: Vec<_> = entries
+            .filter_map(|e| e.ok())
+            .map(|e| e.file_name().to_string_lossy().to_string())
+            .filter(|name| name.starts_with("card") && !name.contains('-'))
+            .collect();
+        cards.sort();
+        for card in cards {
+            let path = Path::new("/sys/class/drm").join(&card).join("device/vbios_version");
+            if let Some(version) = fs::read_to_string(path).ok().map(|v| v.trim().to_string()) {
+                vbios.push(Vbios { gpu: card, version });
+            }
+        }
+    }
+
+    if let Ok(entries) = fs::read_dir("/proc/driver/nvidia/gpus") {
+        for entry in entries.filter_map(|e| e.ok()) {
+            let Ok(information) = fs::read_to_string(entry.path().join("information")) else {
+                continue;
+            };
+            let field = |name: &str| {
+                information
+                    .lines()
+                    .find(|line| line.starts_with(name))
+                    .and_then(|line| line.split_once(':'))
+                    .map(|(_, v)| v.trim().to_string())
+            };
+            if let Some(version) = field("Video BIOS") {
+                vbios.push(Vbios {
+                    gpu: field("Model").unwrap_or_else(|| entry.file_name().to_string_lossy().to_string()),
+                    version,
+                });
+            }
+        }
+    }
+
+    vbios
+}
+
+#[derive(Deserialize)]
+#[serde(rename_all = "PascalCase")]
+struct FwupdDevices {
+    #[serde(default)]
+    devices: Vec<FwupdDevice>,
+}
+
+#[derive(Deserialize)]
+#[serde(rename_all = "PascalCase")]
+struct FwupdDevice {
+    name: String,
+    device_id: String,
+    #[serde(default)]
+    version: String,
+    #[serde(default)]
+    releases: Vec<FwupdRelease>,
+}
+
+#[derive(Deserialize)]
+#[serde(rename_all = "PascalCase")]
+struct FwupdRelease {
+    version: String,
+}
+
+fn detect_fwupd_updates() -> Option<Vec<FirmwareUpdate>> {
+    let output = Command::new("fwupdmgr")
+        .args(["get-updates", "--json"])
+        .output()
+        .ok()?;
+    // Exits 2 when there is nothing to update, and older versions then
+    // print a message rather than JSON
+    let devices: FwupdDevices =
+        serde_json::from_slice(&output.stdout).unwrap_or(FwupdDevices { devices: Vec::new() });
+
+    Some(
+        devices
+            .devices
+            .into_iter()
+            .filter_map(|device| {
+                // Releases are listed newest first
+                let release = device.releases.into_iter().next()?;
+                Some(FirmwareUpdate {
+                    device: device.name,
+                    device_id: device.device_id,
+                    current: device.version,
+                    available: release.version,
+                })
+            })
+            .collect(),
+    )
+}
+
+pub fn get_recommendations(firmware: &FirmwareInfo) -> Vec<Recommendation> {
+    let mut recs = Vec::new();
+
+    if let Some(ref microcode) = firmware.microcode {
+        let revision = microcode
+            .revision
+            .as_deref()
+            .map(|r| format!("Running microcode revision {}. ", r))
+            .unwrap_or_default();
+
+        match (&microcode.installed, &microcode.available) {
+            (None, _) => recs.push(Recommendation {
+                category: RecommendationCategory::Firmware,
+                title: "CPU Microcode Not Installed".to_string(),
+                description: format!(
+                    "{}Without {} the CPU runs whatever microcode the motherboard firmware shipped, missing stability and security fixes.",
+                    revision, microcode.package
+                ),
+                action: Some(format!("sudo pacman -S {}", microcode.package)),
+                priority: Priority::High,
+            }),
+            (Some(installed), Some(available)) => recs.push(Recommendation {
+                category: RecommendationCategory::Firmware,
+                title: "CPU Microcode Outdated".to_string(),
+                description: format!(
+                    "{}{} {} is installed and {} is available; the new microcode loads on the next boot.",
+                    revision, microcode.package, installed, available
+                ),
+                action: Some("sudo pacman -Syu".to_string()),
+                priority: Priority::High,
+            }),
+            (Some(_), None) => {}
+        }
+    }
+
+    match firmware.updates {
+        None => recs.push(Recommendation {
+            category: RecommendationCategory::Firmware,
+            title: "Firmware Updates Unchecked".to_string(),
+            description: "fwupd finds firmware updates for UEFI, SSDs, docks and some GPUs from the LVFS.".to_string(),
+            action: Some("sudo pacman -S fwupd".to_string()),
+            priority: Priority::Low,
+        }),
+        Some(ref updates) => {
+            for update in updates {
+                recs.push(Recommendation {
+                    category: RecommendationCategory::Firmware,
+                    title: format!("Firmware Update: {}", update.device),
+                    description: format!(
+                        "fwupd offers version {} (installed: {}).",
+                        update.available,
+                        if update.current.is_empty() { "unknown" } else { &update.current }
+                    ),
+                    action: Some(format!("fwupdmgr update {}", update.device_id)),
+                    priority: Priority::Medium,
+                });
+            }
+        }
+    }
+
+    recs
+}
@@ -3,6 +3,8 @@ use std::fs;
 use std::path::Path;
 use std::process::Command;
 
+use super::video_engine::{self, HwCodec};
+
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct GpuInfo {
     pub name: String,
@@ -11,6 +13,12 @@ pub struct GpuInfo {
     pub driver: Option<String>,
     pub vram_mb: Option<u32>,
     pub features: GpuFeatures,
+    /// NVENC, VCN or Quick Sync generation, when the model is recognised
+    #[serde(default)]
+    pub video_engine: Option<String>,
+    /// Codecs the video block encodes or decodes
+    #[serde(default)]
+    pub encode_decode: Vec<HwCodec>,
 }
 
 #[derive(Debug, Clone, Copy, Serialize, Deserialize, PartialEq, Eq)]
@@ -63,6 +71,9 @@ pub fn detect() -> Vec<GpuInfo> {
                     .and_then(|s| s.split(']').next())
                     .map(String::from);
                 
+                // Without -D lspci leaves out the PCI domain
+                let slot = line.split_whitespace().next().unwrap_or_default();
+                
                 let name = line.split(':').nth(2)
                     .map(|s| s.trim().to_string())
                     .unwrap_or_else(|| "Unknown GPU".to_string());
@@ -72,8 +83,10 @@ pub fn detect() -> Vec<GpuInfo> {
                     vendor,
                     pci_id,
                     driver: None,
-                    vram_mb: None,
+                    vram_mb: if vendor == GpuVendor::Amd { amdgpu_vram_mb(slot) } else { None },
                     features: GpuFeatures::default(),
+                    video_engine: None,
+                    encode_decode: Vec::new(),
                 });
             } else if line.contains("Kernel driver") {
                 if let Some(ref mut gpu) = current_gpu {
@@ -106,11 +119,23 @@ pub fn detect() -> Vec<GpuInfo> {
     // Detect features
     for gpu in &mut gpus {
         gpu.features = detect_features(gpu);
+        if let Some((engine, codecs)) = video_engine::identify(gpu.vendor, &gpu.name) {
+            gpu.video_engine = Some(engine);
+            gpu.encode_decode = codecs;
+        }
     }
     
     gpus
 }
 
+/// Dedicated VRAM amdgpu reports for the device in PCI `slot` ("03:00.0");
+/// APUs report their carve-out
+fn amdgpu_vram_mb(slot: &str) -> Option<u32> {
+    let path = format!("/sys/bus/pci/devices/0000:{}/mem_info_vram_total", slot);
+    let bytes: u64 = fs::read_to_string(path).ok()?.trim().parse().ok()?;
+    Some((bytes / 1024 / 1024) as u32)
+}
+
 fn detect_features(gpu: &GpuInfo) -> GpuFeatures {
     let mut features = GpuFeatures::default();
     
@@ -166,16 +191,14 @@ pub fn get_recommendations(gpu: &GpuInfo) -> Vec<super::Recommendation> {
                 });
             }
         }
-        GpuVendor::Amd => {
-            if !gpu.features.rocm {
-                recs.push(super::Recommendation {
-                    category: super::RecommendationCategory::Package,
-                    title: "ROCm Support".to_string(),
-                    description: "Install ROCm for GPU compute on AMD.".to_string(),
-                    action: Some("sudo pacman -S rocm-hip-sdk".to_string()),
-                    priority: super::Priority::Medium,
-                });
-            }
+        GpuVendor::Amd if !gpu.features.rocm => {
+            recs.push(super::Recommendation {
+                category: super::RecommendationCategory::Package,
+                title: "ROCm Support".to_string(),
+                description: "Install ROCm for GPU compute on AMD.".to_string(),
+                action: Some("sudo pacman -S rocm-hip-sdk".to_string()),
+                priority: super::Priority::Medium,
+            });
         }
         GpuVendor::Intel => {
             recs.push(super::Recommendation {
@@ -0,0 +1,253 @@
+//! VRAM and system RAM headroom for demanding creative workloads.
+//!
+//! Each workload has a minimum (it runs, with proxies or reduced preview
+//! quality) and a recommended amount (full-quality playback with effects)
+//! of both. RAM is taken from the installed modules when SMBIOS lists them,
+//! since `MemTotal` excludes firmware and kernel reservations; VRAM from the
+//! GPU with the most. Workloads come in tiers, and the recommendations only
+//! name the first tier in each family the machine falls short of.
+
+use super::{gpu, memory, Priority, Recommendation, RecommendationCategory};
+use serde::{Deserialize, Serialize};
+
+/// Share of a requirement that still counts as meeting it; an "8 GB" card
+/// reports a little under 8192 MB and `MemTotal` runs below the modules
+const SLACK: f64 = 0.9;
+
+#[derive(Debug, Clone, Copy, Serialize, Deserialize, PartialEq, Eq)]
+pub enum Workload {
+    Video1080p,
+    Video4k,
+    Video6k,
+    Video8k,
+    Scene3d,
+    HeavyScene3d,
+}
+
+/// Memory a workload needs, in GB
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Needs {
+    pub min_vram_gb: u32,
+    pub recommended_vram_gb: u32,
+    pub min_ram_gb: u32,
+    pub recommended_ram_gb: u32,
+}
+
+impl Workload {
+    /// Video tiers, then 3D, each from lightest to heaviest
+    pub const ALL: [Workload; 6] = [
+        Workload::Video1080p,
+        Workload::Video4k,
+        Workload::Video6k,
+        Workload::Video8k,
+        Workload::Scene3d,
+        Workload::HeavyScene3d,
+    ];
+
+    pub fn name(self) -> &'static str {
+        match self {
+            Workload::Video1080p => "1080p Editing",
+            Workload::Video4k => "4K Editing",
+            Workload::Video6k => "6K Editing",
+            Workload::Video8k => "8K Editing",
+            Workload::Scene3d => "3D Scenes",
+            Workload::HeavyScene3d => "Heavy 3D Scenes",
+        }
+    }
+
+    /// What the workload involves, as used in guidance sentences
+    fn task(self) -> &'static str {
+        match self {
+            Workload::Video1080p => "1080p editing with effects",
+            Workload::Video4k => "4K HEVC grading",
+            Workload::Video6k => "6K RAW timelines",
+            Workload::Video8k => "8K timelines",
+            Workload::Scene3d => "GPU rendering of typical 3D scenes",
+            Workload::HeavyScene3d => "heavy 3D scenes (dense geometry, 8K textures, simulations)",
+        }
+    }
+
+    pub fn needs(self) -> Needs {
+        let (min_vram_gb, recommended_vram_gb, min_ram_gb, recommended_ram_gb) = match self {
+            Workload::Video1080p => (2, 4, 8, 16),
+            Workload::Video4k => (8, 16, 16, 32),
+            Workload::Video6k => (12, 16, 32, 64),
+            Workload::Video8k => (16, 24, 64, 128),
+            Workload::Scene3d => (6, 8, 16, 32),
+            Workload::HeavyScene3d => (12, 24, 32, 64),
+        };
+        Needs {
+            min_vram_gb,
+            recommended_vram_gb,
+            min_ram_gb,
+            recommended_ram_gb,
+        }
+    }
+
+    /// Tiers are compared within a family only
+    fn family(self) -> u8 {
+        match self {
+            Workload::Scene3d | Workload::HeavyScene3d => 1,
+            _ => 0,
+        }
+    }
+}
+
+/// How much of a workload's need is met, worst to best
+#[derive(Debug, Clone, Copy, Serialize, Deserialize, PartialEq, Eq, PartialOrd, Ord)]
+pub enum Fit {
+    BelowMinimum,
+    Minimum,
+    Recommended,
+}
+
+impl Fit {
+    fn of(have_gb: f64, min_gb: u32, recommended_gb: u32) -> Self {
+        if have_gb >= recommended_gb as f64 * SLACK {
+            Fit::Recommended
+        } else if have_gb >= min_gb as f64 * SLACK {
+            Fit::Minimum
+        } else {
+            Fit::BelowMinimum
+        }
+    }
+
+    fn describe(self) -> &'static str {
+        match self {
+            Fit::BelowMinimum => "below minimum",
+            Fit::Minimum => "minimum",
+            Fit::Recommended => "recommended",
+        }
+    }
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct HeadroomCheck {
+    pub workload: Workload,
+    /// `None` when no GPU reports its VRAM
+    pub vram: Option<Fit>,
+    pub ram: Fit,
+}
+
+impl HeadroomCheck {
+    /// The weaker of VRAM and RAM; unknown VRAM doesn't count against it
+    pub fn fit(&self) -> Fit {
+        self.vram.map_or(self.ram, |vram| vram.min(self.ram))
+    }
+}
+
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct Headroom {
+    /// VRAM of the GPU with the most, in GB; `None` when none reports it
+    pub vram_gb: Option<f64>,
+    /// Installed RAM in GB
+    pub ram_gb: f64,
+    pub checks: Vec<HeadroomCheck>,
+}
+
+/// Every workload against the detected GPUs and memory
+pub fn check(gpus: &[gpu::GpuInfo], memory: &memory::MemoryInfo) -> Headroom {
+    let vram_gb = gpus
+        .iter()
+        .filter_map(|g| g.vram_mb)
+        .max()
+        .map(|mb| mb as f64 / 1024.0);
+    let installed_mb: u64 = memory.dimms.iter().filter_map(|d| d.size_mb).sum();
+    let ram_gb = if installed_mb > 0 {
+        installed_mb as f64 / 1024.0
+    } else {
+        memory.total_gb as f64
+    };
+
+    let checks = Workload::ALL
+        .iter()
+        .map(|&workload| {
+            let needs = workload.needs();
+            HeadroomCheck {
+                workload,
+                vram: vram_gb.map(|vram| Fit::of(vram, needs.min_vram_gb, needs.recommended_vram_gb)),
+                ram: Fit::of(ram_gb, needs.min_ram_gb, needs.recommended_ram_gb),
+            }
+        })
+        .collect();
+
+    Headroom { vram_gb, ram_gb, checks }
+}
+
+/// One recommendation per workload family: the lightest tier not met at
+/// the recommended level, or the heaviest when every tier is
+pub fn get_recommendations(headroom: &Headroom) -> Vec<Recommendation> {
+    let mut recs = Vec::new();
+
+    for family in [0, 1] {
+        let tiers: Vec<&HeadroomCheck> =
+            headroom.checks.iter().filter(|c| c.workload.family() == family).collect();
+        let comfortable = tiers.iter().take_while(|c| c.fit() == Fit::Recommended).last();
+
+        let Some(short) = tiers.iter().find(|c| c.fit() != Fit::Recommended) else {
+            if let Some(top) = comfortable {
+                recs.push(Recommendation {
+                    category: RecommendationCategory::Workflow,
+                    title: format!("Headroom for {}", top.workload.name()),
+                    description: format!(
+                        "{} This machine meets the recommended amounts.",
+                        guidance(top.workload)
+                    ),
+                    action: None,
+                    priority: Priority::Low,
+                });
+            }
+            continue;
+        };
+
+        let mut description = format!(
+            "{} This machine has {}.",
+            guidance(short.workload),
+            have(short, headroom)
+        );
+        if let Some(top) = comfortable {
+            description.push_str(&format!(" {} is comfortable.", top.workload.name()));
+        }
+
+        let (title, priority) = match short.fit() {
+            Fit::BelowMinimum => ("Below Minimum", Priority::Medium),
+            _ => ("Minimum Only", Priority::Low),
+        };
+        recs.push(Recommendation {
+            category: RecommendationCategory::Performance,
+            title: format!("{}: {}", short.workload.name(), title),
+            description,
+            action: None,
+            priority,
+        });
+    }
+
+    recs
+}
+
+/// "8 GB VRAM is the minimum for 4K HEVC grading; 16 GB recommended. …"
+pub fn guidance(workload: Workload) -> String {
+    let needs = workload.needs();
+    format!(
+        "{} GB VRAM is the minimum for {}; {} GB recommended. System RAM: {} GB minimum, {} GB recommended.",
+        needs.min_vram_gb,
+        workload.task(),
+        needs.recommended_vram_gb,
+        needs.min_ram_gb,
+        needs.recommended_ram_gb
+    )
+}
+
+/// "8 GB VRAM (minimum) and 32 GB RAM (recommended)"
+fn have(check: &HeadroomCheck, headroom: &Headroom) -> String {
+    let vram = match (headroom.vram_gb, check.vram) {
+        (Some(gb), Some(fit)) => format!("{:.0} GB VRAM ({})", gb, fit.describe()),
+        _ => "unknown VRAM".to_string(),
+    };
+    format!(
+        "{} and {:.0} GB RAM ({})",
+        vram,
+        headroom.ram_gb,
+        check.ram.describe()
+    )
+}
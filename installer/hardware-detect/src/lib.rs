@@ -3,11 +3,20 @@ pub mod gpu;
 pub mod storage;
 pub mod memory;
 pub mod display;
+pub mod environment;
+pub mod firmware;
+pub mod headroom;
 pub mod audio;
 pub mod network;
+pub mod peripherals;
+pub mod plan;
+pub mod validate;
+pub mod video_engine;
 
 use serde::{Deserialize, Serialize};
-use std::collections::HashMap;
+
+pub use plan::{build_install_plan, InstallPlan, PlanStep, StepAction, StepKind, StepResult};
+pub use validate::{validate, Anomaly};
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct HardwareInfo {
@@ -16,8 +25,17 @@ pub struct HardwareInfo {
     pub memory: memory::MemoryInfo,
     pub storage: Vec<storage::StorageInfo>,
     pub displays: Vec<display::DisplayInfo>,
+    pub session: display::session::SessionInfo,
     pub audio: audio::AudioInfo,
     pub network: Vec<network::NetworkInfo>,
+    pub peripherals: peripherals::PeripheralInfo,
+    pub firmware: firmware::FirmwareInfo,
+    /// Bare metal, virtual machine, container or Flatpak sandbox
+    #[serde(default)]
+    pub environment: environment::EnvironmentInfo,
+    /// VRAM and RAM against 4K/6K/8K editing and 3D scene needs
+    #[serde(default)]
+    pub headroom: headroom::Headroom,
     pub recommendations: Vec<Recommendation>,
 }
 
@@ -37,6 +55,7 @@ pub enum RecommendationCategory {
     Workflow,
     Package,
     Configuration,
+    Firmware,
 }
 
 #[derive(Debug, Clone, Copy, Serialize, Deserialize, PartialEq, Eq)]
@@ -47,17 +66,40 @@ pub enum Priority {
     Low,
 }
 
+/// What [`detect_with`] does beyond the default detection
+#[derive(Debug, Clone, Copy, Default)]
+pub struct DetectOptions {
+    /// Time a loopback cable with `jack_iodelay`; takes a few seconds
+    pub measure_audio_latency: bool,
+}
+
 pub fn detect_all() -> HardwareInfo {
+    detect_with(DetectOptions::default())
+}
+
+pub fn detect_with(options: DetectOptions) -> HardwareInfo {
     let cpu = cpu::detect();
     let gpu = gpu::detect();
     let memory = memory::detect();
     let storage = storage::detect();
     let displays = display::detect();
-    let audio = audio::detect();
+    let session = display::session::detect();
+    let audio = if options.measure_audio_latency {
+        audio::detect_measuring_latency()
+    } else {
+        audio::detect()
+    };
     let network = network::detect();
+    let peripherals = peripherals::detect();
+    let firmware = firmware::detect(&cpu);
+    let headroom = headroom::check(&gpu, &memory);
+    let environment = environment::detect(&cpu);
     
     let mut recommendations = Vec::new();
     
+    // Which host-only advice a VM, container or sandbox leaves out
+    recommendations.extend(environment::get_recommendations(&environment));
+    
     // GPU recommendations
     for g in &gpu {
         recommendations.extend(gpu::get_recommendations(g));
@@ -66,11 +108,41 @@ pub fn detect_all() -> HardwareInfo {
     // Memory recommendations
     recommendations.extend(memory::get_recommendations(&memory));
     
+    // Memory and VRAM for high-resolution timelines and heavy scenes
+    recommendations.extend(headroom::get_recommendations(&headroom));
+    
     // CPU recommendations
     recommendations.extend(cpu::get_recommendations(&cpu));
     
     // Workflow recommendations based on hardware
-    recommendations.extend(suggest_workflows(&cpu, &gpu, &memory));
+    let workflow_recs = suggest_workflows(&cpu, &gpu, &memory);
+    
+    // Display session support for the suggested workflows
+    let workflows: Vec<&str> = workflow_recs.iter()
+        .filter_map(|r| r.action.as_deref())
+        .filter_map(|a| a.strip_prefix("rururu-workflow activate "))
+        .collect();
+    recommendations.extend(display::session::get_recommendations(&session, &displays, &workflows));
+    
+    // Wide-gamut and professional monitors to calibrate
+    recommendations.extend(display::get_recommendations(&displays));
+    
+    // Realtime scheduling and buffer size for recording
+    recommendations.extend(audio::get_recommendations(&audio, &workflows));
+    
+    // Codecs editors export to that no GPU encodes or decodes
+    recommendations.extend(video_engine::get_recommendations(&gpu, &workflows));
+    
+    recommendations.extend(workflow_recs);
+    
+    // Tablets, MIDI, capture cards and audio interfaces
+    recommendations.extend(peripherals::get_recommendations(&peripherals, &session, &displays, &audio));
+    
+    // Microcode and device firmware
+    recommendations.extend(firmware::get_recommendations(&firmware));
+    
+    // Drop what only the host can change
+    environment.adjust(&mut recommendations);
     
     HardwareInfo {
         cpu,
@@ -78,8 +150,13 @@ pub fn detect_all() -> HardwareInfo {
         memory,
         storage,
         displays,
+        session,
         audio,
         network,
+        peripherals,
+        firmware,
+        environment,
+        headroom,
         recommendations,
     }
 }
@@ -137,6 +214,10 @@ pub fn generate_report(info: &HardwareInfo) -> String {
     
     report.push_str("# RururuOS Hardware Detection Report\n\n");
     
+    if info.environment.is_virtualized() {
+        report.push_str(&format!("Running in a {}.\n\n", info.environment.label()));
+    }
+    
     report.push_str("## CPU\n");
     report.push_str(&format!("- Model: {}\n", info.cpu.model));
     report.push_str(&format!("- Cores: {} (Threads: {})\n", info.cpu.cores, info.cpu.threads));
@@ -148,12 +229,67 @@ pub fn generate_report(info: &HardwareInfo) -> String {
         if let Some(vram) = gpu.vram_mb {
             report.push_str(&format!("  VRAM: {} MB\n", vram));
         }
+        if let Some(engine) = &gpu.video_engine {
+            let list = |pick: fn(&video_engine::HwCodec) -> bool| {
+                let names: Vec<&str> = gpu.encode_decode.iter()
+                    .filter(|c| pick(c))
+                    .map(|c| c.codec.name())
+                    .collect();
+                if names.is_empty() { "none".to_string() } else { names.join(", ") }
+            };
+            report.push_str(&format!("  Video: {} (encode: {}; decode: {})\n",
+                engine, list(|c| c.encode), list(|c| c.decode)));
+        }
     }
     report.push('\n');
     
     report.push_str("## Memory\n");
     report.push_str(&format!("- Total: {} GB\n", info.memory.total_gb));
-    report.push_str(&format!("- Type: {:?}\n\n", info.memory.memory_type));
+    report.push_str(&format!("- Type: {:?}\n", info.memory.memory_type));
+    report.push_str(&format!("- Channels: {:?}\n", info.memory.channel_config));
+    for dimm in &info.memory.dimms {
+        match dimm.size_mb {
+            Some(size) => report.push_str(&format!("  {}: {} MB {:?} @ {} MT/s ({})\n",
+                dimm.locator, size, dimm.memory_type,
+                dimm.configured_speed_mts.or(dimm.speed_mts).unwrap_or(0),
+                dimm.manufacturer.as_deref().unwrap_or("unknown"))),
+            None => report.push_str(&format!("  {}: empty\n", dimm.locator)),
+        }
+    }
+    report.push('\n');
+    
+    report.push_str("## Headroom\n");
+    for check in &info.headroom.checks {
+        report.push_str(&format!("- {}: {:?}\n", check.workload.name(), check.fit()));
+    }
+    report.push('\n');
+    
+    report.push_str("## Displays\n");
+    for display in &info.displays {
+        report.push_str(&format!("- {}", display.label()));
+        if let Some(line) = &display.professional {
+            report.push_str(&format!(", {}", line));
+        }
+        if let Some(area) = display.gamut_area {
+            report.push_str(&format!(", gamut {:.0}% of sRGB", area * 100.0));
+        }
+        if display.hdr_capable {
+            report.push_str(", HDR");
+        }
+        match display.profiled {
+            Some(true) => report.push_str(", profiled"),
+            Some(false) => report.push_str(", no profile"),
+            None => {}
+        }
+        report.push('\n');
+    }
+    report.push('\n');
+    
+    report.push_str("## Display Session\n");
+    report.push_str(&format!("- Type: {:?}\n", info.session.session_type));
+    report.push_str(&format!("- Compositor: {}\n", info.session.compositor.name()));
+    report.push_str(&format!("- Gamma control: {}, HDR: {}\n\n",
+        info.session.protocols.gamma_control, info.session.protocols.hdr));
     
     report.push_str("## Storage\n");
     for disk in &info.storage {
@@ -162,6 +298,54 @@ pub fn generate_report(info: &HardwareInfo) -> String {
     }
     report.push('\n');
     
+    report.push_str("## Audio\n");
+    report.push_str(&format!("- Server: {:?}\n", info.audio.server));
+    match &info.audio.latency {
+        Some(latency) => {
+            report.push_str(&format!("- Quantum: {}/{} ({:.1} ms round trip",
+                latency.quantum, latency.rate, latency.round_trip_ms));
+            if let Some(measured) = latency.measured_round_trip_ms {
+                report.push_str(&format!(", {:.1} ms measured", measured));
+            }
+            report.push_str(")\n");
+        }
+        None => report.push_str("- Latency: unknown\n"),
+    }
+    report.push_str(&format!("- Realtime scheduling: {}\n\n", info.audio.latency_capable));
+    
+    if !info.peripherals.is_empty() {
+        report.push_str("## Peripherals\n");
+        for tablet in &info.peripherals.tablets {
+            report.push_str(&format!("- Tablet: {} ({:?})\n", tablet.name, tablet.vendor));
+        }
+        for midi in &info.peripherals.midi {
+            report.push_str(&format!("- MIDI: {} ({} ports)\n", midi.name, midi.ports));
+        }
+        for interface in &info.peripherals.audio_interfaces {
+            report.push_str(&format!("- Audio interface: {} (card {})\n", interface.name, interface.card));
+        }
+        for capture in &info.peripherals.capture {
+            report.push_str(&format!("- Capture: {} ({:?}{})\n", capture.name, capture.bus,
+                if capture.needs_driver { ", no driver" } else { "" }));
+        }
+        report.push('\n');
+    }
+    
+    report.push_str("## Firmware\n");
+    if let Some(microcode) = &info.firmware.microcode {
+        report.push_str(&format!("- Microcode: {} ({} {})\n",
+            microcode.revision.as_deref().unwrap_or("unknown"),
+            microcode.package, microcode.installed.as_deref().unwrap_or("not installed")));
+    }
+    for vbios in &info.firmware.vbios {
+        report.push_str(&format!("- VBIOS ({}): {}\n", vbios.gpu, vbios.version));
+    }
+    match &info.firmware.updates {
+        Some(updates) => report.push_str(&format!("- fwupd: {} updates available\n", updates.len())),
+        None => report.push_str("- fwupd: not installed\n"),
+    }
+    report.push('\n');
+    
     if !info.recommendations.is_empty() {
         report.push_str("## Recommendations\n");
         for rec in &info.recommendations {
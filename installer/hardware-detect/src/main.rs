@@ -1,17 +1,32 @@
-use rururu_hardware_detect::{detect_all, generate_report};
+use rururu_hardware_detect::{build_install_plan, detect_with, generate_report, validate, DetectOptions};
 use std::env;
+use std::process::ExitCode;
 
-fn main() {
-    let args: Vec<String> = env::args().collect();
+fn main() -> ExitCode {
+    let args: Vec<String> = env::args().skip(1).collect();
     
-    let format = args.get(1).map(|s| s.as_str()).unwrap_or("text");
+    let check = args.iter().any(|a| a == "--validate");
+    let format = args.iter()
+        .map(|s| s.as_str())
+        .find(|a| !a.starts_with("--"))
+        .unwrap_or("text");
     
-    let info = detect_all();
+    let info = detect_with(DetectOptions {
+        measure_audio_latency: args.iter().any(|a| a == "--measure-latency"),
+    });
+    
+    if check {
+        return print_anomalies(&info, format == "json");
+    }
     
     match format {
         "json" => {
             println!("{}", serde_json::to_string_pretty(&info).unwrap());
         }
+        "plan" => {
+            let plan = build_install_plan(&info);
+            println!("{}", serde_json::to_string_pretty(&plan).unwrap());
+        }
         "markdown" | "md" => {
             println!("{}", generate_report(&info));
         }
@@ -19,12 +34,41 @@ fn main() {
             print_text(&info);
         }
     }
+    
+    ExitCode::SUCCESS
+}
+
+/// `--validate`: list what looks wrong and fail if anything does
+fn print_anomalies(info: &rururu_hardware_detect::HardwareInfo, json: bool) -> ExitCode {
+    let anomalies = validate(info);
+    
+    if json {
+        println!("{}", serde_json::to_string_pretty(&anomalies).unwrap());
+    } else if anomalies.is_empty() {
+        println!("Hardware detection looks consistent");
+    } else {
+        println!("{} anomalies found:", anomalies.len());
+        for anomaly in &anomalies {
+            println!("  [{}] {}", anomaly.component, anomaly.message);
+        }
+    }
+    
+    if anomalies.is_empty() {
+        ExitCode::SUCCESS
+    } else {
+        ExitCode::FAILURE
+    }
 }
 
 fn print_text(info: &rururu_hardware_detect::HardwareInfo) {
     println!("RururuOS Hardware Detection");
     println!("===========================\n");
     
+    if info.environment.is_virtualized() {
+        println!("Environment: {}", info.environment.label());
+        println!();
+    }
+    
     println!("CPU: {} ({:?})", info.cpu.model, info.cpu.arch);
     println!("     {} cores, {} threads", info.cpu.cores, info.cpu.threads);
     println!();
@@ -40,6 +84,11 @@ fn print_text(info: &rururu_hardware_detect::HardwareInfo) {
     println!();
     
     println!("Memory: {} GB {:?}", info.memory.total_gb, info.memory.memory_type);
+    if !info.memory.dimms.is_empty() {
+        let populated = info.memory.dimms.iter().filter(|d| d.is_populated()).count();
+        println!("  {}/{} slots populated, {:?}",
+            populated, info.memory.dimms.len(), info.memory.channel_config);
+    }
     println!();
     
     println!("Storage:");
@@ -51,19 +100,80 @@ fn print_text(info: &rururu_hardware_detect::HardwareInfo) {
     println!("Displays:");
     for display in &info.displays {
         print!("  - {} ({})", display.name, display.connector);
+        if let Some(model) = &display.model {
+            print!(" {}", model);
+        }
         if let Some((w, h)) = display.resolution {
             print!(", {}x{}", w, h);
         }
         if display.hdr_capable {
             print!(", HDR");
         }
+        if display.wide_gamut {
+            print!(", wide gamut");
+        }
         println!();
     }
     println!();
     
+    println!("Session: {:?} ({})", info.session.session_type, info.session.compositor.name());
+    println!("  Gamma control: {}, Output management: {}, HDR: {}",
+        info.session.protocols.gamma_control,
+        info.session.protocols.output_management,
+        info.session.protocols.hdr);
+    println!();
+    
     println!("Audio: {:?}", info.audio.server);
     println!("  Devices: {}", info.audio.devices.len());
     println!("  Low-latency capable: {}", info.audio.latency_capable);
+    if let Some(latency) = &info.audio.latency {
+        print!("  Quantum: {}/{} ({:.1} ms round trip", latency.quantum, latency.rate, latency.round_trip_ms);
+        match latency.measured_round_trip_ms {
+            Some(measured) => println!(", {:.1} ms measured)", measured),
+            None => println!(")"),
+        }
+    }
+    println!();
+    
+    let peripherals = &info.peripherals;
+    if !peripherals.is_empty() {
+        println!("Peripherals:");
+        for tablet in &peripherals.tablets {
+            println!("  - Tablet: {} ({:?})", tablet.name, tablet.vendor);
+        }
+        for midi in &peripherals.midi {
+            println!("  - MIDI: {} ({} ports)", midi.name, midi.ports);
+        }
+        for interface in &peripherals.audio_interfaces {
+            println!("  - Audio interface: {} (card {})", interface.name, interface.card);
+        }
+        for capture in &peripherals.capture {
+            print!("  - Capture: {}", capture.name);
+            match (&capture.video_device, capture.needs_driver) {
+                (_, true) => print!(", no driver"),
+                (Some(node), false) => print!(", {}", node),
+                (None, false) => {}
+            }
+            println!();
+        }
+        println!();
+    }
+    
+    let firmware = &info.firmware;
+    println!("Firmware:");
+    if let Some(microcode) = &firmware.microcode {
+        println!("  Microcode: {} ({} {})",
+            microcode.revision.as_deref().unwrap_or("unknown"),
+            microcode.package,
+            microcode.installed.as_deref().unwrap_or("not installed"));
+    }
+    for vbios in &firmware.vbios {
+        println!("  VBIOS ({}): {}", vbios.gpu, vbios.version);
+    }
+    match &firmware.updates {
+        Some(updates) => println!("  fwupd: {} updates available", updates.len()),
+        None => println!("  fwupd: not installed"),
+    }
     println!();
     
     if !info.recommendations.is_empty() {
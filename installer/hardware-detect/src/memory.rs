@@ -7,6 +7,8 @@ pub struct MemoryInfo {
     pub memory_type: MemoryType,
     pub channels: Option<u32>,
     pub speed_mhz: Option<u32>,
+    pub dimms: Vec<DimmInfo>,
+    pub channel_config: ChannelConfig,
 }
 
 #[derive(Debug, Clone, Copy, Serialize, Deserialize, PartialEq, Eq)]
@@ -19,12 +21,71 @@ pub enum MemoryType {
     Unknown,
 }
 
+/// One memory slot from the SMBIOS type 17 (Memory Device) table.
+/// Empty slots are reported with `size_mb: None`.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct DimmInfo {
+    pub locator: String,
+    pub bank: Option<String>,
+    pub size_mb: Option<u64>,
+    pub memory_type: MemoryType,
+    pub speed_mts: Option<u32>,
+    pub configured_speed_mts: Option<u32>,
+    pub manufacturer: Option<String>,
+}
+
+impl DimmInfo {
+    pub fn is_populated(&self) -> bool {
+        self.size_mb.is_some()
+    }
+
+    /// Channel letter from the slot labels ("ChannelA-DIMM0", "P0 CHANNEL B",
+    /// "DIMM_A1", "B2"), if the firmware names channels at all
+    pub fn channel(&self) -> Option<char> {
+        let labels = [Some(self.locator.as_str()), self.bank.as_deref()];
+        
+        for label in labels.iter().flatten() {
+            let upper = label.to_uppercase();
+            if let Some(pos) = upper.find("CHANNEL") {
+                let rest = upper[pos + "CHANNEL".len()..].trim_start_matches([' ', '_', '-']);
+                if let Some(c) = rest.chars().next().filter(|c| c.is_ascii_alphabetic()) {
+                    return Some(c);
+                }
+            }
+        }
+        
+        // Short slot names: letter followed by the slot number
+        let upper = self.locator.to_uppercase();
+        let name = upper
+            .trim_start_matches("DIMM")
+            .trim_start_matches([' ', '_', '-']);
+        let mut chars = name.chars();
+        match (chars.next(), chars.next()) {
+            (Some(c), Some(d)) if c.is_ascii_alphabetic() && d.is_ascii_digit() => Some(c),
+            _ => None,
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, Serialize, Deserialize, PartialEq, Eq)]
+pub enum ChannelConfig {
+    /// One channel populated on a board with `available` channels
+    Single { available: u32 },
+    Dual,
+    Quad,
+    /// Several channels populated with different capacities
+    Asymmetric,
+    Unknown,
+}
+
 pub fn detect() -> MemoryInfo {
     let mut info = MemoryInfo {
         total_gb: 0,
         memory_type: MemoryType::Unknown,
         channels: None,
         speed_mhz: None,
+        dimms: Vec::new(),
+        channel_config: ChannelConfig::Unknown,
     };
     
     // Read from /proc/meminfo
@@ -40,39 +101,255 @@ pub fn detect() -> MemoryInfo {
         }
     }
     
-    // Try dmidecode for detailed info (requires root)
-    if let Ok(output) = std::process::Command::new("dmidecode")
-        .args(["-t", "memory"])
+    // Both sources need root; dmidecode decodes more vendor quirks
+    info.dimms = detect_dimms_dmidecode();
+    if info.dimms.is_empty() {
+        info.dimms = detect_dimms_dmi_table();
+    }
+    
+    let populated: Vec<&DimmInfo> = info.dimms.iter().filter(|d| d.is_populated()).collect();
+    if let Some(first) = populated.first() {
+        info.memory_type = first.memory_type;
+    }
+    // The slowest module sets the pace for all of them
+    info.speed_mhz = populated
+        .iter()
+        .filter_map(|d| d.configured_speed_mts.or(d.speed_mts))
+        .min();
+    
+    info.channel_config = channel_config(&info.dimms);
+    info.channels = match info.channel_config {
+        ChannelConfig::Single { .. } => Some(1),
+        ChannelConfig::Dual => Some(2),
+        ChannelConfig::Quad => Some(4),
+        ChannelConfig::Asymmetric => Some(populated_channels(&info.dimms).len() as u32),
+        ChannelConfig::Unknown => None,
+    };
+    
+    info
+}
+
+fn detect_dimms_dmidecode() -> Vec<DimmInfo> {
+    let output = match std::process::Command::new("dmidecode")
+        .args(["-t", "17"])
         .output()
     {
-        let text = String::from_utf8_lossy(&output.stdout);
+        Ok(output) if output.status.success() => output,
+        _ => return Vec::new(),
+    };
+    
+    parse_dmidecode(&String::from_utf8_lossy(&output.stdout))
+}
+
+fn parse_dmidecode(text: &str) -> Vec<DimmInfo> {
+    let mut dimms = Vec::new();
+    let mut current: Option<DimmInfo> = None;
+    
+    for line in text.lines() {
+        let line = line.trim();
         
-        for line in text.lines() {
-            let line = line.trim();
-            
-            if line.starts_with("Type:") {
-                let type_str = line.split(':').nth(1).map(|s| s.trim()).unwrap_or("");
-                info.memory_type = match type_str {
-                    "DDR3" => MemoryType::Ddr3,
-                    "DDR4" => MemoryType::Ddr4,
-                    "DDR5" => MemoryType::Ddr5,
-                    "LPDDR4" => MemoryType::Lpddr4,
-                    "LPDDR5" => MemoryType::Lpddr5,
-                    _ => MemoryType::Unknown,
-                };
-            } else if line.starts_with("Speed:") {
-                if let Some(speed_str) = line.split(':').nth(1) {
-                    info.speed_mhz = speed_str
-                        .trim()
-                        .split_whitespace()
-                        .next()
-                        .and_then(|s| s.parse().ok());
-                }
+        if line == "Memory Device" {
+            dimms.extend(current.take());
+            current = Some(DimmInfo {
+                locator: String::new(),
+                bank: None,
+                size_mb: None,
+                memory_type: MemoryType::Unknown,
+                speed_mts: None,
+                configured_speed_mts: None,
+                manufacturer: None,
+            });
+            continue;
+        }
+        
+        let Some(dimm) = current.as_mut() else {
+            continue;
+        };
+        let Some((key, value)) = line.split_once(':') else {
+            continue;
+        };
+        let value = value.trim();
+        
+        match key {
+            "Size" => dimm.size_mb = parse_dmidecode_size(value),
+            "Locator" => dimm.locator = value.to_string(),
+            "Bank Locator" => dimm.bank = known_string(value),
+            "Type" => dimm.memory_type = parse_memory_type(value),
+            "Speed" => dimm.speed_mts = parse_leading_number(value),
+            "Configured Memory Speed" | "Configured Clock Speed" => {
+                dimm.configured_speed_mts = parse_leading_number(value)
             }
+            "Manufacturer" => dimm.manufacturer = known_string(value),
+            _ => {}
         }
     }
     
-    info
+    dimms.extend(current);
+    dimms
+}
+
+/// "16 GB", "8192 MB" or "No Module Installed"
+fn parse_dmidecode_size(value: &str) -> Option<u64> {
+    let mut parts = value.split_whitespace();
+    let amount: u64 = parts.next()?.parse().ok()?;
+    match parts.next()? {
+        "kB" | "KB" => Some(amount / 1024),
+        "MB" => Some(amount),
+        "GB" => Some(amount * 1024),
+        "TB" => Some(amount * 1024 * 1024),
+        _ => None,
+    }
+}
+
+fn parse_leading_number(value: &str) -> Option<u32> {
+    value.split_whitespace().next().and_then(|s| s.parse().ok())
+}
+
+fn parse_memory_type(value: &str) -> MemoryType {
+    match value {
+        "DDR3" => MemoryType::Ddr3,
+        "DDR4" => MemoryType::Ddr4,
+        "DDR5" => MemoryType::Ddr5,
+        "LPDDR4" => MemoryType::Lpddr4,
+        "LPDDR5" => MemoryType::Lpddr5,
+        _ => MemoryType::Unknown,
+    }
+}
+
+/// Firmware fills unused string fields with placeholders
+fn known_string(value: &str) -> Option<String> {
+    match value {
+        "" | "Unknown" | "Not Specified" | "NO DIMM" | "Undefined" => None,
+        _ => Some(value.to_string()),
+    }
+}
+
+/// Read type 17 structures straight from the kernel's copy of the SMBIOS
+/// table, for systems without dmidecode installed.
+fn detect_dimms_dmi_table() -> Vec<DimmInfo> {
+    match fs::read("/sys/firmware/dmi/tables/DMI") {
+        Ok(table) => parse_dmi_table(&table),
+        Err(_) => Vec::new(),
+    }
+}
+
+fn parse_dmi_table(table: &[u8]) -> Vec<DimmInfo> {
+    let mut dimms = Vec::new();
+    let mut pos = 0;
+    
+    while pos + 4 <= table.len() {
+        let kind = table[pos];
+        let length = table[pos + 1] as usize;
+        if length < 4 || pos + length > table.len() {
+            break;
+        }
+        
+        let formatted = &table[pos..pos + length];
+        
+        // Strings follow the formatted area and end with a double NUL
+        let strings_start = pos + length;
+        let mut end = strings_start;
+        while end + 1 < table.len() && !(table[end] == 0 && table[end + 1] == 0) {
+            end += 1;
+        }
+        let strings: Vec<String> = table[strings_start..end]
+            .split(|b| *b == 0)
+            .map(|s| String::from_utf8_lossy(s).trim().to_string())
+            .collect();
+        
+        if kind == 17 {
+            if let Some(dimm) = parse_memory_device(formatted, &strings) {
+                dimms.push(dimm);
+            }
+        } else if kind == 127 {
+            // End-of-table
+            break;
+        }
+        
+        pos = end + 2;
+    }
+    
+    dimms
+}
+
+fn parse_memory_device(data: &[u8], strings: &[String]) -> Option<DimmInfo> {
+    let byte = |offset: usize| data.get(offset).copied();
+    let word = |offset: usize| {
+        Some(u16::from_le_bytes([*data.get(offset)?, *data.get(offset + 1)?]))
+    };
+    let string = |offset: usize| {
+        let index = byte(offset)? as usize;
+        index
+            .checked_sub(1)
+            .and_then(|i| strings.get(i))
+            .and_then(|s| known_string(s))
+    };
+    
+    let size_mb = match word(0x0C)? {
+        0 | 0xFFFF => None,
+        // Real size lives in the extended size field
+        0x7FFF => data
+            .get(0x1C..0x20)
+            .map(|b| u32::from_le_bytes([b[0], b[1], b[2], b[3]]) as u64 & 0x7FFF_FFFF),
+        size if size & 0x8000 != 0 => Some((size & 0x7FFF) as u64 / 1024),
+        size => Some(size as u64),
+    };
+    
+    let memory_type = match byte(0x12) {
+        Some(0x18) => MemoryType::Ddr3,
+        Some(0x1A) => MemoryType::Ddr4,
+        Some(0x22) => MemoryType::Ddr5,
+        Some(0x1E) => MemoryType::Lpddr4,
+        Some(0x23) => MemoryType::Lpddr5,
+        _ => MemoryType::Unknown,
+    };
+    
+    let speed = |offset: usize| word(offset).filter(|s| *s != 0 && *s != 0xFFFF).map(u32::from);
+    
+    Some(DimmInfo {
+        locator: string(0x10).unwrap_or_default(),
+        bank: string(0x11),
+        size_mb,
+        memory_type,
+        speed_mts: speed(0x15),
+        configured_speed_mts: speed(0x20),
+        manufacturer: string(0x17),
+    })
+}
+
+fn populated_channels(dimms: &[DimmInfo]) -> Vec<(char, u64)> {
+    let mut channels: Vec<(char, u64)> = Vec::new();
+    
+    for dimm in dimms {
+        let (Some(channel), Some(size)) = (dimm.channel(), dimm.size_mb) else {
+            continue;
+        };
+        match channels.iter_mut().find(|(c, _)| *c == channel) {
+            Some((_, total)) => *total += size,
+            None => channels.push((channel, size)),
+        }
+    }
+    
+    channels
+}
+
+fn channel_config(dimms: &[DimmInfo]) -> ChannelConfig {
+    let mut available: Vec<char> = dimms.iter().filter_map(|d| d.channel()).collect();
+    available.sort_unstable();
+    available.dedup();
+    
+    let populated = populated_channels(dimms);
+    let symmetric = populated.windows(2).all(|w| w[0].1 == w[1].1);
+    
+    match populated.len() {
+        0 => ChannelConfig::Unknown,
+        1 => ChannelConfig::Single {
+            available: available.len() as u32,
+        },
+        _ if !symmetric => ChannelConfig::Asymmetric,
+        2 | 3 => ChannelConfig::Dual,
+        _ => ChannelConfig::Quad,
+    }
 }
 
 pub fn get_recommendations(memory: &MemoryInfo) -> Vec<super::Recommendation> {
@@ -112,5 +389,40 @@ pub fn get_recommendations(memory: &MemoryInfo) -> Vec<super::Recommendation> {
         });
     }
     
+    match memory.channel_config {
+        ChannelConfig::Single { available } if available >= 2 => {
+            let empty: Vec<&str> = memory
+                .dimms
+                .iter()
+                .filter(|d| !d.is_populated())
+                .map(|d| d.locator.as_str())
+                .collect();
+            recs.push(super::Recommendation {
+                category: super::RecommendationCategory::Performance,
+                title: "Single-Channel Memory".to_string(),
+                description: format!(
+                    "RAM is running single-channel on a {}-channel board, halving memory bandwidth. \
+                     Add a matching module in another channel (free slots: {}).",
+                    available,
+                    if empty.is_empty() { "none".to_string() } else { empty.join(", ") }
+                ),
+                action: None,
+                priority: super::Priority::High,
+            });
+        }
+        ChannelConfig::Asymmetric => {
+            recs.push(super::Recommendation {
+                category: super::RecommendationCategory::Performance,
+                title: "Unbalanced Memory Channels".to_string(),
+                description: "Memory channels hold different capacities, so part of the RAM \
+                              runs without interleaving. Matched modules per channel perform best."
+                    .to_string(),
+                action: None,
+                priority: super::Priority::Medium,
+            });
+        }
+        _ => {}
+    }
+    
     recs
 }
@@ -0,0 +1,493 @@
+//! Creative peripherals: graphics tablets, MIDI controllers, capture cards
+//! and USB audio interfaces.
+//!
+//! Everything is read from sysfs, procfs and the udev database rather than
+//! through libinput or libasound, so detection also works from the live
+//! installer. Devices show up once however many nodes they expose (a tablet
+//! has pen, pad and touch event devices; a capture card several video
+//! nodes).
+
+use super::{audio, display, Priority, Recommendation, RecommendationCategory};
+use serde::{Deserialize, Serialize};
+use std::fs;
+use std::path::Path;
+
+const WACOM_VENDOR: u16 = 0x056a;
+const FOCUSRITE_VENDOR: u16 = 0x1235;
+const BLACKMAGIC_PCI_VENDOR: u16 = 0xbdbd;
+
+/// USB vendors whose video devices are capture hardware rather than webcams
+const USB_CAPTURE_VENDORS: &[(u16, &str)] = &[
+    (0x0fd9, "Elgato"),
+    (0x07ca, "AVerMedia"),
+    (0x2935, "Magewell"),
+    (0x1edb, "Blackmagic Design"),
+    // MS2109 and similar HDMI dongles
+    (0x534d, "MacroSilicon"),
+];
+
+const PCI_CAPTURE_VENDORS: &[(u16, &str)] = &[
+    (BLACKMAGIC_PCI_VENDOR, "Blackmagic Design"),
+    (0x1cd7, "Magewell"),
+    (0x1461, "AVerMedia"),
+    // Makes the Elgato 4K60 Pro and most AVerMedia Live Gamer cards
+    (0x12ab, "YUAN"),
+];
+
+/// Sequencer clients that exist without any hardware attached
+const BUILTIN_SEQ_CLIENTS: &[u32] = &[0, 14];
+
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct PeripheralInfo {
+    pub tablets: Vec<Tablet>,
+    pub midi: Vec<MidiDevice>,
+    pub capture: Vec<CaptureDevice>,
+    pub audio_interfaces: Vec<AudioInterface>,
+}
+
+impl PeripheralInfo {
+    pub fn is_empty(&self) -> bool {
+        self.tablets.is_empty()
+            && self.midi.is_empty()
+            && self.capture.is_empty()
+            && self.audio_interfaces.is_empty()
+    }
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct Tablet {
+    pub name: String,
+    pub vendor: TabletVendor,
+    /// `vendor:product` in hex
+    pub usb_id: Option<String>,
+}
+
+#[derive(Debug, Clone, Copy, Serialize, Deserialize, PartialEq, Eq)]
+pub enum TabletVendor {
+    Wacom,
+    /// Huion, also sold as Gaomon
+    Huion,
+    XpPen,
+    Other,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct MidiDevice {
+    pub name: String,
+    /// ALSA sequencer client; `None` when only the raw MIDI device was found
+    pub client: Option<u32>,
+    pub ports: u32,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct CaptureDevice {
+    pub name: String,
+    pub vendor: String,
+    pub bus: Bus,
+    /// V4L2 node, e.g. `/dev/video0`; cards with their own SDK have none
+    pub video_device: Option<String>,
+    /// No kernel driver is bound, so the vendor's driver is missing
+    pub needs_driver: bool,
+}
+
+#[derive(Debug, Clone, Copy, Serialize, Deserialize, PartialEq, Eq)]
+pub enum Bus {
+    Usb,
+    Pci,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct AudioInterface {
+    pub name: String,
+    /// ALSA card number
+    pub card: u32,
+    /// `vendor:product` in hex
+    pub usb_id: String,
+    pub has_midi: bool,
+}
+
+pub fn detect() -> PeripheralInfo {
+    let cards = sound_cards();
+
+    PeripheralInfo {
+        tablets: detect_tablets(),
+        midi: detect_midi(&cards),
+        capture: detect_capture(),
+        audio_interfaces: cards.iter()
+            .filter(|c| c.playback && c.capture)
+            .filter_map(|c| Some(AudioInterface {
+                name: c.name.clone(),
+                card: c.index,
+                usb_id: c.usb_id.clone()?,
+                has_midi: c.midi,
+            }))
+            .collect(),
+    }
+}
+
+fn detect_tablets() -> Vec<Tablet> {
+    let mut tablets: Vec<Tablet> = Vec::new();
+
+    let Ok(entries) = fs::read_dir("/sys/class/input") else {
+        return tablets;
+    };
+    for entry in entries.flatten() {
+        if !entry.file_name().to_string_lossy().starts_with("event") {
+            continue;
+        }
+        let device = entry.path().join("device");
+        let Some(name) = read_trimmed(&device.join("name")) else {
+            continue;
+        };
+        let vendor_id = read_hex(&device.join("id/vendor"));
+        let product_id = read_hex(&device.join("id/product"));
+        let vendor = match vendor_id {
+            Some(WACOM_VENDOR) => TabletVendor::Wacom,
+            Some(0x256c) => TabletVendor::Huion,
+            Some(0x28bd) => TabletVendor::XpPen,
+            _ => TabletVendor::Other,
+        };
+
+        // udev's input_id builtin tags pen and pad nodes; without its
+        // database fall back to known vendors' pen nodes
+        let is_tablet = match udev_properties(&entry.path()) {
+            Some(props) => props.iter().any(|p| p == "ID_INPUT_TABLET=1"),
+            None => vendor != TabletVendor::Other && name.to_lowercase().contains("pen"),
+        };
+        if !is_tablet {
+            continue;
+        }
+
+        let usb_id = vendor_id.zip(product_id).map(|(v, p)| format!("{:04x}:{:04x}", v, p));
+        let name = device_name(&name).to_string();
+        let seen = tablets.iter().any(|t| match (&t.usb_id, &usb_id) {
+            (Some(a), Some(b)) => a == b,
+            _ => t.name == name,
+        });
+        if !seen {
+            tablets.push(Tablet { name, vendor, usb_id });
+        }
+    }
+
+    tablets
+}
+
+/// Tablet name without the node suffix kernel drivers add per tool
+fn device_name(name: &str) -> &str {
+    [" Pen", " Pad", " Finger", " Touch", " Stylus", " Mouse", " Keyboard"]
+        .iter()
+        .find_map(|suffix| name.strip_suffix(suffix))
+        .unwrap_or(name)
+        .trim()
+}
+
+/// `E:` properties udev recorded for a device node, e.g. `ID_INPUT_TABLET=1`
+fn udev_properties(class_dir: &Path) -> Option<Vec<String>> {
+    let dev = read_trimmed(&class_dir.join("dev"))?;
+    let data = fs::read_to_string(format!("/run/udev/data/c{}", dev)).ok()?;
+    Some(data.lines()
+        .filter_map(|l| l.strip_prefix("E:"))
+        .map(String::from)
+        .collect())
+}
+
+/// One ALSA card from `/proc/asound`
+struct SoundCard {
+    index: u32,
+    name: String,
+    usb_id: Option<String>,
+    playback: bool,
+    capture: bool,
+    midi: bool,
+}
+
+fn sound_cards() -> Vec<SoundCard> {
+    let Ok(cards) = fs::read_to_string("/proc/asound/cards") else {
+        return Vec::new();
+    };
+
+    // ` 1 [Gen            ]: USB-Audio - Scarlett 2i2 USB`
+    cards.lines()
+        .filter_map(|line| {
+            let (index, rest) = line.trim_start().split_once(' ')?;
+            let index: u32 = index.parse().ok()?;
+            let name = rest.split_once(" - ").map(|(_, n)| n.trim())?.to_string();
+
+            let dir = Path::new("/proc/asound").join(format!("card{}", index));
+            let nodes: Vec<String> = fs::read_dir(&dir).ok()?
+                .flatten()
+                .map(|e| e.file_name().to_string_lossy().to_string())
+                .collect();
+            let pcm = |direction: char| nodes.iter()
+                .any(|n| n.starts_with("pcm") && n.ends_with(direction));
+
+            Some(SoundCard {
+                index,
+                name,
+                usb_id: read_trimmed(&dir.join("usbid")),
+                playback: pcm('p'),
+                capture: pcm('c'),
+                midi: nodes.iter().any(|n| n.starts_with("midi")),
+            })
+        })
+        .collect()
+}
+
+fn detect_midi(cards: &[SoundCard]) -> Vec<MidiDevice> {
+    // The sequencer lists every hardware port, also ones on cards without
+    // a rawmidi node; it's only there once snd-seq is loaded
+    let Ok(clients) = fs::read_to_string("/proc/asound/seq/clients") else {
+        return cards.iter()
+            .filter(|c| c.midi)
+            .map(|c| MidiDevice { name: c.name.clone(), client: None, ports: 1 })
+            .collect();
+    };
+    parse_seq_clients(&clients)
+}
+
+/// `Client  20 : "Launchpad Mini" [Kernel Legacy]` followed by its
+/// `  Port   0 : "..." (RWeX)` lines; user clients are software
+fn parse_seq_clients(clients: &str) -> Vec<MidiDevice> {
+    let mut devices: Vec<MidiDevice> = Vec::new();
+    let mut in_hardware_client = false;
+
+    for line in clients.lines() {
+        if let Some(rest) = line.strip_prefix("Client") {
+            in_hardware_client = false;
+            let Some((number, rest)) = rest.split_once(':') else {
+                continue;
+            };
+            let Ok(client) = number.trim().parse::<u32>() else {
+                continue;
+            };
+            let mut quoted = rest.split('"');
+            let name = quoted.nth(1).unwrap_or_default().to_string();
+            let kind = quoted.next().unwrap_or_default();
+            if kind.contains("Kernel") && !BUILTIN_SEQ_CLIENTS.contains(&client) {
+                in_hardware_client = true;
+                devices.push(MidiDevice { name, client: Some(client), ports: 0 });
+            }
+        } else if in_hardware_client && line.trim_start().starts_with("Port") {
+            if let Some(device) = devices.last_mut() {
+                device.ports += 1;
+            }
+        }
+    }
+
+    devices
+}
+
+fn detect_capture() -> Vec<CaptureDevice> {
+    let mut devices: Vec<CaptureDevice> = Vec::new();
+
+    // USB capture is UVC, so look for video nodes on capture vendors' devices
+    if let Ok(entries) = fs::read_dir("/sys/class/video4linux") {
+        let mut seen_usb_devices = Vec::new();
+        let mut nodes: Vec<_> = entries.flatten().map(|e| e.path()).collect();
+        nodes.sort();
+        for node in nodes {
+            // device -> USB interface; its parent is the USB device
+            let Ok(interface) = fs::canonicalize(node.join("device")) else {
+                continue;
+            };
+            let Some(usb_device) = interface.parent() else {
+                continue;
+            };
+            let Some(vendor_id) = read_hex(&usb_device.join("idVendor")) else {
+                continue;
+            };
+            let Some(&(_, vendor)) = USB_CAPTURE_VENDORS.iter().find(|(id, _)| *id == vendor_id) else {
+                continue;
+            };
+            // Metadata nodes come after the capture node of the same device
+            if seen_usb_devices.contains(&usb_device.to_path_buf()) {
+                continue;
+            }
+            seen_usb_devices.push(usb_device.to_path_buf());
+
+            let name = read_trimmed(&usb_device.join("product"))
+                .or_else(|| read_trimmed(&node.join("name")))
+                .unwrap_or_else(|| format!("{} capture device", vendor));
+            devices.push(CaptureDevice {
+                name,
+                vendor: vendor.to_string(),
+                bus: Bus::Usb,
+                video_device: node.file_name().map(|n| format!("/dev/{}", n.to_string_lossy())),
+                needs_driver: false,
+            });
+        }
+    }
+
+    if let Ok(entries) = fs::read_dir("/sys/bus/pci/devices") {
+        for entry in entries.flatten() {
+            let path = entry.path();
+            let Some(vendor_id) = read_hex(&path.join("vendor")) else {
+                continue;
+            };
+            let Some(&(_, vendor)) = PCI_CAPTURE_VENDORS.iter().find(|(id, _)| *id == vendor_id) else {
+                continue;
+            };
+            // Multimedia controllers (class 0x04); these vendors also make
+            // bridges and other chips
+            if !read_trimmed(&path.join("class")).is_some_and(|c| c.starts_with("0x04")) {
+                continue;
+            }
+
+            let video_device = fs::read_dir(path.join("video4linux")).ok()
+                .and_then(|nodes| nodes.flatten().map(|n| n.file_name()).min())
+                .map(|n| format!("/dev/{}", n.to_string_lossy()));
+            let device_id = read_hex(&path.join("device")).unwrap_or(0);
+            devices.push(CaptureDevice {
+                name: format!("{} capture card ({:04x}:{:04x})", vendor, vendor_id, device_id),
+                vendor: vendor.to_string(),
+                bus: Bus::Pci,
+                video_device,
+                needs_driver: !path.join("driver").exists(),
+            });
+        }
+    }
+
+    devices
+}
+
+fn read_trimmed(path: &Path) -> Option<String> {
+    fs::read_to_string(path)
+        .ok()
+        .map(|s| s.trim().to_string())
+        .filter(|s| !s.is_empty())
+}
+
+/// sysfs ids: `056a` in input and USB attributes, `0x10de` in PCI ones
+fn read_hex(path: &Path) -> Option<u16> {
+    let value = read_trimmed(path)?;
+    u16::from_str_radix(value.trim_start_matches("0x"), 16).ok()
+}
+
+pub fn get_recommendations(
+    info: &PeripheralInfo,
+    session: &display::session::SessionInfo,
+    displays: &[display::DisplayInfo],
+    audio: &audio::AudioInfo,
+) -> Vec<Recommendation> {
+    use display::session::{Compositor, SessionType};
+
+    let mut recs = Vec::new();
+    let x11 = session.session_type == SessionType::X11;
+
+    if x11 && info.tablets.iter().any(|t| t.vendor == TabletVendor::Wacom) {
+        recs.push(Recommendation {
+            category: RecommendationCategory::Driver,
+            title: "Wacom X11 Driver".to_string(),
+            description: "xf86-input-wacom adds pressure curves, button mapping and xsetwacom under X11; Wayland sessions use libinput instead.".to_string(),
+            action: Some("sudo pacman -S xf86-input-wacom".to_string()),
+            priority: Priority::High,
+        });
+    }
+
+    if info.tablets.iter().any(|t| matches!(t.vendor, TabletVendor::Huion | TabletVendor::XpPen)) {
+        recs.push(Recommendation {
+            category: RecommendationCategory::Driver,
+            title: "Huion / XP-Pen Tablet".to_string(),
+            description: "Works through the kernel's HID driver and libinput. OpenTabletDriver (AUR) adds per-app pressure curves and express key mapping.".to_string(),
+            action: None,
+            priority: Priority::Low,
+        });
+    }
+
+    // With several screens the pen area stretches across all of them
+    if let Some(tablet) = info.tablets.first().filter(|_| displays.len() > 1) {
+        let display = &displays[0];
+        // `card0-DP-1` in sysfs is `DP-1` to the compositor
+        let output = display.name.split_once('-').map(|(_, o)| o).unwrap_or(&display.name);
+        let action = match session.compositor {
+            Compositor::Sway => Some(format!("swaymsg input type:tablet_tool map_to_output {}", output)),
+            _ if x11 && tablet.vendor == TabletVendor::Wacom => {
+                Some(format!("xsetwacom set \"{} Pen stylus\" MapToOutput {}", tablet.name, output))
+            }
+            _ => None,
+        };
+        recs.push(Recommendation {
+            category: RecommendationCategory::Configuration,
+            title: "Map Tablet to One Display".to_string(),
+            description: format!(
+                "{} covers all {} displays, distorting the pen's aspect ratio; map it to {} (or the display you draw on).",
+                tablet.name, displays.len(), output
+            ),
+            action,
+            priority: Priority::Medium,
+        });
+    }
+
+    if !info.midi.is_empty() {
+        let names: Vec<&str> = info.midi.iter().map(|m| m.name.as_str()).collect();
+        if audio.server == audio::AudioServer::Jack {
+            recs.push(Recommendation {
+                category: RecommendationCategory::Package,
+                title: "ALSA MIDI Bridge for JACK".to_string(),
+                description: format!("a2jmidid makes {} available as JACK MIDI ports.", names.join(", ")),
+                action: Some("sudo pacman -S a2jmidid".to_string()),
+                priority: Priority::Medium,
+            });
+        } else {
+            recs.push(Recommendation {
+                category: RecommendationCategory::Configuration,
+                title: "MIDI Controllers".to_string(),
+                description: format!(
+                    "{} appear as ALSA sequencer ports, which PipeWire passes to DAWs; `aconnect -l` lists them.",
+                    names.join(", ")
+                ),
+                action: None,
+                priority: Priority::Low,
+            });
+        }
+    }
+
+    let focusrite = format!("{:04x}:", FOCUSRITE_VENDOR);
+    if info.audio_interfaces.iter().any(|a| a.usb_id.starts_with(&focusrite)) {
+        recs.push(Recommendation {
+            category: RecommendationCategory::Package,
+            title: "Focusrite Mixer Control".to_string(),
+            description: "alsa-scarlett-gui exposes the Scarlett and Clarett routing, levels and input switches the kernel driver supports.".to_string(),
+            action: Some("sudo pacman -S alsa-scarlett-gui".to_string()),
+            priority: Priority::Low,
+        });
+    }
+    if let Some(interface) = info.audio_interfaces.first() {
+        recs.push(Recommendation {
+            category: RecommendationCategory::Workflow,
+            title: "Audio Interface Detected".to_string(),
+            description: format!(
+                "{} is class compliant and needs no driver; the audio workflow sets a low-latency PipeWire quantum for recording.",
+                interface.name
+            ),
+            action: None,
+            priority: Priority::Low,
+        });
+    }
+
+    for card in info.capture.iter().filter(|c| c.needs_driver) {
+        let description = if card.vendor == "Blackmagic Design" {
+            "DeckLink and Intensity cards need Blackmagic's Desktop Video driver (AUR: decklink); they don't use V4L2.".to_string()
+        } else {
+            format!("No kernel driver is bound to {}; install the vendor's Linux driver.", card.name)
+        };
+        recs.push(Recommendation {
+            category: RecommendationCategory::Driver,
+            title: format!("{} Capture Driver", card.vendor),
+            description,
+            action: None,
+            priority: Priority::High,
+        });
+    }
+    if info.capture.iter().any(|c| c.video_device.is_some()) {
+        recs.push(Recommendation {
+            category: RecommendationCategory::Package,
+            title: "Video Capture Tools".to_string(),
+            description: "v4l2-ctl lists and sets capture formats, frame rates and inputs on capture cards.".to_string(),
+            action: Some("sudo pacman -S v4l-utils".to_string()),
+            priority: Priority::Low,
+        });
+    }
+
+    recs
+}
@@ -0,0 +1,330 @@
+//! Ordered install plan derived from detected hardware.
+//!
+//! Recommendations describe what would help; the plan turns the actionable
+//! ones into concrete steps the post-install wizard can show and run as a
+//! unit: packages first (drivers, firmware, codecs), then runtime tuning,
+//! then config writes that make the tuning survive a reboot.
+
+use super::environment::EnvironmentKind;
+use super::{firmware, HardwareInfo, Priority, RecommendationCategory};
+use serde::{Deserialize, Serialize};
+use std::io::Write;
+use std::path::PathBuf;
+use std::process::{Command, Stdio};
+
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct InstallPlan {
+    pub steps: Vec<PlanStep>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct PlanStep {
+    pub kind: StepKind,
+    pub title: String,
+    pub rationale: String,
+    pub action: StepAction,
+    pub priority: Priority,
+}
+
+/// Execution order of the plan's stages
+#[derive(Debug, Clone, Copy, Serialize, Deserialize, PartialEq, Eq, PartialOrd, Ord)]
+pub enum StepKind {
+    Driver,
+    Firmware,
+    Codec,
+    Package,
+    Tuning,
+    ConfigWrite,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq, Eq)]
+#[serde(tag = "type", rename_all = "snake_case")]
+pub enum StepAction {
+    /// Program and arguments, run without a shell
+    Command { argv: Vec<String>, root: bool },
+    /// Replace `path` with `contents`
+    WriteFile { path: PathBuf, contents: String, root: bool },
+}
+
+impl StepAction {
+    fn root_command(argv: &[&str]) -> Self {
+        StepAction::Command {
+            argv: argv.iter().map(|s| s.to_string()).collect(),
+            root: true,
+        }
+    }
+    
+    /// Shell-style rendering for display
+    pub fn command_line(&self) -> String {
+        match self {
+            StepAction::Command { argv, root } => {
+                let sudo = if *root { "sudo " } else { "" };
+                format!("{}{}", sudo, argv.join(" "))
+            }
+            StepAction::WriteFile { path, root, .. } => {
+                let sudo = if *root { "sudo " } else { "" };
+                format!("{}tee {}", sudo, path.display())
+            }
+        }
+    }
+    
+    pub fn execute(&self) -> std::io::Result<()> {
+        match self {
+            StepAction::Command { argv, root } => {
+                let Some((program, args)) = argv.split_first() else {
+                    return Ok(());
+                };
+                let status = if *root {
+                    Command::new("sudo").arg(program).args(args).status()?
+                } else {
+                    Command::new(program).args(args).status()?
+                };
+                check_status(status, &self.command_line())
+            }
+            StepAction::WriteFile { path, contents, root } => {
+                if !*root {
+                    if let Some(parent) = path.parent() {
+                        std::fs::create_dir_all(parent)?;
+                    }
+                    return std::fs::write(path, contents);
+                }
+                
+                let mut child = Command::new("sudo")
+                    .arg("tee")
+                    .arg(path)
+                    .stdin(Stdio::piped())
+                    .stdout(Stdio::null())
+                    .spawn()?;
+                if let Some(mut stdin) = child.stdin.take() {
+                    stdin.write_all(contents.as_bytes())?;
+                }
+                check_status(child.wait()?, &self.command_line())
+            }
+        }
+    }
+}
+
+fn check_status(status: std::process::ExitStatus, command: &str) -> std::io::Result<()> {
+    if status.success() {
+        Ok(())
+    } else {
+        Err(std::io::Error::other(format!("`{}` failed with {}", command, status)))
+    }
+}
+
+/// Outcome of one step when the plan is executed
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct StepResult {
+    pub title: String,
+    pub error: Option<String>,
+}
+
+impl InstallPlan {
+    pub fn is_empty(&self) -> bool {
+        self.steps.is_empty()
+    }
+    
+    /// Every package the plan installs, in order
+    pub fn packages(&self) -> Vec<&str> {
+        self.steps.iter()
+            .filter_map(|step| match &step.action {
+                StepAction::Command { argv, .. } if argv.starts_with(&pacman_prefix()) => {
+                    Some(argv[PACMAN_INSTALL.len()..].iter().map(String::as_str))
+                }
+                _ => None,
+            })
+            .flatten()
+            .collect()
+    }
+    
+    /// Run every step in order. A failed package step stops the plan, since
+    /// later tuning may depend on it; other failures are recorded and skipped.
+    pub fn execute(&self) -> Vec<StepResult> {
+        let mut results = Vec::new();
+        
+        for step in &self.steps {
+            let error = step.action.execute().err().map(|e| e.to_string());
+            let stop = error.is_some() && step.kind < StepKind::Tuning;
+            results.push(StepResult {
+                title: step.title.clone(),
+                error,
+            });
+            if stop {
+                break;
+            }
+        }
+        
+        results
+    }
+}
+
+const PACMAN_INSTALL: &[&str] = &["pacman", "-S", "--needed", "--noconfirm"];
+
+fn pacman_prefix() -> Vec<String> {
+    PACMAN_INSTALL.iter().map(|s| s.to_string()).collect()
+}
+
+fn install(packages: &[&str]) -> StepAction {
+    let mut argv = PACMAN_INSTALL.to_vec();
+    argv.extend_from_slice(packages);
+    StepAction::root_command(&argv)
+}
+
+/// Action of the "Audio Production Ready" suggestion
+const AUDIO_WORKFLOW_ACTION: &str = "rururu-workflow activate audio";
+
+const SWAPPINESS: u32 = 10;
+
+pub fn build_install_plan(info: &HardwareInfo) -> InstallPlan {
+    let mut steps = Vec::new();
+    
+    // A sandbox can't install packages or change the system at all
+    if info.environment.kind == EnvironmentKind::Flatpak {
+        return InstallPlan { steps };
+    }
+    
+    let mut firmware = vec!["linux-firmware"];
+    // The hypervisor loads microcode for its guests
+    if info.environment.owns_hardware() {
+        firmware.extend(firmware::microcode_package(info.cpu.vendor));
+    }
+    
+    // Packages the recommendations ask for, keeping their rationale
+    for rec in &info.recommendations {
+        let Some(packages) = rec.action.as_deref()
+            .and_then(|a| a.strip_prefix("sudo pacman -S "))
+        else {
+            continue;
+        };
+        let packages: Vec<&str> = packages.split_whitespace().collect();
+        // Microcode is installed by the firmware step below
+        if packages.is_empty() || packages.iter().all(|p| firmware.contains(p)) {
+            continue;
+        }
+        
+        let kind = match rec.category {
+            RecommendationCategory::Driver => StepKind::Driver,
+            RecommendationCategory::Firmware => StepKind::Firmware,
+            _ if packages.iter().any(|p| p.contains("media-driver") || p.starts_with("libva")) => StepKind::Codec,
+            _ => StepKind::Package,
+        };
+        // One step per package set, e.g. for two GPUs of the same vendor
+        let action = install(&packages);
+        if steps.iter().any(|s: &PlanStep| s.action == action) {
+            continue;
+        }
+        steps.push(PlanStep {
+            kind,
+            title: rec.title.clone(),
+            rationale: rec.description.clone(),
+            action,
+            priority: rec.priority,
+        });
+    }
+    
+    // CPU microcode and device firmware; a container boots no kernel to load them
+    if info.environment.has_own_kernel() {
+        steps.push(PlanStep {
+            kind: StepKind::Firmware,
+            title: "Firmware and Microcode".to_string(),
+            rationale: "Device firmware and CPU microcode carry stability and security fixes the kernel loads at boot.".to_string(),
+            action: install(&firmware),
+            priority: Priority::High,
+        });
+    }
+    
+    // Codecs creative apps and previews expect
+    steps.push(PlanStep {
+        kind: StepKind::Codec,
+        title: "Media Codecs".to_string(),
+        rationale: "FFmpeg and GStreamer plugins let editors, players and thumbnails handle common video and audio formats.".to_string(),
+        action: install(&["ffmpeg", "gst-libav", "gst-plugins-good", "gst-plugins-bad", "gst-plugins-ugly"]),
+        priority: Priority::Medium,
+    });
+    
+    // Lower swappiness when there's RAM to spare
+    let low_swappiness = info.recommendations.iter().any(|r| {
+        r.action.as_deref() == Some(&format!("sudo sysctl vm.swappiness={}", SWAPPINESS))
+    });
+    if low_swappiness {
+        let rationale = format!(
+            "{} GB RAM leaves room to keep working sets in memory instead of swapping.",
+            info.memory.total_gb
+        );
+        steps.push(PlanStep {
+            kind: StepKind::Tuning,
+            title: "Reduce Swappiness".to_string(),
+            rationale: rationale.clone(),
+            action: StepAction::root_command(&["sysctl", &format!("vm.swappiness={}", SWAPPINESS)]),
+            priority: Priority::Low,
+        });
+        steps.push(PlanStep {
+            kind: StepKind::ConfigWrite,
+            title: "Persist Swappiness".to_string(),
+            rationale,
+            action: StepAction::WriteFile {
+                path: PathBuf::from("/etc/sysctl.d/99-rururu.conf"),
+                contents: format!("vm.swappiness = {}\n", SWAPPINESS),
+                root: true,
+            },
+            priority: Priority::Low,
+        });
+    }
+    
+    // Real-time audio needs the CPU at full clock to avoid xruns. Guests
+    // and containers don't control CPU frequency; the host does.
+    let audio_ready = info.environment.owns_hardware() && info.recommendations.iter()
+        .any(|r| r.action.as_deref() == Some(AUDIO_WORKFLOW_ACTION));
+    if audio_ready {
+        let rationale = format!(
+            "{} cores suit real-time audio; the performance governor avoids clock ramp-up latency that causes dropouts.",
+            info.cpu.cores
+        );
+        steps.push(PlanStep {
+            kind: StepKind::Package,
+            title: "CPU Frequency Tools".to_string(),
+            rationale: "cpupower sets and restores the CPU frequency governor.".to_string(),
+            action: install(&["cpupower"]),
+            priority: Priority::Medium,
+        });
+        steps.push(PlanStep {
+            kind: StepKind::Tuning,
+            title: "Performance CPU Governor".to_string(),
+            rationale: rationale.clone(),
+            action: StepAction::root_command(&["cpupower", "frequency-set", "-g", "performance"]),
+            priority: Priority::Medium,
+        });
+        steps.push(PlanStep {
+            kind: StepKind::ConfigWrite,
+            title: "Persist CPU Governor".to_string(),
+            rationale: rationale.clone(),
+            action: StepAction::WriteFile {
+                path: PathBuf::from("/etc/default/cpupower"),
+                contents: "governor='performance'\n".to_string(),
+                root: true,
+            },
+            priority: Priority::Medium,
+        });
+        steps.push(PlanStep {
+            kind: StepKind::ConfigWrite,
+            title: "Apply CPU Governor at Boot".to_string(),
+            rationale,
+            action: StepAction::root_command(&["systemctl", "enable", "cpupower.service"]),
+            priority: Priority::Medium,
+        });
+    }
+    
+    // Stable sort: stage order first, then urgency within a stage
+    steps.sort_by_key(|step| (step.kind, priority_rank(step.priority)));
+    
+    InstallPlan { steps }
+}
+
+fn priority_rank(priority: Priority) -> u8 {
+    match priority {
+        Priority::Critical => 0,
+        Priority::High => 1,
+        Priority::Medium => 2,
+        Priority::Low => 3,
+    }
+}
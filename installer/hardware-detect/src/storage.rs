@@ -90,6 +90,6 @@ pub fn detect() -> Vec<StorageInfo> {
         }
     }
     
-    devices.sort_by(|a, b| b.size_gb.cmp(&a.size_gb));
+    devices.sort_by_key(|d| std::cmp::Reverse(d.size_gb));
     devices
 }
@@ -0,0 +1,181 @@
+//! Consistency checks on a detection run.
+//!
+//! Detectors fall back to placeholder values ("Unknown", one core, zero
+//! sizes) rather than failing, which hides regressions. `validate` flags
+//! values that can't be right on real hardware so `rururu-hwdetect
+//! --validate` can fail CI and go into support reports.
+
+use super::{cpu, gpu, memory, network, HardwareInfo};
+use serde::{Deserialize, Serialize};
+
+/// Largest VRAM taken as real; anything above is a parsing error
+const MAX_VRAM_MB: u32 = 512 * 1024;
+const MAX_REFRESH_HZ: f32 = 1000.0;
+
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq, Eq)]
+pub struct Anomaly {
+    /// Detector the value came from: "cpu", "gpu", "memory", ...
+    pub component: String,
+    pub message: String,
+}
+
+impl Anomaly {
+    fn new(component: &str, message: impl Into<String>) -> Self {
+        Self {
+            component: component.to_string(),
+            message: message.into(),
+        }
+    }
+}
+
+/// Everything in `info` that looks like a detection failure; empty when
+/// the run is consistent
+pub fn validate(info: &HardwareInfo) -> Vec<Anomaly> {
+    let mut anomalies = Vec::new();
+
+    check_cpu(&info.cpu, &mut anomalies);
+
+    for g in &info.gpu {
+        check_gpu(g, &mut anomalies);
+    }
+
+    // Usually DRM connector enumeration failing; headless and compute-only
+    // machines will see this too
+    if !info.gpu.is_empty() && info.displays.is_empty() {
+        anomalies.push(Anomaly::new(
+            "display",
+            "GPU detected but no connected display",
+        ));
+    }
+    for display in &info.displays {
+        let label = format!("{} ({})", display.name, display.connector);
+        if display.connector.is_empty() || display.connector == "Unknown" {
+            anomalies.push(Anomaly::new(
+                "display",
+                format!("{}: connector type not detected", display.name),
+            ));
+        }
+        if let Some((w, h)) = display.resolution {
+            if w == 0 || h == 0 {
+                anomalies.push(Anomaly::new(
+                    "display",
+                    format!("{}: resolution {}x{}", label, w, h),
+                ));
+            }
+        }
+        if let Some(rate) = display.refresh_rate {
+            if !rate.is_finite() || rate <= 0.0 || rate > MAX_REFRESH_HZ {
+                anomalies.push(Anomaly::new(
+                    "display",
+                    format!("{}: refresh rate {} Hz", label, rate),
+                ));
+            }
+        }
+    }
+
+    check_memory(&info.memory, &mut anomalies);
+
+    if info.storage.is_empty() {
+        anomalies.push(Anomaly::new("storage", "No disks detected"));
+    }
+    for disk in &info.storage {
+        if disk.size_gb == 0 && !disk.removable {
+            anomalies.push(Anomaly::new(
+                "storage",
+                format!("{}: size reported as 0 GB", disk.name),
+            ));
+        }
+    }
+
+    for device in &info.audio.devices {
+        if device.name.trim().is_empty() {
+            anomalies.push(Anomaly::new(
+                "audio",
+                format!("{:?} device without a name", device.device_type),
+            ));
+        }
+    }
+
+    for iface in &info.network {
+        if iface.mac_address.as_deref() == Some("00:00:00:00:00:00")
+            && iface.interface_type != network::NetworkType::Loopback
+        {
+            anomalies.push(Anomaly::new(
+                "network",
+                format!("{}: all-zero MAC address", iface.name),
+            ));
+        }
+    }
+
+    anomalies
+}
+
+fn check_cpu(info: &cpu::CpuInfo, anomalies: &mut Vec<Anomaly>) {
+    let model = info.model.trim();
+    if model.is_empty() || model == "Unknown" {
+        anomalies.push(Anomaly::new("cpu", "CPU model not detected"));
+    }
+    if info.vendor == cpu::CpuVendor::Unknown {
+        anomalies.push(Anomaly::new("cpu", "CPU vendor not detected"));
+    }
+    if info.arch == cpu::CpuArch::Unknown {
+        anomalies.push(Anomaly::new("cpu", "CPU architecture not detected"));
+    }
+    if info.cores == 0 {
+        anomalies.push(Anomaly::new("cpu", "0 CPU cores"));
+    } else if info.threads < info.cores {
+        anomalies.push(Anomaly::new(
+            "cpu",
+            format!("{} threads but {} cores", info.threads, info.cores),
+        ));
+    }
+    if info.freq_mhz == Some(0) {
+        anomalies.push(Anomaly::new("cpu", "CPU frequency reported as 0 MHz"));
+    }
+}
+
+fn check_gpu(info: &gpu::GpuInfo, anomalies: &mut Vec<Anomaly>) {
+    let name = info.name.trim();
+    if name.is_empty() || name == "Unknown GPU" {
+        anomalies.push(Anomaly::new("gpu", "GPU model not detected"));
+    }
+    if info.vendor == gpu::GpuVendor::Unknown {
+        anomalies.push(Anomaly::new(
+            "gpu",
+            format!("{}: vendor not detected", name),
+        ));
+    }
+    match info.vram_mb {
+        Some(0) => anomalies.push(Anomaly::new(
+            "gpu",
+            format!("{}: VRAM reported as 0 MB", name),
+        )),
+        Some(vram) if vram > MAX_VRAM_MB => anomalies.push(Anomaly::new(
+            "gpu",
+            format!("{}: implausible VRAM of {} MB", name, vram),
+        )),
+        _ => {}
+    }
+}
+
+fn check_memory(info: &memory::MemoryInfo, anomalies: &mut Vec<Anomaly>) {
+    if info.total_gb == 0 {
+        anomalies.push(Anomaly::new("memory", "Total memory reported as 0 GB"));
+    }
+    if info.channels == Some(0) {
+        anomalies.push(Anomaly::new("memory", "0 memory channels"));
+    }
+
+    // Firmware reserves some RAM, so the kernel total can fall short of the
+    // installed DIMMs, but never exceed them
+    let installed_mb: u64 = info.dimms.iter().filter_map(|d| d.size_mb).sum();
+    if installed_mb > 0 && u64::from(info.total_gb) * 1024 > installed_mb {
+        anomalies.push(Anomaly::new(
+            "memory",
+            format!(
+                "{} GB usable but only {} MB in populated slots",
+                info.total_gb, installed_mb
+            ),
+        ));
+    }
+}
@@ -0,0 +1,318 @@
+//! Hardware video encode and decode blocks: NVIDIA NVENC/NVDEC, AMD
+//! VCE/UVD and VCN, and Intel Quick Sync.
+//!
+//! None of the drivers say which codecs a block handles without a working
+//! VA-API or NVENC stack, so the engine is worked out from the GPU model in
+//! the lspci name: NVIDIA and AMD chip codenames (AD102, Navi 31) or
+//! marketing names (RTX 4090, RX 7900), Intel platform names (Alder Lake,
+//! DG2). The tables cover consumer parts for the codecs editors export to;
+//! datacenter variants without an encoder are called out where known.
+
+use super::{gpu, Priority, Recommendation, RecommendationCategory};
+use serde::{Deserialize, Serialize};
+
+#[derive(Debug, Clone, Copy, Serialize, Deserialize, PartialEq, Eq)]
+pub enum VideoCodec {
+    H264,
+    Hevc,
+    Vp9,
+    Av1,
+}
+
+impl VideoCodec {
+    pub fn name(self) -> &'static str {
+        match self {
+            VideoCodec::H264 => "H.264",
+            VideoCodec::Hevc => "HEVC",
+            VideoCodec::Vp9 => "VP9",
+            VideoCodec::Av1 => "AV1",
+        }
+    }
+}
+
+/// What the hardware does with one codec
+#[derive(Debug, Clone, Copy, Serialize, Deserialize, PartialEq, Eq)]
+pub struct HwCodec {
+    pub codec: VideoCodec,
+    pub encode: bool,
+    pub decode: bool,
+}
+
+/// One generation of video block
+struct Engine {
+    name: &'static str,
+    encode: &'static [VideoCodec],
+    decode: &'static [VideoCodec],
+}
+
+use VideoCodec::{Av1, Hevc, Vp9, H264};
+
+const NV_KEPLER: Engine = Engine { name: "NVENC (Kepler)", encode: &[H264], decode: &[H264] };
+const NV_MAXWELL1: Engine = Engine { name: "NVENC (Maxwell 1st gen)", encode: &[H264], decode: &[H264] };
+const NV_MAXWELL: Engine = Engine { name: "NVENC (Maxwell 2nd gen)", encode: &[H264, Hevc], decode: &[H264] };
+const NV_PASCAL: Engine = Engine { name: "NVENC 6th gen (Pascal)", encode: &[H264, Hevc], decode: &[H264, Hevc, Vp9] };
+const NV_TURING: Engine = Engine { name: "NVENC 7th gen (Turing)", encode: &[H264, Hevc], decode: &[H264, Hevc, Vp9] };
+const NV_AMPERE: Engine = Engine { name: "NVENC 7th gen, NVDEC 5th gen (Ampere)", encode: &[H264, Hevc], decode: &[H264, Hevc, Vp9, Av1] };
+const NV_ADA: Engine = Engine { name: "NVENC 8th gen (Ada)", encode: &[H264, Hevc, Av1], decode: &[H264, Hevc, Vp9, Av1] };
+const NV_BLACKWELL: Engine = Engine { name: "NVENC 9th gen (Blackwell)", encode: &[H264, Hevc, Av1], decode: &[H264, Hevc, Vp9, Av1] };
+/// A100 and H100 have NVDEC but no NVENC
+const NV_COMPUTE: Engine = Engine { name: "NVDEC only (datacenter)", encode: &[], decode: &[H264, Hevc, Vp9, Av1] };
+
+const AMD_POLARIS: Engine = Engine { name: "VCE 3.4 / UVD 6.3 (Polaris)", encode: &[H264, Hevc], decode: &[H264, Hevc] };
+const AMD_VEGA: Engine = Engine { name: "VCE 4 / UVD 7 (Vega)", encode: &[H264, Hevc], decode: &[H264, Hevc, Vp9] };
+const AMD_VCN2: Engine = Engine { name: "VCN 1/2 (RDNA 1, Vega APUs)", encode: &[H264, Hevc], decode: &[H264, Hevc, Vp9] };
+const AMD_VCN3: Engine = Engine { name: "VCN 3 (RDNA 2)", encode: &[H264, Hevc], decode: &[H264, Hevc, Vp9, Av1] };
+/// Navi 24 (RX 6400/6500) dropped the encoder
+const AMD_VCN3_DECODE: Engine = Engine { name: "VCN 3, decode only (Navi 24)", encode: &[], decode: &[H264, Hevc, Vp9, Av1] };
+const AMD_VCN4: Engine = Engine { name: "VCN 4 (RDNA 3)", encode: &[H264, Hevc, Av1], decode: &[H264, Hevc, Vp9, Av1] };
+const AMD_VCN5: Engine = Engine { name: "VCN 5 (RDNA 4)", encode: &[H264, Hevc, Av1], decode: &[H264, Hevc, Vp9, Av1] };
+
+const INTEL_GEN7: Engine = Engine { name: "Quick Sync (Gen 7/8)", encode: &[H264], decode: &[H264] };
+const INTEL_GEN9: Engine = Engine { name: "Quick Sync (Gen 9)", encode: &[H264, Hevc], decode: &[H264, Hevc, Vp9] };
+const INTEL_GEN11: Engine = Engine { name: "Quick Sync (Gen 11)", encode: &[H264, Hevc, Vp9], decode: &[H264, Hevc, Vp9] };
+const INTEL_XE: Engine = Engine { name: "Quick Sync (Xe, Gen 12)", encode: &[H264, Hevc, Vp9], decode: &[H264, Hevc, Vp9, Av1] };
+const INTEL_XE_HPG: Engine = Engine { name: "Quick Sync (Xe-HPG and later)", encode: &[H264, Hevc, Vp9, Av1], decode: &[H264, Hevc, Vp9, Av1] };
+
+/// What editors commonly need: H.264 and HEVC both ways, AV1 for delivery
+const EDITING_NEEDS: &[(VideoCodec, bool)] = &[
+    (H264, true),
+    (Hevc, true),
+    (Av1, true),
+    (H264, false),
+    (Hevc, false),
+    (Av1, false),
+];
+
+/// The video block's generation and codecs, or `None` for an unrecognised
+/// model
+pub fn identify(vendor: gpu::GpuVendor, name: &str) -> Option<(String, Vec<HwCodec>)> {
+    let upper = name.to_uppercase();
+    let engine = match vendor {
+        gpu::GpuVendor::Nvidia => nvidia(&upper),
+        gpu::GpuVendor::Amd => amd(&upper),
+        gpu::GpuVendor::Intel => intel(&upper),
+        _ => None,
+    }?;
+
+    let codecs = [H264, Hevc, Vp9, Av1]
+        .into_iter()
+        .map(|codec| HwCodec {
+            codec,
+            encode: engine.encode.contains(&codec),
+            decode: engine.decode.contains(&codec),
+        })
+        .filter(|c| c.encode || c.decode)
+        .collect();
+    Some((engine.name.to_string(), codecs))
+}
+
+fn nvidia(name: &str) -> Option<&'static Engine> {
+    // Chip codename first: "AD102 [GeForce RTX 4090]", "GA107M"
+    for word in words(name) {
+        let family: String = word.chars().take_while(char::is_ascii_alphabetic).collect();
+        let digits: String = word[family.len()..].chars().take_while(char::is_ascii_digit).collect();
+        if digits.len() != 3 {
+            continue;
+        }
+        let engine = match family.as_str() {
+            "GA" | "GH" if digits == "100" => &NV_COMPUTE,
+            "GK" => &NV_KEPLER,
+            "GM" if digits.starts_with('1') => &NV_MAXWELL1,
+            "GM" => &NV_MAXWELL,
+            "GP" => &NV_PASCAL,
+            "GV" | "TU" => &NV_TURING,
+            "GA" => &NV_AMPERE,
+            "AD" => &NV_ADA,
+            "GB" => &NV_BLACKWELL,
+            _ => continue,
+        };
+        return Some(engine);
+    }
+
+    // Then the marketing name: "RTX 3060", "GTX 1080"
+    let model = after(name, &["RTX", "GTX"])?;
+    Some(match model {
+        5000..=5999 => &NV_BLACKWELL,
+        4000..=4999 => &NV_ADA,
+        3000..=3999 => &NV_AMPERE,
+        2000..=2999 | 1600..=1699 => &NV_TURING,
+        1000..=1099 => &NV_PASCAL,
+        900..=999 => &NV_MAXWELL,
+        750..=799 => &NV_MAXWELL1,
+        600..=749 => &NV_KEPLER,
+        _ => return None,
+    })
+}
+
+fn amd(name: &str) -> Option<&'static Engine> {
+    let navi = |n: u32| match n {
+        10 | 12 | 14 => Some(&AMD_VCN2),
+        24 => Some(&AMD_VCN3_DECODE),
+        21..=23 => Some(&AMD_VCN3),
+        31..=33 => Some(&AMD_VCN4),
+        44 | 48 => Some(&AMD_VCN5),
+        _ => None,
+    };
+    if let Some(n) = after(name, &["NAVI"]) {
+        return navi(n);
+    }
+
+    // APUs go by platform name
+    let platforms: &[(&str, &'static Engine)] = &[
+        ("STRIX", &AMD_VCN4),
+        ("HAWK POINT", &AMD_VCN4),
+        ("PHOENIX", &AMD_VCN4),
+        ("REMBRANDT", &AMD_VCN3),
+        ("RAPHAEL", &AMD_VCN3),
+        ("VAN GOGH", &AMD_VCN3),
+        ("BARCELO", &AMD_VCN2),
+        ("CEZANNE", &AMD_VCN2),
+        ("LUCIENNE", &AMD_VCN2),
+        ("RENOIR", &AMD_VCN2),
+        ("PICASSO", &AMD_VCN2),
+        ("RAVEN", &AMD_VCN2),
+        ("VEGA", &AMD_VEGA),
+        ("ELLESMERE", &AMD_POLARIS),
+        ("BAFFIN", &AMD_POLARIS),
+        ("LEXA", &AMD_POLARIS),
+        ("POLARIS", &AMD_POLARIS),
+    ];
+    if let Some((_, engine)) = platforms.iter().find(|(platform, _)| name.contains(platform)) {
+        return Some(engine);
+    }
+
+    // "RX 7900 XTX", "RX 580"
+    Some(match after(name, &["RX"])? {
+        9000..=9999 => &AMD_VCN5,
+        7000..=7999 => &AMD_VCN4,
+        6400..=6500 => &AMD_VCN3_DECODE,
+        6000..=6999 => &AMD_VCN3,
+        5000..=5999 => &AMD_VCN2,
+        400..=599 => &AMD_POLARIS,
+        _ => return None,
+    })
+}
+
+fn intel(name: &str) -> Option<&'static Engine> {
+    let platforms: &[(&str, &'static Engine)] = &[
+        ("BATTLEMAGE", &INTEL_XE_HPG),
+        ("LUNAR LAKE", &INTEL_XE_HPG),
+        ("ARROW LAKE", &INTEL_XE_HPG),
+        ("METEOR LAKE", &INTEL_XE_HPG),
+        ("DG2", &INTEL_XE_HPG),
+        ("ARC A", &INTEL_XE_HPG),
+        ("ARC B", &INTEL_XE_HPG),
+        ("ARC GRAPHICS", &INTEL_XE_HPG),
+        ("RAPTOR LAKE", &INTEL_XE),
+        ("ALDER LAKE", &INTEL_XE),
+        ("ROCKET LAKE", &INTEL_XE),
+        ("TIGER LAKE", &INTEL_XE),
+        ("TIGERLAKE", &INTEL_XE),
+        ("DG1", &INTEL_XE),
+        ("IRIS XE", &INTEL_XE),
+        ("ICE LAKE", &INTEL_GEN11),
+        ("ICELAKE", &INTEL_GEN11),
+        ("JASPER LAKE", &INTEL_GEN11),
+        ("ELKHART LAKE", &INTEL_GEN11),
+        ("COMET LAKE", &INTEL_GEN9),
+        ("COMETLAKE", &INTEL_GEN9),
+        ("COFFEE LAKE", &INTEL_GEN9),
+        ("COFFEELAKE", &INTEL_GEN9),
+        ("WHISKEY LAKE", &INTEL_GEN9),
+        ("KABY LAKE", &INTEL_GEN9),
+        ("KABYLAKE", &INTEL_GEN9),
+        ("GEMINI LAKE", &INTEL_GEN9),
+        ("SKYLAKE", &INTEL_GEN9),
+        ("BROADWELL", &INTEL_GEN7),
+        ("HASWELL", &INTEL_GEN7),
+        ("IVY BRIDGE", &INTEL_GEN7),
+    ];
+    if let Some((_, engine)) = platforms.iter().find(|(platform, _)| name.contains(platform)) {
+        return Some(engine);
+    }
+
+    // Some only give the model: "HD Graphics 620", "UHD Graphics 770"
+    Some(match after(name, &["GRAPHICS"])? {
+        700..=799 => &INTEL_XE,
+        500..=699 => &INTEL_GEN9,
+        4000..=6999 => &INTEL_GEN7,
+        _ => return None,
+    })
+}
+
+/// Words of a device name, brackets and slashes taken as separators
+fn words(name: &str) -> impl Iterator<Item = &str> {
+    name.split(|c: char| c.is_whitespace() || "[]/(),".contains(c))
+        .filter(|w| !w.is_empty())
+}
+
+/// The number after the first of `prefixes`: 4090 in "RTX 4090", 31 in
+/// "NAVI 31"
+fn after(name: &str, prefixes: &[&str]) -> Option<u32> {
+    let mut words = words(name).peekable();
+    while let Some(word) = words.next() {
+        if prefixes.contains(&word) {
+            let digits: String = words.peek()?.chars().take_while(char::is_ascii_digit).collect();
+            return digits.parse().ok();
+        }
+    }
+    None
+}
+
+/// One recommendation per commonly needed codec no GPU handles in
+/// hardware, stronger when the video workflow is suggested. Nothing is said
+/// when no GPU model is recognised.
+pub fn get_recommendations(gpus: &[gpu::GpuInfo], workflows: &[&str]) -> Vec<Recommendation> {
+    if gpus.iter().all(|g| g.video_engine.is_none()) {
+        return Vec::new();
+    }
+    let priority = if workflows.contains(&"video") { Priority::Medium } else { Priority::Low };
+    let supported = |codec: VideoCodec, encode: bool| {
+        gpus.iter()
+            .flat_map(|g| &g.encode_decode)
+            .any(|c| c.codec == codec && if encode { c.encode } else { c.decode })
+    };
+
+    EDITING_NEEDS
+        .iter()
+        .filter(|&&(codec, encode)| !supported(codec, encode))
+        .map(|&(codec, encode)| {
+            let (title, description) = if encode {
+                (
+                    format!("No Hardware {} Encode", codec.name()),
+                    format!(
+                        "Exports to {} run on the CPU, several times slower than a hardware encoder. {}",
+                        codec.name(),
+                        newer_hardware(codec, true)
+                    ),
+                )
+            } else {
+                (
+                    format!("No Hardware {} Decode", codec.name()),
+                    format!(
+                        "{} footage decodes on the CPU, so timeline playback may need proxies. {}",
+                        codec.name(),
+                        newer_hardware(codec, false)
+                    ),
+                )
+            };
+            Recommendation {
+                category: RecommendationCategory::Performance,
+                title,
+                description,
+                action: None,
+                priority,
+            }
+        })
+        .collect()
+}
+
+/// Which current hardware covers `codec`
+fn newer_hardware(codec: VideoCodec, encode: bool) -> &'static str {
+    match (codec, encode) {
+        (Av1, true) => "GeForce RTX 40, Radeon RX 7000 and Intel Arc GPUs encode AV1.",
+        (Av1, false) => "GeForce RTX 30, Radeon RX 6000 and Intel Xe GPUs decode AV1.",
+        (Hevc, _) => "Any GPU from the last several generations handles HEVC.",
+        _ => "Any GPU from the last decade handles H.264.",
+    }
+}
@@ -1,13 +1,16 @@
 use iced::{
     widget::{button, column, container, row, scrollable, text, vertical_space},
-    Element, Length,
+    Command, Element, Length,
 };
-use rururu_hardware_detect::{HardwareInfo, Priority};
+use rururu_hardware_detect::{build_install_plan, HardwareInfo, InstallPlan, Priority, StepResult};
 use crate::wizard::Message;
 
 pub struct HardwarePage {
     pub info: Option<HardwareInfo>,
     pub applied_recommendations: Vec<usize>,
+    pub plan: InstallPlan,
+    pub applying_plan: bool,
+    pub plan_results: Option<Vec<StepResult>>,
 }
 
 impl HardwarePage {
@@ -15,9 +18,77 @@ impl HardwarePage {
         Self {
             info: None,
             applied_recommendations: Vec::new(),
+            plan: InstallPlan::default(),
+            applying_plan: false,
+            plan_results: None,
         }
     }
     
+    pub fn set_info(&mut self, info: HardwareInfo) {
+        self.plan = build_install_plan(&info);
+        self.info = Some(info);
+    }
+    
+    /// Run the whole install plan off the UI thread
+    pub fn apply_plan(&mut self) -> Command<Message> {
+        if self.applying_plan || self.plan.is_empty() {
+            return Command::none();
+        }
+        self.applying_plan = true;
+        
+        let plan = self.plan.clone();
+        Command::perform(
+            async move {
+                tokio::task::spawn_blocking(move || plan.execute())
+                    .await
+                    .unwrap_or_default()
+            },
+            Message::InstallPlanApplied,
+        )
+    }
+    
+    pub fn plan_applied(&mut self, results: Vec<StepResult>) {
+        self.applying_plan = false;
+        self.plan_results = Some(results);
+    }
+    
+    fn plan_view(&self) -> Element<'_, Message> {
+        if self.plan.is_empty() {
+            return column![].into();
+        }
+        
+        let steps = self.plan.steps.iter().enumerate().fold(
+            column![text("Install Plan").size(18)].spacing(8),
+            |col, (i, step)| {
+                let status = match self.plan_results.as_ref().and_then(|r| r.get(i)) {
+                    Some(result) if result.error.is_none() => " ✓".to_string(),
+                    Some(result) => format!(" ✗ {}", result.error.as_deref().unwrap_or_default()),
+                    None => String::new(),
+                };
+                col.push(
+                    column![
+                        text(format!("{}. {}{}", i + 1, step.title, status)),
+                        text(step.action.command_line()).size(12).font(iced::Font::MONOSPACE),
+                        text(&step.rationale).size(12),
+                    ]
+                    .spacing(2)
+                )
+            }
+        );
+        
+        let apply_btn = if self.applying_plan {
+            button(text("Applying..."))
+        } else if self.plan_results.is_some() {
+            button(text("Applied ✓"))
+        } else {
+            button(text("Apply Plan"))
+                .on_press(Message::ApplyInstallPlan)
+                .style(iced::theme::Button::Primary)
+        };
+        
+        column![steps, apply_btn].spacing(10).into()
+    }
+    
     pub fn apply_recommendation(&mut self, index: usize) {
         if let Some(ref info) = self.info {
             if let Some(rec) = info.recommendations.get(index) {
@@ -137,6 +208,8 @@ impl HardwarePage {
                         .spacing(30),
                         vertical_space().height(30),
                         recommendations,
+                        vertical_space().height(30),
+                        self.plan_view(),
                     ]
                     .spacing(10)
                     .padding(10)
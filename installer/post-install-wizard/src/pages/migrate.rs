@@ -0,0 +1,260 @@
+use iced::{
+    widget::{button, checkbox, column, pick_list, row, scrollable, text, text_input, vertical_space},
+    Command, Element, Length,
+};
+use rururu_workflows::migrate::{self, ConflictPolicy, MigrationReport};
+use rururu_workflows::{MigrationPlan, MigrationSource};
+use crate::wizard::Message;
+
+/// Optional step: bring app settings over from a previous system
+pub struct MigratePage {
+    /// Previous systems on mounted drives; `None` until looked for
+    pub sources: Option<Vec<MigrationSource>>,
+    pub path: String,
+    pub scanning: bool,
+    pub plan: Option<Result<MigrationPlan, String>>,
+    /// Whether each planned item is imported
+    pub selected: Vec<bool>,
+    pub policy: ConflictPolicy,
+    pub importing: bool,
+    pub report: Option<MigrationReport>,
+}
+
+impl MigratePage {
+    pub fn new() -> Self {
+        Self {
+            sources: None,
+            path: String::new(),
+            scanning: false,
+            plan: None,
+            selected: Vec::new(),
+            policy: ConflictPolicy::default(),
+            importing: false,
+            report: None,
+        }
+    }
+
+    pub fn find_sources(&self) -> Command<Message> {
+        if self.sources.is_some() {
+            return Command::none();
+        }
+        Command::perform(
+            async {
+                tokio::task::spawn_blocking(migrate::find_sources)
+                    .await
+                    .unwrap_or_default()
+            },
+            Message::MigrationSourcesFound,
+        )
+    }
+
+    /// List what `source` holds, without copying anything yet
+    pub fn scan(&mut self, source: MigrationSource) -> Command<Message> {
+        if self.scanning || self.importing {
+            return Command::none();
+        }
+        self.path = match source {
+            MigrationSource::MountedSystem { ref root, .. } => root.display().to_string(),
+            MigrationSource::Backup(ref dir) => dir.display().to_string(),
+        };
+        self.scanning = true;
+        self.plan = None;
+        self.report = None;
+
+        Command::perform(
+            async move {
+                tokio::task::spawn_blocking(move || {
+                    migrate::plan(&source).map_err(|e| e.to_string())
+                })
+                .await
+                .unwrap_or_else(|e| Err(e.to_string()))
+            },
+            Message::MigrationPlanned,
+        )
+    }
+
+    /// Scan the typed path, which may be an old home directory or the root
+    /// of a whole system
+    pub fn scan_path(&mut self) -> Command<Message> {
+        let path = self.path.trim();
+        if path.is_empty() {
+            return Command::none();
+        }
+        let source = MigrationSource::Backup(path.into());
+        self.scan(source)
+    }
+
+    pub fn planned(&mut self, plan: Result<MigrationPlan, String>) {
+        self.scanning = false;
+        self.selected = match plan {
+            Ok(ref plan) => vec![true; plan.items.len()],
+            Err(_) => Vec::new(),
+        };
+        self.plan = Some(plan);
+    }
+
+    pub fn toggle_item(&mut self, index: usize) {
+        if let Some(selected) = self.selected.get_mut(index) {
+            *selected = !*selected;
+        }
+    }
+
+    /// Copy the selected items off the UI thread
+    pub fn import(&mut self) -> Command<Message> {
+        let Some(Ok(ref plan)) = self.plan else {
+            return Command::none();
+        };
+        if self.importing || !self.selected.contains(&true) {
+            return Command::none();
+        }
+        self.importing = true;
+
+        let mut plan = plan.clone();
+        let mut selected = self.selected.iter();
+        plan.items.retain(|_| selected.next().copied().unwrap_or(false));
+        let policy = self.policy;
+        Command::perform(
+            async move {
+                tokio::task::spawn_blocking(move || plan.apply(policy))
+                    .await
+                    .unwrap_or_default()
+            },
+            Message::SettingsImported,
+        )
+    }
+
+    pub fn imported(&mut self, report: MigrationReport) {
+        self.importing = false;
+        self.report = Some(report);
+    }
+
+    fn plan_view(&self) -> Element<'_, Message> {
+        let plan = match &self.plan {
+            None if self.scanning => return text("Looking for app settings...").into(),
+            None => return column![].into(),
+            Some(Err(e)) => return text(format!("Can't read {}: {}", self.path, e)).into(),
+            Some(Ok(plan)) => plan,
+        };
+        if plan.is_empty() {
+            return text(format!(
+                "No Blender, Krita, GIMP, Darktable or Ardour settings in {}",
+                plan.source_home.display()
+            ))
+            .into();
+        }
+
+        let items = plan.items.iter().enumerate().fold(
+            column![text(format!("Found in {}", plan.source_home.display())).size(18)].spacing(8),
+            |col, (i, item)| {
+                let selected = self.selected.get(i).copied().unwrap_or(false);
+                let mut details = format!(
+                    "{} → {} ({} files)",
+                    item.source.display(),
+                    item.target.display(),
+                    item.file_count(),
+                );
+                if !item.conflicts.is_empty() {
+                    details.push_str(&format!(", {} already here and different", item.conflicts.len()));
+                }
+                col.push(
+                    column![
+                        checkbox(format!("{} {}", item.app, item.kind), selected)
+                            .on_toggle(move |_| Message::ToggleMigrationItem(i)),
+                        text(details).size(12),
+                    ]
+                    .spacing(2)
+                )
+            }
+        );
+
+        let policy: Element<Message> = if plan.conflict_count() > 0 {
+            row![
+                text("Existing files:"),
+                pick_list(
+                    &ConflictPolicy::ALL[..],
+                    Some(self.policy),
+                    Message::MigrationPolicySelected,
+                ),
+            ]
+            .spacing(10)
+            .align_items(iced::Alignment::Center)
+            .into()
+        } else {
+            column![].into()
+        };
+
+        let import_btn = if self.importing {
+            button(text("Importing..."))
+        } else if self.report.is_some() {
+            button(text("Imported ✓"))
+        } else if self.selected.contains(&true) {
+            button(text("Import Settings"))
+                .on_press(Message::ImportSettings)
+                .style(iced::theme::Button::Primary)
+        } else {
+            button(text("Import Settings"))
+        };
+
+        let summary: Element<Message> = match &self.report {
+            Some(report) => {
+                let mut lines = column![text(format!(
+                    "Copied {} files, backed up {}, kept {} existing",
+                    report.copied.len(),
+                    report.backed_up.len(),
+                    report.skipped.len(),
+                ))]
+                .spacing(2);
+                for (file, error) in &report.failed {
+                    lines = lines.push(text(format!("✗ {}: {}", file.display(), error)).size(12));
+                }
+                lines.into()
+            }
+            None => column![].into(),
+        };
+
+        column![items, policy, import_btn, summary].spacing(10).into()
+    }
+
+    pub fn view(&self) -> Element<'_, Message> {
+        let found = match &self.sources {
+            None => column![text("Looking for previous systems...").size(12)],
+            Some(sources) if sources.is_empty() => column![],
+            Some(sources) => sources.iter().fold(
+                column![text("Previous systems on this computer:")].spacing(5),
+                |col, source| {
+                    col.push(
+                        button(text(source.to_string()))
+                            .on_press(Message::MigrationSourcePicked(source.clone()))
+                            .style(iced::theme::Button::Secondary)
+                    )
+                }
+            ),
+        };
+
+        let path_row = row![
+            text_input("Old home directory, system root or backup", &self.path)
+                .on_input(Message::MigrationPathChanged)
+                .on_submit(Message::ScanMigration)
+                .width(Length::Fill),
+            button(text("Scan")).on_press(Message::ScanMigration),
+        ]
+        .spacing(10);
+
+        scrollable(
+            column![
+                text("Import Settings").size(24),
+                vertical_space().height(10),
+                text("Bring Blender, Krita, GIMP, Darktable and Ardour settings over from another distro, Windows, macOS or a backup."),
+                text("This step is optional: press Next to start fresh.").size(12),
+                vertical_space().height(20),
+                found,
+                path_row,
+                vertical_space().height(20),
+                self.plan_view(),
+            ]
+            .spacing(10)
+            .padding(10)
+        )
+        .into()
+    }
+}
@@ -2,5 +2,6 @@ pub mod welcome;
 pub mod hardware;
 pub mod workflow;
 pub mod apps;
+pub mod migrate;
 pub mod settings;
 pub mod finish;
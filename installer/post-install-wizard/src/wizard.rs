@@ -8,6 +8,7 @@ use crate::pages::{
     hardware::HardwarePage,
     workflow::WorkflowPage,
     apps::AppsPage,
+    migrate::MigratePage,
     settings::SettingsPage,
     finish::FinishPage,
 };
@@ -24,6 +25,8 @@ pub enum Message {
     // Hardware
     HardwareDetected(Box<rururu_hardware_detect::HardwareInfo>),
     ApplyRecommendation(usize),
+    ApplyInstallPlan,
+    InstallPlanApplied(Vec<rururu_hardware_detect::StepResult>),
     
     // Workflow
     WorkflowSelected(rururu_workflows::WorkflowType),
@@ -33,6 +36,17 @@ pub enum Message {
     InstallApps,
     AppInstalled(String, bool),
     
+    // Migrate
+    MigrationSourcesFound(Vec<rururu_workflows::MigrationSource>),
+    MigrationSourcePicked(rururu_workflows::MigrationSource),
+    MigrationPathChanged(String),
+    ScanMigration,
+    MigrationPlanned(Result<rururu_workflows::MigrationPlan, String>),
+    ToggleMigrationItem(usize),
+    MigrationPolicySelected(rururu_workflows::migrate::ConflictPolicy),
+    ImportSettings,
+    SettingsImported(rururu_workflows::migrate::MigrationReport),
+    
     // Settings
     ToggleDarkMode(bool),
     ToggleAutoUpdates(bool),
@@ -48,6 +62,7 @@ pub enum Page {
     Hardware,
     Workflow,
     Apps,
+    Migrate,
     Settings,
     Finish,
 }
@@ -59,8 +74,9 @@ impl Page {
             Page::Hardware => 1,
             Page::Workflow => 2,
             Page::Apps => 3,
-            Page::Settings => 4,
-            Page::Finish => 5,
+            Page::Migrate => 4,
+            Page::Settings => 5,
+            Page::Finish => 6,
         }
     }
     
@@ -70,7 +86,8 @@ impl Page {
             1 => Page::Hardware,
             2 => Page::Workflow,
             3 => Page::Apps,
-            4 => Page::Settings,
+            4 => Page::Migrate,
+            5 => Page::Settings,
             _ => Page::Finish,
         }
     }
@@ -81,6 +98,7 @@ impl Page {
             Page::Hardware => "Hardware",
             Page::Workflow => "Workflow",
             Page::Apps => "Applications",
+            Page::Migrate => "Import",
             Page::Settings => "Settings",
             Page::Finish => "Complete",
         }
@@ -95,6 +113,7 @@ pub struct SetupWizard {
     hardware: HardwarePage,
     workflow: WorkflowPage,
     apps: AppsPage,
+    migrate: MigratePage,
     settings: SettingsPage,
     finish: FinishPage,
 }
@@ -113,6 +132,7 @@ impl Application for SetupWizard {
                 hardware: HardwarePage::new(),
                 workflow: WorkflowPage::new(),
                 apps: AppsPage::new(),
+                migrate: MigratePage::new(),
                 settings: SettingsPage::new(),
                 finish: FinishPage::new(),
             },
@@ -127,7 +147,7 @@ impl Application for SetupWizard {
     fn update(&mut self, message: Message) -> Command<Message> {
         match message {
             Message::NextPage => {
-                let next = (self.current_page.index() + 1).min(5);
+                let next = (self.current_page.index() + 1).min(6);
                 self.current_page = Page::from_index(next);
                 
                 // Trigger hardware detection when entering hardware page
@@ -137,6 +157,9 @@ impl Application for SetupWizard {
                         |info| Message::HardwareDetected(Box::new(info)),
                     );
                 }
+                if self.current_page == Page::Migrate {
+                    return self.migrate.find_sources();
+                }
             }
             Message::PrevPage => {
                 let prev = self.current_page.index().saturating_sub(1);
@@ -144,6 +167,9 @@ impl Application for SetupWizard {
             }
             Message::GoToPage(index) => {
                 self.current_page = Page::from_index(index);
+                if self.current_page == Page::Migrate {
+                    return self.migrate.find_sources();
+                }
             }
             
             Message::LanguageSelected(lang) => {
@@ -151,11 +177,17 @@ impl Application for SetupWizard {
             }
             
             Message::HardwareDetected(info) => {
-                self.hardware.info = Some(*info);
+                self.hardware.set_info(*info);
             }
             Message::ApplyRecommendation(index) => {
                 self.hardware.apply_recommendation(index);
             }
+            Message::ApplyInstallPlan => {
+                return self.hardware.apply_plan();
+            }
+            Message::InstallPlanApplied(results) => {
+                self.hardware.plan_applied(results);
+            }
             
             Message::WorkflowSelected(workflow) => {
                 self.workflow.selected = Some(workflow);
@@ -172,6 +204,34 @@ impl Application for SetupWizard {
                 self.apps.mark_installed(&app, success);
             }
             
+            Message::MigrationSourcesFound(sources) => {
+                self.migrate.sources = Some(sources);
+            }
+            Message::MigrationSourcePicked(source) => {
+                return self.migrate.scan(source);
+            }
+            Message::MigrationPathChanged(path) => {
+                self.migrate.path = path;
+            }
+            Message::ScanMigration => {
+                return self.migrate.scan_path();
+            }
+            Message::MigrationPlanned(plan) => {
+                self.migrate.planned(plan);
+            }
+            Message::ToggleMigrationItem(index) => {
+                self.migrate.toggle_item(index);
+            }
+            Message::MigrationPolicySelected(policy) => {
+                self.migrate.policy = policy;
+            }
+            Message::ImportSettings => {
+                return self.migrate.import();
+            }
+            Message::SettingsImported(report) => {
+                self.migrate.imported(report);
+            }
+            
             Message::ToggleDarkMode(enabled) => {
                 self.settings.dark_mode = enabled;
             }
@@ -196,13 +256,14 @@ impl Application for SetupWizard {
     }
 
     fn view(&self) -> Element<Message> {
-        let progress = self.current_page.index() as f32 / 5.0;
+        let progress = self.current_page.index() as f32 / 6.0;
         
         let content: Element<Message> = match self.current_page {
             Page::Welcome => self.welcome.view(),
             Page::Hardware => self.hardware.view(),
             Page::Workflow => self.workflow.view(),
             Page::Apps => self.apps.view(),
+            Page::Migrate => self.migrate.view(),
             Page::Settings => self.settings.view(),
             Page::Finish => self.finish.view(),
         };
@@ -233,7 +294,7 @@ impl Application for SetupWizard {
         };
         
         let page_indicators = row(
-            (0..6).map(|i| {
+            (0..7).map(|i| {
                 let is_current = i == self.current_page.index();
                 let style = if is_current {
                     iced::theme::Button::Primary
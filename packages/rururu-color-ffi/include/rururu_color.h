@@ -0,0 +1,68 @@
+/*
+ * C interface to the RururuOS color engine (librururu_color_ffi.so).
+ *
+ * Functions return RURURU_COLOR_OK or an error code, and reject null or
+ * malformed arguments with RURURU_COLOR_INVALID_ARGUMENT. Objects the
+ * library allocates are released with the matching _free function, which
+ * accepts NULL.
+ *
+ * Pixels are interleaved float RGB (channels = 3) or RGBA (channels = 4),
+ * nominally 0-1, converted in place; alpha is left untouched. `len` counts
+ * floats, not pixels. Color spaces are "srgb", "linear-srgb", "rec709",
+ * "display-p3", "adobe-rgb", "rec2020", "acescg", "aces2065-1" or the path
+ * of an ICC profile.
+ *
+ * From Python, the declarations below can be passed to cffi's ffi.cdef().
+ */
+
+#ifndef RURURU_COLOR_H
+#define RURURU_COLOR_H
+
+#include <stddef.h>
+#include <stdint.h>
+
+#ifdef __cplusplus
+extern "C" {
+#endif
+
+#define RURURU_COLOR_ABI_VERSION 1
+
+#define RURURU_COLOR_OK 0
+#define RURURU_COLOR_FAILED 1
+#define RURURU_COLOR_INVALID_ARGUMENT 2
+#define RURURU_COLOR_PANICKED 3
+
+#define RURURU_COLOR_INTENT_PERCEPTUAL 0
+#define RURURU_COLOR_INTENT_RELATIVE_COLORIMETRIC 1
+#define RURURU_COLOR_INTENT_SATURATION 2
+#define RURURU_COLOR_INTENT_ABSOLUTE_COLORIMETRIC 3
+
+typedef struct RururuColorTransform RururuColorTransform;
+typedef struct RururuColorLut RururuColorLut;
+
+/* RURURU_COLOR_ABI_VERSION the library was built with */
+uint32_t rururu_color_abi_version(void);
+
+/* Convert pixels from one space to another, building the transform each call */
+int32_t rururu_color_transform_buffer(float *pixels, size_t len, uint32_t channels,
+                                      const char *from, const char *to, int32_t intent);
+
+/* A reusable transform, or NULL when either space can't be loaded. Use it
+ * from one thread at a time. */
+RururuColorTransform *rururu_color_transform_new(const char *from, const char *to,
+                                                 int32_t intent);
+int32_t rururu_color_transform_apply(const RururuColorTransform *transform, float *pixels,
+                                     size_t len, uint32_t channels);
+void rururu_color_transform_free(RururuColorTransform *transform);
+
+/* A .cube 3D LUT with the default 0-1 domain, or NULL when it can't be read */
+RururuColorLut *rururu_color_lut_load(const char *path);
+int32_t rururu_color_lut_apply(const RururuColorLut *lut, float *pixels, size_t len,
+                               uint32_t channels);
+void rururu_color_lut_free(RururuColorLut *lut);
+
+#ifdef __cplusplus
+}
+#endif
+
+#endif /* RURURU_COLOR_H */
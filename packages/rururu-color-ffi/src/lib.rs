@@ -0,0 +1,397 @@
+//! C ABI for the color engine, so creative tools in C, C++ or Python
+//! (through cffi) can convert pixels with it. `include/rururu_color.h`
+//! declares everything here.
+//!
+//! The conventions are the plugin ABI's (`rururu_plugin_sdk::ffi`):
+//! functions return [`OK`] or an error code, null or malformed arguments
+//! give [`INVALID_ARGUMENT`], panics are caught before they can unwind into
+//! the caller, and whatever the library allocates is released by the
+//! matching `_free` function, which accepts null.
+//!
+//! Pixels are interleaved `float` RGB or RGBA, converted in place; `len`
+//! counts floats, not pixels. Color spaces are named by one of
+//! [`BUILTIN_SPACES`] or the path of an ICC profile.
+
+use rururu_color::config::RenderingIntent;
+use rururu_color::transform::{self, ColorTransform};
+use rururu_color::{ColorError, Lut3d};
+use std::ffi::{c_char, CStr};
+use std::panic::{self, AssertUnwindSafe};
+use std::path::Path;
+use std::ptr;
+
+pub use rururu_color::transform::BUILTIN_SPACES;
+
+/// Bumped whenever a signature in the header changes
+pub const ABI_VERSION: u32 = 1;
+
+pub const OK: i32 = 0;
+/// The profile, LUT or transform couldn't be loaded or built
+pub const FAILED: i32 = 1;
+pub const INVALID_ARGUMENT: i32 = 2;
+pub const PANICKED: i32 = 3;
+
+/// Rendering intents, numbered as in ICC
+pub const INTENT_PERCEPTUAL: i32 = 0;
+pub const INTENT_RELATIVE_COLORIMETRIC: i32 = 1;
+pub const INTENT_SATURATION: i32 = 2;
+pub const INTENT_ABSOLUTE_COLORIMETRIC: i32 = 3;
+
+/// Opaque to C; from `rururu_color_transform_new`
+pub struct RururuColorTransform(ColorTransform);
+
+/// Opaque to C; from `rururu_color_lut_load`
+pub struct RururuColorLut(Lut3d);
+
+#[no_mangle]
+pub extern "C" fn rururu_color_abi_version() -> u32 {
+    ABI_VERSION
+}
+
+/// Convert `pixels` from `from` to `to` in one call. Builds the transform
+/// every time; use `rururu_color_transform_new` for repeated buffers.
+///
+/// # Safety
+/// `pixels` must be null or point to `len` writable floats, and `from` and
+/// `to` must be null or NUL-terminated strings.
+#[no_mangle]
+pub unsafe extern "C" fn rururu_color_transform_buffer(
+    pixels: *mut f32,
+    len: usize,
+    channels: u32,
+    from: *const c_char,
+    to: *const c_char,
+    intent: i32,
+) -> i32 {
+    let (Some(pixels), Some(from), Some(to), Some(intent)) = (
+        unsafe { pixels_arg(pixels, len) },
+        unsafe { str_arg(from) },
+        unsafe { str_arg(to) },
+        intent_arg(intent),
+    ) else {
+        return INVALID_ARGUMENT;
+    };
+    guard(PANICKED, || {
+        result_code(transform::transform_buffer(
+            pixels,
+            channels as usize,
+            from,
+            to,
+            intent,
+        ))
+    })
+}
+
+/// A reusable transform from `from` to `to`, or null when either space
+/// can't be loaded. Free it with `rururu_color_transform_free`.
+///
+/// # Safety
+/// `from` and `to` must be null or NUL-terminated strings.
+#[no_mangle]
+pub unsafe extern "C" fn rururu_color_transform_new(
+    from: *const c_char,
+    to: *const c_char,
+    intent: i32,
+) -> *mut RururuColorTransform {
+    let (Some(from), Some(to), Some(intent)) = (
+        unsafe { str_arg(from) },
+        unsafe { str_arg(to) },
+        intent_arg(intent),
+    ) else {
+        return ptr::null_mut();
+    };
+    guard(ptr::null_mut(), || {
+        match ColorTransform::new(from, to, intent) {
+            Ok(transform) => Box::into_raw(Box::new(RururuColorTransform(transform))),
+            Err(_) => ptr::null_mut(),
+        }
+    })
+}
+
+/// # Safety
+/// `transform` must be null or from `rururu_color_transform_new` and not
+/// yet freed, and `pixels` null or `len` writable floats. A transform may
+/// be used from one thread at a time.
+#[no_mangle]
+pub unsafe extern "C" fn rururu_color_transform_apply(
+    transform: *const RururuColorTransform,
+    pixels: *mut f32,
+    len: usize,
+    channels: u32,
+) -> i32 {
+    let (Some(transform), Some(pixels)) = (unsafe { transform.as_ref() }, unsafe {
+        pixels_arg(pixels, len)
+    }) else {
+        return INVALID_ARGUMENT;
+    };
+    guard(PANICKED, || {
+        result_code(transform.0.apply(pixels, channels as usize))
+    })
+}
+
+/// # Safety
+/// `transform` must be null or from `rururu_color_transform_new`, and is
+/// only freed once.
+#[no_mangle]
+pub unsafe extern "C" fn rururu_color_transform_free(transform: *mut RururuColorTransform) {
+    if !transform.is_null() {
+        drop(unsafe { Box::from_raw(transform) });
+    }
+}
+
+/// Read a `.cube` 3D LUT, or null when it can't be read or parsed. Free it
+/// with `rururu_color_lut_free`.
+///
+/// # Safety
+/// `path` must be null or a NUL-terminated string.
+#[no_mangle]
+pub unsafe extern "C" fn rururu_color_lut_load(path: *const c_char) -> *mut RururuColorLut {
+    let Some(path) = (unsafe { str_arg(path) }) else {
+        return ptr::null_mut();
+    };
+    guard(ptr::null_mut(), || match Lut3d::load(Path::new(path)) {
+        Ok(lut) => Box::into_raw(Box::new(RururuColorLut(lut))),
+        Err(_) => ptr::null_mut(),
+    })
+}
+
+/// Pass `pixels` through the LUT, with trilinear interpolation
+///
+/// # Safety
+/// `lut` must be null or from `rururu_color_lut_load` and not yet freed,
+/// and `pixels` null or `len` writable floats.
+#[no_mangle]
+pub unsafe extern "C" fn rururu_color_lut_apply(
+    lut: *const RururuColorLut,
+    pixels: *mut f32,
+    len: usize,
+    channels: u32,
+) -> i32 {
+    let (Some(lut), Some(pixels)) = (unsafe { lut.as_ref() }, unsafe { pixels_arg(pixels, len) })
+    else {
+        return INVALID_ARGUMENT;
+    };
+    guard(PANICKED, || {
+        result_code(lut.0.apply_buffer(pixels, channels as usize))
+    })
+}
+
+/// # Safety
+/// `lut` must be null or from `rururu_color_lut_load`, and is only freed
+/// once.
+#[no_mangle]
+pub unsafe extern "C" fn rururu_color_lut_free(lut: *mut RururuColorLut) {
+    if !lut.is_null() {
+        drop(unsafe { Box::from_raw(lut) });
+    }
+}
+
+unsafe fn pixels_arg<'a>(pixels: *mut f32, len: usize) -> Option<&'a mut [f32]> {
+    if pixels.is_null() {
+        return None;
+    }
+    Some(unsafe { std::slice::from_raw_parts_mut(pixels, len) })
+}
+
+/// Space names and paths have to be UTF-8 to reach the engine
+unsafe fn str_arg<'a>(ptr: *const c_char) -> Option<&'a str> {
+    if ptr.is_null() {
+        return None;
+    }
+    unsafe { CStr::from_ptr(ptr) }.to_str().ok()
+}
+
+fn intent_arg(intent: i32) -> Option<RenderingIntent> {
+    match intent {
+        INTENT_PERCEPTUAL => Some(RenderingIntent::Perceptual),
+        INTENT_RELATIVE_COLORIMETRIC => Some(RenderingIntent::RelativeColorimetric),
+        INTENT_SATURATION => Some(RenderingIntent::Saturation),
+        INTENT_ABSOLUTE_COLORIMETRIC => Some(RenderingIntent::AbsoluteColorimetric),
+        _ => None,
+    }
+}
+
+fn result_code(result: rururu_color::Result<()>) -> i32 {
+    match result {
+        Ok(()) => OK,
+        Err(ColorError::Buffer(_)) => INVALID_ARGUMENT,
+        Err(_) => FAILED,
+    }
+}
+
+/// Unwinding out of an `extern "C"` function aborts the caller
+fn guard<T>(fallback: T, f: impl FnOnce() -> T) -> T {
+    panic::catch_unwind(AssertUnwindSafe(f)).unwrap_or(fallback)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use rururu_color::lut;
+    use std::ffi::CString;
+
+    fn close(a: &[f32], b: &[f32], tolerance: f32) -> bool {
+        a.len() == b.len() && a.iter().zip(b).all(|(x, y)| (x - y).abs() <= tolerance)
+    }
+
+    #[test]
+    fn test_transform_round_trip() {
+        assert_eq!(rururu_color_abi_version(), ABI_VERSION);
+
+        let original = [0.8, 0.4, 0.1, 1.0, 0.2, 0.6, 0.9, 0.5];
+        let mut pixels = original;
+        let srgb = CString::new("srgb").unwrap();
+        let p3 = CString::new("display-p3").unwrap();
+
+        unsafe {
+            let result = rururu_color_transform_buffer(
+                pixels.as_mut_ptr(),
+                pixels.len(),
+                4,
+                srgb.as_ptr(),
+                p3.as_ptr(),
+                INTENT_RELATIVE_COLORIMETRIC,
+            );
+            assert_eq!(result, OK);
+            // P3 is wider, so a saturated sRGB color has smaller values
+            assert!(pixels[0] < original[0]);
+            // Alpha is untouched
+            assert_eq!(pixels[3], 1.0);
+            assert_eq!(pixels[7], 0.5);
+
+            let back = rururu_color_transform_new(
+                p3.as_ptr(),
+                srgb.as_ptr(),
+                INTENT_RELATIVE_COLORIMETRIC,
+            );
+            assert!(!back.is_null());
+            assert_eq!(
+                rururu_color_transform_apply(back, pixels.as_mut_ptr(), pixels.len(), 4),
+                OK
+            );
+            rururu_color_transform_free(back);
+            rururu_color_transform_free(ptr::null_mut());
+        }
+        assert!(close(&pixels, &original, 1e-3), "{:?}", pixels);
+    }
+
+    #[test]
+    fn test_intents_map_out_of_gamut_colors() {
+        let rec2020 = CString::new("rec2020").unwrap();
+        let srgb = CString::new("srgb").unwrap();
+        // Rec.2020 green is far outside sRGB
+        let convert = |intent| {
+            let mut pixels = [0.1, 0.9, 0.1];
+            let result = unsafe {
+                rururu_color_transform_buffer(
+                    pixels.as_mut_ptr(),
+                    pixels.len(),
+                    3,
+                    rec2020.as_ptr(),
+                    srgb.as_ptr(),
+                    intent,
+                )
+            };
+            assert_eq!(result, OK);
+            pixels
+        };
+
+        let relative = convert(INTENT_RELATIVE_COLORIMETRIC);
+        assert!(relative[0] < 0.0, "{:?}", relative);
+
+        let perceptual = convert(INTENT_PERCEPTUAL);
+        let saturation = convert(INTENT_SATURATION);
+        for pixels in [perceptual, saturation] {
+            assert!(
+                pixels.iter().all(|c| (0.0..=1.0).contains(c)),
+                "{:?}",
+                pixels
+            );
+        }
+        // Perceptual keeps some red to hold the luminance; saturation clips
+        // it for the most vivid green sRGB has
+        assert!(perceptual[0] > 0.0 && saturation[0] == 0.0);
+        assert!(saturation[1] > perceptual[1]);
+    }
+
+    #[test]
+    fn test_lut_apply() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("invert.cube");
+        let invert = Lut3d::sample(lut::DEFAULT_3D_SIZE, |[r, g, b]| {
+            [1.0 - r, 1.0 - g, 1.0 - b]
+        })
+        .unwrap();
+        invert.save(&path, "Invert").unwrap();
+        let c_path = CString::new(path.to_str().unwrap()).unwrap();
+
+        let mut pixels = [0.25, 0.5, 0.8];
+        unsafe {
+            let lut = rururu_color_lut_load(c_path.as_ptr());
+            assert!(!lut.is_null());
+            assert_eq!(rururu_color_lut_apply(lut, pixels.as_mut_ptr(), 3, 3), OK);
+            rururu_color_lut_free(lut);
+        }
+        assert!(close(&pixels, &[0.75, 0.5, 0.2], 1e-4), "{:?}", pixels);
+    }
+
+    #[test]
+    fn test_invalid_arguments() {
+        let srgb = CString::new("srgb").unwrap();
+        let missing = CString::new("/nonexistent/profile.icc").unwrap();
+        let mut pixels = [0.5; 6];
+
+        unsafe {
+            assert_eq!(
+                rururu_color_transform_buffer(
+                    ptr::null_mut(),
+                    6,
+                    3,
+                    srgb.as_ptr(),
+                    srgb.as_ptr(),
+                    INTENT_PERCEPTUAL
+                ),
+                INVALID_ARGUMENT
+            );
+            // Five floats aren't a whole number of RGB pixels
+            assert_eq!(
+                rururu_color_transform_buffer(
+                    pixels.as_mut_ptr(),
+                    5,
+                    3,
+                    srgb.as_ptr(),
+                    srgb.as_ptr(),
+                    INTENT_PERCEPTUAL
+                ),
+                INVALID_ARGUMENT
+            );
+            assert_eq!(
+                rururu_color_transform_buffer(
+                    pixels.as_mut_ptr(),
+                    6,
+                    3,
+                    srgb.as_ptr(),
+                    srgb.as_ptr(),
+                    42
+                ),
+                INVALID_ARGUMENT
+            );
+            assert_eq!(
+                rururu_color_transform_buffer(
+                    pixels.as_mut_ptr(),
+                    6,
+                    3,
+                    srgb.as_ptr(),
+                    missing.as_ptr(),
+                    INTENT_PERCEPTUAL
+                ),
+                FAILED
+            );
+            assert!(rururu_color_lut_load(missing.as_ptr()).is_null());
+            assert_eq!(
+                rururu_color_lut_apply(ptr::null(), pixels.as_mut_ptr(), 6, 3),
+                INVALID_ARGUMENT
+            );
+        }
+    }
+}
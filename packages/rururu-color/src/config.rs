@@ -1,10 +1,15 @@
+use crate::transform::TransformOptions;
 use serde::{Deserialize, Serialize};
 use std::collections::HashMap;
-use std::path::PathBuf;
+use std::path::{Path, PathBuf};
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct ColorConfig {
     pub version: u32,
+    /// Key in `workflows` of the workflow `rururu-workflow` activated last;
+    /// conversions that don't name a workflow follow it
+    #[serde(default)]
+    pub active_workflow: Option<String>,
     pub global: GlobalColorSettings,
     pub monitors: HashMap<String, MonitorColorConfig>,
     pub ocio: Option<OcioConfig>,
@@ -30,6 +35,48 @@ pub enum RenderingIntent {
     AbsoluteColorimetric,
 }
 
+impl RenderingIntent {
+    pub fn name(&self) -> &'static str {
+        match self {
+            RenderingIntent::Perceptual => "Perceptual",
+            RenderingIntent::RelativeColorimetric => "RelativeColorimetric",
+            RenderingIntent::Saturation => "Saturation",
+            RenderingIntent::AbsoluteColorimetric => "AbsoluteColorimetric",
+        }
+    }
+}
+
+impl std::fmt::Display for RenderingIntent {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.write_str(self.name())
+    }
+}
+
+/// Accepts the variant names workflow profiles use, e.g.
+/// `RelativeColorimetric`, as well as `relative-colorimetric` and the
+/// short `relative`, in any case
+impl std::str::FromStr for RenderingIntent {
+    type Err = crate::ColorError;
+
+    fn from_str(s: &str) -> crate::Result<Self> {
+        let normalized: String = s
+            .chars()
+            .filter(|c| !matches!(c, '-' | '_' | ' '))
+            .collect::<String>()
+            .to_ascii_lowercase();
+        match normalized.as_str() {
+            "perceptual" => Ok(RenderingIntent::Perceptual),
+            "relativecolorimetric" | "relative" => Ok(RenderingIntent::RelativeColorimetric),
+            "saturation" => Ok(RenderingIntent::Saturation),
+            "absolutecolorimetric" | "absolute" => Ok(RenderingIntent::AbsoluteColorimetric),
+            _ => Err(crate::ColorError::Config(format!(
+                "Unknown rendering intent {:?}",
+                s
+            ))),
+        }
+    }
+}
+
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct MonitorColorConfig {
     pub edid_name: String,
@@ -65,6 +112,7 @@ impl Default for ColorConfig {
     fn default() -> Self {
         Self {
             version: 1,
+            active_workflow: None,
             global: GlobalColorSettings {
                 enabled: true,
                 default_profile: "sRGB".to_string(),
@@ -167,6 +215,26 @@ impl ColorConfig {
         Ok(())
     }
 
+    /// Options for conversions in `workflow`, or in the active workflow when
+    /// `None`: its default intent, else the global one, with the global
+    /// black point compensation setting
+    pub fn transform_options(&self, workflow: Option<&str>) -> TransformOptions {
+        let intent = workflow
+            .or(self.active_workflow.as_deref())
+            .and_then(|name| self.workflows.get(name))
+            .map_or(self.global.rendering_intent, |w| w.default_intent);
+        TransformOptions {
+            intent,
+            black_point_compensation: self.global.black_point_compensation,
+        }
+    }
+
+    /// Output profile the active workflow proofs on, if it names one
+    pub fn soft_proof_profile(&self) -> Option<&Path> {
+        let name = self.active_workflow.as_deref()?;
+        self.workflows.get(name)?.soft_proof_profile.as_deref()
+    }
+
     fn config_path() -> PathBuf {
         dirs::config_dir()
             .unwrap_or_else(|| PathBuf::from("."))
@@ -174,3 +242,88 @@ impl ColorConfig {
             .join("color.toml")
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_rendering_intent_from_str() {
+        for (name, intent) in [
+            (
+                "RelativeColorimetric",
+                RenderingIntent::RelativeColorimetric,
+            ),
+            (
+                "relative-colorimetric",
+                RenderingIntent::RelativeColorimetric,
+            ),
+            ("relative", RenderingIntent::RelativeColorimetric),
+            ("Perceptual", RenderingIntent::Perceptual),
+            ("SATURATION", RenderingIntent::Saturation),
+            (
+                "absolute_colorimetric",
+                RenderingIntent::AbsoluteColorimetric,
+            ),
+        ] {
+            assert_eq!(name.parse::<RenderingIntent>().unwrap(), intent, "{}", name);
+        }
+        // Every name written out parses back
+        for intent in [
+            RenderingIntent::Perceptual,
+            RenderingIntent::RelativeColorimetric,
+            RenderingIntent::Saturation,
+            RenderingIntent::AbsoluteColorimetric,
+        ] {
+            assert_eq!(intent.name().parse::<RenderingIntent>().unwrap(), intent);
+        }
+
+        assert!(matches!(
+            "colorimetric".parse::<RenderingIntent>(),
+            Err(crate::ColorError::Config(_))
+        ));
+        assert!("".parse::<RenderingIntent>().is_err());
+    }
+
+    #[test]
+    fn test_transform_options_follow_the_active_workflow() {
+        let mut config = ColorConfig::default();
+        assert_eq!(
+            config.transform_options(None).intent,
+            RenderingIntent::Perceptual
+        );
+        assert_eq!(
+            config.transform_options(Some("print")).intent,
+            RenderingIntent::RelativeColorimetric
+        );
+
+        config.active_workflow = Some("print".to_string());
+        assert_eq!(
+            config.transform_options(None).intent,
+            RenderingIntent::RelativeColorimetric
+        );
+        // A workflow named explicitly wins, an unknown one means the global
+        assert_eq!(
+            config.transform_options(Some("web")).intent,
+            RenderingIntent::Perceptual
+        );
+        assert_eq!(
+            config.transform_options(Some("missing")).intent,
+            RenderingIntent::Perceptual
+        );
+    }
+
+    #[test]
+    fn test_soft_proof_profile_of_the_active_workflow() {
+        let mut config = ColorConfig::default();
+        assert_eq!(config.soft_proof_profile(), None);
+
+        config.active_workflow = Some("print".to_string());
+        assert_eq!(
+            config.soft_proof_profile(),
+            Some(Path::new("/usr/share/color/icc/Fogra39.icc"))
+        );
+        config.active_workflow = Some("web".to_string());
+        assert_eq!(config.soft_proof_profile(), None);
+    }
+}
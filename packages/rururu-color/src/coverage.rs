@@ -0,0 +1,271 @@
+//! How much of a standard gamut a monitor reproduces.
+//!
+//! Area coverage is the share of the reference triangle in the CIE 1931 xy
+//! plane that the monitor's triangle overlaps, the figure monitor spec
+//! sheets quote. Volume coverage also counts how bright the colors get: the
+//! reference gamut, encoded with a 2.2 gamma, is sampled between the
+//! monitor's black (as black point compensation would) and its standard's
+//! white level, and each color checked against what the monitor can produce
+//! up to its peak luminance. References use a D65 white, so DCI-P3 is the
+//! Display P3 variant.
+
+use crate::icc::{apply, invert, IccProfileInfo, Matrix3, ProfileClass};
+use crate::monitor::{triangle_area, ColorGamut, MonitorProfile};
+use crate::profile_match;
+
+const D65: (f64, f64) = (0.3127, 0.3290);
+
+/// Samples per channel of the reference RGB cube for volume coverage
+const VOLUME_STEPS: usize = 17;
+
+/// Slack for rounding when checking a sample is inside the monitor's gamut
+const VOLUME_TOLERANCE: f64 = 1e-3;
+
+impl ColorGamut {
+    /// White luminance in cd/m² the standard is specified at
+    pub fn reference_luminance(self) -> Option<f64> {
+        match self {
+            ColorGamut::Srgb => Some(80.0),
+            ColorGamut::AdobeRgb => Some(160.0),
+            ColorGamut::DciP3 => Some(48.0),
+            ColorGamut::Bt2020 => Some(100.0),
+            ColorGamut::Unknown => None,
+        }
+    }
+}
+
+impl MonitorProfile {
+    /// The monitor's primaries: measured ones from its assigned display
+    /// profile, or the EDID's when the profile is a standard color space or
+    /// there is none
+    pub fn primaries(&self) -> Option<[(f64, f64); 3]> {
+        self.measured().and_then(|info| info.primaries).or_else(|| {
+            self.edid
+                .chromaticity
+                .as_ref()
+                .map(|chromaticity| chromaticity.primaries())
+        })
+    }
+
+    /// The monitor's native white, from the same source as [`primaries`]
+    ///
+    /// [`primaries`]: MonitorProfile::primaries
+    pub fn white(&self) -> Option<(f64, f64)> {
+        match self.measured() {
+            Some(info) => info.white_point,
+            None => self.edid.chromaticity.as_ref().map(|c| c.white),
+        }
+    }
+
+    /// Percentage of `reference`'s xy area the monitor covers, 0 when either
+    /// gamut's primaries are unknown
+    pub fn gamut_coverage(&self, reference: ColorGamut) -> f32 {
+        let (Some(monitor), Some(reference)) = (self.primaries(), reference.primaries()) else {
+            return 0.0;
+        };
+        let area = triangle_area(&reference);
+        if area <= 0.0 {
+            return 0.0;
+        }
+        (intersection_area(&monitor, &reference) / area * 100.0) as f32
+    }
+
+    /// Percentage of `reference`'s colors, light and dark, the monitor can
+    /// show; `None` without its primaries or luminance range
+    pub fn gamut_volume_coverage(&self, reference: ColorGamut) -> Option<f32> {
+        let capabilities = &self.capabilities;
+        let peak = capabilities.max_luminance? as f64;
+        let black = capabilities.min_luminance.unwrap_or(0.0) as f64;
+        if peak <= black {
+            return None;
+        }
+
+        let reference_white = reference.reference_luminance()?;
+        if reference_white <= black {
+            return Some(0.0);
+        }
+        let to_monitor = invert(rgb_to_xyz(&self.primaries()?, self.white().unwrap_or(D65))?)?;
+        let from_reference = rgb_to_xyz(&reference.primaries()?, D65)?;
+
+        let levels: Vec<f64> = (0..VOLUME_STEPS)
+            .map(|i| (i as f64 / (VOLUME_STEPS - 1) as f64).powf(2.2))
+            .collect();
+        let mut covered = 0;
+        for &r in &levels {
+            for &g in &levels {
+                for &b in &levels {
+                    // Absolute luminance, then the monitor's drive above black
+                    let rgb = [r, g, b].map(|c| black + c * (reference_white - black));
+                    let xyz = apply(&from_reference, rgb);
+                    let drive = apply(&to_monitor, xyz).map(|v| (v - black) / (peak - black));
+                    if drive
+                        .iter()
+                        .all(|v| (-VOLUME_TOLERANCE..=1.0 + VOLUME_TOLERANCE).contains(v))
+                    {
+                        covered += 1;
+                    }
+                }
+            }
+        }
+
+        Some((covered as f64 / VOLUME_STEPS.pow(3) as f64 * 100.0) as f32)
+    }
+
+    /// The assigned profile when it was measured on a display rather than
+    /// being a standard space
+    fn measured(&self) -> Option<IccProfileInfo> {
+        let info = IccProfileInfo::from_file(self.icc_profile.as_ref()?).ok()?;
+        let measured = info.profile_class == ProfileClass::Display
+            && info
+                .primaries
+                .is_some_and(|primaries| !profile_match::is_standard_space(&primaries));
+        measured.then_some(info)
+    }
+}
+
+/// Positive for counter-clockwise points
+fn signed_area(polygon: &[(f64, f64)]) -> f64 {
+    let n = polygon.len();
+    (0..n)
+        .map(|i| {
+            let (a, b) = (polygon[i], polygon[(i + 1) % n]);
+            a.0 * b.1 - b.0 * a.1
+        })
+        .sum::<f64>()
+        / 2.0
+}
+
+/// Area of the overlap of two triangles, by clipping `subject` to each edge
+/// of `clip` in turn (Sutherland–Hodgman; both are convex)
+fn intersection_area(subject: &[(f64, f64); 3], clip: &[(f64, f64); 3]) -> f64 {
+    let mut clip = clip.to_vec();
+    if signed_area(&clip) < 0.0 {
+        clip.reverse();
+    }
+
+    let mut polygon = subject.to_vec();
+    for i in 0..clip.len() {
+        let (a, b) = (clip[i], clip[(i + 1) % clip.len()]);
+        // Left of a→b is inside a counter-clockwise polygon
+        let side = |p: (f64, f64)| (b.0 - a.0) * (p.1 - a.1) - (b.1 - a.1) * (p.0 - a.0);
+
+        let input = std::mem::take(&mut polygon);
+        for j in 0..input.len() {
+            let (p, q) = (input[j], input[(j + 1) % input.len()]);
+            let (sp, sq) = (side(p), side(q));
+            if sp >= 0.0 {
+                polygon.push(p);
+            }
+            if (sp >= 0.0) != (sq >= 0.0) {
+                let t = sp / (sp - sq);
+                polygon.push((p.0 + (q.0 - p.0) * t, p.1 + (q.1 - p.1) * t));
+            }
+        }
+        if polygon.is_empty() {
+            return 0.0;
+        }
+    }
+
+    signed_area(&polygon).abs()
+}
+
+/// Linear RGB to XYZ with white at Y = 1
+fn rgb_to_xyz(primaries: &[(f64, f64); 3], white: (f64, f64)) -> Option<Matrix3> {
+    let xyz = |(x, y): (f64, f64)| (y > 0.0).then(|| [x / y, 1.0, (1.0 - x - y) / y]);
+    let [r, g, b] = [xyz(primaries[0])?, xyz(primaries[1])?, xyz(primaries[2])?];
+    let columns = [[r[0], g[0], b[0]], [r[1], g[1], b[1]], [r[2], g[2], b[2]]];
+
+    // Scale each primary so they add up to the white
+    let scale = apply(&invert(columns)?, xyz(white)?);
+    Some([0, 1, 2].map(|row| [0, 1, 2].map(|c| columns[row][c] * scale[c])))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::monitor::{Chromaticity, ColorDepth, EdidInfo, HdrCapability, MonitorCapabilities};
+
+    fn monitor(gamut: ColorGamut, luminance: Option<(f32, u32)>) -> MonitorProfile {
+        let [red, green, blue] = gamut.primaries().unwrap();
+        MonitorProfile {
+            name: "card0-DP-1".to_string(),
+            edid: EdidInfo {
+                manufacturer: "DEL".to_string(),
+                model: "Test".to_string(),
+                serial: None,
+                year: 2024,
+                resolution: (3840, 2160),
+                physical_size_mm: None,
+                hash: None,
+                chromaticity: Some(Chromaticity {
+                    red,
+                    green,
+                    blue,
+                    white: D65,
+                }),
+            },
+            capabilities: MonitorCapabilities {
+                color_depth: ColorDepth::Bit10,
+                hdr_support: HdrCapability::None,
+                wide_gamut: false,
+                native_gamma: 2.2,
+                max_luminance: luminance.map(|(_, peak)| peak),
+                min_luminance: luminance.map(|(black, _)| black),
+                color_gamut: gamut,
+            },
+            calibration: None,
+            icc_profile: None,
+        }
+    }
+
+    #[test]
+    fn test_srgb_covers_srgb() {
+        let srgb = monitor(ColorGamut::Srgb, None);
+        assert!((srgb.gamut_coverage(ColorGamut::Srgb) - 100.0).abs() < 1e-3);
+    }
+
+    #[test]
+    fn test_coverage_against_rec2020() {
+        // Rec.2020 contains sRGB, so the wider panel covers all of it
+        let rec2020 = monitor(ColorGamut::Bt2020, None);
+        assert!((rec2020.gamut_coverage(ColorGamut::Srgb) - 100.0).abs() < 1e-3);
+
+        // sRGB's triangle is 0.11205 in xy against Rec.2020's 0.21187
+        let srgb = monitor(ColorGamut::Srgb, None);
+        let coverage = srgb.gamut_coverage(ColorGamut::Bt2020);
+        assert!((coverage - 52.888).abs() < 0.01, "{}", coverage);
+
+        // Shares the blue corner, so only part of Adobe RGB's green is missing
+        let adobe = srgb.gamut_coverage(ColorGamut::AdobeRgb);
+        assert!(adobe > 70.0 && adobe < 100.0, "{}", adobe);
+    }
+
+    #[test]
+    fn test_coverage_without_primaries() {
+        let mut unknown = monitor(ColorGamut::Srgb, None);
+        unknown.edid.chromaticity = None;
+        assert_eq!(unknown.gamut_coverage(ColorGamut::Srgb), 0.0);
+        assert_eq!(
+            monitor(ColorGamut::Srgb, None).gamut_coverage(ColorGamut::Unknown),
+            0.0
+        );
+    }
+
+    #[test]
+    fn test_volume_coverage() {
+        // Bright enough for sRGB's 80 cd/m² white
+        let srgb = monitor(ColorGamut::Srgb, Some((0.0, 300)));
+        let volume = srgb.gamut_volume_coverage(ColorGamut::Srgb).unwrap();
+        assert!((volume - 100.0).abs() < 1e-3, "{}", volume);
+
+        // Too dim to reach the white level, so the lightest colors are lost
+        let dim = monitor(ColorGamut::Srgb, Some((0.0, 60)));
+        let volume = dim.gamut_volume_coverage(ColorGamut::Srgb).unwrap();
+        assert!(volume < 100.0, "{}", volume);
+
+        assert_eq!(
+            monitor(ColorGamut::Srgb, None).gamut_volume_coverage(ColorGamut::Srgb),
+            None
+        );
+    }
+}
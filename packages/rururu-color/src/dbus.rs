@@ -1,10 +1,14 @@
+use crate::gamma::{self, GammaBackend, LoadedGamma};
 use crate::hdr::HdrSupport;
-use crate::monitor::MonitorProfile;
+use crate::lut::Lut1d;
+use crate::monitor::{CalibrationData, MonitorProfile};
 use crate::ocio::OcioManager;
 use crate::{ColorConfig, ColorError, IccManager, Result};
+use std::collections::HashMap;
+use std::path::Path;
 use std::sync::Arc;
-use tokio::sync::RwLock;
-use zbus::{interface, Connection};
+use tokio::sync::{Mutex, RwLock};
+use zbus::{interface, proxy, Connection};
 
 pub struct ColorService {
     config: Arc<RwLock<ColorConfig>>,
@@ -12,6 +16,8 @@ pub struct ColorService {
     ocio_manager: Arc<RwLock<OcioManager>>,
     hdr_support: Arc<RwLock<HdrSupport>>,
     monitors: Arc<RwLock<Vec<MonitorProfile>>>,
+    /// Calibration ramps loaded per monitor, held so Wayland keeps them
+    gamma: Arc<Mutex<HashMap<String, LoadedGamma>>>,
 }
 
 impl Default for ColorService {
@@ -28,6 +34,7 @@ impl ColorService {
             ocio_manager: Arc::new(RwLock::new(OcioManager::new())),
             hdr_support: Arc::new(RwLock::new(HdrSupport::new())),
             monitors: Arc::new(RwLock::new(Vec::new())),
+            gamma: Arc::new(Mutex::new(HashMap::new())),
         }
     }
 
@@ -39,8 +46,13 @@ impl ColorService {
         // Scan ICC profiles
         self.icc_manager.write().await.scan_profiles();
 
-        // Detect monitors
+        // Detect monitors and load their calibrations
         let monitors = crate::monitor::detect_monitors()?;
+        for monitor in &monitors {
+            if let Some(ref profile) = monitor.icc_profile {
+                self.load_calibration(&monitor.name, Some(profile)).await;
+            }
+        }
         *self.monitors.write().await = monitors;
 
         // Detect HDR support
@@ -49,6 +61,40 @@ impl ColorService {
 
         Ok(())
     }
+
+    /// Load the `vcgt` curves of `profile` onto `monitor`; without a
+    /// profile, or one without curves, the monitor goes back to a linear ramp
+    async fn load_calibration(&self, monitor: &str, profile: Option<&Path>) {
+        let mut loaded = self.gamma.lock().await;
+        // Dropping a Wayland ramp restores the compositor's own
+        let previous = loaded.remove(monitor);
+
+        let ramp = match profile.map(gamma::vcgt_ramp) {
+            Some(Ok(Some(ramp))) => ramp,
+            Some(Ok(None)) | None => {
+                // X11 keeps a ramp until it's replaced
+                if previous.is_some_and(|p| p.backend == GammaBackend::X11) {
+                    if let Ok(linear) = Lut1d::sample(2, |v, _| v) {
+                        if let Err(e) = gamma::load(monitor, &linear) {
+                            tracing::warn!("Failed to reset the gamma ramp of {}: {}", monitor, e);
+                        }
+                    }
+                }
+                return;
+            }
+            Some(Err(e)) => {
+                tracing::warn!("Can't read calibration curves for {}: {}", monitor, e);
+                return;
+            }
+        };
+
+        match gamma::load(monitor, &ramp) {
+            Ok(handle) => {
+                loaded.insert(monitor.to_string(), handle);
+            }
+            Err(e) => tracing::warn!("Failed to load the calibration of {}: {}", monitor, e),
+        }
+    }
 }
 
 #[interface(name = "org.rururu.ColorManagement1")]
@@ -89,18 +135,43 @@ impl ColorService {
     }
 
     async fn set_monitor_profile(&self, monitor: String, profile_path: String) -> bool {
+        let icc_profile: Option<std::path::PathBuf> = if profile_path.is_empty() {
+            None
+        } else {
+            Some(profile_path.into())
+        };
+
+        self.load_calibration(&monitor, icc_profile.as_deref())
+            .await;
+
+        // Remember the assignment and the calibration it applies for this
+        // physical monitor by EDID
+        let calibration = icc_profile.as_deref().and_then(|profile| {
+            CalibrationData::from_profile(profile)
+                .map_err(|e| tracing::warn!("Can't read the calibration of {:?}: {}", profile, e))
+                .ok()
+        });
+        let mut persisted = false;
+        if let Some(detected) = self
+            .monitors
+            .write()
+            .await
+            .iter_mut()
+            .find(|m| m.name == monitor)
+        {
+            detected.icc_profile = icc_profile.clone();
+            detected.calibration = calibration;
+            persisted = detected.save().is_ok();
+        }
+
         let mut config = self.config.write().await;
 
         if let Some(mon_config) = config.monitors.get_mut(&monitor) {
-            mon_config.icc_profile = if profile_path.is_empty() {
-                None
-            } else {
-                Some(profile_path.into())
-            };
+            mon_config.icc_profile = icc_profile;
             return config.save().is_ok();
         }
 
-        false
+        persisted
     }
 
     async fn list_profiles(&self) -> Vec<String> {
@@ -218,6 +289,23 @@ impl ColorService {
     }
 }
 
+/// Client side of [`ColorService`] for other RururuOS components
+#[proxy(
+    interface = "org.rururu.ColorManagement1",
+    default_service = "org.rururu.ColorManagement1",
+    default_path = "/org/rururu/ColorManagement1"
+)]
+pub trait ColorManagement {
+    fn list_monitors(&self) -> zbus::Result<Vec<String>>;
+    fn get_monitor_profile(&self, monitor: &str) -> zbus::Result<String>;
+    fn set_monitor_profile(&self, monitor: &str, profile_path: &str) -> zbus::Result<bool>;
+    fn list_display_profiles(&self) -> zbus::Result<Vec<String>>;
+    fn is_hdr_active(&self, monitor: &str) -> zbus::Result<bool>;
+    fn enable_hdr(&self, monitor: &str) -> zbus::Result<bool>;
+    fn disable_hdr(&self, monitor: &str) -> zbus::Result<bool>;
+    fn refresh(&self) -> zbus::Result<bool>;
+}
+
 pub async fn run_service() -> Result<()> {
     let service = ColorService::new();
     service.init().await?;
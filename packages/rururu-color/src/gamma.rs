@@ -0,0 +1,302 @@
+//! Loading calibration curves into the display's hardware gamma ramps.
+//!
+//! A display profile's `vcgt` tag holds the curves a calibration measured;
+//! they only take effect once loaded into the CRTC's gamma LUT. On Wayland
+//! that goes through wlroots' gamma-control protocol, and the compositor
+//! restores the original ramp as soon as the client that set it
+//! disconnects, so the returned [`LoadedGamma`] has to be kept alive. On X11
+//! RandR's `SetCrtcGamma` changes the ramp until someone else sets it.
+//! XWayland's RandR only reaches its own emulated outputs, so a Wayland
+//! session never falls back to it.
+
+use crate::lut::{Lut1d, DEFAULT_1D_SIZE};
+use crate::screenshot::{connector_matches, shm_file};
+use crate::{ColorError, Result};
+use lcms2::{Profile, Tag, TagSignature};
+use std::io::Write;
+use std::os::fd::AsFd;
+use std::path::Path;
+use wayland_client::globals::{registry_queue_init, GlobalListContents};
+use wayland_client::protocol::{wl_output, wl_registry};
+use wayland_client::{delegate_noop, Connection, Dispatch, EventQueue, Proxy, QueueHandle};
+use wayland_protocols_wlr::gamma_control::v1::client::{
+    zwlr_gamma_control_manager_v1 as manager, zwlr_gamma_control_v1 as control,
+};
+use x11rb::connection::Connection as _;
+use x11rb::protocol::randr::ConnectionExt as _;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum GammaBackend {
+    Wayland,
+    X11,
+}
+
+impl GammaBackend {
+    /// The backend for this session, `None` outside a graphical one
+    pub fn detect() -> Option<Self> {
+        match std::env::var("XDG_SESSION_TYPE")
+            .unwrap_or_default()
+            .as_str()
+        {
+            "wayland" => return Some(GammaBackend::Wayland),
+            "x11" => return Some(GammaBackend::X11),
+            _ => {}
+        }
+
+        if std::env::var_os("WAYLAND_DISPLAY").is_some() {
+            Some(GammaBackend::Wayland)
+        } else if std::env::var_os("DISPLAY").is_some() {
+            Some(GammaBackend::X11)
+        } else {
+            None
+        }
+    }
+}
+
+/// A ramp loaded by [`load`]. On Wayland dropping it restores the
+/// compositor's ramp; on X11 the ramp stays either way.
+pub struct LoadedGamma {
+    pub backend: GammaBackend,
+    _wayland: Option<(
+        Connection,
+        EventQueue<WaylandGamma>,
+        control::ZwlrGammaControlV1,
+    )>,
+}
+
+/// The calibration curves in the profile's `vcgt` tag, `None` when it has
+/// none
+pub fn vcgt_ramp(profile: &Path) -> Result<Option<Lut1d>> {
+    let profile = Profile::new_file(profile).map_err(|e| ColorError::IccError(e.to_string()))?;
+    profile_vcgt(&profile)
+}
+
+/// [`vcgt_ramp`] of a profile already open
+pub(crate) fn profile_vcgt(profile: &Profile) -> Result<Option<Lut1d>> {
+    match profile.read_tag(TagSignature::VcgtTag) {
+        Tag::VcgtCurves(curves) => {
+            Lut1d::sample(DEFAULT_1D_SIZE, |v, channel| curves[channel].eval(v)).map(Some)
+        }
+        _ => Ok(None),
+    }
+}
+
+/// Load `ramp` onto `output` (`DP-1`, or the sysfs `card0-DP-1`) with the
+/// session's backend
+pub fn load(output: &str, ramp: &Lut1d) -> Result<LoadedGamma> {
+    match GammaBackend::detect() {
+        Some(GammaBackend::Wayland) => load_wayland(output, ramp),
+        Some(GammaBackend::X11) => {
+            load_x11(output, ramp)?;
+            Ok(LoadedGamma {
+                backend: GammaBackend::X11,
+                _wayland: None,
+            })
+        }
+        None => Err(ColorError::GammaUnavailable),
+    }
+}
+
+/// `ramp` resampled to the `size` entries per channel the CRTC takes
+fn hardware_ramp(ramp: &Lut1d, size: usize) -> [Vec<u16>; 3] {
+    let last = ramp.size().saturating_sub(1);
+    let channel = |c: usize| {
+        (0..size)
+            .map(|i| {
+                let pos = i as f32 / (size - 1).max(1) as f32 * last as f32;
+                let (lo, t) = (pos.floor() as usize, pos.fract());
+                let hi = (lo + 1).min(last);
+                let v = ramp.table[lo][c] * (1.0 - t) + ramp.table[hi][c] * t;
+                (v.clamp(0.0, 1.0) * u16::MAX as f32).round() as u16
+            })
+            .collect()
+    };
+    [channel(0), channel(1), channel(2)]
+}
+
+fn load_x11(output: &str, ramp: &Lut1d) -> Result<()> {
+    let x11 = |e: &dyn std::fmt::Display| ColorError::Gamma(format!("X11: {}", e));
+
+    let (conn, screen) = x11rb::connect(None).map_err(|e| x11(&e))?;
+    let root = conn.setup().roots[screen].root;
+    let resources = conn
+        .randr_get_screen_resources_current(root)
+        .map_err(|e| x11(&e))?
+        .reply()
+        .map_err(|e| x11(&e))?;
+
+    let mut crtc = None;
+    for &id in &resources.outputs {
+        let info = conn
+            .randr_get_output_info(id, resources.config_timestamp)
+            .map_err(|e| x11(&e))?
+            .reply()
+            .map_err(|e| x11(&e))?;
+        if connector_matches(output, &String::from_utf8_lossy(&info.name)) {
+            crtc = Some(info.crtc);
+            break;
+        }
+    }
+    let crtc = match crtc {
+        Some(0) => return Err(ColorError::Gamma(format!("{} is switched off", output))),
+        Some(crtc) => crtc,
+        None => return Err(ColorError::MonitorNotFound(output.to_string())),
+    };
+
+    let size = conn
+        .randr_get_crtc_gamma_size(crtc)
+        .map_err(|e| x11(&e))?
+        .reply()
+        .map_err(|e| x11(&e))?
+        .size as usize;
+    if size < 2 {
+        return Err(ColorError::Gamma(format!(
+            "{} has no gamma ramp to load",
+            output
+        )));
+    }
+
+    let [red, green, blue] = hardware_ramp(ramp, size);
+    conn.randr_set_crtc_gamma(crtc, &red, &green, &blue)
+        .map_err(|e| x11(&e))?
+        .check()
+        .map_err(|e| x11(&e))?;
+    Ok(())
+}
+
+#[derive(Default)]
+struct WaylandGamma {
+    outputs: Vec<(wl_output::WlOutput, Option<String>)>,
+    gamma_size: Option<u32>,
+    failed: bool,
+}
+
+fn load_wayland(output_name: &str, ramp: &Lut1d) -> Result<LoadedGamma> {
+    let wayland = |e: &dyn std::fmt::Display| ColorError::Gamma(format!("Wayland: {}", e));
+
+    let conn = Connection::connect_to_env().map_err(|e| wayland(&e))?;
+    let (globals, mut queue) =
+        registry_queue_init::<WaylandGamma>(&conn).map_err(|e| wayland(&e))?;
+    let qh = queue.handle();
+
+    let manager: manager::ZwlrGammaControlManagerV1 =
+        globals.bind(&qh, 1..=1, ()).map_err(|_| {
+            ColorError::Gamma(
+                "compositor doesn't support wlr-gamma-control (needs sway or another wlroots compositor)"
+                    .into(),
+            )
+        })?;
+
+    let mut state = WaylandGamma::default();
+    for global in globals.contents().clone_list() {
+        if global.interface == wl_output::WlOutput::interface().name {
+            // Output names arrive with version 4
+            let output = globals.registry().bind::<wl_output::WlOutput, _, _>(
+                global.name,
+                global.version.min(4),
+                &qh,
+                state.outputs.len(),
+            );
+            state.outputs.push((output, None));
+        }
+    }
+    queue.roundtrip(&mut state).map_err(|e| wayland(&e))?;
+
+    let output = match state.outputs.as_slice() {
+        [(output, None)] => output,
+        outputs => outputs
+            .iter()
+            .find(|(_, name)| {
+                name.as_deref()
+                    .is_some_and(|n| connector_matches(output_name, n))
+            })
+            .map(|(output, _)| output)
+            .ok_or_else(|| ColorError::MonitorNotFound(output_name.to_string()))?,
+    };
+
+    let gamma_control = manager.get_gamma_control(output, &qh, ());
+    while state.gamma_size.is_none() && !state.failed {
+        queue
+            .blocking_dispatch(&mut state)
+            .map_err(|e| wayland(&e))?;
+    }
+    let size = match state.gamma_size {
+        Some(size) if !state.failed && size >= 2 => size as usize,
+        // Another client, like a night light, already holds the ramp
+        _ => {
+            return Err(ColorError::Gamma(format!(
+                "compositor refused gamma control of {}",
+                output_name
+            )))
+        }
+    };
+
+    // Red, green then blue, native-endian
+    let bytes: Vec<u8> = hardware_ramp(ramp, size)
+        .iter()
+        .flatten()
+        .flat_map(|v| v.to_ne_bytes())
+        .collect();
+    let mut file = shm_file(c"rururu-gamma", bytes.len() as u64)?;
+    file.write_all(&bytes)?;
+    gamma_control.set_gamma(file.as_fd());
+    queue.roundtrip(&mut state).map_err(|e| wayland(&e))?;
+    if state.failed {
+        return Err(ColorError::Gamma(format!(
+            "compositor rejected the ramp for {}",
+            output_name
+        )));
+    }
+
+    Ok(LoadedGamma {
+        backend: GammaBackend::Wayland,
+        _wayland: Some((conn, queue, gamma_control)),
+    })
+}
+
+impl Dispatch<wl_registry::WlRegistry, GlobalListContents> for WaylandGamma {
+    fn event(
+        _: &mut Self,
+        _: &wl_registry::WlRegistry,
+        _: wl_registry::Event,
+        _: &GlobalListContents,
+        _: &Connection,
+        _: &QueueHandle<Self>,
+    ) {
+        // A ramp belongs to the output it was loaded on; new ones don't matter
+    }
+}
+
+impl Dispatch<wl_output::WlOutput, usize> for WaylandGamma {
+    fn event(
+        state: &mut Self,
+        _: &wl_output::WlOutput,
+        event: wl_output::Event,
+        index: &usize,
+        _: &Connection,
+        _: &QueueHandle<Self>,
+    ) {
+        if let wl_output::Event::Name { name } = event {
+            state.outputs[*index].1 = Some(name);
+        }
+    }
+}
+
+impl Dispatch<control::ZwlrGammaControlV1, ()> for WaylandGamma {
+    fn event(
+        state: &mut Self,
+        _: &control::ZwlrGammaControlV1,
+        event: control::Event,
+        _: &(),
+        _: &Connection,
+        _: &QueueHandle<Self>,
+    ) {
+        match event {
+            control::Event::GammaSize { size } => state.gamma_size = Some(size),
+            control::Event::Failed => state.failed = true,
+            _ => {}
+        }
+    }
+}
+
+delegate_noop!(WaylandGamma: manager::ZwlrGammaControlManagerV1);
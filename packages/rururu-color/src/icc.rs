@@ -35,6 +35,244 @@ pub enum ProfileClass {
     Unknown,
 }
 
+/// What a profile says about the device it was made for, read from its
+/// tags rather than just the header
+#[derive(Debug, Clone, PartialEq)]
+pub struct IccProfileInfo {
+    pub description: Option<String>,
+    /// `dmnd` tag
+    pub device_manufacturer: Option<String>,
+    /// `dmdd` tag, typically the monitor model for calibrated profiles
+    pub device_model: Option<String>,
+    pub profile_class: ProfileClass,
+    pub color_space: ColorSpace,
+    /// CIE xy of the device white before chromatic adaptation to D50
+    pub white_point: Option<(f64, f64)>,
+    /// CIE xy of the red, green and blue colorants, likewise un-adapted
+    pub primaries: Option<[(f64, f64); 3]>,
+}
+
+/// PCS illuminant every ICC profile is adapted to
+pub(crate) const D50_XYZ: [f64; 3] = [0.9642, 1.0, 0.8249];
+
+pub(crate) type Matrix3 = [[f64; 3]; 3];
+
+impl IccProfileInfo {
+    pub fn from_file(path: &Path) -> Result<Self> {
+        Self::parse(&std::fs::read(path)?)
+    }
+
+    pub fn parse(data: &[u8]) -> Result<Self> {
+        if data.len() < 132 {
+            return Err(ColorError::IccError("Profile too small".to_string()));
+        }
+
+        let tags = read_tag_table(data)?;
+        let tag = |sig: &[u8; 4]| tags.get(sig).copied();
+        let text_tag = |sig: &[u8; 4]| tag(sig).and_then(read_text);
+        let xyz_tag = |sig: &[u8; 4]| tag(sig).and_then(read_xyz);
+
+        let chad = tag(b"chad").and_then(read_matrix).and_then(invert);
+        let wtpt = xyz_tag(b"wtpt");
+
+        // v4 profiles store a D50 `wtpt` and keep the device white in the
+        // `chad` adaptation; older ones store the device white directly and
+        // imply Bradford adaptation of the colorants
+        let white = match &chad {
+            Some(undo) => Some(apply(undo, wtpt.unwrap_or(D50_XYZ))),
+            None => wtpt,
+        };
+        let undo = chad.or_else(|| white.and_then(|w| invert(bradford(w, D50_XYZ))));
+
+        let primaries = match (xyz_tag(b"rXYZ"), xyz_tag(b"gXYZ"), xyz_tag(b"bXYZ")) {
+            (Some(r), Some(g), Some(b)) => {
+                let native = |c| undo.as_ref().map_or(c, |m| apply(m, c));
+                match (to_xy(native(r)), to_xy(native(g)), to_xy(native(b))) {
+                    (Some(r), Some(g), Some(b)) => Some([r, g, b]),
+                    _ => None,
+                }
+            }
+            _ => None,
+        };
+
+        Ok(Self {
+            description: text_tag(b"desc"),
+            device_manufacturer: text_tag(b"dmnd"),
+            device_model: text_tag(b"dmdd"),
+            profile_class: profile_class(&data[12..16]),
+            color_space: color_space(&data[16..20]),
+            white_point: white.and_then(to_xy),
+            primaries,
+        })
+    }
+}
+
+/// Tag signature to tag data, with bounds already checked
+pub(crate) fn read_tag_table(data: &[u8]) -> Result<HashMap<[u8; 4], &[u8]>> {
+    let count = be_u32(data, 128).unwrap_or(0) as usize;
+    let mut tags = HashMap::new();
+
+    for i in 0..count.min(1024) {
+        let entry = 132 + i * 12;
+        let (Some(sig), Some(offset), Some(size)) = (
+            data.get(entry..entry + 4),
+            be_u32(data, entry + 4),
+            be_u32(data, entry + 8),
+        ) else {
+            return Err(ColorError::IccError("Truncated tag table".to_string()));
+        };
+        let (offset, size) = (offset as usize, size as usize);
+        if let Some(tag) = data.get(offset..offset.saturating_add(size)) {
+            tags.insert([sig[0], sig[1], sig[2], sig[3]], tag);
+        }
+    }
+    Ok(tags)
+}
+
+/// `desc` (v2), `mluc` (v4, first record) or plain `text`
+fn read_text(tag: &[u8]) -> Option<String> {
+    let text = match tag.get(0..4)? {
+        b"desc" => {
+            let len = be_u32(tag, 8)? as usize;
+            String::from_utf8_lossy(tag.get(12..12 + len)?).into_owned()
+        }
+        b"mluc" => {
+            if be_u32(tag, 8)? == 0 {
+                return None;
+            }
+            let len = be_u32(tag, 20)? as usize;
+            let offset = be_u32(tag, 24)? as usize;
+            let units: Vec<u16> = tag
+                .get(offset..offset + len)?
+                .chunks_exact(2)
+                .map(|c| u16::from_be_bytes([c[0], c[1]]))
+                .collect();
+            String::from_utf16_lossy(&units)
+        }
+        b"text" => String::from_utf8_lossy(tag.get(8..)?).into_owned(),
+        _ => return None,
+    };
+
+    let text = text.trim_end_matches('\0').trim().to_string();
+    (!text.is_empty()).then_some(text)
+}
+
+pub(crate) fn read_xyz(tag: &[u8]) -> Option<[f64; 3]> {
+    if tag.get(0..4)? != b"XYZ " {
+        return None;
+    }
+    Some([
+        s15_fixed16(tag, 8)?,
+        s15_fixed16(tag, 12)?,
+        s15_fixed16(tag, 16)?,
+    ])
+}
+
+fn read_matrix(tag: &[u8]) -> Option<Matrix3> {
+    if tag.get(0..4)? != b"sf32" {
+        return None;
+    }
+    let mut m = [[0.0; 3]; 3];
+    for (i, value) in m.iter_mut().flatten().enumerate() {
+        *value = s15_fixed16(tag, 8 + i * 4)?;
+    }
+    Some(m)
+}
+
+pub(crate) fn be_u32(data: &[u8], offset: usize) -> Option<u32> {
+    let bytes = data.get(offset..offset + 4)?;
+    Some(u32::from_be_bytes([bytes[0], bytes[1], bytes[2], bytes[3]]))
+}
+
+pub(crate) fn s15_fixed16(data: &[u8], offset: usize) -> Option<f64> {
+    Some(be_u32(data, offset)? as i32 as f64 / 65536.0)
+}
+
+pub(crate) fn to_xy([x, y, z]: [f64; 3]) -> Option<(f64, f64)> {
+    let sum = x + y + z;
+    (sum > 0.0).then(|| (x / sum, y / sum))
+}
+
+pub(crate) fn apply(m: &Matrix3, v: [f64; 3]) -> [f64; 3] {
+    [0, 1, 2].map(|r| m[r][0] * v[0] + m[r][1] * v[1] + m[r][2] * v[2])
+}
+
+fn multiply(a: &Matrix3, b: &Matrix3) -> Matrix3 {
+    let mut out = [[0.0; 3]; 3];
+    for (r, row) in out.iter_mut().enumerate() {
+        for (c, value) in row.iter_mut().enumerate() {
+            *value = (0..3).map(|k| a[r][k] * b[k][c]).sum();
+        }
+    }
+    out
+}
+
+pub(crate) fn invert(m: Matrix3) -> Option<Matrix3> {
+    let det = m[0][0] * (m[1][1] * m[2][2] - m[1][2] * m[2][1])
+        - m[0][1] * (m[1][0] * m[2][2] - m[1][2] * m[2][0])
+        + m[0][2] * (m[1][0] * m[2][1] - m[1][1] * m[2][0]);
+    if det.abs() < 1e-12 {
+        return None;
+    }
+
+    let mut out = [[0.0; 3]; 3];
+    for (r, row) in out.iter_mut().enumerate() {
+        for (c, value) in row.iter_mut().enumerate() {
+            // Cofactor of the transposed element
+            let (r1, r2) = ((c + 1) % 3, (c + 2) % 3);
+            let (c1, c2) = ((r + 1) % 3, (r + 2) % 3);
+            *value = (m[r1][c1] * m[r2][c2] - m[r1][c2] * m[r2][c1]) / det;
+        }
+    }
+    Some(out)
+}
+
+/// Bradford adaptation from white `from` to white `to`
+fn bradford(from: [f64; 3], to: [f64; 3]) -> Matrix3 {
+    const CONE: Matrix3 = [
+        [0.8951, 0.2664, -0.1614],
+        [-0.7502, 1.7135, 0.0367],
+        [0.0389, -0.0685, 1.0296],
+    ];
+    const CONE_INV: Matrix3 = [
+        [0.9869929, -0.1470543, 0.1599627],
+        [0.4323053, 0.5183603, 0.0492912],
+        [-0.0085287, 0.0400428, 0.9684867],
+    ];
+
+    let (src, dst) = (apply(&CONE, from), apply(&CONE, to));
+    let scale = [
+        [dst[0] / src[0], 0.0, 0.0],
+        [0.0, dst[1] / src[1], 0.0],
+        [0.0, 0.0, dst[2] / src[2]],
+    ];
+    multiply(&CONE_INV, &multiply(&scale, &CONE))
+}
+
+pub(crate) fn color_space(sig: &[u8]) -> ColorSpace {
+    match sig {
+        b"RGB " => ColorSpace::RGB,
+        b"CMYK" => ColorSpace::CMYK,
+        b"GRAY" => ColorSpace::Gray,
+        b"Lab " => ColorSpace::Lab,
+        b"XYZ " => ColorSpace::XYZ,
+        _ => ColorSpace::Unknown,
+    }
+}
+
+pub(crate) fn profile_class(sig: &[u8]) -> ProfileClass {
+    match sig {
+        b"scnr" => ProfileClass::Input,
+        b"mntr" => ProfileClass::Display,
+        b"prtr" => ProfileClass::Output,
+        b"link" => ProfileClass::DeviceLink,
+        b"spac" => ProfileClass::ColorSpace,
+        b"abst" => ProfileClass::Abstract,
+        b"nmcl" => ProfileClass::NamedColor,
+        _ => ProfileClass::Unknown,
+    }
+}
+
 pub struct IccManager {
     profiles: HashMap<String, IccProfile>,
     system_paths: Vec<PathBuf>,
@@ -116,26 +354,10 @@ impl IccManager {
         }
 
         // Color space signature at offset 16
-        let color_space = match &data[16..20] {
-            b"RGB " => ColorSpace::RGB,
-            b"CMYK" => ColorSpace::CMYK,
-            b"GRAY" => ColorSpace::Gray,
-            b"Lab " => ColorSpace::Lab,
-            b"XYZ " => ColorSpace::XYZ,
-            _ => ColorSpace::Unknown,
-        };
+        let color_space = color_space(&data[16..20]);
 
         // Profile class at offset 12
-        let profile_class = match &data[12..16] {
-            b"scnr" => ProfileClass::Input,
-            b"mntr" => ProfileClass::Display,
-            b"prtr" => ProfileClass::Output,
-            b"link" => ProfileClass::DeviceLink,
-            b"spac" => ProfileClass::ColorSpace,
-            b"abst" => ProfileClass::Abstract,
-            b"nmcl" => ProfileClass::NamedColor,
-            _ => ProfileClass::Unknown,
-        };
+        let profile_class = profile_class(&data[12..16]);
 
         // Extract description from filename for now
         let name = path
@@ -170,6 +392,14 @@ impl IccManager {
             .collect()
     }
 
+    /// Printer and other output profiles, the targets of a soft proof
+    pub fn list_output_profiles(&self) -> Vec<&IccProfile> {
+        self.profiles
+            .values()
+            .filter(|p| p.profile_class == ProfileClass::Output)
+            .collect()
+    }
+
     pub fn list_rgb_profiles(&self) -> Vec<&IccProfile> {
         self.profiles
             .values()
@@ -216,7 +446,8 @@ impl Default for IccManager {
     }
 }
 
-pub fn apply_profile_to_monitor(_profile: &IccProfile, _monitor_name: &str) -> Result<()> {
+#[cfg_attr(not(target_os = "linux"), allow(unused_variables))]
+pub fn apply_profile_to_monitor(profile: &IccProfile, monitor_name: &str) -> Result<()> {
     // Use colord or direct gamma ramp setting
     #[cfg(target_os = "linux")]
     {
@@ -242,3 +473,180 @@ pub fn apply_profile_to_monitor(_profile: &IccProfile, _monitor_name: &str) -> R
 
     Ok(())
 }
+
+#[cfg(test)]
+pub(crate) mod tests {
+    use super::*;
+
+    fn s15(v: f64) -> [u8; 4] {
+        ((v * 65536.0).round() as i32).to_be_bytes()
+    }
+
+    fn xyz_of((x, y): (f64, f64)) -> [f64; 3] {
+        [x / y, 1.0, (1.0 - x - y) / y]
+    }
+
+    pub(crate) fn xyz_tag(xyz: [f64; 3]) -> Vec<u8> {
+        let mut tag = b"XYZ \0\0\0\0".to_vec();
+        tag.extend(xyz.iter().flat_map(|&v| s15(v)));
+        tag
+    }
+
+    /// v2 `desc`: ASCII, then empty Unicode and ScriptCode parts
+    fn desc_tag(text: &str) -> Vec<u8> {
+        let mut tag = b"desc\0\0\0\0".to_vec();
+        tag.extend((text.len() as u32 + 1).to_be_bytes());
+        tag.extend(text.as_bytes());
+        tag.push(0);
+        tag.extend([0; 8 + 3 + 67]);
+        tag
+    }
+
+    fn text_tag(text: &str) -> Vec<u8> {
+        let mut tag = b"text\0\0\0\0".to_vec();
+        tag.extend(text.as_bytes());
+        tag.push(0);
+        tag
+    }
+
+    fn mluc_tag(text: &str) -> Vec<u8> {
+        let units: Vec<u8> = text.encode_utf16().flat_map(u16::to_be_bytes).collect();
+        let mut tag = b"mluc\0\0\0\0".to_vec();
+        tag.extend(1u32.to_be_bytes());
+        tag.extend(12u32.to_be_bytes());
+        tag.extend(b"enUS");
+        tag.extend((units.len() as u32).to_be_bytes());
+        tag.extend(28u32.to_be_bytes());
+        tag.extend(units);
+        tag
+    }
+
+    /// A profile assembled byte by byte: the header fields the parser reads
+    /// and a tag table pointing at `tags` in order
+    pub(crate) fn profile_bytes(class: &[u8; 4], tags: &[([u8; 4], Vec<u8>)]) -> Vec<u8> {
+        let mut data = vec![0u8; 128];
+        data[8] = 2;
+        data[9] = 0x10;
+        data[12..16].copy_from_slice(class);
+        data[16..20].copy_from_slice(b"RGB ");
+        data[20..24].copy_from_slice(b"XYZ ");
+        data[36..40].copy_from_slice(b"acsp");
+        for (i, v) in D50_XYZ.iter().enumerate() {
+            data[68 + i * 4..72 + i * 4].copy_from_slice(&s15(*v));
+        }
+
+        data.extend((tags.len() as u32).to_be_bytes());
+        let mut offset = 132 + 12 * tags.len();
+        for (sig, tag) in tags {
+            data.extend(sig);
+            data.extend((offset as u32).to_be_bytes());
+            data.extend((tag.len() as u32).to_be_bytes());
+            offset += tag.len();
+        }
+        for (_, tag) in tags {
+            data.extend(tag);
+        }
+        let size = data.len() as u32;
+        data[0..4].copy_from_slice(&size.to_be_bytes());
+        data
+    }
+
+    /// A v2 matrix/shaper profile of `class` for a device with `white` and
+    /// `primaries` in xy: the device white in `wtpt` and the colorants
+    /// Bradford-adapted to D50, as v2 profiles store them
+    pub(crate) fn matrix_profile(
+        class: &[u8; 4],
+        white: (f64, f64),
+        primaries: [(f64, f64); 3],
+        model: Option<&str>,
+    ) -> Vec<u8> {
+        let white_xyz = xyz_of(white);
+        let colorants = primaries.map(xyz_of);
+        let to_xyz = [0, 1, 2].map(|r| colorants.map(|c| c[r]));
+        let scale = apply(&invert(to_xyz).unwrap(), white_xyz);
+        let adapt = bradford(white_xyz, D50_XYZ);
+        let adapted = [0, 1, 2].map(|i| apply(&adapt, colorants[i].map(|v| v * scale[i])));
+
+        let mut tags = vec![
+            (*b"desc", desc_tag("Fixture")),
+            (*b"wtpt", xyz_tag(white_xyz)),
+            (*b"rXYZ", xyz_tag(adapted[0])),
+            (*b"gXYZ", xyz_tag(adapted[1])),
+            (*b"bXYZ", xyz_tag(adapted[2])),
+        ];
+        if let Some(model) = model {
+            tags.push((*b"dmdd", desc_tag(model)));
+        }
+        profile_bytes(class, &tags)
+    }
+
+    pub(crate) const D65: (f64, f64) = (0.3127, 0.3290);
+    pub(crate) const SRGB: [(f64, f64); 3] = [(0.64, 0.33), (0.30, 0.60), (0.15, 0.06)];
+
+    fn assert_xy_near(actual: (f64, f64), expected: (f64, f64)) {
+        assert!(
+            (actual.0 - expected.0).abs() < 1e-3 && (actual.1 - expected.1).abs() < 1e-3,
+            "{:?} != {:?}",
+            actual,
+            expected
+        );
+    }
+
+    #[test]
+    fn test_parse_v2_matrix_profile() {
+        let data = matrix_profile(b"mntr", D65, SRGB, Some("U2720Q"));
+        let info = IccProfileInfo::parse(&data).unwrap();
+
+        assert_eq!(info.description.as_deref(), Some("Fixture"));
+        assert_eq!(info.device_model.as_deref(), Some("U2720Q"));
+        assert_eq!(info.device_manufacturer, None);
+        assert_eq!(info.profile_class, ProfileClass::Display);
+        assert_eq!(info.color_space, ColorSpace::RGB);
+        // Colorants come back un-adapted from D50
+        assert_xy_near(info.white_point.unwrap(), D65);
+        for (actual, expected) in info.primaries.unwrap().into_iter().zip(SRGB) {
+            assert_xy_near(actual, expected);
+        }
+    }
+
+    #[test]
+    fn test_parse_v4_profile_with_chad() {
+        // lcms2 writes v4: a D50 `wtpt`, `mluc` text and a `chad` matrix
+        let data = lcms2::Profile::new_srgb().icc().unwrap();
+        let info = IccProfileInfo::parse(&data).unwrap();
+
+        assert!(info.description.unwrap().contains("sRGB"));
+        assert_xy_near(info.white_point.unwrap(), D65);
+        for (actual, expected) in info.primaries.unwrap().into_iter().zip(SRGB) {
+            assert_xy_near(actual, expected);
+        }
+    }
+
+    #[test]
+    fn test_read_text_types() {
+        assert_eq!(read_text(&desc_tag("Desc")).as_deref(), Some("Desc"));
+        assert_eq!(read_text(&text_tag("Text ")).as_deref(), Some("Text"));
+        assert_eq!(read_text(&mluc_tag("Mlüc")).as_deref(), Some("Mlüc"));
+        assert_eq!(read_text(&text_tag("")), None);
+        assert_eq!(read_text(b"XYZ \0\0\0\0"), None);
+    }
+
+    #[test]
+    fn test_parse_rejects_broken_profiles() {
+        assert!(IccProfileInfo::parse(&[0; 100]).is_err());
+
+        // Five tags declared, only one entry present
+        let mut data = profile_bytes(b"mntr", &[(*b"desc", desc_tag("Cut"))]);
+        data[128..132].copy_from_slice(&5u32.to_be_bytes());
+        data.truncate(132 + 12);
+        assert!(IccProfileInfo::parse(&data).is_err());
+
+        // A tag pointing past the end is left out rather than read
+        let mut data = profile_bytes(b"mntr", &[(*b"desc", desc_tag("Gone"))]);
+        data[136..140].copy_from_slice(&10_000u32.to_be_bytes());
+        let info = IccProfileInfo::parse(&data).unwrap();
+        assert_eq!(info.description, None);
+        assert_eq!(info.white_point, None);
+        assert_eq!(info.primaries, None);
+    }
+}
@@ -1,14 +1,32 @@
 pub mod config;
+pub mod coverage;
 pub mod dbus;
+pub mod gamma;
 pub mod hdr;
 pub mod icc;
+pub mod lut;
 pub mod monitor;
 pub mod ocio;
+pub mod print_target;
+pub mod profile_match;
+pub mod scopes;
+pub mod screenshot;
+pub mod transform;
+pub mod validation;
 
 pub use config::ColorConfig;
+pub use gamma::{GammaBackend, LoadedGamma};
 pub use hdr::HdrSupport;
-pub use icc::IccManager;
+pub use icc::{IccManager, IccProfileInfo};
+pub use lut::{Lut1d, Lut3d, LutFormat};
 pub use monitor::MonitorProfile;
+pub use ocio::OcioManager;
+pub use print_target::{PaperSize, PrintTarget};
+pub use profile_match::ColorWarning;
+pub use scopes::{ScopeOptions, Scopes};
+pub use screenshot::ColorManagedImage;
+pub use transform::{BuiltinSpace, ColorTransform, ProofOptions, SoftProof, TransformOptions};
+pub use validation::{IccIssue, IccReport};
 
 use thiserror::Error;
 
@@ -31,6 +49,21 @@ pub enum ColorError {
 
     #[error("Config error: {0}")]
     Config(String),
+
+    #[error("Screenshot failed: {0}")]
+    Capture(String),
+
+    #[error("Failed to load gamma ramp: {0}")]
+    Gamma(String),
+
+    #[error("No X11 or Wayland display to load a gamma ramp into")]
+    GammaUnavailable,
+
+    #[error("Invalid pixel buffer: {0}")]
+    Buffer(String),
+
+    #[error("Failed to write image: {0}")]
+    Export(String),
 }
 
 pub type Result<T> = std::result::Result<T, ColorError>;
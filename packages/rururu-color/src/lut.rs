@@ -0,0 +1,288 @@
+//! 1D and 3D LUTs sampled from a color transform.
+//!
+//! Calibrations are stored as ICC profiles, but NLEs and OCIO display
+//! transforms take LUTs. A transform is sampled over a regular grid on
+//! [0, 1] and written as a Resolve/Adobe `.cube` 3D LUT, or as a per-channel
+//! Sony Imageworks `.spi1d` or Cinespace `.csp` 1D LUT. `.cube` 3D LUTs
+//! with the default 0–1 domain can also be read back and applied to pixels.
+
+use crate::transform::{check_buffer, ColorTransform};
+use crate::{ColorError, Result};
+use std::fmt::Write;
+use std::path::Path;
+
+/// Grid size Resolve and most OCIO configs use for display LUTs
+pub const DEFAULT_3D_SIZE: usize = 33;
+pub const DEFAULT_1D_SIZE: usize = 1024;
+
+/// Largest `LUT_3D_SIZE` the `.cube` spec allows
+const MAX_3D_SIZE: usize = 256;
+const MAX_1D_SIZE: usize = 65536;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum LutFormat {
+    Cube,
+    Spi1d,
+    Csp,
+}
+
+impl LutFormat {
+    pub fn all() -> &'static [LutFormat] {
+        &[LutFormat::Cube, LutFormat::Spi1d, LutFormat::Csp]
+    }
+
+    pub fn extension(&self) -> &'static str {
+        match self {
+            LutFormat::Cube => "cube",
+            LutFormat::Spi1d => "spi1d",
+            LutFormat::Csp => "csp",
+        }
+    }
+
+    pub fn from_path(path: &Path) -> Option<Self> {
+        let ext = path.extension()?.to_str()?.to_lowercase();
+        Self::all().iter().copied().find(|f| f.extension() == ext)
+    }
+}
+
+/// Independent curve per channel
+#[derive(Debug, Clone, PartialEq)]
+pub struct Lut1d {
+    /// `size` entries of RGB output for evenly spaced inputs
+    pub table: Vec<[f32; 3]>,
+}
+
+impl Lut1d {
+    /// Sample `transform(input, channel)` (0 = red, 1 = green, 2 = blue) at
+    /// `size` points from 0 to 1
+    pub fn sample(size: usize, transform: impl Fn(f32, usize) -> f32) -> Result<Self> {
+        check_size(size, MAX_1D_SIZE)?;
+
+        let table = (0..size)
+            .map(|i| {
+                let v = i as f32 / (size - 1) as f32;
+                [transform(v, 0), transform(v, 1), transform(v, 2)]
+            })
+            .collect();
+        Ok(Self { table })
+    }
+
+    pub fn size(&self) -> usize {
+        self.table.len()
+    }
+
+    pub fn to_spi1d(&self) -> String {
+        let mut out = format!(
+            "Version 1\nFrom 0.0 1.0\nLength {}\nComponents 3\n{{\n",
+            self.size()
+        );
+        for [r, g, b] in &self.table {
+            let _ = writeln!(out, "    {:.6} {:.6} {:.6}", r, g, b);
+        }
+        out.push_str("}\n");
+        out
+    }
+
+    pub fn to_csp(&self, title: &str) -> String {
+        let mut out = String::from("CSPLUTV100\n1D\n\n");
+        let _ = write!(out, "BEGIN METADATA\n{}\nEND METADATA\n\n", title);
+
+        // Identity pre-LUT for each channel: two points mapping 0-1 to 0-1
+        for _ in 0..3 {
+            out.push_str("2\n0.0 1.0\n0.0 1.0\n");
+        }
+        let _ = write!(out, "\n{}\n", self.size());
+        for [r, g, b] in &self.table {
+            let _ = writeln!(out, "{:.6} {:.6} {:.6}", r, g, b);
+        }
+        out
+    }
+
+    /// Write as `.spi1d` or `.csp`, going by the extension
+    pub fn save(&self, path: &Path, title: &str) -> Result<()> {
+        let content = match LutFormat::from_path(path) {
+            Some(LutFormat::Spi1d) => self.to_spi1d(),
+            Some(LutFormat::Csp) => self.to_csp(title),
+            _ => return Err(unsupported(path, "1D")),
+        };
+        write_file(path, &content)
+    }
+}
+
+/// Full RGB-to-RGB transform on a cube grid
+#[derive(Debug, Clone, PartialEq)]
+pub struct Lut3d {
+    pub size: usize,
+    /// `size³` RGB entries, red changing fastest as in `.cube` files
+    pub table: Vec<[f32; 3]>,
+}
+
+impl Lut3d {
+    pub fn sample(size: usize, transform: impl Fn([f32; 3]) -> [f32; 3]) -> Result<Self> {
+        check_size(size, MAX_3D_SIZE)?;
+
+        let step = |i: usize| i as f32 / (size - 1) as f32;
+        let mut table = Vec::with_capacity(size * size * size);
+        for b in 0..size {
+            for g in 0..size {
+                for r in 0..size {
+                    table.push(transform([step(r), step(g), step(b)]));
+                }
+            }
+        }
+        Ok(Self { size, table })
+    }
+
+    /// Bake `transform`, rendering intent and all, for apps that take LUTs
+    /// rather than profiles
+    pub fn from_transform(size: usize, transform: &ColorTransform) -> Result<Self> {
+        Self::sample(size, |rgb| transform.apply_rgb(rgb))
+    }
+
+    pub fn to_cube(&self, title: &str) -> String {
+        // TITLE is a quoted string; drop quotes rather than escape them
+        let mut out = format!(
+            "TITLE \"{}\"\nLUT_3D_SIZE {}\nDOMAIN_MIN 0.0 0.0 0.0\nDOMAIN_MAX 1.0 1.0 1.0\n\n",
+            title.replace('"', ""),
+            self.size
+        );
+        for [r, g, b] in &self.table {
+            let _ = writeln!(out, "{:.6} {:.6} {:.6}", r, g, b);
+        }
+        out
+    }
+
+    /// Read a `.cube` 3D LUT
+    pub fn load(path: &Path) -> Result<Self> {
+        if LutFormat::from_path(path) != Some(LutFormat::Cube) {
+            return Err(unsupported(path, "3D"));
+        }
+        Self::parse_cube(&std::fs::read_to_string(path)?)
+    }
+
+    pub fn parse_cube(content: &str) -> Result<Self> {
+        let invalid =
+            |message: String| ColorError::Config(format!("Invalid .cube LUT: {}", message));
+
+        let mut size = None;
+        let mut table = Vec::new();
+        for line in content.lines().map(str::trim) {
+            if line.is_empty() || line.starts_with('#') {
+                continue;
+            }
+            let mut fields = line.split_whitespace();
+            let keyword = fields.next().unwrap_or_default();
+            let numbers = |fields: std::str::SplitWhitespace| -> Result<Vec<f32>> {
+                fields
+                    .map(|f| {
+                        f.parse()
+                            .map_err(|_| invalid(format!("{:?} is not a number", f)))
+                    })
+                    .collect()
+            };
+            match keyword {
+                "TITLE" => {}
+                "LUT_1D_SIZE" => return Err(invalid("this is a 1D LUT".into())),
+                "LUT_3D_SIZE" => {
+                    let n = fields.next().and_then(|n| n.parse().ok());
+                    size = Some(n.ok_or_else(|| invalid(format!("bad size in {:?}", line)))?);
+                }
+                "DOMAIN_MIN" | "DOMAIN_MAX" => {
+                    let expected = if keyword == "DOMAIN_MIN" { 0.0 } else { 1.0 };
+                    if numbers(fields)?.iter().any(|&v| v != expected) {
+                        return Err(invalid("only the 0–1 input domain is supported".into()));
+                    }
+                }
+                _ if keyword.starts_with(|c: char| c.is_ascii_alphabetic()) => {
+                    return Err(invalid(format!("unknown keyword {}", keyword)));
+                }
+                _ => match numbers(line.split_whitespace())?.as_slice() {
+                    &[r, g, b] => table.push([r, g, b]),
+                    _ => return Err(invalid(format!("expected three values in {:?}", line))),
+                },
+            }
+        }
+
+        let size = size.ok_or_else(|| invalid("no LUT_3D_SIZE".into()))?;
+        check_size(size, MAX_3D_SIZE)?;
+        if table.len() != size * size * size {
+            return Err(invalid(format!(
+                "{} entries for a {}³ grid",
+                table.len(),
+                size
+            )));
+        }
+        Ok(Self { size, table })
+    }
+
+    /// Look up `rgb` with trilinear interpolation, clamping it to the grid
+    pub fn apply(&self, rgb: [f32; 3]) -> [f32; 3] {
+        let max = (self.size - 1) as f32;
+        let mut base = [0; 3];
+        let mut frac = [0.0; 3];
+        for c in 0..3 {
+            let x = rgb[c].clamp(0.0, 1.0) * max;
+            base[c] = (x.floor() as usize).min(self.size - 2);
+            frac[c] = x - base[c] as f32;
+        }
+
+        let entry = |r: usize, g: usize, b: usize| {
+            self.table
+                [(base[2] + b) * self.size * self.size + (base[1] + g) * self.size + base[0] + r]
+        };
+        let mut out = [0.0; 3];
+        for corner in 0..8 {
+            let (r, g, b) = (corner & 1, (corner >> 1) & 1, (corner >> 2) & 1);
+            let weight = [r, g, b]
+                .iter()
+                .enumerate()
+                .map(|(c, &bit)| if bit == 1 { frac[c] } else { 1.0 - frac[c] })
+                .product::<f32>();
+            let value = entry(r, g, b);
+            for c in 0..3 {
+                out[c] += weight * value[c];
+            }
+        }
+        out
+    }
+
+    /// [`apply`](Lut3d::apply) to interleaved RGB or RGBA in place
+    pub fn apply_buffer(&self, pixels: &mut [f32], channels: usize) -> Result<()> {
+        check_buffer(pixels, channels)?;
+        for pixel in pixels.chunks_exact_mut(channels) {
+            let rgb = self.apply([pixel[0], pixel[1], pixel[2]]);
+            pixel[..3].copy_from_slice(&rgb);
+        }
+        Ok(())
+    }
+
+    pub fn save(&self, path: &Path, title: &str) -> Result<()> {
+        if LutFormat::from_path(path) != Some(LutFormat::Cube) {
+            return Err(unsupported(path, "3D"));
+        }
+        write_file(path, &self.to_cube(title))
+    }
+}
+
+fn check_size(size: usize, max: usize) -> Result<()> {
+    if (2..=max).contains(&size) {
+        Ok(())
+    } else {
+        Err(ColorError::Config(format!(
+            "LUT size {} is outside 2-{}",
+            size, max
+        )))
+    }
+}
+
+fn unsupported(path: &Path, kind: &str) -> ColorError {
+    ColorError::Config(format!("{:?} is not a supported {} LUT format", path, kind))
+}
+
+fn write_file(path: &Path, content: &str) -> Result<()> {
+    if let Some(parent) = path.parent() {
+        std::fs::create_dir_all(parent)?;
+    }
+    std::fs::write(path, content)?;
+    Ok(())
+}
@@ -1,8 +1,14 @@
 #![allow(dead_code)]
 
-use crate::{ColorError, Result};
+use crate::icc::IccProfileInfo;
+use crate::{gamma, validation, ColorError, Result};
+use lcms2::{Profile, Tag, TagSignature};
 use serde::{Deserialize, Serialize};
-use std::path::PathBuf;
+use std::fmt;
+use std::path::{Path, PathBuf};
+
+/// Model name `parse_edid` falls back to when the EDID has no name descriptor
+pub const UNNAMED_MODEL: &str = "Monitor";
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct MonitorProfile {
@@ -21,6 +27,27 @@ pub struct EdidInfo {
     pub year: u16,
     pub resolution: (u32, u32),
     pub physical_size_mm: Option<(u32, u32)>,
+    /// Hash of the raw EDID blob, `None` when the connector exposes none
+    #[serde(default)]
+    pub hash: Option<String>,
+    /// Panel primaries and white as reported by the manufacturer
+    #[serde(default)]
+    pub chromaticity: Option<Chromaticity>,
+}
+
+/// CIE xy coordinates from the EDID color characteristics block
+#[derive(Debug, Clone, Copy, Serialize, Deserialize, PartialEq)]
+pub struct Chromaticity {
+    pub red: (f64, f64),
+    pub green: (f64, f64),
+    pub blue: (f64, f64),
+    pub white: (f64, f64),
+}
+
+impl Chromaticity {
+    pub fn primaries(&self) -> [(f64, f64); 3] {
+        [self.red, self.green, self.blue]
+    }
 }
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
@@ -60,6 +87,75 @@ pub enum ColorGamut {
     Unknown,
 }
 
+/// Largest distance in xy a primary may be off and still count as the
+/// standard's; panel EDIDs are only this accurate
+const GAMUT_TOLERANCE: f64 = 0.04;
+
+/// Gamut area relative to sRGB above which a panel counts as wide-gamut
+const WIDE_GAMUT_AREA: f64 = 1.15;
+
+impl ColorGamut {
+    /// Red, green and blue primaries in CIE xy
+    pub fn primaries(self) -> Option<[(f64, f64); 3]> {
+        match self {
+            ColorGamut::Srgb => Some([(0.640, 0.330), (0.300, 0.600), (0.150, 0.060)]),
+            ColorGamut::AdobeRgb => Some([(0.640, 0.330), (0.210, 0.710), (0.150, 0.060)]),
+            ColorGamut::DciP3 => Some([(0.680, 0.320), (0.265, 0.690), (0.150, 0.060)]),
+            ColorGamut::Bt2020 => Some([(0.708, 0.292), (0.170, 0.797), (0.131, 0.046)]),
+            ColorGamut::Unknown => None,
+        }
+    }
+
+    /// The standard gamut `primaries` are closest to, if any is close enough
+    pub fn classify(primaries: &[(f64, f64); 3]) -> Self {
+        [
+            ColorGamut::Srgb,
+            ColorGamut::AdobeRgb,
+            ColorGamut::DciP3,
+            ColorGamut::Bt2020,
+        ]
+        .into_iter()
+        .filter_map(|gamut| Some((gamut, primaries_distance(primaries, &gamut.primaries()?))))
+        .filter(|(_, distance)| *distance <= GAMUT_TOLERANCE)
+        .min_by(|a, b| a.1.total_cmp(&b.1))
+        .map_or(ColorGamut::Unknown, |(gamut, _)| gamut)
+    }
+
+    /// Whether `primaries` cover noticeably more than sRGB
+    pub fn is_wide(primaries: &[(f64, f64); 3]) -> bool {
+        let srgb = ColorGamut::Srgb.primaries().unwrap_or_default();
+        triangle_area(primaries) > triangle_area(&srgb) * WIDE_GAMUT_AREA
+    }
+}
+
+impl fmt::Display for ColorGamut {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(match self {
+            ColorGamut::Srgb => "sRGB",
+            ColorGamut::AdobeRgb => "Adobe RGB",
+            ColorGamut::DciP3 => "DCI-P3",
+            ColorGamut::Bt2020 => "BT.2020",
+            ColorGamut::Unknown => "unknown gamut",
+        })
+    }
+}
+
+/// Worst xy distance between corresponding primaries
+pub(crate) fn primaries_distance(a: &[(f64, f64); 3], b: &[(f64, f64); 3]) -> f64 {
+    a.iter()
+        .zip(b)
+        .map(|(p, q)| xy_distance(*p, *q))
+        .fold(0.0, f64::max)
+}
+
+pub(crate) fn xy_distance(a: (f64, f64), b: (f64, f64)) -> f64 {
+    ((a.0 - b.0).powi(2) + (a.1 - b.1).powi(2)).sqrt()
+}
+
+pub(crate) fn triangle_area([r, g, b]: &[(f64, f64); 3]) -> f64 {
+    ((g.0 - r.0) * (b.1 - r.1) - (b.0 - r.0) * (g.1 - r.1)).abs() / 2.0
+}
+
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct CalibrationData {
     pub date: String,
@@ -71,6 +167,73 @@ pub struct CalibrationData {
     pub gamma_curve: Option<Vec<(f32, f32)>>,
 }
 
+impl CalibrationData {
+    /// What the display profile at `path` calibrates to: the white and tone
+    /// response it was measured for, its creation date and the `vcgt` curves
+    /// loaded into the gamma ramps, averaged over the three channels
+    pub fn from_profile(path: &Path) -> Result<Self> {
+        let data = std::fs::read(path)?;
+        let info = IccProfileInfo::parse(&data)?;
+        let profile = Profile::new_icc(&data).map_err(|e| ColorError::IccError(e.to_string()))?;
+
+        let white_point = info
+            .white_point
+            .map_or_else(WhitePoint::d65, |(x, y)| WhitePoint {
+                temperature: validation::mccamy_cct(x, y).round().max(0.0) as u32,
+                x: x as f32,
+                y: y as f32,
+            });
+
+        let gamma = match profile.read_tag(TagSignature::RedTRCTag) {
+            Tag::ToneCurve(curve) => curve.estimated_gamma(0.01),
+            _ => None,
+        };
+
+        let vcgt = gamma::profile_vcgt(&profile)?;
+        let last = vcgt
+            .as_ref()
+            .map_or(0, |ramp| ramp.size().saturating_sub(1));
+        let rgb_gains = vcgt
+            .as_ref()
+            .and_then(|ramp| ramp.table.last())
+            .map_or((1.0, 1.0, 1.0), |[r, g, b]| (*r, *g, *b));
+        let gamma_curve = vcgt.map(|ramp| {
+            ramp.table
+                .iter()
+                .enumerate()
+                .map(|(i, [r, g, b])| (i as f32 / last.max(1) as f32, (r + g + b) / 3.0))
+                .collect()
+        });
+
+        Ok(Self {
+            date: profile_date(&data),
+            white_point,
+            gamma: gamma.map_or(2.2, |g| g as f32),
+            brightness: 1.0,
+            contrast: 1.0,
+            rgb_gains,
+            gamma_curve,
+        })
+    }
+}
+
+/// The header's creation `dateTimeNumber`, empty when it isn't set
+fn profile_date(data: &[u8]) -> String {
+    let field = |i: usize| u16::from_be_bytes([data[24 + i * 2], data[25 + i * 2]]);
+    if field(0) == 0 {
+        return String::new();
+    }
+    format!(
+        "{:04}-{:02}-{:02} {:02}:{:02}:{:02}",
+        field(0),
+        field(1),
+        field(2),
+        field(3),
+        field(4),
+        field(5)
+    )
+}
+
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct WhitePoint {
     pub temperature: u32,
@@ -85,6 +248,10 @@ impl Default for WhitePoint {
 }
 
 impl WhitePoint {
+    pub fn xy(&self) -> (f64, f64) {
+        (self.x as f64, self.y as f64)
+    }
+
     pub fn d65() -> Self {
         Self {
             temperature: 6500,
@@ -110,6 +277,73 @@ impl WhitePoint {
     }
 }
 
+impl MonitorProfile {
+    /// Persist this monitor's profile so it is found again by EDID,
+    /// whichever connector it is plugged into.
+    pub fn save(&self) -> Result<()> {
+        let Some(hash) = self.edid.hash.as_ref() else {
+            return Err(ColorError::Config(format!(
+                "{} has no EDID to key on",
+                self.name
+            )));
+        };
+
+        let dir = monitors_dir();
+        std::fs::create_dir_all(&dir)?;
+
+        let content =
+            serde_json::to_string_pretty(self).map_err(|e| ColorError::Config(e.to_string()))?;
+        std::fs::write(dir.join(format!("{}.json", hash)), content)?;
+        Ok(())
+    }
+
+    /// Every saved monitor profile; unreadable files are skipped
+    pub fn load_all() -> Result<Vec<MonitorProfile>> {
+        let dir = monitors_dir();
+        if !dir.exists() {
+            return Ok(Vec::new());
+        }
+
+        let mut profiles = Vec::new();
+        for entry in std::fs::read_dir(&dir)?.flatten() {
+            let path = entry.path();
+            if path.extension().and_then(|e| e.to_str()) != Some("json") {
+                continue;
+            }
+
+            let profile = std::fs::read_to_string(&path)
+                .ok()
+                .and_then(|content| serde_json::from_str::<MonitorProfile>(&content).ok());
+            match profile {
+                Some(profile) if profile.edid.hash.is_some() => profiles.push(profile),
+                _ => tracing::warn!("Skipping unreadable monitor profile {:?}", path),
+            }
+        }
+
+        Ok(profiles)
+    }
+
+    /// Take calibration and ICC assignment from a saved profile of the same
+    /// physical monitor.
+    fn restore_from(&mut self, saved: &[MonitorProfile]) {
+        let Some(hash) = self.edid.hash.as_ref() else {
+            return;
+        };
+
+        if let Some(saved) = saved.iter().find(|p| p.edid.hash.as_ref() == Some(hash)) {
+            self.calibration = saved.calibration.clone();
+            self.icc_profile = saved.icc_profile.clone();
+        }
+    }
+}
+
+fn monitors_dir() -> PathBuf {
+    dirs::config_dir()
+        .unwrap_or_else(|| PathBuf::from("."))
+        .join("rururu")
+        .join("monitors")
+}
+
 pub fn detect_monitors() -> Result<Vec<MonitorProfile>> {
     let mut monitors = Vec::new();
 
@@ -143,10 +377,17 @@ pub fn detect_monitors() -> Result<Vec<MonitorProfile>> {
                         default_edid(&name)
                     };
 
+                    let mut capabilities = detect_capabilities(&entry.path());
+                    if let Some(chromaticity) = &edid.chromaticity {
+                        let primaries = chromaticity.primaries();
+                        capabilities.color_gamut = ColorGamut::classify(&primaries);
+                        capabilities.wide_gamut = ColorGamut::is_wide(&primaries);
+                    }
+
                     monitors.push(MonitorProfile {
                         name: name.clone(),
                         edid,
-                        capabilities: detect_capabilities(&entry.path()),
+                        capabilities,
                         calibration: None,
                         icc_profile: None,
                     });
@@ -166,6 +407,8 @@ pub fn detect_monitors() -> Result<Vec<MonitorProfile>> {
                 year: 2024,
                 resolution: (1920, 1080),
                 physical_size_mm: None,
+                hash: None,
+                chromaticity: None,
             },
             capabilities: MonitorCapabilities {
                 color_depth: ColorDepth::Bit8,
@@ -181,12 +424,30 @@ pub fn detect_monitors() -> Result<Vec<MonitorProfile>> {
         });
     }
 
+    let saved = MonitorProfile::load_all().unwrap_or_default();
+    for monitor in &mut monitors {
+        monitor.restore_from(&saved);
+    }
+
     Ok(monitors)
 }
 
-fn parse_edid(path: &std::path::Path) -> Result<EdidInfo> {
-    let data = std::fs::read(path)?;
+/// FNV-1a over the whole EDID blob; the serial number it contains keeps two
+/// panels of the same model apart. Stable across builds, unlike `std` hashers.
+fn edid_hash(data: &[u8]) -> String {
+    let mut hash: u64 = 0xcbf2_9ce4_8422_2325;
+    for byte in data {
+        hash ^= *byte as u64;
+        hash = hash.wrapping_mul(0x0000_0100_0000_01b3);
+    }
+    format!("{:016x}", hash)
+}
 
+fn parse_edid(path: &Path) -> Result<EdidInfo> {
+    parse_edid_data(&std::fs::read(path)?)
+}
+
+pub(crate) fn parse_edid_data(data: &[u8]) -> Result<EdidInfo> {
     if data.len() < 128 {
         return Err(ColorError::IccError("EDID too small".to_string()));
     }
@@ -205,10 +466,27 @@ fn parse_edid(path: &std::path::Path) -> Result<EdidInfo> {
     let h_size = ((data[68] as u32 & 0xF0) << 4) | data[66] as u32;
     let v_size = ((data[68] as u32 & 0x0F) << 8) | data[67] as u32;
 
+    // Four 18-byte descriptors follow the first detailed timing; display
+    // descriptors start with three zero bytes and a tag
+    let descriptor = |tag: u8| {
+        data[54..126].chunks_exact(18).find_map(|block| {
+            if block[..3] != [0, 0, 0] || block[3] != tag {
+                return None;
+            }
+            let text: String = block[5..]
+                .iter()
+                .take_while(|b| **b != 0x0A)
+                .map(|b| *b as char)
+                .collect();
+            let text = text.trim().to_string();
+            (!text.is_empty()).then_some(text)
+        })
+    };
+
     Ok(EdidInfo {
         manufacturer,
-        model: "Monitor".to_string(),
-        serial: None,
+        model: descriptor(0xFC).unwrap_or_else(|| UNNAMED_MODEL.to_string()),
+        serial: descriptor(0xFF),
         year,
         resolution: (h_active.max(1920), v_active.max(1080)),
         physical_size_mm: if h_size > 0 && v_size > 0 {
@@ -216,9 +494,37 @@ fn parse_edid(path: &std::path::Path) -> Result<EdidInfo> {
         } else {
             None
         },
+        hash: Some(edid_hash(data)),
+        chromaticity: parse_chromaticity(data),
     })
 }
 
+/// Bytes 25-34: the top eight bits of each 10-bit coordinate, with the two
+/// low bits of all eight packed into the first two bytes
+fn parse_chromaticity(data: &[u8]) -> Option<Chromaticity> {
+    let low = |byte: usize, shift: u32| ((data[byte] >> shift) & 0x03) as u16;
+    let coord = |high: usize, low_bits: u16| ((data[high] as u16) << 2 | low_bits) as f64 / 1024.0;
+
+    let chromaticity = Chromaticity {
+        red: (coord(27, low(25, 6)), coord(28, low(25, 4))),
+        green: (coord(29, low(25, 2)), coord(30, low(25, 0))),
+        blue: (coord(31, low(26, 6)), coord(32, low(26, 4))),
+        white: (coord(33, low(26, 2)), coord(34, low(26, 0))),
+    };
+
+    // Some panels leave the block zeroed
+    let points = [
+        chromaticity.red,
+        chromaticity.green,
+        chromaticity.blue,
+        chromaticity.white,
+    ];
+    points
+        .iter()
+        .all(|(x, y)| *x > 0.0 && *y > 0.0)
+        .then_some(chromaticity)
+}
+
 fn decode_manufacturer_id(id: u16) -> String {
     let c1 = ((id >> 10) & 0x1F) as u8 + b'A' - 1;
     let c2 = ((id >> 5) & 0x1F) as u8 + b'A' - 1;
@@ -235,10 +541,12 @@ fn default_edid(name: &str) -> EdidInfo {
         year: 2024,
         resolution: (1920, 1080),
         physical_size_mm: None,
+        hash: None,
+        chromaticity: None,
     }
 }
 
-fn detect_capabilities(_path: &std::path::Path) -> MonitorCapabilities {
+fn detect_capabilities(_path: &Path) -> MonitorCapabilities {
     // Default capabilities - would need deeper inspection for accurate values
     MonitorCapabilities {
         color_depth: ColorDepth::Bit8,
@@ -250,3 +558,70 @@ fn detect_capabilities(_path: &std::path::Path) -> MonitorCapabilities {
         color_gamut: ColorGamut::Srgb,
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn monitor(name: &str, hash: Option<&str>) -> MonitorProfile {
+        let mut edid = default_edid(name);
+        edid.hash = hash.map(str::to_string);
+        MonitorProfile {
+            name: name.to_string(),
+            edid,
+            capabilities: detect_capabilities(Path::new(name)),
+            calibration: None,
+            icc_profile: None,
+        }
+    }
+
+    #[test]
+    fn test_calibration_from_profile() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("srgb.icc");
+        std::fs::write(&path, Profile::new_srgb().icc().unwrap()).unwrap();
+
+        let calibration = CalibrationData::from_profile(&path).unwrap();
+        assert!((calibration.white_point.x - 0.3127).abs() < 0.001);
+        assert!((calibration.white_point.y - 0.3290).abs() < 0.001);
+        assert!(calibration.white_point.temperature.abs_diff(6500) < 50);
+        assert!((calibration.gamma - 2.2).abs() < 0.1);
+        // No `vcgt`, so nothing was loaded into the ramps
+        assert_eq!(calibration.rgb_gains, (1.0, 1.0, 1.0));
+        assert!(calibration.gamma_curve.is_none());
+    }
+
+    #[test]
+    fn test_calibration_survives_a_connector_change() {
+        let mut applied = monitor("card0-DP-1", Some("0123456789abcdef"));
+        applied.icc_profile = Some(PathBuf::from("/profiles/dell.icc"));
+        applied.calibration = Some(CalibrationData {
+            date: "2026-10-01 12:00:00".to_string(),
+            white_point: WhitePoint::d50(),
+            gamma: 2.4,
+            brightness: 1.0,
+            contrast: 1.0,
+            rgb_gains: (1.0, 0.98, 0.95),
+            gamma_curve: Some(vec![(0.0, 0.0), (1.0, 0.95)]),
+        });
+        let json = serde_json::to_string(&applied).unwrap();
+        let saved = [serde_json::from_str::<MonitorProfile>(&json).unwrap()];
+
+        let mut replugged = monitor("card0-HDMI-A-1", Some("0123456789abcdef"));
+        replugged.restore_from(&saved);
+        let calibration = replugged.calibration.unwrap();
+        assert_eq!(calibration.white_point.temperature, 5000);
+        assert_eq!(calibration.gamma, 2.4);
+        assert_eq!(calibration.gamma_curve, Some(vec![(0.0, 0.0), (1.0, 0.95)]));
+        assert_eq!(replugged.icc_profile, applied.icc_profile);
+
+        let mut other = monitor("card0-DP-2", Some("fedcba9876543210"));
+        other.restore_from(&saved);
+        assert!(other.calibration.is_none());
+
+        // Without an EDID there's nothing to match on
+        let mut unknown = monitor("card0-DP-1", None);
+        unknown.restore_from(&saved);
+        assert!(unknown.calibration.is_none());
+    }
+}
@@ -1,6 +1,53 @@
+use crate::transform::{self, BuiltinSpace, TransformOptions};
 use crate::{ColorError, Result};
 use std::path::{Path, PathBuf};
 
+/// Common OCIO color space names, lowercased to letters and digits with
+/// family prefixes and encoding suffixes removed (see [`name_key`]), and the
+/// built-in space each matches. `false` marks names whose transfer function
+/// or white point differs from the built-in one.
+const KNOWN_SPACES: &[(&str, BuiltinSpace, bool)] = &[
+    ("srgb", BuiltinSpace::Srgb, true),
+    ("srgbtexture", BuiltinSpace::Srgb, true),
+    ("srgbencodedrec709srgb", BuiltinSpace::Srgb, true),
+    ("linear", BuiltinSpace::LinearSrgb, true),
+    ("linearsrgb", BuiltinSpace::LinearSrgb, true),
+    ("linsrgb", BuiltinSpace::LinearSrgb, true),
+    ("linrec709", BuiltinSpace::LinearSrgb, true),
+    ("linearrec709", BuiltinSpace::LinearSrgb, true),
+    ("linearrec709srgb", BuiltinSpace::LinearSrgb, true),
+    ("srgblinear", BuiltinSpace::LinearSrgb, true),
+    ("rec709", BuiltinSpace::Rec709, true),
+    ("bt709", BuiltinSpace::Rec709, true),
+    ("rec1886rec709", BuiltinSpace::Rec709, false),
+    ("gamma24rec709", BuiltinSpace::Rec709, false),
+    ("acescg", BuiltinSpace::AcesCg, true),
+    ("linearacescg", BuiltinSpace::AcesCg, true),
+    ("linap1", BuiltinSpace::AcesCg, true),
+    ("aces20651", BuiltinSpace::Aces2065_1, true),
+    ("aces", BuiltinSpace::Aces2065_1, true),
+    ("linearaces", BuiltinSpace::Aces2065_1, true),
+    ("linap0", BuiltinSpace::Aces2065_1, true),
+    ("acesinterchange", BuiltinSpace::Aces2065_1, true),
+    ("displayp3", BuiltinSpace::DisplayP3, true),
+    ("p3d65", BuiltinSpace::DisplayP3, false),
+    ("dcip3", BuiltinSpace::DisplayP3, false),
+    ("p3dci", BuiltinSpace::DisplayP3, false),
+    ("adobergb", BuiltinSpace::AdobeRgb, true),
+    ("adobergb1998", BuiltinSpace::AdobeRgb, true),
+    ("rec2020", BuiltinSpace::Rec2020, true),
+    ("bt2020", BuiltinSpace::Rec2020, true),
+];
+
+/// Leading parts of names like "Utility - Linear - sRGB", which group
+/// spaces in a config without changing what they are
+const FAMILY_PREFIXES: &[&str] = &["utility", "input", "output", "role", "generic", "aces"];
+
+/// Trailing parts that name the encoding's use. "display" spaces and
+/// "output" families are display-referred and reached through a view
+/// transform, which has no built-in equivalent.
+const USE_SUFFIXES: &[&str] = &["texture", "camera", "display"];
+
 #[derive(Debug, Clone)]
 pub struct OcioManager {
     config_path: Option<PathBuf>,
@@ -187,6 +234,88 @@ impl OcioManager {
             .and_then(|c| c.roles.scene_linear.as_deref())
     }
 
+    /// The built-in space closest to OCIO color space or role `name`, so
+    /// [`transform::transform_buffer`] can stand in for OpenColorIO.
+    ///
+    /// Roles resolve through the loaded config. Data spaces and names with
+    /// no built-in equivalent (log encodings, camera gamuts) give `None`.
+    /// Matches that leave out a view transform, a transfer function or a
+    /// white point are logged.
+    pub fn resolve_to_colorspace(&self, name: &str) -> Option<BuiltinSpace> {
+        if self.is_data(name) {
+            return None;
+        }
+        let name = self.role(name).unwrap_or(name);
+
+        let (key, display_referred) = name_key(name);
+        let &(_, space, exact) = KNOWN_SPACES.iter().find(|(known, _, _)| *known == key)?;
+        if display_referred {
+            tracing::info!(
+                "Approximating OCIO color space {:?} with {} without its view transform",
+                name,
+                space.name()
+            );
+        } else if !exact {
+            tracing::info!(
+                "Approximating OCIO color space {:?} with {}, which has a different transfer function or white",
+                name,
+                space.name()
+            );
+        }
+        Some(space)
+    }
+
+    /// Convert `pixels` between OCIO color spaces or roles with the closest
+    /// built-in spaces. Data spaces are left untouched, as OpenColorIO
+    /// leaves them.
+    pub fn transform_buffer(
+        &self,
+        pixels: &mut [f32],
+        channels: usize,
+        from: &str,
+        to: &str,
+        options: impl Into<TransformOptions>,
+    ) -> Result<()> {
+        if self.is_data(from) || self.is_data(to) {
+            return Ok(());
+        }
+        let resolve = |name: &str| {
+            self.resolve_to_colorspace(name).ok_or_else(|| {
+                ColorError::OcioError(format!("No built-in equivalent of color space {:?}", name))
+            })
+        };
+        let (source, destination) = (resolve(from)?, resolve(to)?);
+        tracing::warn!(
+            "OpenColorIO isn't available; approximating {:?} to {:?} with {} to {}",
+            from,
+            to,
+            source.name(),
+            destination.name()
+        );
+        transform::transform_buffer(pixels, channels, source.name(), destination.name(), options)
+    }
+
+    /// The color space the loaded config assigns role `name`
+    fn role(&self, name: &str) -> Option<&str> {
+        let roles = &self.config.as_ref()?.roles;
+        match name {
+            "default" => roles.default.as_deref(),
+            "reference" => roles.reference.as_deref(),
+            "scene_linear" => roles.scene_linear.as_deref(),
+            _ => None,
+        }
+    }
+
+    fn is_data(&self, name: &str) -> bool {
+        let name = self.role(name).unwrap_or(name);
+        let flagged = self
+            .config
+            .as_ref()
+            .and_then(|c| c.color_spaces.iter().find(|cs| cs.name == name))
+            .is_some_and(|cs| cs.is_data);
+        flagged || ["raw", "noncolor", "data"].contains(&name_key(name).0.as_str())
+    }
+
     pub fn unload_config(&mut self) {
         self.config = None;
         self.config_path = None;
@@ -200,6 +329,30 @@ impl Default for OcioManager {
     }
 }
 
+/// `name` reduced for lookup in [`KNOWN_SPACES`], and whether it names a
+/// display-referred space: "Output - Rec.709" is ("rec709", true),
+/// "Utility - Linear - sRGB" is ("linearsrgb", false)
+fn name_key(name: &str) -> (String, bool) {
+    let lower = name.to_lowercase();
+    let mut parts: Vec<&str> = lower.split(" - ").map(str::trim).collect();
+    let mut display_referred = false;
+
+    while parts.len() > 1 && FAMILY_PREFIXES.contains(&parts[0]) {
+        display_referred |= parts[0] == "output";
+        parts.remove(0);
+    }
+    while parts.len() > 1 && USE_SUFFIXES.contains(&parts[parts.len() - 1]) {
+        display_referred |= parts.pop() == Some("display");
+    }
+
+    let key = parts
+        .concat()
+        .chars()
+        .filter(char::is_ascii_alphanumeric)
+        .collect();
+    (key, display_referred)
+}
+
 pub fn find_ocio_configs() -> Vec<PathBuf> {
     let mut configs = Vec::new();
 
@@ -280,3 +433,118 @@ pub fn builtin_presets() -> Vec<OcioPreset> {
         },
     ]
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const CONFIG: &str = "\
+ocio_profile_version: 1
+description: Test config
+roles:
+  default: Utility - sRGB - Texture
+  reference: Utility - Raw
+  scene_linear: ACES - ACEScg
+colorspaces:
+  - !<ColorSpace>
+    name: ACES - ACEScg
+    family: ACES
+  - !<ColorSpace>
+    name: Utility - Raw
+    family: Utility
+    isdata: true
+  - !<ColorSpace>
+    name: Utility - sRGB - Texture
+    family: Utility
+";
+
+    fn manager() -> OcioManager {
+        let path = Path::new("/test/config.ocio");
+        OcioManager {
+            config_path: Some(path.to_path_buf()),
+            config: Some(OcioManager::parse_config(CONFIG, path).unwrap()),
+        }
+    }
+
+    #[test]
+    fn test_name_key() {
+        assert_eq!(name_key("ACES - ACEScg"), ("acescg".to_string(), false));
+        assert_eq!(
+            name_key("Utility - Linear - sRGB"),
+            ("linearsrgb".to_string(), false)
+        );
+        assert_eq!(name_key("Output - Rec.709"), ("rec709".to_string(), true));
+        assert_eq!(name_key("sRGB - Display"), ("srgb".to_string(), true));
+        assert_eq!(
+            name_key("Utility - sRGB - Texture"),
+            ("srgb".to_string(), false)
+        );
+        // A family name on its own is a name, not a prefix
+        assert_eq!(name_key("ACES"), ("aces".to_string(), false));
+        assert_eq!(name_key("ACES2065-1"), ("aces20651".to_string(), false));
+    }
+
+    #[test]
+    fn test_known_spaces_resolve() {
+        let ocio = OcioManager::new();
+        for (name, space) in [
+            ("ACES - ACEScg", BuiltinSpace::AcesCg),
+            ("ACEScg", BuiltinSpace::AcesCg),
+            ("ACES - ACES2065-1", BuiltinSpace::Aces2065_1),
+            ("Utility - Linear - sRGB", BuiltinSpace::LinearSrgb),
+            ("lin_rec709", BuiltinSpace::LinearSrgb),
+            ("sRGB - Texture", BuiltinSpace::Srgb),
+            ("Output - Rec.709", BuiltinSpace::Rec709),
+            ("Utility - Rec.2020 - Camera", BuiltinSpace::Rec2020),
+            ("Display P3", BuiltinSpace::DisplayP3),
+        ] {
+            assert_eq!(ocio.resolve_to_colorspace(name), Some(space), "{}", name);
+        }
+        assert_eq!(ocio.resolve_to_colorspace("Input - ARRI - LogC"), None);
+        assert_eq!(ocio.resolve_to_colorspace("ACEScct"), None);
+
+        // Every entry is reachable under its own key
+        for &(key, space, _) in KNOWN_SPACES {
+            assert_eq!(name_key(key).0, key);
+            assert_eq!(ocio.resolve_to_colorspace(key), Some(space));
+        }
+    }
+
+    #[test]
+    fn test_roles_and_data_spaces() {
+        let ocio = manager();
+        assert_eq!(
+            ocio.resolve_to_colorspace("scene_linear"),
+            Some(BuiltinSpace::AcesCg)
+        );
+        assert_eq!(
+            ocio.resolve_to_colorspace("default"),
+            Some(BuiltinSpace::Srgb)
+        );
+        // The reference role is a data space here, flagged in the config
+        assert_eq!(ocio.resolve_to_colorspace("reference"), None);
+        assert_eq!(ocio.resolve_to_colorspace("Utility - Raw"), None);
+        assert_eq!(ocio.resolve_to_colorspace("Non-Color"), None);
+
+        // Data spaces are left untouched
+        let mut pixels = vec![0.25, 0.5, 0.75];
+        ocio.transform_buffer(
+            &mut pixels,
+            3,
+            "Utility - Raw",
+            "sRGB",
+            TransformOptions::default(),
+        )
+        .unwrap();
+        assert_eq!(pixels, [0.25, 0.5, 0.75]);
+        assert!(ocio
+            .transform_buffer(
+                &mut pixels,
+                3,
+                "Input - ARRI - LogC",
+                "sRGB",
+                TransformOptions::default()
+            )
+            .is_err());
+    }
+}
@@ -0,0 +1,328 @@
+//! Printable validation targets.
+//!
+//! A target is printed through the same profile the soft proof uses and
+//! held next to the screen to compare them. It is laid out in landscape on
+//! the chosen paper, with rows of patches from top to bottom:
+//!
+//! 1. A grayscale ramp in 5% steps, for neutrality and shadow detail
+//! 2. Red, green, blue, cyan, magenta and yellow at full strength, then
+//!    white and black
+//! 3. Skin tones from pale to deep, defined in sRGB so they are the same
+//!    colors whatever the working space
+//! 4. Gamut markers: each primary and secondary at 25, 50, 75 and 100%
+//!    saturation, one row per step. Steps the printer can't tell apart show
+//!    where its gamut ends.
+//!
+//! The target is written as a 16-bit TIFF in the working space, with that
+//! space's profile embedded and the resolution set so it prints at size.
+
+use crate::config::RenderingIntent;
+use crate::transform::{self, TransformOptions};
+use crate::{ColorError, Result};
+use std::fmt;
+use std::fs::File;
+use std::io::BufWriter;
+use std::path::Path;
+use tiff::encoder::{colortype, Compression, Rational, TiffEncoder};
+use tiff::tags::{ResolutionUnit, Tag};
+
+/// Unprinted border, for printers that can't print to the edge
+const MARGIN_MM: f32 = 10.0;
+/// Space between patches and between rows
+const GUTTER_MM: f32 = 1.5;
+
+/// Built-in spaces worth printing a target in; linear sRGB is left out
+/// since prints aren't edited in it
+pub const WORKING_SPACES: &[&str] = &["srgb", "display-p3", "adobe-rgb", "rec2020"];
+
+/// Skin tones in 8-bit sRGB, pale to deep
+const SKIN_TONES: [[u8; 3]; 6] = [
+    [246, 219, 196],
+    [232, 190, 160],
+    [194, 150, 130],
+    [165, 114, 86],
+    [115, 82, 68],
+    [74, 50, 40],
+];
+
+/// Saturation of each gamut marker row
+const GAMUT_STEPS: [f32; 4] = [0.25, 0.5, 0.75, 1.0];
+
+/// Primaries and secondaries, in the order each row lists them
+const HUES: [[f32; 3]; 6] = [
+    [1.0, 0.0, 0.0],
+    [0.0, 1.0, 0.0],
+    [0.0, 0.0, 1.0],
+    [0.0, 1.0, 1.0],
+    [1.0, 0.0, 1.0],
+    [1.0, 1.0, 0.0],
+];
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum PaperSize {
+    #[default]
+    A4,
+    A3,
+    /// Super A3, 329 × 483 mm
+    A3Plus,
+    Letter,
+    /// 4 × 6 in photo paper
+    Photo4x6,
+    /// 5 × 7 in photo paper
+    Photo5x7,
+}
+
+impl PaperSize {
+    pub const ALL: [PaperSize; 6] = [
+        PaperSize::A4,
+        PaperSize::A3,
+        PaperSize::A3Plus,
+        PaperSize::Letter,
+        PaperSize::Photo4x6,
+        PaperSize::Photo5x7,
+    ];
+
+    pub fn name(&self) -> &'static str {
+        match self {
+            PaperSize::A4 => "A4",
+            PaperSize::A3 => "A3",
+            PaperSize::A3Plus => "A3+",
+            PaperSize::Letter => "Letter",
+            PaperSize::Photo4x6 => "4×6 in",
+            PaperSize::Photo5x7 => "5×7 in",
+        }
+    }
+
+    /// Width and height in millimeters, portrait
+    pub fn dimensions_mm(&self) -> (f32, f32) {
+        match self {
+            PaperSize::A4 => (210.0, 297.0),
+            PaperSize::A3 => (297.0, 420.0),
+            PaperSize::A3Plus => (329.0, 483.0),
+            PaperSize::Letter => (215.9, 279.4),
+            PaperSize::Photo4x6 => (101.6, 152.4),
+            PaperSize::Photo5x7 => (127.0, 177.8),
+        }
+    }
+}
+
+impl fmt::Display for PaperSize {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(self.name())
+    }
+}
+
+/// A validation target for one paper size and working space
+#[derive(Debug, Clone)]
+pub struct PrintTarget {
+    pub paper: PaperSize,
+    pub dpi: u32,
+    /// A built-in space, e.g. one of [`WORKING_SPACES`], or an RGB ICC
+    /// profile on disk
+    pub working_space: String,
+    /// How sRGB patches are converted into the working space
+    pub options: TransformOptions,
+}
+
+/// The chart as rendered: 16-bit interleaved RGB in the working space
+pub struct PrintTargetImage {
+    pub width: u32,
+    pub height: u32,
+    pub pixels: Vec<u16>,
+    pub dpi: u32,
+    /// ICC profile of the working space
+    pub icc_profile: Vec<u8>,
+}
+
+impl PrintTarget {
+    pub fn new(paper: PaperSize, working_space: &str) -> Self {
+        Self {
+            paper,
+            dpi: 300,
+            working_space: working_space.to_string(),
+            options: RenderingIntent::RelativeColorimetric.into(),
+        }
+    }
+
+    /// Rows of patch colors, top to bottom, in the working space
+    fn rows(&self) -> Result<Vec<Vec<[f32; 3]>>> {
+        let ramp = (0..=20).map(|i| [i as f32 / 20.0; 3]).collect();
+
+        let mut full: Vec<[f32; 3]> = HUES.to_vec();
+        full.extend([[1.0; 3], [0.0; 3]]);
+
+        let mut skin: Vec<f32> = SKIN_TONES
+            .iter()
+            .flatten()
+            .map(|&v| v as f32 / 255.0)
+            .collect();
+        transform::transform_buffer(&mut skin, 3, "srgb", &self.working_space, self.options)?;
+        let skin = skin.chunks_exact(3).map(|p| [p[0], p[1], p[2]]).collect();
+
+        let mut rows = vec![ramp, full, skin];
+        rows.extend(GAMUT_STEPS.iter().map(|&s| {
+            HUES.iter()
+                .map(|hue| hue.map(|c| 1.0 - s + s * c))
+                .collect()
+        }));
+        Ok(rows)
+    }
+
+    pub fn render(&self) -> Result<PrintTargetImage> {
+        if !(72..=1200).contains(&self.dpi) {
+            return Err(ColorError::Config(format!(
+                "{} dpi is outside 72–1200",
+                self.dpi
+            )));
+        }
+
+        let icc_profile = transform::space_icc(&self.working_space)?;
+        let rows = self.rows()?;
+
+        let (short, long) = self.paper.dimensions_mm();
+        let px = |mm: f32| (mm / 25.4 * self.dpi as f32).round() as u32;
+        let (width, height) = (px(long), px(short));
+        let mut pixels = vec![u16::MAX; width as usize * height as usize * 3];
+
+        let chart_w = long - 2.0 * MARGIN_MM;
+        let row_h =
+            (short - 2.0 * MARGIN_MM - GUTTER_MM * (rows.len() - 1) as f32) / rows.len() as f32;
+
+        for (r, patches) in rows.iter().enumerate() {
+            let top = MARGIN_MM + r as f32 * (row_h + GUTTER_MM);
+            let patch_w = (chart_w - GUTTER_MM * (patches.len() - 1) as f32) / patches.len() as f32;
+
+            for (p, color) in patches.iter().enumerate() {
+                let left = MARGIN_MM + p as f32 * (patch_w + GUTTER_MM);
+                let value = color.map(|c| (c.clamp(0.0, 1.0) * u16::MAX as f32).round() as u16);
+
+                for y in px(top)..px(top + row_h).min(height) {
+                    let start = (y * width + px(left)) as usize * 3;
+                    let end = (y * width + px(left + patch_w).min(width)) as usize * 3;
+                    for pixel in pixels[start..end].chunks_exact_mut(3) {
+                        pixel.copy_from_slice(&value);
+                    }
+                }
+            }
+        }
+
+        Ok(PrintTargetImage {
+            width,
+            height,
+            pixels,
+            dpi: self.dpi,
+            icc_profile,
+        })
+    }
+
+    /// Render and write the target to `path`
+    pub fn save_tiff(&self, path: &Path) -> Result<()> {
+        self.render()?.save_tiff(path)
+    }
+}
+
+impl PrintTargetImage {
+    pub fn save_tiff(&self, path: &Path) -> Result<()> {
+        let tiff_error = |e: tiff::TiffError| ColorError::Export(e.to_string());
+
+        let file = BufWriter::new(File::create(path)?);
+        let mut encoder = TiffEncoder::new(file)
+            .map_err(tiff_error)?
+            .with_compression(Compression::Lzw);
+        let mut image = encoder
+            .new_image::<colortype::RGB16>(self.width, self.height)
+            .map_err(tiff_error)?;
+        image.resolution(ResolutionUnit::Inch, Rational { n: self.dpi, d: 1 });
+        image
+            .encoder()
+            .write_tag(Tag::IccProfile, self.icc_profile.as_slice())
+            .map_err(tiff_error)?;
+        image.write_data(&self.pixels).map_err(tiff_error)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn render(paper: PaperSize, dpi: u32) -> PrintTargetImage {
+        let mut target = PrintTarget::new(paper, "srgb");
+        target.dpi = dpi;
+        target.render().unwrap()
+    }
+
+    fn pixel(image: &PrintTargetImage, x: u32, y: u32) -> [u16; 3] {
+        let at = (y * image.width + x) as usize * 3;
+        [image.pixels[at], image.pixels[at + 1], image.pixels[at + 2]]
+    }
+
+    #[test]
+    fn test_page_is_landscape_at_size() {
+        let a4 = render(PaperSize::A4, 300);
+        assert_eq!((a4.width, a4.height), (3508, 2480));
+        assert_eq!(a4.pixels.len(), 3508 * 2480 * 3);
+
+        let photo = render(PaperSize::Photo4x6, 300);
+        assert_eq!((photo.width, photo.height), (1800, 1200));
+    }
+
+    #[test]
+    fn test_margins_stay_blank() {
+        let image = render(PaperSize::A4, 72);
+        // 10 mm at 72 dpi
+        let margin = 28;
+        for y in 0..image.height {
+            for x in 0..image.width {
+                let inside = (margin..image.width - margin).contains(&x)
+                    && (margin..image.height - margin).contains(&y);
+                if !inside {
+                    assert_eq!(pixel(&image, x, y), [u16::MAX; 3], "({}, {})", x, y);
+                }
+            }
+        }
+    }
+
+    #[test]
+    fn test_patches_and_gutters() {
+        let dpi = 300;
+        let image = render(PaperSize::A4, dpi);
+        let px = |mm: f32| (mm / 25.4 * dpi as f32).round() as u32;
+
+        // Seven rows: ramp, full colors, skin tones and four gamut steps
+        let row_h = (210.0 - 2.0 * MARGIN_MM - 6.0 * GUTTER_MM) / 7.0;
+        let row_middle =
+            |row: usize| px(MARGIN_MM + row as f32 * (row_h + GUTTER_MM) + row_h / 2.0);
+
+        // The ramp's 21 patches start at black and rise in 5% steps
+        let patch_w = (297.0 - 2.0 * MARGIN_MM - 20.0 * GUTTER_MM) / 21.0;
+        let patch_middle =
+            |p: usize| px(MARGIN_MM + p as f32 * (patch_w + GUTTER_MM) + patch_w / 2.0);
+        let y = row_middle(0);
+        assert_eq!(pixel(&image, patch_middle(0), y), [0; 3]);
+        assert_eq!(pixel(&image, patch_middle(10), y), [32768; 3]);
+        assert_eq!(pixel(&image, patch_middle(20), y), [u16::MAX; 3]);
+
+        // Gutters between patches and rows are left white
+        let gutter = px(MARGIN_MM + patch_w + GUTTER_MM / 2.0);
+        assert_eq!(pixel(&image, gutter, y), [u16::MAX; 3]);
+        let between_rows = px(MARGIN_MM + row_h + GUTTER_MM / 2.0);
+        assert_eq!(pixel(&image, patch_middle(0), between_rows), [u16::MAX; 3]);
+
+        // The second row opens with full red, the last ends with full yellow
+        let full_w = (297.0 - 2.0 * MARGIN_MM - 7.0 * GUTTER_MM) / 8.0;
+        let x = px(MARGIN_MM + full_w / 2.0);
+        assert_eq!(pixel(&image, x, row_middle(1)), [u16::MAX, 0, 0]);
+        let hue_w = (297.0 - 2.0 * MARGIN_MM - 5.0 * GUTTER_MM) / 6.0;
+        let x = px(297.0 - MARGIN_MM - hue_w / 2.0);
+        assert_eq!(pixel(&image, x, row_middle(6)), [u16::MAX, u16::MAX, 0]);
+    }
+
+    #[test]
+    fn test_dpi_outside_range_is_rejected() {
+        let mut target = PrintTarget::new(PaperSize::A4, "srgb");
+        target.dpi = 50;
+        assert!(target.render().is_err());
+        target.dpi = 1201;
+        assert!(target.render().is_err());
+    }
+}
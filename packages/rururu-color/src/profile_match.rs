@@ -0,0 +1,361 @@
+//! Catching ICC profiles assigned to the wrong display.
+//!
+//! A profile made for another monitor, another white point or another gamut
+//! applies without complaint and colors are silently wrong.
+//! `check_profile_match` compares what the profile describes with what the
+//! monitor reports in its EDID.
+
+use crate::icc::{IccProfileInfo, ProfileClass};
+use crate::monitor::{self, ColorGamut, MonitorProfile, WhitePoint, UNNAMED_MODEL};
+use std::fmt;
+
+/// White points further apart than this in xy are different illuminants;
+/// D50 and D65 are 0.05 apart, panel EDIDs are good to about 0.005
+const WHITE_TOLERANCE: f64 = 0.015;
+
+/// Colorants this close to a standard make a color space profile (sRGB,
+/// Adobe RGB, ...) rather than one measured on some device
+const STANDARD_SPACE_TOLERANCE: f64 = 0.005;
+
+#[derive(Debug, Clone, PartialEq)]
+pub enum ColorWarning {
+    /// Printer, input or other non-display profile
+    NotDisplayProfile(ProfileClass),
+    /// Calibrated on a different monitor model
+    OtherDevice {
+        profile_model: String,
+        monitor_model: String,
+    },
+    WhitePointMismatch {
+        profile: (f64, f64),
+        display: (f64, f64),
+    },
+    /// Oversaturates unless the monitor emulates sRGB itself
+    SrgbOnWideGamut { display: ColorGamut },
+    /// Desaturates everything
+    WideGamutOnSrgb { profile: ColorGamut },
+    GamutMismatch {
+        profile: ColorGamut,
+        display: ColorGamut,
+    },
+}
+
+impl fmt::Display for ColorWarning {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            ColorWarning::NotDisplayProfile(class) => {
+                write!(f, "{:?} profile, not a display profile", class)
+            }
+            ColorWarning::OtherDevice {
+                profile_model,
+                monitor_model,
+            } => write!(
+                f,
+                "Profile was made for {}, not this {}",
+                profile_model, monitor_model
+            ),
+            ColorWarning::WhitePointMismatch { profile, display } => write!(
+                f,
+                "{} profile on a {} panel; whites will be tinted",
+                illuminant_name(*profile),
+                illuminant_name(*display)
+            ),
+            ColorWarning::SrgbOnWideGamut { display } => {
+                let display = match display {
+                    ColorGamut::Unknown => "wide-gamut".to_string(),
+                    gamut => gamut.to_string(),
+                };
+                write!(
+                    f,
+                    "sRGB profile on a {} panel; colors will be oversaturated unless the monitor is in its sRGB mode",
+                    display
+                )
+            }
+            ColorWarning::WideGamutOnSrgb { profile } => write!(
+                f,
+                "{} profile on an sRGB panel; colors will look washed out",
+                profile
+            ),
+            ColorWarning::GamutMismatch { profile, display } => {
+                write!(f, "{} profile on a {} panel", profile, display)
+            }
+        }
+    }
+}
+
+impl MonitorProfile {
+    /// Obvious ways `icc` doesn't describe this monitor; empty when nothing
+    /// looks wrong or the EDID is too sparse to tell
+    pub fn check_profile_match(&self, icc: &IccProfileInfo) -> Vec<ColorWarning> {
+        if icc.profile_class != ProfileClass::Display {
+            return vec![ColorWarning::NotDisplayProfile(icc.profile_class)];
+        }
+
+        let mut warnings = Vec::new();
+        let chromaticity = self.edid.chromaticity.as_ref();
+
+        let standard_space = icc.primaries.as_ref().is_some_and(is_standard_space);
+        let monitor_model = self.edid.model.trim();
+        if let Some(profile_model) = icc.device_model.as_deref() {
+            let named = self.edid.hash.is_some() && monitor_model != UNNAMED_MODEL;
+            if named && !standard_space && !same_model(profile_model, monitor_model) {
+                warnings.push(ColorWarning::OtherDevice {
+                    profile_model: profile_model.to_string(),
+                    monitor_model: monitor_model.to_string(),
+                });
+            }
+        }
+
+        // A calibration targets its own white, which beats the panel's native one
+        let display_white = self
+            .calibration
+            .as_ref()
+            .map(|c| c.white_point.xy())
+            .or_else(|| chromaticity.map(|c| c.white));
+        if let (Some(profile), Some(display)) = (icc.white_point, display_white) {
+            if monitor::xy_distance(profile, display) > WHITE_TOLERANCE {
+                warnings.push(ColorWarning::WhitePointMismatch { profile, display });
+            }
+        }
+
+        if let (Some(profile), Some(chromaticity)) = (icc.primaries, chromaticity) {
+            let display = chromaticity.primaries();
+            let (profile_gamut, display_gamut) = (
+                ColorGamut::classify(&profile),
+                ColorGamut::classify(&display),
+            );
+            let (profile_wide, display_wide) =
+                (ColorGamut::is_wide(&profile), ColorGamut::is_wide(&display));
+
+            if profile_gamut == ColorGamut::Srgb && display_wide {
+                warnings.push(ColorWarning::SrgbOnWideGamut {
+                    display: display_gamut,
+                });
+            } else if profile_wide && !display_wide {
+                warnings.push(ColorWarning::WideGamutOnSrgb {
+                    profile: profile_gamut,
+                });
+            } else if profile_gamut != display_gamut
+                && profile_gamut != ColorGamut::Unknown
+                && display_gamut != ColorGamut::Unknown
+            {
+                warnings.push(ColorWarning::GamutMismatch {
+                    profile: profile_gamut,
+                    display: display_gamut,
+                });
+            }
+        }
+
+        warnings
+    }
+}
+
+/// Tools write the EDID name into `dmdd` with their own spacing and case
+fn same_model(a: &str, b: &str) -> bool {
+    let normalize = |s: &str| -> String {
+        s.chars()
+            .filter(|c| c.is_alphanumeric())
+            .flat_map(char::to_lowercase)
+            .collect()
+    };
+    let (a, b) = (normalize(a), normalize(b));
+    !a.is_empty() && !b.is_empty() && (a.contains(&b) || b.contains(&a))
+}
+
+/// "D65" for the standard illuminants, coordinates otherwise
+fn illuminant_name(xy: (f64, f64)) -> String {
+    [WhitePoint::d50(), WhitePoint::d65(), WhitePoint::d93()]
+        .iter()
+        .find(|white| monitor::xy_distance(white.xy(), xy) < 0.005)
+        .map(|white| format!("D{}", white.temperature / 100))
+        .unwrap_or_else(|| format!("x {:.3}, y {:.3}", xy.0, xy.1))
+}
+
+/// Whether colorants are a standard color space's rather than measured
+pub(crate) fn is_standard_space(primaries: &[(f64, f64); 3]) -> bool {
+    [
+        ColorGamut::Srgb,
+        ColorGamut::AdobeRgb,
+        ColorGamut::DciP3,
+        ColorGamut::Bt2020,
+    ]
+    .into_iter()
+    .filter_map(|gamut| gamut.primaries())
+    .any(|standard| monitor::primaries_distance(primaries, &standard) <= STANDARD_SPACE_TOLERANCE)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::icc::tests::{matrix_profile, D65, SRGB};
+    use crate::monitor::{
+        parse_edid_data, CalibrationData, ColorDepth, HdrCapability, MonitorCapabilities,
+    };
+
+    const P3: [(f64, f64); 3] = [(0.680, 0.320), (0.265, 0.690), (0.150, 0.060)];
+    const ADOBE_RGB: [(f64, f64); 3] = [(0.640, 0.330), (0.210, 0.710), (0.150, 0.060)];
+    const BT2020: [(f64, f64); 3] = [(0.708, 0.292), (0.170, 0.797), (0.131, 0.046)];
+    const D50: (f64, f64) = (0.3457, 0.3585);
+    /// A measured panel, off every standard
+    const MEASURED: [(f64, f64); 3] = [(0.655, 0.335), (0.285, 0.625), (0.148, 0.058)];
+
+    /// A 128-byte EDID block for a "DEL" panel called `model`, with the
+    /// chromaticity block filled in
+    fn edid(model: &str, primaries: [(f64, f64); 3], white: (f64, f64)) -> Vec<u8> {
+        let mut data = vec![0u8; 128];
+        data[..8].copy_from_slice(&[0, 0xFF, 0xFF, 0xFF, 0xFF, 0xFF, 0xFF, 0]);
+        let id: u16 = (4 << 10) | (5 << 5) | 12;
+        data[8..10].copy_from_slice(&id.to_be_bytes());
+        data[17] = 30;
+
+        let [r, g, b] = primaries;
+        let coords = [r.0, r.1, g.0, g.1, b.0, b.1, white.0, white.1];
+        for (i, c) in coords.into_iter().enumerate() {
+            let v = (c * 1024.0).round() as u16;
+            data[27 + i] = (v >> 2) as u8;
+            data[25 + i / 4] |= ((v & 0x03) as u8) << (6 - 2 * (i % 4));
+        }
+
+        // Monitor name in the second descriptor, newline-terminated
+        data[75] = 0xFC;
+        let name = format!("{}\n", model);
+        data[77..77 + name.len()].copy_from_slice(name.as_bytes());
+        data
+    }
+
+    fn monitor(edid_data: &[u8]) -> MonitorProfile {
+        MonitorProfile {
+            name: "card0-DP-1".to_string(),
+            edid: parse_edid_data(edid_data).unwrap(),
+            capabilities: MonitorCapabilities {
+                color_depth: ColorDepth::Bit8,
+                hdr_support: HdrCapability::None,
+                wide_gamut: false,
+                native_gamma: 2.2,
+                max_luminance: None,
+                min_luminance: None,
+                color_gamut: ColorGamut::Unknown,
+            },
+            calibration: None,
+            icc_profile: None,
+        }
+    }
+
+    fn check(
+        panel: [(f64, f64); 3],
+        profile: [(f64, f64); 3],
+        profile_white: (f64, f64),
+        model: Option<&str>,
+    ) -> Vec<ColorWarning> {
+        let monitor = monitor(&edid("DELL U2720Q", panel, D65));
+        let icc =
+            IccProfileInfo::parse(&matrix_profile(b"mntr", profile_white, profile, model)).unwrap();
+        monitor.check_profile_match(&icc)
+    }
+
+    #[test]
+    fn test_parse_edid() {
+        let info = parse_edid_data(&edid("DELL U2720Q", P3, D65)).unwrap();
+        assert_eq!(info.manufacturer, "DEL");
+        assert_eq!(info.model, "DELL U2720Q");
+        assert_eq!(info.year, 2020);
+        assert!(info.hash.is_some());
+
+        // 10-bit coordinates are good to 1/1024
+        let chromaticity = info.chromaticity.unwrap();
+        for (actual, expected) in chromaticity.primaries().into_iter().zip(P3) {
+            assert!(monitor::xy_distance(actual, expected) < 1.0 / 1024.0);
+        }
+        assert!(monitor::xy_distance(chromaticity.white, D65) < 1.0 / 1024.0);
+        assert_eq!(
+            ColorGamut::classify(&chromaticity.primaries()),
+            ColorGamut::DciP3
+        );
+
+        // A zeroed block and a missing name
+        let mut data = edid("", P3, D65);
+        data[25..35].fill(0);
+        let info = parse_edid_data(&data).unwrap();
+        assert_eq!(info.chromaticity, None);
+        assert_eq!(info.model, UNNAMED_MODEL);
+
+        assert!(parse_edid_data(&data[..100]).is_err());
+    }
+
+    #[test]
+    fn test_matching_profile_has_no_warnings() {
+        assert_eq!(check(SRGB, SRGB, D65, None), []);
+        assert_eq!(check(P3, P3, D65, None), []);
+        // A measured profile for this very monitor, named differently
+        assert_eq!(check(MEASURED, MEASURED, D65, Some("Dell U2720Q")), []);
+    }
+
+    #[test]
+    fn test_gamut_rules() {
+        assert_eq!(
+            check(P3, SRGB, D65, None),
+            [ColorWarning::SrgbOnWideGamut {
+                display: ColorGamut::DciP3
+            }]
+        );
+        assert_eq!(
+            check(SRGB, ADOBE_RGB, D65, None),
+            [ColorWarning::WideGamutOnSrgb {
+                profile: ColorGamut::AdobeRgb
+            }]
+        );
+        assert_eq!(
+            check(BT2020, P3, D65, None),
+            [ColorWarning::GamutMismatch {
+                profile: ColorGamut::DciP3,
+                display: ColorGamut::Bt2020,
+            }]
+        );
+    }
+
+    #[test]
+    fn test_white_point_rule() {
+        let warnings = check(SRGB, SRGB, D50, None);
+        assert!(matches!(
+            warnings[..],
+            [ColorWarning::WhitePointMismatch { .. }]
+        ));
+        assert!(warnings[0]
+            .to_string()
+            .starts_with("D50 profile on a D65 panel"));
+
+        // A calibration to D50 makes a D50 profile the right one
+        let mut monitor = monitor(&edid("DELL U2720Q", SRGB, D65));
+        monitor.calibration = Some(CalibrationData {
+            date: String::new(),
+            white_point: WhitePoint::d50(),
+            gamma: 2.2,
+            brightness: 1.0,
+            contrast: 1.0,
+            rgb_gains: (1.0, 1.0, 1.0),
+            gamma_curve: None,
+        });
+        let icc = IccProfileInfo::parse(&matrix_profile(b"mntr", D50, SRGB, None)).unwrap();
+        assert_eq!(monitor.check_profile_match(&icc), []);
+    }
+
+    #[test]
+    fn test_device_rules() {
+        assert_eq!(
+            check(MEASURED, MEASURED, D65, Some("LG 27UK850")),
+            [ColorWarning::OtherDevice {
+                profile_model: "LG 27UK850".to_string(),
+                monitor_model: "DELL U2720Q".to_string(),
+            }]
+        );
+        // Standard spaces name no particular monitor
+        assert_eq!(check(SRGB, SRGB, D65, Some("sRGB IEC61966-2.1")), []);
+
+        let monitor = monitor(&edid("DELL U2720Q", SRGB, D65));
+        let printer = IccProfileInfo::parse(&matrix_profile(b"prtr", D65, SRGB, None)).unwrap();
+        assert_eq!(
+            monitor.check_profile_match(&printer),
+            [ColorWarning::NotDisplayProfile(ProfileClass::Output)]
+        );
+    }
+}
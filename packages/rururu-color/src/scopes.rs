@@ -0,0 +1,393 @@
+//! Video scopes of a pixel buffer: luma waveform, RGB parade, vectorscope
+//! and histogram, as counts for a GUI to draw.
+//!
+//! Buffers follow [`ColorTransform::apply`]: rows top to bottom, 3 (RGB) or
+//! 4 (RGBA) channels per pixel, and encoded Rec.709 or sRGB values in 0–1;
+//! 8-bit buffers go through [`analyze_u8`]. Like a hardware scope, levels
+//! are measured on the encoded signal, with the Rec.709 luma and chroma
+//! coefficients both spaces share. Values outside 0–1 land in the edge
+//! bins. Large images are read on a grid of at most
+//! [`ScopeOptions::max_samples`] pixels.
+//!
+//! [`ColorTransform::apply`]: crate::ColorTransform::apply
+
+use crate::transform::check_buffer;
+use crate::{ColorError, Result};
+use serde::{Deserialize, Serialize};
+
+/// Rec.709 luma coefficients
+const LUMA: [f32; 3] = [0.2126, 0.7152, 0.0722];
+
+/// Divisors taking B'−Y' and R'−Y' to Cb and Cr in −0.5..=0.5
+const CB_SCALE: f32 = 1.8556;
+const CR_SCALE: f32 = 1.5748;
+
+/// The 75% color bars a vectorscope graticule marks, by name
+const TARGETS: [(&str, [f32; 3]); 6] = [
+    ("R", [0.75, 0.0, 0.0]),
+    ("Mg", [0.75, 0.0, 0.75]),
+    ("B", [0.0, 0.0, 0.75]),
+    ("Cy", [0.0, 0.75, 0.75]),
+    ("G", [0.0, 0.75, 0.0]),
+    ("Yl", [0.75, 0.75, 0.0]),
+];
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub struct ScopeOptions {
+    /// Most waveform columns; images sampled narrower get one per column
+    pub columns: usize,
+    /// Waveform levels, histogram bins, and vectorscope width and height
+    pub resolution: usize,
+    /// Most pixels read
+    pub max_samples: usize,
+}
+
+impl Default for ScopeOptions {
+    fn default() -> Self {
+        Self {
+            columns: 256,
+            resolution: 256,
+            max_samples: 256 * 256,
+        }
+    }
+}
+
+/// How many samples of each image column fall on each level
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct Waveform {
+    pub columns: usize,
+    pub levels: usize,
+    /// `levels` rows of `columns` counts, black first
+    pub counts: Vec<u32>,
+}
+
+impl Waveform {
+    fn new(columns: usize, levels: usize) -> Self {
+        Self {
+            columns,
+            levels,
+            counts: vec![0; columns * levels],
+        }
+    }
+
+    fn add(&mut self, column: usize, value: f32) {
+        self.counts[bin(value, self.levels) * self.columns + column] += 1;
+    }
+
+    /// Samples of `column` at `level`, 0 being black
+    pub fn count(&self, column: usize, level: usize) -> u32 {
+        self.counts[level * self.columns + column]
+    }
+
+    /// The highest count, for scaling the trace
+    pub fn peak(&self) -> u32 {
+        self.counts.iter().copied().max().unwrap_or(0)
+    }
+}
+
+/// Chroma of every sample, plotted as Cb across and Cr up with neutral in
+/// the middle
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct Vectorscope {
+    pub size: usize,
+    /// `size` rows of `size` counts, top (most red) first
+    pub counts: Vec<u32>,
+}
+
+impl Vectorscope {
+    fn new(size: usize) -> Self {
+        Self {
+            size,
+            counts: vec![0; size * size],
+        }
+    }
+
+    fn add(&mut self, rgb: [f32; 3]) {
+        let (x, y) = Self::position(rgb);
+        self.counts[bin(y, self.size) * self.size + bin(x, self.size)] += 1;
+    }
+
+    /// Where a color plots, as fractions of the scope's width and height
+    /// from its top left corner
+    pub fn position(rgb: [f32; 3]) -> (f32, f32) {
+        let [r, g, b] = rgb;
+        // B'−Y' and R'−Y' expanded, so grays come out exactly neutral
+        let cb = (LUMA[0] * (b - r) + LUMA[1] * (b - g)) / CB_SCALE;
+        let cr = (LUMA[1] * (r - g) + LUMA[2] * (r - b)) / CR_SCALE;
+        (0.5 + cb, 0.5 - cr)
+    }
+
+    /// Graticule targets of the 75% color bars, named R, Mg, B, Cy, G and Yl
+    pub fn targets() -> Vec<(&'static str, (f32, f32))> {
+        TARGETS
+            .iter()
+            .map(|&(name, rgb)| (name, Self::position(rgb)))
+            .collect()
+    }
+
+    /// Samples at column `x` of row `y`
+    pub fn count(&self, x: usize, y: usize) -> u32 {
+        self.counts[y * self.size + x]
+    }
+
+    pub fn peak(&self) -> u32 {
+        self.counts.iter().copied().max().unwrap_or(0)
+    }
+}
+
+/// Samples per level of each channel and of luma, black first
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct Histogram {
+    pub bins: usize,
+    pub red: Vec<u32>,
+    pub green: Vec<u32>,
+    pub blue: Vec<u32>,
+    pub luma: Vec<u32>,
+}
+
+impl Histogram {
+    fn new(bins: usize) -> Self {
+        Self {
+            bins,
+            red: vec![0; bins],
+            green: vec![0; bins],
+            blue: vec![0; bins],
+            luma: vec![0; bins],
+        }
+    }
+
+    fn add(&mut self, rgb: [f32; 3], y: f32) {
+        self.red[bin(rgb[0], self.bins)] += 1;
+        self.green[bin(rgb[1], self.bins)] += 1;
+        self.blue[bin(rgb[2], self.bins)] += 1;
+        self.luma[bin(y, self.bins)] += 1;
+    }
+
+    /// The highest count of any channel
+    pub fn peak(&self) -> u32 {
+        [&self.red, &self.green, &self.blue, &self.luma]
+            .iter()
+            .flat_map(|bins| bins.iter().copied())
+            .max()
+            .unwrap_or(0)
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct Scopes {
+    /// Luma waveform
+    pub waveform: Waveform,
+    /// Red, green and blue waveforms, shown side by side as a parade
+    pub parade: [Waveform; 3],
+    pub vectorscope: Vectorscope,
+    pub histogram: Histogram,
+    /// Pixels read
+    pub samples: usize,
+}
+
+/// Scopes of a float buffer `width` pixels wide
+pub fn analyze(
+    pixels: &[f32],
+    channels: usize,
+    width: usize,
+    options: ScopeOptions,
+) -> Result<Scopes> {
+    check_buffer(pixels, channels)?;
+    analyze_with(pixels.len() / channels, width, options, |i| {
+        let p = &pixels[i * channels..];
+        [p[0], p[1], p[2]]
+    })
+}
+
+/// Scopes of an 8-bit buffer, such as a decoded image
+pub fn analyze_u8(
+    pixels: &[u8],
+    channels: usize,
+    width: usize,
+    options: ScopeOptions,
+) -> Result<Scopes> {
+    check_buffer(pixels, channels)?;
+    analyze_with(pixels.len() / channels, width, options, |i| {
+        let p = &pixels[i * channels..];
+        [p[0], p[1], p[2]].map(|c| c as f32 / 255.0)
+    })
+}
+
+fn analyze_with(
+    count: usize,
+    width: usize,
+    options: ScopeOptions,
+    pixel: impl Fn(usize) -> [f32; 3],
+) -> Result<Scopes> {
+    if width == 0 || !count.is_multiple_of(width) {
+        return Err(ColorError::Buffer(format!(
+            "{} pixels is not a whole number of {}-pixel rows",
+            count, width
+        )));
+    }
+    let height = count / width;
+    let resolution = options.resolution.max(2);
+
+    // Every `step`th pixel of every `step`th row
+    let step = ((count as f64 / options.max_samples.max(1) as f64)
+        .sqrt()
+        .ceil() as usize)
+        .max(1);
+    let sampled_width = width.div_ceil(step);
+    let columns = sampled_width.min(options.columns.max(1));
+
+    let mut scopes = Scopes {
+        waveform: Waveform::new(columns, resolution),
+        parade: std::array::from_fn(|_| Waveform::new(columns, resolution)),
+        vectorscope: Vectorscope::new(resolution),
+        histogram: Histogram::new(resolution),
+        samples: 0,
+    };
+    for y in (0..height).step_by(step) {
+        for x in (0..width).step_by(step) {
+            let rgb = pixel(y * width + x);
+            let y = luma(rgb);
+            let column = x / step * columns / sampled_width;
+
+            scopes.waveform.add(column, y);
+            for (trace, value) in scopes.parade.iter_mut().zip(rgb) {
+                trace.add(column, value);
+            }
+            scopes.vectorscope.add(rgb);
+            scopes.histogram.add(rgb, y);
+            scopes.samples += 1;
+        }
+    }
+    Ok(scopes)
+}
+
+fn luma(rgb: [f32; 3]) -> f32 {
+    rgb.iter().zip(LUMA).map(|(c, w)| c * w).sum()
+}
+
+/// Bin of a 0–1 value among `bins`, clamping out-of-range values to the ends
+fn bin(value: f32, bins: usize) -> usize {
+    // NaN casts to 0
+    (value.clamp(0.0, 1.0) * (bins - 1) as f32).round() as usize
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn column_total(waveform: &Waveform, column: usize) -> u32 {
+        (0..waveform.levels)
+            .map(|l| waveform.count(column, l))
+            .sum()
+    }
+
+    #[test]
+    fn test_gray_ramp_plots_at_vectorscope_centre() {
+        let ramp: Vec<f32> = (0..=100).flat_map(|i| [i as f32 / 100.0; 3]).collect();
+        // One level per step of the ramp
+        let options = ScopeOptions {
+            resolution: 101,
+            ..ScopeOptions::default()
+        };
+        let scopes = analyze(&ramp, 3, 101, options).unwrap();
+
+        let centre = bin(0.5, scopes.vectorscope.size);
+        assert_eq!(scopes.samples, 101);
+        assert_eq!(scopes.vectorscope.count(centre, centre), 101);
+        assert_eq!(scopes.vectorscope.peak(), 101);
+
+        // Luma of gray is the gray itself
+        assert_eq!(scopes.histogram.luma, vec![1; 101]);
+        assert_eq!(scopes.histogram.red, vec![1; 101]);
+    }
+
+    #[test]
+    fn test_out_of_range_values_land_in_edge_bins() {
+        let pixels = [
+            0.0,
+            0.0,
+            0.0, //
+            -0.5,
+            -0.5,
+            -0.5, //
+            1.0,
+            1.0,
+            1.0, //
+            2.0,
+            2.0,
+            2.0, //
+            f32::NAN,
+            0.0,
+            0.0,
+        ];
+        let options = ScopeOptions {
+            resolution: 16,
+            ..ScopeOptions::default()
+        };
+        let scopes = analyze(&pixels, 3, 5, options).unwrap();
+
+        assert_eq!(scopes.histogram.red[0], 3);
+        assert_eq!(scopes.histogram.red[15], 2);
+        assert_eq!(scopes.histogram.blue[0], 3);
+        assert_eq!(scopes.histogram.blue[15], 2);
+        assert_eq!(bin(0.5, 256), 128);
+        assert_eq!(bin(0.49, 256), 125);
+    }
+
+    #[test]
+    fn test_large_images_are_sampled_on_a_grid() {
+        // 100x100 with room for 2500 samples reads every other pixel of
+        // every other row, 50 columns folded into 10
+        let pixels: Vec<u8> = (0..100 * 100)
+            .flat_map(|i| {
+                let x = (i % 100) as u8;
+                [x * 2, x * 2, x * 2, 255]
+            })
+            .collect();
+        let options = ScopeOptions {
+            columns: 10,
+            resolution: 256,
+            max_samples: 2500,
+        };
+        let scopes = analyze_u8(&pixels, 4, 100, options).unwrap();
+
+        assert_eq!(scopes.samples, 2500);
+        assert_eq!(scopes.waveform.columns, 10);
+        for column in 0..10 {
+            assert_eq!(column_total(&scopes.waveform, column), 250);
+        }
+        // Column 0 holds x = 0, 2, 4, 6 and 8, so levels 0 to 16 only
+        let brightest = (0..256)
+            .rev()
+            .find(|&l| scopes.waveform.count(0, l) > 0)
+            .unwrap();
+        assert_eq!(brightest, 16);
+
+        // Narrow images get one column per sampled pixel
+        let narrow = analyze_u8(&pixels[..4 * 6], 4, 3, options).unwrap();
+        assert_eq!(narrow.waveform.columns, 3);
+        assert_eq!(narrow.samples, 6);
+    }
+
+    #[test]
+    fn test_partial_rows_are_an_error() {
+        let pixels = [0u8; 4 * 10];
+        assert!(matches!(
+            analyze_u8(&pixels, 4, 3, ScopeOptions::default()),
+            Err(ColorError::Buffer(_))
+        ));
+        assert!(analyze_u8(&pixels, 4, 0, ScopeOptions::default()).is_err());
+        assert!(analyze(&[0.0; 7], 3, 1, ScopeOptions::default()).is_err());
+        assert!(analyze_u8(&pixels, 4, 5, ScopeOptions::default()).is_ok());
+    }
+
+    #[test]
+    fn test_u8_matches_float() {
+        let bytes: Vec<u8> = (0..=255).flat_map(|v| [v, 255 - v, v / 2]).collect();
+        let floats: Vec<f32> = bytes.iter().map(|&b| b as f32 / 255.0).collect();
+        assert_eq!(
+            analyze_u8(&bytes, 3, 16, ScopeOptions::default()).unwrap(),
+            analyze(&floats, 3, 16, ScopeOptions::default()).unwrap()
+        );
+    }
+}
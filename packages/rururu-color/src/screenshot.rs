@@ -0,0 +1,346 @@
+//! Screenshots that keep their color management.
+//!
+//! The compositor hands over the pixels through wlroots' screencopy
+//! protocol exactly as they were sent to the panel, so they are in the
+//! display's color space, not sRGB. `capture` tags them with the ICC
+//! profile assigned to that display, one built from its EDID primaries when
+//! none is assigned, or sRGB when the EDID reports no primaries either.
+//! `save_png` embeds the profile as an `iCCP` chunk, so the screenshot looks
+//! the same on someone else's calibrated screen.
+
+use crate::icc::IccProfileInfo;
+use crate::monitor::{self, MonitorProfile};
+use crate::{ColorError, Result};
+use image::codecs::png::PngEncoder;
+use image::{ExtendedColorType, ImageEncoder, RgbImage};
+use lcms2::{CIExyY, CIExyYTRIPLE, Profile, ToneCurve};
+use nix::sys::memfd::{memfd_create, MemFdCreateFlag};
+use std::ffi::CStr;
+use std::fs::File;
+use std::io::{BufWriter, Read, Seek, SeekFrom};
+use std::os::fd::AsFd;
+use std::path::{Path, PathBuf};
+use wayland_client::globals::{registry_queue_init, GlobalListContents};
+use wayland_client::protocol::{wl_buffer, wl_output, wl_registry, wl_shm, wl_shm_pool};
+use wayland_client::{delegate_noop, Connection, Dispatch, Proxy, QueueHandle, WEnum};
+use wayland_protocols_wlr::screencopy::v1::client::{
+    zwlr_screencopy_frame_v1 as frame, zwlr_screencopy_manager_v1 as manager,
+};
+
+/// Where the embedded profile came from
+#[derive(Debug, Clone, PartialEq)]
+pub enum ProfileSource {
+    /// The ICC profile assigned to the display
+    Assigned(PathBuf),
+    /// Built from the EDID primaries, white point and native gamma
+    Edid,
+    /// Nothing is known about the display
+    Srgb,
+}
+
+#[derive(Debug, Clone)]
+pub struct ColorManagedImage {
+    /// Pixels in the display's color space
+    pub image: RgbImage,
+    /// Profile describing that space
+    pub icc_profile: Vec<u8>,
+    pub profile_source: ProfileSource,
+}
+
+impl ColorManagedImage {
+    pub fn save_png(&self, path: &Path) -> Result<()> {
+        let file = BufWriter::new(File::create(path)?);
+        let mut encoder = PngEncoder::new(file);
+        encoder
+            .set_icc_profile(self.icc_profile.clone())
+            .map_err(|e| ColorError::Capture(e.to_string()))?;
+        encoder
+            .write_image(
+                self.image.as_raw(),
+                self.image.width(),
+                self.image.height(),
+                ExtendedColorType::Rgb8,
+            )
+            .map_err(|e| ColorError::Capture(e.to_string()))
+    }
+}
+
+/// Grab `output` (the Wayland name, e.g. `DP-1`) with its display profile
+pub fn capture(output: &str) -> Result<ColorManagedImage> {
+    let image = screencopy(output)?;
+
+    let monitor = monitor::detect_monitors()?
+        .into_iter()
+        .find(|m| connector_matches(&m.name, output));
+    let (icc_profile, profile_source) = display_profile(monitor.as_ref())?;
+
+    Ok(ColorManagedImage {
+        image,
+        icc_profile,
+        profile_source,
+    })
+}
+
+/// `card0-DP-1` in sysfs is `DP-1` on Wayland
+pub(crate) fn connector_matches(drm_name: &str, output: &str) -> bool {
+    drm_name == output
+        || drm_name
+            .split_once('-')
+            .is_some_and(|(_, connector)| connector == output)
+}
+
+fn display_profile(monitor: Option<&MonitorProfile>) -> Result<(Vec<u8>, ProfileSource)> {
+    if let Some(path) = monitor.and_then(|m| m.icc_profile.as_ref()) {
+        match std::fs::read(path) {
+            Ok(data) if IccProfileInfo::parse(&data).is_ok() => {
+                return Ok((data, ProfileSource::Assigned(path.clone())));
+            }
+            Ok(_) => tracing::warn!("{:?} is not a valid ICC profile; not embedding it", path),
+            Err(e) => tracing::warn!("Can't read assigned profile {:?}: {}", path, e),
+        }
+    }
+
+    let icc_error = |e: lcms2::Error| ColorError::IccError(e.to_string());
+
+    if let Some(monitor) = monitor {
+        if let Some(chromaticity) = &monitor.edid.chromaticity {
+            let xy = |(x, y): (f64, f64)| CIExyY { x, y, Y: 1.0 };
+            let gamma = ToneCurve::new(monitor.capabilities.native_gamma as f64);
+            let profile = Profile::new_rgb(
+                &xy(chromaticity.white),
+                &CIExyYTRIPLE {
+                    Red: xy(chromaticity.red),
+                    Green: xy(chromaticity.green),
+                    Blue: xy(chromaticity.blue),
+                },
+                &[&gamma, &gamma, &gamma],
+            )
+            .map_err(icc_error)?;
+            return Ok((profile.icc().map_err(icc_error)?, ProfileSource::Edid));
+        }
+    }
+
+    let srgb = Profile::new_srgb().icc().map_err(icc_error)?;
+    Ok((srgb, ProfileSource::Srgb))
+}
+
+#[derive(Default)]
+struct Capture {
+    outputs: Vec<(wl_output::WlOutput, Option<String>)>,
+    /// Format, width, height and stride of the shm buffer to copy into
+    buffer: Option<(WEnum<wl_shm::Format>, u32, u32, u32)>,
+    /// Whether all buffer types have been announced (frame v3)
+    buffer_done: bool,
+    y_invert: bool,
+    /// Set once the copy finished or failed
+    result: Option<std::result::Result<(), String>>,
+}
+
+fn screencopy(output_name: &str) -> Result<RgbImage> {
+    let wayland = |e: &dyn std::fmt::Display| ColorError::Capture(format!("Wayland: {}", e));
+
+    let conn = Connection::connect_to_env().map_err(|e| wayland(&e))?;
+    let (globals, mut queue) = registry_queue_init::<Capture>(&conn).map_err(|e| wayland(&e))?;
+    let qh = queue.handle();
+
+    let shm: wl_shm::WlShm = globals
+        .bind(&qh, 1..=1, ())
+        .map_err(|_| ColorError::Capture("compositor has no wl_shm".into()))?;
+    let manager: manager::ZwlrScreencopyManagerV1 = globals.bind(&qh, 1..=3, ()).map_err(|_| {
+        ColorError::Capture(
+            "compositor doesn't support wlr-screencopy (needs sway or another wlroots compositor)"
+                .into(),
+        )
+    })?;
+
+    let mut state = Capture::default();
+    for global in globals.contents().clone_list() {
+        if global.interface == wl_output::WlOutput::interface().name {
+            // Output names arrive with version 4
+            let output = globals.registry().bind::<wl_output::WlOutput, _, _>(
+                global.name,
+                global.version.min(4),
+                &qh,
+                state.outputs.len(),
+            );
+            state.outputs.push((output, None));
+        }
+    }
+    queue.roundtrip(&mut state).map_err(|e| wayland(&e))?;
+
+    // Without names the only output is the one meant
+    let output = match state.outputs.as_slice() {
+        [(output, None)] => output,
+        outputs => outputs
+            .iter()
+            .find(|(_, name)| name.as_deref() == Some(output_name))
+            .map(|(output, _)| output)
+            .ok_or_else(|| ColorError::MonitorNotFound(output_name.to_string()))?,
+    };
+
+    let frame = manager.capture_output(0, output, &qh, ());
+    while state.result.is_none() && !state.buffer_done {
+        // Before v3 the buffer event is the last one until `copy`
+        if state.buffer.is_some() && frame.version() < 3 {
+            break;
+        }
+        queue
+            .blocking_dispatch(&mut state)
+            .map_err(|e| wayland(&e))?;
+    }
+    if let Some(Err(e)) = state.result.take() {
+        return Err(ColorError::Capture(e));
+    }
+    let Some((format, width, height, stride)) = state.buffer else {
+        return Err(ColorError::Capture(
+            "compositor offered no shared-memory buffer".into(),
+        ));
+    };
+    let WEnum::Value(shm_format) = format else {
+        return Err(ColorError::Capture(format!(
+            "unknown pixel format {:?}",
+            format
+        )));
+    };
+
+    let size = stride as u64 * height as u64;
+    let mut file = shm_file(c"rururu-screenshot", size)?;
+    let pool = shm.create_pool(file.as_fd(), size as i32, &qh, ());
+    let buffer = pool.create_buffer(
+        0,
+        width as i32,
+        height as i32,
+        stride as i32,
+        shm_format,
+        &qh,
+        (),
+    );
+    frame.copy(&buffer);
+    while state.result.is_none() {
+        queue
+            .blocking_dispatch(&mut state)
+            .map_err(|e| wayland(&e))?;
+    }
+
+    frame.destroy();
+    buffer.destroy();
+    pool.destroy();
+    state
+        .result
+        .take()
+        .unwrap_or(Ok(()))
+        .map_err(ColorError::Capture)?;
+
+    let mut data = vec![0u8; size as usize];
+    file.seek(SeekFrom::Start(0))?;
+    file.read_exact(&mut data)?;
+
+    let mut image = RgbImage::new(width, height);
+    for (y, row) in data.chunks_exact(stride as usize).enumerate() {
+        let y = if state.y_invert {
+            height as usize - 1 - y
+        } else {
+            y
+        };
+        for (x, pixel) in row.chunks_exact(4).take(width as usize).enumerate() {
+            let rgb = to_rgb(shm_format, [pixel[0], pixel[1], pixel[2], pixel[3]])?;
+            image.put_pixel(x as u32, y as u32, image::Rgb(rgb));
+        }
+    }
+
+    Ok(image)
+}
+
+/// One little-endian 32-bit shm pixel as 8-bit RGB
+fn to_rgb(format: wl_shm::Format, [b0, b1, b2, b3]: [u8; 4]) -> Result<[u8; 3]> {
+    use wl_shm::Format;
+
+    let word = u32::from_le_bytes([b0, b1, b2, b3]);
+    // 10-bit outputs; the two low bits don't survive an 8-bit PNG
+    let ten_bit = |shift: u32| (((word >> shift) & 0x3ff) >> 2) as u8;
+
+    match format {
+        Format::Xrgb8888 | Format::Argb8888 => Ok([b2, b1, b0]),
+        Format::Xbgr8888 | Format::Abgr8888 => Ok([b0, b1, b2]),
+        Format::Xrgb2101010 | Format::Argb2101010 => Ok([ten_bit(20), ten_bit(10), ten_bit(0)]),
+        Format::Xbgr2101010 | Format::Abgr2101010 => Ok([ten_bit(0), ten_bit(10), ten_bit(20)]),
+        other => Err(ColorError::Capture(format!(
+            "unsupported pixel format {:?}",
+            other
+        ))),
+    }
+}
+
+/// Memory shared with the compositor: an anonymous memfd, read back with
+/// ordinary reads once the copy is done
+pub(crate) fn shm_file(name: &CStr, size: u64) -> Result<File> {
+    let fd = memfd_create(name, MemFdCreateFlag::MFD_CLOEXEC).map_err(std::io::Error::from)?;
+    let file = File::from(fd);
+    file.set_len(size)?;
+    Ok(file)
+}
+
+impl Dispatch<wl_registry::WlRegistry, GlobalListContents> for Capture {
+    fn event(
+        _: &mut Self,
+        _: &wl_registry::WlRegistry,
+        _: wl_registry::Event,
+        _: &GlobalListContents,
+        _: &Connection,
+        _: &QueueHandle<Self>,
+    ) {
+        // Outputs plugged in mid-capture don't matter
+    }
+}
+
+impl Dispatch<wl_output::WlOutput, usize> for Capture {
+    fn event(
+        state: &mut Self,
+        _: &wl_output::WlOutput,
+        event: wl_output::Event,
+        index: &usize,
+        _: &Connection,
+        _: &QueueHandle<Self>,
+    ) {
+        if let wl_output::Event::Name { name } = event {
+            state.outputs[*index].1 = Some(name);
+        }
+    }
+}
+
+impl Dispatch<frame::ZwlrScreencopyFrameV1, ()> for Capture {
+    fn event(
+        state: &mut Self,
+        _: &frame::ZwlrScreencopyFrameV1,
+        event: frame::Event,
+        _: &(),
+        _: &Connection,
+        _: &QueueHandle<Self>,
+    ) {
+        match event {
+            frame::Event::Buffer {
+                format,
+                width,
+                height,
+                stride,
+            } => {
+                state.buffer.get_or_insert((format, width, height, stride));
+            }
+            frame::Event::BufferDone => state.buffer_done = true,
+            frame::Event::Flags { flags } => {
+                state.y_invert =
+                    matches!(flags, WEnum::Value(f) if f.contains(frame::Flags::YInvert));
+            }
+            frame::Event::Ready { .. } => state.result = Some(Ok(())),
+            frame::Event::Failed => {
+                state.result = Some(Err("compositor failed to copy the output".into()))
+            }
+            _ => {}
+        }
+    }
+}
+
+delegate_noop!(Capture: ignore wl_shm::WlShm);
+delegate_noop!(Capture: wl_shm_pool::WlShmPool);
+delegate_noop!(Capture: ignore wl_buffer::WlBuffer);
+delegate_noop!(Capture: manager::ZwlrScreencopyManagerV1);
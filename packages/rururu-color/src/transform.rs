@@ -0,0 +1,591 @@
+//! Pixel buffer transforms between color spaces.
+//!
+//! Either end is one of the built-in working spaces or an ICC profile on
+//! disk, and lcms2 converts between them with the chosen rendering intent.
+//! Buffers are interleaved 32-bit float RGB or RGBA with channels nominally
+//! in 0–1; alpha is passed through untouched.
+//!
+//! Profiles with LUTs for an intent bring their own gamut mapping, and lcms2
+//! picks the table for the intent asked for. Matrix-shaper profiles, which
+//! include all the built-in spaces, have none: lcms2 converts them
+//! colorimetrically whatever the intent and leaves out-of-gamut colors
+//! outside 0–1. For those the intent is carried out here, in the
+//! destination's linear light. Perceptual desaturates colors toward gray at
+//! the same luminance, starting a little inside the gamut edge so nearby
+//! colors stay distinct; saturation clips each channel, keeping colors as
+//! vivid as the destination allows; the colorimetric intents leave values
+//! as they are.
+//!
+//! A soft proof goes through an output profile on the way to the display,
+//! showing how a print or other output would reproduce the image, and can
+//! paint colors the output can't reach in [`GAMUT_WARNING`]. It works on
+//! 8-bit pixels, since lcms2 only flags out-of-gamut colors in integer
+//! transforms.
+
+use crate::config::RenderingIntent;
+use crate::monitor::ColorGamut;
+use crate::{ColorError, Result};
+use lcms2::{
+    CIExyY, CIExyYTRIPLE, Flags, Intent, PixelFormat, Profile, Tag, TagSignature, ThreadContext,
+    ToneCurve, Transform, CIEXYZ,
+};
+use std::fmt;
+use std::path::Path;
+
+/// Names accepted for the built-in spaces, in place of an ICC profile path
+pub const BUILTIN_SPACES: &[&str] = &[
+    "srgb",
+    "linear-srgb",
+    "rec709",
+    "display-p3",
+    "adobe-rgb",
+    "rec2020",
+    "acescg",
+    "aces2065-1",
+];
+
+/// A built-in space, for code that picks one rather than taking a name
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum BuiltinSpace {
+    Srgb,
+    LinearSrgb,
+    /// BT.709 primaries with its camera curve
+    Rec709,
+    DisplayP3,
+    AdobeRgb,
+    Rec2020,
+    /// Linear AP1, the ACES working space
+    AcesCg,
+    /// Linear AP0, the ACES interchange space
+    Aces2065_1,
+}
+
+impl BuiltinSpace {
+    /// The space's entry in [`BUILTIN_SPACES`]
+    pub fn name(self) -> &'static str {
+        match self {
+            BuiltinSpace::Srgb => "srgb",
+            BuiltinSpace::LinearSrgb => "linear-srgb",
+            BuiltinSpace::Rec709 => "rec709",
+            BuiltinSpace::DisplayP3 => "display-p3",
+            BuiltinSpace::AdobeRgb => "adobe-rgb",
+            BuiltinSpace::Rec2020 => "rec2020",
+            BuiltinSpace::AcesCg => "acescg",
+            BuiltinSpace::Aces2065_1 => "aces2065-1",
+        }
+    }
+
+    /// Name for people, which is also the name
+    /// `rururu_wrappers::color::ColorSpace::from_name` takes, so a resolved
+    /// space can go on to `ColorManager::transform_buffer`
+    pub fn label(self) -> &'static str {
+        match self {
+            BuiltinSpace::Srgb => "sRGB",
+            BuiltinSpace::LinearSrgb => "Linear",
+            BuiltinSpace::Rec709 => "Rec.709",
+            BuiltinSpace::DisplayP3 => "Display P3",
+            BuiltinSpace::AdobeRgb => "Adobe RGB",
+            BuiltinSpace::Rec2020 => "Rec.2020",
+            BuiltinSpace::AcesCg => "ACEScg",
+            BuiltinSpace::Aces2065_1 => "ACES2065-1",
+        }
+    }
+}
+
+impl fmt::Display for BuiltinSpace {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(self.label())
+    }
+}
+
+/// Pixels converted per lcms2 call, to bound the scratch buffer
+const CHUNK_PIXELS: usize = 4096;
+
+const D65: (f64, f64) = (0.3127, 0.3290);
+
+/// The ACES white, near D60
+const ACES_WHITE: (f64, f64) = (0.32168, 0.33767);
+
+/// ACES primaries in CIE xy. AP0 encloses the spectral locus, so its blue
+/// lies below the xy plane's visible part.
+const AP0: [(f64, f64); 3] = [(0.7347, 0.2653), (0.0, 1.0), (0.0001, -0.077)];
+const AP1: [(f64, f64); 3] = [(0.713, 0.293), (0.165, 0.830), (0.128, 0.044)];
+
+/// PCS white, which matrix-shaper colorants are adapted to
+const D50: CIEXYZ = CIEXYZ {
+    X: 0.9642,
+    Y: 1.0,
+    Z: 0.8249,
+};
+
+/// `UsedDirection` arguments to lcms2's profile queries
+const USED_AS_INPUT: u32 = 0;
+const USED_AS_OUTPUT: u32 = 1;
+
+/// Fraction of the way to the gamut edge where perceptual mapping starts
+/// compressing saturation
+const PERCEPTUAL_KNEE: f32 = 0.8;
+
+/// Out-of-gamut colors in a soft proof with gamut warning on
+pub const GAMUT_WARNING: [u8; 3] = [255, 0, 255];
+
+impl From<RenderingIntent> for Intent {
+    fn from(intent: RenderingIntent) -> Self {
+        match intent {
+            RenderingIntent::Perceptual => Intent::Perceptual,
+            RenderingIntent::RelativeColorimetric => Intent::RelativeColorimetric,
+            RenderingIntent::Saturation => Intent::Saturation,
+            RenderingIntent::AbsoluteColorimetric => Intent::AbsoluteColorimetric,
+        }
+    }
+}
+
+/// How a [`ColorTransform`] renders
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct TransformOptions {
+    pub intent: RenderingIntent,
+    /// Scale the source's black to the destination's rather than clipping
+    /// shadows below it
+    pub black_point_compensation: bool,
+}
+
+impl TransformOptions {
+    /// `intent`, with black point compensation for relative colorimetric.
+    /// Perceptual tables are expected to map black already, and absolute
+    /// colorimetric is meant to reproduce it as measured.
+    pub fn new(intent: RenderingIntent) -> Self {
+        Self {
+            intent,
+            black_point_compensation: intent == RenderingIntent::RelativeColorimetric,
+        }
+    }
+}
+
+impl Default for TransformOptions {
+    fn default() -> Self {
+        Self::new(RenderingIntent::default())
+    }
+}
+
+impl From<RenderingIntent> for TransformOptions {
+    fn from(intent: RenderingIntent) -> Self {
+        Self::new(intent)
+    }
+}
+
+/// A conversion from one color space to another, built once and applied to
+/// any number of buffers
+pub struct ColorTransform {
+    /// Into the destination, or its linear light when `gamut_map` is set
+    transform: Transform<[f32; 3], [f32; 3]>,
+    gamut_map: Option<GamutMap>,
+}
+
+impl ColorTransform {
+    /// `from` and `to` are names from [`BUILTIN_SPACES`] or ICC profile paths
+    pub fn new(from: &str, to: &str, intent: RenderingIntent) -> Result<Self> {
+        Self::with_options(from, to, TransformOptions::new(intent))
+    }
+
+    pub fn with_options(from: &str, to: &str, options: TransformOptions) -> Result<Self> {
+        let icc_error = |e: lcms2::Error| ColorError::IccError(e.to_string());
+        let (from, to) = (load_space(from)?, load_space(to)?);
+        let intent = options.intent.into();
+        let mut flags = Flags::default();
+        if options.black_point_compensation {
+            flags = flags | Flags::BLACKPOINT_COMPENSATION;
+        }
+
+        let has_tables = from.is_clut(intent, USED_AS_INPUT) || to.is_clut(intent, USED_AS_OUTPUT);
+        let mapped = match options.intent {
+            RenderingIntent::Perceptual | RenderingIntent::Saturation if !has_tables => {
+                linear_twin(&to).map_err(icc_error)?
+            }
+            _ => None,
+        };
+
+        let Some((linear, weights)) = mapped else {
+            let transform = Transform::new_flags(
+                &from,
+                PixelFormat::RGB_FLT,
+                &to,
+                PixelFormat::RGB_FLT,
+                intent,
+                flags,
+            )
+            .map_err(icc_error)?;
+            return Ok(Self {
+                transform,
+                gamut_map: None,
+            });
+        };
+
+        let transform = Transform::new_flags(
+            &from,
+            PixelFormat::RGB_FLT,
+            &linear,
+            PixelFormat::RGB_FLT,
+            intent,
+            flags,
+        )
+        .map_err(icc_error)?;
+        // The twin shares the destination's colorants, so this only applies
+        // its tone curves
+        let encode = Transform::new(
+            &linear,
+            PixelFormat::RGB_FLT,
+            &to,
+            PixelFormat::RGB_FLT,
+            Intent::RelativeColorimetric,
+        )
+        .map_err(icc_error)?;
+        Ok(Self {
+            transform,
+            gamut_map: Some(GamutMap {
+                intent: options.intent,
+                weights,
+                encode,
+            }),
+        })
+    }
+
+    /// Convert `pixels` in place; `channels` is 3 for RGB or 4 for RGBA
+    pub fn apply(&self, pixels: &mut [f32], channels: usize) -> Result<()> {
+        check_buffer(pixels, channels)?;
+
+        let mut scratch = Vec::with_capacity(CHUNK_PIXELS);
+        for chunk in pixels.chunks_mut(CHUNK_PIXELS * channels) {
+            scratch.clear();
+            scratch.extend(chunk.chunks_exact(channels).map(|p| [p[0], p[1], p[2]]));
+            self.transform.transform_in_place(&mut scratch);
+            if let Some(ref map) = self.gamut_map {
+                map.apply(&mut scratch);
+            }
+            for (pixel, rgb) in chunk.chunks_exact_mut(channels).zip(&scratch) {
+                pixel[..3].copy_from_slice(rgb);
+            }
+        }
+        Ok(())
+    }
+
+    /// Convert a single color
+    pub fn apply_rgb(&self, rgb: [f32; 3]) -> [f32; 3] {
+        let mut pixel = [rgb];
+        self.transform.transform_in_place(&mut pixel);
+        if let Some(ref map) = self.gamut_map {
+            map.apply(&mut pixel);
+        }
+        pixel[0]
+    }
+}
+
+/// Gamut mapping for destinations without intent tables, done in the
+/// destination's linear light
+struct GamutMap {
+    intent: RenderingIntent,
+    /// Luminance of each destination primary
+    weights: [f32; 3],
+    /// The destination's linear light to its encoding
+    encode: Transform<[f32; 3], [f32; 3]>,
+}
+
+impl GamutMap {
+    fn apply(&self, pixels: &mut [[f32; 3]]) {
+        for pixel in pixels.iter_mut() {
+            *pixel = self.map(*pixel);
+        }
+        self.encode.transform_in_place(pixels);
+    }
+
+    fn map(&self, rgb: [f32; 3]) -> [f32; 3] {
+        let luminance: f32 = rgb.iter().zip(self.weights).map(|(c, w)| c * w).sum();
+        // Brighter than white is HDR headroom, not a gamut problem
+        let ceiling = luminance.max(1.0);
+        if luminance <= 0.0 {
+            return rgb.map(|c| c.clamp(0.0, ceiling));
+        }
+
+        match self.intent {
+            RenderingIntent::Saturation => rgb.map(|c| c.clamp(0.0, ceiling)),
+            _ => {
+                // How far toward the gamut edge the color is, from gray at
+                // its luminance; 1 is on the edge
+                let reach = rgb
+                    .iter()
+                    .map(|&c| {
+                        if c < luminance {
+                            (luminance - c) / luminance
+                        } else if ceiling > luminance {
+                            (c - luminance) / (ceiling - luminance)
+                        } else {
+                            0.0
+                        }
+                    })
+                    .fold(0.0, f32::max);
+                if reach <= PERCEPTUAL_KNEE {
+                    return rgb;
+                }
+                // Compresses everything past the knee into what's left of
+                // the gamut, approaching the edge but never crossing it
+                let over = (reach - PERCEPTUAL_KNEE) / (1.0 - PERCEPTUAL_KNEE);
+                let mapped = PERCEPTUAL_KNEE + (1.0 - PERCEPTUAL_KNEE) * over / (1.0 + over);
+                let scale = mapped / reach;
+                rgb.map(|c| luminance + (c - luminance) * scale)
+            }
+        }
+    }
+}
+
+/// One-off conversion of `pixels` from `from` to `to`
+pub fn transform_buffer(
+    pixels: &mut [f32],
+    channels: usize,
+    from: &str,
+    to: &str,
+    options: impl Into<TransformOptions>,
+) -> Result<()> {
+    ColorTransform::with_options(from, to, options.into())?.apply(pixels, channels)
+}
+
+/// How a soft proof renders
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub struct ProofOptions {
+    /// Intent the output would print with
+    pub intent: RenderingIntent,
+    pub black_point_compensation: bool,
+    /// Paint colors the output can't reproduce in [`GAMUT_WARNING`]
+    pub gamut_warning: bool,
+}
+
+/// Simulation of an output profile on a display
+pub struct SoftProof {
+    transform: Transform<[u8; 3], [u8; 3], ThreadContext>,
+    // The transform refers to its context, so this is dropped after it
+    _ctx: ThreadContext,
+}
+
+impl SoftProof {
+    /// Show pixels in `source` as `proof` would reproduce them, for viewing
+    /// on `display`. Each is a name from [`BUILTIN_SPACES`] or an ICC
+    /// profile path; `proof` is normally a printer profile.
+    pub fn new(source: &str, proof: &str, display: &str, options: ProofOptions) -> Result<Self> {
+        let ctx = proof_context();
+        let source = load_space_in(&ctx, source)?;
+        Self::build(ctx, source, proof, display, options)
+    }
+
+    /// [`new`](SoftProof::new) for pixels tagged with the ICC profile in
+    /// `source`, such as one embedded in an image
+    pub fn with_source_icc(
+        source: &[u8],
+        proof: &str,
+        display: &str,
+        options: ProofOptions,
+    ) -> Result<Self> {
+        let ctx = proof_context();
+        let source = Profile::new_icc_context(&ctx, source)
+            .map_err(|e| ColorError::IccError(e.to_string()))?;
+        Self::build(ctx, source, proof, display, options)
+    }
+
+    fn build(
+        ctx: ThreadContext,
+        source: Profile<ThreadContext>,
+        proof: &str,
+        display: &str,
+        options: ProofOptions,
+    ) -> Result<Self> {
+        let (proof, display) = (load_space_in(&ctx, proof)?, load_space_in(&ctx, display)?);
+        let mut flags = Flags::SOFT_PROOFING;
+        if options.gamut_warning {
+            flags = flags | Flags::GAMUT_CHECK;
+        }
+        if options.black_point_compensation {
+            flags = flags | Flags::BLACKPOINT_COMPENSATION;
+        }
+
+        let transform = Transform::new_proofing_context(
+            &ctx,
+            &source,
+            PixelFormat::RGB_8,
+            &display,
+            PixelFormat::RGB_8,
+            &proof,
+            options.intent.into(),
+            // Keep the display's white rather than simulating paper
+            Intent::RelativeColorimetric,
+            flags,
+        )
+        .map_err(|e| ColorError::IccError(e.to_string()))?;
+        Ok(Self {
+            transform,
+            _ctx: ctx,
+        })
+    }
+
+    /// Convert 8-bit `pixels` in place; `channels` is 3 for RGB or 4 for RGBA
+    pub fn apply(&self, pixels: &mut [u8], channels: usize) -> Result<()> {
+        check_buffer(pixels, channels)?;
+
+        let mut scratch = Vec::with_capacity(CHUNK_PIXELS);
+        for chunk in pixels.chunks_mut(CHUNK_PIXELS * channels) {
+            scratch.clear();
+            scratch.extend(chunk.chunks_exact(channels).map(|p| [p[0], p[1], p[2]]));
+            self.transform.transform_in_place(&mut scratch);
+            for (pixel, rgb) in chunk.chunks_exact_mut(channels).zip(&scratch) {
+                pixel[..3].copy_from_slice(rgb);
+            }
+        }
+        Ok(())
+    }
+}
+
+/// Alarm codes belong to the context, so each proof gets its own
+fn proof_context() -> ThreadContext {
+    let mut ctx = ThreadContext::new();
+    let [r, g, b] = GAMUT_WARNING.map(|c| c as u16 * 257);
+    let mut alarm = [0; 16];
+    alarm[..3].copy_from_slice(&[r, g, b]);
+    ctx.set_alarm_codes(alarm);
+    ctx
+}
+
+/// One-off soft proof of 8-bit `pixels` from `source` through `proof` to
+/// `display`
+pub fn soft_proof(
+    pixels: &mut [u8],
+    channels: usize,
+    source: &str,
+    proof: &str,
+    display: &str,
+    options: ProofOptions,
+) -> Result<()> {
+    SoftProof::new(source, proof, display, options)?.apply(pixels, channels)
+}
+
+/// Interleaved RGB or RGBA holding whole pixels
+pub(crate) fn check_buffer<T>(pixels: &[T], channels: usize) -> Result<()> {
+    if channels != 3 && channels != 4 {
+        return Err(ColorError::Buffer(format!(
+            "{} channels per pixel; only RGB (3) and RGBA (4) are supported",
+            channels
+        )));
+    }
+    if !pixels.len().is_multiple_of(channels) {
+        return Err(ColorError::Buffer(format!(
+            "{} values is not a whole number of {}-channel pixels",
+            pixels.len(),
+            channels
+        )));
+    }
+    Ok(())
+}
+
+/// The ICC profile of a built-in space or profile on disk, for embedding
+pub(crate) fn space_icc(space: &str) -> Result<Vec<u8>> {
+    load_space(space)?
+        .icc()
+        .map_err(|e| ColorError::IccError(e.to_string()))
+}
+
+/// [`load_space`] opened in `ctx` rather than the global context
+fn load_space_in(ctx: &ThreadContext, space: &str) -> Result<Profile<ThreadContext>> {
+    let icc_error = |e: lcms2::Error| ColorError::IccError(e.to_string());
+    let icc = load_space(space)?.icc().map_err(icc_error)?;
+    Profile::new_icc_context(ctx, &icc).map_err(icc_error)
+}
+
+/// A built-in space by name, or the ICC profile at `space`
+fn load_space(space: &str) -> Result<Profile> {
+    let icc_error = |e: lcms2::Error| ColorError::IccError(e.to_string());
+    let name = space.to_ascii_lowercase();
+
+    // sRGB's piecewise curve; Display P3 shares it
+    let srgb_curve =
+        || ToneCurve::new_parametric(4, &[2.4, 1.0 / 1.055, 0.055 / 1.055, 1.0 / 12.92, 0.04045]);
+    // BT.709's camera curve, inverted for display; BT.2020 shares it
+    let bt709_curve = || {
+        ToneCurve::new_parametric(
+            4,
+            &[1.0 / 0.45, 1.0 / 1.099, 0.099 / 1.099, 1.0 / 4.5, 0.081],
+        )
+    };
+    let profile = match name.as_str() {
+        "srgb" => return Ok(Profile::new_srgb()),
+        "linear-srgb" => rgb_profile(ColorGamut::Srgb, &ToneCurve::new(1.0)),
+        "rec709" => rgb_profile(ColorGamut::Srgb, &bt709_curve().map_err(icc_error)?),
+        "display-p3" => rgb_profile(ColorGamut::DciP3, &srgb_curve().map_err(icc_error)?),
+        // 563/256, as in Adobe's specification
+        "adobe-rgb" => rgb_profile(ColorGamut::AdobeRgb, &ToneCurve::new(2.19921875)),
+        "rec2020" => rgb_profile(ColorGamut::Bt2020, &bt709_curve().map_err(icc_error)?),
+        "acescg" => primaries_profile(ACES_WHITE, AP1, &ToneCurve::new(1.0)),
+        "aces2065-1" => primaries_profile(ACES_WHITE, AP0, &ToneCurve::new(1.0)),
+        _ => {
+            let path = Path::new(space);
+            if !path.is_file() {
+                return Err(ColorError::IccError(format!(
+                    "{:?} is neither a built-in space ({}) nor an ICC profile",
+                    space,
+                    BUILTIN_SPACES.join(", ")
+                )));
+            }
+            return Profile::new_file(path)
+                .map_err(|e| ColorError::IccError(format!("Can't load {:?}: {}", path, e)));
+        }
+    };
+    profile.map_err(icc_error)
+}
+
+/// A linear-light profile with the colorants of matrix-shaper `profile`,
+/// and the luminance of each; `None` for other profiles
+fn linear_twin(profile: &Profile) -> lcms2::LCMSResult<Option<(Profile, [f32; 3])>> {
+    if !profile.is_matrix_shaper() {
+        return Ok(None);
+    }
+    let colorant = |sig| match profile.read_tag(sig) {
+        Tag::CIEXYZ(xyz) => Some(*xyz),
+        _ => None,
+    };
+    let (Some(red), Some(green), Some(blue)) = (
+        colorant(TagSignature::RedColorantTag),
+        colorant(TagSignature::GreenColorantTag),
+        colorant(TagSignature::BlueColorantTag),
+    ) else {
+        return Ok(None);
+    };
+
+    // Colorants are already adapted to D50, so the twin's white is D50 too
+    let linear = ToneCurve::new(1.0);
+    let twin = Profile::new_rgb(
+        &lcms2::XYZ2xyY(&D50),
+        &CIExyYTRIPLE {
+            Red: lcms2::XYZ2xyY(&red),
+            Green: lcms2::XYZ2xyY(&green),
+            Blue: lcms2::XYZ2xyY(&blue),
+        },
+        &[&linear, &linear, &linear],
+    )?;
+    let weights = [red.Y, green.Y, blue.Y].map(|y| y as f32);
+    Ok(Some((twin, weights)))
+}
+
+fn rgb_profile(gamut: ColorGamut, curve: &ToneCurve) -> lcms2::LCMSResult<Profile> {
+    primaries_profile(D65, gamut.primaries().unwrap_or_default(), curve)
+}
+
+fn primaries_profile(
+    white: (f64, f64),
+    [red, green, blue]: [(f64, f64); 3],
+    curve: &ToneCurve,
+) -> lcms2::LCMSResult<Profile> {
+    let xy = |(x, y): (f64, f64)| CIExyY { x, y, Y: 1.0 };
+    Profile::new_rgb(
+        &xy(white),
+        &CIExyYTRIPLE {
+            Red: xy(red),
+            Green: xy(green),
+            Blue: xy(blue),
+        },
+        &[curve, curve, curve],
+    )
+}
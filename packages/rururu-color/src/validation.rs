@@ -0,0 +1,828 @@
+//! Checks for broken and poor-quality ICC profiles.
+//!
+//! [`IccManager::validate`] reads a profile the way a CMM would and lists
+//! what is wrong with it:
+//!
+//! - The header: the `acsp` signature, a version CMMs understand, a known
+//!   class, color space and PCS, a declared size matching the file, a D50
+//!   illuminant, and for v4 profiles the MD5 Profile ID
+//! - The tag table: every tag inside the file, and the tags ICC.1 requires
+//!   for the profile's class, e.g. colorants and curves or A2B0/B2A0 tables
+//!   for a display profile
+//! - Tone curves, which must rise from black to white without dipping
+//! - The white point, which should be near daylight or a blackbody: a
+//!   correlated color temperature of 3000–10000 K and within 0.02 Δuv of
+//!   the Planckian locus
+//! - For RGB profiles, the gamut volume in CIELAB, from a grid of device
+//!   colors through lcms2, compared with sRGB's. Parts of the grid that
+//!   turn inside out show the tables fold colors over each other.
+//!
+//! Each issue costs the 100-point quality score according to its severity.
+
+use crate::icc::{
+    be_u32, color_space, profile_class, read_xyz, s15_fixed16, to_xy, ColorSpace, IccManager,
+    IccProfileInfo, ProfileClass, D50_XYZ,
+};
+use lcms2::{CIExyY, GlobalContext, Intent, PixelFormat, Profile, Transform};
+use md5::{Digest, Md5};
+use std::collections::HashMap;
+use std::fmt;
+use std::path::Path;
+
+/// Score at or above which a profile without errors is worth trusting
+pub const TRUSTED_SCORE: u8 = 70;
+
+/// Gamut below this share of sRGB's volume is too small to edit in
+const SMALL_GAMUT_PERCENT: f64 = 50.0;
+/// Below this share the colorants or tables have all but collapsed
+const DEGENERATE_GAMUT_PERCENT: f64 = 10.0;
+/// Share of the gamut volume allowed to fold over before it is reported
+const FOLD_PERCENT: f64 = 1.0;
+/// Device values per channel sampled for the gamut volume
+const VOLUME_STEPS: usize = 9;
+
+/// Largest drop between neighbouring curve entries put down to rounding
+/// in measured curves; 1/1024 of full scale
+const CURVE_NOISE: u16 = 64;
+
+const MATRIX_SHAPER_TAGS: [&[u8; 4]; 6] = [b"rXYZ", b"gXYZ", b"bXYZ", b"rTRC", b"gTRC", b"bTRC"];
+const TRC_TAGS: [&[u8; 4]; 4] = [b"rTRC", b"gTRC", b"bTRC", b"kTRC"];
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord)]
+pub enum Severity {
+    /// Worth knowing, no effect on color
+    Info,
+    /// Colors may be off, or some CMMs reject the profile
+    Warning,
+    /// Color management through this profile is broken
+    Error,
+}
+
+impl Severity {
+    fn penalty(self) -> u8 {
+        match self {
+            Severity::Info => 2,
+            Severity::Warning => 10,
+            Severity::Error => 35,
+        }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub enum IccIssue {
+    Unreadable(String),
+    /// Shorter than its header says, or too short for a header
+    Truncated {
+        declared: u32,
+        actual: usize,
+    },
+    /// Bytes after the size the header declares
+    TrailingData {
+        declared: u32,
+        actual: usize,
+    },
+    BadSignature,
+    UnsupportedVersion {
+        major: u8,
+        minor: u8,
+    },
+    UnknownClass([u8; 4]),
+    UnknownColorSpace([u8; 4]),
+    BadPcs([u8; 4]),
+    /// The v4 Profile ID isn't the MD5 of the profile, so it was changed
+    /// after the ID was written or damaged since
+    ChecksumMismatch,
+    /// Header illuminant other than D50, as XYZ
+    NonD50Illuminant([f64; 3]),
+    TruncatedTagTable,
+    TagOutOfBounds([u8; 4]),
+    /// A tag the profile's class needs; `essential` when color can't be
+    /// converted without it
+    MissingTag {
+        tag: [u8; 4],
+        essential: bool,
+    },
+    /// A tag of a type it can't hold, or too short for its type
+    MalformedTag([u8; 4]),
+    NonMonotonicCurve([u8; 4]),
+    FlatCurve([u8; 4]),
+    UnusualGamma {
+        tag: [u8; 4],
+        gamma: f64,
+    },
+    /// White far from daylight or a blackbody, as CIE xy
+    UnusualWhitePoint {
+        x: f64,
+        y: f64,
+        cct: f64,
+        duv: f64,
+    },
+    /// `wtpt` luminance other than 1
+    WhiteLuminance(f64),
+    /// lcms2 couldn't build a transform to CIELAB from the profile
+    NoTransform(String),
+    /// The device colors map onto almost nothing
+    DegenerateGamut,
+    SmallGamut {
+        percent_of_srgb: f64,
+    },
+    /// Share of the gamut volume that the tables turn inside out
+    GamutFolds {
+        percent: f64,
+    },
+}
+
+impl IccIssue {
+    pub fn severity(&self) -> Severity {
+        match self {
+            IccIssue::TrailingData { .. } => Severity::Info,
+            IccIssue::MissingTag {
+                essential: false, ..
+            }
+            | IccIssue::UnsupportedVersion { major: 5, .. }
+            | IccIssue::NonD50Illuminant(_)
+            | IccIssue::UnusualGamma { .. }
+            | IccIssue::UnusualWhitePoint { .. }
+            | IccIssue::WhiteLuminance(_)
+            | IccIssue::SmallGamut { .. }
+            | IccIssue::GamutFolds { .. } => Severity::Warning,
+            _ => Severity::Error,
+        }
+    }
+}
+
+fn tag_name(sig: &[u8; 4]) -> String {
+    String::from_utf8_lossy(sig).trim_end().to_string()
+}
+
+impl fmt::Display for IccIssue {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            IccIssue::Unreadable(e) => write!(f, "Can't read the profile: {}", e),
+            IccIssue::Truncated { declared, actual } => write!(
+                f,
+                "Truncated: {} bytes where the header says {}",
+                actual, declared
+            ),
+            IccIssue::TrailingData { declared, actual } => write!(
+                f,
+                "{} bytes after the {} the header declares",
+                actual - *declared as usize,
+                declared
+            ),
+            IccIssue::BadSignature => write!(f, "Not an ICC profile: no 'acsp' signature"),
+            IccIssue::UnsupportedVersion { major, minor } => {
+                write!(f, "ICC version {}.{} isn't widely supported", major, minor)
+            }
+            IccIssue::UnknownClass(sig) => write!(f, "Unknown profile class '{}'", tag_name(sig)),
+            IccIssue::UnknownColorSpace(sig) => {
+                write!(f, "Unknown color space '{}'", tag_name(sig))
+            }
+            IccIssue::BadPcs(sig) => write!(f, "'{}' is not a PCS (XYZ or Lab)", tag_name(sig)),
+            IccIssue::ChecksumMismatch => {
+                write!(
+                    f,
+                    "Profile ID doesn't match the contents; it was altered or damaged"
+                )
+            }
+            IccIssue::NonD50Illuminant([x, y, z]) => write!(
+                f,
+                "Header illuminant is XYZ {:.4} {:.4} {:.4} rather than D50",
+                x, y, z
+            ),
+            IccIssue::TruncatedTagTable => write!(f, "Tag table runs past the end of the file"),
+            IccIssue::TagOutOfBounds(sig) => {
+                write!(f, "Tag '{}' points outside the file", tag_name(sig))
+            }
+            IccIssue::MissingTag {
+                tag,
+                essential: true,
+            } => write!(f, "Missing required tag '{}'", tag_name(tag)),
+            IccIssue::MissingTag { tag, .. } => write!(f, "Missing tag '{}'", tag_name(tag)),
+            IccIssue::MalformedTag(sig) => write!(f, "Tag '{}' is malformed", tag_name(sig)),
+            IccIssue::NonMonotonicCurve(sig) => {
+                write!(f, "Tone curve '{}' doesn't rise steadily", tag_name(sig))
+            }
+            IccIssue::FlatCurve(sig) => write!(f, "Tone curve '{}' is flat", tag_name(sig)),
+            IccIssue::UnusualGamma { tag, gamma } => {
+                write!(
+                    f,
+                    "Tone curve '{}' has an unusual gamma of {:.2}",
+                    tag_name(tag),
+                    gamma
+                )
+            }
+            IccIssue::UnusualWhitePoint { x, y, cct, duv } => write!(
+                f,
+                "White point x {:.4} y {:.4} ({:.0} K, Δuv {:.3}) is an unusual white",
+                x, y, cct, duv
+            ),
+            IccIssue::WhiteLuminance(y) => {
+                write!(f, "White point luminance is {:.3} rather than 1", y)
+            }
+            IccIssue::NoTransform(e) => write!(f, "Can't convert colors with it: {}", e),
+            IccIssue::DegenerateGamut => write!(f, "Its gamut holds almost no colors"),
+            IccIssue::SmallGamut { percent_of_srgb } => {
+                write!(f, "Gamut is only {:.0}% of sRGB's volume", percent_of_srgb)
+            }
+            IccIssue::GamutFolds { percent } => {
+                write!(f, "{:.1}% of its gamut folds over itself", percent)
+            }
+        }
+    }
+}
+
+/// Issues found in one profile, with its score
+#[derive(Debug, Clone, PartialEq)]
+pub struct IccReport {
+    pub issues: Vec<IccIssue>,
+    /// CIELAB volume of an RGB profile's gamut as a percentage of sRGB's
+    pub gamut_volume: Option<f64>,
+    /// 0–100
+    pub score: u8,
+}
+
+impl IccReport {
+    pub fn worst(&self) -> Option<Severity> {
+        self.issues.iter().map(IccIssue::severity).max()
+    }
+
+    /// No errors and a score of at least [`TRUSTED_SCORE`]
+    pub fn is_trustworthy(&self) -> bool {
+        self.worst() != Some(Severity::Error) && self.score >= TRUSTED_SCORE
+    }
+}
+
+/// 100, less each issue's penalty
+pub fn quality_score(issues: &[IccIssue]) -> u8 {
+    issues.iter().fold(100u8, |score, issue| {
+        score.saturating_sub(issue.severity().penalty())
+    })
+}
+
+impl IccManager {
+    /// Problems with the profile at `path`, most fundamental first; empty
+    /// for a sound profile
+    pub fn validate(path: &Path) -> Vec<IccIssue> {
+        Self::report(path).issues
+    }
+
+    /// [`validate`](IccManager::validate), with the gamut volume and score
+    pub fn report(path: &Path) -> IccReport {
+        match std::fs::read(path) {
+            Ok(data) => validate_data(&data),
+            Err(e) => {
+                let issues = vec![IccIssue::Unreadable(e.to_string())];
+                IccReport {
+                    score: quality_score(&issues),
+                    issues,
+                    gamut_volume: None,
+                }
+            }
+        }
+    }
+}
+
+/// Validate a profile already in memory
+pub fn validate_data(data: &[u8]) -> IccReport {
+    let mut issues = Vec::new();
+    let mut gamut_volume = None;
+
+    if check_header(data, &mut issues) {
+        let tags = check_tag_table(data, &mut issues);
+        check_required_tags(data, &tags, &mut issues);
+        for sig in TRC_TAGS {
+            if let Some(tag) = tags.get(sig) {
+                check_curve(sig, tag, &mut issues);
+            }
+        }
+        check_white(&tags, data, &mut issues);
+
+        // lcms2 is only handed profiles that are structurally sound
+        let sound = issues.iter().all(|i| i.severity() != Severity::Error);
+        if sound && color_space(&data[16..20]) == ColorSpace::RGB {
+            gamut_volume = check_gamut(data, &mut issues);
+        }
+    }
+
+    IccReport {
+        score: quality_score(&issues),
+        issues,
+        gamut_volume,
+    }
+}
+
+/// Whether the rest of the profile can be read
+fn check_header(data: &[u8], issues: &mut Vec<IccIssue>) -> bool {
+    let declared = be_u32(data, 0).unwrap_or(0);
+    if data.len() < 132 || (declared as usize) < 132 {
+        issues.push(IccIssue::Truncated {
+            declared,
+            actual: data.len(),
+        });
+        return false;
+    }
+    if &data[36..40] != b"acsp" {
+        issues.push(IccIssue::BadSignature);
+        return false;
+    }
+    match (declared as usize).cmp(&data.len()) {
+        std::cmp::Ordering::Greater => {
+            issues.push(IccIssue::Truncated {
+                declared,
+                actual: data.len(),
+            });
+            return false;
+        }
+        std::cmp::Ordering::Less => issues.push(IccIssue::TrailingData {
+            declared,
+            actual: data.len(),
+        }),
+        std::cmp::Ordering::Equal => {}
+    }
+
+    let (major, minor) = (data[8], data[9] >> 4);
+    if !matches!(major, 2 | 4) {
+        issues.push(IccIssue::UnsupportedVersion { major, minor });
+    }
+    let sig = |at: usize| [data[at], data[at + 1], data[at + 2], data[at + 3]];
+    if profile_class(&data[12..16]) == ProfileClass::Unknown {
+        issues.push(IccIssue::UnknownClass(sig(12)));
+    }
+    if color_space(&data[16..20]) == ColorSpace::Unknown {
+        issues.push(IccIssue::UnknownColorSpace(sig(16)));
+    }
+    // Device links keep a device space here instead
+    if profile_class(&data[12..16]) != ProfileClass::DeviceLink
+        && !matches!(&data[20..24], b"XYZ " | b"Lab ")
+    {
+        issues.push(IccIssue::BadPcs(sig(20)));
+    }
+
+    let illuminant = [68, 72, 76].map(|at| s15_fixed16(data, at).unwrap_or(0.0));
+    if illuminant
+        .iter()
+        .zip(D50_XYZ)
+        .any(|(v, d50)| (v - d50).abs() > 0.002)
+    {
+        issues.push(IccIssue::NonD50Illuminant(illuminant));
+    }
+
+    // The ID is the MD5 of the profile with the flags, intent and ID zeroed;
+    // all zeros means it was never computed
+    let id = &data[84..100];
+    if id.iter().any(|&b| b != 0) {
+        let mut copy = data[..declared as usize].to_vec();
+        copy[44..48].fill(0);
+        copy[64..68].fill(0);
+        copy[84..100].fill(0);
+        if Md5::digest(&copy)[..] != *id {
+            issues.push(IccIssue::ChecksumMismatch);
+        }
+    }
+    true
+}
+
+/// Tags by signature, leaving out those outside the file
+fn check_tag_table<'a>(data: &'a [u8], issues: &mut Vec<IccIssue>) -> HashMap<[u8; 4], &'a [u8]> {
+    let end = data.len().min(be_u32(data, 0).unwrap_or(0) as usize);
+    let count = be_u32(data, 128).unwrap_or(0) as usize;
+    let mut tags = HashMap::new();
+
+    for i in 0..count {
+        let entry = 132 + i * 12;
+        let (Some(sig), Some(offset), Some(size)) = (
+            data.get(entry..entry + 4),
+            be_u32(data, entry + 4),
+            be_u32(data, entry + 8),
+        ) else {
+            issues.push(IccIssue::TruncatedTagTable);
+            break;
+        };
+        let sig = [sig[0], sig[1], sig[2], sig[3]];
+        let (offset, size) = (offset as usize, size as usize);
+        match data[..end].get(offset..offset.saturating_add(size)) {
+            Some(tag) => {
+                tags.insert(sig, tag);
+            }
+            None => issues.push(IccIssue::TagOutOfBounds(sig)),
+        }
+    }
+    tags
+}
+
+/// The tags ICC.1 requires for the profile's class and color space. RGB
+/// and gray profiles other than printers may use curves and colorants in
+/// place of lookup tables.
+fn check_required_tags(data: &[u8], tags: &HashMap<[u8; 4], &[u8]>, issues: &mut Vec<IccIssue>) {
+    let has = |sig: &[u8; 4]| tags.contains_key(sig);
+    let mut missing = |sigs: &[&[u8; 4]], essential: bool| {
+        for sig in sigs.iter().filter(|sig| !has(sig)) {
+            issues.push(IccIssue::MissingTag {
+                tag: **sig,
+                essential,
+            });
+        }
+    };
+
+    missing(&[b"desc", b"cprt"], false);
+    let class = profile_class(&data[12..16]);
+    if class != ProfileClass::DeviceLink {
+        missing(&[b"wtpt"], true);
+    }
+
+    match class {
+        ProfileClass::DeviceLink => {
+            missing(&[b"A2B0"], true);
+            missing(&[b"pseq"], false);
+        }
+        ProfileClass::Abstract => missing(&[b"A2B0"], true),
+        ProfileClass::NamedColor => missing(&[b"ncl2"], true),
+        ProfileClass::Unknown => {}
+        _ => {
+            let tables: &[&[u8; 4]] = match class {
+                ProfileClass::Input => &[b"A2B0"],
+                _ => &[b"A2B0", b"B2A0"],
+            };
+            let shaper: &[&[u8; 4]] = match color_space(&data[16..20]) {
+                ColorSpace::Gray => &[b"kTRC"],
+                ColorSpace::RGB if class != ProfileClass::Output => &MATRIX_SHAPER_TAGS,
+                _ => &[],
+            };
+
+            let has_tables = tables.iter().all(|sig| has(sig));
+            let has_shaper = !shaper.is_empty() && shaper.iter().all(|sig| has(sig));
+            if !has_tables && !has_shaper {
+                // Whichever the profile has started on
+                if shaper.iter().any(|sig| has(sig)) {
+                    missing(shaper, true);
+                } else {
+                    missing(tables, true);
+                }
+            }
+            if class == ProfileClass::Output && has_tables {
+                missing(&[b"A2B1", b"A2B2", b"B2A1", b"B2A2", b"gamt"], false);
+            }
+        }
+    }
+}
+
+/// A `curv` or `para` tone curve rising from black to white
+fn check_curve(sig: &[u8; 4], tag: &[u8], issues: &mut Vec<IccIssue>) {
+    let sig = *sig;
+    let samples: Vec<f64> = match tag.get(0..4) {
+        Some(b"curv") => {
+            let Some(count) = be_u32(tag, 8).map(|c| c as usize) else {
+                issues.push(IccIssue::MalformedTag(sig));
+                return;
+            };
+            let Some(entries) = tag.get(12..12 + count * 2) else {
+                issues.push(IccIssue::MalformedTag(sig));
+                return;
+            };
+            let entries: Vec<u16> = entries
+                .chunks_exact(2)
+                .map(|c| u16::from_be_bytes([c[0], c[1]]))
+                .collect();
+            match entries[..] {
+                // Identity
+                [] => return,
+                [gamma] => {
+                    check_gamma(sig, gamma as f64 / 256.0, issues);
+                    return;
+                }
+                _ => {
+                    let dips = entries
+                        .windows(2)
+                        .any(|w| w[0] > w[1] && w[0] - w[1] > CURVE_NOISE);
+                    if dips {
+                        issues.push(IccIssue::NonMonotonicCurve(sig));
+                        return;
+                    }
+                    entries.iter().map(|&v| v as f64 / 65535.0).collect()
+                }
+            }
+        }
+        Some(b"para") => {
+            let params = tag.get(8..10).and_then(|kind| {
+                let kind = u16::from_be_bytes([kind[0], kind[1]]);
+                let count = [1, 3, 4, 5, 7].get(kind as usize)?;
+                let params: Option<Vec<f64>> =
+                    (0..*count).map(|i| s15_fixed16(tag, 12 + i * 4)).collect();
+                Some((kind, params?))
+            });
+            let Some((kind, params)) = params else {
+                issues.push(IccIssue::MalformedTag(sig));
+                return;
+            };
+            check_gamma(sig, params[0], issues);
+            if params[0] <= 0.0 {
+                return;
+            }
+            (0..=255)
+                .map(|i| parametric(kind, &params, i as f64 / 255.0))
+                .collect()
+        }
+        _ => {
+            issues.push(IccIssue::MalformedTag(sig));
+            return;
+        }
+    };
+
+    if samples.windows(2).any(|w| w[1] < w[0] - 1e-3) {
+        issues.push(IccIssue::NonMonotonicCurve(sig));
+    } else if samples.last().copied().unwrap_or(0.0) - samples[0] < 0.01 {
+        issues.push(IccIssue::FlatCurve(sig));
+    }
+}
+
+/// Gammas outside 1–3 (linear to very dark) are almost always mistakes
+fn check_gamma(sig: [u8; 4], gamma: f64, issues: &mut Vec<IccIssue>) {
+    if gamma <= 0.0 {
+        issues.push(IccIssue::FlatCurve(sig));
+    } else if !(1.0..=3.0).contains(&gamma) {
+        issues.push(IccIssue::UnusualGamma { tag: sig, gamma });
+    }
+}
+
+/// ICC.1 parametric curve functions 0–4
+fn parametric(kind: u16, p: &[f64], x: f64) -> f64 {
+    let pow = |base: f64| base.max(0.0).powf(p[0]);
+    match kind {
+        0 => pow(x),
+        1 if x >= -p[2] / p[1] => pow(p[1] * x + p[2]),
+        1 => 0.0,
+        2 if x >= -p[2] / p[1] => pow(p[1] * x + p[2]) + p[3],
+        2 => p[3],
+        3 if x >= p[4] => pow(p[1] * x + p[2]),
+        3 => p[3] * x,
+        _ if x >= p[4] => pow(p[1] * x + p[2]) + p[5],
+        _ => p[3] * x + p[6],
+    }
+}
+
+/// The device white, before adaptation to D50, should be a plausible white
+fn check_white(tags: &HashMap<[u8; 4], &[u8]>, data: &[u8], issues: &mut Vec<IccIssue>) {
+    let Some(tag) = tags.get(b"wtpt") else {
+        return;
+    };
+    let Some(wtpt) = read_xyz(tag) else {
+        issues.push(IccIssue::MalformedTag(*b"wtpt"));
+        return;
+    };
+    if (wtpt[1] - 1.0).abs() > 0.05 {
+        issues.push(IccIssue::WhiteLuminance(wtpt[1]));
+    }
+
+    let white = IccProfileInfo::parse(data)
+        .ok()
+        .and_then(|info| info.white_point)
+        .or_else(|| to_xy(wtpt));
+    let Some((x, y)) = white else {
+        issues.push(IccIssue::MalformedTag(*b"wtpt"));
+        return;
+    };
+
+    let cct = mccamy_cct(x, y);
+    let (u, v) = uv(x, y);
+    let (pu, pv) = planckian_uv(cct.clamp(1000.0, 15000.0));
+    let duv = ((u - pu).powi(2) + (v - pv).powi(2)).sqrt();
+    if !(3000.0..=10000.0).contains(&cct) || duv > 0.02 {
+        issues.push(IccIssue::UnusualWhitePoint { x, y, cct, duv });
+    }
+}
+
+/// McCamy's approximation of the correlated color temperature
+pub(crate) fn mccamy_cct(x: f64, y: f64) -> f64 {
+    let n = (x - 0.3320) / (0.1858 - y);
+    449.0 * n.powi(3) + 3525.0 * n.powi(2) + 6823.3 * n + 5520.33
+}
+
+/// CIE 1960 UCS coordinates
+fn uv(x: f64, y: f64) -> (f64, f64) {
+    let d = -2.0 * x + 12.0 * y + 3.0;
+    (4.0 * x / d, 6.0 * y / d)
+}
+
+/// Krystek's rational approximation of the Planckian locus, 1000–15000 K
+fn planckian_uv(t: f64) -> (f64, f64) {
+    let u = (0.860117757 + 1.54118254e-4 * t + 1.28641212e-7 * t * t)
+        / (1.0 + 8.42420235e-4 * t + 7.08145163e-7 * t * t);
+    let v = (0.317398726 + 4.22806245e-5 * t + 4.20481691e-8 * t * t)
+        / (1.0 - 2.89741816e-5 * t + 1.61456053e-7 * t * t);
+    (u, v)
+}
+
+/// Gamut volume as a percentage of sRGB's, reporting small, collapsed and
+/// folded gamuts
+fn check_gamut(data: &[u8], issues: &mut Vec<IccIssue>) -> Option<f64> {
+    let volume = Profile::new_icc(data)
+        .map_err(|e| e.to_string())
+        .and_then(|p| lab_volume(&p).map_err(|e| e.to_string()));
+    let (volume, folded) = match volume {
+        Ok(volume) => volume,
+        Err(e) => {
+            issues.push(IccIssue::NoTransform(e));
+            return None;
+        }
+    };
+    let (srgb, _) = lab_volume(&Profile::new_srgb()).ok()?;
+
+    let percent = volume / srgb * 100.0;
+    if percent < DEGENERATE_GAMUT_PERCENT {
+        issues.push(IccIssue::DegenerateGamut);
+    } else if percent < SMALL_GAMUT_PERCENT {
+        issues.push(IccIssue::SmallGamut {
+            percent_of_srgb: percent,
+        });
+    }
+    let folded = folded / volume.max(f64::EPSILON) * 100.0;
+    if percent >= DEGENERATE_GAMUT_PERCENT && folded > FOLD_PERCENT {
+        issues.push(IccIssue::GamutFolds { percent: folded });
+    }
+    Some(percent)
+}
+
+/// CIELAB volume of an RGB profile's gamut and how much of it is inside
+/// out. The device cube is split into cells of six tetrahedra each; mapped
+/// to Lab they keep their orientation unless the tables fold colors over.
+fn lab_volume(profile: &Profile) -> lcms2::LCMSResult<(f64, f64)> {
+    let d50 = CIExyY {
+        x: 0.3457,
+        y: 0.3585,
+        Y: 1.0,
+    };
+    let lab = Profile::new_lab4_context(GlobalContext::new(), &d50)?;
+    let transform = Transform::new(
+        profile,
+        PixelFormat::RGB_DBL,
+        &lab,
+        PixelFormat::Lab_DBL,
+        Intent::RelativeColorimetric,
+    )?;
+
+    let n = VOLUME_STEPS;
+    let step = |i: usize| i as f64 / (n - 1) as f64;
+    let rgb: Vec<[f64; 3]> = (0..n * n * n)
+        .map(|i| [step(i / (n * n)), step(i / n % n), step(i % n)])
+        .collect();
+    let mut points = vec![[0.0f64; 3]; rgb.len()];
+    transform.transform_pixels(&rgb, &mut points);
+
+    let at = |r: usize, g: usize, b: usize| points[(r * n + g) * n + b];
+    // Each order the three axes can be walked in, corner to corner
+    const PATHS: [[usize; 3]; 6] = [
+        [0, 1, 2],
+        [0, 2, 1],
+        [1, 0, 2],
+        [1, 2, 0],
+        [2, 0, 1],
+        [2, 1, 0],
+    ];
+    let mut volumes = Vec::with_capacity((n - 1).pow(3) * 6);
+    for r in 0..n - 1 {
+        for g in 0..n - 1 {
+            for b in 0..n - 1 {
+                for path in PATHS {
+                    let mut corner = [r, g, b];
+                    let mut vertices = [at(r, g, b); 4];
+                    for (vertex, axis) in vertices[1..].iter_mut().zip(path) {
+                        corner[axis] += 1;
+                        *vertex = at(corner[0], corner[1], corner[2]);
+                    }
+                    // The paths alternate in handedness
+                    let sign = if matches!(path, [0, 1, 2] | [1, 2, 0] | [2, 0, 1]) {
+                        1.0
+                    } else {
+                        -1.0
+                    };
+                    volumes.push(sign * signed_volume(&vertices));
+                }
+            }
+        }
+    }
+
+    let total: f64 = volumes.iter().sum();
+    let folded: f64 = volumes
+        .iter()
+        .filter(|v| v.signum() != total.signum())
+        .map(|v| v.abs())
+        .sum();
+    Ok((total.abs(), folded))
+}
+
+fn signed_volume([a, b, c, d]: &[[f64; 3]; 4]) -> f64 {
+    let sub = |p: &[f64; 3]| [p[0] - a[0], p[1] - a[1], p[2] - a[2]];
+    let (u, v, w) = (sub(b), sub(c), sub(d));
+    let cross = [
+        v[1] * w[2] - v[2] * w[1],
+        v[2] * w[0] - v[0] * w[2],
+        v[0] * w[1] - v[1] * w[0],
+    ];
+    (u[0] * cross[0] + u[1] * cross[1] + u[2] * cross[2]) / 6.0
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use lcms2::{CIExyYTRIPLE, ToneCurve};
+
+    /// lcms2's sRGB, with the Profile ID lcms2 leaves out filled in
+    fn srgb() -> Vec<u8> {
+        let mut data = Profile::new_srgb().icc().unwrap();
+        let mut copy = data.clone();
+        copy[44..48].fill(0);
+        copy[64..68].fill(0);
+        let id = Md5::digest(&copy);
+        data[84..100].copy_from_slice(&id);
+        data
+    }
+
+    fn has(report: &IccReport, issue: impl Fn(&IccIssue) -> bool) -> bool {
+        report.issues.iter().any(issue)
+    }
+
+    #[test]
+    fn test_srgb_is_sound() {
+        let report = validate_data(&srgb());
+        assert!(report.issues.is_empty(), "{:?}", report.issues);
+        assert_eq!(report.score, 100);
+        assert!(report.is_trustworthy());
+        let volume = report.gamut_volume.unwrap();
+        assert!(volume > 0.0);
+    }
+
+    #[test]
+    fn test_truncated() {
+        let data = srgb();
+        let report = validate_data(&data[..data.len() - 100]);
+        assert_eq!(
+            report.issues,
+            vec![IccIssue::Truncated {
+                declared: data.len() as u32,
+                actual: data.len() - 100,
+            }]
+        );
+
+        let report = validate_data(&data[..64]);
+        assert!(matches!(report.issues[..], [IccIssue::Truncated { .. }]));
+        assert!(!report.is_trustworthy());
+    }
+
+    #[test]
+    fn test_bad_signature() {
+        let mut data = srgb();
+        data[36..40].copy_from_slice(b"xxxx");
+        let report = validate_data(&data);
+        assert_eq!(report.issues, vec![IccIssue::BadSignature]);
+        assert_eq!(report.worst(), Some(Severity::Error));
+    }
+
+    #[test]
+    fn test_profile_id_mismatch() {
+        let mut data = srgb();
+        // The creator, which the ID covers
+        data[80] ^= 0xff;
+        let report = validate_data(&data);
+        assert_eq!(report.issues, vec![IccIssue::ChecksumMismatch]);
+
+        // Fields the ID leaves out can change freely
+        let mut data = srgb();
+        data[64] = 3;
+        assert!(validate_data(&data).issues.is_empty());
+    }
+
+    #[test]
+    fn test_non_monotonic_curve() {
+        let d65 = CIExyY {
+            x: 0.3127,
+            y: 0.3290,
+            Y: 1.0,
+        };
+        let primaries = CIExyYTRIPLE {
+            Red: CIExyY {
+                x: 0.64,
+                y: 0.33,
+                Y: 1.0,
+            },
+            Green: CIExyY {
+                x: 0.30,
+                y: 0.60,
+                Y: 1.0,
+            },
+            Blue: CIExyY {
+                x: 0.15,
+                y: 0.06,
+                Y: 1.0,
+            },
+        };
+        let dipping = ToneCurve::new_tabulated(&[0, 20000, 40000, 30000, 50000, 65535]);
+        let gamma = ToneCurve::new(2.2);
+        let profile = Profile::new_rgb(&d65, &primaries, &[&dipping, &gamma, &gamma]).unwrap();
+
+        let report = validate_data(&profile.icc().unwrap());
+        assert!(has(&report, |i| *i == IccIssue::NonMonotonicCurve(*b"rTRC")));
+        assert!(!has(&report, |i| *i == IccIssue::NonMonotonicCurve(*b"gTRC")));
+        assert!(!report.is_trustworthy());
+    }
+}
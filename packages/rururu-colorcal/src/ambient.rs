@@ -0,0 +1,101 @@
+//! Room lighting from an ambient light sensor, and the brightness to
+//! calibrate to under it.
+//!
+//! Laptops and some monitors expose their sensor through IIO as
+//! `/sys/bus/iio/devices/iio:deviceN/in_illuminance_*`: either a reading
+//! already in lux (`_input`) or a raw count with a scale and offset. The
+//! targets follow ISO 3664/12646: 80–120 cd/m² in a dim room, more only
+//! when the room is bright enough that a dimmer screen looks murky.
+
+use std::path::{Path, PathBuf};
+
+const IIO_DEVICES: &str = "/sys/bus/iio/devices";
+
+/// Rooms darker than this exaggerate on-screen contrast
+const TOO_DARK_LUX: f32 = 10.0;
+/// ISO 12646's limit for soft proofing
+const DIM_LUX: f32 = 64.0;
+/// Past this, room light washes out the black level
+const TOO_BRIGHT_LUX: f32 = 300.0;
+
+#[derive(Debug, Clone)]
+pub struct AmbientSensor {
+    dir: PathBuf,
+}
+
+impl AmbientSensor {
+    /// The first IIO device with an illuminance channel
+    pub fn find() -> Option<Self> {
+        let mut dirs: Vec<PathBuf> = std::fs::read_dir(IIO_DEVICES)
+            .ok()?
+            .flatten()
+            .map(|entry| entry.path())
+            .collect();
+        dirs.sort();
+
+        dirs.into_iter()
+            .find(|dir| {
+                dir.join("in_illuminance_input").exists() || dir.join("in_illuminance_raw").exists()
+            })
+            .map(|dir| Self { dir })
+    }
+
+    /// Current reading in lux
+    pub fn read_lux(&self) -> Option<f32> {
+        if let Some(lux) = read_value(&self.dir.join("in_illuminance_input")) {
+            return Some(lux);
+        }
+
+        let raw = read_value(&self.dir.join("in_illuminance_raw"))?;
+        let scale = read_value(&self.dir.join("in_illuminance_scale")).unwrap_or(1.0);
+        let offset = read_value(&self.dir.join("in_illuminance_offset")).unwrap_or(0.0);
+        Some((raw + offset) * scale)
+    }
+}
+
+fn read_value(path: &Path) -> Option<f32> {
+    std::fs::read_to_string(path).ok()?.trim().parse().ok()
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct BrightnessAdvice {
+    /// Target white luminance in cd/m²
+    pub target_luminance: u32,
+    /// Why the room isn't suited to accurate work, if it isn't
+    pub warning: Option<&'static str>,
+}
+
+/// White luminance to calibrate to at `lux`
+pub fn recommend(lux: f32) -> BrightnessAdvice {
+    if lux < TOO_DARK_LUX {
+        return BrightnessAdvice {
+            target_luminance: 80,
+            warning: Some("Room is very dark; a dim bias light behind the display keeps contrast judgments honest"),
+        };
+    }
+    if lux <= DIM_LUX {
+        // 80 cd/m² in a near-dark room up to 120 at the ISO 12646 limit
+        let t = (lux - TOO_DARK_LUX) / (DIM_LUX - TOO_DARK_LUX);
+        return BrightnessAdvice {
+            target_luminance: round_to_ten(80.0 + t * 40.0),
+            warning: None,
+        };
+    }
+    if lux <= TOO_BRIGHT_LUX {
+        let t = (lux - DIM_LUX) / (TOO_BRIGHT_LUX - DIM_LUX);
+        return BrightnessAdvice {
+            target_luminance: round_to_ten(120.0 + t * 40.0),
+            warning: Some(
+                "Room is brighter than the 64 lux ISO 12646 recommends for soft proofing",
+            ),
+        };
+    }
+    BrightnessAdvice {
+        target_luminance: 160,
+        warning: Some("Room is too bright for accurate work; dim the lights or close the blinds before calibrating"),
+    }
+}
+
+fn round_to_ten(luminance: f32) -> u32 {
+    ((luminance / 10.0).round() * 10.0) as u32
+}
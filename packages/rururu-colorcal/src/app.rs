@@ -1,8 +1,15 @@
-use crate::calibration::{CalibrationState, CalibrationStep};
+use crate::ambient::{self, AmbientSensor};
+use crate::calibration::{CalibrationState, CalibrationStep, CandidateRamp};
 use crate::icc::IccProfile;
 use crate::patterns::TestPattern;
+use crate::verification::{self, MeasurementSource, Patch, Tolerance, VerificationReport};
 use iced::widget::{button, column, container, pick_list, row, slider, text, Space};
-use iced::{Application, Command, Element, Length, Theme};
+use iced::{keyboard, Application, Command, Element, Length, Subscription, Theme};
+use rururu_color::monitor::ColorGamut;
+use rururu_color::validation::Severity;
+use rururu_color::{ColorConfig, PaperSize, PrintTarget};
+use rururu_color::{IccManager, IccProfileInfo};
+use std::path::PathBuf;
 
 #[derive(Debug, Clone)]
 pub enum Message {
@@ -19,17 +26,34 @@ pub enum Message {
     PreviousStep,
     CancelCalibration,
     SaveProfile,
+    ExportLut,
+
+    // Verification
+    MeasureWithInstrument,
+    EstimateVerification,
+    PatchMeasured(Result<[f32; 3], String>),
+    ToleranceChanged(Tolerance),
 
     // Adjustments
     BrightnessChanged(f32),
     ContrastChanged(f32),
     GammaChanged(f32),
     WhitePointChanged(u32),
+    ToggleSplitCompare,
+
+    // Ambient light
+    AmbientTick,
 
     // Test patterns
     SelectPattern(TestPattern),
     ToggleFullscreen,
 
+    // Print validation target
+    PrintPaperChanged(PaperSize),
+    PrintSpaceChanged(&'static str),
+    ExportPrintTarget,
+    PrintTargetExported(Result<PathBuf, String>),
+
     // Profile management
     SelectProfile(String),
     ApplyProfile,
@@ -74,6 +98,29 @@ pub struct ColorCalApp {
     contrast: f32,
     gamma: f32,
     white_point: u32,
+
+    // Before/after comparison of the candidate ramp
+    split_compare: bool,
+
+    // Verification: patch being read while measuring, readings so far
+    patches: Vec<Patch>,
+    measuring: Option<usize>,
+    measurements: Vec<[f32; 3]>,
+    tolerance: Tolerance,
+    verification: Option<VerificationReport>,
+    verification_error: Option<String>,
+
+    // Outcome of the last "Export as LUT"
+    lut_export: Option<Result<PathBuf, String>>,
+
+    // Print target options; `None` while exporting
+    print_paper: PaperSize,
+    print_space: &'static str,
+    print_target_export: Option<Option<Result<PathBuf, String>>>,
+
+    // Room lighting, when the machine has a light sensor
+    ambient_sensor: Option<AmbientSensor>,
+    ambient_lux: Option<f32>,
 }
 
 #[derive(Debug, Clone)]
@@ -84,6 +131,11 @@ pub struct DisplayInfo {
     pub refresh_rate: u32,
     pub hdr_capable: bool,
     pub current_profile: Option<String>,
+    /// Ways the assigned profile doesn't fit this display
+    pub profile_warnings: Vec<String>,
+    /// Area and, with a known luminance range, volume coverage of each
+    /// standard gamut
+    pub gamut_coverage: Vec<(ColorGamut, f32, Option<f32>)>,
 }
 
 impl Application for ColorCalApp {
@@ -95,12 +147,20 @@ impl Application for ColorCalApp {
     fn new(_flags: ()) -> (Self, Command<Message>) {
         let displays = detect_displays();
         let profiles = load_profiles();
+        let ambient_sensor = AmbientSensor::find();
+        let ambient_lux = ambient_sensor.as_ref().and_then(AmbientSensor::read_lux);
+
+        // `--display NAME` (passed by the settings Color page) preselects an output
+        let requested = std::env::args()
+            .skip_while(|arg| arg != "--display")
+            .nth(1)
+            .filter(|name| displays.iter().any(|d| &d.name == name));
 
         (
             Self {
                 current_tab: Tab::default(),
                 displays: displays.clone(),
-                selected_display: displays.first().map(|d| d.name.clone()),
+                selected_display: requested.or_else(|| displays.first().map(|d| d.name.clone())),
                 calibration: CalibrationState::default(),
                 profiles,
                 selected_profile: None,
@@ -110,6 +170,19 @@ impl Application for ColorCalApp {
                 contrast: 50.0,
                 gamma: 2.2,
                 white_point: 6500,
+                split_compare: false,
+                patches: verification::standard_patches(),
+                measuring: None,
+                measurements: Vec::new(),
+                tolerance: Tolerance::default(),
+                verification: None,
+                verification_error: None,
+                lut_export: None,
+                print_paper: PaperSize::default(),
+                print_space: "adobe-rgb",
+                print_target_export: None,
+                ambient_sensor,
+                ambient_lux,
             },
             Command::none(),
         )
@@ -132,6 +205,8 @@ impl Application for ColorCalApp {
             }
             Message::StartCalibration => {
                 self.calibration.start();
+                self.verification = None;
+                self.verification_error = None;
             }
             Message::NextStep => {
                 self.calibration.next_step();
@@ -141,6 +216,7 @@ impl Application for ColorCalApp {
             }
             Message::CancelCalibration => {
                 self.calibration.cancel();
+                self.measuring = None;
             }
             Message::SaveProfile => {
                 if let Some(display) = &self.selected_display {
@@ -151,8 +227,73 @@ impl Application for ColorCalApp {
                         self.gamma,
                         self.white_point,
                     );
+                    if let Some(ref report) = self.verification {
+                        if let Err(e) = report.save(std::path::Path::new(&profile.path)) {
+                            tracing::warn!("Failed to save verification report: {}", e);
+                        }
+                    }
                     self.profiles.push(profile);
                     self.calibration.finish();
+                    self.verification = None;
+                }
+            }
+            Message::ExportLut => {
+                if let Some(display) = &self.selected_display {
+                    let name = IccProfile::file_name(display, self.gamma, self.white_point);
+                    let dir = lut_dir();
+                    self.lut_export = Some(
+                        self.candidate_ramp()
+                            .export_luts(&dir, &name)
+                            .map(|_| dir.join(&name))
+                            .map_err(|e| e.to_string()),
+                    );
+                }
+            }
+            Message::MeasureWithInstrument => {
+                self.measurements.clear();
+                self.verification_error = None;
+                self.measuring = Some(0);
+                return Command::perform(verification::read_instrument(), Message::PatchMeasured);
+            }
+            Message::EstimateVerification => {
+                let ramp = self.candidate_ramp();
+                let estimated: Vec<[f32; 3]> = self
+                    .patches
+                    .iter()
+                    .map(|p| verification::estimate_xyz(p.rgb, &ramp))
+                    .collect();
+                self.finish_verification(&estimated, MeasurementSource::Estimated);
+            }
+            Message::PatchMeasured(result) => {
+                let Some(index) = self.measuring else {
+                    return Command::none();
+                };
+
+                match result {
+                    Ok(xyz) => {
+                        self.measurements.push(xyz);
+                        if index + 1 < self.patches.len() {
+                            self.measuring = Some(index + 1);
+                            return Command::perform(
+                                verification::read_instrument(),
+                                Message::PatchMeasured,
+                            );
+                        }
+
+                        self.measuring = None;
+                        let measured = std::mem::take(&mut self.measurements);
+                        self.finish_verification(&measured, MeasurementSource::Instrument);
+                    }
+                    Err(e) => {
+                        self.measuring = None;
+                        self.verification_error = Some(e);
+                    }
+                }
+            }
+            Message::ToleranceChanged(tolerance) => {
+                self.tolerance = tolerance;
+                if let Some(ref mut report) = self.verification {
+                    report.tolerance = tolerance;
                 }
             }
             Message::BrightnessChanged(val) => {
@@ -167,12 +308,50 @@ impl Application for ColorCalApp {
             Message::WhitePointChanged(val) => {
                 self.white_point = val;
             }
+            Message::ToggleSplitCompare => {
+                self.split_compare = !self.split_compare;
+            }
+            Message::AmbientTick => {
+                self.ambient_lux = self
+                    .ambient_sensor
+                    .as_ref()
+                    .and_then(AmbientSensor::read_lux);
+            }
             Message::SelectPattern(pattern) => {
                 self.current_pattern = pattern;
             }
             Message::ToggleFullscreen => {
                 self.fullscreen_pattern = !self.fullscreen_pattern;
             }
+            Message::PrintPaperChanged(paper) => {
+                self.print_paper = paper;
+            }
+            Message::PrintSpaceChanged(space) => {
+                self.print_space = space;
+            }
+            Message::ExportPrintTarget => {
+                let mut target = PrintTarget::new(self.print_paper, self.print_space);
+                let path = print_target_path(&target);
+                self.print_target_export = Some(None);
+                return Command::perform(
+                    async move {
+                        tokio::task::spawn_blocking(move || {
+                            // With the intent of the active workflow
+                            target.options = ColorConfig::load()
+                                .unwrap_or_default()
+                                .transform_options(None);
+                            target.save_tiff(&path).map(|_| path)
+                        })
+                        .await
+                        .map_err(|e| e.to_string())?
+                        .map_err(|e| e.to_string())
+                    },
+                    Message::PrintTargetExported,
+                );
+            }
+            Message::PrintTargetExported(result) => {
+                self.print_target_export = Some(Some(result));
+            }
             Message::SelectProfile(name) => {
                 self.selected_profile = Some(name);
             }
@@ -218,12 +397,207 @@ impl Application for ColorCalApp {
             .into()
     }
 
+    fn subscription(&self) -> Subscription<Message> {
+        let shortcuts = keyboard::on_key_press(|key, _modifiers| match key.as_ref() {
+            keyboard::Key::Character("s") => Some(Message::ToggleSplitCompare),
+            _ => None,
+        });
+
+        // Only the Calibrate tab shows the reading
+        let ambient = if self.ambient_sensor.is_some() && self.current_tab == Tab::Calibrate {
+            iced::time::every(std::time::Duration::from_secs(2)).map(|_| Message::AmbientTick)
+        } else {
+            Subscription::none()
+        };
+
+        Subscription::batch([shortcuts, ambient])
+    }
+
     fn theme(&self) -> Theme {
         Theme::Dark
     }
 }
 
 impl ColorCalApp {
+    fn candidate_ramp(&self) -> CandidateRamp {
+        CandidateRamp {
+            brightness: self.brightness,
+            contrast: self.contrast,
+            gamma: self.gamma,
+            white_point: self.white_point,
+        }
+    }
+
+    fn finish_verification(&mut self, measured: &[[f32; 3]], source: MeasurementSource) {
+        self.verification = Some(VerificationReport::build(
+            &self.patches,
+            measured,
+            &self.candidate_ramp(),
+            source,
+            self.tolerance,
+        ));
+        if self.calibration.current_step() == CalibrationStep::Verify {
+            self.calibration.next_step();
+        }
+    }
+
+    /// Room lighting and the brightness to aim for under it; nothing
+    /// without a sensor
+    fn view_ambient(&self) -> Element<'_, Message> {
+        let Some(lux) = self.ambient_lux else {
+            return Space::with_height(Length::Shrink).into();
+        };
+
+        let advice = ambient::recommend(lux);
+        let mut content = column![row![
+            text("Room Light:").size(12),
+            Space::with_width(Length::Fixed(8.0)),
+            text(format!(
+                "{:.0} lux, aim for {} cd/m² white",
+                lux, advice.target_luminance
+            ))
+            .size(12),
+        ]]
+        .spacing(4);
+        if let Some(warning) = advice.warning {
+            content = content.push(text(format!("⚠ {}", warning)).size(12));
+        }
+        content.into()
+    }
+
+    fn view_verify(&self) -> Element<'_, Message> {
+        if let Some(index) = self.measuring {
+            let patch = &self.patches[index];
+            let [r, g, b] = patch.rgb;
+            return column![
+                text(format!(
+                    "Measuring {} ({} of {}), keep the instrument on the patch",
+                    patch.name,
+                    index + 1,
+                    self.patches.len()
+                ))
+                .size(12),
+                crate::patterns::swatch(
+                    iced::Color::from_rgb(r, g, b),
+                    Length::Fixed(600.0),
+                    Length::Fixed(300.0),
+                ),
+            ]
+            .spacing(8)
+            .into();
+        }
+
+        let measure = button(text("Measure with Instrument")).style(iced::theme::Button::Primary);
+        let measure = if verification::instrument_available() {
+            measure.on_press(Message::MeasureWithInstrument)
+        } else {
+            measure
+        };
+
+        let mut content = column![
+            crate::patterns::view_pattern(&TestPattern::ColorBars),
+            Space::with_height(Length::Fixed(8.0)),
+            crate::patterns::view_pattern(&TestPattern::Gradient),
+            Space::with_height(Length::Fixed(8.0)),
+            row![
+                measure,
+                Space::with_width(Length::Fixed(8.0)),
+                button(text("Estimate without Instrument"))
+                    .style(iced::theme::Button::Secondary)
+                    .on_press(Message::EstimateVerification),
+                Space::with_width(Length::Fixed(16.0)),
+                text("Tolerance:").size(12),
+                pick_list(
+                    &Tolerance::ALL[..],
+                    Some(self.tolerance),
+                    Message::ToleranceChanged
+                ),
+            ]
+            .align_items(iced::Alignment::Center),
+        ];
+        if !verification::instrument_available() {
+            content =
+                content.push(text("Install ArgyllCMS to measure with an instrument").size(10));
+        }
+        if let Some(ref e) = self.verification_error {
+            content = content.push(text(e).size(12));
+        }
+
+        content.into()
+    }
+
+    fn view_report(&self) -> Element<'_, Message> {
+        let Some(ref report) = self.verification else {
+            return text("Not verified. Go back to Verification to measure the result.")
+                .size(12)
+                .into();
+        };
+
+        let source = match report.source {
+            MeasurementSource::Instrument => "measured",
+            MeasurementSource::Estimated => "estimated",
+        };
+        let verdict = if report.passed() {
+            text(format!("PASS: {}", report.tolerance)).style(iced::theme::Text::Color(
+                iced::Color::from_rgb(0.4, 0.8, 0.4),
+            ))
+        } else {
+            text(format!("FAIL: {}", report.tolerance)).style(iced::theme::Text::Color(
+                iced::Color::from_rgb(0.9, 0.3, 0.3),
+            ))
+        };
+
+        let rows: Vec<Element<Message>> = report
+            .patches
+            .iter()
+            .map(|r| {
+                row![
+                    text(&r.patch.name).size(11).width(Length::Fixed(100.0)),
+                    text(format!("ΔE {:.2}", r.delta_e)).size(11),
+                ]
+                .into()
+            })
+            .collect();
+        let (left, right) = rows.into_iter().enumerate().fold(
+            (column![].spacing(2), column![].spacing(2)),
+            |(left, right), (i, row)| {
+                if i % 2 == 0 {
+                    (left.push(row), right)
+                } else {
+                    (left, right.push(row))
+                }
+            },
+        );
+
+        column![
+            row![
+                verdict.size(14),
+                Space::with_width(Length::Fixed(16.0)),
+                pick_list(
+                    &Tolerance::ALL[..],
+                    Some(self.tolerance),
+                    Message::ToleranceChanged
+                ),
+            ]
+            .align_items(iced::Alignment::Center),
+            text(format!(
+                "Average ΔE2000 {:.2}, max {:.2} ({} over {} patches)",
+                report.average_delta_e,
+                report.max_delta_e,
+                source,
+                report.patches.len()
+            ))
+            .size(12),
+            row![
+                left.width(Length::Fixed(200.0)),
+                right.width(Length::Fixed(200.0))
+            ],
+            text("The report is saved next to the profile").size(10),
+        ]
+        .spacing(4)
+        .into()
+    }
+
     fn view_calibrate(&self) -> Element<'_, Message> {
         // Display selector
         let display_names: Vec<String> = self.displays.iter().map(|d| d.name.clone()).collect();
@@ -249,7 +623,7 @@ impl ColorCalApp {
             .as_ref()
             .and_then(|name| self.displays.iter().find(|d| &d.name == name))
         {
-            column![
+            let mut info = column![
                 row![
                     text("Model:").size(12),
                     Space::with_width(Length::Fixed(8.0)),
@@ -280,8 +654,22 @@ impl ColorCalApp {
                     text(display.current_profile.as_deref().unwrap_or("None")).size(12),
                 ],
             ]
-            .spacing(4)
-            .into()
+            .spacing(4);
+            for (gamut, area, volume) in &display.gamut_coverage {
+                let coverage = match volume {
+                    Some(volume) => format!("{:.0}% area, {:.0}% volume", area, volume),
+                    None => format!("{:.0}% area", area),
+                };
+                info = info.push(row![
+                    text(format!("{}:", gamut)).size(12),
+                    Space::with_width(Length::Fixed(8.0)),
+                    text(coverage).size(12),
+                ]);
+            }
+            for warning in &display.profile_warnings {
+                info = info.push(text(format!("⚠ {}", warning)).size(12));
+            }
+            info.into()
         } else {
             text("No display selected").size(12).into()
         };
@@ -333,18 +721,56 @@ impl ColorCalApp {
                     button(text("Save Profile"))
                         .style(iced::theme::Button::Secondary)
                         .on_press(Message::SaveProfile),
+                    Space::with_width(Length::Fixed(8.0)),
+                    button(text("Export as LUT"))
+                        .style(iced::theme::Button::Secondary)
+                        .on_press(Message::ExportLut),
+                    Space::with_width(Length::Fixed(8.0)),
+                    button(text("Compare Before/After"))
+                        .style(if self.split_compare {
+                            iced::theme::Button::Primary
+                        } else {
+                            iced::theme::Button::Secondary
+                        })
+                        .on_press(Message::ToggleSplitCompare),
                 ],
             ]
             .spacing(12)
             .into()
         };
 
+        let comparison: Element<Message> = if self.split_compare {
+            crate::patterns::view_split_comparison(&self.candidate_ramp())
+        } else {
+            Space::with_height(Length::Shrink).into()
+        };
+
+        let lut_status: Element<Message> = match &self.lut_export {
+            Some(Ok(base)) => text(format!(
+                "LUTs written to {}.{{cube,spi1d,csp}}",
+                base.display()
+            ))
+            .size(12)
+            .into(),
+            Some(Err(e)) => text(format!("LUT export failed: {}", e))
+                .size(12)
+                .style(iced::theme::Text::Color(iced::Color::from_rgb(
+                    0.9, 0.3, 0.3,
+                )))
+                .into(),
+            None => Space::with_height(Length::Shrink).into(),
+        };
+
         column![
             display_selector,
             Space::with_height(Length::Fixed(16.0)),
             display_info,
+            self.view_ambient(),
             Space::with_height(Length::Fixed(24.0)),
             calibration_content,
+            lut_status,
+            Space::with_height(Length::Fixed(16.0)),
+            comparison,
         ]
         .spacing(8)
         .into()
@@ -404,13 +830,8 @@ impl ColorCalApp {
             ),
             CalibrationStep::Verify => (
                 "Verification",
-                "Review test patterns to verify calibration quality.",
-                column![
-                    crate::patterns::view_pattern(&TestPattern::ColorBars),
-                    Space::with_height(Length::Fixed(8.0)),
-                    crate::patterns::view_pattern(&TestPattern::Gradient),
-                ]
-                .into(),
+                "Review the test patterns, then measure a patch set to check the calibration against its targets.",
+                self.view_verify(),
             ),
             CalibrationStep::Complete => (
                 "Calibration Complete",
@@ -421,6 +842,8 @@ impl ColorCalApp {
                     text(format!("Contrast: {:.0}%", self.contrast)).size(12),
                     text(format!("Gamma: {:.1}", self.gamma)).size(12),
                     text(format!("White Point: {}K", self.white_point)).size(12),
+                    Space::with_height(Length::Fixed(8.0)),
+                    self.view_report(),
                 ]
                 .spacing(4)
                 .into(),
@@ -446,6 +869,14 @@ impl ColorCalApp {
                     .style(iced::theme::Button::Text)
                     .on_press(Message::CancelCalibration),
                 Space::with_width(Length::Fill),
+                if matches!(step, CalibrationStep::Complete) {
+                    button(text("Export as LUT"))
+                        .style(iced::theme::Button::Secondary)
+                        .on_press(Message::ExportLut)
+                } else {
+                    button(text("Export as LUT")).style(iced::theme::Button::Text)
+                },
+                Space::with_width(Length::Fixed(8.0)),
                 if matches!(step, CalibrationStep::Complete) {
                     button(text("Save Profile"))
                         .style(iced::theme::Button::Primary)
@@ -558,6 +989,12 @@ impl ColorCalApp {
             button(text("Fullscreen"))
                 .style(iced::theme::Button::Primary)
                 .on_press(Message::ToggleFullscreen),
+            Space::with_height(Length::Fixed(16.0)),
+            crate::patterns::view_print_target(
+                self.print_paper,
+                self.print_space,
+                self.print_target_export.as_ref(),
+            ),
         ]
         .spacing(8)
         .into()
@@ -617,7 +1054,7 @@ fn tab_button(label: &str, tab: Tab, current: Tab) -> Element<'_, Message> {
 
 fn detect_displays() -> Vec<DisplayInfo> {
     // In real implementation, would use wlr-randr or similar
-    vec![
+    let mut displays = vec![
         DisplayInfo {
             name: "DP-1".to_string(),
             model: "Dell U2720Q".to_string(),
@@ -625,6 +1062,8 @@ fn detect_displays() -> Vec<DisplayInfo> {
             refresh_rate: 60,
             hdr_capable: true,
             current_profile: None,
+            profile_warnings: Vec::new(),
+            gamut_coverage: Vec::new(),
         },
         DisplayInfo {
             name: "HDMI-1".to_string(),
@@ -633,8 +1072,78 @@ fn detect_displays() -> Vec<DisplayInfo> {
             refresh_rate: 60,
             hdr_capable: true,
             current_profile: Some("BenQ_SW271_D65.icc".to_string()),
+            profile_warnings: Vec::new(),
+            gamut_coverage: Vec::new(),
         },
-    ]
+    ];
+    check_profiles(&mut displays);
+    displays
+}
+
+/// Fill in the profile the color service assigned to each display and any
+/// mismatch with it. DRM names connectors `card0-DP-1`; displays here are
+/// just `DP-1`.
+fn check_profiles(displays: &mut [DisplayInfo]) {
+    let monitors = rururu_color::monitor::detect_monitors().unwrap_or_default();
+
+    for display in displays {
+        let suffix = format!("-{}", display.name);
+        let Some(monitor) = monitors
+            .iter()
+            .find(|m| m.name == display.name || m.name.ends_with(&suffix))
+        else {
+            continue;
+        };
+
+        if monitor.primaries().is_some() {
+            display.gamut_coverage = [
+                ColorGamut::Srgb,
+                ColorGamut::AdobeRgb,
+                ColorGamut::DciP3,
+                ColorGamut::Bt2020,
+            ]
+            .into_iter()
+            .map(|gamut| {
+                (
+                    gamut,
+                    monitor.gamut_coverage(gamut),
+                    monitor.gamut_volume_coverage(gamut),
+                )
+            })
+            .collect();
+        }
+
+        let Some(path) = &monitor.icc_profile else {
+            continue;
+        };
+
+        display.current_profile = path
+            .file_name()
+            .map(|name| name.to_string_lossy().into_owned());
+        display.profile_warnings = match IccProfileInfo::from_file(path) {
+            Ok(info) => monitor
+                .check_profile_match(&info)
+                .iter()
+                .map(|w| w.to_string())
+                .collect(),
+            Err(e) => vec![format!("Could not read the profile: {}", e)],
+        };
+
+        // A broken profile makes verification against it meaningless
+        let report = IccManager::report(path);
+        if !report.is_trustworthy() {
+            display
+                .profile_warnings
+                .push(format!("Profile quality {}/100", report.score));
+            display.profile_warnings.extend(
+                report
+                    .issues
+                    .iter()
+                    .filter(|i| i.severity() > Severity::Info)
+                    .map(|i| i.to_string()),
+            );
+        }
+    }
 }
 
 fn load_profiles() -> Vec<IccProfile> {
@@ -659,3 +1168,29 @@ fn apply_profile(profile: &IccProfile) {
     // Would use colord or similar to apply profile
     tracing::info!("Applying profile: {}", profile.name);
 }
+
+/// Where a print target is saved:
+/// `~/Pictures/rururu-print-target-<paper>-<space>.tif`
+fn print_target_path(target: &PrintTarget) -> PathBuf {
+    let paper: String = target
+        .paper
+        .name()
+        .to_lowercase()
+        .replace('+', "plus")
+        .replace(['×', ' '], "");
+    dirs::picture_dir()
+        .or_else(dirs::home_dir)
+        .unwrap_or_else(|| PathBuf::from("."))
+        .join(format!(
+            "rururu-print-target-{}-{}.tif",
+            paper, target.working_space
+        ))
+}
+
+/// Where "Export as LUT" writes: `~/.local/share/rururu/luts`
+fn lut_dir() -> PathBuf {
+    dirs::data_dir()
+        .unwrap_or_else(|| PathBuf::from("."))
+        .join("rururu")
+        .join("luts")
+}
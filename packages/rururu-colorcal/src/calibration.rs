@@ -1,3 +1,6 @@
+use rururu_color::lut::{self, Lut1d, Lut3d, LutFormat};
+use std::path::{Path, PathBuf};
+
 #[derive(Debug, Clone, Copy, PartialEq, Eq)]
 pub enum CalibrationStep {
     Warmup,
@@ -76,3 +79,90 @@ impl CalibrationState {
         }
     }
 }
+
+/// The ramp the current slider values would load into the video LUT,
+/// applied in software so it can be previewed next to the raw signal.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct CandidateRamp {
+    pub brightness: f32,
+    pub contrast: f32,
+    pub gamma: f32,
+    pub white_point: u32,
+}
+
+impl CandidateRamp {
+    /// Gamma the uncalibrated display is assumed to have
+    const NATIVE_GAMMA: f32 = 2.2;
+
+    /// Map one sRGB-encoded channel value through the ramp for `channel`
+    /// (0 = red, 1 = green, 2 = blue).
+    pub fn apply_channel(&self, value: f32, channel: usize) -> f32 {
+        let contrast = self.contrast / 50.0;
+        let brightness = (self.brightness - 50.0) / 100.0;
+        let v = ((value - 0.5) * contrast + 0.5 + brightness).clamp(0.0, 1.0);
+
+        // Displayed luminance is ramp(v)^native, so this yields v^gamma
+        let v = v.powf(self.gamma / Self::NATIVE_GAMMA);
+
+        // The gains are in linear light: decode, scale and re-encode, which
+        // for a pure power curve is scaling by the gain's encoding
+        let gain = self.white_gains()[channel].powf(1.0 / Self::NATIVE_GAMMA);
+        (v * gain).clamp(0.0, 1.0)
+    }
+
+    pub fn apply(&self, rgb: [f32; 3]) -> [f32; 3] {
+        [
+            self.apply_channel(rgb[0], 0),
+            self.apply_channel(rgb[1], 1),
+            self.apply_channel(rgb[2], 2),
+        ]
+    }
+
+    /// Per-channel gains in linear light that move D65 white to the target
+    /// white point.
+    pub fn white_gains(&self) -> [f32; 3] {
+        let wp = crate::icc::WhitePoint::from_temperature(self.white_point);
+
+        // xyY (Y = 1) to XYZ, then XYZ to linear sRGB
+        let x = wp.x / wp.y;
+        let z = (1.0 - wp.x - wp.y) / wp.y;
+        let r = 3.2406 * x - 1.5372 - 0.4986 * z;
+        let g = -0.9689 * x + 1.8758 + 0.0415 * z;
+        let b = 0.0557 * x - 0.2040 + 1.0570 * z;
+
+        let max = r.max(g).max(b);
+        if max <= 0.0 {
+            return [1.0, 1.0, 1.0];
+        }
+        [
+            (r / max).clamp(0.0, 1.0),
+            (g / max).clamp(0.0, 1.0),
+            (b / max).clamp(0.0, 1.0),
+        ]
+    }
+}
+
+impl CandidateRamp {
+    /// Write the ramp into `dir` as `<name>.cube`, `<name>.spi1d` and
+    /// `<name>.csp`, for display transforms in Resolve or an OCIO config.
+    /// Inputs and outputs are display-encoded signal values.
+    pub fn export_luts(&self, dir: &Path, name: &str) -> rururu_color::Result<Vec<PathBuf>> {
+        let title = format!(
+            "RururuOS calibration: gamma {:.1}, {}K",
+            self.gamma, self.white_point
+        );
+        let curves = Lut1d::sample(lut::DEFAULT_1D_SIZE, |v, c| self.apply_channel(v, c))?;
+        let cube = Lut3d::sample(lut::DEFAULT_3D_SIZE, |rgb| self.apply(rgb))?;
+
+        let mut written = Vec::new();
+        for format in LutFormat::all() {
+            let path = dir.join(format!("{}.{}", name, format.extension()));
+            match format {
+                LutFormat::Cube => cube.save(&path, &title)?,
+                LutFormat::Spi1d | LutFormat::Csp => curves.save(&path, &title)?,
+            }
+            written.push(path);
+        }
+        Ok(written)
+    }
+}
@@ -19,12 +19,7 @@ impl IccProfile {
         gamma: f32,
         white_point: u32,
     ) -> Self {
-        let name = format!(
-            "{}_{}K_g{:.1}",
-            display_name.replace("-", "_"),
-            white_point,
-            gamma
-        );
+        let name = Self::file_name(display_name, gamma, white_point);
 
         let timestamp = chrono_lite_timestamp();
 
@@ -43,6 +38,16 @@ impl IccProfile {
         }
     }
 
+    /// Name for a calibration's files, e.g. `DP_1_6500K_g2.2`
+    pub fn file_name(display_name: &str, gamma: f32, white_point: u32) -> String {
+        format!(
+            "{}_{}K_g{:.1}",
+            display_name.replace("-", "_"),
+            white_point,
+            gamma
+        )
+    }
+
     pub fn save(&self) -> std::io::Result<()> {
         let path = PathBuf::from(&self.path);
         if let Some(parent) = path.parent() {
@@ -1,7 +1,9 @@
+mod ambient;
 mod app;
 mod calibration;
 mod icc;
 mod patterns;
+mod verification;
 
 use app::ColorCalApp;
 use iced::{Application, Settings};
@@ -1,6 +1,10 @@
 use crate::app::Message;
-use iced::widget::{column, container, row, text, Space};
+use crate::calibration::CandidateRamp;
+use iced::widget::{button, column, container, pick_list, row, text, Space};
 use iced::{Color, Element, Length};
+use rururu_color::print_target::WORKING_SPACES;
+use rururu_color::PaperSize;
+use std::path::PathBuf;
 
 #[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
 pub enum TestPattern {
@@ -272,3 +276,116 @@ fn view_dead_pixel<'a>() -> Element<'a, Message> {
     .spacing(4)
     .into()
 }
+
+struct Swatch(Color);
+
+impl iced::widget::container::StyleSheet for Swatch {
+    type Style = iced::Theme;
+
+    fn appearance(&self, _style: &Self::Style) -> iced::widget::container::Appearance {
+        iced::widget::container::Appearance {
+            background: Some(self.0.into()),
+            ..Default::default()
+        }
+    }
+}
+
+pub fn swatch<'a>(color: Color, width: Length, height: Length) -> Element<'a, Message> {
+    container(Space::new(width, height))
+        .style(iced::theme::Container::Custom(Box::new(Swatch(color))))
+        .into()
+}
+
+/// Same patches rendered raw on the left and through the candidate ramp on
+/// the right. Compositors don't let us load a LUT for half an output, so the
+/// "after" half is the software transform of the "before" half.
+pub fn view_split_comparison<'a>(ramp: &CandidateRamp) -> Element<'a, Message> {
+    let patches: Vec<[f32; 3]> = (0..8)
+        .map(|i| {
+            let v = i as f32 / 7.0;
+            [v, v, v]
+        })
+        .chain([
+            [0.75, 0.75, 0.0],
+            [0.0, 0.75, 0.75],
+            [0.0, 0.75, 0.0],
+            [0.75, 0.0, 0.75],
+            [0.75, 0.0, 0.0],
+            [0.0, 0.0, 0.75],
+            [0.9, 0.7, 0.6],
+        ])
+        .collect();
+
+    let half = |transform: bool| -> Element<'a, Message> {
+        let rows: Vec<Element<Message>> = patches
+            .iter()
+            .map(|rgb| {
+                let [r, g, b] = if transform { ramp.apply(*rgb) } else { *rgb };
+                swatch(Color::from_rgb(r, g, b), Length::Fill, Length::Fixed(16.0))
+            })
+            .collect();
+        column(rows).width(Length::FillPortion(1)).into()
+    };
+
+    column![
+        row![
+            text("Before").size(12).width(Length::FillPortion(1)),
+            text("After").size(12).width(Length::FillPortion(1)),
+        ],
+        container(row![half(false), half(true)].spacing(2))
+            .width(Length::Fixed(600.0))
+            .style(iced::theme::Container::Box),
+        text("Press S to toggle the comparison").size(10),
+    ]
+    .width(Length::Fixed(600.0))
+    .spacing(4)
+    .into()
+}
+
+/// Options for the printable validation target; `export` is `Some(None)`
+/// while one is being written
+pub fn view_print_target<'a>(
+    paper: PaperSize,
+    space: &'static str,
+    export: Option<&Option<Result<PathBuf, String>>>,
+) -> Element<'a, Message> {
+    let exporting = matches!(export, Some(None));
+    let status: Element<Message> = match export {
+        Some(Some(Ok(path))) => text(format!("Print target written to {}", path.display()))
+            .size(12)
+            .into(),
+        Some(Some(Err(e))) => text(format!("Print target export failed: {}", e))
+            .size(12)
+            .style(iced::theme::Text::Color(Color::from_rgb(0.9, 0.3, 0.3)))
+            .into(),
+        Some(None) => text("Writing print target…").size(12).into(),
+        None => Space::with_height(Length::Shrink).into(),
+    };
+
+    column![
+        text("Print Validation Target").size(14),
+        text(
+            "Grayscale ramp, primaries and secondaries, skin tones and gamut markers. \
+             Print it with the profile you soft-proof against and compare it to the screen."
+        )
+        .size(11),
+        row![
+            text("Paper").width(Length::Fixed(100.0)),
+            pick_list(&PaperSize::ALL[..], Some(paper), Message::PrintPaperChanged),
+        ]
+        .spacing(8)
+        .align_items(iced::Alignment::Center),
+        row![
+            text("Working space").width(Length::Fixed(100.0)),
+            pick_list(WORKING_SPACES, Some(space), Message::PrintSpaceChanged),
+        ]
+        .spacing(8)
+        .align_items(iced::Alignment::Center),
+        button(text("Export print target"))
+            .style(iced::theme::Button::Secondary)
+            .on_press_maybe((!exporting).then_some(Message::ExportPrintTarget)),
+        status,
+    ]
+    .spacing(8)
+    .into()
+}
@@ -0,0 +1,340 @@
+//! Verification of a calibration against its targets with CIE ΔE 2000.
+//!
+//! Each patch's target is what an ideal display with sRGB primaries, the
+//! chosen gamma and the chosen white point would show. Measurements come from
+//! an instrument through ArgyllCMS `spotread`, or, without one, are estimated
+//! by running the patch through the candidate ramp on a nominal sRGB panel.
+//! Both sides are converted to L*a*b* relative to the target white, so a
+//! white point error shows up in every patch.
+
+use crate::calibration::CandidateRamp;
+use serde::{Deserialize, Serialize};
+use std::path::{Path, PathBuf};
+use std::time::Duration;
+
+/// Linear sRGB to XYZ (D65)
+const SRGB_TO_XYZ: [[f32; 3]; 3] = [
+    [0.4124, 0.3576, 0.1805],
+    [0.2126, 0.7152, 0.0722],
+    [0.0193, 0.1192, 0.9505],
+];
+
+/// Gamma the uncalibrated panel is assumed to have when estimating
+const NATIVE_GAMMA: f32 = 2.2;
+
+/// Time for the compositor to put the patch on screen before reading
+const SETTLE_TIME: Duration = Duration::from_millis(600);
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct Patch {
+    pub name: String,
+    pub rgb: [f32; 3],
+}
+
+/// Grayscale ramp in 10% steps followed by the primaries and secondaries.
+/// White comes first so later readings can be normalized against it.
+pub fn standard_patches() -> Vec<Patch> {
+    let gray = (0..=10).rev().map(|i| {
+        let v = i as f32 / 10.0;
+        Patch {
+            name: format!("Gray {}%", i * 10),
+            rgb: [v, v, v],
+        }
+    });
+
+    let colors = [
+        ("Red", [1.0, 0.0, 0.0]),
+        ("Green", [0.0, 1.0, 0.0]),
+        ("Blue", [0.0, 0.0, 1.0]),
+        ("Cyan", [0.0, 1.0, 1.0]),
+        ("Magenta", [1.0, 0.0, 1.0]),
+        ("Yellow", [1.0, 1.0, 0.0]),
+    ]
+    .into_iter()
+    .map(|(name, rgb)| Patch {
+        name: name.to_string(),
+        rgb,
+    });
+
+    gray.chain(colors).collect()
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub enum MeasurementSource {
+    Instrument,
+    Estimated,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default, Serialize, Deserialize)]
+pub enum Tolerance {
+    Strict,
+    #[default]
+    Proofing,
+    General,
+}
+
+impl Tolerance {
+    pub const ALL: [Tolerance; 3] = [Tolerance::Strict, Tolerance::Proofing, Tolerance::General];
+
+    pub fn max_delta_e(&self) -> f32 {
+        match self {
+            Tolerance::Strict => 1.0,
+            Tolerance::Proofing => 2.0,
+            Tolerance::General => 3.0,
+        }
+    }
+}
+
+impl std::fmt::Display for Tolerance {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        let name = match self {
+            Tolerance::Strict => "Strict",
+            Tolerance::Proofing => "Proofing",
+            Tolerance::General => "General",
+        };
+        write!(f, "{} (ΔE < {:.0})", name, self.max_delta_e())
+    }
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct PatchResult {
+    pub patch: Patch,
+    pub target_lab: [f32; 3],
+    pub measured_lab: [f32; 3],
+    pub delta_e: f32,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct VerificationReport {
+    pub source: MeasurementSource,
+    pub tolerance: Tolerance,
+    pub gamma: f32,
+    pub white_point: u32,
+    pub patches: Vec<PatchResult>,
+    pub average_delta_e: f32,
+    pub max_delta_e: f32,
+}
+
+impl VerificationReport {
+    /// Compare `measured` XYZ readings, one per entry of `patches`, against
+    /// the targets for `ramp`. Readings only need to be relative; they are
+    /// scaled so the white patch has Y = 1.
+    pub fn build(
+        patches: &[Patch],
+        measured: &[[f32; 3]],
+        ramp: &CandidateRamp,
+        source: MeasurementSource,
+        tolerance: Tolerance,
+    ) -> Self {
+        let target_white = target_xyz([1.0, 1.0, 1.0], ramp);
+        let reference = normalize(target_white, target_white[1]);
+
+        let measured_white = patches
+            .iter()
+            .zip(measured)
+            .find(|(p, _)| p.rgb == [1.0, 1.0, 1.0])
+            .map(|(_, xyz)| xyz[1])
+            .filter(|y| *y > 0.0)
+            .unwrap_or(1.0);
+
+        let results: Vec<PatchResult> = patches
+            .iter()
+            .zip(measured)
+            .map(|(patch, xyz)| {
+                let target_lab = xyz_to_lab(
+                    normalize(target_xyz(patch.rgb, ramp), target_white[1]),
+                    reference,
+                );
+                let measured_lab = xyz_to_lab(normalize(*xyz, measured_white), reference);
+                PatchResult {
+                    patch: patch.clone(),
+                    target_lab,
+                    measured_lab,
+                    delta_e: delta_e_2000(target_lab, measured_lab),
+                }
+            })
+            .collect();
+
+        let max_delta_e = results.iter().map(|r| r.delta_e).fold(0.0, f32::max);
+        let average_delta_e = if results.is_empty() {
+            0.0
+        } else {
+            results.iter().map(|r| r.delta_e).sum::<f32>() / results.len() as f32
+        };
+
+        Self {
+            source,
+            tolerance,
+            gamma: ramp.gamma,
+            white_point: ramp.white_point,
+            patches: results,
+            average_delta_e,
+            max_delta_e,
+        }
+    }
+
+    /// Every patch has to be within tolerance
+    pub fn passed(&self) -> bool {
+        !self.patches.is_empty() && self.max_delta_e < self.tolerance.max_delta_e()
+    }
+
+    /// Where the report for the profile at `profile_path` is stored
+    pub fn path_for(profile_path: &Path) -> PathBuf {
+        profile_path.with_extension("verification.json")
+    }
+
+    pub fn save(&self, profile_path: &Path) -> std::io::Result<PathBuf> {
+        let path = Self::path_for(profile_path);
+        if let Some(parent) = path.parent() {
+            std::fs::create_dir_all(parent)?;
+        }
+
+        let content = serde_json::to_string_pretty(self)?;
+        std::fs::write(&path, content)?;
+        Ok(path)
+    }
+}
+
+/// XYZ the calibration target would produce for an sRGB-encoded patch
+pub fn target_xyz(rgb: [f32; 3], ramp: &CandidateRamp) -> [f32; 3] {
+    // The ramp's white gains are in linear light
+    let gains = ramp.white_gains();
+    let linear = [0, 1, 2].map(|c| rgb[c].powf(ramp.gamma) * gains[c]);
+    linear_to_xyz(linear)
+}
+
+/// What a nominal sRGB panel shows for `rgb` through the candidate ramp
+pub fn estimate_xyz(rgb: [f32; 3], ramp: &CandidateRamp) -> [f32; 3] {
+    let encoded = ramp.apply(rgb);
+    linear_to_xyz(encoded.map(|v| v.powf(NATIVE_GAMMA)))
+}
+
+fn linear_to_xyz(rgb: [f32; 3]) -> [f32; 3] {
+    SRGB_TO_XYZ.map(|row| row[0] * rgb[0] + row[1] * rgb[1] + row[2] * rgb[2])
+}
+
+fn normalize(xyz: [f32; 3], white_y: f32) -> [f32; 3] {
+    xyz.map(|v| v / white_y)
+}
+
+fn xyz_to_lab(xyz: [f32; 3], white: [f32; 3]) -> [f32; 3] {
+    let f = |t: f32| {
+        const DELTA: f32 = 6.0 / 29.0;
+        if t > DELTA * DELTA * DELTA {
+            t.cbrt()
+        } else {
+            t / (3.0 * DELTA * DELTA) + 4.0 / 29.0
+        }
+    };
+
+    let fx = f(xyz[0] / white[0]);
+    let fy = f(xyz[1] / white[1]);
+    let fz = f(xyz[2] / white[2]);
+
+    [116.0 * fy - 16.0, 500.0 * (fx - fy), 200.0 * (fy - fz)]
+}
+
+/// CIEDE2000 colour difference (Sharma, Wu & Dalal 2005) with kL = kC = kH = 1
+pub fn delta_e_2000(lab1: [f32; 3], lab2: [f32; 3]) -> f32 {
+    let [l1, a1, b1] = lab1.map(|v| v as f64);
+    let [l2, a2, b2] = lab2.map(|v| v as f64);
+
+    let c1 = a1.hypot(b1);
+    let c2 = a2.hypot(b2);
+    let c_bar7 = ((c1 + c2) / 2.0).powi(7);
+    let g = 0.5 * (1.0 - (c_bar7 / (c_bar7 + 25f64.powi(7))).sqrt());
+
+    let a1p = a1 * (1.0 + g);
+    let a2p = a2 * (1.0 + g);
+    let c1p = a1p.hypot(b1);
+    let c2p = a2p.hypot(b2);
+
+    let hue = |b: f64, a: f64| {
+        if a == 0.0 && b == 0.0 {
+            0.0
+        } else {
+            b.atan2(a).to_degrees().rem_euclid(360.0)
+        }
+    };
+    let h1p = hue(b1, a1p);
+    let h2p = hue(b2, a2p);
+
+    let dl = l2 - l1;
+    let dc = c2p - c1p;
+    let dh = if c1p * c2p == 0.0 {
+        0.0
+    } else if (h2p - h1p).abs() <= 180.0 {
+        h2p - h1p
+    } else if h2p - h1p > 180.0 {
+        h2p - h1p - 360.0
+    } else {
+        h2p - h1p + 360.0
+    };
+    let dh_big = 2.0 * (c1p * c2p).sqrt() * (dh / 2.0).to_radians().sin();
+
+    let l_bar = (l1 + l2) / 2.0;
+    let c_bar = (c1p + c2p) / 2.0;
+    let h_bar = if c1p * c2p == 0.0 {
+        h1p + h2p
+    } else if (h1p - h2p).abs() <= 180.0 {
+        (h1p + h2p) / 2.0
+    } else if h1p + h2p < 360.0 {
+        (h1p + h2p + 360.0) / 2.0
+    } else {
+        (h1p + h2p - 360.0) / 2.0
+    };
+
+    let t = 1.0 - 0.17 * (h_bar - 30.0).to_radians().cos()
+        + 0.24 * (2.0 * h_bar).to_radians().cos()
+        + 0.32 * (3.0 * h_bar + 6.0).to_radians().cos()
+        - 0.20 * (4.0 * h_bar - 63.0).to_radians().cos();
+
+    let l50 = (l_bar - 50.0).powi(2);
+    let sl = 1.0 + 0.015 * l50 / (20.0 + l50).sqrt();
+    let sc = 1.0 + 0.045 * c_bar;
+    let sh = 1.0 + 0.015 * c_bar * t;
+
+    let c_bar_p7 = c_bar.powi(7);
+    let rc = 2.0 * (c_bar_p7 / (c_bar_p7 + 25f64.powi(7))).sqrt();
+    let d_theta = 30.0 * (-((h_bar - 275.0) / 25.0).powi(2)).exp();
+    let rt = -(2.0 * d_theta).to_radians().sin() * rc;
+
+    let dl = dl / sl;
+    let dc = dc / sc;
+    let dh = dh_big / sh;
+
+    (dl * dl + dc * dc + dh * dh + rt * dc * dh).sqrt() as f32
+}
+
+/// Whether ArgyllCMS `spotread` is on the PATH
+pub fn instrument_available() -> bool {
+    std::env::var_os("PATH")
+        .map(|paths| std::env::split_paths(&paths).any(|dir| dir.join("spotread").is_file()))
+        .unwrap_or(false)
+}
+
+/// Take one reading of whatever is on screen under the instrument.
+/// `spotread -O` reads once and exits, printing `Result is XYZ: X Y Z, ...`.
+pub async fn read_instrument() -> Result<[f32; 3], String> {
+    tokio::time::sleep(SETTLE_TIME).await;
+
+    let output = tokio::process::Command::new("spotread")
+        .args(["-e", "-O"])
+        .output()
+        .await
+        .map_err(|e| format!("Failed to run spotread: {}", e))?;
+
+    let stdout = String::from_utf8_lossy(&output.stdout);
+    parse_spotread(&stdout).ok_or_else(|| {
+        let stderr = String::from_utf8_lossy(&output.stderr);
+        format!("No reading from instrument: {}", stderr.trim())
+    })
+}
+
+fn parse_spotread(output: &str) -> Option<[f32; 3]> {
+    let line = output.lines().find(|l| l.contains("Result is XYZ:"))?;
+    let values = line.split("XYZ:").nth(1)?.split(',').next()?;
+
+    let mut numbers = values.split_whitespace().filter_map(|v| v.parse().ok());
+    Some([numbers.next()?, numbers.next()?, numbers.next()?])
+}
@@ -0,0 +1,283 @@
+//! Camera RAW formats that are TIFF inside.
+//!
+//! infer calls these plain TIFF, so the TIFF structure is read to tell them
+//! apart. CR2 marks itself with `CR` after the TIFF header. DNG carries a
+//! `DNGVersion` tag in its first IFD, whichever camera made it. NEF and ARW
+//! are found by the maker note in their EXIF IFD, `Nikon` or `SONY`, falling
+//! back to the `Make` tag. NEF keeps its raw data in SubIFDs, which tells it
+//! apart from the TIFFs some Nikon cameras can also shoot.
+
+use crate::file_detector::{FileCategory, FileInfo};
+
+const TAG_MAKE: u16 = 0x010F;
+const TAG_SUB_IFDS: u16 = 0x014A;
+const TAG_EXIF_IFD: u16 = 0x8769;
+const TAG_MAKER_NOTE: u16 = 0x927C;
+const TAG_DNG_VERSION: u16 = 0xC612;
+
+/// TIFF field type for ASCII strings
+const TYPE_ASCII: u16 = 2;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct RawFormat {
+    /// Extensions the format is known by, lowercase
+    pub extensions: &'static [&'static str],
+    pub mime_type: &'static str,
+    /// Format name, reported as the file's `codec`
+    pub codec: &'static str,
+}
+
+pub const CR2: RawFormat = RawFormat {
+    extensions: &["cr2"],
+    mime_type: "image/x-canon-cr2",
+    codec: "Canon RAW",
+};
+
+pub const NEF: RawFormat = RawFormat {
+    extensions: &["nef"],
+    mime_type: "image/x-nikon-nef",
+    codec: "Nikon RAW",
+};
+
+pub const ARW: RawFormat = RawFormat {
+    extensions: &["arw"],
+    mime_type: "image/x-sony-arw",
+    codec: "Sony RAW",
+};
+
+pub const DNG: RawFormat = RawFormat {
+    extensions: &["dng"],
+    mime_type: "image/x-adobe-dng",
+    codec: "DNG",
+};
+
+pub const RAW_FORMATS: &[RawFormat] = &[CR2, NEF, ARW, DNG];
+
+impl RawFormat {
+    pub fn for_extension(ext: &str) -> Option<&'static RawFormat> {
+        RAW_FORMATS
+            .iter()
+            .find(|f| f.extensions.iter().any(|e| e.eq_ignore_ascii_case(ext)))
+    }
+
+    pub(crate) fn file_info(&self, extension: Option<&str>) -> FileInfo {
+        FileInfo {
+            mime_type: self.mime_type.to_string(),
+            category: FileCategory::Image,
+            extension: extension.map(String::from),
+            codec: Some(self.codec.to_string()),
+            animated: None,
+            orientation: None,
+            dimensions: None,
+            extension_mime: None,
+            extension_mismatch: false,
+        }
+    }
+}
+
+/// RAW format of a TIFF-structured `data`; `None` for plain TIFF and
+/// anything else
+pub fn detect(data: &[u8]) -> Option<&'static RawFormat> {
+    let tiff = Tiff::new(data)?;
+    if data.get(8..10) == Some(b"CR") {
+        return Some(&CR2);
+    }
+
+    let ifd0 = tiff.u32(4)? as usize;
+    let make = tiff.entry(ifd0, TAG_MAKE).and_then(|e| tiff.ascii(e));
+    let has_sub_ifds = tiff.entry(ifd0, TAG_SUB_IFDS).is_some();
+    if tiff.entry(ifd0, TAG_DNG_VERSION).is_some() {
+        return Some(&DNG);
+    }
+
+    let maker_note = tiff
+        .entry(ifd0, TAG_EXIF_IFD)
+        .and_then(|e| tiff.u32(e + 8))
+        .and_then(|exif| tiff.entry(exif as usize, TAG_MAKER_NOTE))
+        .and_then(|e| tiff.value(e));
+
+    let is_nikon = maker_note.is_some_and(|note| note.starts_with(b"Nikon\0"))
+        || make.is_some_and(|make| make.starts_with(b"NIKON"));
+    let is_sony = maker_note.is_some_and(|note| note.starts_with(b"SONY"))
+        || make.is_some_and(|make| make.starts_with(b"SONY"));
+
+    if is_nikon && has_sub_ifds {
+        Some(&NEF)
+    } else if is_sony {
+        Some(&ARW)
+    } else {
+        None
+    }
+}
+
+/// Just enough of a TIFF reader to look up tags in an IFD
+struct Tiff<'a> {
+    data: &'a [u8],
+    little_endian: bool,
+}
+
+impl<'a> Tiff<'a> {
+    fn new(data: &'a [u8]) -> Option<Self> {
+        let little_endian = match data.get(0..4)? {
+            b"II*\0" => true,
+            b"MM\0*" => false,
+            _ => return None,
+        };
+        Some(Self {
+            data,
+            little_endian,
+        })
+    }
+
+    fn u16(&self, at: usize) -> Option<u16> {
+        let bytes = [*self.data.get(at)?, *self.data.get(at + 1)?];
+        Some(if self.little_endian {
+            u16::from_le_bytes(bytes)
+        } else {
+            u16::from_be_bytes(bytes)
+        })
+    }
+
+    fn u32(&self, at: usize) -> Option<u32> {
+        let bytes: [u8; 4] = self.data.get(at..at + 4)?.try_into().ok()?;
+        Some(if self.little_endian {
+            u32::from_le_bytes(bytes)
+        } else {
+            u32::from_be_bytes(bytes)
+        })
+    }
+
+    /// Offset of the 12-byte entry for `tag` in the IFD at `ifd`
+    fn entry(&self, ifd: usize, tag: u16) -> Option<usize> {
+        let count = self.u16(ifd)? as usize;
+        (0..count)
+            .map(|i| ifd + 2 + i * 12)
+            .find(|&entry| self.u16(entry) == Some(tag))
+    }
+
+    /// Bytes of the entry's value, for byte-sized types (ASCII, UNDEFINED)
+    fn value(&self, entry: usize) -> Option<&'a [u8]> {
+        let len = self.u32(entry + 4)? as usize;
+        let start = if len <= 4 {
+            entry + 8
+        } else {
+            self.u32(entry + 8)? as usize
+        };
+        self.data.get(start..start.checked_add(len)?)
+    }
+
+    fn ascii(&self, entry: usize) -> Option<&'a [u8]> {
+        if self.u16(entry + 2)? != TYPE_ASCII {
+            return None;
+        }
+        self.value(entry)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// Little-endian TIFF whose first IFD holds `ifd0` entries, with the
+    /// EXIF IFD holding `maker_note` when given. Values longer than four
+    /// bytes go after the IFDs.
+    fn tiff(ifd0: &[(u16, u16, &[u8])], maker_note: Option<&[u8]>) -> Vec<u8> {
+        let mut entries: Vec<(u16, u16, Vec<u8>)> = ifd0
+            .iter()
+            .map(|(tag, kind, value)| (*tag, *kind, value.to_vec()))
+            .collect();
+        if maker_note.is_some() {
+            entries.push((TAG_EXIF_IFD, 4, Vec::new()));
+        }
+
+        let ifd0_len = 2 + entries.len() * 12 + 4;
+        let exif_at = 8 + ifd0_len;
+        let exif_len = if maker_note.is_some() { 2 + 12 + 4 } else { 0 };
+        let mut extra_at = exif_at + exif_len;
+        let mut extra = Vec::new();
+
+        let mut data = b"II*\0".to_vec();
+        data.extend_from_slice(&8u32.to_le_bytes());
+        data.extend_from_slice(&(entries.len() as u16).to_le_bytes());
+
+        let mut write_entry = |data: &mut Vec<u8>, tag: u16, kind: u16, value: &[u8]| {
+            data.extend_from_slice(&tag.to_le_bytes());
+            data.extend_from_slice(&kind.to_le_bytes());
+            data.extend_from_slice(&(value.len() as u32).to_le_bytes());
+            if value.len() <= 4 {
+                let mut inline = value.to_vec();
+                inline.resize(4, 0);
+                data.extend_from_slice(&inline);
+            } else {
+                data.extend_from_slice(&(extra_at as u32).to_le_bytes());
+                extra.extend_from_slice(value);
+                extra_at += value.len();
+            }
+        };
+
+        for (tag, kind, value) in &entries {
+            if *tag == TAG_EXIF_IFD {
+                data.extend_from_slice(&TAG_EXIF_IFD.to_le_bytes());
+                data.extend_from_slice(&4u16.to_le_bytes());
+                data.extend_from_slice(&1u32.to_le_bytes());
+                data.extend_from_slice(&(exif_at as u32).to_le_bytes());
+            } else {
+                write_entry(&mut data, *tag, *kind, value);
+            }
+        }
+        data.extend_from_slice(&0u32.to_le_bytes());
+
+        if let Some(note) = maker_note {
+            data.extend_from_slice(&1u16.to_le_bytes());
+            write_entry(&mut data, TAG_MAKER_NOTE, 7, note);
+            data.extend_from_slice(&0u32.to_le_bytes());
+        }
+
+        data.extend_from_slice(&extra);
+        data
+    }
+
+    #[test]
+    fn test_raw_sniffing() {
+        let mut cr2 = b"II*\0\x10\0\0\0CR\x02\0".to_vec();
+        cr2.resize(32, 0);
+        assert_eq!(detect(&cr2), Some(&CR2));
+
+        let sub_ifds: (u16, u16, &[u8]) = (TAG_SUB_IFDS, 4, &[0, 1, 0, 0]);
+        let nef = tiff(
+            &[(TAG_MAKE, 2, b"NIKON CORPORATION\0"), sub_ifds],
+            Some(b"Nikon\0\x02\x10\0\0II*\0"),
+        );
+        assert_eq!(detect(&nef), Some(&NEF));
+
+        // Found by the maker note even when the make is missing
+        let nef = tiff(&[sub_ifds], Some(b"Nikon\0\x02\x10\0\0II*\0"));
+        assert_eq!(detect(&nef), Some(&NEF));
+
+        let arw = tiff(&[(TAG_MAKE, 2, b"SONY\0")], Some(b"SONY DSC \0\0\0"));
+        assert_eq!(detect(&arw), Some(&ARW));
+
+        let dng = tiff(
+            &[
+                (TAG_MAKE, 2, b"NIKON CORPORATION\0"),
+                (TAG_DNG_VERSION, 1, &[1, 4, 0, 0]),
+            ],
+            None,
+        );
+        assert_eq!(detect(&dng), Some(&DNG));
+    }
+
+    #[test]
+    fn test_plain_tiff() {
+        // What a Nikon camera writes when set to shoot TIFF
+        let nikon_tiff = tiff(
+            &[(TAG_MAKE, 2, b"NIKON CORPORATION\0")],
+            Some(b"Nikon\0\x02\x10\0\0II*\0"),
+        );
+        assert_eq!(detect(&nikon_tiff), None);
+
+        assert_eq!(detect(&tiff(&[(TAG_MAKE, 2, b"Canon\0")], None)), None);
+        assert_eq!(detect(b"II*\0\xff\xff\xff\xff"), None);
+        assert_eq!(detect(b"\x89PNG\r\n\x1a\n"), None);
+    }
+}
@@ -1,6 +1,22 @@
-use std::path::Path;
+use crate::camera_raw::{self, RawFormat};
+use crate::model3d::{self, ModelFormat};
+use crate::orientation::{orientation_from_bytes, Orientation};
+use crate::sidecar::{self, SidecarFormat};
+use crate::svg;
+use std::collections::{HashMap, VecDeque};
+use std::io::Read;
+use std::path::{Path, PathBuf};
+use std::sync::Mutex;
+use std::time::SystemTime;
 use thiserror::Error;
 
+/// Default number of paths whose detection result is kept
+const DEFAULT_CACHE_CAPACITY: usize = 4096;
+
+/// Bytes of a stream [`FileDetector::detect_from_reader`] looks at, enough
+/// to cover the XMP packet orientation is read from
+pub const STREAM_PREFIX_LIMIT: usize = 1024 * 1024;
+
 #[derive(Error, Debug)]
 pub enum DetectorError {
     #[error("Failed to read file: {0}")]
@@ -9,7 +25,7 @@ pub enum DetectorError {
     UnknownFormat,
 }
 
-#[derive(Debug, Clone, PartialEq)]
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
 pub enum FileCategory {
     Video,
     Audio,
@@ -18,29 +34,159 @@ pub enum FileCategory {
     Model3D,
     Archive,
     Code,
+    /// Project or session file of a creative app, named in `codec`
+    Project,
+    /// Subtitle track beside a video, format named in `codec`
+    Subtitle,
+    /// Timeline interchange (EDL, Final Cut XML, AAF, OpenTimelineIO),
+    /// format named in `codec`
+    EditExchange,
     Unknown,
 }
 
+/// Project/session file format of a creative application
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct ProjectFormat {
+    pub extension: &'static str,
+    pub mime_type: &'static str,
+    /// Application name, reported as the file's `codec`
+    pub app: &'static str,
+    /// Program that opens the file, as named in the workflow profiles
+    pub executable: &'static str,
+}
+
+pub const PROJECT_FORMATS: &[ProjectFormat] = &[
+    ProjectFormat {
+        extension: "blend",
+        mime_type: "application/x-blender",
+        app: "Blender",
+        executable: "blender",
+    },
+    ProjectFormat {
+        extension: "kra",
+        mime_type: "application/x-krita",
+        app: "Krita",
+        executable: "krita",
+    },
+    ProjectFormat {
+        extension: "xcf",
+        mime_type: "image/x-xcf",
+        app: "GIMP",
+        executable: "gimp",
+    },
+    ProjectFormat {
+        extension: "ardour",
+        mime_type: "application/x-ardour-session",
+        app: "Ardour",
+        executable: "ardour8",
+    },
+    ProjectFormat {
+        extension: "drp",
+        mime_type: "application/x-davinci-resolve-project",
+        app: "DaVinci Resolve",
+        executable: "resolve",
+    },
+];
+
+impl ProjectFormat {
+    pub fn for_extension(ext: &str) -> Option<&'static ProjectFormat> {
+        PROJECT_FORMATS
+            .iter()
+            .find(|f| f.extension.eq_ignore_ascii_case(ext))
+    }
+
+    /// Format whose `app` matches a detected file's `codec`
+    pub fn for_app(app: &str) -> Option<&'static ProjectFormat> {
+        PROJECT_FORMATS.iter().find(|f| f.app == app)
+    }
+
+    fn file_info(&self, extension: Option<&str>) -> FileInfo {
+        FileInfo {
+            mime_type: self.mime_type.to_string(),
+            category: FileCategory::Project,
+            extension: extension.map(String::from),
+            codec: Some(self.app.to_string()),
+            animated: None,
+            orientation: None,
+            dimensions: None,
+            extension_mime: None,
+            extension_mismatch: false,
+        }
+    }
+}
+
 #[derive(Debug, Clone)]
 pub struct FileInfo {
     pub mime_type: String,
     pub category: FileCategory,
     pub extension: Option<String>,
     pub codec: Option<String>,
+    /// Whether a GIF/WebP/AVIF/HEIF holds more than one frame; `None` for
+    /// formats that are never animated or when it could not be determined
+    pub animated: Option<bool>,
+    /// EXIF/XMP orientation of a photo; `None` when it records none
+    pub orientation: Option<Orientation>,
+    /// Width and height an SVG declares, in CSS pixels; not read for other
+    /// formats
+    pub dimensions: Option<(u32, u32)>,
+    /// Type the extension alone names, when it is a known format
+    pub extension_mime: Option<String>,
+    /// The content is confidently something other than `extension_mime`,
+    /// like a renamed executable or a WebP saved as `.jpg`. `mime_type`
+    /// always follows the content.
+    pub extension_mismatch: bool,
+}
+
+impl FileInfo {
+    /// Executable content under a name that claims otherwise, the classic
+    /// disguise for malware
+    pub fn is_suspicious(&self) -> bool {
+        self.extension_mismatch && EXECUTABLE_MIME_TYPES.contains(&self.mime_type.as_str())
+    }
 }
 
 pub struct FileDetector {
     // Using infer crate for magic byte detection
+    cache: Mutex<DetectionCache>,
 }
 
 impl FileDetector {
     pub fn new() -> Self {
-        Self {}
+        Self::with_cache_capacity(DEFAULT_CACHE_CAPACITY)
+    }
+
+    pub fn with_cache_capacity(capacity: usize) -> Self {
+        Self {
+            cache: Mutex::new(DetectionCache::new(capacity)),
+        }
     }
 
+    /// Detect the format of a file on disk. Results are cached per path and
+    /// reused until the file's modification time changes.
     pub fn detect(&self, path: &Path) -> Result<FileInfo, DetectorError> {
+        let modified = std::fs::metadata(path)?.modified().ok();
+
+        if let Some(modified) = modified {
+            if let Some(info) = self.cache.lock().unwrap().get(path, modified) {
+                return Ok(info);
+            }
+        }
+
         let data = std::fs::read(path)?;
-        self.detect_from_bytes(&data, path.extension().and_then(|e| e.to_str()))
+        let info = self.detect_from_bytes(&data, path.extension().and_then(|e| e.to_str()))?;
+
+        if let Some(modified) = modified {
+            self.cache
+                .lock()
+                .unwrap()
+                .insert(path.to_path_buf(), modified, info.clone());
+        }
+
+        Ok(info)
+    }
+
+    pub fn clear_cache(&self) {
+        self.cache.lock().unwrap().clear();
     }
 
     pub fn detect_from_bytes(
@@ -48,23 +194,98 @@ impl FileDetector {
         data: &[u8],
         extension: Option<&str>,
     ) -> Result<FileInfo, DetectorError> {
-        // Try magic byte detection first
+        let from_content = self.detect_from_content(data, extension);
+        let by_extension = extension.and_then(|ext| self.detect_by_extension(ext).ok());
+
+        match (from_content, by_extension) {
+            (Some(mut info), by_extension) => {
+                if let Some(by_extension) = by_extension {
+                    info.extension_mismatch =
+                        !content_fits_extension(&info.mime_type, &by_extension.mime_type);
+                    info.extension_mime = Some(by_extension.mime_type);
+                }
+                Ok(info)
+            }
+            // Fallback to extension-based detection
+            (None, Some(info)) => Ok(info),
+            (None, None) => Err(DetectorError::UnknownFormat),
+        }
+    }
+
+    /// Detect the format of a stream without a path, like stdin or an
+    /// archive entry, from its first [`STREAM_PREFIX_LIMIT`] bytes. With no
+    /// extension to fall back on, only content that is recognised counts.
+    /// The prefix is consumed from `reader`.
+    pub fn detect_from_reader<R: Read>(&self, reader: R) -> Result<FileInfo, DetectorError> {
+        let mut prefix = Vec::new();
+        reader
+            .take(STREAM_PREFIX_LIMIT as u64)
+            .read_to_end(&mut prefix)?;
+        self.detect_from_bytes(&prefix, None)
+    }
+
+    /// Type of `data` from its magic bytes, if they are recognised
+    fn detect_from_content(&self, data: &[u8], extension: Option<&str>) -> Option<FileInfo> {
+        // Project files are often ZIP or gzip containers, which infer would
+        // report as archives
+        if let Some(project) = detect_project(data, extension) {
+            return Some(project.file_info(extension));
+        }
+
+        // 3D formats infer doesn't know, and USDZ, which it would call a ZIP
+        if let Some(format) = model3d::detect(data) {
+            return Some(format.file_info(extension));
+        }
+
+        // infer would call TIFF-based RAW plain TIFF
+        if let Some(format) = camera_raw::detect(data) {
+            let mut info = format.file_info(extension);
+            info.orientation = orientation_from_bytes(data);
+            return Some(info);
+        }
+
+        // Text formats infer doesn't know, or would call plain XML, and AAF,
+        // which it would call a generic compound file
+        if let Some(format) = sidecar::detect(data, extension) {
+            return Some(format.file_info(extension));
+        }
+
+        // infer would call an SVG with an XML declaration plain XML
+        if let Some(svg) = svg::parse(data) {
+            return Some(FileInfo {
+                mime_type: "image/svg+xml".to_string(),
+                category: FileCategory::Image,
+                extension: extension.map(String::from),
+                codec: Some("SVG".to_string()),
+                animated: Some(svg.animated),
+                orientation: None,
+                dimensions: svg.dimensions(),
+                extension_mime: None,
+                extension_mismatch: false,
+            });
+        }
+
         if let Some(kind) = infer::get(data) {
             let category = self.categorize_mime(kind.mime_type());
-            return Ok(FileInfo {
+            let orientation = if category == FileCategory::Image {
+                orientation_from_bytes(data)
+            } else {
+                None
+            };
+            return Some(FileInfo {
                 mime_type: kind.mime_type().to_string(),
                 category,
                 extension: extension.map(String::from),
                 codec: self.detect_codec(kind.mime_type(), data),
+                animated: detect_animation(kind.mime_type(), data),
+                orientation,
+                dimensions: None,
+                extension_mime: None,
+                extension_mismatch: false,
             });
         }
 
-        // Fallback to extension-based detection
-        if let Some(ext) = extension {
-            return self.detect_by_extension(ext);
-        }
-
-        Err(DetectorError::UnknownFormat)
+        None
     }
 
     fn categorize_mime(&self, mime: &str) -> FileCategory {
@@ -108,6 +329,27 @@ impl FileDetector {
     }
 
     fn detect_by_extension(&self, ext: &str) -> Result<FileInfo, DetectorError> {
+        if let Some(project) = ProjectFormat::for_extension(ext) {
+            let mut info = project.file_info(Some(ext));
+            info.extension_mime = Some(info.mime_type.clone());
+            return Ok(info);
+        }
+        if let Some(format) = SidecarFormat::for_extension(ext) {
+            let mut info = format.file_info(Some(ext));
+            info.extension_mime = Some(info.mime_type.clone());
+            return Ok(info);
+        }
+        if let Some(format) = ModelFormat::for_extension(ext) {
+            let mut info = format.file_info(Some(ext));
+            info.extension_mime = Some(info.mime_type.clone());
+            return Ok(info);
+        }
+        if let Some(format) = RawFormat::for_extension(ext) {
+            let mut info = format.file_info(Some(ext));
+            info.extension_mime = Some(info.mime_type.clone());
+            return Ok(info);
+        }
+
         let (mime, category, codec) = match ext.to_lowercase().as_str() {
             // Video
             "mp4" | "m4v" => ("video/mp4", FileCategory::Video, Some("H.264")),
@@ -135,30 +377,14 @@ impl FileDetector {
             "tiff" | "tif" => ("image/tiff", FileCategory::Image, Some("TIFF")),
             "exr" => ("image/x-exr", FileCategory::Image, Some("OpenEXR")),
             "hdr" => ("image/vnd.radiance", FileCategory::Image, Some("RGBE")),
+            "svg" => ("image/svg+xml", FileCategory::Image, Some("SVG")),
 
-            // RAW
-            "cr2" | "cr3" => ("image/x-canon-cr2", FileCategory::Image, Some("Canon RAW")),
-            "nef" => ("image/x-nikon-nef", FileCategory::Image, Some("Nikon RAW")),
-            "arw" => ("image/x-sony-arw", FileCategory::Image, Some("Sony RAW")),
-            "dng" => ("image/x-adobe-dng", FileCategory::Image, Some("DNG")),
+            // RAW not built on TIFF
+            "cr3" => ("image/x-canon-cr3", FileCategory::Image, Some("Canon RAW")),
 
             // 3D
             "gltf" | "glb" => ("model/gltf+json", FileCategory::Model3D, Some("glTF")),
             "obj" => ("model/obj", FileCategory::Model3D, None),
-            "fbx" => (
-                "application/octet-stream",
-                FileCategory::Model3D,
-                Some("FBX"),
-            ),
-            "blend" => (
-                "application/x-blender",
-                FileCategory::Model3D,
-                Some("Blender"),
-            ),
-            "stl" => ("model/stl", FileCategory::Model3D, None),
-            "usd" | "usda" | "usdc" | "usdz" => {
-                ("model/vnd.usd+zip", FileCategory::Model3D, Some("USD"))
-            }
 
             // Documents
             "pdf" => ("application/pdf", FileCategory::Document, None),
@@ -197,10 +423,329 @@ impl FileDetector {
             category,
             extension: Some(ext.to_string()),
             codec: codec.map(String::from),
+            animated: None,
+            orientation: None,
+            dimensions: None,
+            extension_mime: Some(mime.to_string()),
+            extension_mismatch: false,
         })
     }
 }
 
+/// What infer reports for programs and shared libraries
+const EXECUTABLE_MIME_TYPES: &[&str] = &[
+    "application/vnd.microsoft.portable-executable",
+    "application/x-msdownload",
+    "application/x-executable",
+    "application/x-sharedlib",
+    "application/x-mach-binary",
+    "application/vnd.android.dex",
+    "application/x-shellscript",
+    "text/x-shellscript",
+];
+
+/// Formats that share a container, so magic bytes can only name the
+/// container and say nothing about which of them a file is
+const CONTAINER_FAMILIES: &[&[&str]] = &[
+    // ISO base media
+    &[
+        "video/mp4",
+        "video/quicktime",
+        "video/m4v",
+        "video/3gpp",
+        "audio/mp4",
+        "audio/m4a",
+        "image/heic",
+        "image/heif",
+        "image/avif",
+        "image/canon-cr3",
+    ],
+    // TIFF; most camera RAW formats are TIFF inside
+    &[
+        "image/tiff",
+        "image/canon-cr2",
+        "image/nikon-nef",
+        "image/sony-arw",
+        "image/adobe-dng",
+    ],
+    &[
+        "application/zip",
+        "application/vnd.openxmlformats-officedocument.wordprocessingml.document",
+        "application/vnd.openxmlformats-officedocument.spreadsheetml.sheet",
+        "application/vnd.oasis.opendocument.text",
+        "model/vnd.usdz+zip",
+    ],
+    // USD; `.usd` may hold either encoding
+    &["model/vnd.usd", "model/vnd.usda"],
+    &["audio/ogg", "audio/opus", "video/ogg"],
+    &["video/webm", "video/matroska"],
+];
+
+/// Whether content detected as `content` is plausible for a file whose
+/// extension names `by_extension`. A match of the container alone is not
+/// confident enough to call the name wrong, and neither is an extension
+/// that only means "binary". A shebang is how any script starts, so it fits
+/// every text and code extension.
+fn content_fits_extension(content: &str, by_extension: &str) -> bool {
+    // infer and the extension table disagree on which types carry `x-`
+    let canonical = |mime: &str| mime.replacen("/x-", "/", 1);
+    let (content, by_extension) = (canonical(content), canonical(by_extension));
+
+    let shebang = content == "text/shellscript" || content == "application/shellscript";
+
+    content == by_extension
+        || by_extension == "application/octet-stream"
+        || (shebang && by_extension.starts_with("text/"))
+        || CONTAINER_FAMILIES.iter().any(|family| {
+            family.contains(&content.as_str()) && family.contains(&by_extension.as_str())
+        })
+}
+
+/// Project format of `data`, from its own magic where it has one and from
+/// the extension plus the container's magic otherwise
+fn detect_project(data: &[u8], extension: Option<&str>) -> Option<&'static ProjectFormat> {
+    const ZIP: &[u8] = b"PK\x03\x04";
+    const GZIP: &[u8] = &[0x1F, 0x8B];
+    const ZSTD: &[u8] = &[0x28, 0xB5, 0x2F, 0xFD];
+
+    let by_magic = if data.starts_with(b"BLENDER") {
+        Some("blend")
+    } else if data.starts_with(b"gimp xcf ") {
+        Some("xcf")
+    } else if zip_mimetype(data) == Some(b"application/x-krita".as_slice()) {
+        Some("kra")
+    } else {
+        None
+    };
+    if let Some(ext) = by_magic {
+        return ProjectFormat::for_extension(ext);
+    }
+
+    let format = ProjectFormat::for_extension(extension?)?;
+    let matches = match format.extension {
+        // Blender can save compressed: gzip before 3.0, Zstandard since
+        "blend" => data.starts_with(GZIP) || data.starts_with(ZSTD),
+        "ardour" => {
+            let head = &data[..data.len().min(4096)];
+            head.starts_with(b"<?xml") && head.windows(9).any(|w| w == b"<Session ")
+        }
+        // Resolve exports are plain ZIP archives
+        "drp" => data.starts_with(ZIP),
+        _ => false,
+    };
+    matches.then_some(format)
+}
+
+/// Contents of a stored `mimetype` first entry, as in OpenDocument-style
+/// ZIP containers (Krita, ORA, ODF)
+fn zip_mimetype(data: &[u8]) -> Option<&[u8]> {
+    if !data.starts_with(b"PK\x03\x04") || data.get(8..10)? != [0, 0] {
+        return None;
+    }
+
+    let read_u16 = |at: usize| Some(u16::from_le_bytes([*data.get(at)?, *data.get(at + 1)?]));
+    let read_u32 = |at: usize| {
+        Some(u32::from_le_bytes([
+            *data.get(at)?,
+            *data.get(at + 1)?,
+            *data.get(at + 2)?,
+            *data.get(at + 3)?,
+        ]))
+    };
+
+    let size = read_u32(18)? as usize;
+    let name_len = read_u16(26)? as usize;
+    let extra_len = read_u16(28)? as usize;
+    if data.get(30..30 + name_len)? != b"mimetype" {
+        return None;
+    }
+
+    let start = 30 + name_len + extra_len;
+    data.get(start..start + size)
+}
+
+/// Decide whether an image is animated by looking at just enough of it:
+/// a second GIF image descriptor, the WebP animation flag/ANIM chunk, or an
+/// AVIF/HEIF image sequence brand or track.
+pub fn detect_animation(mime: &str, data: &[u8]) -> Option<bool> {
+    match mime {
+        "image/gif" => gif_is_animated(data),
+        "image/webp" => webp_is_animated(data),
+        "image/avif" | "image/heif" | "image/heic" => isobmff_is_animated(data),
+        _ => None,
+    }
+}
+
+fn gif_is_animated(data: &[u8]) -> Option<bool> {
+    if data.len() < 13 || !(data.starts_with(b"GIF87a") || data.starts_with(b"GIF89a")) {
+        return None;
+    }
+
+    let color_table_len = |packed: u8| {
+        if packed & 0x80 != 0 {
+            3 * (1usize << ((packed & 0x07) + 1))
+        } else {
+            0
+        }
+    };
+
+    // Skips a run of data sub-blocks, returning the offset after the terminator
+    let skip_sub_blocks = |mut pos: usize| -> Option<usize> {
+        loop {
+            let size = *data.get(pos)? as usize;
+            pos += 1;
+            if size == 0 {
+                return Some(pos);
+            }
+            pos += size;
+        }
+    };
+
+    let mut pos = 13 + color_table_len(data[10]);
+    let mut frames = 0;
+
+    loop {
+        match *data.get(pos)? {
+            // Image descriptor
+            0x2C => {
+                frames += 1;
+                if frames > 1 {
+                    return Some(true);
+                }
+                let packed = *data.get(pos + 9)?;
+                // Descriptor, local color table, LZW minimum code size
+                pos = skip_sub_blocks(pos + 10 + color_table_len(packed) + 1)?;
+            }
+            // Extension: introducer, label, sub-blocks
+            0x21 => pos = skip_sub_blocks(pos + 2)?,
+            // Trailer
+            0x3B => return Some(false),
+            _ => return None,
+        }
+    }
+}
+
+fn webp_is_animated(data: &[u8]) -> Option<bool> {
+    if data.len() < 16 || &data[0..4] != b"RIFF" || &data[8..12] != b"WEBP" {
+        return None;
+    }
+
+    let mut pos = 12;
+    while let Some(header) = data.get(pos..pos + 8) {
+        let fourcc = &header[0..4];
+        let size = u32::from_le_bytes([header[4], header[5], header[6], header[7]]) as usize;
+
+        match fourcc {
+            // Extended format: the animation bit is in the first flags byte
+            b"VP8X" if *data.get(pos + 8)? & 0x02 != 0 => return Some(true),
+            b"ANIM" | b"ANMF" => return Some(true),
+            // Image data before any animation chunk means a still image
+            b"VP8 " | b"VP8L" => return Some(false),
+            _ => {}
+        }
+
+        // Chunks are padded to an even size
+        pos += 8 + size + (size & 1);
+    }
+
+    None
+}
+
+fn isobmff_is_animated(data: &[u8]) -> Option<bool> {
+    // Image sequences use the `avis`/`msf1`/`hevs` brands and carry a `moov`
+    // track; counting `meta` items would also count thumbnails and EXIF.
+    let mut pos = 0;
+    let mut saw_ftyp = false;
+
+    while let Some(header) = data.get(pos..pos + 8) {
+        let size = u32::from_be_bytes([header[0], header[1], header[2], header[3]]) as usize;
+        let box_type = &header[4..8];
+
+        match box_type {
+            b"ftyp" => {
+                saw_ftyp = true;
+                let brands = data
+                    .get(pos + 8..pos + size.max(8))
+                    .unwrap_or(&data[pos + 8..]);
+                if brands
+                    .chunks(4)
+                    .enumerate()
+                    // Skip the minor version field
+                    .filter(|(i, _)| *i != 1)
+                    .any(|(_, b)| matches!(b, b"avis" | b"msf1" | b"hevs"))
+                {
+                    return Some(true);
+                }
+            }
+            b"moov" => return Some(true),
+            b"meta" if saw_ftyp => return Some(false),
+            _ => {}
+        }
+
+        if size < 8 {
+            // Zero means "to end of file", anything else is malformed
+            break;
+        }
+        pos += size;
+    }
+
+    None
+}
+
+/// Bounded map from path to the last detection result and the mtime it was
+/// taken at. A changed mtime is a miss and the entry is replaced; when full,
+/// the oldest inserted path is evicted.
+struct DetectionCache {
+    entries: HashMap<PathBuf, (SystemTime, FileInfo)>,
+    order: VecDeque<PathBuf>,
+    capacity: usize,
+}
+
+impl DetectionCache {
+    fn new(capacity: usize) -> Self {
+        Self {
+            entries: HashMap::new(),
+            order: VecDeque::new(),
+            capacity,
+        }
+    }
+
+    fn get(&self, path: &Path, modified: SystemTime) -> Option<FileInfo> {
+        self.entries
+            .get(path)
+            .filter(|(cached_at, _)| *cached_at == modified)
+            .map(|(_, info)| info.clone())
+    }
+
+    fn insert(&mut self, path: PathBuf, modified: SystemTime, info: FileInfo) {
+        if self.capacity == 0 {
+            return;
+        }
+
+        if self
+            .entries
+            .insert(path.clone(), (modified, info))
+            .is_none()
+        {
+            self.order.push_back(path);
+        }
+
+        while self.entries.len() > self.capacity {
+            match self.order.pop_front() {
+                Some(oldest) => {
+                    self.entries.remove(&oldest);
+                }
+                None => break,
+            }
+        }
+    }
+
+    fn clear(&mut self) {
+        self.entries.clear();
+        self.order.clear();
+    }
+}
+
 impl Default for FileDetector {
     fn default() -> Self {
         Self::new()
@@ -226,5 +771,236 @@ mod tests {
 
         let info = detector.detect_by_extension("gltf").unwrap();
         assert_eq!(info.category, FileCategory::Model3D);
+
+        let info = detector.detect_by_extension("srt").unwrap();
+        assert_eq!(info.category, FileCategory::Subtitle);
+        assert_eq!(info.codec.as_deref(), Some("SubRip"));
+
+        let info = detector.detect_by_extension("edl").unwrap();
+        assert_eq!(info.category, FileCategory::EditExchange);
+    }
+
+    /// ZIP local header for a stored first entry
+    fn zip_entry(name: &str, content: &[u8]) -> Vec<u8> {
+        let mut data = b"PK\x03\x04".to_vec();
+        // Version, flags, method (stored), time, date, CRC
+        data.extend_from_slice(&[20, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0]);
+        data.extend_from_slice(&(content.len() as u32).to_le_bytes());
+        data.extend_from_slice(&(content.len() as u32).to_le_bytes());
+        data.extend_from_slice(&(name.len() as u16).to_le_bytes());
+        data.extend_from_slice(&0u16.to_le_bytes());
+        data.extend_from_slice(name.as_bytes());
+        data.extend_from_slice(content);
+        data
+    }
+
+    #[test]
+    fn test_reader_detection() {
+        let detector = FileDetector::new();
+
+        let blend = detector
+            .detect_from_reader(std::io::Cursor::new(b"BLENDER-v402REND"))
+            .unwrap();
+        assert_eq!(blend.codec.as_deref(), Some("Blender"));
+        assert_eq!(blend.extension_mime, None);
+
+        // Only the prefix is read, however long the stream
+        assert!(matches!(
+            detector.detect_from_reader(std::io::repeat(0)),
+            Err(DetectorError::UnknownFormat)
+        ));
+        let mut stream = std::io::Cursor::new(vec![0u8; STREAM_PREFIX_LIMIT + 16]);
+        let _ = detector.detect_from_reader(&mut stream);
+        assert_eq!(stream.position(), STREAM_PREFIX_LIMIT as u64);
+    }
+
+    #[test]
+    fn test_project_detection() {
+        let detector = FileDetector::new();
+        let detect =
+            |data: &[u8], ext: Option<&str>| detector.detect_from_bytes(data, ext).unwrap();
+
+        let blend = detect(b"BLENDER-v402REND", None);
+        assert_eq!(blend.category, FileCategory::Project);
+        assert_eq!(blend.codec.as_deref(), Some("Blender"));
+
+        // Compressed .blend files only look like gzip/zstd
+        let zstd_blend = detect(&[0x28, 0xB5, 0x2F, 0xFD, 0, 0], Some("blend"));
+        assert_eq!(zstd_blend.mime_type, "application/x-blender");
+
+        let xcf = detect(b"gimp xcf v011\0\0\0\x10", Some("xcf"));
+        assert_eq!(xcf.codec.as_deref(), Some("GIMP"));
+
+        let kra = detect(&zip_entry("mimetype", b"application/x-krita"), None);
+        assert_eq!(kra.codec.as_deref(), Some("Krita"));
+
+        let session = b"<?xml version=\"1.0\" encoding=\"UTF-8\"?>\n<Session version=\"7003\">";
+        assert_eq!(
+            detect(session, Some("ardour")).codec.as_deref(),
+            Some("Ardour")
+        );
+
+        let drp = detect(&zip_entry("project.xml", b"<Project/>"), Some("drp"));
+        assert_eq!(drp.codec.as_deref(), Some("DaVinci Resolve"));
+
+        // The same ZIP under another name is just an archive
+        let zip = detect(&zip_entry("project.xml", b"<Project/>"), Some("zip"));
+        assert_eq!(zip.category, FileCategory::Archive);
+
+        let info = detector.detect_by_extension("KRA").unwrap();
+        assert_eq!(info.category, FileCategory::Project);
+        assert_eq!(
+            ProjectFormat::for_app(info.codec.as_deref().unwrap())
+                .unwrap()
+                .executable,
+            "krita"
+        );
+    }
+
+    #[test]
+    fn test_extension_mismatch() {
+        let detector = FileDetector::new();
+        let detect =
+            |data: &[u8], ext: Option<&str>| detector.detect_from_bytes(data, ext).unwrap();
+        let png = b"\x89PNG\r\n\x1a\n\0\0\0\rIHDR";
+        let webp = b"RIFF\0\0\0\0WEBPVP8L\0\0\0\0";
+
+        let info = detect(png, Some("png"));
+        assert!(!info.extension_mismatch);
+        assert_eq!(info.extension_mime.as_deref(), Some("image/png"));
+
+        let info = detect(webp, Some("JPG"));
+        assert!(info.extension_mismatch);
+        assert!(!info.is_suspicious());
+        assert_eq!(info.mime_type, "image/webp");
+        assert_eq!(info.extension_mime.as_deref(), Some("image/jpeg"));
+
+        let exe = b"MZ\x90\0\x03\0\0\0\x04\0\0\0\xff\xff\0\0";
+        assert!(detect(exe, Some("png")).is_suspicious());
+
+        // Scripts start with a shebang whatever language they are in
+        let script = b"#!/usr/bin/env python3\nprint('hi')\n";
+        let info = detect(script, Some("py"));
+        assert!(!info.extension_mismatch);
+        assert!(!info.is_suspicious());
+        assert!(!detect(script, Some("txt")).extension_mismatch);
+        assert!(detect(script, Some("png")).is_suspicious());
+
+        // `x-` spelling, shared containers and unknown extensions are fine
+        assert!(!detect(b"fLaC\0\0\0\x22", Some("flac")).extension_mismatch);
+        assert!(!detect(b"II*\0\x08\0\0\0", Some("dng")).extension_mismatch);
+        assert!(!detect(png, Some("asset")).extension_mismatch);
+        assert!(!detect(png, None).extension_mismatch);
+
+        // Nothing to compare against without magic
+        let info = detect(b"plain words", Some("txt"));
+        assert!(!info.extension_mismatch);
+        assert_eq!(info.extension_mime.as_deref(), Some("text/plain"));
+    }
+
+    #[test]
+    fn test_renamed_model_and_raw_files() {
+        let detector = FileDetector::new();
+        let detect =
+            |data: &[u8], ext: Option<&str>| detector.detect_from_bytes(data, ext).unwrap();
+
+        let fbx = detect(b"Kaydara FBX Binary  \0\x1a\0", None);
+        assert_eq!(fbx.category, FileCategory::Model3D);
+        assert_eq!(fbx.codec.as_deref(), Some("FBX"));
+
+        // A USD text file saved as `.usd` is fine, as a `.png` it is not
+        assert!(!detect(b"#usda 1.0\n", Some("usd")).extension_mismatch);
+        assert!(detect(b"#usda 1.0\n", Some("png")).extension_mismatch);
+
+        let mut cr2 = b"II*\0\x10\0\0\0CR\x02\0".to_vec();
+        cr2.resize(32, 0);
+        let info = detect(&cr2, Some("tif"));
+        assert_eq!(info.mime_type, "image/x-canon-cr2");
+        assert_eq!(info.codec.as_deref(), Some("Canon RAW"));
+        assert!(!info.extension_mismatch);
+
+        let info = detector.detect_by_extension("NEF").unwrap();
+        assert_eq!(info.codec.as_deref(), Some("Nikon RAW"));
+    }
+
+    #[test]
+    fn test_svg_detection() {
+        let detector = FileDetector::new();
+        let svg = br#"<?xml version="1.0" encoding="UTF-8"?>
+<svg xmlns="http://www.w3.org/2000/svg" viewBox="0 0 48 24">
+  <circle r="4"><animate attributeName="r" to="8" dur="1s"/></circle>
+</svg>"#;
+
+        let info = detector.detect_from_bytes(svg, Some("svg")).unwrap();
+        assert_eq!(info.mime_type, "image/svg+xml");
+        assert_eq!(info.category, FileCategory::Image);
+        assert_eq!(info.dimensions, Some((48, 24)));
+        assert_eq!(info.animated, Some(true));
+        assert!(!info.extension_mismatch);
+
+        // Other XML stays unrecognised
+        let xml = b"<?xml version=\"1.0\"?><feed/>";
+        let info = detector.detect_from_bytes(xml, None);
+        assert!(info.map_or(true, |info| info.mime_type != "image/svg+xml"));
+    }
+
+    fn gif_with_frames(frames: usize) -> Vec<u8> {
+        let mut data = b"GIF89a".to_vec();
+        // 1x1 screen, no global color table
+        data.extend_from_slice(&[1, 0, 1, 0, 0x00, 0, 0]);
+        for _ in 0..frames {
+            // Graphic control extension
+            data.extend_from_slice(&[0x21, 0xF9, 4, 0, 10, 0, 0, 0]);
+            // Image descriptor, LZW min code size, one data sub-block
+            data.extend_from_slice(&[0x2C, 0, 0, 0, 0, 1, 0, 1, 0, 0x00]);
+            data.extend_from_slice(&[2, 2, 0x44, 0x01, 0]);
+        }
+        data.push(0x3B);
+        data
+    }
+
+    #[test]
+    fn test_animation_detection() {
+        assert_eq!(gif_is_animated(&gif_with_frames(1)), Some(false));
+        assert_eq!(gif_is_animated(&gif_with_frames(3)), Some(true));
+
+        let mut webp = b"RIFF\0\0\0\0WEBPVP8X".to_vec();
+        webp.extend_from_slice(&10u32.to_le_bytes());
+        webp.extend_from_slice(&[0x02, 0, 0, 0, 0, 0, 0, 0, 0, 0]);
+        assert_eq!(webp_is_animated(&webp), Some(true));
+
+        let mut still = b"RIFF\0\0\0\0WEBPVP8L".to_vec();
+        still.extend_from_slice(&[0, 0, 0, 0]);
+        assert_eq!(webp_is_animated(&still), Some(false));
+
+        assert_eq!(detect_animation("image/png", &[]), None);
+    }
+
+    #[test]
+    fn test_detection_cache() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("image");
+        let png = b"\x89PNG\r\n\x1a\n\0\0\0\rIHDR";
+
+        std::fs::write(&path, png).unwrap();
+        let mtime = std::fs::metadata(&path).unwrap().modified().unwrap();
+
+        let detector = FileDetector::new();
+        assert_eq!(detector.detect(&path).unwrap().mime_type, "image/png");
+
+        // Same mtime: served from the cache even though the bytes changed
+        std::fs::write(&path, gif_with_frames(1)).unwrap();
+        let file = std::fs::File::options().write(true).open(&path).unwrap();
+        file.set_modified(mtime).unwrap();
+        assert_eq!(detector.detect(&path).unwrap().mime_type, "image/png");
+
+        // A new mtime invalidates the entry
+        file.set_modified(mtime + std::time::Duration::from_secs(5))
+            .unwrap();
+        assert_eq!(detector.detect(&path).unwrap().mime_type, "image/gif");
+
+        file.set_modified(mtime).unwrap();
+        detector.clear_cache();
+        assert_eq!(detector.detect(&path).unwrap().mime_type, "image/gif");
     }
 }
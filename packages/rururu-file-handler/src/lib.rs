@@ -1,10 +1,23 @@
 pub mod cache;
+pub mod camera_raw;
 pub mod codec_registry;
 pub mod dbus_service;
 pub mod file_detector;
 pub mod media;
+pub mod model3d;
+pub mod orientation;
 pub mod plugin;
+pub mod sidecar;
+pub mod svg;
 pub mod thumbnail;
 
+pub use camera_raw::{RawFormat, RAW_FORMATS};
 pub use codec_registry::{CodecCategory, CodecInfo, CodecRegistry};
-pub use file_detector::{DetectorError, FileCategory, FileDetector, FileInfo};
+pub use file_detector::{
+    DetectorError, FileCategory, FileDetector, FileInfo, ProjectFormat, PROJECT_FORMATS,
+    STREAM_PREFIX_LIMIT,
+};
+pub use media::{AudioTags, CoverArt, LoudnessInfo, TagEdits, TagField};
+pub use model3d::{ModelFormat, MODEL_FORMATS};
+pub use orientation::Orientation;
+pub use sidecar::{SidecarFormat, SIDECAR_FORMATS};
@@ -1,13 +1,7 @@
-use std::path::Path;
+use rururu_file_handler::{CodecRegistry, FileDetector};
 use tracing::{info, Level};
 use tracing_subscriber::FmtSubscriber;
 
-mod codec_registry;
-mod file_detector;
-
-pub use codec_registry::CodecRegistry;
-pub use file_detector::FileDetector;
-
 #[tokio::main]
 async fn main() -> Result<(), Box<dyn std::error::Error>> {
     let subscriber = FmtSubscriber::builder().with_max_level(Level::INFO).init();
@@ -1,3 +1,4 @@
+use crate::thumbnail::ThumbnailSize;
 use std::path::Path;
 use std::time::Duration;
 use thiserror::Error;
@@ -12,6 +13,14 @@ pub enum MediaError {
     MetadataError(String),
     #[error("Unsupported format: {0}")]
     UnsupportedFormat(String),
+    #[error("Failed to decode frame: {0}")]
+    DecodeError(String),
+    #[error("Tag editing not available")]
+    TagEditingNotAvailable,
+    #[error("{format} tags can't store {field}")]
+    UnsupportedTagField { format: String, field: TagField },
+    #[error("Failed to write tags: {0}")]
+    WriteError(String),
     #[error("IO error: {0}")]
     IoError(#[from] std::io::Error),
 }
@@ -24,6 +33,24 @@ pub struct VideoInfo {
     pub frame_rate: Option<f64>,
     pub codec: Option<String>,
     pub bitrate: Option<u64>,
+    /// FFmpeg names (`bt709`, `smpte2084`, `arib-std-b67`, ...); `None`
+    /// when the stream leaves them unspecified
+    #[serde(default)]
+    pub color_primaries: Option<String>,
+    #[serde(default)]
+    pub color_transfer: Option<String>,
+    #[serde(default)]
+    pub color_matrix: Option<String>,
+}
+
+impl VideoInfo {
+    /// Whether the stream uses an HDR transfer function (PQ or HLG)
+    pub fn is_hdr(&self) -> bool {
+        matches!(
+            self.color_transfer.as_deref(),
+            Some("smpte2084" | "arib-std-b67")
+        )
+    }
 }
 
 #[derive(Debug, Clone, serde::Serialize, serde::Deserialize)]
@@ -35,6 +62,30 @@ pub struct AudioInfo {
     pub bitrate: Option<u64>,
 }
 
+/// EBU R128 loudness of a file's best audio stream. Values are `None`
+/// when the file is too short or too quiet to measure: integrated loudness
+/// needs one 400 ms gating block above -70 LUFS, loudness range a 3 s
+/// short-term window, and digital silence has no true peak.
+#[derive(Debug, Clone, Copy, PartialEq, serde::Serialize, serde::Deserialize)]
+pub struct LoudnessInfo {
+    pub integrated_lufs: Option<f64>,
+    pub true_peak_dbtp: Option<f64>,
+    /// Loudness range in LU
+    pub lra: Option<f64>,
+}
+
+/// Frame-accurate timing of a file's best video stream, read from every
+/// packet rather than the container header. Timestamps are relative to the
+/// stream's first frame.
+#[derive(Debug, Clone, PartialEq, serde::Serialize, serde::Deserialize)]
+pub struct Timeline {
+    /// From the first frame's start to the last frame's end
+    pub precise_duration: Duration,
+    /// Presentation time of each keyframe, in order
+    pub keyframes: Vec<Duration>,
+    pub frame_count: u64,
+}
+
 #[derive(Debug, Clone, serde::Serialize, serde::Deserialize)]
 pub struct MediaInfo {
     pub video: Option<VideoInfo>,
@@ -45,6 +96,125 @@ pub struct MediaInfo {
     pub album: Option<String>,
 }
 
+/// A tag field [`MediaHandler::write_audio_tags`] can change
+#[derive(Debug, Clone, Copy, PartialEq, Eq, serde::Serialize, serde::Deserialize)]
+pub enum TagField {
+    Title,
+    Artist,
+    Album,
+    AlbumArtist,
+    Genre,
+    Comment,
+    Year,
+    Track,
+    TrackTotal,
+    Disc,
+    CoverArt,
+}
+
+impl TagField {
+    pub const ALL: [TagField; 11] = [
+        TagField::Title,
+        TagField::Artist,
+        TagField::Album,
+        TagField::AlbumArtist,
+        TagField::Genre,
+        TagField::Comment,
+        TagField::Year,
+        TagField::Track,
+        TagField::TrackTotal,
+        TagField::Disc,
+        TagField::CoverArt,
+    ];
+
+    pub fn name(self) -> &'static str {
+        match self {
+            TagField::Title => "title",
+            TagField::Artist => "artist",
+            TagField::Album => "album",
+            TagField::AlbumArtist => "album artist",
+            TagField::Genre => "genre",
+            TagField::Comment => "comment",
+            TagField::Year => "year",
+            TagField::Track => "track number",
+            TagField::TrackTotal => "track total",
+            TagField::Disc => "disc number",
+            TagField::CoverArt => "cover art",
+        }
+    }
+}
+
+impl std::fmt::Display for TagField {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.write_str(self.name())
+    }
+}
+
+/// Changes to an audio file's tags. `None` leaves a field alone; an empty
+/// string or zero removes it.
+#[derive(Debug, Clone, Default, PartialEq, serde::Serialize, serde::Deserialize)]
+pub struct TagEdits {
+    pub title: Option<String>,
+    pub artist: Option<String>,
+    pub album: Option<String>,
+    pub album_artist: Option<String>,
+    pub genre: Option<String>,
+    pub comment: Option<String>,
+    pub year: Option<u32>,
+    pub track: Option<u32>,
+    pub track_total: Option<u32>,
+    pub disc: Option<u32>,
+    /// Front cover to embed, replacing any there is. Never filled in by
+    /// [`MediaHandler::read_audio_tags`]; see [`AudioTags::has_cover_art`].
+    pub cover_art: Option<CoverArt>,
+}
+
+/// A JPEG or PNG image and the MIME type it was picked as
+#[derive(Debug, Clone, PartialEq, serde::Serialize, serde::Deserialize)]
+pub struct CoverArt {
+    pub data: Vec<u8>,
+    pub mime: String,
+}
+
+impl TagEdits {
+    /// Fields this changes, in [`TagField::ALL`] order
+    pub fn fields(&self) -> Vec<TagField> {
+        let set = [
+            self.title.is_some(),
+            self.artist.is_some(),
+            self.album.is_some(),
+            self.album_artist.is_some(),
+            self.genre.is_some(),
+            self.comment.is_some(),
+            self.year.is_some(),
+            self.track.is_some(),
+            self.track_total.is_some(),
+            self.disc.is_some(),
+            self.cover_art.is_some(),
+        ];
+        TagField::ALL
+            .into_iter()
+            .zip(set)
+            .filter_map(|(field, set)| set.then_some(field))
+            .collect()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.fields().is_empty()
+    }
+}
+
+/// The tags an audio file has now, and which fields its container can hold
+#[derive(Debug, Clone, Default, PartialEq, serde::Serialize, serde::Deserialize)]
+pub struct AudioTags {
+    /// Every field the file has, as `Some`
+    pub values: TagEdits,
+    pub has_cover_art: bool,
+    /// Tag format edits are written as, e.g. "ID3v2"
+    pub format: String,
+    pub writable: Vec<TagField>,
+}
+
 pub struct MediaHandler {
     #[cfg(feature = "ffmpeg")]
     _ffmpeg_initialized: bool,
@@ -100,6 +270,9 @@ impl MediaHandler {
                                 .map(|r| r.numerator() as f64 / r.denominator() as f64),
                             codec: stream.parameters().id().name().map(String::from),
                             bitrate: Some(stream.parameters().bit_rate() as u64),
+                            color_primaries: specified(video.color_primaries().name()),
+                            color_transfer: specified(video.color_transfer_characteristic().name()),
+                            color_matrix: specified(video.color_space().name()),
                         });
                     }
                 }
@@ -169,6 +342,333 @@ impl MediaHandler {
         }
     }
 
+    /// Measure integrated loudness, true peak and loudness range per
+    /// EBU R128, decoding the whole audio stream.
+    #[cfg(feature = "ffmpeg")]
+    pub fn analyze_loudness(&self, path: &Path) -> Result<LoudnessInfo, MediaError> {
+        use ffmpeg_next::format::sample::{Sample, Type as SampleType};
+        use ffmpeg_next::media::Type;
+        use ffmpeg_next::software::resampling::Context as Resampler;
+        use ffmpeg_next::ChannelLayout;
+
+        let mut ictx =
+            ffmpeg_next::format::input(&path).map_err(|e| MediaError::OpenError(e.to_string()))?;
+        let stream = ictx
+            .streams()
+            .best(Type::Audio)
+            .ok_or_else(|| MediaError::UnsupportedFormat("No audio stream".into()))?;
+        let stream_index = stream.index();
+
+        let mut decoder =
+            ffmpeg_next::codec::context::Context::from_parameters(stream.parameters())
+                .and_then(|c| c.decoder().audio())
+                .map_err(|e| MediaError::DecodeError(e.to_string()))?;
+
+        // WAV and some raw streams leave the layout unset
+        let layout = if decoder.channel_layout().is_empty() {
+            ChannelLayout::default(decoder.channels() as i32)
+        } else {
+            decoder.channel_layout()
+        };
+        // The meter takes interleaved f32 at the source rate
+        let mut resampler = Resampler::get(
+            decoder.format(),
+            layout,
+            decoder.rate(),
+            Sample::F32(SampleType::Packed),
+            layout,
+            decoder.rate(),
+        )
+        .map_err(|e| MediaError::DecodeError(e.to_string()))?;
+
+        let mut meter = LoudnessMeter::new(decoder.channels() as u32, decoder.rate())?;
+
+        for (stream, packet) in ictx.packets() {
+            if stream.index() != stream_index {
+                continue;
+            }
+            decoder
+                .send_packet(&packet)
+                .map_err(|e| MediaError::DecodeError(e.to_string()))?;
+            meter.receive(&mut decoder, &mut resampler)?;
+        }
+        decoder
+            .send_eof()
+            .map_err(|e| MediaError::DecodeError(e.to_string()))?;
+        meter.receive(&mut decoder, &mut resampler)?;
+
+        meter.finish()
+    }
+
+    #[cfg(not(feature = "ffmpeg"))]
+    pub fn analyze_loudness(&self, _path: &Path) -> Result<LoudnessInfo, MediaError> {
+        Err(MediaError::FfmpegNotAvailable)
+    }
+
+    /// Demux every packet of the best video stream to count frames and
+    /// index keyframes. Nothing is decoded, but the whole file is read, so
+    /// this is meant for on-demand use rather than `get_info`.
+    #[cfg(feature = "ffmpeg")]
+    pub fn probe_timeline(&self, path: &Path) -> Result<Timeline, MediaError> {
+        use ffmpeg_next::media::Type;
+
+        let mut ictx =
+            ffmpeg_next::format::input(&path).map_err(|e| MediaError::OpenError(e.to_string()))?;
+        let stream = ictx
+            .streams()
+            .best(Type::Video)
+            .ok_or_else(|| MediaError::UnsupportedFormat("No video stream".into()))?;
+        let stream_index = stream.index();
+
+        let time_base = f64::from(stream.time_base());
+        if time_base <= 0.0 {
+            return Err(MediaError::MetadataError("Invalid stream time base".into()));
+        }
+        // Some containers leave packet durations at 0; the frame rate then
+        // gives the length of the last frame
+        let rate = f64::from(stream.avg_frame_rate());
+        let frame_ticks = if rate > 0.0 {
+            (1.0 / rate / time_base).round() as i64
+        } else {
+            0
+        };
+
+        let mut builder = TimelineBuilder::new(time_base, frame_ticks);
+        for (stream, packet) in ictx.packets() {
+            if stream.index() != stream_index {
+                continue;
+            }
+            builder.add(
+                packet.pts().or(packet.dts()),
+                packet.duration(),
+                packet.is_key(),
+            );
+        }
+
+        Ok(builder.finish())
+    }
+
+    #[cfg(not(feature = "ffmpeg"))]
+    pub fn probe_timeline(&self, _path: &Path) -> Result<Timeline, MediaError> {
+        Err(MediaError::FfmpegNotAvailable)
+    }
+
+    /// `count` evenly spaced frames of `src`, each scaled to `frame_size`
+    /// and packed as RGB24.
+    #[cfg(feature = "ffmpeg")]
+    pub fn filmstrip_frames(
+        &self,
+        src: &Path,
+        count: u32,
+        frame_size: ThumbnailSize,
+    ) -> Result<Vec<Vec<u8>>, MediaError> {
+        if count == 0 {
+            return Err(MediaError::DecodeError(
+                "Frame count must be non-zero".into(),
+            ));
+        }
+
+        let mut grabber = FrameGrabber::open(src, frame_size.width, frame_size.height)?;
+        let duration = grabber.duration();
+        if duration == 0 {
+            return Err(MediaError::MetadataError("Unknown duration".into()));
+        }
+
+        // Middle of each of `count` equal slices, so the strip never starts
+        // on a fade-in or ends past the last keyframe
+        (0..count)
+            .map(|i| grabber.frame_at(duration * (2 * i as i64 + 1) / (2 * count as i64), 1))
+            .collect()
+    }
+
+    #[cfg(not(feature = "ffmpeg"))]
+    pub fn filmstrip_frames(
+        &self,
+        _src: &Path,
+        _count: u32,
+        _frame_size: ThumbnailSize,
+    ) -> Result<Vec<Vec<u8>>, MediaError> {
+        Err(MediaError::FfmpegNotAvailable)
+    }
+
+    /// Composite `count` evenly spaced frames of `src` into a horizontal PNG
+    /// sprite sheet, each scaled to `frame_size`.
+    #[cfg(all(feature = "ffmpeg", feature = "image-processing"))]
+    pub fn generate_filmstrip(
+        &self,
+        src: &Path,
+        count: u32,
+        frame_size: ThumbnailSize,
+    ) -> Result<Vec<u8>, MediaError> {
+        let frames = self.filmstrip_frames(src, count, frame_size)?;
+        let mut sheet = image::RgbImage::new(frame_size.width * count, frame_size.height);
+
+        for (i, rgb) in frames.into_iter().enumerate() {
+            let frame = image::RgbImage::from_raw(frame_size.width, frame_size.height, rgb)
+                .ok_or_else(|| MediaError::DecodeError("Frame buffer size mismatch".into()))?;
+            image::imageops::replace(&mut sheet, &frame, i as i64 * frame_size.width as i64, 0);
+        }
+
+        let mut png = Vec::new();
+        sheet
+            .write_to(&mut std::io::Cursor::new(&mut png), image::ImageFormat::Png)
+            .map_err(|e| MediaError::DecodeError(e.to_string()))?;
+
+        Ok(png)
+    }
+
+    #[cfg(not(all(feature = "ffmpeg", feature = "image-processing")))]
+    pub fn generate_filmstrip(
+        &self,
+        _src: &Path,
+        _count: u32,
+        _frame_size: ThumbnailSize,
+    ) -> Result<Vec<u8>, MediaError> {
+        Err(MediaError::FfmpegNotAvailable)
+    }
+
+    /// Read the tags of an MP3, FLAC, Ogg, Opus, MP4/M4A, WAV, AIFF or
+    /// APE file
+    #[cfg(feature = "tag-editing")]
+    pub fn read_audio_tags(&self, path: &Path) -> Result<AudioTags, MediaError> {
+        use lofty::prelude::*;
+
+        let tagged =
+            lofty::read_from_path(path).map_err(|e| MediaError::MetadataError(e.to_string()))?;
+        let tag_type = tagged.primary_tag_type();
+        let mut tags = AudioTags {
+            format: tag_format_name(tag_type).to_string(),
+            writable: writable_fields(tag_type).to_vec(),
+            ..Default::default()
+        };
+
+        let Some(tag) = tagged.primary_tag().or_else(|| tagged.first_tag()) else {
+            return Ok(tags);
+        };
+        tags.values = TagEdits {
+            title: tag.title().map(|v| v.into_owned()),
+            artist: tag.artist().map(|v| v.into_owned()),
+            album: tag.album().map(|v| v.into_owned()),
+            album_artist: tag
+                .get_string(&lofty::tag::ItemKey::AlbumArtist)
+                .map(str::to_owned),
+            genre: tag.genre().map(|v| v.into_owned()),
+            comment: tag.comment().map(|v| v.into_owned()),
+            year: tag.year(),
+            track: tag.track(),
+            track_total: tag.track_total(),
+            disc: tag.disk(),
+            cover_art: None,
+        };
+        tags.has_cover_art = !tag.pictures().is_empty();
+        Ok(tags)
+    }
+
+    #[cfg(not(feature = "tag-editing"))]
+    pub fn read_audio_tags(&self, _path: &Path) -> Result<AudioTags, MediaError> {
+        Err(MediaError::TagEditingNotAvailable)
+    }
+
+    /// Write tag changes in place, as ID3v2 in MP3, WAV and AIFF, Vorbis
+    /// comments in FLAC, Ogg and Opus, and iTunes atoms in MP4. Fails
+    /// without touching the file if the container can't hold one of the
+    /// fields.
+    #[cfg(feature = "tag-editing")]
+    pub fn write_audio_tags(&self, path: &Path, edits: &TagEdits) -> Result<(), MediaError> {
+        use lofty::picture::{MimeType, Picture, PictureType};
+        use lofty::prelude::*;
+        use lofty::tag::{ItemKey, Tag};
+
+        /// Apply one edit; `T::default()` is the empty string or zero
+        fn edit<T: Clone + Default + PartialEq>(
+            tag: &mut Tag,
+            value: &Option<T>,
+            set: fn(&mut Tag, T),
+            remove: fn(&mut Tag),
+        ) {
+            match value {
+                None => {}
+                Some(v) if *v == T::default() => remove(tag),
+                Some(v) => set(tag, v.clone()),
+            }
+        }
+
+        if edits.is_empty() {
+            return Ok(());
+        }
+        let cover = match &edits.cover_art {
+            Some(cover) => Some(match cover_art_mime(&cover.data, &cover.mime)? {
+                "image/png" => (MimeType::Png, cover),
+                _ => (MimeType::Jpeg, cover),
+            }),
+            None => None,
+        };
+
+        edit_tag(path, &edits.fields(), |tag| {
+            edit(tag, &edits.title, Tag::set_title, Tag::remove_title);
+            edit(tag, &edits.artist, Tag::set_artist, Tag::remove_artist);
+            edit(tag, &edits.album, Tag::set_album, Tag::remove_album);
+            edit(
+                tag,
+                &edits.album_artist,
+                |tag, v| {
+                    tag.insert_text(ItemKey::AlbumArtist, v);
+                },
+                |tag| {
+                    tag.remove_key(&ItemKey::AlbumArtist);
+                },
+            );
+            edit(tag, &edits.genre, Tag::set_genre, Tag::remove_genre);
+            edit(tag, &edits.comment, Tag::set_comment, Tag::remove_comment);
+            edit(tag, &edits.year, Tag::set_year, Tag::remove_year);
+            edit(tag, &edits.track, Tag::set_track, Tag::remove_track);
+            edit(
+                tag,
+                &edits.track_total,
+                Tag::set_track_total,
+                Tag::remove_track_total,
+            );
+            edit(tag, &edits.disc, Tag::set_disk, Tag::remove_disk);
+            if let Some((mime_type, cover)) = cover {
+                tag.remove_picture_type(PictureType::CoverFront);
+                tag.push_picture(Picture::new_unchecked(
+                    PictureType::CoverFront,
+                    Some(mime_type),
+                    None,
+                    cover.data.clone(),
+                ));
+            }
+        })
+    }
+
+    #[cfg(not(feature = "tag-editing"))]
+    pub fn write_audio_tags(&self, _path: &Path, _edits: &TagEdits) -> Result<(), MediaError> {
+        Err(MediaError::TagEditingNotAvailable)
+    }
+
+    /// Embed a JPEG or PNG as the front cover, replacing any there is
+    #[cfg(feature = "tag-editing")]
+    pub fn embed_cover_art(&self, path: &Path, image: &[u8], mime: &str) -> Result<(), MediaError> {
+        let edits = TagEdits {
+            cover_art: Some(CoverArt {
+                data: image.to_vec(),
+                mime: mime.to_string(),
+            }),
+            ..Default::default()
+        };
+        self.write_audio_tags(path, &edits)
+    }
+
+    #[cfg(not(feature = "tag-editing"))]
+    pub fn embed_cover_art(
+        &self,
+        _path: &Path,
+        _image: &[u8],
+        _mime: &str,
+    ) -> Result<(), MediaError> {
+        Err(MediaError::TagEditingNotAvailable)
+    }
+
     fn get_mp3_info(&self, path: &Path) -> Result<AudioInfo, MediaError> {
         let tag =
             id3::Tag::read_from_path(path).map_err(|e| MediaError::MetadataError(e.to_string()))?;
@@ -183,12 +683,407 @@ impl MediaHandler {
     }
 }
 
+/// Change the primary tag of `path`, creating it if the file has none.
+/// The edited file is written to a temporary file next to the original and
+/// renamed over it, so a failed write leaves the original as it was.
+#[cfg(feature = "tag-editing")]
+fn edit_tag(
+    path: &Path,
+    fields: &[TagField],
+    apply: impl FnOnce(&mut lofty::tag::Tag),
+) -> Result<(), MediaError> {
+    use lofty::config::WriteOptions;
+    use lofty::prelude::*;
+    use lofty::tag::Tag;
+
+    let mut tagged =
+        lofty::read_from_path(path).map_err(|e| MediaError::OpenError(e.to_string()))?;
+    let tag_type = tagged.primary_tag_type();
+    let writable = writable_fields(tag_type);
+    if let Some(&field) = fields.iter().find(|f| !writable.contains(f)) {
+        return Err(MediaError::UnsupportedTagField {
+            format: tag_format_name(tag_type).to_string(),
+            field,
+        });
+    }
+
+    if tagged.primary_tag().is_none() {
+        tagged.insert_tag(Tag::new(tag_type));
+    }
+    let Some(tag) = tagged.primary_tag_mut() else {
+        return Err(MediaError::WriteError("no tag to edit".into()));
+    };
+    apply(tag);
+
+    // Keep the extension last so the copy is probed as the same format.
+    // Dropped, and so removed, on any error before it's persisted.
+    let dir = path
+        .parent()
+        .filter(|dir| !dir.as_os_str().is_empty())
+        .unwrap_or(Path::new("."));
+    let suffix = path
+        .extension()
+        .map(|ext| format!(".{}", ext.to_string_lossy()))
+        .unwrap_or_default();
+    let temp = tempfile::Builder::new()
+        .prefix(".tagging.")
+        .suffix(&suffix)
+        .tempfile_in(dir)?;
+    let mut original = std::fs::File::open(path)?;
+    std::io::copy(&mut original, &mut temp.as_file())?;
+    temp.as_file()
+        .set_permissions(original.metadata()?.permissions())?;
+    tag.save_to_path(temp.path(), WriteOptions::default())
+        .map_err(|e| MediaError::WriteError(e.to_string()))?;
+    temp.as_file().sync_all()?;
+    temp.persist(path).map_err(|e| MediaError::from(e.error))?;
+    Ok(())
+}
+
+/// Fields each tag format can hold. ID3v1, RIFF INFO and AIFF text chunks
+/// are only written to files lofty can't give a richer tag.
+#[cfg(feature = "tag-editing")]
+fn writable_fields(tag_type: lofty::tag::TagType) -> &'static [TagField] {
+    use lofty::tag::TagType;
+
+    match tag_type {
+        TagType::Id3v2 | TagType::VorbisComments | TagType::Mp4Ilst | TagType::Ape => {
+            &TagField::ALL
+        }
+        TagType::Id3v1 | TagType::RiffInfo => &[
+            TagField::Title,
+            TagField::Artist,
+            TagField::Album,
+            TagField::Genre,
+            TagField::Comment,
+            TagField::Year,
+            TagField::Track,
+        ],
+        TagType::AiffText => &[TagField::Title, TagField::Artist, TagField::Comment],
+        _ => &[],
+    }
+}
+
+#[cfg(feature = "tag-editing")]
+fn tag_format_name(tag_type: lofty::tag::TagType) -> &'static str {
+    use lofty::tag::TagType;
+
+    match tag_type {
+        TagType::Id3v2 => "ID3v2",
+        TagType::Id3v1 => "ID3v1",
+        TagType::VorbisComments => "Vorbis comment",
+        TagType::Mp4Ilst => "MP4",
+        TagType::Ape => "APE",
+        TagType::RiffInfo => "RIFF INFO",
+        TagType::AiffText => "AIFF text",
+        _ => "Unknown",
+    }
+}
+
+/// Check cover art is a JPEG or PNG and really is what `mime` says,
+/// returning the canonical MIME type
+#[cfg(any(feature = "tag-editing", test))]
+fn cover_art_mime(image: &[u8], mime: &str) -> Result<&'static str, MediaError> {
+    let expected = match mime {
+        "image/jpeg" | "image/jpg" => "image/jpeg",
+        "image/png" => "image/png",
+        other => {
+            return Err(MediaError::UnsupportedFormat(format!(
+                "cover art must be JPEG or PNG, not {}",
+                other
+            )))
+        }
+    };
+    match infer::get(image).map(|t| t.mime_type()) {
+        Some(actual) if actual == expected => Ok(expected),
+        actual => Err(MediaError::UnsupportedFormat(format!(
+            "cover art is {}, not {}",
+            actual.unwrap_or("not an image"),
+            expected
+        ))),
+    }
+}
+
+/// Gating block integrated loudness needs at least one of
+#[cfg(any(feature = "ffmpeg", test))]
+const MIN_INTEGRATED_SECS: f64 = 0.4;
+/// Short-term window loudness range is measured over
+#[cfg(any(feature = "ffmpeg", test))]
+const MIN_LRA_SECS: f64 = 3.0;
+
+/// EBU R128 meter over interleaved f32 samples
+#[cfg(any(feature = "ffmpeg", test))]
+struct LoudnessMeter {
+    meter: ebur128::EbuR128,
+    channels: u32,
+    sample_rate: u32,
+    frames: u64,
+}
+
+#[cfg(any(feature = "ffmpeg", test))]
+impl LoudnessMeter {
+    fn new(channels: u32, sample_rate: u32) -> Result<Self, MediaError> {
+        let mode = ebur128::Mode::I | ebur128::Mode::LRA | ebur128::Mode::TRUE_PEAK;
+        let meter = ebur128::EbuR128::new(channels, sample_rate, mode)
+            .map_err(|e| MediaError::UnsupportedFormat(format!("{:?}", e)))?;
+        Ok(Self {
+            meter,
+            channels,
+            sample_rate,
+            frames: 0,
+        })
+    }
+
+    fn add(&mut self, samples: &[f32]) -> Result<(), MediaError> {
+        self.meter
+            .add_frames_f32(samples)
+            .map_err(|e| MediaError::DecodeError(format!("{:?}", e)))?;
+        self.frames += (samples.len() / self.channels as usize) as u64;
+        Ok(())
+    }
+
+    /// Feed every frame the decoder has ready
+    #[cfg(feature = "ffmpeg")]
+    fn receive(
+        &mut self,
+        decoder: &mut ffmpeg_next::decoder::Audio,
+        resampler: &mut ffmpeg_next::software::resampling::Context,
+    ) -> Result<(), MediaError> {
+        use ffmpeg_next::util::frame::audio::Audio;
+
+        let mut decoded = Audio::empty();
+        let mut converted = Audio::empty();
+        while decoder.receive_frame(&mut decoded).is_ok() {
+            resampler
+                .run(&decoded, &mut converted)
+                .map_err(|e| MediaError::DecodeError(e.to_string()))?;
+
+            // Packed audio keeps every channel in plane 0
+            let len = converted.samples() * self.channels as usize * 4;
+            let samples: Vec<f32> = converted.data(0)[..len]
+                .chunks_exact(4)
+                .map(|b| f32::from_ne_bytes([b[0], b[1], b[2], b[3]]))
+                .collect();
+            self.add(&samples)?;
+        }
+        Ok(())
+    }
+
+    fn finish(self) -> Result<LoudnessInfo, MediaError> {
+        let seconds = self.frames as f64 / self.sample_rate as f64;
+
+        // -inf when no block passed the gates
+        let integrated_lufs = self
+            .meter
+            .loudness_global()
+            .ok()
+            .filter(|l| l.is_finite() && seconds >= MIN_INTEGRATED_SECS);
+        let lra = self
+            .meter
+            .loudness_range()
+            .ok()
+            .filter(|_| seconds >= MIN_LRA_SECS);
+
+        let mut peak: f64 = 0.0;
+        for channel in 0..self.channels {
+            if let Ok(p) = self.meter.true_peak(channel) {
+                peak = peak.max(p);
+            }
+        }
+        let true_peak_dbtp = (peak > 0.0).then(|| 20.0 * peak.log10());
+
+        Ok(LoudnessInfo {
+            integrated_lufs,
+            true_peak_dbtp,
+            lra,
+        })
+    }
+}
+
+/// Collects packet timestamps of one stream into a [`Timeline`]. Packets
+/// arrive in decode order, so with B-frames presentation times jump back and
+/// forth; the extent is taken over all of them.
+#[cfg(any(feature = "ffmpeg", test))]
+struct TimelineBuilder {
+    /// Seconds per timestamp tick
+    time_base: f64,
+    /// Ticks per frame, for packets without a duration
+    frame_ticks: i64,
+    start: Option<i64>,
+    end: Option<i64>,
+    keyframes: Vec<i64>,
+    frames: u64,
+}
+
+#[cfg(any(feature = "ffmpeg", test))]
+impl TimelineBuilder {
+    fn new(time_base: f64, frame_ticks: i64) -> Self {
+        Self {
+            time_base,
+            frame_ticks,
+            start: None,
+            end: None,
+            keyframes: Vec::new(),
+            frames: 0,
+        }
+    }
+
+    fn add(&mut self, pts: Option<i64>, duration: i64, key: bool) {
+        self.frames += 1;
+        // Counted, but without a timestamp it can't be placed
+        let Some(pts) = pts else {
+            return;
+        };
+        let duration = if duration > 0 {
+            duration
+        } else {
+            self.frame_ticks
+        };
+
+        self.start = Some(self.start.map_or(pts, |s| s.min(pts)));
+        self.end = Some(self.end.map_or(pts + duration, |e| e.max(pts + duration)));
+        if key {
+            self.keyframes.push(pts);
+        }
+    }
+
+    fn finish(mut self) -> Timeline {
+        let start = self.start.unwrap_or(0);
+        let seconds =
+            |ticks: i64| Duration::from_secs_f64((ticks - start).max(0) as f64 * self.time_base);
+
+        self.keyframes.sort_unstable();
+        self.keyframes.dedup();
+
+        Timeline {
+            precise_duration: seconds(self.end.unwrap_or(start)),
+            keyframes: self.keyframes.iter().map(|&k| seconds(k)).collect(),
+            frame_count: self.frames,
+        }
+    }
+}
+
+/// Decodes frames from the best video stream of a file, scaled to a fixed
+/// size and packed as RGB24. Shared by video thumbnails and filmstrips.
+#[cfg(feature = "ffmpeg")]
+pub(crate) struct FrameGrabber {
+    ictx: ffmpeg_next::format::context::Input,
+    decoder: ffmpeg_next::decoder::Video,
+    scaler: ffmpeg_next::software::scaling::Context,
+    stream_index: usize,
+}
+
+#[cfg(feature = "ffmpeg")]
+impl FrameGrabber {
+    pub(crate) fn open(path: &Path, width: u32, height: u32) -> Result<Self, MediaError> {
+        use ffmpeg_next::format::Pixel;
+        use ffmpeg_next::media::Type;
+        use ffmpeg_next::software::scaling::{context::Context as ScalingContext, flag::Flags};
+
+        let ictx =
+            ffmpeg_next::format::input(&path).map_err(|e| MediaError::OpenError(e.to_string()))?;
+
+        let stream = ictx
+            .streams()
+            .best(Type::Video)
+            .ok_or_else(|| MediaError::UnsupportedFormat("No video stream".into()))?;
+        let stream_index = stream.index();
+
+        let decoder = ffmpeg_next::codec::context::Context::from_parameters(stream.parameters())
+            .and_then(|ctx| ctx.decoder().video())
+            .map_err(|e| MediaError::OpenError(e.to_string()))?;
+
+        let scaler = ScalingContext::get(
+            decoder.format(),
+            decoder.width(),
+            decoder.height(),
+            Pixel::RGB24,
+            width,
+            height,
+            Flags::BILINEAR,
+        )
+        .map_err(|e| MediaError::DecodeError(e.to_string()))?;
+
+        Ok(Self {
+            ictx,
+            decoder,
+            scaler,
+            stream_index,
+        })
+    }
+
+    /// Container duration in `AV_TIME_BASE` units, 0 when unknown
+    pub(crate) fn duration(&self) -> i64 {
+        self.ictx.duration().max(0)
+    }
+
+    /// Seek near `timestamp` (`AV_TIME_BASE` units) and return the `skip`-th
+    /// frame decoded after the seek point.
+    pub(crate) fn frame_at(&mut self, timestamp: i64, skip: usize) -> Result<Vec<u8>, MediaError> {
+        use ffmpeg_next::util::frame::video::Video;
+
+        if timestamp > 0 {
+            self.ictx
+                .seek(timestamp, ..)
+                .map_err(|e| MediaError::DecodeError(e.to_string()))?;
+            self.decoder.flush();
+        }
+
+        let mut frame_count = 0;
+        for (stream, packet) in self.ictx.packets() {
+            if stream.index() != self.stream_index {
+                continue;
+            }
+
+            self.decoder
+                .send_packet(&packet)
+                .map_err(|e| MediaError::DecodeError(e.to_string()))?;
+
+            let mut decoded = Video::empty();
+            while self.decoder.receive_frame(&mut decoded).is_ok() {
+                frame_count += 1;
+                if frame_count >= skip {
+                    let mut rgb_frame = Video::empty();
+                    self.scaler
+                        .run(&decoded, &mut rgb_frame)
+                        .map_err(|e| MediaError::DecodeError(e.to_string()))?;
+                    return Ok(pack_rgb24(&rgb_frame));
+                }
+            }
+        }
+
+        Err(MediaError::DecodeError("Failed to extract frame".into()))
+    }
+}
+
+/// Copy an RGB24 frame into a tightly packed buffer, dropping the row
+/// padding FFmpeg adds for alignment.
+#[cfg(feature = "ffmpeg")]
+fn pack_rgb24(frame: &ffmpeg_next::util::frame::video::Video) -> Vec<u8> {
+    let row_bytes = frame.width() as usize * 3;
+    let stride = frame.stride(0);
+    let data = frame.data(0);
+
+    let mut packed = Vec::with_capacity(row_bytes * frame.height() as usize);
+    for row in data.chunks(stride).take(frame.height() as usize) {
+        packed.extend_from_slice(&row[..row_bytes]);
+    }
+    packed
+}
+
 impl Default for MediaHandler {
     fn default() -> Self {
         Self::new().expect("Failed to initialize media handler")
     }
 }
 
+/// FFmpeg reports unset color fields as "unknown"/"reserved"
+#[cfg(feature = "ffmpeg")]
+fn specified(name: Option<&str>) -> Option<String> {
+    name.filter(|n| !matches!(*n, "unknown" | "unspecified" | "reserved"))
+        .map(String::from)
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
@@ -198,4 +1093,263 @@ mod tests {
         let handler = MediaHandler::new();
         assert!(handler.is_ok());
     }
+
+    #[test]
+    fn test_video_is_hdr() {
+        let mut info = VideoInfo {
+            width: 3840,
+            height: 2160,
+            duration: None,
+            frame_rate: Some(24.0),
+            codec: Some("hevc".into()),
+            bitrate: None,
+            color_primaries: Some("bt2020".into()),
+            color_transfer: Some("smpte2084".into()),
+            color_matrix: Some("bt2020nc".into()),
+        };
+        assert!(info.is_hdr());
+
+        info.color_transfer = Some("arib-std-b67".into());
+        assert!(info.is_hdr());
+
+        info.color_transfer = Some("bt709".into());
+        assert!(!info.is_hdr());
+
+        info.color_transfer = None;
+        assert!(!info.is_hdr());
+    }
+
+    /// Interleaved stereo 1 kHz sine at `amplitude` in both channels
+    fn stereo_sine(amplitude: f32, seconds: f32, rate: u32) -> Vec<f32> {
+        (0..(seconds * rate as f32) as usize)
+            .flat_map(|i| {
+                let t = i as f32 / rate as f32;
+                let s = amplitude * (2.0 * std::f32::consts::PI * 1000.0 * t).sin();
+                [s, s]
+            })
+            .collect()
+    }
+
+    #[test]
+    fn test_loudness_of_sine() {
+        // A 0 dBFS 1 kHz sine reads -3.01 LUFS in one channel and 0 LUFS in
+        // both, so -20 dBFS stereo is -20 LUFS
+        let mut meter = LoudnessMeter::new(2, 48000).unwrap();
+        meter.add(&stereo_sine(0.1, 5.0, 48000)).unwrap();
+        let info = meter.finish().unwrap();
+
+        let integrated = info.integrated_lufs.unwrap();
+        assert!((integrated + 20.0).abs() < 0.1, "{}", integrated);
+        let peak = info.true_peak_dbtp.unwrap();
+        assert!((peak + 20.0).abs() < 0.5, "{}", peak);
+        assert!(info.lra.unwrap() < 1.0);
+    }
+
+    #[test]
+    fn test_loudness_of_short_and_silent_audio() {
+        let mut meter = LoudnessMeter::new(2, 48000).unwrap();
+        meter.add(&stereo_sine(0.5, 0.2, 48000)).unwrap();
+        let info = meter.finish().unwrap();
+        assert_eq!(info.integrated_lufs, None);
+        assert_eq!(info.lra, None);
+        assert!(info.true_peak_dbtp.is_some());
+
+        let mut meter = LoudnessMeter::new(2, 48000).unwrap();
+        meter.add(&vec![0.0; 48000 * 2 * 4]).unwrap();
+        let info = meter.finish().unwrap();
+        assert_eq!(info.integrated_lufs, None);
+        assert_eq!(info.true_peak_dbtp, None);
+    }
+
+    #[test]
+    #[cfg(not(feature = "ffmpeg"))]
+    fn test_filmstrip_requires_ffmpeg() {
+        let handler = MediaHandler::new().unwrap();
+        let result =
+            handler.generate_filmstrip(Path::new("/nonexistent.mp4"), 4, ThumbnailSize::SMALL);
+        assert!(matches!(result, Err(MediaError::FfmpegNotAvailable)));
+        let result =
+            handler.filmstrip_frames(Path::new("/nonexistent.mp4"), 4, ThumbnailSize::SMALL);
+        assert!(matches!(result, Err(MediaError::FfmpegNotAvailable)));
+    }
+
+    #[test]
+    fn test_timeline_with_reordered_frames() {
+        // 25 fps in a 1/12800 time base, decode order I P B B I P B B with
+        // the last packet missing its duration
+        let mut builder = TimelineBuilder::new(1.0 / 12800.0, 512);
+        let packets = [
+            (0, true),
+            (1536, false),
+            (512, false),
+            (1024, false),
+            (2048, true),
+            (3584, false),
+            (2560, false),
+            (3072, false),
+        ];
+        for (i, &(pts, key)) in packets.iter().enumerate() {
+            let duration = if i == packets.len() - 1 { 0 } else { 512 };
+            builder.add(Some(pts), duration, key);
+        }
+        let timeline = builder.finish();
+
+        assert_eq!(timeline.frame_count, 8);
+        assert_eq!(timeline.precise_duration, Duration::from_millis(320));
+        assert_eq!(
+            timeline.keyframes,
+            vec![Duration::ZERO, Duration::from_millis(160)]
+        );
+    }
+
+    #[test]
+    fn test_timeline_relative_to_first_frame() {
+        let mut builder = TimelineBuilder::new(0.001, 40);
+        builder.add(Some(1000), 40, true);
+        builder.add(None, 40, false);
+        builder.add(Some(1040), 40, false);
+        let timeline = builder.finish();
+
+        assert_eq!(timeline.frame_count, 3);
+        assert_eq!(timeline.precise_duration, Duration::from_millis(80));
+        assert_eq!(timeline.keyframes, vec![Duration::ZERO]);
+    }
+
+    #[test]
+    #[cfg(not(feature = "ffmpeg"))]
+    fn test_timeline_requires_ffmpeg() {
+        let handler = MediaHandler::new().unwrap();
+        let result = handler.probe_timeline(Path::new("/nonexistent.mp4"));
+        assert!(matches!(result, Err(MediaError::FfmpegNotAvailable)));
+    }
+
+    #[test]
+    fn test_tag_edits_fields() {
+        assert!(TagEdits::default().is_empty());
+
+        let edits = TagEdits {
+            title: Some("Intro".into()),
+            // Removing a field is still a change to it
+            genre: Some(String::new()),
+            track: Some(1),
+            ..Default::default()
+        };
+        assert_eq!(
+            edits.fields(),
+            vec![TagField::Title, TagField::Genre, TagField::Track]
+        );
+    }
+
+    #[test]
+    fn test_cover_art_mime_checked() {
+        let png = b"\x89PNG\r\n\x1a\n\0\0\0\rIHDR";
+        let jpeg = b"\xff\xd8\xff\xe0\0\x10JFIF\0";
+
+        assert_eq!(cover_art_mime(png, "image/png").unwrap(), "image/png");
+        assert_eq!(cover_art_mime(jpeg, "image/jpg").unwrap(), "image/jpeg");
+        assert!(matches!(
+            cover_art_mime(png, "image/jpeg"),
+            Err(MediaError::UnsupportedFormat(_))
+        ));
+        assert!(matches!(
+            cover_art_mime(b"GIF89a", "image/gif"),
+            Err(MediaError::UnsupportedFormat(_))
+        ));
+    }
+
+    /// A second of 8 kHz mono 16-bit silence
+    #[cfg(feature = "tag-editing")]
+    fn silent_wav() -> Vec<u8> {
+        let samples = vec![0u8; 8000 * 2];
+        let mut wav = b"RIFF".to_vec();
+        wav.extend((36 + samples.len() as u32).to_le_bytes());
+        wav.extend(b"WAVEfmt ");
+        wav.extend(16u32.to_le_bytes());
+        wav.extend(1u16.to_le_bytes()); // PCM
+        wav.extend(1u16.to_le_bytes()); // mono
+        wav.extend(8000u32.to_le_bytes());
+        wav.extend((8000u32 * 2).to_le_bytes());
+        wav.extend(2u16.to_le_bytes());
+        wav.extend(16u16.to_le_bytes());
+        wav.extend(b"data");
+        wav.extend((samples.len() as u32).to_le_bytes());
+        wav.extend(samples);
+        wav
+    }
+
+    #[test]
+    #[cfg(feature = "tag-editing")]
+    fn test_tag_round_trip() {
+        use std::os::unix::fs::PermissionsExt;
+
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("take.wav");
+        std::fs::write(&path, silent_wav()).unwrap();
+        std::fs::set_permissions(&path, std::fs::Permissions::from_mode(0o640)).unwrap();
+
+        let handler = MediaHandler::new().unwrap();
+        let edits = TagEdits {
+            title: Some("Room tone".into()),
+            artist: Some("Location sound".into()),
+            track: Some(3),
+            cover_art: Some(CoverArt {
+                data: b"\x89PNG\r\n\x1a\n\0\0\0\rIHDR".to_vec(),
+                mime: "image/png".into(),
+            }),
+            ..Default::default()
+        };
+        handler.write_audio_tags(&path, &edits).unwrap();
+
+        let tags = handler.read_audio_tags(&path).unwrap();
+        assert_eq!(tags.format, "ID3v2");
+        assert_eq!(tags.values.title.as_deref(), Some("Room tone"));
+        assert_eq!(tags.values.artist.as_deref(), Some("Location sound"));
+        assert_eq!(tags.values.track, Some(3));
+        assert!(tags.has_cover_art);
+
+        // An empty value removes the field and leaves the rest
+        let edits = TagEdits {
+            artist: Some(String::new()),
+            ..Default::default()
+        };
+        handler.write_audio_tags(&path, &edits).unwrap();
+        let tags = handler.read_audio_tags(&path).unwrap();
+        assert_eq!(tags.values.artist, None);
+        assert_eq!(tags.values.title.as_deref(), Some("Room tone"));
+        assert!(tags.has_cover_art);
+
+        // Written in place, with no temporary file left behind
+        let mode = std::fs::metadata(&path).unwrap().permissions().mode();
+        assert_eq!(mode & 0o777, 0o640);
+        let entries: Vec<_> = std::fs::read_dir(dir.path()).unwrap().collect();
+        assert_eq!(entries.len(), 1);
+    }
+
+    #[test]
+    #[cfg(feature = "tag-editing")]
+    fn test_bad_cover_art_leaves_file_alone() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("take.wav");
+        std::fs::write(&path, silent_wav()).unwrap();
+
+        let edits = TagEdits {
+            title: Some("Room tone".into()),
+            cover_art: Some(CoverArt {
+                data: b"GIF89a".to_vec(),
+                mime: "image/gif".into(),
+            }),
+            ..Default::default()
+        };
+        let result = MediaHandler::new().unwrap().write_audio_tags(&path, &edits);
+        assert!(matches!(result, Err(MediaError::UnsupportedFormat(_))));
+        assert_eq!(std::fs::read(&path).unwrap(), silent_wav());
+    }
+
+    #[test]
+    #[cfg(not(feature = "tag-editing"))]
+    fn test_tag_writing_requires_feature() {
+        let handler = MediaHandler::new().unwrap();
+        let result = handler.write_audio_tags(Path::new("/nonexistent.mp3"), &TagEdits::default());
+        assert!(matches!(result, Err(MediaError::TagEditingNotAvailable)));
+    }
 }
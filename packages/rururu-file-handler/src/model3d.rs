@@ -0,0 +1,213 @@
+//! 3D scene formats that infer doesn't know.
+//!
+//! Binary FBX opens with `Kaydara FBX Binary` and ASCII FBX with a `; FBX`
+//! comment. Binary STL has no magic: an 80-byte header is followed by a
+//! triangle count, so it is recognised when the file is exactly as long as
+//! that count implies. ASCII STL starts with `solid` and goes on to list
+//! facets. USD's crate files start with `PXR-USDC` and its text files with
+//! `#usda`; USDZ is a ZIP whose first entry is one of those.
+
+use crate::file_detector::{FileCategory, FileInfo};
+
+/// How much of a text file is looked at for a format sniff
+const SNIFF_LEN: usize = 8192;
+
+const FBX_BINARY_MAGIC: &[u8] = b"Kaydara FBX Binary  \0";
+
+/// STL header, then the triangle count
+const STL_HEADER_LEN: usize = 84;
+/// Normal, three vertices and an attribute word
+const STL_TRIANGLE_LEN: usize = 50;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct ModelFormat {
+    /// Extensions the format is known by, lowercase
+    pub extensions: &'static [&'static str],
+    pub mime_type: &'static str,
+    /// Format name, reported as the file's `codec`
+    pub codec: &'static str,
+}
+
+pub const FBX: ModelFormat = ModelFormat {
+    extensions: &["fbx"],
+    mime_type: "model/x-fbx",
+    codec: "FBX",
+};
+
+pub const STL: ModelFormat = ModelFormat {
+    extensions: &["stl"],
+    mime_type: "model/stl",
+    codec: "STL",
+};
+
+/// `.usd` holds either encoding
+pub const USD: ModelFormat = ModelFormat {
+    extensions: &["usd"],
+    mime_type: "model/vnd.usd",
+    codec: "USD",
+};
+
+pub const USDC: ModelFormat = ModelFormat {
+    extensions: &["usdc"],
+    mime_type: "model/vnd.usd",
+    codec: "USD (Crate)",
+};
+
+pub const USDA: ModelFormat = ModelFormat {
+    extensions: &["usda"],
+    mime_type: "model/vnd.usda",
+    codec: "USD (ASCII)",
+};
+
+pub const USDZ: ModelFormat = ModelFormat {
+    extensions: &["usdz"],
+    mime_type: "model/vnd.usdz+zip",
+    codec: "USDZ",
+};
+
+pub const MODEL_FORMATS: &[ModelFormat] = &[FBX, STL, USD, USDC, USDA, USDZ];
+
+impl ModelFormat {
+    pub fn for_extension(ext: &str) -> Option<&'static ModelFormat> {
+        MODEL_FORMATS
+            .iter()
+            .find(|f| f.extensions.iter().any(|e| e.eq_ignore_ascii_case(ext)))
+    }
+
+    pub(crate) fn file_info(&self, extension: Option<&str>) -> FileInfo {
+        FileInfo {
+            mime_type: self.mime_type.to_string(),
+            category: FileCategory::Model3D,
+            extension: extension.map(String::from),
+            codec: Some(self.codec.to_string()),
+            animated: None,
+            orientation: None,
+            dimensions: None,
+            extension_mime: None,
+            extension_mismatch: false,
+        }
+    }
+}
+
+/// 3D format of `data` from its content alone. Binary STL is only found in
+/// the whole file, since it is recognised by its length.
+pub fn detect(data: &[u8]) -> Option<&'static ModelFormat> {
+    if data.starts_with(FBX_BINARY_MAGIC) {
+        return Some(&FBX);
+    }
+    if data.starts_with(b"PXR-USDC") {
+        return Some(&USDC);
+    }
+    if let Some(name) = zip_first_entry_name(data) {
+        let name = name.to_ascii_lowercase();
+        return (name.ends_with(b".usdc") || name.ends_with(b".usda")).then_some(&USDZ);
+    }
+    if is_binary_stl(data) {
+        return Some(&STL);
+    }
+
+    let head = &data[..data.len().min(SNIFF_LEN)];
+    let text = head.strip_prefix(b"\xEF\xBB\xBF").unwrap_or(head);
+    if text.starts_with(b"#usda ") {
+        Some(&USDA)
+    } else if text.starts_with(b"; FBX ") {
+        Some(&FBX)
+    } else if is_ascii_stl(text) {
+        Some(&STL)
+    } else {
+        None
+    }
+}
+
+/// Exactly the length the triangle count after the header implies. Binary
+/// STL headers may themselves start with `solid`, so this is checked first.
+fn is_binary_stl(data: &[u8]) -> bool {
+    let Some(count) = data.get(80..STL_HEADER_LEN) else {
+        return false;
+    };
+    let count = u32::from_le_bytes([count[0], count[1], count[2], count[3]]) as usize;
+    count > 0
+        && count
+            .checked_mul(STL_TRIANGLE_LEN)
+            .and_then(|len| len.checked_add(STL_HEADER_LEN))
+            == Some(data.len())
+}
+
+/// `solid <name>` followed by facets, or an empty `endsolid`
+fn is_ascii_stl(text: &[u8]) -> bool {
+    let text = text.trim_ascii_start();
+    if !text.starts_with(b"solid") || !text.get(5).is_none_or(u8::is_ascii_whitespace) {
+        return false;
+    }
+    let contains = |needle: &[u8]| text.windows(needle.len()).any(|w| w == needle);
+    contains(b"facet normal") || contains(b"endsolid")
+}
+
+/// Name of the first entry of a ZIP archive
+fn zip_first_entry_name(data: &[u8]) -> Option<&[u8]> {
+    if !data.starts_with(b"PK\x03\x04") {
+        return None;
+    }
+    let name_len = u16::from_le_bytes([*data.get(26)?, *data.get(27)?]) as usize;
+    data.get(30..30 + name_len)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn binary_stl(triangles: u32, header: &[u8]) -> Vec<u8> {
+        let mut data = header.to_vec();
+        data.resize(80, 0);
+        data.extend_from_slice(&triangles.to_le_bytes());
+        data.resize(STL_HEADER_LEN + triangles as usize * STL_TRIANGLE_LEN, 0);
+        data
+    }
+
+    #[test]
+    fn test_model_sniffing() {
+        assert_eq!(
+            detect(b"Kaydara FBX Binary  \0\x1a\0\xe8\x1c\0\0"),
+            Some(&FBX)
+        );
+        assert_eq!(detect(b"; FBX 7.4.0 project file\n; ----\n"), Some(&FBX));
+
+        assert_eq!(detect(b"PXR-USDC\0\x08\0\0\0\0\0\0"), Some(&USDC));
+        assert_eq!(
+            detect(b"#usda 1.0\n(\n    upAxis = \"Y\"\n)\n"),
+            Some(&USDA)
+        );
+
+        let mut usdz = b"PK\x03\x04".to_vec();
+        usdz.extend_from_slice(&[20, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0]);
+        usdz.extend_from_slice(&[0; 8]);
+        usdz.extend_from_slice(&10u16.to_le_bytes());
+        usdz.extend_from_slice(&0u16.to_le_bytes());
+        usdz.extend_from_slice(b"scene.usdc");
+        assert_eq!(detect(&usdz), Some(&USDZ));
+    }
+
+    #[test]
+    fn test_stl_sniffing() {
+        assert_eq!(detect(&binary_stl(2, b"binary export")), Some(&STL));
+        // Many exporters start the binary header with "solid" too
+        assert_eq!(detect(&binary_stl(1, b"solid part")), Some(&STL));
+
+        // A header and count alone, without the promised triangles
+        let mut truncated = binary_stl(3, b"binary export");
+        truncated.truncate(120);
+        assert_eq!(detect(&truncated), None);
+
+        let ascii = b"solid cube\n  facet normal 0 0 1\n    outer loop\n";
+        assert_eq!(detect(ascii), Some(&STL));
+        assert_eq!(detect(b"solid cube\nendsolid cube\n"), Some(&STL));
+        assert_eq!(detect(b"solidarity with the workers"), None);
+    }
+
+    #[test]
+    fn test_extensions() {
+        assert_eq!(ModelFormat::for_extension("FBX"), Some(&FBX));
+        assert_eq!(ModelFormat::for_extension("usd"), Some(&USD));
+        assert_eq!(ModelFormat::for_extension("obj"), None);
+    }
+}
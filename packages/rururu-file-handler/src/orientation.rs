@@ -0,0 +1,245 @@
+//! EXIF/XMP orientation of photos.
+//!
+//! Cameras and phones store pixels as the sensor saw them and record how to
+//! display them in the EXIF `Orientation` tag (or `tiff:Orientation` in an
+//! XMP packet). Thumbnails and previews have to apply it themselves.
+
+use serde::{Deserialize, Serialize};
+use std::fs::File;
+use std::io::{BufReader, Cursor, Read};
+use std::path::Path;
+
+/// How much of a file is searched for an XMP packet
+const XMP_SCAN_LIMIT: usize = 512 * 1024;
+
+/// The EXIF `Orientation` values, named by the transform that displays the
+/// stored image upright
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default, Serialize, Deserialize)]
+pub enum Orientation {
+    #[default]
+    Normal,
+    FlipHorizontal,
+    Rotate180,
+    FlipVertical,
+    /// Mirrored along the top-left to bottom-right diagonal
+    Transpose,
+    /// 90° clockwise
+    Rotate90,
+    /// Mirrored along the top-right to bottom-left diagonal
+    Transverse,
+    /// 270° clockwise
+    Rotate270,
+}
+
+impl Orientation {
+    pub fn from_exif(value: u32) -> Option<Self> {
+        Some(match value {
+            1 => Orientation::Normal,
+            2 => Orientation::FlipHorizontal,
+            3 => Orientation::Rotate180,
+            4 => Orientation::FlipVertical,
+            5 => Orientation::Transpose,
+            6 => Orientation::Rotate90,
+            7 => Orientation::Transverse,
+            8 => Orientation::Rotate270,
+            _ => return None,
+        })
+    }
+
+    pub fn exif_value(self) -> u32 {
+        match self {
+            Orientation::Normal => 1,
+            Orientation::FlipHorizontal => 2,
+            Orientation::Rotate180 => 3,
+            Orientation::FlipVertical => 4,
+            Orientation::Transpose => 5,
+            Orientation::Rotate90 => 6,
+            Orientation::Transverse => 7,
+            Orientation::Rotate270 => 8,
+        }
+    }
+
+    /// Whether the upright image is the stored one turned on its side
+    pub fn swaps_dimensions(self) -> bool {
+        matches!(
+            self,
+            Orientation::Transpose
+                | Orientation::Rotate90
+                | Orientation::Transverse
+                | Orientation::Rotate270
+        )
+    }
+
+    /// Turn the stored pixels upright
+    #[cfg(feature = "image-processing")]
+    pub fn apply(self, img: image::DynamicImage) -> image::DynamicImage {
+        match self {
+            Orientation::Normal => img,
+            Orientation::FlipHorizontal => img.fliph(),
+            Orientation::Rotate180 => img.rotate180(),
+            Orientation::FlipVertical => img.flipv(),
+            Orientation::Transpose => img.rotate90().fliph(),
+            Orientation::Rotate90 => img.rotate90(),
+            Orientation::Transverse => img.rotate270().fliph(),
+            Orientation::Rotate270 => img.rotate270(),
+        }
+    }
+}
+
+/// Orientation recorded in the file at `path`, if any
+pub fn read_orientation(path: &Path) -> Option<Orientation> {
+    let mut reader = BufReader::new(File::open(path).ok()?);
+    if let Some(orientation) = exif_orientation(&mut reader) {
+        return Some(orientation);
+    }
+
+    let mut head = Vec::new();
+    File::open(path)
+        .ok()?
+        .take(XMP_SCAN_LIMIT as u64)
+        .read_to_end(&mut head)
+        .ok()?;
+    xmp_orientation(&head)
+}
+
+/// Orientation recorded in an in-memory image file, if any
+pub fn orientation_from_bytes(data: &[u8]) -> Option<Orientation> {
+    exif_orientation(&mut Cursor::new(data))
+        .or_else(|| xmp_orientation(&data[..data.len().min(XMP_SCAN_LIMIT)]))
+}
+
+fn exif_orientation<R: std::io::BufRead + std::io::Seek>(reader: &mut R) -> Option<Orientation> {
+    let exif = exif::Reader::new().read_from_container(reader).ok()?;
+    let field = exif.get_field(exif::Tag::Orientation, exif::In::PRIMARY)?;
+    Orientation::from_exif(field.value.get_uint(0)?)
+}
+
+/// `tiff:Orientation` as an attribute (`tiff:Orientation="6"`) or an
+/// element (`<tiff:Orientation>6</tiff:Orientation>`)
+fn xmp_orientation(data: &[u8]) -> Option<Orientation> {
+    const KEY: &[u8] = b"tiff:Orientation";
+
+    let start = data.windows(KEY.len()).position(|w| w == KEY)? + KEY.len();
+    let rest = &data[start..];
+    let rest = match rest.first()? {
+        b'=' => rest.get(2..)?,
+        b'>' => &rest[1..],
+        _ => return None,
+    };
+
+    let digit = rest.first()?;
+    if !digit.is_ascii_digit() || rest.get(1).is_some_and(u8::is_ascii_digit) {
+        return None;
+    }
+    Orientation::from_exif((digit - b'0') as u32)
+}
+
+#[cfg(test)]
+pub(crate) mod tests {
+    use super::*;
+
+    /// APP1 segment holding a little-endian TIFF with only `Orientation`
+    #[cfg(feature = "image-processing")]
+    fn exif_segment(orientation: u16) -> Vec<u8> {
+        let mut tiff = b"II*\0".to_vec();
+        tiff.extend_from_slice(&8u32.to_le_bytes());
+        // One IFD entry: tag 0x0112, SHORT, count 1, value
+        tiff.extend_from_slice(&1u16.to_le_bytes());
+        tiff.extend_from_slice(&0x0112u16.to_le_bytes());
+        tiff.extend_from_slice(&3u16.to_le_bytes());
+        tiff.extend_from_slice(&1u32.to_le_bytes());
+        tiff.extend_from_slice(&orientation.to_le_bytes());
+        tiff.extend_from_slice(&[0, 0]);
+        // No next IFD
+        tiff.extend_from_slice(&0u32.to_le_bytes());
+
+        let mut segment = vec![0xFF, 0xE1];
+        segment.extend_from_slice(&((tiff.len() + 8) as u16).to_be_bytes());
+        segment.extend_from_slice(b"Exif\0\0");
+        segment.extend_from_slice(&tiff);
+        segment
+    }
+
+    /// A 32x16 JPEG, blue with a red top-left quadrant, tagged `orientation`
+    #[cfg(feature = "image-processing")]
+    pub(crate) fn tagged_jpeg(orientation: u16) -> Vec<u8> {
+        let img = image::RgbImage::from_fn(32, 16, |x, y| {
+            if x < 16 && y < 8 {
+                image::Rgb([255, 0, 0])
+            } else {
+                image::Rgb([0, 0, 255])
+            }
+        });
+        let mut jpeg = Vec::new();
+        image::codecs::jpeg::JpegEncoder::new_with_quality(&mut jpeg, 95)
+            .encode_image(&img)
+            .unwrap();
+
+        // Splice the EXIF segment in right after SOI
+        let mut tagged = jpeg[..2].to_vec();
+        tagged.extend_from_slice(&exif_segment(orientation));
+        tagged.extend_from_slice(&jpeg[2..]);
+        tagged
+    }
+
+    #[test]
+    fn test_exif_value_round_trip() {
+        for value in 1..=8 {
+            assert_eq!(Orientation::from_exif(value).unwrap().exif_value(), value);
+        }
+        assert_eq!(Orientation::from_exif(0), None);
+        assert_eq!(Orientation::from_exif(9), None);
+    }
+
+    #[test]
+    #[cfg(feature = "image-processing")]
+    fn test_jpeg_orientation() {
+        for value in 1..=8u16 {
+            let expected = Orientation::from_exif(value as u32);
+            assert_eq!(orientation_from_bytes(&tagged_jpeg(value)), expected);
+        }
+    }
+
+    #[test]
+    fn test_xmp_orientation() {
+        let attribute = br#"<rdf:Description tiff:Orientation="6" tiff:Make="X"/>"#;
+        assert_eq!(xmp_orientation(attribute), Some(Orientation::Rotate90));
+
+        let element = b"<tiff:Orientation>3</tiff:Orientation>";
+        assert_eq!(xmp_orientation(element), Some(Orientation::Rotate180));
+
+        assert_eq!(xmp_orientation(b"tiff:Orientation=\"12\""), None);
+        assert_eq!(xmp_orientation(b"no packet here"), None);
+    }
+
+    #[test]
+    #[cfg(feature = "image-processing")]
+    fn test_apply_orientation() {
+        let img = image::load_from_memory(&tagged_jpeg(1)).unwrap();
+        let red_at = |img: &image::DynamicImage, x: u32, y: u32| {
+            let p = img.to_rgb8().get_pixel(x, y).0;
+            p[0] > 150 && p[2] < 100
+        };
+
+        for value in 1..=8 {
+            let orientation = Orientation::from_exif(value).unwrap();
+            let upright = orientation.apply(img.clone());
+            let (w, h) = (upright.width(), upright.height());
+            assert_eq!((w, h) == (16, 32), orientation.swaps_dimensions());
+
+            // Where the stored top-left (red) corner ends up
+            let (x, y) = match orientation {
+                Orientation::Normal | Orientation::Transpose => (0, 0),
+                Orientation::FlipHorizontal | Orientation::Rotate90 => (w - 1, 0),
+                Orientation::Rotate180 | Orientation::Transverse => (w - 1, h - 1),
+                Orientation::FlipVertical | Orientation::Rotate270 => (0, h - 1),
+            };
+            assert!(red_at(&upright, x, y), "orientation {}", value);
+            assert!(
+                !red_at(&upright, w - 1 - x, h - 1 - y),
+                "orientation {}",
+                value
+            );
+        }
+    }
+}
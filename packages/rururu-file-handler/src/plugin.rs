@@ -1,3 +1,13 @@
+//! Native plugin loading.
+//!
+//! Plugins are shared libraries running with full process privileges, so
+//! nothing here is a sandbox. The checks are defense-in-depth: libraries are
+//! only loaded from trusted directories that others can't write to, and a
+//! plugin is only handed paths it plausibly has business with — an existing
+//! file with one of its registered extensions, and a thumbnail destination
+//! outside system directories. A malicious plugin can still ignore the path
+//! it was given; run untrusted plugins out of process instead.
+
 use libloading::{Library, Symbol};
 use std::collections::HashMap;
 use std::ffi::OsStr;
@@ -13,28 +23,18 @@ pub enum PluginError {
     NotFound(String),
     #[error("Invalid plugin: {0}")]
     InvalidPlugin(String),
+    #[error("Untrusted plugin: {0}")]
+    Untrusted(String),
+    #[error("Path outside plugin scope: {0}")]
+    OutOfScope(String),
     #[error("IO error: {0}")]
     IoError(#[from] std::io::Error),
 }
 
-#[repr(C)]
-pub struct PluginInfo {
-    pub name: *const std::ffi::c_char,
-    pub version: *const std::ffi::c_char,
-    pub description: *const std::ffi::c_char,
-    pub supported_extensions: *const *const std::ffi::c_char,
-    pub extension_count: usize,
-}
-
-#[repr(C)]
-pub struct FileMetadata {
-    pub mime_type: *const std::ffi::c_char,
-    pub width: u32,
-    pub height: u32,
-    pub duration_ms: u64,
-    pub extra_json: *const std::ffi::c_char,
-}
+pub use rururu_plugin_sdk::ffi::{FileMetadata, PluginInfo};
+use rururu_plugin_sdk::ABI_VERSION;
 
+type PluginAbiVersionFn = unsafe extern "C" fn() -> u32;
 type PluginInfoFn = unsafe extern "C" fn() -> PluginInfo;
 type PluginInitFn = unsafe extern "C" fn() -> i32;
 type PluginDeinitFn = unsafe extern "C" fn();
@@ -43,6 +43,15 @@ type FreeMetadataFn = unsafe extern "C" fn(*mut FileMetadata);
 type GenerateThumbnailFn =
     unsafe extern "C" fn(*const std::ffi::c_char, *const std::ffi::c_char, u32, u32) -> i32;
 
+/// Pseudo-filesystems and device nodes no file plugin should read
+const FORBIDDEN_SOURCE_DIRS: &[&str] = &["/proc", "/sys", "/dev"];
+
+/// Places a thumbnail must never be written to
+const FORBIDDEN_DEST_DIRS: &[&str] = &[
+    "/proc", "/sys", "/dev", "/boot", "/etc", "/usr", "/bin", "/sbin", "/lib", "/lib64", "/opt",
+    "/var/lib", "/run",
+];
+
 pub struct LoadedPlugin {
     _library: Library,
     pub name: String,
@@ -61,6 +70,7 @@ impl LoadedPlugin {
             .ok_or_else(|| PluginError::InvalidPlugin("No get_metadata function".into()))?;
         let free_fn = self.free_metadata;
 
+        let path = scoped_source(path, &self.extensions)?;
         let path_cstr = std::ffi::CString::new(path.to_string_lossy().as_bytes())
             .map_err(|e| PluginError::InvalidPlugin(e.to_string()))?;
 
@@ -82,6 +92,14 @@ impl LoadedPlugin {
                 "width": metadata.width,
                 "height": metadata.height,
                 "duration_ms": metadata.duration_ms,
+                "extra": if metadata.extra_json.is_null() {
+                    None
+                } else {
+                    serde_json::from_slice::<serde_json::Value>(
+                        std::ffi::CStr::from_ptr(metadata.extra_json).to_bytes(),
+                    )
+                    .ok()
+                },
             });
 
             if let Some(free) = free_fn {
@@ -103,6 +121,9 @@ impl LoadedPlugin {
             .generate_thumbnail
             .ok_or_else(|| PluginError::InvalidPlugin("No generate_thumbnail function".into()))?;
 
+        let source = scoped_source(source, &self.extensions)?;
+        let dest = scoped_dest(dest, &source)?;
+
         let source_cstr = std::ffi::CString::new(source.to_string_lossy().as_bytes())
             .map_err(|e| PluginError::InvalidPlugin(e.to_string()))?;
         let dest_cstr = std::ffi::CString::new(dest.to_string_lossy().as_bytes())
@@ -122,8 +143,99 @@ impl LoadedPlugin {
     }
 }
 
+/// Canonical form of a file a plugin is asked to read. It must be an existing
+/// regular file, outside pseudo-filesystems, with an extension the plugin
+/// registered for.
+fn scoped_source(path: &Path, extensions: &[String]) -> Result<PathBuf, PluginError> {
+    let canonical = path
+        .canonicalize()
+        .map_err(|e| PluginError::OutOfScope(format!("{:?}: {}", path, e)))?;
+
+    if !canonical.is_file() {
+        return Err(PluginError::OutOfScope(format!(
+            "{:?} is not a regular file",
+            canonical
+        )));
+    }
+    if is_under_any(&canonical, FORBIDDEN_SOURCE_DIRS) {
+        return Err(PluginError::OutOfScope(format!("{:?}", canonical)));
+    }
+
+    // Check the name the caller used too, so a symlink can't widen the scope
+    let handles = |p: &Path| {
+        p.extension()
+            .and_then(OsStr::to_str)
+            .map(|ext| extensions.iter().any(|e| e.eq_ignore_ascii_case(ext)))
+            .unwrap_or(false)
+    };
+    if !handles(path) || !handles(&canonical) {
+        return Err(PluginError::OutOfScope(format!(
+            "{:?} has no extension this plugin handles",
+            path
+        )));
+    }
+
+    Ok(canonical)
+}
+
+/// Canonical form of a thumbnail destination. Its directory must exist and
+/// lie outside system directories; the file itself may not be a symlink or
+/// the source.
+fn scoped_dest(dest: &Path, source: &Path) -> Result<PathBuf, PluginError> {
+    let file_name = dest
+        .file_name()
+        .ok_or_else(|| PluginError::OutOfScope(format!("{:?} has no file name", dest)))?;
+    let parent = match dest.parent() {
+        Some(p) if !p.as_os_str().is_empty() => p,
+        _ => Path::new("."),
+    };
+    let canonical = parent
+        .canonicalize()
+        .map_err(|e| PluginError::OutOfScope(format!("{:?}: {}", parent, e)))?
+        .join(file_name);
+
+    if is_under_any(&canonical, FORBIDDEN_DEST_DIRS) {
+        return Err(PluginError::OutOfScope(format!("{:?}", canonical)));
+    }
+    if canonical
+        .symlink_metadata()
+        .map(|m| m.file_type().is_symlink())
+        .unwrap_or(false)
+    {
+        return Err(PluginError::OutOfScope(format!(
+            "{:?} is a symlink",
+            canonical
+        )));
+    }
+    if canonical == source {
+        return Err(PluginError::OutOfScope(
+            "Thumbnail would overwrite its source".into(),
+        ));
+    }
+
+    Ok(canonical)
+}
+
+fn is_under_any(path: &Path, dirs: &[&str]) -> bool {
+    dirs.iter().any(|dir| path.starts_with(dir))
+}
+
+/// Whether others can write to `path`, which would let them swap the library
+#[cfg(unix)]
+fn is_world_writable(path: &Path) -> std::io::Result<bool> {
+    use std::os::unix::fs::PermissionsExt;
+    Ok(std::fs::metadata(path)?.permissions().mode() & 0o002 != 0)
+}
+
+#[cfg(not(unix))]
+fn is_world_writable(_path: &Path) -> std::io::Result<bool> {
+    Ok(false)
+}
+
 pub struct PluginManager {
     plugin_dir: PathBuf,
+    /// Directories plugins may be loaded from; always includes `plugin_dir`
+    trusted_dirs: Vec<PathBuf>,
     plugins: HashMap<String, LoadedPlugin>,
     extension_map: HashMap<String, String>, // extension -> plugin name
 }
@@ -131,12 +243,59 @@ pub struct PluginManager {
 impl PluginManager {
     pub fn new(plugin_dir: PathBuf) -> Self {
         Self {
+            trusted_dirs: vec![plugin_dir.clone()],
             plugin_dir,
             plugins: HashMap::new(),
             extension_map: HashMap::new(),
         }
     }
 
+    /// Also allow loading plugins from `dir`
+    pub fn trust_dir(&mut self, dir: PathBuf) {
+        if !self.trusted_dirs.contains(&dir) {
+            self.trusted_dirs.push(dir);
+        }
+    }
+
+    pub fn trusted_dirs(&self) -> &[PathBuf] {
+        &self.trusted_dirs
+    }
+
+    /// Refuse libraries outside the trusted directories, and any that others
+    /// could have replaced: a world-writable file or containing directory.
+    fn check_trusted(&self, path: &Path) -> Result<PathBuf, PluginError> {
+        let canonical = path.canonicalize()?;
+
+        let trusted = self
+            .trusted_dirs
+            .iter()
+            .filter_map(|dir| dir.canonicalize().ok())
+            .any(|dir| canonical.parent() == Some(dir.as_path()));
+        if !trusted {
+            return Err(PluginError::Untrusted(format!(
+                "{:?} is not in a trusted plugin directory",
+                canonical
+            )));
+        }
+
+        if is_world_writable(&canonical)? {
+            return Err(PluginError::Untrusted(format!(
+                "{:?} is world-writable",
+                canonical
+            )));
+        }
+        if let Some(parent) = canonical.parent() {
+            if is_world_writable(parent)? {
+                return Err(PluginError::Untrusted(format!(
+                    "{:?} is world-writable",
+                    parent
+                )));
+            }
+        }
+
+        Ok(canonical)
+    }
+
     pub fn load_all(&mut self) -> Result<(), PluginError> {
         if !self.plugin_dir.exists() {
             warn!("Plugin directory does not exist: {:?}", self.plugin_dir);
@@ -175,8 +334,23 @@ impl PluginManager {
     }
 
     pub fn load_plugin(&mut self, path: &Path) -> Result<(), PluginError> {
+        let path = self.check_trusted(path)?;
+
         unsafe {
-            let library = Library::new(path).map_err(|e| PluginError::LoadError(e.to_string()))?;
+            let library = Library::new(&path).map_err(|e| PluginError::LoadError(e.to_string()))?;
+
+            // Libraries from before the version export speak ABI 1. Checked
+            // first, since nothing else can be read with the wrong layout.
+            let abi_version = library
+                .get::<PluginAbiVersionFn>(b"rururu_plugin_abi_version")
+                .map(|f| f())
+                .unwrap_or(1);
+            if abi_version != ABI_VERSION {
+                return Err(PluginError::InvalidPlugin(format!(
+                    "Plugin ABI version {} (supported: {})",
+                    abi_version, ABI_VERSION
+                )));
+            }
 
             // Get plugin info
             let info_fn: Symbol<PluginInfoFn> = library
@@ -302,4 +476,79 @@ mod tests {
         assert!(manager.load_all().is_ok());
         assert_eq!(manager.plugin_count(), 0);
     }
+
+    #[test]
+    fn test_rejects_untrusted_plugin_dir() {
+        let trusted = tempdir().unwrap();
+        let other = tempdir().unwrap();
+        let library = other.path().join("evil.so");
+        std::fs::write(&library, b"").unwrap();
+
+        let mut manager = PluginManager::new(trusted.path().to_path_buf());
+        assert!(matches!(
+            manager.load_plugin(&library),
+            Err(PluginError::Untrusted(_))
+        ));
+
+        // Symlinking into the trusted dir doesn't help
+        #[cfg(unix)]
+        {
+            let link = trusted.path().join("evil.so");
+            std::os::unix::fs::symlink(&library, &link).unwrap();
+            assert!(matches!(
+                manager.load_plugin(&link),
+                Err(PluginError::Untrusted(_))
+            ));
+        }
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn test_rejects_world_writable_plugin() {
+        use std::os::unix::fs::PermissionsExt;
+
+        let dir = tempdir().unwrap();
+        let library = dir.path().join("plugin.so");
+        std::fs::write(&library, b"").unwrap();
+        std::fs::set_permissions(&library, std::fs::Permissions::from_mode(0o666)).unwrap();
+
+        let mut manager = PluginManager::new(dir.path().to_path_buf());
+        assert!(matches!(
+            manager.load_plugin(&library),
+            Err(PluginError::Untrusted(_))
+        ));
+    }
+
+    #[test]
+    fn test_scoped_source() {
+        let dir = tempdir().unwrap();
+        let extensions = vec!["xyz".to_string()];
+
+        let file = dir.path().join("model.XYZ");
+        std::fs::write(&file, b"data").unwrap();
+        assert_eq!(
+            scoped_source(&file, &extensions).unwrap(),
+            file.canonicalize().unwrap()
+        );
+
+        let other = dir.path().join("notes.txt");
+        std::fs::write(&other, b"data").unwrap();
+        assert!(scoped_source(&other, &extensions).is_err());
+        assert!(scoped_source(dir.path(), &extensions).is_err());
+        assert!(scoped_source(Path::new("/proc/self/environ"), &extensions).is_err());
+    }
+
+    #[test]
+    fn test_scoped_dest() {
+        let dir = tempdir().unwrap();
+        let source = dir.path().join("model.xyz");
+        std::fs::write(&source, b"data").unwrap();
+        let source = source.canonicalize().unwrap();
+
+        let dest = dir.path().join("thumb.png");
+        assert!(scoped_dest(&dest, &source).is_ok());
+        assert!(scoped_dest(&source, &source).is_err());
+        assert!(scoped_dest(Path::new("/etc/passwd"), &source).is_err());
+        assert!(scoped_dest(&dir.path().join("missing/thumb.png"), &source).is_err());
+    }
 }
@@ -0,0 +1,317 @@
+//! Subtitles and edit-exchange files that travel alongside video.
+//!
+//! Subtitle formats are plain text without magic bytes, so they are
+//! recognised by their cue timing: SubRip's numbered `00:00:01,000 -->`
+//! lines, the `WEBVTT` header, or SSA/ASS's `[Script Info]` section. Edit
+//! exchange covers CMX 3600 EDLs, found by their event lines, Final Cut XML
+//! by its root element, OpenTimelineIO by its schema key, and AAF. AAF is a
+//! compound file like old Office documents, so the container is only called
+//! AAF under its own extension. Generic `.xml` is left alone unless the
+//! content is Final Cut XML.
+
+use crate::file_detector::{FileCategory, FileInfo};
+use crate::svg;
+
+/// How much of a file is looked at for a format sniff
+const SNIFF_LEN: usize = 8192;
+
+/// Microsoft compound file header, which AAF is stored in
+const COMPOUND_FILE: &[u8] = &[0xD0, 0xCF, 0x11, 0xE0, 0xA1, 0xB1, 0x1A, 0xE1];
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct SidecarFormat {
+    /// Extensions the format is known by, lowercase; empty when it has none
+    /// of its own
+    pub extensions: &'static [&'static str],
+    pub mime_type: &'static str,
+    pub category: FileCategory,
+    /// Format name, reported as the file's `codec`
+    pub codec: &'static str,
+}
+
+pub const SUBRIP: SidecarFormat = SidecarFormat {
+    extensions: &["srt"],
+    mime_type: "application/x-subrip",
+    category: FileCategory::Subtitle,
+    codec: "SubRip",
+};
+
+pub const WEBVTT: SidecarFormat = SidecarFormat {
+    extensions: &["vtt"],
+    mime_type: "text/vtt",
+    category: FileCategory::Subtitle,
+    codec: "WebVTT",
+};
+
+pub const ASS: SidecarFormat = SidecarFormat {
+    extensions: &["ass"],
+    mime_type: "text/x-ass",
+    category: FileCategory::Subtitle,
+    codec: "ASS",
+};
+
+pub const SSA: SidecarFormat = SidecarFormat {
+    extensions: &["ssa"],
+    mime_type: "text/x-ssa",
+    category: FileCategory::Subtitle,
+    codec: "SSA",
+};
+
+pub const EDL: SidecarFormat = SidecarFormat {
+    extensions: &["edl"],
+    mime_type: "text/x-edl",
+    category: FileCategory::EditExchange,
+    codec: "CMX 3600 EDL",
+};
+
+pub const FINAL_CUT_XML: SidecarFormat = SidecarFormat {
+    extensions: &[],
+    mime_type: "application/x-final-cut-xml",
+    category: FileCategory::EditExchange,
+    codec: "Final Cut Pro XML",
+};
+
+pub const FCPXML: SidecarFormat = SidecarFormat {
+    extensions: &["fcpxml"],
+    mime_type: "application/x-fcpxml",
+    category: FileCategory::EditExchange,
+    codec: "FCPXML",
+};
+
+pub const OTIO: SidecarFormat = SidecarFormat {
+    extensions: &["otio"],
+    mime_type: "application/x-otio+json",
+    category: FileCategory::EditExchange,
+    codec: "OpenTimelineIO",
+};
+
+pub const AAF: SidecarFormat = SidecarFormat {
+    extensions: &["aaf"],
+    mime_type: "application/x-aaf",
+    category: FileCategory::EditExchange,
+    codec: "AAF",
+};
+
+pub const SIDECAR_FORMATS: &[SidecarFormat] = &[
+    SUBRIP,
+    WEBVTT,
+    ASS,
+    SSA,
+    EDL,
+    FINAL_CUT_XML,
+    FCPXML,
+    OTIO,
+    AAF,
+];
+
+impl SidecarFormat {
+    pub fn for_extension(ext: &str) -> Option<&'static SidecarFormat> {
+        SIDECAR_FORMATS
+            .iter()
+            .find(|f| f.extensions.iter().any(|e| e.eq_ignore_ascii_case(ext)))
+    }
+
+    pub(crate) fn file_info(&self, extension: Option<&str>) -> FileInfo {
+        FileInfo {
+            mime_type: self.mime_type.to_string(),
+            category: self.category,
+            extension: extension.map(String::from),
+            codec: Some(self.codec.to_string()),
+            animated: None,
+            orientation: None,
+            dimensions: None,
+            extension_mime: None,
+            extension_mismatch: false,
+        }
+    }
+}
+
+/// Sidecar format of `data` from its content, or for AAF from the extension
+/// plus the compound file header
+pub fn detect(data: &[u8], extension: Option<&str>) -> Option<&'static SidecarFormat> {
+    if data.starts_with(COMPOUND_FILE) {
+        return extension
+            .filter(|ext| ext.eq_ignore_ascii_case("aaf"))
+            .map(|_| &AAF);
+    }
+
+    let text = text_start(data)?;
+    if is_webvtt(text) {
+        Some(&WEBVTT)
+    } else if let Some(format) = ssa_version(text) {
+        Some(format)
+    } else if is_subrip(text) {
+        Some(&SUBRIP)
+    } else if is_edl(text) {
+        Some(&EDL)
+    } else if let Some(format) = final_cut_xml(text) {
+        Some(format)
+    } else if is_otio(text) {
+        Some(&OTIO)
+    } else {
+        None
+    }
+}
+
+/// The first [`SNIFF_LEN`] bytes as text, without a byte order mark; `None`
+/// for binary data
+fn text_start(data: &[u8]) -> Option<&str> {
+    let head = &data[..data.len().min(SNIFF_LEN)];
+    let text = match std::str::from_utf8(head) {
+        Ok(text) => text,
+        // Cut mid-character at the sniff limit
+        Err(e) if e.error_len().is_none() => std::str::from_utf8(&head[..e.valid_up_to()]).ok()?,
+        Err(_) => return None,
+    };
+    Some(text.trim_start_matches('\u{feff}'))
+}
+
+fn is_webvtt(text: &str) -> bool {
+    text.strip_prefix("WEBVTT")
+        .is_some_and(|rest| rest.is_empty() || rest.starts_with([' ', '\t', '\r', '\n']))
+}
+
+/// ASS for `ScriptType: v4.00+`, SSA for any other script
+fn ssa_version(text: &str) -> Option<&'static SidecarFormat> {
+    if !text.trim_start().starts_with("[Script Info]") {
+        return None;
+    }
+    let script_type = text.lines().find_map(|line| {
+        let (key, value) = line.split_once(':')?;
+        key.trim()
+            .eq_ignore_ascii_case("ScriptType")
+            .then(|| value.trim())
+    });
+    match script_type {
+        Some(version) if version.eq_ignore_ascii_case("v4.00+") => Some(&ASS),
+        _ => Some(&SSA),
+    }
+}
+
+/// A cue number followed by a `00:00:01,000 --> 00:00:04,000` timing line
+fn is_subrip(text: &str) -> bool {
+    let mut lines = text.lines().map(str::trim).skip_while(|l| l.is_empty());
+    let (Some(number), Some(timing)) = (lines.next(), lines.next()) else {
+        return false;
+    };
+    if number.is_empty() || !number.bytes().all(|b| b.is_ascii_digit()) {
+        return false;
+    }
+
+    let Some((start, end)) = timing.split_once("-->") else {
+        return false;
+    };
+    // Players accept position coordinates after the end time
+    let end = end.split_whitespace().next().unwrap_or("");
+    is_timestamp(start.trim()) && is_timestamp(end)
+}
+
+/// `HH:MM:SS,mmm`
+fn is_timestamp(value: &str) -> bool {
+    let Some((clock, millis)) = value.split_once(',') else {
+        return false;
+    };
+    millis.len() == 3 && millis.bytes().all(|b| b.is_ascii_digit()) && is_clock(clock, 3)
+}
+
+/// `parts` groups of two digits joined by colons
+fn is_clock(value: &str, parts: usize) -> bool {
+    let groups: Vec<&str> = value.split(':').collect();
+    groups.len() == parts
+        && groups
+            .iter()
+            .all(|g| g.len() == 2 && g.bytes().all(|b| b.is_ascii_digit()))
+}
+
+/// An event line: number, reel, track, transition, then source and record
+/// in and out as SMPTE timecodes
+fn is_edl(text: &str) -> bool {
+    // Drop-frame timecodes use `;` before the frames
+    let is_timecode = |t: &str| is_clock(&t.replace(';', ":"), 4);
+
+    text.lines().take(50).any(|line| {
+        let fields: Vec<&str> = line.split_whitespace().collect();
+        fields.len() >= 8
+            && fields[0].len() >= 3
+            && fields[0].bytes().all(|b| b.is_ascii_digit())
+            && fields[fields.len() - 4..].iter().all(|t| is_timecode(t))
+    })
+}
+
+/// Final Cut Pro 7's `<xmeml>` or Final Cut Pro X's `<fcpxml>` root
+fn final_cut_xml(text: &str) -> Option<&'static SidecarFormat> {
+    let root = svg::root_element(text)?;
+    let name_end = root
+        .find(|c: char| c.is_whitespace() || c == '>' || c == '/')
+        .unwrap_or(root.len());
+    match &root[..name_end] {
+        "xmeml" => Some(&FINAL_CUT_XML),
+        "fcpxml" => Some(&FCPXML),
+        _ => None,
+    }
+}
+
+fn is_otio(text: &str) -> bool {
+    text.trim_start().starts_with('{') && text.contains("\"OTIO_SCHEMA\"")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_subtitle_sniffing() {
+        let srt =
+            "1\n00:00:01,000 --> 00:00:04,200\nHello\n\n2\n00:00:05,000 --> 00:00:06,000\nBye\n";
+        assert_eq!(detect(srt.as_bytes(), None), Some(&SUBRIP));
+
+        let bom_srt = "\u{feff}1\r\n00:00:01,000 --> 00:00:04,200 X1:40 X2:600\r\nHi\r\n";
+        assert_eq!(detect(bom_srt.as_bytes(), Some("txt")), Some(&SUBRIP));
+
+        let vtt = "WEBVTT - chapters\n\n00:01.000 --> 00:04.000\nHello\n";
+        assert_eq!(detect(vtt.as_bytes(), None), Some(&WEBVTT));
+
+        let ass = "[Script Info]\nTitle: Demo\nScriptType: v4.00+\n\n[V4+ Styles]\n";
+        assert_eq!(detect(ass.as_bytes(), Some("ass")), Some(&ASS));
+        let ssa = "[Script Info]\nScriptType: v4.00\n";
+        assert_eq!(detect(ssa.as_bytes(), None), Some(&SSA));
+
+        // Numbered lines without cue timing are just text
+        assert_eq!(detect(b"1\nfirst\n2\nsecond\n", None), None);
+        // SubRip puts a comma before the milliseconds
+        assert_eq!(detect(b"1\n00:00:01.000 --> 00:00:02.000\n", None), None);
+    }
+
+    #[test]
+    fn test_edit_exchange_sniffing() {
+        let edl = "TITLE: Reel Cut\nFCM: NON-DROP FRAME\n\n\
+                   001  AX       V     C        01:00:00:00 01:00:05:00 00:00:00:00 00:00:05:00\n";
+        assert_eq!(detect(edl.as_bytes(), None), Some(&EDL));
+
+        let drop_frame = "001  TAPE1 AA/V  C  00:59:59;28 01:00:10;02 01:00:00;00 01:00:10;04\n";
+        assert_eq!(detect(drop_frame.as_bytes(), Some("edl")), Some(&EDL));
+
+        let xmeml =
+            "<?xml version=\"1.0\"?>\n<!DOCTYPE xmeml>\n<xmeml version=\"5\"><sequence/></xmeml>";
+        assert_eq!(detect(xmeml.as_bytes(), Some("xml")), Some(&FINAL_CUT_XML));
+        let fcpxml = "<?xml version=\"1.0\"?>\n<fcpxml version=\"1.10\"/>";
+        assert_eq!(detect(fcpxml.as_bytes(), Some("fcpxml")), Some(&FCPXML));
+        assert_eq!(detect(b"<?xml version=\"1.0\"?><feed/>", Some("xml")), None);
+
+        let otio = "{\n    \"OTIO_SCHEMA\": \"Timeline.1\",\n    \"name\": \"Cut\"\n}";
+        assert_eq!(detect(otio.as_bytes(), None), Some(&OTIO));
+
+        // A compound file is only AAF by name; .doc and .msi share the header
+        let mut compound = COMPOUND_FILE.to_vec();
+        compound.extend_from_slice(&[0; 24]);
+        assert_eq!(detect(&compound, Some("AAF")), Some(&AAF));
+        assert_eq!(detect(&compound, Some("doc")), None);
+    }
+
+    #[test]
+    fn test_extensions() {
+        assert_eq!(SidecarFormat::for_extension("SRT"), Some(&SUBRIP));
+        assert_eq!(SidecarFormat::for_extension("otio"), Some(&OTIO));
+        assert_eq!(SidecarFormat::for_extension("xml"), None);
+    }
+}
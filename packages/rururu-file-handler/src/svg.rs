@@ -0,0 +1,227 @@
+//! SVG documents: recognising them and reading their declared size.
+//!
+//! An SVG is plain XML, so there are no magic bytes; it is one when the
+//! first element, past any XML declaration, comments, doctype and
+//! processing instructions, is `<svg>`. Its size comes from the root's
+//! `width` and `height`, with the `viewBox` filling in whatever they leave
+//! out or give as percentages, the way browsers size an SVG `<img>`.
+
+/// CSS pixels per unit for the absolute lengths SVG allows
+const UNITS: &[(&str, f64)] = &[
+    ("px", 1.0),
+    ("pt", 96.0 / 72.0),
+    ("pc", 16.0),
+    ("mm", 96.0 / 25.4),
+    ("cm", 96.0 / 2.54),
+    ("in", 96.0),
+];
+
+/// SMIL animation elements
+const SMIL_ELEMENTS: &[&str] = &["animate", "animateTransform", "animateMotion", "set"];
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct SvgInfo {
+    /// Declared width in CSS pixels; `None` for percentages and font-relative units
+    pub width: Option<f64>,
+    pub height: Option<f64>,
+    /// `min-x`, `min-y`, `width`, `height`
+    pub view_box: Option<[f64; 4]>,
+    /// Contains SMIL animation elements or CSS `@keyframes`
+    pub animated: bool,
+}
+
+impl SvgInfo {
+    /// Width and height to render at, rounded to whole pixels
+    pub fn dimensions(&self) -> Option<(u32, u32)> {
+        let aspect = self
+            .view_box
+            .filter(|vb| vb[2] > 0.0 && vb[3] > 0.0)
+            .map(|vb| vb[2] / vb[3]);
+
+        let (width, height) = match (self.width, self.height, aspect) {
+            (Some(w), Some(h), _) => (w, h),
+            (Some(w), None, Some(aspect)) => (w, w / aspect),
+            (None, Some(h), Some(aspect)) => (h * aspect, h),
+            (None, None, Some(_)) => {
+                let vb = self.view_box?;
+                (vb[2], vb[3])
+            }
+            _ => return None,
+        };
+
+        let round = |v: f64| (v.round() >= 1.0).then(|| v.round() as u32);
+        Some((round(width)?, round(height)?))
+    }
+}
+
+/// Parse `data` as an SVG, `None` when its root element is anything else
+pub fn parse(data: &[u8]) -> Option<SvgInfo> {
+    let text = std::str::from_utf8(data).ok()?;
+    let root = root_element(text)?;
+
+    let name_end = root
+        .find(|c: char| c.is_whitespace() || c == '>' || c == '/')
+        .unwrap_or(root.len());
+    let name = &root[..name_end];
+    // Namespace-prefixed documents from some exporters use `<svg:svg>`
+    if name != "svg" && !name.ends_with(":svg") {
+        return None;
+    }
+    let prefix = &name[..name.len() - 3];
+
+    let tag_end = root.find('>')?;
+    let attributes = &root[name_end..tag_end];
+
+    Some(SvgInfo {
+        width: attribute(attributes, "width").and_then(parse_length),
+        height: attribute(attributes, "height").and_then(parse_length),
+        view_box: attribute(attributes, "viewBox").and_then(parse_view_box),
+        animated: is_animated(root, prefix),
+    })
+}
+
+/// The text from the first element's name on, skipping the prolog
+pub(crate) fn root_element(text: &str) -> Option<&str> {
+    let mut rest = text.trim_start_matches('\u{feff}');
+    loop {
+        rest = rest.trim_start();
+        if let Some(comment) = rest.strip_prefix("<!--") {
+            rest = &comment[comment.find("-->")? + 3..];
+        } else if rest.starts_with("<?") {
+            rest = &rest[rest.find("?>")? + 2..];
+        } else if rest.starts_with("<!DOCTYPE") {
+            // An internal subset can hold `>` of its own
+            let end = match (rest.find('['), rest.find('>')) {
+                (Some(open), Some(close)) if open < close => rest.find("]>")? + 2,
+                (_, Some(close)) => close + 1,
+                _ => return None,
+            };
+            rest = &rest[end..];
+        } else {
+            return rest.strip_prefix('<');
+        }
+    }
+}
+
+/// Value of `name` in a tag's attribute list
+fn attribute<'a>(attributes: &'a str, name: &str) -> Option<&'a str> {
+    let mut rest = attributes;
+    loop {
+        let at = rest.find(name)?;
+        let before = rest[..at].chars().next_back();
+        let after = rest[at + name.len()..].trim_start();
+        rest = &rest[at + name.len()..];
+
+        // Whole attribute names only, so `width` doesn't match `stroke-width`
+        if !before.is_some_and(char::is_whitespace) {
+            continue;
+        }
+        let Some(value) = after.strip_prefix('=') else {
+            continue;
+        };
+        let value = value.trim_start();
+        let quote = value.chars().next()?;
+        if quote != '"' && quote != '\'' {
+            return None;
+        }
+        let value = &value[1..];
+        return Some(&value[..value.find(quote)?]);
+    }
+}
+
+/// A length in CSS pixels, `None` for relative units
+fn parse_length(value: &str) -> Option<f64> {
+    let value = value.trim();
+    let (number, factor) = UNITS
+        .iter()
+        .find_map(|(unit, factor)| value.strip_suffix(unit).map(|n| (n, *factor)))
+        .unwrap_or((value, 1.0));
+    let length: f64 = number.trim().parse().ok()?;
+    (length.is_finite() && length > 0.0).then_some(length * factor)
+}
+
+fn parse_view_box(value: &str) -> Option<[f64; 4]> {
+    let numbers: Vec<f64> = value
+        .split(|c: char| c.is_whitespace() || c == ',')
+        .filter(|n| !n.is_empty())
+        .map(|n| n.parse().ok())
+        .collect::<Option<_>>()?;
+    numbers.try_into().ok()
+}
+
+fn is_animated(document: &str, prefix: &str) -> bool {
+    let smil = SMIL_ELEMENTS.iter().any(|element| {
+        let tag = format!("<{}{}", prefix, element);
+        document.match_indices(&tag).any(|(at, _)| {
+            // `<set` must not match `<setting>`
+            document[at + tag.len()..]
+                .chars()
+                .next()
+                .is_some_and(|c| c.is_whitespace() || c == '/' || c == '>')
+        })
+    });
+    smil || document.contains("@keyframes")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_root_detection() {
+        let svg = br#"<?xml version="1.0"?>
+<!-- Generator: Inkscape -->
+<!DOCTYPE svg PUBLIC "-//W3C//DTD SVG 1.1//EN" "http://www.w3.org/Graphics/SVG/1.1/DTD/svg11.dtd">
+<svg xmlns="http://www.w3.org/2000/svg" width="64" height="32"></svg>"#;
+        let info = parse(svg).unwrap();
+        assert_eq!(info.dimensions(), Some((64, 32)));
+        assert!(!info.animated);
+
+        let entity = b"<!DOCTYPE svg [<!ENTITY a \"b\">]><svg viewBox=\"0 0 10 10\"/>";
+        assert!(parse(entity).is_some());
+        assert!(parse(b"\xef\xbb\xbf<svg:svg xmlns:svg=\"x\"/>").is_some());
+
+        assert!(parse(b"<?xml version=\"1.0\"?><html><svg/></html>").is_none());
+        assert!(parse(b"<svgfoo/>").is_none());
+        assert!(parse(b"plain words about <svg>").is_none());
+    }
+
+    #[test]
+    fn test_dimensions() {
+        let size = |svg: &str| parse(svg.as_bytes()).unwrap().dimensions();
+
+        assert_eq!(size(r#"<svg width="10mm" height="1in">"#), Some((38, 96)));
+        // Percentages and missing sizes fall back to the viewBox
+        assert_eq!(
+            size(r#"<svg width="100%" height="100%" viewBox="0,0,300,150">"#),
+            Some((300, 150))
+        );
+        assert_eq!(
+            size(r#"<svg width="600" viewBox="0 0 300 150">"#),
+            Some((600, 300))
+        );
+        assert_eq!(
+            size(r#"<svg stroke-width="2" viewBox="0 0 24 24">"#),
+            Some((24, 24))
+        );
+        assert_eq!(size("<svg>"), None);
+
+        let info = parse(br#"<svg viewBox='-5 -5 10 20'>"#).unwrap();
+        assert_eq!(info.view_box, Some([-5.0, -5.0, 10.0, 20.0]));
+    }
+
+    #[test]
+    fn test_animation() {
+        let animated = |svg: &str| parse(svg.as_bytes()).unwrap().animated;
+
+        assert!(animated(
+            r#"<svg><circle><animate attributeName="r"/></circle></svg>"#
+        ));
+        assert!(animated(r#"<svg><rect><set to="red"/></rect></svg>"#));
+        assert!(animated(
+            "<svg><style>@keyframes spin { to { transform: rotate(1turn) } }</style></svg>"
+        ));
+        assert!(animated(r#"<s:svg xmlns:s="x"><s:animateMotion/></s:svg>"#));
+        assert!(!animated("<svg><setting/><g/></svg>"));
+    }
+}
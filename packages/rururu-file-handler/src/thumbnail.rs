@@ -1,4 +1,8 @@
+#[cfg(feature = "image-processing")]
+use crate::orientation::{read_orientation, Orientation};
 use std::path::{Path, PathBuf};
+#[cfg(feature = "image-processing")]
+use std::sync::{Arc, OnceLock};
 use thiserror::Error;
 use tracing::{debug, warn};
 
@@ -65,6 +69,7 @@ impl ThumbnailGenerator {
             "jpg" | "jpeg" | "png" | "gif" | "webp" | "bmp" | "tiff" | "tif" => {
                 self.generate_image_thumbnail(source, &cache_path, size)
             }
+            "svg" | "svgz" => self.generate_svg_thumbnail(source, &cache_path, size),
             // RAW photos
             "cr2" | "cr3" | "nef" | "arw" | "dng" | "orf" | "rw2" | "raf" => {
                 self.generate_raw_thumbnail(source, &cache_path, size)
@@ -98,7 +103,8 @@ impl ThumbnailGenerator {
     ) -> Result<(), ThumbnailError> {
         let img = image::open(source).map_err(|e| ThumbnailError::ImageError(e.to_string()))?;
 
-        let thumbnail = img.thumbnail(size.width, size.height);
+        let orientation = read_orientation(source).unwrap_or_default();
+        let thumbnail = upright_thumbnail(img, orientation, size);
 
         thumbnail
             .save(dest)
@@ -120,6 +126,30 @@ impl ThumbnailGenerator {
         ))
     }
 
+    #[cfg(feature = "image-processing")]
+    fn generate_svg_thumbnail(
+        &self,
+        source: &Path,
+        dest: &Path,
+        size: ThumbnailSize,
+    ) -> Result<(), ThumbnailError> {
+        std::fs::write(dest, render_svg(source, size)?)?;
+        debug!("Generated SVG thumbnail: {:?}", dest);
+        Ok(())
+    }
+
+    #[cfg(not(feature = "image-processing"))]
+    fn generate_svg_thumbnail(
+        &self,
+        _source: &Path,
+        _dest: &Path,
+        _size: ThumbnailSize,
+    ) -> Result<(), ThumbnailError> {
+        Err(ThumbnailError::GenerationError(
+            "Image processing not enabled".into(),
+        ))
+    }
+
     fn generate_raw_thumbnail(
         &self,
         source: &Path,
@@ -139,7 +169,8 @@ impl ThumbnailGenerator {
             {
                 let img = image::load_from_memory(thumb)
                     .map_err(|e| ThumbnailError::ImageError(e.to_string()))?;
-                let thumbnail = img.thumbnail(size.width, size.height);
+                let orientation = read_orientation(source).unwrap_or_default();
+                let thumbnail = upright_thumbnail(img, orientation, size);
                 thumbnail
                     .save(dest)
                     .map_err(|e| ThumbnailError::ImageError(e.to_string()))?;
@@ -160,91 +191,28 @@ impl ThumbnailGenerator {
         dest: &Path,
         size: ThumbnailSize,
     ) -> Result<(), ThumbnailError> {
-        use ffmpeg_next::format::{input, Pixel};
-        use ffmpeg_next::media::Type;
-        use ffmpeg_next::software::scaling::{context::Context as ScalingContext, flag::Flags};
-        use ffmpeg_next::util::frame::video::Video;
+        use crate::media::FrameGrabber;
 
-        let mut ictx =
-            input(&source).map_err(|e| ThumbnailError::GenerationError(e.to_string()))?;
-
-        let input = ictx
-            .streams()
-            .best(Type::Video)
-            .ok_or_else(|| ThumbnailError::GenerationError("No video stream".into()))?;
-
-        let video_stream_index = input.index();
-
-        let context_decoder =
-            ffmpeg_next::codec::context::Context::from_parameters(input.parameters())
-                .map_err(|e| ThumbnailError::GenerationError(e.to_string()))?;
+        let mut grabber = FrameGrabber::open(source, size.width, size.height)
+            .map_err(|e| ThumbnailError::GenerationError(e.to_string()))?;
 
-        let mut decoder = context_decoder
-            .decoder()
-            .video()
+        // Seek to 10% of duration and skip the first few frames
+        #[cfg_attr(not(feature = "image-processing"), allow(unused_variables))]
+        let rgb = grabber
+            .frame_at(grabber.duration() / 10, 5)
             .map_err(|e| ThumbnailError::GenerationError(e.to_string()))?;
 
-        // Seek to 10% of duration for thumbnail
-        let duration = ictx.duration();
-        if duration > 0 {
-            let seek_pos = duration / 10;
-            ictx.seek(seek_pos, ..)
-                .map_err(|e| ThumbnailError::GenerationError(e.to_string()))?;
-        }
+        #[cfg(feature = "image-processing")]
+        {
+            let img = image::RgbImage::from_raw(size.width, size.height, rgb)
+                .ok_or_else(|| ThumbnailError::GenerationError("Failed to create image".into()))?;
 
-        let mut scaler = ScalingContext::get(
-            decoder.format(),
-            decoder.width(),
-            decoder.height(),
-            Pixel::RGB24,
-            size.width,
-            size.height,
-            Flags::BILINEAR,
-        )
-        .map_err(|e| ThumbnailError::GenerationError(e.to_string()))?;
-
-        let mut frame_count = 0;
-        for (stream, packet) in ictx.packets() {
-            if stream.index() == video_stream_index {
-                decoder
-                    .send_packet(&packet)
-                    .map_err(|e| ThumbnailError::GenerationError(e.to_string()))?;
-
-                let mut decoded = Video::empty();
-                while decoder.receive_frame(&mut decoded).is_ok() {
-                    frame_count += 1;
-                    if frame_count >= 5 {
-                        // Skip first few frames
-                        let mut rgb_frame = Video::empty();
-                        scaler
-                            .run(&decoded, &mut rgb_frame)
-                            .map_err(|e| ThumbnailError::GenerationError(e.to_string()))?;
-
-                        #[cfg(feature = "image-processing")]
-                        {
-                            let img = image::RgbImage::from_raw(
-                                rgb_frame.width(),
-                                rgb_frame.height(),
-                                rgb_frame.data(0).to_vec(),
-                            )
-                            .ok_or_else(|| {
-                                ThumbnailError::GenerationError("Failed to create image".into())
-                            })?;
-
-                            img.save(dest)
-                                .map_err(|e| ThumbnailError::ImageError(e.to_string()))?;
-                        }
-
-                        debug!("Generated video thumbnail: {:?}", dest);
-                        return Ok(());
-                    }
-                }
-            }
+            img.save(dest)
+                .map_err(|e| ThumbnailError::ImageError(e.to_string()))?;
         }
 
-        Err(ThumbnailError::GenerationError(
-            "Failed to extract frame".into(),
-        ))
+        debug!("Generated video thumbnail: {:?}", dest);
+        Ok(())
     }
 
     #[cfg(not(feature = "ffmpeg"))]
@@ -321,6 +289,64 @@ impl ThumbnailGenerator {
     }
 }
 
+/// `img` shrunk to fit `size` once turned upright, so portrait photos get
+/// portrait thumbnails. Shrinking comes first, so only the small image is
+/// rotated.
+#[cfg(feature = "image-processing")]
+fn upright_thumbnail(
+    img: image::DynamicImage,
+    orientation: Orientation,
+    size: ThumbnailSize,
+) -> image::DynamicImage {
+    let (width, height) = if orientation.swaps_dimensions() {
+        (size.height, size.width)
+    } else {
+        (size.width, size.height)
+    };
+    orientation.apply(img.thumbnail(width, height))
+}
+
+/// Render an SVG or SVGZ to a PNG fitting `size`, text included. Small
+/// icons are scaled up as well as big artwork down.
+#[cfg(feature = "image-processing")]
+pub fn render_svg(source: &Path, size: ThumbnailSize) -> Result<Vec<u8>, ThumbnailError> {
+    use resvg::tiny_skia;
+    use resvg::usvg::{self, TreeParsing, TreeTextToPath};
+
+    let data = std::fs::read(source)?;
+    let mut tree = usvg::Tree::from_data(&data, &usvg::Options::default())
+        .map_err(|e| ThumbnailError::ImageError(e.to_string()))?;
+    tree.convert_text(&system_fonts());
+
+    let scale =
+        (size.width as f32 / tree.size.width()).min(size.height as f32 / tree.size.height());
+    let width = ((tree.size.width() * scale).round() as u32).max(1);
+    let height = ((tree.size.height() * scale).round() as u32).max(1);
+    let mut pixmap = tiny_skia::Pixmap::new(width, height)
+        .ok_or_else(|| ThumbnailError::GenerationError("Invalid SVG size".into()))?;
+
+    resvg::Tree::from_usvg(&tree).render(
+        tiny_skia::Transform::from_scale(scale, scale),
+        &mut pixmap.as_mut(),
+    );
+    pixmap
+        .encode_png()
+        .map_err(|e| ThumbnailError::ImageError(e.to_string()))
+}
+
+/// The system fonts, scanned on first use and kept for later SVGs
+#[cfg(feature = "image-processing")]
+fn system_fonts() -> Arc<resvg::usvg::fontdb::Database> {
+    static FONTS: OnceLock<Arc<resvg::usvg::fontdb::Database>> = OnceLock::new();
+    FONTS
+        .get_or_init(|| {
+            let mut fonts = resvg::usvg::fontdb::Database::new();
+            fonts.load_system_fonts();
+            Arc::new(fonts)
+        })
+        .clone()
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
@@ -336,4 +362,47 @@ mod tests {
 
         assert_ne!(key1, key2);
     }
+
+    #[test]
+    #[cfg(feature = "image-processing")]
+    fn test_svg_thumbnail() {
+        let dir = tempdir().unwrap();
+        let gen = ThumbnailGenerator::new(dir.path().join("cache"));
+        let source = dir.path().join("wide.svg");
+        std::fs::write(
+            &source,
+            r#"<svg xmlns="http://www.w3.org/2000/svg" width="40" height="20"><rect width="40" height="20" fill="red"/></svg>"#,
+        )
+        .unwrap();
+
+        let thumb = gen.generate(&source, ThumbnailSize::SMALL).unwrap();
+        let thumb = image::open(thumb).unwrap().to_rgba8();
+        assert_eq!(thumb.dimensions(), (128, 64));
+        assert_eq!(thumb.get_pixel(64, 32).0, [255, 0, 0, 255]);
+    }
+
+    #[test]
+    #[cfg(feature = "image-processing")]
+    fn test_thumbnail_is_upright() {
+        use crate::orientation::{tests::tagged_jpeg, Orientation};
+
+        let dir = tempdir().unwrap();
+        let gen = ThumbnailGenerator::new(dir.path().join("cache"));
+
+        for value in 1..=8u16 {
+            let source = dir.path().join(format!("photo-{}.jpg", value));
+            std::fs::write(&source, tagged_jpeg(value)).unwrap();
+
+            let thumb = gen.generate(&source, ThumbnailSize::SMALL).unwrap();
+            let thumb = image::open(thumb).unwrap();
+            let portrait = thumb.height() > thumb.width();
+            let orientation = Orientation::from_exif(value as u32).unwrap();
+            assert_eq!(
+                portrait,
+                orientation.swaps_dimensions(),
+                "orientation {}",
+                value
+            );
+        }
+    }
 }
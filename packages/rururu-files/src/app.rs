@@ -1,11 +1,42 @@
-use crate::file_list::{FileEntry, FileList};
-use crate::preview::Preview;
+use crate::archive::{self, ArchivePath};
+use crate::audio_tags::{self, AudioTagEditor};
+use crate::batch::{self, BatchOperation, BatchOperationType, BatchResult};
+use crate::checksums::{self, ChecksumEvent, ChecksumTask, Checksums, HashAlgorithm};
+use crate::columns::{self, Column, ColumnData, ColumnSettings, MediaDetails};
+use crate::compare::{self, Comparison};
+use crate::file_list::{self, FileEntry, FileList};
+use crate::folder_views::{FolderView, FolderViews};
+use crate::icons::IconResolver;
+use crate::image_view::{self, ImageView, Zoom};
+use crate::keybindings::{self, Action, KeyCombo, KeybindingEditor, Keybindings};
+use crate::listing;
+use crate::open_with;
+use crate::panes::{self, Pane, PaneSide};
+use crate::preview::{self, ImageFilter, Preview, ProofProfile, SoftProofing};
+use crate::quick_look::{self, QuickLook};
+use crate::recents::{self, UsageDatabase, UsageView};
+use crate::remotes::{self, AuthPrompt, MountError, RemoteDraft, RemotePanel};
+use crate::scopes::{self, ScopeKind};
+use crate::selection::Selection;
 use crate::sidebar::Sidebar;
-use crate::toolbar::Toolbar;
-use iced::widget::{column, container, row, scrollable, text};
-use iced::{Application, Command, Element, Length, Theme};
-use std::path::PathBuf;
-use tracing::{debug, info};
+use crate::smart_folders::{self, SmartFolderDraft, SmartFolders};
+use crate::sorting::{SortKey, SortSettings};
+use crate::tags::TagDatabase;
+use crate::toolbar::{self, Toolbar};
+use crate::tools::{self, ToolRun, ToolsConfig, ToolsMenu};
+use crate::trash_view::{self, TrashEntry, TrashPurge, TrashView};
+use iced::keyboard;
+use iced::widget::scrollable::Viewport;
+use iced::widget::{column, container, row, scrollable, text, text_input};
+use iced::{Application, Command, Element, Length, Subscription, Theme};
+use rururu_file_handler::thumbnail::{self, ThumbnailSize};
+use rururu_file_handler::{FileDetector, ProjectFormat};
+use rururu_wrappers::CvdType;
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+use std::ffi::OsString;
+use std::path::{Path, PathBuf};
+use tracing::{debug, info, warn};
 
 #[derive(Debug, Clone)]
 pub enum Message {
@@ -22,16 +53,57 @@ pub enum Message {
     OpenFile(PathBuf),
     DeleteSelected,
     RenameStart,
+    RenameInputChanged(String),
     RenameConfirm(String),
+    RenameCancel,
     CopySelected,
     CutSelected,
     Paste,
     NewFolder,
+    ExtractSelected,
+    Extracted(Vec<PathBuf>),
+    CompareSelected,
+    CompareFinished(Comparison),
+    CloseComparison,
+
+    // Audio tag editor
+    EditAudioTags,
+    AudioTagsLoaded(PathBuf, Result<rururu_file_handler::AudioTags, String>),
+    AudioTagChanged(rururu_file_handler::TagField, String),
+    CoverArtPathChanged(String),
+    SaveAudioTags,
+    AudioTagsSaved(Result<(), String>),
+    CloseAudioTagEditor,
 
     // View
     ToggleHiddenFiles,
     SetViewMode(ViewMode),
     TogglePreview,
+    SetCvdPreview(Option<CvdType>),
+    SetSoftProof(Option<ProofProfile>),
+    SetScope(Option<ScopeKind>),
+    /// Scope analysis finished, for the request numbered as given
+    ScopesAnalyzed(u64, Option<rururu_color::Scopes>),
+    SetGamutWarning(bool),
+    SoftProofingLoaded(SoftProofing),
+    SetSortKey(SortKey),
+    ToggleSortDirection,
+    ToggleDirsFirst,
+    ToggleColumnEditor,
+    ToggleColumn(Column),
+    /// Move a List view column this many places right, or left if negative
+    MoveColumn(Column, i32),
+    ResizeColumn(Column, f32),
+    ListScrolled(Viewport),
+
+    // Dual pane
+    ToggleDualPane,
+    SwitchPane,
+    /// From the pane without focus, which takes focus before handling it
+    OtherPane(Box<Message>),
+    CopyToOtherPane,
+    MoveToOtherPane,
+    PaneTransferFinished(BatchOperationType, Vec<BatchResult>),
 
     // Search
     SearchChanged(String),
@@ -41,10 +113,95 @@ pub enum Message {
     BookmarkClicked(PathBuf),
     AddBookmark,
     RemoveBookmark(PathBuf),
+    ShowUsage(UsageView),
+    UsageLoaded(UsageView, Vec<FileEntry>),
+    ShowSmartFolder(String),
+    SmartFolderLoaded(String, Vec<FileEntry>),
+    /// Open, change or (with `None`) close the smart folder editor
+    EditSmartFolder(Option<SmartFolderDraft>),
+    SaveSmartFolder,
+    DeleteSmartFolder(String),
+    /// Browse a remote location, mounting it first if needed
+    ConnectRemote(String),
+    RemoteMounted(String, Result<PathBuf, MountError>),
+    UnmountRemote(String),
+    RemoteUnmounted(String, Result<(), String>),
+    /// Open, change or (with `None`) close the remote location form
+    EditRemote(Option<RemoteDraft>),
+    SaveRemote,
+    /// Forget a remote location, unmounting it
+    RemoveRemote(String),
+    /// Change or (with `None`) close the login form
+    EditRemoteAuth(Option<AuthPrompt>),
+    SubmitRemoteAuth,
+
+    // Trash
+    ShowTrash,
+    TrashLoaded(Vec<TrashEntry>),
+    TrashItemSelected(OsString),
+    RestoreTrashItem,
+    PurgeTrashItem,
+    EmptyTrash,
+    ConfirmPurge,
+    CancelPurge,
 
     // Preview
     PreviewLoaded(PreviewData),
     PreviewError(String),
+    SetPreviewZoom(Zoom),
+    /// Zoom the image preview about its centre by this factor
+    ZoomPreview(f32),
+    /// Zoom factor and pan from the image viewer
+    PreviewViewChanged(f32, iced::Vector),
+    /// Quarter turns clockwise, or anticlockwise when negative
+    RotatePreview(i32),
+    /// Image turned from the one with this handle id
+    PreviewRotated(u64, ImageView),
+
+    // Quick Look
+    ToggleQuickLook,
+    CloseQuickLook,
+    QuickLookStep(i32),
+    QuickLookLoaded(PathBuf, PreviewData),
+    QuickLookTogglePlay,
+    QuickLookSeek(usize),
+    QuickLookTick,
+
+    // External tools
+    ShowToolsMenu(PathBuf),
+    ToolsMenuLoaded(Option<ToolsMenu>),
+    CloseToolsMenu,
+    RunTool(usize),
+    ToolFinished(Vec<ToolRun>),
+    CloseToolResults,
+
+    // Checksums
+    ShowChecksums,
+    VerifyChecksums(PathBuf),
+    ChecksumAlgorithmChanged(HashAlgorithm),
+    // Tagged with the job it belongs to
+    ChecksumProgress(u64, ChecksumEvent),
+    WriteChecksumSidecars,
+    WriteChecksumSums,
+    ChecksumsWritten(Result<Vec<PathBuf>, String>),
+    CloseChecksums,
+
+    // Path bar
+    EditPath,
+    PathInputChanged(String),
+    PathInputSubmit,
+    CancelPathEdit,
+
+    // Input
+    ModifiersChanged(keyboard::Modifiers),
+    /// A key press no widget took, looked up in the keybindings
+    KeyPressed(keyboard::Key, keyboard::Modifiers),
+    ToggleKeybindings,
+    /// Bind the next key press to this action, or (with `None`) stop waiting
+    CaptureKeybinding(Option<Action>),
+    UnbindKey(Action, KeyCombo),
+    ResetKeybinding(Action),
+    ResetAllKeybindings,
 
     // File system events
     DirectoryChanged,
@@ -52,8 +209,14 @@ pub enum Message {
 
     // Async results
     FilesLoaded(Vec<FileEntry>),
+    // Streamed folder listings, tagged with the load they belong to
+    FilesAppended(u64, Vec<FileEntry>),
+    FilesListed(u64),
+    FileDetailsLoaded(u64, Vec<FileEntry>),
+    DirectoryLoaded(u64),
     MetadataLoaded(PathBuf, serde_json::Value),
     ThumbnailLoaded(PathBuf, Vec<u8>),
+    MediaDetailsLoaded(Vec<(PathBuf, MediaDetails)>),
 
     // Errors
     Error(String),
@@ -74,12 +237,20 @@ pub enum Message {
     BatchDeselectAll,
     BatchSetOperation(crate::batch::BatchOperationType),
     BatchRenamePatternChanged(String),
+    BatchTagChanged(String),
     BatchSelectTargetDir,
     BatchExecute,
     BatchCancel,
+    BatchFinished(Vec<BatchResult>),
 }
 
-#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+/// Largest archive entry unpacked just to preview it
+const ARCHIVE_PREVIEW_LIMIT: u64 = 256 * 1024 * 1024;
+
+/// Files whose dimensions and duration are kept before the cache is reset
+const MEDIA_DETAILS_LIMIT: usize = 10_000;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default, Serialize, Deserialize)]
 pub enum ViewMode {
     #[default]
     List,
@@ -90,31 +261,130 @@ pub enum ViewMode {
 #[derive(Debug, Clone)]
 pub enum PreviewData {
     Image(Vec<u8>),
+    /// Decoded image the side pane zooms, pans and rotates
+    Viewer(ImageView),
     Text(String),
     Metadata(serde_json::Value),
+    /// Decoded video frames, in playback order
+    Frames(Vec<iced::widget::image::Handle>),
     None,
 }
 
+/// How much `load_preview` loads: the side pane stays cheap, Quick Look
+/// also renders PDFs and video frames and reads more text.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum PreviewFidelity {
+    Pane,
+    Full,
+}
+
+/// A folder being streamed in by `listing::subscription`
+pub struct Listing {
+    id: u64,
+    path: PathBuf,
+    /// Whether entries from this load have replaced the previous listing
+    started: bool,
+}
+
 pub struct RururuFiles {
     current_path: PathBuf,
+    /// The archive `current_path` is inside, looked up once per load
+    archive: Option<ArchivePath>,
     history: Vec<PathBuf>,
     history_index: usize,
 
     files: Vec<FileEntry>,
-    selected: Option<PathBuf>,
+    selection: Selection,
+    modifiers: keyboard::Modifiers,
+    batch: BatchOperation,
 
     show_hidden: bool,
     view_mode: ViewMode,
     show_preview: bool,
+    /// Color vision deficiency simulated in the image preview
+    cvd_preview: Option<CvdType>,
+    /// Output profile the image preview is soft-proofed on
+    soft_proofing: SoftProofing,
+    /// Scope shown with image previews and video frames
+    scope: Option<ScopeKind>,
+    /// Analysis of the picture on screen, while a scope is shown
+    scope_data: Option<rururu_color::Scopes>,
+    /// Number of the latest scope analysis, so an earlier one finishing
+    /// late doesn't replace it
+    scope_request: u64,
+    sort_by: SortKey,
+    /// Sort key for folders without one of their own
+    default_sort: SortKey,
+    sort_ascending: bool,
+    dirs_first: bool,
+    folder_views: FolderViews,
+    columns: ColumnSettings,
+    column_editor: bool,
+    /// Dimensions and duration read for the List view, by path
+    media_details: HashMap<PathBuf, MediaDetails>,
+    /// Where the List view is scrolled to, once it has been
+    list_viewport: Option<Viewport>,
 
     search_query: String,
 
     bookmarks: Vec<PathBuf>,
+    usage: UsageDatabase,
+    /// Showing Recent or Frequent rather than `current_path`
+    usage_view: Option<UsageView>,
+    smart_folders: SmartFolders,
+    tags: TagDatabase,
+    /// Smart folder listed rather than `current_path`
+    smart_folder: Option<String>,
+    smart_folder_draft: Option<SmartFolderDraft>,
+    remotes: RemotePanel,
+
+    in_trash: bool,
+    trash_entries: Vec<TrashEntry>,
+    trash_selected: Option<OsString>,
+    /// Permanent deletion shown for confirmation
+    trash_purge: Option<TrashPurge>,
 
     preview_data: PreviewData,
+    quick_look: Option<QuickLook>,
+
+    tools: ToolsConfig,
+    /// Tools offered for the current selection while the menu is open
+    tools_menu: Option<Vec<usize>>,
+    /// Project file and the installed app that opens it, for the menu
+    tools_menu_project: Option<(PathBuf, &'static ProjectFormat)>,
+    /// Selection a menu is being worked out for after a right-click
+    tools_menu_pending: Option<Vec<PathBuf>>,
+    tool_runs: Vec<ToolRun>,
+    tool_running: Option<String>,
+
+    comparison: Option<Comparison>,
+    comparing: bool,
+
+    checksums: Option<Checksums>,
+    checksum_jobs: u64,
+
+    audio_tag_editor: Option<AudioTagEditor>,
 
     clipboard: Option<(Vec<PathBuf>, bool)>, // (paths, is_cut)
+    /// File being renamed and the name typed so far
+    rename: Option<(PathBuf, String)>,
+    /// Path typed into the path bar while it's being edited
+    path_input: Option<String>,
+
+    keybindings: Keybindings,
+    keybinding_editor: Option<KeybindingEditor>,
 
+    icons: IconResolver,
+
+    /// The unfocused pane while in dual-pane mode
+    dual_pane: Option<Pane>,
+    /// Side the focused pane is drawn on
+    focus: PaneSide,
+    /// Outcome of the last copy or move between panes
+    pane_status: Option<String>,
+
+    listing: Option<Listing>,
+    listings_started: u64,
     loading: bool,
     error: Option<String>,
 }
@@ -140,57 +410,124 @@ impl Application for RururuFiles {
         .filter(|p| p.exists())
         .collect();
 
-        let app = Self {
+        let sorting = SortSettings::load();
+
+        let mut app = Self {
             current_path: home.clone(),
+            archive: None,
             history: vec![home.clone()],
             history_index: 0,
             files: Vec::new(),
-            selected: None,
+            selection: Selection::default(),
+            modifiers: keyboard::Modifiers::default(),
+            batch: BatchOperation::default(),
             show_hidden: false,
             view_mode: ViewMode::List,
             show_preview: true,
+            cvd_preview: None,
+            soft_proofing: SoftProofing::default(),
+            scope: None,
+            scope_data: None,
+            scope_request: 0,
+            sort_by: sorting.key,
+            default_sort: sorting.key,
+            sort_ascending: sorting.ascending,
+            dirs_first: sorting.dirs_first,
+            folder_views: FolderViews::load(),
+            columns: ColumnSettings::load(),
+            column_editor: false,
+            media_details: HashMap::new(),
+            list_viewport: None,
             search_query: String::new(),
             bookmarks,
+            usage: UsageDatabase::load(),
+            usage_view: None,
+            smart_folders: SmartFolders::load(),
+            tags: TagDatabase::load(),
+            smart_folder: None,
+            smart_folder_draft: None,
+            remotes: RemotePanel::load(),
+            in_trash: false,
+            trash_entries: Vec::new(),
+            trash_selected: None,
+            trash_purge: None,
             preview_data: PreviewData::None,
+            quick_look: None,
+            tools: ToolsConfig::load(),
+            tools_menu: None,
+            tools_menu_project: None,
+            tools_menu_pending: None,
+            tool_runs: Vec::new(),
+            tool_running: None,
+            comparison: None,
+            comparing: false,
+            checksums: None,
+            checksum_jobs: 0,
+            audio_tag_editor: None,
             clipboard: None,
+            rename: None,
+            path_input: None,
+            keybindings: Keybindings::load(),
+            keybinding_editor: None,
+            icons: IconResolver::new(),
+            dual_pane: None,
+            focus: PaneSide::Left,
+            pane_status: None,
+            listing: None,
+            listings_started: 0,
             loading: true,
             error: None,
         };
+        app.apply_folder_view(&home);
+        let command = Command::batch([
+            app.load_directory(),
+            Command::perform(preview::load_soft_proofing(), Message::SoftProofingLoaded),
+        ]);
 
-        (
-            app,
-            Command::perform(load_directory(home), |result| match result {
-                Ok(files) => Message::FilesLoaded(files),
-                Err(e) => Message::Error(e.to_string()),
-            }),
-        )
+        (app, command)
     }
 
     fn title(&self) -> String {
+        if self.in_trash {
+            return "RururuOS Files - Trash".to_string();
+        }
+        if let Some(view) = self.usage_view {
+            return format!("RururuOS Files - {}", view.title());
+        }
+        if let Some(name) = &self.smart_folder {
+            return format!("RururuOS Files - {}", name);
+        }
         format!("RururuOS Files - {}", self.current_path.display())
     }
 
     fn update(&mut self, message: Message) -> Command<Message> {
-        match message {
-            Message::NavigateTo(path) => {
-                if path.is_dir() {
-                    info!("Navigating to: {:?}", path);
-                    self.current_path = path.clone();
-
-                    // Update history
-                    self.history.truncate(self.history_index + 1);
-                    self.history.push(path.clone());
-                    self.history_index = self.history.len() - 1;
+        // The unfocused pane's listing is applied to it without taking focus
+        if let Some(id) = listing_id(&message) {
+            let other = self.dual_pane.as_ref().and_then(|p| p.listing.as_ref());
+            if other.map(|l| l.id) == Some(id) {
+                return self.in_other_pane(|app| app.update(message));
+            }
+        }
 
-                    self.loading = true;
-                    self.selected = None;
-                    self.preview_data = PreviewData::None;
+        match message {
+            Message::NavigateTo(path) if path.is_dir() || ArchivePath::locate(&path).is_some() => {
+                info!("Navigating to: {:?}", path);
+                self.current_path = path.clone();
+                self.in_trash = false;
+                self.usage_view = None;
+                self.smart_folder = None;
+                self.apply_folder_view(&path);
+
+                // Update history
+                self.history.truncate(self.history_index + 1);
+                self.history.push(path.clone());
+                self.history_index = self.history.len() - 1;
+
+                self.selection.clear();
+                self.batch.deselect_all();
+                self.preview_data = PreviewData::None;
 
-                    return Command::perform(load_directory(path), |result| match result {
-                        Ok(files) => Message::FilesLoaded(files),
-                        Err(e) => Message::Error(e.to_string()),
-                    });
-                }
+                return self.load_directory();
             }
 
             Message::NavigateBack => {
@@ -198,12 +535,12 @@ impl Application for RururuFiles {
                     self.history_index -= 1;
                     let path = self.history[self.history_index].clone();
                     self.current_path = path.clone();
-                    self.loading = true;
+                    self.in_trash = false;
+                    self.usage_view = None;
+                    self.smart_folder = None;
+                    self.apply_folder_view(&path);
 
-                    return Command::perform(load_directory(path), |result| match result {
-                        Ok(files) => Message::FilesLoaded(files),
-                        Err(e) => Message::Error(e.to_string()),
-                    });
+                    return self.load_directory();
                 }
             }
 
@@ -212,12 +549,12 @@ impl Application for RururuFiles {
                     self.history_index += 1;
                     let path = self.history[self.history_index].clone();
                     self.current_path = path.clone();
-                    self.loading = true;
+                    self.in_trash = false;
+                    self.usage_view = None;
+                    self.smart_folder = None;
+                    self.apply_folder_view(&path);
 
-                    return Command::perform(load_directory(path), |result| match result {
-                        Ok(files) => Message::FilesLoaded(files),
-                        Err(e) => Message::Error(e.to_string()),
-                    });
+                    return self.load_directory();
                 }
             }
 
@@ -238,18 +575,87 @@ impl Application for RururuFiles {
 
             Message::FileSelected(path) => {
                 debug!("File selected: {:?}", path);
-                self.selected = Some(path.clone());
+                let files = self.listed_paths();
+                self.selection.click(&files, path, self.modifiers);
+                return self.selection_changed();
+            }
 
-                if self.show_preview {
-                    return Command::perform(load_preview(path), |result| match result {
-                        Ok(data) => Message::PreviewLoaded(data),
-                        Err(e) => Message::PreviewError(e.to_string()),
-                    });
+            Message::BatchToggleSelect(path) => {
+                self.selection.toggle(path);
+                return self.selection_changed();
+            }
+
+            Message::BatchSelectAll if !self.in_trash => {
+                let files = self.listed_paths();
+                self.selection.select_all(&files);
+                return self.selection_changed();
+            }
+
+            Message::BatchDeselectAll => {
+                self.selection.clear();
+                return self.selection_changed();
+            }
+
+            Message::BatchSetOperation(operation) => {
+                if matches!(
+                    operation,
+                    BatchOperationType::Copy | BatchOperationType::Move
+                ) && self.batch.target_directory.is_none()
+                {
+                    self.batch.target_directory = self
+                        .dual_pane
+                        .as_ref()
+                        .map(|other| other.current_path.clone());
+                }
+                self.batch.set_operation(operation);
+                self.batch.results.clear();
+                self.batch.progress = 0.0;
+            }
+
+            Message::BatchRenamePatternChanged(pattern) => {
+                self.batch.rename_pattern = pattern;
+            }
+
+            Message::BatchTagChanged(tag) => {
+                self.batch.tag = tag;
+            }
+
+            Message::BatchCancel => {
+                self.batch.operation = None;
+                self.batch.results.clear();
+            }
+
+            Message::BatchExecute if !self.batch.is_running => {
+                if self.batch.is_tag_operation() {
+                    self.batch.apply_tags(&mut self.tags);
+                    // Smart folders read the saved database
+                    if self.smart_folder.is_some() {
+                        return self.update(Message::RefreshDirectory);
+                    }
+                    return Command::none();
                 }
+
+                self.batch.is_running = true;
+                let mut batch = self.batch.clone();
+                return Command::perform(
+                    async move { batch.execute().await },
+                    Message::BatchFinished,
+                );
+            }
+
+            Message::BatchFinished(results) => {
+                self.batch.results = results;
+                self.batch.is_running = false;
+                self.batch.progress = 1.0;
+                return self.update(Message::RefreshDirectory);
+            }
+
+            Message::ModifiersChanged(modifiers) => {
+                self.modifiers = modifiers;
             }
 
             Message::FileDoubleClicked(path) => {
-                if path.is_dir() {
+                if self.is_folder(&path) {
                     return Command::perform(async move { path }, Message::NavigateTo);
                 } else {
                     return Command::perform(async move { path }, Message::OpenFile);
@@ -258,17 +664,34 @@ impl Application for RururuFiles {
 
             Message::OpenFile(path) => {
                 debug!("Opening file: {:?}", path);
-                if let Err(e) = open::that(&path) {
-                    self.error = Some(format!("Failed to open file: {}", e));
+                self.tools_menu = None;
+                if let Some(location) = archive_entry(&path) {
+                    return Command::perform(
+                        unpack_to_temp(location, None),
+                        |result| match result {
+                            Ok(extracted) => Message::OpenFile(extracted),
+                            Err(e) => Message::Error(format!("Failed to extract file: {}", e)),
+                        },
+                    );
+                }
+                let result = match open_with::project_app(&FileDetector::new(), &path) {
+                    Some(format) => open_with::open_in_app(format, &path),
+                    None => open::that(&path),
+                };
+                match result {
+                    Ok(()) => self.usage.record_open(&path),
+                    Err(e) => self.error = Some(format!("Failed to open file: {}", e)),
                 }
             }
 
             Message::DeleteSelected => {
-                if let Some(ref path) = self.selected {
-                    let path = path.clone();
+                if self.in_archive() {
+                    self.error = Some("Files inside an archive can't be deleted".to_string());
+                } else if !self.selection.is_empty() {
+                    let paths = self.selection.paths().to_vec();
                     return Command::perform(
                         async move {
-                            trash::delete(&path)?;
+                            trash::delete_all(&paths)?;
                             Ok::<_, trash::Error>(())
                         },
                         |result| match result {
@@ -279,180 +702,1971 @@ impl Application for RururuFiles {
                 }
             }
 
+            Message::CopySelected => self.set_clipboard(false),
+
+            Message::CutSelected => self.set_clipboard(true),
+
+            Message::Paste => {
+                return self.paste();
+            }
+
+            Message::NewFolder => {
+                if !self.in_folder_on_disk() {
+                    self.error = Some("New folders go in a folder on disk".to_string());
+                    return Command::none();
+                }
+                let path = (1..)
+                    .map(|n| match n {
+                        1 => self.current_path.join("New Folder"),
+                        n => self.current_path.join(format!("New Folder {}", n)),
+                    })
+                    .find(|path| path.symlink_metadata().is_err())
+                    .unwrap_or_default();
+                if let Err(e) = std::fs::create_dir(&path) {
+                    self.error = Some(format!("Failed to create folder: {}", e));
+                    return Command::none();
+                }
+
+                // Straight into naming it
+                self.selection.select_only(path.clone());
+                let name = file_name(&path);
+                self.rename = Some((path, name));
+                return Command::batch([
+                    self.load_directory(),
+                    text_input::focus(file_list::rename_input_id()),
+                    text_input::select_all(file_list::rename_input_id()),
+                ]);
+            }
+
+            Message::RenameStart => {
+                if self.in_trash || self.in_archive() {
+                    return Command::none();
+                }
+                if let [path] = self.selection.paths() {
+                    self.rename = Some((path.clone(), file_name(path)));
+                    return Command::batch([
+                        text_input::focus(file_list::rename_input_id()),
+                        text_input::select_all(file_list::rename_input_id()),
+                    ]);
+                }
+            }
+
+            Message::RenameInputChanged(name) => {
+                if let Some((_, ref mut typed)) = self.rename {
+                    *typed = name;
+                }
+            }
+
+            Message::RenameCancel => {
+                self.rename = None;
+            }
+
+            Message::RenameConfirm(name) => {
+                let Some((path, _)) = self.rename.take() else {
+                    return Command::none();
+                };
+                let name = name.trim();
+                let target = path.with_file_name(name);
+                let problem =
+                    if name.is_empty() || name == "." || name == ".." || name.contains('/') {
+                        Some(format!("\"{}\" isn't a valid name", name))
+                    } else if target != path && target.symlink_metadata().is_ok() {
+                        Some(format!("{} already exists", name))
+                    } else {
+                        None
+                    };
+                if let Some(problem) = problem {
+                    self.error = Some(problem);
+                    self.rename = Some((path, name.to_string()));
+                    return Command::none();
+                }
+                if target == path {
+                    return Command::none();
+                }
+
+                if let Err(e) = std::fs::rename(&path, &target) {
+                    self.error = Some(format!("Failed to rename: {}", e));
+                    return Command::none();
+                }
+                // Tags are kept by path
+                let tags: Vec<String> = self
+                    .tags
+                    .get_file_tags(&path)
+                    .iter()
+                    .map(|t| t.name.clone())
+                    .collect();
+                for tag in &tags {
+                    self.tags.add_tag_to_file(&target, tag);
+                    self.tags.remove_tag_from_file(&path, tag);
+                }
+                self.selection.select_only(target);
+                return Command::batch([
+                    self.update(Message::RefreshDirectory),
+                    self.selection_changed(),
+                ]);
+            }
+
+            Message::EditPath => {
+                self.path_input = Some(self.current_path.display().to_string());
+                return Command::batch([
+                    text_input::focus(toolbar::path_input_id()),
+                    text_input::select_all(toolbar::path_input_id()),
+                ]);
+            }
+
+            Message::PathInputChanged(path) => {
+                self.path_input = Some(path);
+            }
+
+            Message::PathInputSubmit => {
+                let Some(input) = self.path_input.take() else {
+                    return Command::none();
+                };
+                let input = input.trim();
+                let path = match input.strip_prefix('~') {
+                    Some(rest) => dirs::home_dir()
+                        .unwrap_or_default()
+                        .join(rest.trim_start_matches('/')),
+                    None => PathBuf::from(input),
+                };
+                if path.is_dir() || ArchivePath::locate(&path).is_some() {
+                    return self.update(Message::NavigateTo(path));
+                }
+                self.error = Some(format!("No folder at {}", path.display()));
+            }
+
+            Message::CancelPathEdit => {
+                self.path_input = None;
+            }
+
+            Message::KeyPressed(key, modifiers) => {
+                let Some(combo) = KeyCombo::from_key(&key, modifiers) else {
+                    return Command::none();
+                };
+
+                // The shortcuts dialog is waiting for a key to bind
+                let capturing = self
+                    .keybinding_editor
+                    .as_mut()
+                    .and_then(|e| e.capturing.take());
+                if let Some(action) = capturing {
+                    let status = if combo.to_string() == "Escape" {
+                        None
+                    } else {
+                        let status = match self.keybindings.bind(action, combo.clone()) {
+                            Some(previous) => {
+                                format!("{} moved from {} to {}", combo, previous, action)
+                            }
+                            None => format!("{} bound to {}", combo, action),
+                        };
+                        self.keybindings_changed();
+                        Some(status)
+                    };
+                    if let Some(editor) = self.keybinding_editor.as_mut() {
+                        editor.status = status;
+                    }
+                    return Command::none();
+                }
+
+                if let Some(action) = self.keybindings.action_for(&combo) {
+                    return self.update(action.message());
+                }
+            }
+
+            Message::ToggleKeybindings => {
+                self.keybinding_editor = match self.keybinding_editor {
+                    Some(_) => None,
+                    None => Some(KeybindingEditor::default()),
+                };
+            }
+
+            Message::CaptureKeybinding(action) => {
+                if let Some(ref mut editor) = self.keybinding_editor {
+                    editor.capturing = action;
+                    editor.status = None;
+                }
+            }
+
+            Message::UnbindKey(action, combo) => {
+                self.keybindings.unbind(action, &combo);
+                self.keybindings_changed();
+            }
+
+            Message::ResetKeybinding(action) => {
+                self.keybindings.reset(action);
+                self.keybindings_changed();
+            }
+
+            Message::ResetAllKeybindings => {
+                self.keybindings.reset_all();
+                self.keybindings_changed();
+            }
+
             Message::ToggleHiddenFiles => {
                 self.show_hidden = !self.show_hidden;
-                return Command::perform(load_directory(self.current_path.clone()), |result| {
-                    match result {
-                        Ok(files) => Message::FilesLoaded(files),
-                        Err(e) => Message::Error(e.to_string()),
-                    }
-                });
+                let show_hidden = self.show_hidden;
+                self.remember_folder_view(|view| view.show_hidden = Some(show_hidden));
+                return self.load_directory();
             }
 
             Message::SetViewMode(mode) => {
                 self.view_mode = mode;
+                self.remember_folder_view(|view| view.view_mode = Some(mode));
+                return self.load_visible_details();
             }
 
             Message::TogglePreview => {
                 self.show_preview = !self.show_preview;
             }
 
-            Message::SearchChanged(query) => {
-                self.search_query = query;
+            Message::SetCvdPreview(kind) => {
+                self.cvd_preview = kind;
+                return self.selection_changed();
             }
 
-            Message::BookmarkClicked(path) => {
-                return Command::perform(async move { path }, Message::NavigateTo);
+            Message::SetSoftProof(profile) => {
+                self.soft_proofing.selected = profile;
+                return self.selection_changed();
             }
 
-            Message::FilesLoaded(files) => {
-                let mut files = files;
-                if !self.show_hidden {
-                    files.retain(|f| !f.name.starts_with('.'));
+            Message::SoftProofingLoaded(proofing) => {
+                self.soft_proofing = SoftProofing {
+                    gamut_warning: self.soft_proofing.gamut_warning,
+                    ..proofing
+                };
+                if self.soft_proofing.selected.is_some() {
+                    return self.selection_changed();
                 }
+            }
 
-                // Apply search filter
-                if !self.search_query.is_empty() {
-                    let query = self.search_query.to_lowercase();
-                    files.retain(|f| f.name.to_lowercase().contains(&query));
+            Message::SetGamutWarning(enabled) => {
+                self.soft_proofing.gamut_warning = enabled;
+                if self.soft_proofing.selected.is_some() {
+                    return self.selection_changed();
                 }
+            }
 
-                // Sort: directories first, then by name
-                files.sort_by(|a, b| match (a.is_dir, b.is_dir) {
-                    (true, false) => std::cmp::Ordering::Less,
-                    (false, true) => std::cmp::Ordering::Greater,
-                    _ => a.name.to_lowercase().cmp(&b.name.to_lowercase()),
-                });
+            Message::SetSortKey(key) => {
+                self.sort_by = key;
+                self.remember_folder_view(|view| view.sort_key = Some(key));
+                self.sorting_changed();
+            }
 
-                self.files = files;
-                self.loading = false;
-                self.error = None;
+            Message::ToggleSortDirection => {
+                self.sort_ascending = !self.sort_ascending;
+                self.sorting_changed();
             }
 
-            Message::PreviewLoaded(data) => {
-                self.preview_data = data;
+            Message::ToggleDirsFirst => {
+                self.dirs_first = !self.dirs_first;
+                self.sorting_changed();
             }
 
-            Message::PreviewError(e) => {
-                debug!("Preview error: {}", e);
+            Message::ToggleColumnEditor => {
+                self.column_editor = !self.column_editor;
+            }
+
+            Message::ToggleColumn(column) => {
+                self.columns.toggle(column);
+                self.columns_changed();
+                return self.load_visible_details();
+            }
+
+            Message::MoveColumn(column, offset) => {
+                self.columns.shift(column, offset);
+                self.columns_changed();
+            }
+
+            Message::ResizeColumn(column, delta) => {
+                self.columns.resize(column, delta);
+                self.columns_changed();
+            }
+
+            Message::ListScrolled(viewport) => {
+                self.list_viewport = Some(viewport);
+                return self.load_visible_details();
+            }
+
+            Message::MediaDetailsLoaded(details) => {
+                self.media_details.extend(details);
+            }
+
+            Message::ToggleDualPane => {
+                self.pane_status = None;
+                if self.dual_pane.take().is_some() {
+                    self.focus = PaneSide::Left;
+                    return Command::none();
+                }
+
+                // Opens on the same folder; the preview doesn't fit beside
+                // two lists
+                let path = self.current_path.clone();
+                self.dual_pane = Some(Pane::new(path.clone(), self.default_sort));
+                self.focus = PaneSide::Left;
                 self.preview_data = PreviewData::None;
+                return self.in_other_pane(|app| {
+                    app.apply_folder_view(&path);
+                    app.load_directory()
+                });
             }
 
-            Message::RefreshDirectory => {
-                self.loading = true;
-                return Command::perform(load_directory(self.current_path.clone()), |result| {
-                    match result {
-                        Ok(files) => Message::FilesLoaded(files),
-                        Err(e) => Message::Error(e.to_string()),
+            Message::SwitchPane if self.dual_pane.is_some() && self.quick_look.is_none() => {
+                self.switch_pane();
+            }
+
+            // Scrolling the other pane doesn't take focus
+            Message::OtherPane(message) if matches!(*message, Message::ListScrolled(_)) => {
+                return self.in_other_pane(|app| app.update(*message));
+            }
+
+            Message::OtherPane(message) => {
+                self.switch_pane();
+                return self.update(*message);
+            }
+
+            Message::CopyToOtherPane => {
+                return self.transfer_to_other_pane(BatchOperationType::Copy);
+            }
+
+            Message::MoveToOtherPane => {
+                return self.transfer_to_other_pane(BatchOperationType::Move);
+            }
+
+            Message::PaneTransferFinished(operation, results) => {
+                let failed: Vec<_> = results.iter().filter(|r| !r.success).collect();
+                self.pane_status = Some(match failed.first() {
+                    None => format!("{} {} item(s)", past_tense(&operation), results.len()),
+                    Some(first) => format!(
+                        "{} of {} item(s) failed; {}: {}",
+                        failed.len(),
+                        results.len(),
+                        first.path.file_name().unwrap_or_default().to_string_lossy(),
+                        first.message
+                    ),
+                });
+
+                // Pasting shows no pane status line
+                if self.dual_pane.is_none() {
+                    if !failed.is_empty() {
+                        self.error = self.pane_status.take();
                     }
+                    return self.update(Message::RefreshDirectory);
+                }
+
+                // Both folders changed
+                let focused = self.update(Message::RefreshDirectory);
+                let other = self.in_other_pane(|app| app.update(Message::RefreshDirectory));
+                return Command::batch([focused, other]);
+            }
+
+            Message::SearchChanged(query) => {
+                self.search_query = query;
+            }
+
+            Message::BookmarkClicked(path) => {
+                return Command::perform(async move { path }, Message::NavigateTo);
+            }
+
+            Message::ShowUsage(view) => {
+                self.usage_view = Some(view);
+                self.smart_folder = None;
+                self.in_trash = false;
+                self.listing = None;
+                self.selection.clear();
+                self.batch.deselect_all();
+                self.preview_data = PreviewData::None;
+                self.loading = true;
+                let paths = self.usage.paths(view);
+                return Command::perform(recents::load_entries(paths), move |entries| {
+                    Message::UsageLoaded(view, entries)
                 });
             }
 
-            Message::Error(e) => {
-                self.error = Some(e);
-                self.loading = false;
+            Message::UsageLoaded(view, mut entries) if self.usage_view == Some(view) => {
+                // Kept in usage order, not the folder sort
+                self.filter_listing(&mut entries);
+                self.files = entries;
+                return self.listing_complete();
             }
 
-            _ => {}
-        }
+            Message::ShowSmartFolder(name) => {
+                let Some(folder) = self.smart_folders.get(&name).cloned() else {
+                    return Command::none();
+                };
+                self.smart_folder = Some(name.clone());
+                self.usage_view = None;
+                self.in_trash = false;
+                self.listing = None;
+                self.selection.clear();
+                self.batch.deselect_all();
+                self.preview_data = PreviewData::None;
+                self.loading = true;
+                return Command::perform(smart_folders::load_entries(folder), move |entries| {
+                    Message::SmartFolderLoaded(name, entries)
+                });
+            }
 
-        Command::none()
-    }
+            Message::SmartFolderLoaded(name, mut entries)
+                if self.smart_folder.as_ref() == Some(&name) =>
+            {
+                self.filter_listing(&mut entries);
+                self.sort_settings().sort(&mut entries);
+                self.files = entries;
+                return self.listing_complete();
+            }
 
-    fn view(&self) -> Element<Message> {
-        let toolbar = Toolbar::view(self);
-        let sidebar = Sidebar::view(&self.bookmarks, &self.current_path);
-        let file_list = FileList::view(&self.files, &self.selected, self.view_mode);
+            Message::EditSmartFolder(draft) => {
+                self.smart_folder_draft = draft;
+            }
 
-        let main_content = if self.show_preview {
-            row![file_list, Preview::view(&self.preview_data, &self.selected),].spacing(8)
-        } else {
-            row![file_list]
-        };
+            Message::SaveSmartFolder => {
+                let folder = self
+                    .smart_folder_draft
+                    .as_ref()
+                    .and_then(|draft| draft.build(&self.current_path));
+                if let Some(folder) = folder {
+                    let name = folder.name.clone();
+                    self.smart_folders.insert(folder);
+                    self.smart_folder_draft = None;
+                    if let Err(e) = self.smart_folders.save() {
+                        warn!("Failed to save smart folders: {}", e);
+                    }
+                    return self.update(Message::ShowSmartFolder(name));
+                }
+            }
 
-        let content = row![sidebar, column![toolbar, main_content,].spacing(8),]
-            .spacing(8)
-            .padding(8);
+            Message::DeleteSmartFolder(name) => {
+                self.smart_folders.remove(&name);
+                if let Err(e) = self.smart_folders.save() {
+                    warn!("Failed to save smart folders: {}", e);
+                }
+                if self.smart_folder.as_ref() == Some(&name) {
+                    let path = self.current_path.clone();
+                    return self.update(Message::NavigateTo(path));
+                }
+            }
 
-        let content = if let Some(ref error) = self.error {
-            column![
-                content,
-                container(
-                    text(error).style(iced::theme::Text::Color(iced::Color::from_rgb(
-                        0.9, 0.3, 0.3
-                    )))
-                )
-                .padding(8)
-            ]
-            .into()
-        } else {
-            content.into()
-        };
+            Message::ConnectRemote(name) => {
+                if let Some(path) = self.remotes.mounts.get(&name) {
+                    let path = path.clone();
+                    return self.update(Message::NavigateTo(path));
+                }
+                let Some(location) = self.remotes.remotes.get(&name).cloned() else {
+                    return Command::none();
+                };
+                self.remotes.connecting = Some(name.clone());
+                return Command::perform(remotes::mount(location, None), move |result| {
+                    Message::RemoteMounted(name, result)
+                });
+            }
 
-        container(content)
-            .width(Length::Fill)
-            .height(Length::Fill)
-            .into()
-    }
+            Message::RemoteMounted(name, result) => {
+                if self.remotes.connecting.as_ref() == Some(&name) {
+                    self.remotes.connecting = None;
+                }
+                let Some(location) = self.remotes.remotes.get(&name) else {
+                    return Command::none();
+                };
+                match result {
+                    Ok(path) => {
+                        self.remotes.auth = None;
+                        self.remotes.mounts.insert(name, path.clone());
+                        return self.update(Message::NavigateTo(path));
+                    }
+                    Err(MountError::NeedsLogin) => {
+                        self.remotes.auth = Some(AuthPrompt::new(location, None));
+                    }
+                    Err(MountError::LoginFailed) => {
+                        let error = "Wrong user name or password".to_string();
+                        self.remotes.auth = Some(AuthPrompt::new(location, Some(error)));
+                    }
+                    Err(MountError::Failed(e)) => {
+                        self.remotes.auth = None;
+                        self.error = Some(format!("Can't connect to {}: {}", name, e));
+                    }
+                }
+            }
 
-    fn theme(&self) -> Theme {
-        Theme::Dark
-    }
-}
+            Message::UnmountRemote(name) => {
+                let location = self.remotes.remotes.get(&name).cloned();
+                if let (Some(location), Some(path)) = (location, self.remotes.mounts.get(&name)) {
+                    let path = path.clone();
+                    return Command::perform(remotes::unmount(location, path), move |result| {
+                        Message::RemoteUnmounted(name, result)
+                    });
+                }
+            }
 
-async fn load_directory(path: PathBuf) -> Result<Vec<FileEntry>, std::io::Error> {
-    let mut entries = Vec::new();
+            Message::RemoteUnmounted(name, result) => {
+                if let Err(e) = result {
+                    self.error = Some(format!("Can't unmount {}: {}", name, e));
+                    // A removed location has no sidebar row left to retry
+                    // from, so forget its mount anyway
+                    if self.remotes.remotes.get(&name).is_some() {
+                        return Command::none();
+                    }
+                }
+                // Leave the share before its folders disappear
+                if let Some(path) = self.remotes.mounts.remove(&name) {
+                    if self.current_path.starts_with(&path) {
+                        let home = dirs::home_dir().unwrap_or_else(|| PathBuf::from("/"));
+                        return self.update(Message::NavigateTo(home));
+                    }
+                }
+            }
 
-    let mut read_dir = tokio::fs::read_dir(&path).await?;
+            Message::EditRemote(draft) => {
+                self.remotes.draft = draft;
+            }
 
-    while let Some(entry) = read_dir.next_entry().await? {
-        let metadata = entry.metadata().await?;
-        let file_type = if metadata.is_dir() {
-            "directory"
-        } else {
-            entry
-                .path()
-                .extension()
-                .and_then(|e| e.to_str())
-                .unwrap_or("file")
-        };
+            Message::SaveRemote => {
+                let location = self.remotes.draft.as_ref().and_then(RemoteDraft::build);
+                if let Some(location) = location {
+                    let name = location.name.clone();
+                    self.remotes.remotes.insert(location);
+                    self.remotes.draft = None;
+                    if let Err(e) = self.remotes.remotes.save() {
+                        warn!("Failed to save remote locations: {}", e);
+                    }
+                    return self.update(Message::ConnectRemote(name));
+                }
+            }
 
-        entries.push(FileEntry {
-            name: entry.file_name().to_string_lossy().to_string(),
-            path: entry.path(),
-            is_dir: metadata.is_dir(),
-            size: metadata.len(),
-            modified: metadata.modified().ok(),
-            file_type: file_type.to_string(),
-        });
-    }
+            Message::RemoveRemote(name) => {
+                let command = self.update(Message::UnmountRemote(name.clone()));
+                self.remotes.remotes.remove(&name);
+                if self.remotes.auth.as_ref().is_some_and(|a| a.name == name) {
+                    self.remotes.auth = None;
+                }
+                if let Err(e) = self.remotes.remotes.save() {
+                    warn!("Failed to save remote locations: {}", e);
+                }
+                return command;
+            }
 
-    Ok(entries)
-}
+            Message::EditRemoteAuth(auth) => {
+                self.remotes.auth = auth;
+            }
 
-async fn load_preview(
+            Message::SubmitRemoteAuth => {
+                let Some(auth) = self.remotes.auth.clone() else {
+                    return Command::none();
+                };
+                let Some(location) = self.remotes.remotes.get(&auth.name).cloned() else {
+                    return Command::none();
+                };
+                let name = auth.name;
+                self.remotes.connecting = Some(name.clone());
+                return Command::perform(
+                    remotes::mount(location, Some(auth.credentials)),
+                    move |result| Message::RemoteMounted(name, result),
+                );
+            }
+
+            Message::ShowTrash => {
+                self.in_trash = true;
+                self.usage_view = None;
+                self.smart_folder = None;
+                self.listing = None;
+                self.trash_selected = None;
+                self.trash_purge = None;
+                self.selection.clear();
+                self.batch.deselect_all();
+                self.preview_data = PreviewData::None;
+                self.loading = true;
+                return Command::perform(trash_view::load_trash(), |result| match result {
+                    Ok(entries) => Message::TrashLoaded(entries),
+                    Err(e) => Message::Error(e.to_string()),
+                });
+            }
+
+            Message::TrashLoaded(entries) => {
+                self.trash_entries = entries;
+                self.loading = false;
+                self.error = None;
+            }
+
+            Message::TrashItemSelected(id) => {
+                self.trash_selected = Some(id);
+                self.trash_purge = None;
+            }
+
+            Message::RestoreTrashItem => {
+                if let Some(id) = self.trash_selected.take() {
+                    let items = self.trash_items(&id);
+                    return Command::perform(
+                        trash_view::restore_items(items),
+                        |result| match result {
+                            Ok(()) => Message::ShowTrash,
+                            Err(e) => Message::Error(e.to_string()),
+                        },
+                    );
+                }
+            }
+
+            Message::PurgeTrashItem => {
+                self.trash_purge = self.trash_selected.clone().map(TrashPurge::Item);
+            }
+
+            Message::EmptyTrash => {
+                self.trash_purge = Some(TrashPurge::All);
+            }
+
+            Message::CancelPurge => {
+                self.trash_purge = None;
+            }
+
+            Message::ConfirmPurge => {
+                let purged = |result: Result<(), trash::Error>| match result {
+                    Ok(()) => Message::ShowTrash,
+                    Err(e) => Message::Error(e.to_string()),
+                };
+                match self.trash_purge.take() {
+                    Some(TrashPurge::Item(id)) => {
+                        self.trash_selected = None;
+                        let items = self.trash_items(&id);
+                        return Command::perform(trash_view::purge_items(items), purged);
+                    }
+                    Some(TrashPurge::All) => {
+                        self.trash_selected = None;
+                        return Command::perform(trash_view::empty_trash(), purged);
+                    }
+                    None => {}
+                }
+            }
+
+            Message::FilesLoaded(files) => {
+                // Only archives are listed in one go; a listing arriving
+                // after leaving the archive is stale
+                if !self.in_archive() {
+                    return Command::none();
+                }
+                let mut files = files;
+                self.filter_listing(&mut files);
+                self.sort_settings().sort(&mut files);
+                self.files = files;
+                return self.listing_complete();
+            }
+
+            Message::FilesAppended(id, mut files) => {
+                if !self.start_listing(id) {
+                    return Command::none();
+                }
+                self.filter_listing(&mut files);
+                self.sort_settings().merge(&mut self.files, files);
+            }
+
+            Message::FilesListed(id) if self.start_listing(id) => {
+                return self.listing_complete();
+            }
+
+            Message::FileDetailsLoaded(id, details) => {
+                if self.listing.as_ref().map(|l| l.id) != Some(id) {
+                    return Command::none();
+                }
+                let mut details: HashMap<PathBuf, FileEntry> =
+                    details.into_iter().map(|f| (f.path.clone(), f)).collect();
+                for file in &mut self.files {
+                    if let Some(entry) = details.remove(&file.path) {
+                        *file = entry;
+                    }
+                }
+                if matches!(self.sort_by, SortKey::Size | SortKey::Modified) {
+                    self.sort_settings().sort(&mut self.files);
+                }
+            }
+
+            Message::DirectoryLoaded(id) if self.listing.as_ref().map(|l| l.id) == Some(id) => {
+                self.listing = None;
+                return self.load_visible_details();
+            }
+
+            Message::PreviewLoaded(data) => {
+                self.preview_data = data;
+                return self.refresh_scopes();
+            }
+
+            Message::SetScope(scope) => {
+                self.scope = scope;
+                return self.refresh_scopes();
+            }
+
+            Message::ScopesAnalyzed(request, scopes) if request == self.scope_request => {
+                self.scope_data = scopes;
+            }
+
+            Message::PreviewError(e) => {
+                debug!("Preview error: {}", e);
+                self.preview_data = PreviewData::None;
+            }
+
+            Message::SetPreviewZoom(zoom) => {
+                if let PreviewData::Viewer(ref mut view) = self.preview_data {
+                    view.set_zoom(zoom);
+                }
+            }
+
+            Message::ZoomPreview(factor) => {
+                if let PreviewData::Viewer(ref mut view) = self.preview_data {
+                    view.zoom_by(factor);
+                }
+            }
+
+            Message::PreviewViewChanged(factor, offset) => {
+                if let PreviewData::Viewer(ref mut view) = self.preview_data {
+                    view.factor = factor;
+                    view.offset = offset;
+                }
+            }
+
+            Message::RotatePreview(quarter_turns) => {
+                if let PreviewData::Viewer(ref view) = self.preview_data {
+                    let view = view.clone();
+                    let from = view.handle.id();
+                    return Command::perform(
+                        async move {
+                            tokio::task::spawn_blocking(move || view.rotated(quarter_turns))
+                                .await
+                                .ok()
+                                .flatten()
+                        },
+                        move |rotated| match rotated {
+                            Some(view) => Message::PreviewRotated(from, view),
+                            None => Message::PreviewError("Couldn't rotate the image".to_string()),
+                        },
+                    );
+                }
+            }
+
+            Message::PreviewRotated(from, rotated) => {
+                // Dropped when another file was selected, or it was turned again
+                if let PreviewData::Viewer(ref mut view) = self.preview_data {
+                    if view.handle.id() == from {
+                        *view = rotated;
+                    }
+                }
+            }
+
+            Message::ToggleQuickLook => {
+                if self.quick_look.take().is_none() && !self.in_trash {
+                    if let Some(path) = self.selection.last().cloned() {
+                        return self.open_quick_look(path);
+                    }
+                }
+                return self.refresh_scopes();
+            }
+
+            Message::CloseQuickLook => {
+                self.quick_look = None;
+                self.tools_menu = None;
+                return self.refresh_scopes();
+            }
+
+            Message::QuickLookStep(delta) => {
+                let Some(ref ql) = self.quick_look else {
+                    return Command::none();
+                };
+                let Some(index) = self.files.iter().position(|f| f.path == ql.path) else {
+                    return Command::none();
+                };
+
+                let target = (index as i64 + delta as i64).clamp(0, self.files.len() as i64 - 1);
+                let path = self.files[target as usize].path.clone();
+                if path != ql.path {
+                    // Keep the list selection in step so closing lands on it
+                    self.selection.select_only(path.clone());
+                    return Command::batch([self.selection_changed(), self.open_quick_look(path)]);
+                }
+            }
+
+            Message::QuickLookLoaded(path, data) => {
+                if let Some(ref mut ql) = self.quick_look {
+                    if ql.path == path {
+                        ql.data = Some(data);
+                    }
+                }
+                return self.refresh_scopes();
+            }
+
+            Message::QuickLookTogglePlay => {
+                if let Some(ref mut ql) = self.quick_look {
+                    ql.playing = !ql.playing && ql.frame_count() > 1;
+                }
+            }
+
+            Message::QuickLookSeek(frame) => {
+                if let Some(ref mut ql) = self.quick_look {
+                    ql.frame = frame.min(ql.frame_count().saturating_sub(1));
+                }
+                return self.refresh_scopes();
+            }
+
+            Message::QuickLookTick => {
+                if let Some(ref mut ql) = self.quick_look {
+                    ql.tick();
+                }
+                return self.refresh_scopes();
+            }
+
+            Message::ShowToolsMenu(path) => {
+                if self.in_trash || self.in_archive() {
+                    return Command::none();
+                }
+
+                // Right-clicking outside the selection acts on that file alone
+                let mut command = Command::none();
+                if !self.selection.contains(&path) {
+                    self.selection.select_only(path);
+                    command = self.selection_changed();
+                }
+
+                let paths = self.selection.paths().to_vec();
+                self.tools_menu = None;
+                self.tools_menu_pending = Some(paths.clone());
+                let menu = Command::perform(tools::load_menu(paths), Message::ToolsMenuLoaded);
+                return Command::batch([command, menu]);
+            }
+
+            Message::ToolsMenuLoaded(menu) => {
+                // Dropped when closed, or right-clicked again, meanwhile
+                let Some(menu) =
+                    menu.filter(|m| self.tools_menu_pending.as_ref() == Some(&m.paths))
+                else {
+                    return Command::none();
+                };
+                self.tools_menu_pending = None;
+                self.tools = menu.config;
+                self.tools_menu = Some(menu.matching);
+                self.tools_menu_project = menu.project;
+            }
+
+            Message::CloseToolsMenu => {
+                self.tools_menu = None;
+                self.tools_menu_pending = None;
+            }
+
+            Message::RunTool(index) => {
+                self.tools_menu = None;
+                if let Some(tool) = self.tools.tools.get(index).cloned() {
+                    self.tool_running = Some(tool.label.clone());
+                    let paths = self.selection.paths().to_vec();
+                    return Command::perform(tools::run_tool(tool, paths), Message::ToolFinished);
+                }
+            }
+
+            Message::ToolFinished(runs) => {
+                self.tool_running = None;
+                self.tool_runs = runs;
+                // Tools often write next to their input
+                return self.update(Message::RefreshDirectory);
+            }
+
+            Message::CloseToolResults => {
+                self.tool_runs.clear();
+            }
+
+            Message::RefreshDirectory => {
+                if self.in_trash {
+                    return self.update(Message::ShowTrash);
+                }
+                if let Some(view) = self.usage_view {
+                    return self.update(Message::ShowUsage(view));
+                }
+                if let Some(name) = self.smart_folder.clone() {
+                    return self.update(Message::ShowSmartFolder(name));
+                }
+                return self.load_directory();
+            }
+
+            Message::ExtractSelected => {
+                let Some(dest) = self
+                    .archive
+                    .as_ref()
+                    .and_then(|location| location.archive.parent().map(Path::to_path_buf))
+                else {
+                    return Command::none();
+                };
+                let locations: Vec<_> = self
+                    .selection
+                    .paths()
+                    .iter()
+                    .filter_map(|path| archive_entry(path))
+                    .collect();
+                if locations.is_empty() {
+                    return Command::none();
+                }
+
+                self.loading = true;
+                return Command::perform(extract_entries(locations, dest), |result| match result {
+                    Ok(extracted) => Message::Extracted(extracted),
+                    Err(e) => Message::Error(format!("Failed to extract: {}", e)),
+                });
+            }
+
+            Message::Extracted(extracted) => {
+                // Show the unpacked files where they landed, next to the archive
+                let Some(dest) = extracted.first().and_then(|p| p.parent()) else {
+                    return Command::none();
+                };
+                let command = self.update(Message::NavigateTo(dest.to_path_buf()));
+                for path in extracted {
+                    self.selection.toggle(path);
+                }
+                return command;
+            }
+
+            Message::ShowChecksums => {
+                self.tools_menu = None;
+                let files: Vec<PathBuf> = self
+                    .selection
+                    .paths()
+                    .iter()
+                    .filter(|path| path.is_file())
+                    .cloned()
+                    .collect();
+                if files.is_empty() {
+                    return Command::none();
+                }
+                let algorithm = self
+                    .checksums
+                    .as_ref()
+                    .map(|c| c.algorithm)
+                    .unwrap_or_default();
+                self.start_checksums(algorithm, ChecksumTask::Compute(files));
+            }
+
+            Message::VerifyChecksums(sums) => {
+                self.tools_menu = None;
+                let algorithm = HashAlgorithm::for_sums_file(&sums).unwrap_or_default();
+                self.start_checksums(algorithm, ChecksumTask::Verify(sums));
+            }
+
+            Message::ChecksumAlgorithmChanged(algorithm) => {
+                if let Some(task) = self.checksums.as_ref().map(|c| c.task.clone()) {
+                    self.start_checksums(algorithm, task);
+                }
+            }
+
+            Message::ChecksumProgress(id, event) => {
+                if let Some(ref mut job) = self.checksums {
+                    if job.id == id {
+                        job.apply(event);
+                    }
+                }
+            }
+
+            Message::WriteChecksumSidecars => {
+                if let Some(job) = self.checksums.clone() {
+                    return Command::perform(
+                        checksums::write_sidecars(job),
+                        Message::ChecksumsWritten,
+                    );
+                }
+            }
+
+            Message::WriteChecksumSums => {
+                if let Some(job) = self.checksums.clone() {
+                    // Next to the files, named relative to it
+                    let dir = match job.task {
+                        ChecksumTask::Compute(ref files) => files
+                            .first()
+                            .and_then(|f| f.parent())
+                            .map(Path::to_path_buf)
+                            .unwrap_or_else(|| self.current_path.clone()),
+                        ChecksumTask::Verify(_) => return Command::none(),
+                    };
+                    return Command::perform(
+                        checksums::write_sums_file(job, dir),
+                        Message::ChecksumsWritten,
+                    );
+                }
+            }
+
+            Message::ChecksumsWritten(result) => {
+                if let Some(ref mut job) = self.checksums {
+                    job.status = Some(match result {
+                        Ok(ref written) => match written.as_slice() {
+                            [path] => format!(
+                                "Wrote {}",
+                                path.file_name().unwrap_or_default().to_string_lossy()
+                            ),
+                            written => format!("Wrote {} files", written.len()),
+                        },
+                        Err(ref e) => format!("Failed to write checksums: {}", e),
+                    });
+                }
+                if result.is_ok() {
+                    return self.update(Message::RefreshDirectory);
+                }
+            }
+
+            Message::CloseChecksums => {
+                self.checksums = None;
+            }
+
+            Message::CompareSelected => {
+                let [a, b] = self.selection.paths() else {
+                    return Command::none();
+                };
+                self.comparing = true;
+                self.comparison = None;
+                return Command::perform(compare::compare(a.clone(), b.clone()), |result| {
+                    match result {
+                        Ok(comparison) => Message::CompareFinished(comparison),
+                        Err(e) => Message::Error(format!("Failed to compare: {}", e)),
+                    }
+                });
+            }
+
+            Message::CompareFinished(comparison) => {
+                self.comparing = false;
+                self.comparison = Some(comparison);
+            }
+
+            Message::CloseComparison => {
+                self.comparing = false;
+                self.comparison = None;
+            }
+
+            Message::EditAudioTags => {
+                let [path] = self.selection.paths() else {
+                    return Command::none();
+                };
+                if !audio_tags::is_taggable(path) {
+                    return Command::none();
+                }
+                let path = path.clone();
+                self.audio_tag_editor = Some(AudioTagEditor::new(path.clone()));
+                return Command::perform(audio_tags::read(path.clone()), move |result| {
+                    Message::AudioTagsLoaded(path.clone(), result)
+                });
+            }
+
+            Message::AudioTagsLoaded(path, result) => {
+                // Ignore tags of a file the editor was closed on
+                if let Some(ref mut editor) = self.audio_tag_editor {
+                    if editor.path == path {
+                        editor.loaded(result);
+                    }
+                }
+            }
+
+            Message::AudioTagChanged(field, value) => {
+                if let Some(ref mut editor) = self.audio_tag_editor {
+                    editor.set(field, value);
+                }
+            }
+
+            Message::CoverArtPathChanged(value) => {
+                if let Some(ref mut editor) = self.audio_tag_editor {
+                    editor.cover_path = value;
+                    editor.status = None;
+                }
+            }
+
+            Message::SaveAudioTags => {
+                let Some(ref mut editor) = self.audio_tag_editor else {
+                    return Command::none();
+                };
+                if editor.saving {
+                    return Command::none();
+                }
+                let edits = match editor.edits() {
+                    Ok(edits) => edits,
+                    Err(e) => {
+                        editor.status = Some(e);
+                        return Command::none();
+                    }
+                };
+                let cover = editor.cover();
+                if edits.is_empty() && cover.is_none() {
+                    editor.status = Some("Nothing changed".into());
+                    return Command::none();
+                }
+                editor.saving = true;
+                editor.status = None;
+                return Command::perform(
+                    audio_tags::write(editor.path.clone(), edits, cover),
+                    Message::AudioTagsSaved,
+                );
+            }
+
+            Message::AudioTagsSaved(result) => {
+                let Some(ref mut editor) = self.audio_tag_editor else {
+                    return Command::none();
+                };
+                editor.saving = false;
+                match result {
+                    Ok(()) => {
+                        // Read back what was written, so the form shows it
+                        editor.status = Some("Saved".into());
+                        editor.cover_path.clear();
+                        let path = editor.path.clone();
+                        return Command::perform(audio_tags::read(path.clone()), move |result| {
+                            Message::AudioTagsLoaded(path.clone(), result)
+                        });
+                    }
+                    Err(e) => editor.status = Some(format!("Not saved: {}", e)),
+                }
+            }
+
+            Message::CloseAudioTagEditor => {
+                self.audio_tag_editor = None;
+            }
+
+            Message::Error(e) => {
+                self.error = Some(e);
+                self.loading = false;
+                self.comparing = false;
+            }
+
+            _ => {}
+        }
+
+        Command::none()
+    }
+
+    fn view(&self) -> Element<'_, Message> {
+        if let Some(ref ql) = self.quick_look {
+            let position = self
+                .files
+                .iter()
+                .position(|f| f.path == ql.path)
+                .unwrap_or(0);
+            return container(ql.view(
+                position,
+                self.files.len(),
+                self.scope,
+                self.scope_data.as_ref(),
+            ))
+            .width(Length::Fill)
+            .height(Length::Fill)
+            .padding(8)
+            .into();
+        }
+
+        let toolbar = Toolbar::view(self);
+        let sidebar = Sidebar::view(
+            &self.bookmarks,
+            &self.current_path,
+            self.in_trash,
+            self.usage_view,
+            &self.smart_folders,
+            self.smart_folder.as_deref(),
+            self.smart_folder_draft.as_ref(),
+            &self.remotes,
+        );
+
+        let mut main_content = if let Some(ref other) = self.dual_pane {
+            let focused = panes::view(
+                panes::title(
+                    &self.current_path,
+                    self.in_trash,
+                    self.usage_view,
+                    self.smart_folder.as_deref(),
+                ),
+                if self.in_trash {
+                    TrashView::view(&self.trash_entries, &self.trash_selected, &self.trash_purge)
+                } else {
+                    FileList::view(
+                        &self.files,
+                        &self.selection,
+                        self.view_mode,
+                        &self.icons,
+                        self.column_data(self.column_editor),
+                    )
+                },
+                true,
+            );
+            let unfocused = panes::view(
+                panes::title(
+                    &other.current_path,
+                    other.in_trash,
+                    other.usage_view,
+                    other.smart_folder.as_deref(),
+                ),
+                if other.in_trash {
+                    TrashView::view(
+                        &other.trash_entries,
+                        &other.trash_selected,
+                        &other.trash_purge,
+                    )
+                } else {
+                    FileList::view(
+                        &other.files,
+                        &other.selection,
+                        other.view_mode,
+                        &self.icons,
+                        self.column_data(false),
+                    )
+                },
+                false,
+            )
+            .map(|message| Message::OtherPane(Box::new(message)));
+
+            let panes = match self.focus {
+                PaneSide::Left => row![focused, unfocused],
+                PaneSide::Right => row![unfocused, focused],
+            };
+            row![column![
+                panes.spacing(8).height(Length::Fill),
+                text(self.pane_status.as_deref().unwrap_or("")).size(12),
+            ]
+            .spacing(4)]
+        } else if self.in_trash {
+            row![TrashView::view(
+                &self.trash_entries,
+                &self.trash_selected,
+                &self.trash_purge
+            )]
+        } else if self.show_preview {
+            let file_list = FileList::view(
+                &self.files,
+                &self.selection,
+                self.view_mode,
+                &self.icons,
+                self.column_data(self.column_editor),
+            );
+            row![
+                file_list,
+                Preview::view(
+                    &self.preview_data,
+                    self.selection.last(),
+                    self.cvd_preview,
+                    &self.soft_proofing,
+                    self.scope,
+                    self.scope_data.as_ref(),
+                ),
+            ]
+            .spacing(8)
+        } else {
+            row![FileList::view(
+                &self.files,
+                &self.selection,
+                self.view_mode,
+                &self.icons,
+                self.column_data(self.column_editor),
+            )]
+        };
+        if let Some(ref matching) = self.tools_menu {
+            main_content = main_content.push(tools::view_tools_menu(
+                &self.tools.tools,
+                matching,
+                self.tools_menu_project.as_ref(),
+                match self.selection.paths() {
+                    [path] if HashAlgorithm::for_sums_file(path).is_some() => Some(path),
+                    _ => None,
+                },
+            ));
+        }
+
+        let mut center = column![toolbar].spacing(8);
+        if let Some((ref path, ref name)) = self.rename {
+            center = center.push(file_list::view_rename(path, name));
+        }
+        center = center.push(main_content);
+        if self.batch.selection_count() > 1 && !self.in_trash && !self.in_archive() {
+            center = center.push(batch::view_batch_toolbar(&self.batch));
+        }
+        if self.batch.operation.is_some() {
+            center = center.push(batch::view_batch_dialog(&self.batch, &self.tags));
+        }
+        if self.tool_running.is_some() || !self.tool_runs.is_empty() {
+            center = center.push(tools::view_tool_results(
+                &self.tool_runs,
+                self.tool_running.as_deref(),
+            ));
+        }
+        if let Some(ref job) = self.checksums {
+            center = center.push(checksums::view(job));
+        }
+        if let Some(ref editor) = self.keybinding_editor {
+            center = center.push(keybindings::view_editor(&self.keybindings, editor));
+        }
+        if let Some(ref editor) = self.audio_tag_editor {
+            center = center.push(audio_tags::view(editor));
+        }
+        if self.comparing || self.comparison.is_some() {
+            center = center.push(compare::view_comparison(
+                self.comparison.as_ref(),
+                self.comparing,
+            ));
+        }
+
+        let content = row![sidebar, center].spacing(8).padding(8);
+
+        let content = if let Some(ref error) = self.error {
+            column![
+                content,
+                container(
+                    text(error).style(iced::theme::Text::Color(iced::Color::from_rgb(
+                        0.9, 0.3, 0.3
+                    )))
+                )
+                .padding(8)
+            ]
+            .into()
+        } else {
+            content.into()
+        };
+
+        container(content)
+            .width(Length::Fill)
+            .height(Length::Fill)
+            .into()
+    }
+
+    fn subscription(&self) -> Subscription<Message> {
+        // Clicks don't carry modifiers, so track them for Ctrl/Shift+click
+        let modifiers = iced::event::listen_with(|event, _status| match event {
+            iced::Event::Keyboard(keyboard::Event::ModifiersChanged(modifiers)) => {
+                Some(Message::ModifiersChanged(modifiers))
+            }
+            _ => None,
+        });
+
+        // Looked up in `update`, where the keybindings are
+        let shortcuts =
+            keyboard::on_key_press(|key, modifiers| Some(Message::KeyPressed(key, modifiers)));
+
+        let playback = match self.quick_look {
+            Some(ref ql) if ql.playing => iced::time::every(std::time::Duration::from_millis(250))
+                .map(|_| Message::QuickLookTick),
+            _ => Subscription::none(),
+        };
+
+        let listing = match self.listing {
+            Some(ref listing) => listing::subscription(listing.id, listing.path.clone()),
+            None => Subscription::none(),
+        };
+
+        let other_listing = match self.dual_pane.as_ref().and_then(|p| p.listing.as_ref()) {
+            Some(listing) => listing::subscription(listing.id, listing.path.clone()),
+            None => Subscription::none(),
+        };
+
+        let checksums = match self.checksums {
+            Some(ref job) if job.running => checksums::subscription(job),
+            _ => Subscription::none(),
+        };
+
+        Subscription::batch([
+            modifiers,
+            shortcuts,
+            playback,
+            listing,
+            other_listing,
+            checksums,
+        ])
+    }
+
+    fn theme(&self) -> Theme {
+        Theme::Dark
+    }
+}
+
+impl RururuFiles {
+    /// Analyze the picture Quick Look or the preview pane shows, for the
+    /// scope, off the UI thread: an image may still have to be decoded,
+    /// and playback asks again for every frame. The scope shown stays up
+    /// until the new analysis replaces it.
+    fn refresh_scopes(&mut self) -> Command<Message> {
+        self.scope_request += 1;
+        let request = self.scope_request;
+
+        let analyze: Option<Box<dyn FnOnce() -> Option<rururu_color::Scopes> + Send>> =
+            self.scope.and_then(|_| {
+                let (data, frame) = match self.quick_look {
+                    Some(ref ql) => (ql.data.as_ref()?, ql.frame),
+                    None => (&self.preview_data, 0),
+                };
+                match data {
+                    PreviewData::Image(bytes) => {
+                        let bytes = bytes.clone();
+                        Some(Box::new(move || scopes::of_encoded(&bytes)) as Box<_>)
+                    }
+                    PreviewData::Viewer(view) => {
+                        let handle = view.handle.clone();
+                        Some(Box::new(move || scopes::of_handle(&handle)) as Box<_>)
+                    }
+                    PreviewData::Frames(frames) => {
+                        let handle = frames.get(frame)?.clone();
+                        Some(Box::new(move || scopes::of_handle(&handle)) as Box<_>)
+                    }
+                    _ => None,
+                }
+            });
+
+        let Some(analyze) = analyze else {
+            self.scope_data = None;
+            return Command::none();
+        };
+        Command::perform(
+            async move { tokio::task::spawn_blocking(analyze).await.ok().flatten() },
+            move |scopes| Message::ScopesAnalyzed(request, scopes),
+        )
+    }
+
+    fn open_quick_look(&mut self, path: PathBuf) -> Command<Message> {
+        self.quick_look = Some(QuickLook::new(path.clone()));
+        self.scope_data = None;
+        self.scope_request += 1;
+
+        Command::perform(
+            async move {
+                let data = load_preview(path.clone(), PreviewFidelity::Full)
+                    .await
+                    .unwrap_or(PreviewData::None);
+                (path, data)
+            },
+            |(path, data)| Message::QuickLookLoaded(path, data),
+        )
+    }
+
+    /// The trash items with `id`, for restoring or purging
+    fn trash_items(&self, id: &OsString) -> Vec<trash::TrashItem> {
+        self.trash_entries
+            .iter()
+            .filter(|e| &e.item.id == id)
+            .map(|e| e.item.clone())
+            .collect()
+    }
+
+    fn sort_settings(&self) -> SortSettings {
+        SortSettings {
+            key: self.sort_by,
+            ascending: self.sort_ascending,
+            dirs_first: self.dirs_first,
+        }
+    }
+
+    /// Re-sort the listing in place and remember the direction and folders
+    /// first as the default; the key is kept per folder
+    fn sorting_changed(&mut self) {
+        self.sort_settings().sort(&mut self.files);
+        let defaults = SortSettings {
+            key: self.default_sort,
+            ..self.sort_settings()
+        };
+        if let Err(e) = defaults.save() {
+            debug!("Failed to save sort settings: {}", e);
+        }
+    }
+
+    fn keybindings_changed(&mut self) {
+        if let Err(e) = self.keybindings.save() {
+            debug!("Failed to save keybindings: {}", e);
+        }
+    }
+
+    fn columns_changed(&mut self) {
+        if let Err(e) = self.columns.save() {
+            debug!("Failed to save column settings: {}", e);
+        }
+    }
+
+    fn column_data(&self, editing: bool) -> ColumnData<'_> {
+        ColumnData {
+            settings: &self.columns,
+            details: &self.media_details,
+            tags: &self.tags,
+            editing,
+        }
+    }
+
+    /// Read dimensions and duration for the listed files in view that
+    /// don't have them yet, when the List view shows either column
+    fn load_visible_details(&mut self) -> Command<Message> {
+        if self.view_mode == ViewMode::Grid || !self.columns.reads_media() || self.in_trash {
+            return Command::none();
+        }
+
+        let rows = columns::visible_rows(self.list_viewport, self.files.len());
+        let wanted: Vec<(PathBuf, Option<std::time::SystemTime>)> = self.files[rows]
+            .iter()
+            .filter(|f| !f.is_dir)
+            .filter(|f| {
+                self.media_details
+                    .get(&f.path)
+                    .is_none_or(|d| !d.is_current(f.modified))
+            })
+            .map(|f| (f.path.clone(), f.modified))
+            .collect();
+        if wanted.is_empty() {
+            return Command::none();
+        }
+
+        if self.media_details.len() + wanted.len() > MEDIA_DETAILS_LIMIT {
+            self.media_details.clear();
+        }
+        // Marked as read so scrolling doesn't ask again while they load
+        for (path, modified) in &wanted {
+            self.media_details.insert(
+                path.clone(),
+                MediaDetails {
+                    modified: *modified,
+                    ..MediaDetails::default()
+                },
+            );
+        }
+        Command::perform(
+            columns::load_media_details(wanted),
+            Message::MediaDetailsLoaded,
+        )
+    }
+
+    /// Switch to the view saved for `dir`, or the global defaults
+    fn apply_folder_view(&mut self, dir: &Path) {
+        let view = self.folder_views.get(dir);
+        self.view_mode = view.view_mode.unwrap_or_default();
+        self.sort_by = view.sort_key.unwrap_or(self.default_sort);
+        self.show_hidden = view.show_hidden.unwrap_or(false);
+    }
+
+    fn remember_folder_view(&mut self, change: impl FnOnce(&mut FolderView)) {
+        if !self.in_trash
+            && self.usage_view.is_none()
+            && self.smart_folder.is_none()
+            && !self.in_archive()
+        {
+            self.folder_views.update(&self.current_path, change);
+        }
+    }
+
+    /// Browsing inside a zip or tar rather than a real folder
+    pub fn in_archive(&self) -> bool {
+        !self.in_trash
+            && self.usage_view.is_none()
+            && self.smart_folder.is_none()
+            && self.archive.is_some()
+    }
+
+    /// Open the checksum panel and hash under a fresh job id
+    fn start_checksums(&mut self, algorithm: HashAlgorithm, task: ChecksumTask) {
+        self.checksum_jobs += 1;
+        let mut job = Checksums::new(self.checksum_jobs, algorithm, task);
+        job.start(self.checksum_jobs);
+        self.checksums = Some(job);
+    }
+
+    /// Exactly two regular files on disk are selected
+    pub fn can_compare(&self) -> bool {
+        matches!(self.selection.paths(), [a, b] if a.is_file() && b.is_file())
+    }
+
+    /// One audio file the file handler can write tags to is selected
+    pub fn can_edit_audio_tags(&self) -> bool {
+        matches!(self.selection.paths(), [path] if audio_tags::is_taggable(path))
+    }
+
+    /// Folders, including those inside archives, and archives themselves
+    fn is_folder(&self, path: &Path) -> bool {
+        path.is_dir()
+            || self.files.iter().any(|f| f.path == path && f.is_dir)
+            || (path.is_file() && archive::ArchiveKind::from_path(path).is_some())
+    }
+
+    /// In dual-pane mode
+    pub fn is_dual_pane(&self) -> bool {
+        self.dual_pane.is_some()
+    }
+
+    /// Move focus to the other pane
+    fn switch_pane(&mut self) {
+        self.swap_panes();
+        self.focus = self.focus.other();
+        self.tools_menu = None;
+        self.batch.set_selection(self.selection.paths());
+    }
+
+    /// Run `f` against the unfocused pane, leaving focus where it is
+    fn in_other_pane<R>(&mut self, f: impl FnOnce(&mut Self) -> R) -> R {
+        self.swap_panes();
+        let result = f(self);
+        self.swap_panes();
+        self.batch.set_selection(self.selection.paths());
+        result
+    }
+
+    /// Exchange the browsing state with the parked pane
+    fn swap_panes(&mut self) {
+        let Some(other) = self.dual_pane.as_mut() else {
+            return;
+        };
+        std::mem::swap(&mut self.current_path, &mut other.current_path);
+        std::mem::swap(&mut self.archive, &mut other.archive);
+        std::mem::swap(&mut self.history, &mut other.history);
+        std::mem::swap(&mut self.history_index, &mut other.history_index);
+        std::mem::swap(&mut self.files, &mut other.files);
+        std::mem::swap(&mut self.selection, &mut other.selection);
+        std::mem::swap(&mut self.listing, &mut other.listing);
+        std::mem::swap(&mut self.loading, &mut other.loading);
+        std::mem::swap(&mut self.view_mode, &mut other.view_mode);
+        std::mem::swap(&mut self.sort_by, &mut other.sort_by);
+        std::mem::swap(&mut self.show_hidden, &mut other.show_hidden);
+        std::mem::swap(&mut self.search_query, &mut other.search_query);
+        std::mem::swap(&mut self.usage_view, &mut other.usage_view);
+        std::mem::swap(&mut self.smart_folder, &mut other.smart_folder);
+        std::mem::swap(&mut self.in_trash, &mut other.in_trash);
+        std::mem::swap(&mut self.trash_entries, &mut other.trash_entries);
+        std::mem::swap(&mut self.trash_selected, &mut other.trash_selected);
+        std::mem::swap(&mut self.trash_purge, &mut other.trash_purge);
+        std::mem::swap(&mut self.list_viewport, &mut other.list_viewport);
+    }
+
+    /// Copy or move the selection into the other pane's folder
+    fn transfer_to_other_pane(&mut self, operation: BatchOperationType) -> Command<Message> {
+        let Some(ref other) = self.dual_pane else {
+            return Command::none();
+        };
+        if self.selection.is_empty() || self.in_trash {
+            return Command::none();
+        }
+        let target = other.current_path.clone();
+        if self.in_archive()
+            || other.in_trash
+            || other.usage_view.is_some()
+            || other.smart_folder.is_some()
+            || !target.is_dir()
+        {
+            self.pane_status = Some("Copy and move work between folders on disk".to_string());
+            return Command::none();
+        }
+
+        let paths = self.selection.paths().to_vec();
+        self.transfer(paths, target, operation)
+    }
+
+    /// Remember the selection for `Paste`, to be moved rather than copied
+    /// if `cut`
+    fn set_clipboard(&mut self, cut: bool) {
+        if self.selection.is_empty() || self.in_trash {
+            return;
+        }
+        if self.in_archive() {
+            self.error = Some("Extract files from the archive before copying them".to_string());
+            return;
+        }
+        self.clipboard = Some((self.selection.paths().to_vec(), cut));
+    }
+
+    /// Copy or move the clipboard into the current folder. A cut is pasted
+    /// once; a copy can be pasted again.
+    fn paste(&mut self) -> Command<Message> {
+        let Some((paths, cut)) = self.clipboard.clone() else {
+            return Command::none();
+        };
+        if !self.in_folder_on_disk() {
+            self.error = Some("Paste into a folder on disk".to_string());
+            return Command::none();
+        }
+        let operation = if cut {
+            self.clipboard = None;
+            BatchOperationType::Move
+        } else {
+            BatchOperationType::Copy
+        };
+        let target = self.current_path.clone();
+        self.transfer(paths, target, operation)
+    }
+
+    /// Listing a real folder, rather than the trash, an archive or a view
+    /// gathered from several folders
+    fn in_folder_on_disk(&self) -> bool {
+        !self.in_trash
+            && self.usage_view.is_none()
+            && self.smart_folder.is_none()
+            && !self.in_archive()
+            && self.current_path.is_dir()
+    }
+
+    /// Copy or move `paths` into `target` with the batch operations. Names
+    /// already taken there are skipped rather than overwritten.
+    fn transfer(
+        &mut self,
+        paths: Vec<PathBuf>,
+        target: PathBuf,
+        operation: BatchOperationType,
+    ) -> Command<Message> {
+        let mut sources = Vec::new();
+        let mut skipped = Vec::new();
+        for path in &paths {
+            let dest = target.join(path.file_name().unwrap_or_default());
+            let reason = if target.starts_with(path) {
+                "can't go inside itself"
+            } else if dest.symlink_metadata().is_ok() {
+                "already exists there"
+            } else {
+                sources.push(path.clone());
+                continue;
+            };
+            skipped.push(BatchResult {
+                path: path.clone(),
+                success: false,
+                message: reason.to_string(),
+            });
+        }
+
+        self.pane_status = Some(format!("{}…", operation));
+        let mut batch = BatchOperation {
+            selected_files: sources,
+            operation: Some(operation.clone()),
+            target_directory: Some(target),
+            ..Default::default()
+        };
+        Command::perform(
+            async move {
+                let mut results = batch.execute().await;
+                results.extend(skipped);
+                results
+            },
+            move |results| Message::PaneTransferFinished(operation, results),
+        )
+    }
+
+    /// Reload `current_path`. Archives are listed in one go; real folders
+    /// are streamed in by the listing subscription, which replaces (and so
+    /// cancels) any load still running.
+    fn load_directory(&mut self) -> Command<Message> {
+        self.loading = true;
+        self.listing = None;
+        self.archive = ArchivePath::locate(&self.current_path);
+
+        if let Some(location) = self.archive.clone() {
+            return Command::perform(list_archive(location), |result| match result {
+                Ok(files) => Message::FilesLoaded(files),
+                Err(e) => Message::Error(e.to_string()),
+            });
+        }
+
+        self.listings_started += 1;
+        self.listing = Some(Listing {
+            id: self.listings_started,
+            path: self.current_path.clone(),
+            started: false,
+        });
+        Command::none()
+    }
+
+    /// Whether `id` is the running load; its first message clears the
+    /// previous listing, which stays up until then to avoid flicker
+    fn start_listing(&mut self, id: u64) -> bool {
+        match self.listing {
+            Some(ref mut listing) if listing.id == id => {
+                if !listing.started {
+                    listing.started = true;
+                    self.files.clear();
+                }
+                true
+            }
+            _ => false,
+        }
+    }
+
+    fn filter_listing(&self, files: &mut Vec<FileEntry>) {
+        if !self.show_hidden {
+            files.retain(|f| !f.name.starts_with('.'));
+        }
+
+        // Apply search filter
+        if !self.search_query.is_empty() {
+            let query = self.search_query.to_lowercase();
+            files.retain(|f| f.name.to_lowercase().contains(&query));
+        }
+    }
+
+    /// Every entry is in: drop selected paths that are gone
+    fn listing_complete(&mut self) -> Command<Message> {
+        self.loading = false;
+        self.error = None;
+
+        // Streamed folders wait for their modification times, which the
+        // details are cached by, and load them on `DirectoryLoaded`
+        let details = if self.listing.is_none() {
+            self.load_visible_details()
+        } else {
+            Command::none()
+        };
+
+        let previewed = self.selection.last().cloned();
+        let listed = self.listed_paths();
+        self.selection.retain_existing(&listed);
+        if self.selection.last() != previewed.as_ref() {
+            return Command::batch([details, self.selection_changed()]);
+        }
+        self.batch.set_selection(self.selection.paths());
+        details
+    }
+
+    fn listed_paths(&self) -> Vec<PathBuf> {
+        self.files.iter().map(|f| f.path.clone()).collect()
+    }
+
+    /// Push the selection to the batch targets and reload the preview for
+    /// the most recently selected item.
+    fn selection_changed(&mut self) -> Command<Message> {
+        self.batch.set_selection(self.selection.paths());
+
+        match self.selection.last() {
+            Some(path) if self.show_preview && self.dual_pane.is_none() => {
+                let filter = ImageFilter {
+                    proof: self
+                        .soft_proofing
+                        .selected
+                        .as_ref()
+                        .map(|p| (p.path.clone(), self.soft_proofing.gamut_warning)),
+                    cvd: self.cvd_preview,
+                };
+                let preview = load_pane_preview(path.clone(), filter);
+                Command::perform(preview, |result| match result {
+                    Ok(data) => Message::PreviewLoaded(data),
+                    Err(e) => Message::PreviewError(e.to_string()),
+                })
+            }
+            Some(_) => Command::none(),
+            None => {
+                self.preview_data = PreviewData::None;
+                Command::none()
+            }
+        }
+    }
+}
+
+/// The listing a streamed message belongs to
+fn listing_id(message: &Message) -> Option<u64> {
+    match message {
+        Message::FilesAppended(id, _) | Message::FileDetailsLoaded(id, _) => Some(*id),
+        Message::FilesListed(id) | Message::DirectoryLoaded(id) => Some(*id),
+        _ => None,
+    }
+}
+
+/// Last path component for display, lossily
+fn file_name(path: &Path) -> String {
+    path.file_name()
+        .map(|n| n.to_string_lossy().to_string())
+        .unwrap_or_default()
+}
+
+fn past_tense(operation: &BatchOperationType) -> &'static str {
+    match operation {
+        BatchOperationType::Move => "Moved",
+        _ => "Copied",
+    }
+}
+
+async fn list_archive(location: ArchivePath) -> Result<Vec<FileEntry>, std::io::Error> {
+    tokio::task::spawn_blocking(move || archive::list(&location))
+        .await
+        .map_err(std::io::Error::other)?
+}
+
+/// The archive entry at `path`, unless it is a real file or folder
+fn archive_entry(path: &Path) -> Option<ArchivePath> {
+    if path.exists() {
+        return None;
+    }
+    ArchivePath::locate(path).filter(|location| !location.inner.as_os_str().is_empty())
+}
+
+async fn unpack_to_temp(location: ArchivePath, limit: Option<u64>) -> std::io::Result<PathBuf> {
+    tokio::task::spawn_blocking(move || archive::extract_to_temp(&location, limit))
+        .await
+        .map_err(std::io::Error::other)?
+}
+
+async fn extract_entries(
+    locations: Vec<ArchivePath>,
+    dest: PathBuf,
+) -> std::io::Result<Vec<PathBuf>> {
+    tokio::task::spawn_blocking(move || {
+        locations
+            .iter()
+            .map(|location| archive::extract(location, &dest))
+            .collect()
+    })
+    .await
+    .map_err(std::io::Error::other)?
+}
+
+/// Side-pane preview, with images passed through the soft proof and
+/// color-blindness simulation when either is selected
+async fn load_pane_preview(
     path: PathBuf,
+    filter: ImageFilter,
 ) -> Result<PreviewData, Box<dyn std::error::Error + Send + Sync>> {
+    let data = load_preview(path, PreviewFidelity::Pane).await?;
+    let PreviewData::Image(bytes) = data else {
+        return Ok(data);
+    };
+    if filter.is_none() {
+        return Ok(image_view::decode(bytes, None).await);
+    }
+    // The filtered image is smaller than the file
+    let original = image_view::original_size(&bytes);
+    Ok(match preview::filter_image(bytes, filter).await {
+        PreviewData::Image(filtered) => image_view::decode(filtered, original).await,
+        data => data,
+    })
+}
+
+async fn load_preview(
+    path: PathBuf,
+    fidelity: PreviewFidelity,
+) -> Result<PreviewData, Box<dyn std::error::Error + Send + Sync>> {
+    // Archive entries are unpacked on demand; folders inside fail here and
+    // get no preview, like real ones
+    let path = match archive_entry(&path) {
+        Some(location) => unpack_to_temp(location, Some(ARCHIVE_PREVIEW_LIMIT)).await?,
+        None => path,
+    };
+
     let ext = path
         .extension()
         .and_then(|e| e.to_str())
         .unwrap_or("")
         .to_lowercase();
 
+    let text_limit = match fidelity {
+        PreviewFidelity::Pane => 10_000,
+        PreviewFidelity::Full => 1_000_000,
+    };
+
     match ext.as_str() {
         "png" | "jpg" | "jpeg" | "gif" | "webp" | "bmp" => {
             let data = tokio::fs::read(&path).await?;
             Ok(PreviewData::Image(data))
         }
-        "txt" | "md" | "rs" | "py" | "js" | "ts" | "json" | "toml" | "yaml" | "yml" | "sh" => {
+        "svg" | "svgz" => {
+            let size = match fidelity {
+                PreviewFidelity::Pane => ThumbnailSize::LARGE,
+                PreviewFidelity::Full => ThumbnailSize {
+                    width: 2048,
+                    height: 2048,
+                },
+            };
+            let png =
+                tokio::task::spawn_blocking(move || thumbnail::render_svg(&path, size)).await??;
+            Ok(PreviewData::Image(png))
+        }
+        "txt" | "md" | "rs" | "py" | "js" | "ts" | "json" | "toml" | "yaml" | "yml" | "sh"
+        // Subtitles and the text edit-exchange formats
+        | "srt" | "vtt" | "ass" | "ssa" | "edl" | "xml" | "fcpxml" | "otio" => {
             let content = tokio::fs::read_to_string(&path).await?;
-            let truncated = if content.len() > 10000 {
-                format!("{}...\n\n[Truncated]", &content[..10000])
-            } else {
-                content
+            let truncated = match content.char_indices().nth(text_limit) {
+                Some((end, _)) => format!("{}...\n\n[Truncated]", &content[..end]),
+                None => content,
             };
             Ok(PreviewData::Text(truncated))
         }
+        "pdf" if fidelity == PreviewFidelity::Full => Ok(quick_look::render_pdf_page(&path).await?),
+        "mp4" | "mkv" | "avi" | "mov" | "webm" if fidelity == PreviewFidelity::Full => {
+            Ok(quick_look::load_video_frames(path).await)
+        }
+        "mp4" | "mkv" | "avi" | "mov" | "webm" => Ok(quick_look::load_video_metadata(path).await),
+        "wav" | "flac" | "mp3" | "ogg" | "opus" | "m4a" | "aac" | "aiff" => {
+            Ok(quick_look::load_audio_metadata(path, fidelity == PreviewFidelity::Full).await)
+        }
         _ => Ok(PreviewData::None),
     }
 }
@@ -0,0 +1,528 @@
+//! Browsing inside zip and tar archives without unpacking them.
+//!
+//! A location inside an archive is an ordinary path with the archive file
+//! as one of its ancestors, e.g. `~/photos.zip/2024/img.jpg`, so history,
+//! the path bar and "up" work unchanged. Listings only keep the direct
+//! children of the folder being shown; single entries are extracted to a
+//! private temp directory when they are previewed or opened.
+
+use crate::file_list::FileEntry;
+use std::collections::hash_map::DefaultHasher;
+use std::collections::BTreeMap;
+use std::fs::File;
+use std::hash::{Hash, Hasher};
+use std::io::{self, BufReader, Read};
+use std::os::unix::fs::PermissionsExt;
+use std::path::{Component, Path, PathBuf};
+use std::sync::{Arc, Mutex, PoisonError};
+use std::time::{Duration, SystemTime, UNIX_EPOCH};
+use tempfile::TempDir;
+
+/// How many archives keep their entry list around for navigation
+const CACHED_INDEXES: usize = 4;
+
+/// Entry lists of recently browsed archives, most recent last
+static INDEXES: Mutex<Vec<Index>> = Mutex::new(Vec::new());
+
+/// Where entries are unpacked for previewing and opening; created on first
+/// use (mode 0700) and removed by [`remove_temp_dir`]
+static TEMP_DIR: Mutex<Option<TempDir>> = Mutex::new(None);
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum ArchiveKind {
+    Zip,
+    Tar,
+    TarGz,
+}
+
+impl ArchiveKind {
+    pub fn from_path(path: &Path) -> Option<Self> {
+        let name = path.file_name()?.to_str()?.to_lowercase();
+        if name.ends_with(".zip") {
+            Some(Self::Zip)
+        } else if name.ends_with(".tar.gz") || name.ends_with(".tgz") {
+            Some(Self::TarGz)
+        } else if name.ends_with(".tar") {
+            Some(Self::Tar)
+        } else {
+            None
+        }
+    }
+}
+
+/// An archive file and a path inside it
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+pub struct ArchivePath {
+    pub archive: PathBuf,
+    pub kind: ArchiveKind,
+    /// Relative to the archive root; empty for the root itself
+    pub inner: PathBuf,
+}
+
+impl ArchivePath {
+    /// The archive `path` points into, if any
+    pub fn locate(path: &Path) -> Option<Self> {
+        path.ancestors().find_map(|ancestor| {
+            let kind = ArchiveKind::from_path(ancestor)?;
+            if !ancestor.is_file() {
+                return None;
+            }
+            Some(Self {
+                archive: ancestor.to_path_buf(),
+                kind,
+                inner: path.strip_prefix(ancestor).ok()?.to_path_buf(),
+            })
+        })
+    }
+
+    pub fn virtual_path(&self) -> PathBuf {
+        self.archive.join(&self.inner)
+    }
+}
+
+/// What the listing needs from a zip or tar entry
+struct RawEntry {
+    path: PathBuf,
+    is_dir: bool,
+    size: u64,
+    modified: Option<SystemTime>,
+}
+
+/// Every entry of one archive as it was when read
+struct Index {
+    archive: PathBuf,
+    modified: Option<SystemTime>,
+    len: u64,
+    entries: Arc<Vec<RawEntry>>,
+}
+
+/// The entries of `location.archive`, read once and reused until the file
+/// changes, so moving between folders inside it doesn't re-walk the archive
+fn entries(location: &ArchivePath) -> io::Result<Arc<Vec<RawEntry>>> {
+    let metadata = std::fs::metadata(&location.archive)?;
+    let modified = metadata.modified().ok();
+    let len = metadata.len();
+
+    let cached = INDEXES
+        .lock()
+        .unwrap_or_else(PoisonError::into_inner)
+        .iter()
+        .find(|index| {
+            index.archive == location.archive && index.modified == modified && index.len == len
+        })
+        .map(|index| Arc::clone(&index.entries));
+    if let Some(entries) = cached {
+        return Ok(entries);
+    }
+
+    let mut entries = Vec::new();
+    for_each_entry(location, false, |entry, _| {
+        entries.push(entry);
+        Ok(())
+    })?;
+    let entries = Arc::new(entries);
+
+    let mut indexes = INDEXES.lock().unwrap_or_else(PoisonError::into_inner);
+    indexes.retain(|index| index.archive != location.archive);
+    if indexes.len() >= CACHED_INDEXES {
+        indexes.remove(0);
+    }
+    indexes.push(Index {
+        archive: location.archive.clone(),
+        modified,
+        len,
+        entries: Arc::clone(&entries),
+    });
+    Ok(entries)
+}
+
+/// Direct children of `location.inner`, with folders that only exist as
+/// prefixes of deeper entries filled in
+pub fn list(location: &ArchivePath) -> io::Result<Vec<FileEntry>> {
+    let mut children: BTreeMap<String, FileEntry> = BTreeMap::new();
+    let base = location.virtual_path();
+
+    for entry in entries(location)?.iter() {
+        let Ok(rest) = entry.path.strip_prefix(&location.inner) else {
+            continue;
+        };
+        let mut components = rest.components();
+        let Some(first) = components.next() else {
+            continue;
+        };
+        let name = first.as_os_str().to_string_lossy().into_owned();
+        let nested = components.next().is_some();
+        let is_dir = nested || entry.is_dir;
+
+        // An explicit entry beats a folder made up from a deeper path
+        if nested && children.contains_key(&name) {
+            continue;
+        }
+        let file_type = if is_dir {
+            "directory".to_string()
+        } else {
+            Path::new(&name)
+                .extension()
+                .and_then(|e| e.to_str())
+                .unwrap_or("file")
+                .to_string()
+        };
+        children.insert(
+            name.clone(),
+            FileEntry {
+                path: base.join(&name),
+                name,
+                is_dir,
+                is_symlink: false,
+                executable: false,
+                size: if is_dir { 0 } else { entry.size },
+                modified: if nested { None } else { entry.modified },
+                file_type,
+            },
+        );
+    }
+
+    Ok(children.into_values().collect())
+}
+
+/// Unpack the entry (a file, or a folder and everything under it) into
+/// `dest`, returning where it ended up. Never overwrites anything there.
+pub fn extract(location: &ArchivePath, dest: &Path) -> io::Result<PathBuf> {
+    if let Some(name) = location.inner.file_name() {
+        if dest.join(name).exists() {
+            return Err(io::Error::new(
+                io::ErrorKind::AlreadyExists,
+                format!("{} already exists", dest.join(name).display()),
+            ));
+        }
+    }
+    unpack(location, dest, true, None)
+}
+
+/// Extract a single file into a per-entry directory under the private temp
+/// directory for previewing or opening, reusing an earlier copy while the
+/// archive is unchanged. Fails for folders, and once more than `limit`
+/// bytes would be written.
+pub fn extract_to_temp(location: &ArchivePath, limit: Option<u64>) -> io::Result<PathBuf> {
+    let modified = std::fs::metadata(&location.archive)?.modified().ok();
+    let mut hasher = DefaultHasher::new();
+    location.hash(&mut hasher);
+    modified.hash(&mut hasher);
+
+    let dir = temp_dir()?.join(format!("{:016x}", hasher.finish()));
+    let cached = location.inner.file_name().map(|name| dir.join(name));
+    if let Some(cached) = cached.filter(|p| p.exists()) {
+        return Ok(cached);
+    }
+
+    std::fs::create_dir_all(&dir)?;
+    unpack(location, &dir, false, limit).inspect_err(|_| {
+        let _ = std::fs::remove_dir_all(&dir);
+    })
+}
+
+/// Delete everything [`extract_to_temp`] unpacked; called on exit
+pub fn remove_temp_dir() {
+    TEMP_DIR
+        .lock()
+        .unwrap_or_else(PoisonError::into_inner)
+        .take();
+}
+
+fn temp_dir() -> io::Result<PathBuf> {
+    let mut temp = TEMP_DIR.lock().unwrap_or_else(PoisonError::into_inner);
+    if let Some(dir) = temp.as_ref() {
+        return Ok(dir.path().to_path_buf());
+    }
+    let dir = tempfile::Builder::new()
+        .prefix("rururu-files-")
+        .permissions(std::fs::Permissions::from_mode(0o700))
+        .tempdir()?;
+    let path = dir.path().to_path_buf();
+    *temp = Some(dir);
+    Ok(path)
+}
+
+fn unpack(
+    location: &ArchivePath,
+    dest: &Path,
+    folders: bool,
+    limit: Option<u64>,
+) -> io::Result<PathBuf> {
+    let name = location.inner.file_name().ok_or_else(|| {
+        io::Error::new(
+            io::ErrorKind::InvalidInput,
+            "cannot extract the archive root",
+        )
+    })?;
+    let target = dest.join(name);
+    let mut remaining = limit.unwrap_or(u64::MAX);
+    let mut found = false;
+
+    for_each_entry(location, true, |entry, data| {
+        let Ok(rest) = entry.path.strip_prefix(&location.inner) else {
+            return Ok(());
+        };
+        let is_file = rest.as_os_str().is_empty() && !entry.is_dir;
+        if !folders && !is_file {
+            return Ok(());
+        }
+        found = true;
+        // Joining an empty path would add a trailing slash
+        let out = if rest.as_os_str().is_empty() {
+            target.clone()
+        } else {
+            target.join(rest)
+        };
+
+        if entry.is_dir {
+            return std::fs::create_dir_all(&out);
+        }
+        let Some(data) = data else {
+            return Ok(());
+        };
+        if let Some(parent) = out.parent() {
+            std::fs::create_dir_all(parent)?;
+        }
+        // `create_new` refuses existing files and symlinks alike
+        let mut file = File::options().write(true).create_new(true).open(&out)?;
+        let mut limited = data.take(remaining.saturating_add(1));
+        let written = io::copy(&mut limited, &mut file)?;
+        if written > remaining {
+            let _ = std::fs::remove_file(&out);
+            return Err(io::Error::other(format!(
+                "{} is too large to extract",
+                location.inner.display()
+            )));
+        }
+        remaining -= written;
+        Ok(())
+    })?;
+
+    if found {
+        Ok(target)
+    } else {
+        Err(io::Error::new(
+            io::ErrorKind::NotFound,
+            format!("nothing to extract at {}", location.inner.display()),
+        ))
+    }
+}
+
+/// Walk the entries in archive order. With `read_data`, `f` gets the
+/// contents of regular files; symlinks and other special entries are
+/// skipped.
+fn for_each_entry(
+    location: &ArchivePath,
+    read_data: bool,
+    mut f: impl FnMut(RawEntry, Option<&mut dyn Read>) -> io::Result<()>,
+) -> io::Result<()> {
+    let file = BufReader::new(File::open(&location.archive)?);
+
+    match location.kind {
+        ArchiveKind::Zip => {
+            let mut zip = zip::ZipArchive::new(file).map_err(io::Error::other)?;
+            for i in 0..zip.len() {
+                // Raw entries skip decryption, so listing works without a password
+                let entry = if read_data {
+                    zip.by_index(i)
+                } else {
+                    zip.by_index_raw(i)
+                };
+                let mut entry = entry.map_err(io::Error::other)?;
+                // `enclosed_name` drops entries that would escape the
+                // archive; `normalize` also drops any `..` left inside
+                let Some(path) = entry.enclosed_name().as_deref().and_then(normalize) else {
+                    continue;
+                };
+                if entry.is_symlink() {
+                    continue;
+                }
+                let raw = RawEntry {
+                    path,
+                    is_dir: entry.is_dir(),
+                    size: entry.size(),
+                    modified: entry.last_modified().and_then(zip_time),
+                };
+                if raw.is_dir || !read_data {
+                    f(raw, None)?;
+                } else {
+                    f(raw, Some(&mut entry))?;
+                }
+            }
+        }
+        ArchiveKind::Tar => tar_entries(tar::Archive::new(file), f)?,
+        ArchiveKind::TarGz => {
+            tar_entries(tar::Archive::new(flate2::read::GzDecoder::new(file)), f)?
+        }
+    }
+    Ok(())
+}
+
+fn tar_entries<R: Read>(
+    mut archive: tar::Archive<R>,
+    mut f: impl FnMut(RawEntry, Option<&mut dyn Read>) -> io::Result<()>,
+) -> io::Result<()> {
+    for entry in archive.entries()? {
+        let mut entry = entry?;
+        let Some(path) = normalize(&entry.path()?) else {
+            continue;
+        };
+        let kind = entry.header().entry_type();
+        let raw = RawEntry {
+            path,
+            is_dir: kind.is_dir(),
+            size: entry.header().size().unwrap_or(0),
+            modified: entry
+                .header()
+                .mtime()
+                .ok()
+                .map(|secs| UNIX_EPOCH + Duration::from_secs(secs)),
+        };
+        if kind.is_file() {
+            f(raw, Some(&mut entry))?;
+        } else if kind.is_dir() {
+            f(raw, None)?;
+        }
+    }
+    Ok(())
+}
+
+/// Tar paths often start with `./`; anything absolute or climbing out
+/// with `..` is ignored
+fn normalize(path: &Path) -> Option<PathBuf> {
+    let mut out = PathBuf::new();
+    for component in path.components() {
+        match component {
+            Component::Normal(part) => out.push(part),
+            Component::CurDir => {}
+            _ => return None,
+        }
+    }
+    (!out.as_os_str().is_empty()).then_some(out)
+}
+
+/// Zip timestamps are local time without a zone; treat them as UTC
+fn zip_time(time: zip::DateTime) -> Option<SystemTime> {
+    let date = chrono::NaiveDate::from_ymd_opt(
+        time.year() as i32,
+        time.month() as u32,
+        time.day() as u32,
+    )?;
+    let datetime = date.and_hms_opt(
+        time.hour() as u32,
+        time.minute() as u32,
+        time.second() as u32,
+    )?;
+    Some(datetime.and_utc().into())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::io::Write;
+
+    fn zip_with(dir: &Path, names: &[&str]) -> ArchivePath {
+        let path = dir.join("test.zip");
+        let mut zip = zip::ZipWriter::new(File::create(&path).unwrap());
+        let options = zip::write::SimpleFileOptions::default()
+            .compression_method(zip::CompressionMethod::Stored);
+        for name in names {
+            zip.start_file(*name, options).unwrap();
+            zip.write_all(b"data").unwrap();
+        }
+        zip.finish().unwrap();
+        ArchivePath::locate(&path).unwrap()
+    }
+
+    fn listed_names(location: &ArchivePath) -> Vec<String> {
+        list(location)
+            .unwrap()
+            .into_iter()
+            .map(|entry| entry.name)
+            .collect()
+    }
+
+    #[test]
+    fn test_normalize_drops_current_dir_components() {
+        assert_eq!(
+            normalize(Path::new("./photos/./img.jpg")),
+            Some(PathBuf::from("photos/img.jpg"))
+        );
+    }
+
+    #[test]
+    fn test_normalize_rejects_paths_leaving_the_archive() {
+        assert_eq!(normalize(Path::new("../evil.txt")), None);
+        assert_eq!(normalize(Path::new("a/../../evil.txt")), None);
+        assert_eq!(normalize(Path::new("a/../b.txt")), None);
+        assert_eq!(normalize(Path::new("/etc/passwd")), None);
+    }
+
+    #[test]
+    fn test_normalize_rejects_the_empty_path() {
+        assert_eq!(normalize(Path::new("./")), None);
+        assert_eq!(normalize(Path::new("")), None);
+    }
+
+    #[test]
+    fn test_zip_entries_escaping_the_archive_are_not_listed() {
+        let dir = tempfile::tempdir().unwrap();
+        let root = zip_with(
+            dir.path(),
+            &["ok.txt", "../evil.txt", "/abs.txt", "sub/../../evil2.txt"],
+        );
+
+        assert_eq!(listed_names(&root), ["ok.txt"]);
+    }
+
+    #[test]
+    fn test_zip_entries_escaping_the_archive_are_not_extracted() {
+        let dir = tempfile::tempdir().unwrap();
+        let dest = dir.path().join("dest");
+        std::fs::create_dir(&dest).unwrap();
+        let root = zip_with(dir.path(), &["sub/../../evil.txt", "sub/ok.txt"]);
+        let sub = ArchivePath {
+            inner: PathBuf::from("sub"),
+            ..root
+        };
+
+        let out = extract(&sub, &dest).unwrap();
+
+        assert_eq!(std::fs::read(out.join("ok.txt")).unwrap(), b"data");
+        assert!(!dir.path().join("evil.txt").exists());
+        assert!(!dest.join("evil.txt").exists());
+    }
+
+    #[test]
+    fn test_extract_never_overwrites() {
+        let dir = tempfile::tempdir().unwrap();
+        let root = zip_with(dir.path(), &["ok.txt"]);
+        let entry = ArchivePath {
+            inner: PathBuf::from("ok.txt"),
+            ..root
+        };
+        std::fs::write(dir.path().join("ok.txt"), b"mine").unwrap();
+
+        assert!(extract(&entry, dir.path()).is_err());
+        assert_eq!(std::fs::read(dir.path().join("ok.txt")).unwrap(), b"mine");
+    }
+
+    #[test]
+    fn test_temp_copies_live_in_a_private_directory() {
+        let dir = tempfile::tempdir().unwrap();
+        let root = zip_with(dir.path(), &["ok.txt"]);
+        let entry = ArchivePath {
+            inner: PathBuf::from("ok.txt"),
+            ..root
+        };
+
+        let out = extract_to_temp(&entry, None).unwrap();
+        assert_eq!(std::fs::read(&out).unwrap(), b"data");
+
+        let private = temp_dir().unwrap();
+        assert!(out.starts_with(&private));
+        let mode = std::fs::metadata(&private).unwrap().permissions().mode();
+        assert_eq!(mode & 0o777, 0o700);
+    }
+}
@@ -0,0 +1,276 @@
+//! Tag and cover art editor for audio files. The file handler writes the
+//! changes in place, through a temporary copy renamed over the original.
+
+use crate::app::Message;
+use iced::widget::{button, column, container, row, text, text_input, Space};
+use iced::{Element, Length};
+use rururu_file_handler::media::MediaHandler;
+use rururu_file_handler::{AudioTags, CoverArt, TagEdits, TagField};
+use std::path::{Path, PathBuf};
+
+/// Files the file handler can write tags to
+const TAGGABLE_EXTENSIONS: &[&str] = &[
+    "mp3", "flac", "ogg", "oga", "opus", "m4a", "m4b", "mp4", "wav", "aiff", "aif", "ape",
+];
+
+/// Fields edited as text, in form order; cover art has its own row
+const TEXT_FIELDS: [TagField; 10] = [
+    TagField::Title,
+    TagField::Artist,
+    TagField::Album,
+    TagField::AlbumArtist,
+    TagField::Genre,
+    TagField::Comment,
+    TagField::Year,
+    TagField::Track,
+    TagField::TrackTotal,
+    TagField::Disc,
+];
+
+pub fn is_taggable(path: &Path) -> bool {
+    path.is_file()
+        && path
+            .extension()
+            .and_then(|e| e.to_str())
+            .map(|e| TAGGABLE_EXTENSIONS.contains(&e.to_lowercase().as_str()))
+            .unwrap_or(false)
+}
+
+pub struct AudioTagEditor {
+    pub path: PathBuf,
+    /// `None` while the tags are read
+    pub tags: Option<Result<AudioTags, String>>,
+    /// Form contents, one per [`TEXT_FIELDS`] entry
+    pub values: Vec<String>,
+    /// Image to embed as the front cover on save
+    pub cover_path: String,
+    pub saving: bool,
+    pub status: Option<String>,
+}
+
+impl AudioTagEditor {
+    pub fn new(path: PathBuf) -> Self {
+        Self {
+            path,
+            tags: None,
+            values: vec![String::new(); TEXT_FIELDS.len()],
+            cover_path: String::new(),
+            saving: false,
+            status: None,
+        }
+    }
+
+    pub fn loaded(&mut self, result: Result<AudioTags, String>) {
+        if let Ok(ref tags) = result {
+            self.values = TEXT_FIELDS
+                .iter()
+                .map(|&field| current(&tags.values, field).unwrap_or_default())
+                .collect();
+        }
+        self.tags = Some(result);
+    }
+
+    pub fn set(&mut self, field: TagField, value: String) {
+        if let Some(i) = TEXT_FIELDS.iter().position(|&f| f == field) {
+            self.values[i] = value;
+            self.status = None;
+        }
+    }
+
+    /// The fields the form changed. Clearing a field removes it.
+    pub fn edits(&self) -> Result<TagEdits, String> {
+        let Some(Ok(ref tags)) = self.tags else {
+            return Ok(TagEdits::default());
+        };
+
+        let mut edits = TagEdits::default();
+        for (&field, value) in TEXT_FIELDS.iter().zip(&self.values) {
+            let value = value.trim();
+            if current(&tags.values, field).as_deref().unwrap_or("") == value {
+                continue;
+            }
+
+            let number = || -> Result<Option<u32>, String> {
+                if value.is_empty() {
+                    return Ok(Some(0));
+                }
+                value
+                    .parse()
+                    .map(Some)
+                    .map_err(|_| format!("The {} must be a number", field))
+            };
+            let text = Some(value.to_string());
+            match field {
+                TagField::Title => edits.title = text,
+                TagField::Artist => edits.artist = text,
+                TagField::Album => edits.album = text,
+                TagField::AlbumArtist => edits.album_artist = text,
+                TagField::Genre => edits.genre = text,
+                TagField::Comment => edits.comment = text,
+                TagField::Year => edits.year = number()?,
+                TagField::Track => edits.track = number()?,
+                TagField::TrackTotal => edits.track_total = number()?,
+                TagField::Disc => edits.disc = number()?,
+                TagField::CoverArt => {}
+            }
+        }
+        Ok(edits)
+    }
+
+    pub fn cover(&self) -> Option<PathBuf> {
+        let path = self.cover_path.trim();
+        (!path.is_empty()).then(|| PathBuf::from(path))
+    }
+}
+
+/// A field's value as the form shows it
+fn current(values: &TagEdits, field: TagField) -> Option<String> {
+    match field {
+        TagField::Title => values.title.clone(),
+        TagField::Artist => values.artist.clone(),
+        TagField::Album => values.album.clone(),
+        TagField::AlbumArtist => values.album_artist.clone(),
+        TagField::Genre => values.genre.clone(),
+        TagField::Comment => values.comment.clone(),
+        TagField::Year => values.year.map(|v| v.to_string()),
+        TagField::Track => values.track.map(|v| v.to_string()),
+        TagField::TrackTotal => values.track_total.map(|v| v.to_string()),
+        TagField::Disc => values.disc.map(|v| v.to_string()),
+        TagField::CoverArt => None,
+    }
+}
+
+pub async fn read(path: PathBuf) -> Result<AudioTags, String> {
+    tokio::task::spawn_blocking(move || {
+        MediaHandler::new()
+            .and_then(|h| h.read_audio_tags(&path))
+            .map_err(|e| e.to_string())
+    })
+    .await
+    .map_err(|e| e.to_string())?
+}
+
+/// Write the edits and the cover image, if one was picked, in one go
+pub async fn write(
+    path: PathBuf,
+    mut edits: TagEdits,
+    cover: Option<PathBuf>,
+) -> Result<(), String> {
+    tokio::task::spawn_blocking(move || {
+        if let Some(cover) = cover {
+            let data = std::fs::read(&cover)
+                .map_err(|e| format!("Can't read {}: {}", cover.display(), e))?;
+            let mime = infer::get(&data)
+                .map(|t| t.mime_type())
+                .unwrap_or("application/octet-stream")
+                .to_string();
+            edits.cover_art = Some(CoverArt { data, mime });
+        }
+
+        MediaHandler::new()
+            .and_then(|handler| handler.write_audio_tags(&path, &edits))
+            .map_err(|e| e.to_string())
+    })
+    .await
+    .map_err(|e| e.to_string())?
+}
+
+pub fn view(editor: &AudioTagEditor) -> Element<'_, Message> {
+    let name = editor
+        .path
+        .file_name()
+        .map(|n| n.to_string_lossy().to_string())
+        .unwrap_or_default();
+    let header = row![
+        text(format!("Tags of {}", name)).size(14),
+        Space::with_width(Length::Fill),
+        button(text("✕"))
+            .style(iced::theme::Button::Text)
+            .on_press(Message::CloseAudioTagEditor),
+    ]
+    .spacing(8)
+    .align_items(iced::Alignment::Center);
+
+    let tags = match editor.tags {
+        None => return panel(column![header, text("Reading tags...").size(12)]),
+        Some(Err(ref e)) => {
+            return panel(column![
+                header,
+                text(format!("Can't read tags: {}", e)).size(12)
+            ])
+        }
+        Some(Ok(ref tags)) => tags,
+    };
+
+    let mut form = column![text(format!("Saved as {} tags", tags.format)).size(11)].spacing(4);
+    for (&field, value) in TEXT_FIELDS.iter().zip(&editor.values) {
+        let label = text(capitalized(field.name()))
+            .size(12)
+            .width(Length::Fixed(100.0));
+        let input: Element<Message> = if tags.writable.contains(&field) {
+            text_input("", value)
+                .size(12)
+                .on_input(move |v| Message::AudioTagChanged(field, v))
+                .on_submit(Message::SaveAudioTags)
+                .into()
+        } else {
+            text(format!("Not stored in {} tags", tags.format))
+                .size(11)
+                .into()
+        };
+        form = form.push(
+            row![label, input]
+                .spacing(8)
+                .align_items(iced::Alignment::Center),
+        );
+    }
+
+    if tags.writable.contains(&TagField::CoverArt) {
+        let state = if tags.has_cover_art {
+            "Replace cover"
+        } else {
+            "Add cover"
+        };
+        form = form.push(
+            row![
+                text(state).size(12).width(Length::Fixed(100.0)),
+                text_input("JPEG or PNG image", &editor.cover_path)
+                    .size(12)
+                    .on_input(Message::CoverArtPathChanged)
+                    .on_submit(Message::SaveAudioTags),
+            ]
+            .spacing(8)
+            .align_items(iced::Alignment::Center),
+        );
+    }
+
+    let save = if editor.saving {
+        button(text("Saving...").size(12))
+    } else {
+        button(text("Save").size(12)).on_press(Message::SaveAudioTags)
+    };
+    let mut footer = row![save.style(iced::theme::Button::Primary)]
+        .spacing(8)
+        .align_items(iced::Alignment::Center);
+    if let Some(ref status) = editor.status {
+        footer = footer.push(text(status).size(12));
+    }
+
+    panel(column![header, form, footer])
+}
+
+fn panel(content: iced::widget::Column<'_, Message>) -> Element<'_, Message> {
+    container(content.spacing(8))
+        .width(Length::Fill)
+        .padding(8)
+        .style(iced::theme::Container::Box)
+        .into()
+}
+
+fn capitalized(name: &str) -> String {
+    let mut chars = name.chars();
+    match chars.next() {
+        Some(first) => first.to_uppercase().chain(chars).collect(),
+        None => String::new(),
+    }
+}
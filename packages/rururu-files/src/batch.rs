@@ -1,20 +1,24 @@
 use crate::app::Message;
+use crate::tags::TagDatabase;
 use iced::widget::{
     button, checkbox, column, container, pick_list, progress_bar, row, text, text_input, Space,
 };
 use iced::{Element, Length};
-use std::collections::HashSet;
 use std::path::{Path, PathBuf};
 
 #[derive(Debug, Clone)]
 pub struct BatchOperation {
-    pub selected_files: HashSet<PathBuf>,
+    /// In selection order, so `{n}` in rename patterns follows it
+    pub selected_files: Vec<PathBuf>,
     pub operation: Option<BatchOperationType>,
     pub progress: f32,
     pub is_running: bool,
     pub results: Vec<BatchResult>,
     pub rename_pattern: String,
     pub target_directory: Option<PathBuf>,
+    /// Tag added or removed by [`BatchOperationType::AddTag`] and
+    /// [`BatchOperationType::RemoveTag`]
+    pub tag: String,
 }
 
 #[derive(Debug, Clone, PartialEq, Eq)]
@@ -54,38 +58,46 @@ pub struct BatchResult {
 impl Default for BatchOperation {
     fn default() -> Self {
         Self {
-            selected_files: HashSet::new(),
+            selected_files: Vec::new(),
             operation: None,
             progress: 0.0,
             is_running: false,
             results: Vec::new(),
             rename_pattern: String::from("{name}_{n}"),
             target_directory: None,
+            tag: String::new(),
         }
     }
 }
 
 impl BatchOperation {
     pub fn select_file(&mut self, path: PathBuf) {
-        if self.selected_files.contains(&path) {
-            self.selected_files.remove(&path);
+        if let Some(pos) = self.selected_files.iter().position(|p| p == &path) {
+            self.selected_files.remove(pos);
         } else {
-            self.selected_files.insert(path);
+            self.selected_files.push(path);
         }
     }
 
     pub fn select_all(&mut self, files: &[PathBuf]) {
         for file in files {
-            self.selected_files.insert(file.clone());
+            if !self.selected_files.contains(file) {
+                self.selected_files.push(file.clone());
+            }
         }
     }
 
+    /// Replace the batch targets with the file list's selection
+    pub fn set_selection(&mut self, files: &[PathBuf]) {
+        self.selected_files = files.to_vec();
+    }
+
     pub fn deselect_all(&mut self) {
         self.selected_files.clear();
     }
 
     pub fn is_selected(&self, path: &Path) -> bool {
-        self.selected_files.contains(path)
+        self.selected_files.iter().any(|p| p == path)
     }
 
     pub fn selection_count(&self) -> usize {
@@ -96,13 +108,59 @@ impl BatchOperation {
         self.operation = Some(op);
     }
 
+    pub fn is_tag_operation(&self) -> bool {
+        matches!(
+            self.operation,
+            Some(BatchOperationType::AddTag | BatchOperationType::RemoveTag)
+        )
+    }
+
+    /// Add or remove [`Self::tag`] on every selected file in `db`, then
+    /// save it. Files that already had it, or didn't, are reported as
+    /// unchanged rather than failed.
+    pub fn apply_tags(&mut self, db: &mut TagDatabase) -> Vec<BatchResult> {
+        let tag = self.tag.trim().trim_start_matches('#').to_string();
+        let adding = self.operation == Some(BatchOperationType::AddTag);
+        self.results.clear();
+
+        for file in &self.selected_files {
+            let had_tag = db.get_file_tags(file).iter().any(|t| t.name == tag);
+            let message = match (adding, had_tag) {
+                (true, false) => {
+                    db.add_tag_to_file(file, &tag);
+                    format!("Tagged #{}", tag)
+                }
+                (false, true) => {
+                    db.remove_tag_from_file(file, &tag);
+                    format!("Removed #{}", tag)
+                }
+                (true, true) => format!("Already tagged #{}", tag),
+                (false, false) => format!("Not tagged #{}", tag),
+            };
+            self.results.push(BatchResult {
+                path: file.clone(),
+                success: true,
+                message,
+            });
+        }
+
+        if let Err(e) = db.save() {
+            for result in &mut self.results {
+                result.success = false;
+                result.message = format!("Couldn't save tags: {}", e);
+            }
+        }
+        self.progress = 1.0;
+        self.results.clone()
+    }
+
     pub async fn execute(&mut self) -> Vec<BatchResult> {
         self.is_running = true;
         self.results.clear();
         self.progress = 0.0;
 
         let total = self.selected_files.len();
-        let files: Vec<PathBuf> = self.selected_files.iter().cloned().collect();
+        let files = self.selected_files.clone();
 
         for (i, file) in files.iter().enumerate() {
             let result = match &self.operation {
@@ -111,6 +169,7 @@ impl BatchOperation {
                 Some(BatchOperationType::Delete) => self.delete_file(file).await,
                 Some(BatchOperationType::Rename) => self.rename_file(file, i).await,
                 Some(BatchOperationType::Compress) => self.compress_file(file).await,
+                // Tags live in the app's database; see `apply_tags`
                 _ => BatchResult {
                     path: file.clone(),
                     success: false,
@@ -132,7 +191,7 @@ impl BatchOperation {
         match target_dir {
             Some(dir) => {
                 let dest = dir.join(source.file_name().unwrap_or_default());
-                match tokio::fs::copy(source, &dest).await {
+                match copy_path(source.to_path_buf(), dest.clone()).await {
                     Ok(_) => BatchResult {
                         path: source.to_path_buf(),
                         success: true,
@@ -159,7 +218,7 @@ impl BatchOperation {
         match target_dir {
             Some(dir) => {
                 let dest = dir.join(source.file_name().unwrap_or_default());
-                match tokio::fs::rename(source, &dest).await {
+                match move_path(source.to_path_buf(), dest.clone()).await {
                     Ok(_) => BatchResult {
                         path: source.to_path_buf(),
                         success: true,
@@ -240,6 +299,45 @@ impl BatchOperation {
     }
 }
 
+/// Copy a file, or a folder with everything in it
+async fn copy_path(source: PathBuf, dest: PathBuf) -> std::io::Result<()> {
+    tokio::task::spawn_blocking(move || {
+        if !source.is_dir() {
+            return std::fs::copy(&source, &dest).map(|_| ());
+        }
+        for entry in walkdir::WalkDir::new(&source) {
+            let entry = entry?;
+            let target = dest.join(entry.path().strip_prefix(&source).unwrap_or(entry.path()));
+            if entry.file_type().is_dir() {
+                std::fs::create_dir_all(&target)?;
+            } else if entry.file_type().is_symlink() {
+                std::os::unix::fs::symlink(std::fs::read_link(entry.path())?, &target)?;
+            } else {
+                std::fs::copy(entry.path(), &target)?;
+            }
+        }
+        Ok(())
+    })
+    .await
+    .map_err(std::io::Error::other)?
+}
+
+/// Rename, or copy and remove the original when `dest` is on another
+/// filesystem
+async fn move_path(source: PathBuf, dest: PathBuf) -> std::io::Result<()> {
+    match tokio::fs::rename(&source, &dest).await {
+        Err(e) if e.kind() == std::io::ErrorKind::CrossesDevices => {
+            copy_path(source.clone(), dest).await?;
+            if source.is_dir() {
+                tokio::fs::remove_dir_all(&source).await
+            } else {
+                tokio::fs::remove_file(&source).await
+            }
+        }
+        result => result,
+    }
+}
+
 pub fn view_batch_toolbar<'a>(batch: &'a BatchOperation) -> Element<'a, Message> {
     if batch.selected_files.is_empty() {
         return Space::new(Length::Shrink, Length::Shrink).into();
@@ -268,6 +366,12 @@ pub fn view_batch_toolbar<'a>(batch: &'a BatchOperation) -> Element<'a, Message>
         button(text("Rename"))
             .style(iced::theme::Button::Secondary)
             .on_press(Message::BatchSetOperation(BatchOperationType::Rename)),
+        button(text("Tag"))
+            .style(iced::theme::Button::Secondary)
+            .on_press(Message::BatchSetOperation(BatchOperationType::AddTag)),
+        button(text("Untag"))
+            .style(iced::theme::Button::Secondary)
+            .on_press(Message::BatchSetOperation(BatchOperationType::RemoveTag)),
         Space::with_width(Length::Fill),
         button(text("Deselect All"))
             .style(iced::theme::Button::Text)
@@ -279,7 +383,10 @@ pub fn view_batch_toolbar<'a>(batch: &'a BatchOperation) -> Element<'a, Message>
     .into()
 }
 
-pub fn view_batch_dialog<'a>(batch: &'a BatchOperation) -> Element<'a, Message> {
+pub fn view_batch_dialog<'a>(
+    batch: &'a BatchOperation,
+    tags: &'a TagDatabase,
+) -> Element<'a, Message> {
     let op = match &batch.operation {
         Some(op) => op,
         None => return Space::new(Length::Shrink, Length::Shrink).into(),
@@ -316,6 +423,40 @@ pub fn view_batch_dialog<'a>(batch: &'a BatchOperation) -> Element<'a, Message>
         .spacing(4)
         .into(),
         BatchOperationType::Delete => text("Files will be moved to trash.").size(12).into(),
+        BatchOperationType::AddTag => {
+            let mut known: Vec<String> =
+                tags.get_all_tags().iter().map(|t| t.name.clone()).collect();
+            known.sort();
+            column![
+                text("Tag:").size(12),
+                text_input("New or existing tag", &batch.tag).on_input(Message::BatchTagChanged),
+                pick_list(known, None::<String>, Message::BatchTagChanged)
+                    .placeholder("Existing tags"),
+            ]
+            .spacing(4)
+            .into()
+        }
+        BatchOperationType::RemoveTag => {
+            let mut carried: Vec<String> = batch
+                .selected_files
+                .iter()
+                .flat_map(|file| tags.get_file_tags(file))
+                .map(|t| t.name.clone())
+                .collect();
+            carried.sort();
+            carried.dedup();
+            if carried.is_empty() {
+                text("None of these files are tagged.").size(12).into()
+            } else {
+                let selected = carried.contains(&batch.tag).then(|| batch.tag.clone());
+                column![
+                    text("Tag to remove:").size(12),
+                    pick_list(carried, selected, Message::BatchTagChanged),
+                ]
+                .spacing(4)
+                .into()
+            }
+        }
         _ => Space::new(Length::Shrink, Length::Shrink).into(),
     };
 
@@ -361,7 +502,11 @@ pub fn view_batch_dialog<'a>(batch: &'a BatchOperation) -> Element<'a, Message>
                     .on_press(Message::BatchCancel),
                 button(text("Execute"))
                     .style(iced::theme::Button::Primary)
-                    .on_press(Message::BatchExecute),
+                    .on_press_maybe(
+                        (!(batch.is_running
+                            || (batch.is_tag_operation() && batch.tag.trim().is_empty())))
+                        .then_some(Message::BatchExecute)
+                    ),
             ]
             .spacing(8),
         ]
@@ -0,0 +1,613 @@
+//! MD5, SHA-256 and BLAKE3 checksums of the selection, sidecar and sums
+//! file export, and verification against an existing sums file.
+//!
+//! Sums files use the coreutils layout (`<hex>  <name>`, names relative to
+//! the sums file), so `sha256sum -c` and `b3sum -c` read what this writes
+//! and the other way round. Hashing runs as a subscription keyed on the
+//! job id, like folder listings, so closing the panel stops it.
+
+use crate::app::Message;
+use futures::channel::mpsc::Sender;
+use futures::SinkExt;
+use iced::widget::{
+    button, column, container, pick_list, progress_bar, row, scrollable, text, Space,
+};
+use iced::{Element, Length, Subscription};
+use sha2::Digest;
+use std::fmt;
+use std::fs::File;
+use std::io::{self, Read};
+use std::path::{Component, Path, PathBuf};
+
+const READ_CHUNK: usize = 1024 * 1024;
+
+/// Files to hash, with the digest a sums file expects of each
+type Files = Vec<(PathBuf, Option<String>)>;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum HashAlgorithm {
+    Md5,
+    #[default]
+    Sha256,
+    Blake3,
+}
+
+impl HashAlgorithm {
+    pub const ALL: [HashAlgorithm; 3] = [
+        HashAlgorithm::Md5,
+        HashAlgorithm::Sha256,
+        HashAlgorithm::Blake3,
+    ];
+
+    /// Extension of per-file sidecars, after the file's own name
+    pub fn extension(self) -> &'static str {
+        match self {
+            HashAlgorithm::Md5 => "md5",
+            HashAlgorithm::Sha256 => "sha256",
+            HashAlgorithm::Blake3 => "b3",
+        }
+    }
+
+    /// Name of the combined sums file
+    pub fn sums_file(self) -> &'static str {
+        match self {
+            HashAlgorithm::Md5 => "MD5SUMS",
+            HashAlgorithm::Sha256 => "SHA256SUMS",
+            HashAlgorithm::Blake3 => "B3SUMS",
+        }
+    }
+
+    /// The algorithm a sums file or sidecar is for, going by its name
+    pub fn for_sums_file(path: &Path) -> Option<Self> {
+        let name = path.file_name()?.to_str()?;
+        Self::ALL.into_iter().find(|algorithm| {
+            name == algorithm.sums_file()
+                || path.extension().and_then(|e| e.to_str()) == Some(algorithm.extension())
+        })
+    }
+
+    /// Length of the hex digest
+    fn digest_len(self) -> usize {
+        match self {
+            HashAlgorithm::Md5 => 32,
+            HashAlgorithm::Sha256 | HashAlgorithm::Blake3 => 64,
+        }
+    }
+
+    fn hasher(self) -> Hasher {
+        match self {
+            HashAlgorithm::Md5 => Hasher::Md5(md5::Md5::new()),
+            HashAlgorithm::Sha256 => Hasher::Sha256(sha2::Sha256::new()),
+            HashAlgorithm::Blake3 => Hasher::Blake3(Box::new(blake3::Hasher::new())),
+        }
+    }
+}
+
+impl fmt::Display for HashAlgorithm {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(match self {
+            HashAlgorithm::Md5 => "MD5",
+            HashAlgorithm::Sha256 => "SHA-256",
+            HashAlgorithm::Blake3 => "BLAKE3",
+        })
+    }
+}
+
+enum Hasher {
+    Md5(md5::Md5),
+    Sha256(sha2::Sha256),
+    Blake3(Box<blake3::Hasher>),
+}
+
+impl Hasher {
+    fn update(&mut self, data: &[u8]) {
+        match self {
+            Hasher::Md5(h) => h.update(data),
+            Hasher::Sha256(h) => h.update(data),
+            Hasher::Blake3(h) => {
+                h.update(data);
+            }
+        }
+    }
+
+    fn finish(self) -> String {
+        let hex = |bytes: &[u8]| bytes.iter().map(|b| format!("{:02x}", b)).collect();
+        match self {
+            Hasher::Md5(h) => hex(&h.finalize()),
+            Hasher::Sha256(h) => hex(&h.finalize()),
+            Hasher::Blake3(h) => h.finalize().to_hex().to_string(),
+        }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum ChecksumTask {
+    Compute(Vec<PathBuf>),
+    /// Check the files a sums file lists
+    Verify(PathBuf),
+}
+
+#[derive(Debug, Clone)]
+pub struct ChecksumResult {
+    pub path: PathBuf,
+    /// Hex digest, or why the file couldn't be read
+    pub digest: Result<String, String>,
+    /// What the sums file says, when verifying
+    pub expected: Option<String>,
+}
+
+impl ChecksumResult {
+    pub fn verified(&self) -> bool {
+        matches!((&self.digest, &self.expected), (Ok(digest), Some(expected)) if digest.eq_ignore_ascii_case(expected))
+    }
+}
+
+#[derive(Debug, Clone)]
+pub enum ChecksumEvent {
+    Progress { done: u64, total: u64 },
+    Hashed(ChecksumResult),
+    Finished,
+    Failed(String),
+}
+
+/// The checksum panel: what's being hashed and what came out so far
+#[derive(Debug, Clone)]
+pub struct Checksums {
+    pub id: u64,
+    pub algorithm: HashAlgorithm,
+    pub task: ChecksumTask,
+    pub running: bool,
+    pub done: u64,
+    pub total: u64,
+    pub results: Vec<ChecksumResult>,
+    pub status: Option<String>,
+}
+
+impl Checksums {
+    pub fn new(id: u64, algorithm: HashAlgorithm, task: ChecksumTask) -> Self {
+        Self {
+            id,
+            algorithm,
+            task,
+            running: false,
+            done: 0,
+            total: 0,
+            results: Vec::new(),
+            status: None,
+        }
+    }
+
+    /// Clear earlier results and hash again, under a new id so messages
+    /// still queued from the last run are ignored
+    pub fn start(&mut self, id: u64) {
+        self.id = id;
+        self.running = true;
+        self.done = 0;
+        self.total = 0;
+        self.results.clear();
+        self.status = None;
+    }
+
+    pub fn apply(&mut self, event: ChecksumEvent) {
+        match event {
+            ChecksumEvent::Progress { done, total } => {
+                self.done = done;
+                self.total = total;
+            }
+            ChecksumEvent::Hashed(result) => self.results.push(result),
+            ChecksumEvent::Finished => {
+                self.running = false;
+                if let ChecksumTask::Verify(_) = self.task {
+                    let failed = self.results.iter().filter(|r| !r.verified()).count();
+                    self.status = Some(if failed == 0 {
+                        format!("All {} files OK", self.results.len())
+                    } else {
+                        format!("{} of {} files failed", failed, self.results.len())
+                    });
+                }
+            }
+            ChecksumEvent::Failed(e) => {
+                self.running = false;
+                self.status = Some(e);
+            }
+        }
+    }
+
+    /// Digests ready to write out, in selection order
+    fn digests(&self) -> Vec<(PathBuf, String)> {
+        self.results
+            .iter()
+            .filter_map(|r| Some((r.path.clone(), r.digest.clone().ok()?)))
+            .collect()
+    }
+}
+
+/// Subscriptions are told apart by the type of their id as well as its
+/// value, so a type of its own keeps a job from colliding with a listing
+/// that happens to have the same number
+#[derive(Hash)]
+struct JobId(u64);
+
+pub fn subscription(job: &Checksums) -> Subscription<Message> {
+    let (id, algorithm, task) = (job.id, job.algorithm, job.task.clone());
+    iced::subscription::channel(JobId(id), 16, move |mut output| async move {
+        let sender = output.clone();
+        let result = tokio::task::spawn_blocking(move || run(algorithm, task, id, sender))
+            .await
+            .map_err(io::Error::other)
+            .and_then(|r| r);
+        let event = match result {
+            Ok(()) => ChecksumEvent::Finished,
+            Err(e) => ChecksumEvent::Failed(e.to_string()),
+        };
+        let _ = output.send(Message::ChecksumProgress(id, event)).await;
+        // Finished; the app drops the subscription once it sees that
+        std::future::pending().await
+    })
+}
+
+fn run(
+    algorithm: HashAlgorithm,
+    task: ChecksumTask,
+    id: u64,
+    mut output: Sender<Message>,
+) -> io::Result<()> {
+    let (algorithm, files) = match task {
+        ChecksumTask::Compute(paths) => (
+            algorithm,
+            paths.into_iter().map(|path| (path, None)).collect(),
+        ),
+        ChecksumTask::Verify(sums) => read_sums_file(&sums)?,
+    };
+
+    let total = files
+        .iter()
+        .filter_map(|(path, _)| std::fs::metadata(path).ok())
+        .map(|m| m.len())
+        .sum();
+    let mut done = 0;
+
+    for (path, expected) in files {
+        let digest = hash_file(&path, algorithm, |read| {
+            // The panel was closed; don't read the rest of a large file
+            if output.is_closed() {
+                return Err(io::ErrorKind::Interrupted.into());
+            }
+            done += read;
+            // Progress may be dropped when the app is behind; results not
+            let _ = output.try_send(Message::ChecksumProgress(
+                id,
+                ChecksumEvent::Progress { done, total },
+            ));
+            Ok(())
+        });
+        let digest = match digest {
+            Err(e) if e.kind() == io::ErrorKind::Interrupted => return Ok(()),
+            digest => digest.map_err(|e| e.to_string()),
+        };
+
+        let result = ChecksumResult {
+            path,
+            digest,
+            expected,
+        };
+        let sent = futures::executor::block_on(
+            output.send(Message::ChecksumProgress(id, ChecksumEvent::Hashed(result))),
+        );
+        if sent.is_err() {
+            // The panel was closed
+            return Ok(());
+        }
+    }
+
+    Ok(())
+}
+
+/// Digest of the file at `path`. `progress` is told the bytes read after
+/// each chunk, and stops hashing by returning an error.
+fn hash_file(
+    path: &Path,
+    algorithm: HashAlgorithm,
+    mut progress: impl FnMut(u64) -> io::Result<()>,
+) -> io::Result<String> {
+    let mut file = File::open(path)?;
+    let mut hasher = algorithm.hasher();
+    let mut buf = vec![0u8; READ_CHUNK];
+    loop {
+        let n = file.read(&mut buf)?;
+        if n == 0 {
+            return Ok(hasher.finish());
+        }
+        hasher.update(&buf[..n]);
+        progress(n as u64)?;
+    }
+}
+
+/// The files `sums` lists with their expected digests. The algorithm comes
+/// from the file's name, or the digest length when the name doesn't say;
+/// digests of another length, and names outside the sums file's folder,
+/// are rejected.
+fn read_sums_file(sums: &Path) -> io::Result<(HashAlgorithm, Files)> {
+    let content = std::fs::read_to_string(sums)?;
+    let dir = sums.parent().unwrap_or(Path::new("."));
+
+    let mut files = Vec::new();
+    for line in content.lines().map(str::trim_end) {
+        if line.is_empty() || line.starts_with('#') {
+            continue;
+        }
+        // `<hex>  <name>`, or `<hex> *<name>` for binary mode
+        let Some((digest, name)) = line.split_once(' ') else {
+            return Err(invalid_line(line));
+        };
+        let name = name.strip_prefix([' ', '*']).unwrap_or(name);
+        if name.is_empty() || !digest.chars().all(|c| c.is_ascii_hexdigit()) {
+            return Err(invalid_line(line));
+        }
+        let inside = Path::new(name)
+            .components()
+            .all(|c| matches!(c, Component::Normal(_) | Component::CurDir));
+        if !inside {
+            return Err(io::Error::new(
+                io::ErrorKind::InvalidData,
+                format!("{} is outside the folder of the sums file", name),
+            ));
+        }
+        files.push((dir.join(name), digest.to_lowercase()));
+    }
+
+    let algorithm = match HashAlgorithm::for_sums_file(sums) {
+        Some(algorithm) => algorithm,
+        None => match files.first().map(|(_, digest)| digest.len()) {
+            Some(32) => HashAlgorithm::Md5,
+            // SHA-256 and BLAKE3 digests are both 64 digits long
+            Some(64) => {
+                return Err(io::Error::new(
+                    io::ErrorKind::InvalidData,
+                    "Can't tell SHA-256 from BLAKE3; name the file SHA256SUMS or B3SUMS",
+                ))
+            }
+            Some(_) => return Err(invalid_line(&files[0].1)),
+            None => HashAlgorithm::default(),
+        },
+    };
+    if let Some((_, digest)) = files
+        .iter()
+        .find(|(_, d)| d.len() != algorithm.digest_len())
+    {
+        return Err(io::Error::new(
+            io::ErrorKind::InvalidData,
+            format!("Not a {} digest: {}", algorithm, digest),
+        ));
+    }
+
+    let files = files
+        .into_iter()
+        .map(|(path, digest)| (path, Some(digest)))
+        .collect();
+    Ok((algorithm, files))
+}
+
+fn invalid_line(line: &str) -> io::Error {
+    io::Error::new(
+        io::ErrorKind::InvalidData,
+        format!("Not a checksum line: {}", line),
+    )
+}
+
+fn sums_line(digest: &str, name: &Path) -> String {
+    format!("{}  {}\n", digest, name.display())
+}
+
+/// Write `<file>.<ext>` next to each hashed file
+pub async fn write_sidecars(job: Checksums) -> Result<Vec<PathBuf>, String> {
+    let extension = job.algorithm.extension();
+    let digests = job.digests();
+    tokio::task::spawn_blocking(move || {
+        digests
+            .into_iter()
+            .map(|(path, digest)| {
+                let mut sidecar = path.clone().into_os_string();
+                sidecar.push(".");
+                sidecar.push(extension);
+                let sidecar = PathBuf::from(sidecar);
+                let name = path.file_name().map(Path::new).unwrap_or(&path);
+                std::fs::write(&sidecar, sums_line(&digest, name))?;
+                Ok(sidecar)
+            })
+            .collect::<io::Result<Vec<_>>>()
+    })
+    .await
+    .map_err(|e| e.to_string())?
+    .map_err(|e| e.to_string())
+}
+
+/// Write one sums file into `dir` listing every hashed file
+pub async fn write_sums_file(job: Checksums, dir: PathBuf) -> Result<Vec<PathBuf>, String> {
+    let path = dir.join(job.algorithm.sums_file());
+    let content: String = job
+        .digests()
+        .iter()
+        .map(|(file, digest)| sums_line(digest, file.strip_prefix(&dir).unwrap_or(file)))
+        .collect();
+    tokio::fs::write(&path, content)
+        .await
+        .map_err(|e| e.to_string())?;
+    Ok(vec![path])
+}
+
+pub fn view(job: &Checksums) -> Element<'_, Message> {
+    let title = match job.task {
+        ChecksumTask::Compute(_) => format!("{} checksums", job.algorithm),
+        ChecksumTask::Verify(ref sums) => format!(
+            "Verifying {}",
+            sums.file_name()
+                .map(|n| n.to_string_lossy())
+                .unwrap_or_default()
+        ),
+    };
+
+    let mut header = row![text(title).size(14), Space::with_width(Length::Fill)]
+        .spacing(8)
+        .align_items(iced::Alignment::Center);
+    if let ChecksumTask::Compute(_) = job.task {
+        let algorithm = pick_list(
+            &HashAlgorithm::ALL[..],
+            Some(job.algorithm),
+            Message::ChecksumAlgorithmChanged,
+        )
+        .text_size(12);
+        header = header.push(algorithm);
+    }
+    header = header.push(
+        button(text("✕"))
+            .style(iced::theme::Button::Text)
+            .on_press(Message::CloseChecksums),
+    );
+
+    let mut content = column![header].spacing(8);
+    if job.running {
+        let fraction = if job.total > 0 {
+            job.done as f32 / job.total as f32
+        } else {
+            0.0
+        };
+        content = content.push(progress_bar(0.0..=1.0, fraction).height(Length::Fixed(6.0)));
+    }
+
+    let mut entries = column![].spacing(2);
+    for result in &job.results {
+        let name = result
+            .path
+            .file_name()
+            .map(|n| n.to_string_lossy().to_string())
+            .unwrap_or_default();
+        let line = match (&result.digest, &result.expected) {
+            (Ok(_), Some(_)) if result.verified() => format!("OK      {}", name),
+            (Ok(_), Some(_)) => format!("FAILED  {}", name),
+            (Ok(digest), None) => format!("{}  {}", digest, name),
+            (Err(e), _) => format!("ERROR   {}: {}", name, e),
+        };
+        entries = entries.push(text(line).font(iced::Font::MONOSPACE).size(11));
+    }
+    content = content.push(scrollable(entries).height(Length::Fixed(140.0)));
+
+    let mut footer = row![].spacing(8).align_items(iced::Alignment::Center);
+    let computed = matches!(job.task, ChecksumTask::Compute(_)) && !job.running;
+    if computed && job.results.iter().any(|r| r.digest.is_ok()) {
+        footer = footer
+            .push(
+                button(text(format!("Write .{} files", job.algorithm.extension())).size(12))
+                    .style(iced::theme::Button::Secondary)
+                    .on_press(Message::WriteChecksumSidecars),
+            )
+            .push(
+                button(text(format!("Write {}", job.algorithm.sums_file())).size(12))
+                    .style(iced::theme::Button::Secondary)
+                    .on_press(Message::WriteChecksumSums),
+            );
+    }
+    if let Some(ref status) = job.status {
+        footer = footer.push(text(status).size(12));
+    }
+    content = content.push(footer);
+
+    container(content)
+        .width(Length::Fill)
+        .padding(8)
+        .style(iced::theme::Container::Box)
+        .into()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const MD5: &str = "d41d8cd98f00b204e9800998ecf8427e";
+    const SHA256: &str = "e3b0c44298fc1c149afbf4c8996fb92427ae41e4649b934ca495991b7852b855";
+
+    fn sums_file(name: &str, content: &str) -> (tempfile::TempDir, PathBuf) {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join(name);
+        std::fs::write(&path, content).unwrap();
+        (dir, path)
+    }
+
+    #[test]
+    fn test_sums_files_are_recognized_by_name() {
+        let cases = [
+            ("MD5SUMS", Some(HashAlgorithm::Md5)),
+            ("SHA256SUMS", Some(HashAlgorithm::Sha256)),
+            ("B3SUMS", Some(HashAlgorithm::Blake3)),
+            ("photo.jpg.md5", Some(HashAlgorithm::Md5)),
+            ("photo.jpg.sha256", Some(HashAlgorithm::Sha256)),
+            ("photo.jpg.b3", Some(HashAlgorithm::Blake3)),
+            ("photo.jpg", None),
+            ("sums.txt", None),
+        ];
+        for (name, expected) in cases {
+            assert_eq!(
+                HashAlgorithm::for_sums_file(Path::new(name)),
+                expected,
+                "{}",
+                name
+            );
+        }
+    }
+
+    #[test]
+    fn test_read_sums_file_lists_text_and_binary_mode_lines() {
+        let content = format!("# comment\n{}  a.txt\n{} *sub/b.bin\n\n", SHA256, SHA256);
+        let (dir, path) = sums_file("SHA256SUMS", &content);
+
+        let (algorithm, files) = read_sums_file(&path).unwrap();
+
+        assert_eq!(algorithm, HashAlgorithm::Sha256);
+        assert_eq!(
+            files,
+            [
+                (dir.path().join("a.txt"), Some(SHA256.to_string())),
+                (dir.path().join("sub/b.bin"), Some(SHA256.to_string())),
+            ]
+        );
+    }
+
+    #[test]
+    fn test_read_sums_file_infers_md5_from_digest_length() {
+        let (_dir, path) = sums_file("checksums.txt", &format!("{}  a.txt\n", MD5));
+        assert_eq!(read_sums_file(&path).unwrap().0, HashAlgorithm::Md5);
+    }
+
+    #[test]
+    fn test_read_sums_file_rejects_ambiguous_64_digit_digests() {
+        let (_dir, path) = sums_file("checksums.txt", &format!("{}  a.txt\n", SHA256));
+        assert!(read_sums_file(&path).is_err());
+    }
+
+    #[test]
+    fn test_read_sums_file_rejects_digests_of_the_wrong_length() {
+        let (_dir, path) = sums_file("SHA256SUMS", &format!("{}  a.txt\n", MD5));
+        assert!(read_sums_file(&path).is_err());
+
+        let mixed = format!("{}  a.txt\n{}  b.txt\n", MD5, SHA256);
+        let (_dir, path) = sums_file("checksums.txt", &mixed);
+        assert!(read_sums_file(&path).is_err());
+    }
+
+    #[test]
+    fn test_read_sums_file_rejects_names_outside_its_folder() {
+        for name in ["../secret", "/etc/passwd", "sub/../../secret"] {
+            let (_dir, path) = sums_file("MD5SUMS", &format!("{}  {}\n", MD5, name));
+            assert!(read_sums_file(&path).is_err(), "{}", name);
+        }
+    }
+
+    #[test]
+    fn test_read_sums_file_rejects_malformed_lines() {
+        let no_name = format!("{}  ", MD5);
+        for line in ["not-a-checksum", "zzzz  a.txt", no_name.as_str()] {
+            let (_dir, path) = sums_file("MD5SUMS", &format!("{}\n", line));
+            assert!(read_sums_file(&path).is_err(), "{}", line);
+        }
+    }
+}
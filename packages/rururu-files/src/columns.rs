@@ -0,0 +1,380 @@
+//! Columns of the List view.
+//!
+//! Which columns are shown, their order and their widths are saved to
+//! `~/.config/rururu-files/columns.toml`. Name always comes first and can't
+//! be hidden. Dimensions and Duration have to open the file, so they are
+//! only read for the rows in view and cached by path and modification time.
+
+use crate::app::Message;
+use crate::tags::{FileMetadata, TagDatabase};
+use iced::widget::scrollable::Viewport;
+use iced::widget::{button, checkbox, column, container, row, text, Space};
+use iced::{Element, Length};
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+use std::ops::Range;
+use std::path::{Path, PathBuf};
+use std::time::SystemTime;
+use tracing::warn;
+
+pub const MIN_WIDTH: f32 = 48.0;
+pub const MAX_WIDTH: f32 = 640.0;
+/// How much one press of − or + changes a width
+pub const RESIZE_STEP: f32 = 20.0;
+
+/// Height of a List view row and the gap below it, so the rows in view can
+/// be worked out from the scroll position
+pub const ROW_HEIGHT: f32 = 34.0;
+pub const ROW_SPACING: f32 = 2.0;
+/// Rows either side of the view whose details are read ahead of scrolling
+const OVERSCAN: usize = 10;
+/// Rows assumed in view before the list has been scrolled
+const INITIAL_ROWS: usize = 40;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Serialize, Deserialize)]
+pub enum Column {
+    Name,
+    Size,
+    Modified,
+    Type,
+    Tags,
+    Dimensions,
+    Duration,
+}
+
+impl Column {
+    pub const ALL: [Column; 7] = [
+        Column::Name,
+        Column::Size,
+        Column::Modified,
+        Column::Type,
+        Column::Tags,
+        Column::Dimensions,
+        Column::Duration,
+    ];
+
+    pub fn default_width(self) -> f32 {
+        match self {
+            Column::Name => 280.0,
+            Column::Size => 90.0,
+            Column::Modified => 140.0,
+            Column::Type => 80.0,
+            Column::Tags => 140.0,
+            Column::Dimensions => 100.0,
+            Column::Duration => 80.0,
+        }
+    }
+
+    /// Read from the file itself rather than the listing
+    pub fn reads_media(self) -> bool {
+        matches!(self, Column::Dimensions | Column::Duration)
+    }
+}
+
+impl std::fmt::Display for Column {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        let label = match self {
+            Column::Name => "Name",
+            Column::Size => "Size",
+            Column::Modified => "Modified",
+            Column::Type => "Type",
+            Column::Tags => "Tags",
+            Column::Dimensions => "Dimensions",
+            Column::Duration => "Duration",
+        };
+        write!(f, "{}", label)
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
+pub struct ColumnWidth {
+    pub column: Column,
+    pub width: f32,
+}
+
+/// Shown columns, left to right, saved to
+/// `~/.config/rururu-files/columns.toml`
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct ColumnSettings {
+    pub columns: Vec<ColumnWidth>,
+}
+
+impl Default for ColumnSettings {
+    fn default() -> Self {
+        Self {
+            columns: [Column::Name, Column::Size, Column::Modified, Column::Type]
+                .into_iter()
+                .map(|column| ColumnWidth {
+                    column,
+                    width: column.default_width(),
+                })
+                .collect(),
+        }
+    }
+}
+
+impl ColumnSettings {
+    pub fn config_path() -> PathBuf {
+        dirs::config_dir()
+            .unwrap_or_else(|| PathBuf::from("."))
+            .join("rururu-files")
+            .join("columns.toml")
+    }
+
+    pub fn load() -> Self {
+        let path = Self::config_path();
+        let Ok(content) = std::fs::read_to_string(&path) else {
+            return Self::default();
+        };
+
+        match toml::from_str::<Self>(&content) {
+            Ok(mut settings) => {
+                settings.normalize();
+                settings
+            }
+            Err(e) => {
+                warn!("Ignoring invalid {:?}: {}", path, e);
+                Self::default()
+            }
+        }
+    }
+
+    pub fn save(&self) -> std::io::Result<()> {
+        let path = Self::config_path();
+        if let Some(parent) = path.parent() {
+            std::fs::create_dir_all(parent)?;
+        }
+        let content = toml::to_string(self).map_err(std::io::Error::other)?;
+        std::fs::write(path, content)
+    }
+
+    /// Drop repeated columns, keep widths in range and put Name first, so
+    /// a hand-edited file can't hide it
+    fn normalize(&mut self) {
+        let mut seen = Vec::new();
+        self.columns.retain(|c| {
+            let first = !seen.contains(&c.column);
+            seen.push(c.column);
+            first
+        });
+        for c in &mut self.columns {
+            c.width = c.width.clamp(MIN_WIDTH, MAX_WIDTH);
+        }
+        match self.columns.iter().position(|c| c.column == Column::Name) {
+            Some(0) => {}
+            Some(i) => {
+                let name = self.columns.remove(i);
+                self.columns.insert(0, name);
+            }
+            None => self.columns.insert(
+                0,
+                ColumnWidth {
+                    column: Column::Name,
+                    width: Column::Name.default_width(),
+                },
+            ),
+        }
+    }
+
+    pub fn is_shown(&self, column: Column) -> bool {
+        self.columns.iter().any(|c| c.column == column)
+    }
+
+    /// Whether any shown column has to open the files
+    pub fn reads_media(&self) -> bool {
+        self.columns.iter().any(|c| c.column.reads_media())
+    }
+
+    /// Show a hidden column at the right, or hide a shown one
+    pub fn toggle(&mut self, column: Column) {
+        if column == Column::Name {
+            return;
+        }
+        if self.is_shown(column) {
+            self.columns.retain(|c| c.column != column);
+        } else {
+            self.columns.push(ColumnWidth {
+                column,
+                width: column.default_width(),
+            });
+        }
+    }
+
+    /// Move a shown column `offset` places to the right, or left when
+    /// negative, without passing Name
+    pub fn shift(&mut self, column: Column, offset: i32) {
+        let Some(from) = self.columns.iter().position(|c| c.column == column) else {
+            return;
+        };
+        if from == 0 {
+            return;
+        }
+        let to = (from as i64 + offset as i64).clamp(1, self.columns.len() as i64 - 1) as usize;
+        let moved = self.columns.remove(from);
+        self.columns.insert(to, moved);
+    }
+
+    pub fn resize(&mut self, column: Column, delta: f32) {
+        if let Some(c) = self.columns.iter_mut().find(|c| c.column == column) {
+            c.width = (c.width + delta).clamp(MIN_WIDTH, MAX_WIDTH);
+        }
+    }
+}
+
+/// Dimensions and duration of a file, as far as they apply to it
+#[derive(Debug, Clone, Copy, PartialEq, Default)]
+pub struct MediaDetails {
+    /// Modification time of the file when they were read
+    pub modified: Option<SystemTime>,
+    pub dimensions: Option<(u32, u32)>,
+    /// Seconds
+    pub duration: Option<f64>,
+}
+
+impl MediaDetails {
+    /// Whether these were read from the file as it is now
+    pub fn is_current(&self, modified: Option<SystemTime>) -> bool {
+        self.modified == modified
+    }
+}
+
+/// Read the details of `files`, each given with its modification time,
+/// off the UI thread. Files that can't be opened get empty details, so they
+/// aren't asked for again.
+pub async fn load_media_details(
+    files: Vec<(PathBuf, Option<SystemTime>)>,
+) -> Vec<(PathBuf, MediaDetails)> {
+    tokio::task::spawn_blocking(move || {
+        files
+            .into_iter()
+            .map(|(path, modified)| {
+                let details = read_media_details(&path, modified);
+                (path, details)
+            })
+            .collect()
+    })
+    .await
+    .unwrap_or_default()
+}
+
+fn read_media_details(path: &Path, modified: Option<SystemTime>) -> MediaDetails {
+    let mut details = MediaDetails {
+        modified,
+        ..MediaDetails::default()
+    };
+    if let Ok(mut metadata) = FileMetadata::from_path(path) {
+        metadata.read_media();
+        details.dimensions = metadata.dimensions;
+        details.duration = metadata.duration;
+    }
+    details
+}
+
+/// Rows of a `len`-row list in view at `viewport`, with some either side.
+/// The stored scroll position can be past the end of a shorter listing
+/// until it is scrolled again, so the range is kept inside the list.
+pub fn visible_rows(viewport: Option<Viewport>, len: usize) -> Range<usize> {
+    let stride = ROW_HEIGHT + ROW_SPACING;
+    let (first, count) = match viewport {
+        Some(viewport) => (
+            (viewport.absolute_offset().y / stride) as usize,
+            (viewport.bounds().height / stride).ceil() as usize + 1,
+        ),
+        None => (0, INITIAL_ROWS),
+    };
+    let end = (first + count + OVERSCAN).min(len);
+    let start = first
+        .saturating_sub(OVERSCAN)
+        .min(len.saturating_sub(count + OVERSCAN));
+    start..end
+}
+
+/// What the List view shows besides the listing itself
+#[derive(Clone, Copy)]
+pub struct ColumnData<'a> {
+    pub settings: &'a ColumnSettings,
+    pub details: &'a HashMap<PathBuf, MediaDetails>,
+    pub tags: &'a TagDatabase,
+    /// Show the column editor above the rows
+    pub editing: bool,
+}
+
+pub fn format_duration(seconds: f64) -> String {
+    let total = seconds.round() as u64;
+    let (hours, mins, secs) = (total / 3600, total / 60 % 60, total % 60);
+    if hours > 0 {
+        format!("{}:{:02}:{:02}", hours, mins, secs)
+    } else {
+        format!("{}:{:02}", mins, secs)
+    }
+}
+
+/// Every column with a checkbox to show it, and for shown columns buttons
+/// to move and resize them; shown columns are listed in their order
+pub fn view_editor(settings: &ColumnSettings) -> Element<'_, Message> {
+    let hidden = Column::ALL
+        .into_iter()
+        .filter(|&c| !settings.is_shown(c))
+        .map(|column| (column, None));
+    let entries = settings
+        .columns
+        .iter()
+        .map(|c| (c.column, Some(c.width)))
+        .chain(hidden);
+
+    let last = settings.columns.len() - 1;
+    let rows: Vec<Element<Message>> = entries
+        .enumerate()
+        .map(|(i, (column, width))| {
+            let mut toggle = checkbox(column.to_string(), width.is_some()).text_size(13);
+            if column != Column::Name {
+                toggle = toggle.on_toggle(move |_| Message::ToggleColumn(column));
+            }
+
+            let controls: Element<Message> = match width {
+                Some(width) => {
+                    let movable = column != Column::Name;
+                    let small = |label| button(text(label).size(12)).padding([2, 8]);
+                    row![
+                        small("◀").on_press_maybe(
+                            (movable && i > 1).then_some(Message::MoveColumn(column, -1))
+                        ),
+                        small("▶").on_press_maybe(
+                            (movable && i < last).then_some(Message::MoveColumn(column, 1))
+                        ),
+                        Space::with_width(Length::Fixed(8.0)),
+                        small("−").on_press_maybe(
+                            (width > MIN_WIDTH)
+                                .then_some(Message::ResizeColumn(column, -RESIZE_STEP))
+                        ),
+                        text(format!("{:.0} px", width))
+                            .size(12)
+                            .width(Length::Fixed(52.0)),
+                        small("+").on_press_maybe(
+                            (width < MAX_WIDTH)
+                                .then_some(Message::ResizeColumn(column, RESIZE_STEP))
+                        ),
+                    ]
+                    .spacing(4)
+                    .align_items(iced::Alignment::Center)
+                    .into()
+                }
+                None => Space::with_width(Length::Shrink).into(),
+            };
+
+            row![container(toggle).width(Length::Fixed(120.0)), controls]
+                .spacing(8)
+                .align_items(iced::Alignment::Center)
+                .into()
+        })
+        .collect();
+
+    container(
+        column![text("Columns").size(13), column(rows).spacing(4)]
+            .spacing(6)
+            .padding(8),
+    )
+    .style(iced::theme::Container::Box)
+    .into()
+}
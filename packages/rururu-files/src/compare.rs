@@ -0,0 +1,261 @@
+//! Comparing two selected files: whether their bytes match, how their size
+//! and modification time differ, and for images how far the pixels are
+//! apart.
+
+use crate::app::Message;
+use humansize::{format_size, BINARY};
+use iced::widget::{button, column, container, row, text, Space};
+use iced::{Element, Length};
+use std::fs::File;
+use std::io::{self, BufReader, Read};
+use std::path::{Path, PathBuf};
+use std::time::SystemTime;
+
+#[derive(Debug, Clone)]
+pub struct FileStats {
+    pub path: PathBuf,
+    pub size: u64,
+    pub modified: Option<SystemTime>,
+}
+
+#[derive(Debug, Clone)]
+pub struct ImageDiff {
+    pub a_dimensions: (u32, u32),
+    pub b_dimensions: (u32, u32),
+    /// Root mean square difference over all RGB channels, 0 (same pixels)
+    /// to 255; only computed when the dimensions match
+    pub rms: Option<f64>,
+}
+
+impl ImageDiff {
+    pub fn dimensions_match(&self) -> bool {
+        self.a_dimensions == self.b_dimensions
+    }
+}
+
+#[derive(Debug, Clone)]
+pub struct Comparison {
+    pub a: FileStats,
+    pub b: FileStats,
+    /// Same size and same content hash
+    pub identical: bool,
+    /// Present when both files decode as images
+    pub image: Option<ImageDiff>,
+}
+
+/// Compare `a` with `b` on a blocking thread
+pub async fn compare(a: PathBuf, b: PathBuf) -> Result<Comparison, String> {
+    tokio::task::spawn_blocking(move || compare_files(&a, &b).map_err(|e| e.to_string()))
+        .await
+        .map_err(|e| e.to_string())?
+}
+
+fn compare_files(a: &Path, b: &Path) -> io::Result<Comparison> {
+    let a = stats(a)?;
+    let b = stats(b)?;
+
+    // Files of different sizes can't match, so skip reading them
+    let identical = a.size == b.size && same_contents(&a.path, &b.path)?;
+    let image = if identical {
+        None
+    } else {
+        image_diff(&a.path, &b.path)
+    };
+
+    Ok(Comparison {
+        a,
+        b,
+        identical,
+        image,
+    })
+}
+
+fn stats(path: &Path) -> io::Result<FileStats> {
+    let metadata = std::fs::metadata(path)?;
+    Ok(FileStats {
+        path: path.to_path_buf(),
+        size: metadata.len(),
+        modified: metadata.modified().ok(),
+    })
+}
+
+/// Read both files side by side, stopping at the first chunk that differs
+fn same_contents(a: &Path, b: &Path) -> io::Result<bool> {
+    let mut a = BufReader::new(File::open(a)?);
+    let mut b = BufReader::new(File::open(b)?);
+    let mut buf_a = vec![0u8; 64 * 1024];
+    let mut buf_b = vec![0u8; 64 * 1024];
+    loop {
+        let n = fill(&mut a, &mut buf_a)?;
+        if n != fill(&mut b, &mut buf_b)? || buf_a[..n] != buf_b[..n] {
+            return Ok(false);
+        }
+        if n == 0 {
+            return Ok(true);
+        }
+    }
+}
+
+/// Read until `buf` is full or the reader ends, so both files are compared
+/// in chunks of the same length
+fn fill(reader: &mut impl Read, buf: &mut [u8]) -> io::Result<usize> {
+    let mut filled = 0;
+    while filled < buf.len() {
+        match reader.read(&mut buf[filled..]) {
+            Ok(0) => break,
+            Ok(n) => filled += n,
+            Err(e) if e.kind() == io::ErrorKind::Interrupted => {}
+            Err(e) => return Err(e),
+        }
+    }
+    Ok(filled)
+}
+
+fn image_diff(a: &Path, b: &Path) -> Option<ImageDiff> {
+    let a = image::open(a).ok()?.to_rgb8();
+    let b = image::open(b).ok()?.to_rgb8();
+
+    let rms = (a.dimensions() == b.dimensions()).then(|| {
+        let sum: f64 = a
+            .as_raw()
+            .iter()
+            .zip(b.as_raw())
+            .map(|(&x, &y)| {
+                let d = x as f64 - y as f64;
+                d * d
+            })
+            .sum();
+        (sum / a.as_raw().len().max(1) as f64).sqrt()
+    });
+
+    Some(ImageDiff {
+        a_dimensions: a.dimensions(),
+        b_dimensions: b.dimensions(),
+        rms,
+    })
+}
+
+pub fn view_comparison<'a>(
+    comparison: Option<&'a Comparison>,
+    running: bool,
+) -> Element<'a, Message> {
+    let title = if running {
+        "Comparing…"
+    } else {
+        "Comparison"
+    };
+
+    let header = row![
+        text(title).size(14),
+        Space::with_width(Length::Fill),
+        button(text("✕"))
+            .style(iced::theme::Button::Text)
+            .on_press(Message::CloseComparison),
+    ]
+    .align_items(iced::Alignment::Center);
+
+    let mut entries = column![header].spacing(6);
+    if let Some(c) = comparison {
+        let name = |stats: &FileStats| {
+            stats
+                .path
+                .file_name()
+                .map(|n| n.to_string_lossy().to_string())
+                .unwrap_or_default()
+        };
+        entries = entries.push(text(format!("{} ↔ {}", name(&c.a), name(&c.b))).size(12));
+
+        entries = entries.push(
+            text(if c.identical {
+                "Contents are identical"
+            } else {
+                "Contents differ"
+            })
+            .size(12),
+        );
+
+        let size = if c.a.size == c.b.size {
+            format!("Same size ({})", format_size(c.a.size, BINARY))
+        } else {
+            format!(
+                "Size: {} vs {}",
+                format_size(c.a.size, BINARY),
+                format_size(c.b.size, BINARY)
+            )
+        };
+        entries = entries.push(text(size).size(12));
+
+        let modified = match (c.a.modified, c.b.modified) {
+            (Some(a), Some(b)) if a == b => "Same modification time".to_string(),
+            (Some(a), Some(b)) => {
+                let (newer, by) = match a.duration_since(b) {
+                    Ok(by) => (name(&c.a), by),
+                    Err(e) => (name(&c.b), e.duration()),
+                };
+                format!("{} is newer by {}", newer, format_age(by.as_secs()))
+            }
+            _ => "Modification time unknown".to_string(),
+        };
+        entries = entries.push(text(modified).size(12));
+
+        if let Some(ref image) = c.image {
+            let (aw, ah) = image.a_dimensions;
+            let pixels = if image.dimensions_match() {
+                format!(
+                    "Both {}×{}, RMS pixel difference {:.2}",
+                    aw,
+                    ah,
+                    image.rms.unwrap_or_default()
+                )
+            } else {
+                let (bw, bh) = image.b_dimensions;
+                format!("Dimensions differ: {}×{} vs {}×{}", aw, ah, bw, bh)
+            };
+            entries = entries.push(text(pixels).size(12));
+        }
+    }
+
+    container(entries)
+        .width(Length::Fill)
+        .padding(8)
+        .style(iced::theme::Container::Box)
+        .into()
+}
+
+fn format_age(secs: u64) -> String {
+    match secs {
+        0..=59 => format!("{}s", secs),
+        60..=3599 => format!("{}m", secs / 60),
+        3600..=86399 => format!("{}h", secs / 3600),
+        _ => format!("{}d", secs / 86400),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_same_contents_compares_every_byte() {
+        let dir = tempfile::tempdir().unwrap();
+        let write = |name: &str, data: &[u8]| {
+            let path = dir.path().join(name);
+            std::fs::write(&path, data).unwrap();
+            path
+        };
+        let data = vec![7u8; 200 * 1024];
+        let mut last_differs = data.clone();
+        *last_differs.last_mut().unwrap() = 8;
+
+        let a = write("a", &data);
+        let copy = write("copy", &data);
+        let changed = write("changed", &last_differs);
+        let shorter = write("shorter", &data[..1000]);
+        let empty = write("empty", b"");
+
+        assert!(same_contents(&a, &copy).unwrap());
+        assert!(!same_contents(&a, &changed).unwrap());
+        assert!(!same_contents(&a, &shorter).unwrap());
+        assert!(same_contents(&empty, &empty).unwrap());
+    }
+}
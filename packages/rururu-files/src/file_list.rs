@@ -1,7 +1,12 @@
 use crate::app::{Message, ViewMode};
-use iced::widget::{button, column, container, row, scrollable, text, Space};
+use crate::columns::{self, Column, ColumnData, ROW_HEIGHT, ROW_SPACING};
+use crate::icons::IconResolver;
+use crate::selection::Selection;
+use iced::widget::{
+    button, column, container, mouse_area, row, scrollable, text, text_input, Space,
+};
 use iced::{Element, Length};
-use std::path::PathBuf;
+use std::path::{Path, PathBuf};
 use std::time::SystemTime;
 
 #[derive(Debug, Clone)]
@@ -9,6 +14,10 @@ pub struct FileEntry {
     pub name: String,
     pub path: PathBuf,
     pub is_dir: bool,
+    /// A symlink, whatever it points at
+    pub is_symlink: bool,
+    /// Regular file with an execute bit set
+    pub executable: bool,
     pub size: u64,
     pub modified: Option<SystemTime>,
     pub file_type: String,
@@ -19,68 +28,76 @@ pub struct FileList;
 impl FileList {
     pub fn view<'a>(
         files: &'a [FileEntry],
-        selected: &'a Option<PathBuf>,
+        selection: &'a Selection,
         view_mode: ViewMode,
+        icons: &IconResolver,
+        columns: ColumnData<'a>,
     ) -> Element<'a, Message> {
         match view_mode {
-            ViewMode::List => Self::list_view(files, selected),
-            ViewMode::Grid => Self::grid_view(files, selected),
-            ViewMode::Columns => Self::list_view(files, selected), // TODO: implement columns
+            ViewMode::List => Self::list_view(files, selection, icons, columns),
+            ViewMode::Grid => Self::grid_view(files, selection, icons),
+            ViewMode::Columns => Self::list_view(files, selection, icons, columns), // TODO: implement columns
+        }
+    }
+
+    /// Themed icon, or the emoji for the file type when the theme has none
+    fn icon<'a>(entry: &FileEntry, icons: &IconResolver, size: u16) -> Element<'a, Message> {
+        match icons.icon(entry, size) {
+            Some(icon) => icon.view(size as f32),
+            None if entry.is_dir => text("📁").size(size).into(),
+            None => text(Self::file_icon(&entry.file_type)).size(size).into(),
         }
     }
 
     fn list_view<'a>(
         files: &'a [FileEntry],
-        selected: &'a Option<PathBuf>,
+        selection: &'a Selection,
+        icons: &IconResolver,
+        columns: ColumnData<'a>,
     ) -> Element<'a, Message> {
-        let header = row![
-            text("Name").width(Length::FillPortion(4)),
-            text("Size").width(Length::FillPortion(1)),
-            text("Modified").width(Length::FillPortion(2)),
-            text("Type").width(Length::FillPortion(1)),
-        ]
-        .spacing(8)
-        .padding(8);
+        let header = columns
+            .settings
+            .columns
+            .iter()
+            .fold(row![].spacing(8), |header, c| {
+                header.push(text(c.column.to_string()).width(Length::Fixed(c.width)))
+            })
+            .push(Space::with_width(Length::Fill))
+            .push(
+                button(text("⚙").size(13))
+                    .on_press(Message::ToggleColumnEditor)
+                    .style(if columns.editing {
+                        iced::theme::Button::Primary
+                    } else {
+                        iced::theme::Button::Text
+                    })
+                    .padding([0, 6]),
+            )
+            // Lines up with the cells inside the row buttons
+            .padding([8, 10])
+            .align_items(iced::Alignment::Center);
 
         let rows: Vec<Element<Message>> = files
             .iter()
             .map(|entry| {
-                let is_selected = selected.as_ref().map(|s| s == &entry.path).unwrap_or(false);
-
-                let icon = if entry.is_dir {
-                    "📁"
-                } else {
-                    Self::file_icon(&entry.file_type)
-                };
-
-                let size_str = if entry.is_dir {
-                    "—".to_string()
-                } else {
-                    humansize::format_size(entry.size, humansize::BINARY)
-                };
-
-                let modified_str = entry
-                    .modified
-                    .and_then(|t| {
-                        t.duration_since(SystemTime::UNIX_EPOCH).ok().map(|d| {
-                            chrono::DateTime::from_timestamp(d.as_secs() as i64, 0)
-                                .map(|dt| dt.format("%Y-%m-%d %H:%M").to_string())
-                                .unwrap_or_default()
-                        })
-                    })
-                    .unwrap_or_else(|| "—".to_string());
+                let is_selected = selection.contains(&entry.path);
 
                 let path = entry.path.clone();
                 let path2 = entry.path.clone();
 
-                let row_content = row![
-                    text(format!("{} {}", icon, entry.name)).width(Length::FillPortion(4)),
-                    text(size_str).width(Length::FillPortion(1)),
-                    text(modified_str).width(Length::FillPortion(2)),
-                    text(&entry.file_type).width(Length::FillPortion(1)),
-                ]
-                .spacing(8)
-                .padding(4);
+                let row_content = columns
+                    .settings
+                    .columns
+                    .iter()
+                    .fold(row![].spacing(8), |cells, c| {
+                        cells.push(
+                            container(Self::cell(entry, c.column, icons, columns))
+                                .width(Length::Fixed(c.width))
+                                .clip(true),
+                        )
+                    })
+                    .align_items(iced::Alignment::Center)
+                    .padding(2);
 
                 let style = if is_selected {
                     iced::theme::Button::Primary
@@ -88,17 +105,25 @@ impl FileList {
                     iced::theme::Button::Text
                 };
 
-                button(row_content)
-                    .style(style)
-                    .width(Length::Fill)
-                    .on_press(Message::FileSelected(path))
-                    .into()
+                mouse_area(
+                    button(row_content)
+                        .style(style)
+                        .width(Length::Fill)
+                        .height(Length::Fixed(ROW_HEIGHT))
+                        .padding([4, 8])
+                        .on_press(Message::FileSelected(path)),
+                )
+                .on_right_press(Message::ShowToolsMenu(path2))
+                .into()
             })
             .collect();
 
-        let content = column![header]
-            .push(scrollable(column(rows).spacing(2)))
-            .spacing(4);
+        let mut content = column![header].spacing(4);
+        if columns.editing {
+            content = content.push(columns::view_editor(columns.settings));
+        }
+        let content = content
+            .push(scrollable(column(rows).spacing(ROW_SPACING)).on_scroll(Message::ListScrolled));
 
         container(content)
             .width(Length::FillPortion(3))
@@ -106,21 +131,80 @@ impl FileList {
             .into()
     }
 
+    fn cell<'a>(
+        entry: &'a FileEntry,
+        column: Column,
+        icons: &IconResolver,
+        columns: ColumnData<'a>,
+    ) -> Element<'a, Message> {
+        let details = || {
+            columns
+                .details
+                .get(&entry.path)
+                .filter(|d| d.is_current(entry.modified))
+        };
+
+        let value = match column {
+            Column::Name => {
+                return row![Self::icon(entry, icons, 16), text(&entry.name)]
+                    .spacing(6)
+                    .align_items(iced::Alignment::Center)
+                    .into();
+            }
+            Column::Tags => {
+                let mut tags = columns.tags.get_file_tags(&entry.path);
+                if tags.is_empty() {
+                    return text("").into();
+                }
+                tags.sort_by(|a, b| a.name.cmp(&b.name));
+                return tags
+                    .into_iter()
+                    .fold(row![].spacing(6), |cell, tag| {
+                        let [r, g, b] = tag.color.to_rgb();
+                        cell.push(
+                            text(format!("● {}", tag.name))
+                                .size(13)
+                                .style(iced::Color::from_rgb8(r, g, b)),
+                        )
+                    })
+                    .into();
+            }
+            Column::Size if entry.is_dir => "—".to_string(),
+            Column::Size => humansize::format_size(entry.size, humansize::BINARY),
+            Column::Modified => entry
+                .modified
+                .and_then(|t| {
+                    t.duration_since(SystemTime::UNIX_EPOCH).ok().map(|d| {
+                        chrono::DateTime::from_timestamp(d.as_secs() as i64, 0)
+                            .map(|dt| dt.format("%Y-%m-%d %H:%M").to_string())
+                            .unwrap_or_default()
+                    })
+                })
+                .unwrap_or_else(|| "—".to_string()),
+            Column::Type => entry.file_type.clone(),
+            Column::Dimensions => details()
+                .and_then(|d| d.dimensions)
+                .map(|(w, h)| format!("{}×{}", w, h))
+                .unwrap_or_default(),
+            Column::Duration => details()
+                .and_then(|d| d.duration)
+                .map(columns::format_duration)
+                .unwrap_or_default(),
+        };
+        text(value).into()
+    }
+
     fn grid_view<'a>(
         files: &'a [FileEntry],
-        selected: &'a Option<PathBuf>,
+        selection: &'a Selection,
+        icons: &IconResolver,
     ) -> Element<'a, Message> {
         let items: Vec<Element<Message>> = files
             .iter()
             .map(|entry| {
-                let is_selected = selected.as_ref().map(|s| s == &entry.path).unwrap_or(false);
-
-                let icon = if entry.is_dir {
-                    "📁"
-                } else {
-                    Self::file_icon(&entry.file_type)
-                };
+                let is_selected = selection.contains(&entry.path);
                 let path = entry.path.clone();
+                let menu_path = entry.path.clone();
 
                 let name = if entry.name.len() > 12 {
                     format!("{}...", &entry.name[..12])
@@ -128,7 +212,7 @@ impl FileList {
                     entry.name.clone()
                 };
 
-                let item = column![text(icon).size(32), text(name).size(12),]
+                let item = column![Self::icon(entry, icons, 32), text(name).size(12),]
                     .align_items(iced::Alignment::Center)
                     .spacing(4)
                     .width(Length::Fixed(100.0))
@@ -140,10 +224,13 @@ impl FileList {
                     iced::theme::Button::Text
                 };
 
-                button(item)
-                    .style(style)
-                    .on_press(Message::FileSelected(path))
-                    .into()
+                mouse_area(
+                    button(item)
+                        .style(style)
+                        .on_press(Message::FileSelected(path)),
+                )
+                .on_right_press(Message::ShowToolsMenu(menu_path))
+                .into()
             })
             .collect();
 
@@ -204,3 +291,39 @@ impl FileList {
         }
     }
 }
+
+pub fn rename_input_id() -> text_input::Id {
+    text_input::Id::new("rename")
+}
+
+/// Name field for the file being renamed; Enter renames it
+pub fn view_rename<'a>(path: &'a Path, name: &'a str) -> Element<'a, Message> {
+    let original = path
+        .file_name()
+        .map(|n| n.to_string_lossy().to_string())
+        .unwrap_or_default();
+
+    container(
+        row![
+            text(format!("Rename {} to", original)).size(13),
+            text_input("Name", name)
+                .id(rename_input_id())
+                .on_input(Message::RenameInputChanged)
+                .on_submit(Message::RenameConfirm(name.to_string()))
+                .size(13)
+                .width(Length::Fill),
+            button(text("Rename").size(12))
+                .style(iced::theme::Button::Primary)
+                .on_press(Message::RenameConfirm(name.to_string())),
+            button(text("Cancel").size(12))
+                .style(iced::theme::Button::Secondary)
+                .on_press(Message::RenameCancel),
+        ]
+        .spacing(8)
+        .align_items(iced::Alignment::Center),
+    )
+    .padding(8)
+    .width(Length::Fill)
+    .style(iced::theme::Container::Box)
+    .into()
+}
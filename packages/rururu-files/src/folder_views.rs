@@ -0,0 +1,94 @@
+use crate::app::ViewMode;
+use crate::sorting::SortKey;
+use serde::{Deserialize, Serialize};
+use std::collections::BTreeMap;
+use std::path::{Path, PathBuf};
+use tracing::warn;
+
+/// What a folder overrides; unset fields use the global defaults
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub struct FolderView {
+    #[serde(rename = "v", default, skip_serializing_if = "Option::is_none")]
+    pub view_mode: Option<ViewMode>,
+    #[serde(rename = "s", default, skip_serializing_if = "Option::is_none")]
+    pub sort_key: Option<SortKey>,
+    #[serde(rename = "h", default, skip_serializing_if = "Option::is_none")]
+    pub show_hidden: Option<bool>,
+}
+
+/// Per-directory view settings, saved to
+/// `~/.config/rururu-files/folder-views.json`
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+#[serde(transparent)]
+pub struct FolderViews {
+    /// Keyed by path; non-UTF-8 paths are never remembered
+    entries: BTreeMap<String, FolderView>,
+}
+
+impl FolderViews {
+    pub fn config_path() -> PathBuf {
+        dirs::config_dir()
+            .unwrap_or_else(|| PathBuf::from("."))
+            .join("rururu-files")
+            .join("folder-views.json")
+    }
+
+    /// Load the saved views, dropping directories that no longer exist
+    pub fn load() -> Self {
+        let path = Self::config_path();
+        let Ok(content) = std::fs::read_to_string(&path) else {
+            return Self::default();
+        };
+
+        let mut views: Self = match serde_json::from_str(&content) {
+            Ok(views) => views,
+            Err(e) => {
+                warn!("Ignoring invalid {:?}: {}", path, e);
+                return Self::default();
+            }
+        };
+
+        let before = views.entries.len();
+        views.entries.retain(|dir, _| Path::new(dir).is_dir());
+        if views.entries.len() != before {
+            if let Err(e) = views.save() {
+                warn!("Failed to prune {:?}: {}", path, e);
+            }
+        }
+
+        views
+    }
+
+    pub fn save(&self) -> std::io::Result<()> {
+        let path = Self::config_path();
+        if let Some(parent) = path.parent() {
+            std::fs::create_dir_all(parent)?;
+        }
+        let content = serde_json::to_string(self)?;
+        std::fs::write(path, content)
+    }
+
+    pub fn get(&self, dir: &Path) -> FolderView {
+        dir.to_str()
+            .and_then(|dir| self.entries.get(dir))
+            .copied()
+            .unwrap_or_default()
+    }
+
+    /// Change the view of `dir` and save
+    pub fn update(&mut self, dir: &Path, change: impl FnOnce(&mut FolderView)) {
+        let Some(key) = dir.to_str() else {
+            return;
+        };
+
+        let view = self.entries.entry(key.to_string()).or_default();
+        change(view);
+        if *view == FolderView::default() {
+            self.entries.remove(key);
+        }
+
+        if let Err(e) = self.save() {
+            warn!("Failed to save folder views: {}", e);
+        }
+    }
+}
@@ -0,0 +1,182 @@
+//! File icons from the active freedesktop icon theme.
+//!
+//! Each entry maps to icon names from most to least specific: the MIME
+//! type's own icon (`image-png`), its generic one (`image-x-generic`), then
+//! one for the file's category. `freedesktop_icons` searches the theme, its
+//! `Inherits` chain and hicolor in `~/.local/share/icons`, `/usr/share/icons`
+//! and the other XDG data dirs. The first hit is kept per kind of entry, so
+//! a folder of a thousand JPEGs costs one lookup and one decoded image.
+
+use crate::file_list::FileEntry;
+use iced::widget::{image, svg};
+use iced::{Element, Length};
+use rururu_file_handler::{FileCategory, FileDetector};
+use std::cell::RefCell;
+use std::collections::HashMap;
+use std::path::Path;
+
+/// Every theme inherits from hicolor in the end
+const FALLBACK_THEME: &str = "hicolor";
+
+#[derive(Debug, Clone)]
+pub enum Icon {
+    Raster(image::Handle),
+    Vector(svg::Handle),
+}
+
+impl Icon {
+    pub fn view<'a, Message: 'a>(&self, size: f32) -> Element<'a, Message> {
+        match self {
+            Icon::Raster(handle) => image(handle.clone())
+                .width(Length::Fixed(size))
+                .height(Length::Fixed(size))
+                .into(),
+            Icon::Vector(handle) => svg(handle.clone())
+                .width(Length::Fixed(size))
+                .height(Length::Fixed(size))
+                .into(),
+        }
+    }
+}
+
+pub struct IconResolver {
+    theme: String,
+    detector: FileDetector,
+    /// By entry kind and pixel size; `None` when the theme has no candidate,
+    /// so misses aren't looked up again either
+    cache: RefCell<HashMap<(String, u16), Option<Icon>>>,
+}
+
+impl IconResolver {
+    pub fn new() -> Self {
+        let theme = active_theme().unwrap_or_else(|| FALLBACK_THEME.to_string());
+        tracing::debug!("Using icon theme {}", theme);
+        Self {
+            theme,
+            detector: FileDetector::new(),
+            cache: RefCell::new(HashMap::new()),
+        }
+    }
+
+    /// Themed icon for `entry` at `size` pixels; `None` when neither the
+    /// theme nor hicolor has anything that fits
+    pub fn icon(&self, entry: &FileEntry, size: u16) -> Option<Icon> {
+        let key = (self.kind(entry), size);
+        if let Some(icon) = self.cache.borrow().get(&key) {
+            return icon.clone();
+        }
+
+        let icon = self
+            .candidates(entry)
+            .iter()
+            .find_map(|name| self.load(name, size));
+        self.cache.borrow_mut().insert(key, icon.clone());
+        icon
+    }
+
+    /// What decides an entry's icon; entries of the same kind share one
+    fn kind(&self, entry: &FileEntry) -> String {
+        let kind = if entry.is_dir {
+            "folder".to_string()
+        } else if entry.executable && self.category(entry).is_none() {
+            "executable".to_string()
+        } else {
+            format!("ext:{}", extension(&entry.path))
+        };
+        if entry.is_symlink {
+            format!("symlink:{}", kind)
+        } else {
+            kind
+        }
+    }
+
+    fn candidates(&self, entry: &FileEntry) -> Vec<String> {
+        let mut names = Vec::new();
+        if entry.is_symlink {
+            names.push("inode-symlink".to_string());
+        }
+
+        if entry.is_dir {
+            names.push("folder".to_string());
+            names.push("inode-directory".to_string());
+            return names;
+        }
+
+        match self.category(entry) {
+            Some((mime, category)) => {
+                names.push(mime.replace('/', "-"));
+                if let Some((top, _)) = mime.split_once('/') {
+                    names.push(format!("{}-x-generic", top));
+                }
+                names.extend(category_icons(category).iter().map(|n| n.to_string()));
+            }
+            // The execute bit alone would make every file on a FAT or NTFS
+            // mount look like a program, so it only counts for unknown types
+            None if entry.executable => names.push("application-x-executable".to_string()),
+            None => {}
+        }
+        names.push("text-x-generic".to_string());
+        names
+    }
+
+    /// MIME type and category from the extension alone; listings are too
+    /// large to read every file
+    fn category(&self, entry: &FileEntry) -> Option<(String, FileCategory)> {
+        let ext = entry.path.extension()?.to_str()?;
+        let info = self.detector.detect_from_bytes(&[], Some(ext)).ok()?;
+        Some((info.mime_type, info.category))
+    }
+
+    fn load(&self, name: &str, size: u16) -> Option<Icon> {
+        let path = freedesktop_icons::lookup(name)
+            .with_theme(&self.theme)
+            .with_size(size)
+            .with_cache()
+            .find()?;
+
+        match path.extension().and_then(|e| e.to_str()) {
+            Some("svg") => Some(Icon::Vector(svg::Handle::from_path(path))),
+            Some("png") => Some(Icon::Raster(image::Handle::from_path(path))),
+            // XPM and other legacy formats aren't decodable here
+            _ => None,
+        }
+    }
+}
+
+/// Generic icons from the icon naming spec, for types the theme has no
+/// specific icon for
+fn category_icons(category: FileCategory) -> &'static [&'static str] {
+    match category {
+        FileCategory::Image => &["image-x-generic"],
+        FileCategory::Video => &["video-x-generic"],
+        FileCategory::Audio => &["audio-x-generic"],
+        FileCategory::Document | FileCategory::Project => &["x-office-document"],
+        FileCategory::Model3D => &["application-x-model", "image-x-generic"],
+        FileCategory::Archive => &["package-x-generic"],
+        FileCategory::Code => &["text-x-script"],
+        FileCategory::Subtitle => &["application-x-subrip", "text-x-generic"],
+        FileCategory::EditExchange => &["text-x-generic"],
+        FileCategory::Unknown => &["application-x-generic"],
+    }
+}
+
+fn extension(path: &Path) -> String {
+    path.extension()
+        .and_then(|e| e.to_str())
+        .unwrap_or_default()
+        .to_lowercase()
+}
+
+/// `gtk-icon-theme-name` from the GTK settings the desktop writes, newest
+/// GTK first
+fn active_theme() -> Option<String> {
+    let config = dirs::config_dir()?;
+    ["gtk-4.0", "gtk-3.0"].iter().find_map(|gtk| {
+        let settings = std::fs::read_to_string(config.join(gtk).join("settings.ini")).ok()?;
+        settings.lines().find_map(|line| {
+            let (key, value) = line.split_once('=')?;
+            let value = value.trim().trim_matches('"');
+            (key.trim() == "gtk-icon-theme-name" && !value.is_empty()).then(|| value.to_string())
+        })
+    })
+}
@@ -0,0 +1,370 @@
+//! Zoom, pan and rotation of the image preview.
+//!
+//! The image is decoded once and turned upright by its EXIF orientation;
+//! zooming and panning only change how those pixels are drawn, and rotating
+//! turns the decoded pixels instead of reading the file again. Images larger
+//! than [`MAX_DECODED_SIDE`] are kept downscaled, with 100% still meaning one
+//! pixel of the original per screen pixel.
+
+use crate::app::{Message, PreviewData};
+use iced::advanced::image::{self as raster, Data, FilterMethod};
+use iced::advanced::layout::{self, Layout};
+use iced::advanced::renderer;
+use iced::advanced::widget::{tree, Tree, Widget};
+use iced::advanced::{Clipboard, Shell};
+use iced::widget::image::Handle;
+use iced::{event, mouse, Element, Event, Length, Point, Rectangle, Size, Vector};
+use rururu_file_handler::orientation::orientation_from_bytes;
+use std::io::Cursor;
+
+/// Longest side kept in memory
+pub const MAX_DECODED_SIDE: u32 = 8192;
+/// How much one key press, button or wheel notch zooms
+pub const ZOOM_STEP: f32 = 1.25;
+/// Wheel movement in pixels that counts as one notch
+const PIXELS_PER_NOTCH: f32 = 50.0;
+/// Screen pixels per original pixel
+const MIN_SCALE: f32 = 0.01;
+const MAX_SCALE: f32 = 32.0;
+/// Furthest zooming from a mode can go before the scale is known
+const MAX_FACTOR: f32 = 64.0;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum Zoom {
+    /// The whole image in view
+    #[default]
+    Fit,
+    /// One original pixel per screen pixel
+    Actual,
+    /// The view covered, cropping the image's longer side
+    Fill,
+}
+
+impl std::fmt::Display for Zoom {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        let label = match self {
+            Zoom::Fit => "Fit",
+            Zoom::Actual => "100%",
+            Zoom::Fill => "Fill",
+        };
+        write!(f, "{}", label)
+    }
+}
+
+/// A decoded image and how it is shown
+#[derive(Debug, Clone)]
+pub struct ImageView {
+    /// Upright RGBA pixels
+    pub handle: Handle,
+    pub width: u32,
+    pub height: u32,
+    /// Original pixels per decoded pixel
+    pub downscale: f32,
+    /// Quarter turns clockwise on top of the EXIF orientation
+    pub rotation: u8,
+    pub zoom: Zoom,
+    /// Zoomed in or out this much from `zoom`
+    pub factor: f32,
+    /// Image centre from the view's centre, in screen pixels
+    pub offset: Vector,
+}
+
+impl ImageView {
+    /// Decode an encoded image. `original` is the upright size of the file
+    /// when `bytes` is a smaller rendition of it, so 100% stays true.
+    pub fn decode(bytes: &[u8], original: Option<(u32, u32)>) -> Option<Self> {
+        let mut img = ::image::load_from_memory(bytes).ok()?;
+        let orientation = orientation_from_bytes(bytes).unwrap_or_default();
+        let (source_width, _) = original.unwrap_or(if orientation.swaps_dimensions() {
+            (img.height(), img.width())
+        } else {
+            (img.width(), img.height())
+        });
+
+        if img.width().max(img.height()) > MAX_DECODED_SIDE {
+            img = img.thumbnail(MAX_DECODED_SIDE, MAX_DECODED_SIDE);
+        }
+        let rgba = orientation.apply(img).to_rgba8();
+        let (width, height) = rgba.dimensions();
+
+        Some(Self {
+            handle: Handle::from_pixels(width, height, rgba.into_raw()),
+            width,
+            height,
+            downscale: source_width as f32 / width.max(1) as f32,
+            rotation: 0,
+            zoom: Zoom::Fit,
+            factor: 1.0,
+            offset: Vector::ZERO,
+        })
+    }
+
+    /// Whether the view is exactly `zoom`, not zoomed away from it
+    pub fn is_at(&self, zoom: Zoom) -> bool {
+        self.zoom == zoom && self.factor == 1.0
+    }
+
+    pub fn set_zoom(&mut self, zoom: Zoom) {
+        self.zoom = zoom;
+        self.factor = 1.0;
+        self.offset = Vector::ZERO;
+    }
+
+    /// Zoom about the centre of the view
+    pub fn zoom_by(&mut self, factor: f32) {
+        let previous = self.factor;
+        self.factor = (self.factor * factor).clamp(1.0 / MAX_FACTOR, MAX_FACTOR);
+        self.offset = self.offset * (self.factor / previous);
+    }
+
+    /// Screen pixels per decoded pixel in a view of `bounds`
+    fn scale(&self, bounds: Size) -> f32 {
+        let width = self.width.max(1) as f32;
+        let height = self.height.max(1) as f32;
+        let base = match self.zoom {
+            Zoom::Fit => (bounds.width / width).min(bounds.height / height),
+            Zoom::Actual => self.downscale,
+            Zoom::Fill => (bounds.width / width).max(bounds.height / height),
+        };
+        (base * self.factor).clamp(MIN_SCALE * self.downscale, MAX_SCALE * self.downscale)
+    }
+
+    /// `offset` kept so the image can't be dragged out of a view of
+    /// `bounds`; an image smaller than the view stays centred
+    fn clamp_offset(&self, offset: Vector, scale: f32, bounds: Size) -> Vector {
+        let spare_x = ((self.width as f32 * scale - bounds.width) / 2.0).max(0.0);
+        let spare_y = ((self.height as f32 * scale - bounds.height) / 2.0).max(0.0);
+        Vector::new(
+            offset.x.clamp(-spare_x, spare_x),
+            offset.y.clamp(-spare_y, spare_y),
+        )
+    }
+
+    /// The zoom mode, or how far from it the view has been zoomed
+    pub fn label(&self) -> String {
+        match self.zoom {
+            Zoom::Actual => format!("{:.0}%", self.factor * 100.0),
+            zoom if self.factor == 1.0 => zoom.to_string(),
+            zoom => format!("{} ×{:.2}", zoom, self.factor),
+        }
+    }
+
+    /// The same view with the pixels turned `quarter_turns` clockwise, or
+    /// anticlockwise when negative. The zoom is kept and the pan reset.
+    pub fn rotated(&self, quarter_turns: i32) -> Option<Self> {
+        let Data::Rgba {
+            width,
+            height,
+            pixels,
+        } = self.handle.data()
+        else {
+            return None;
+        };
+        let img = ::image::RgbaImage::from_raw(*width, *height, pixels.as_ref().to_vec())?;
+        let turns = quarter_turns.rem_euclid(4);
+        let turned = match turns {
+            1 => ::image::imageops::rotate90(&img),
+            2 => ::image::imageops::rotate180(&img),
+            3 => ::image::imageops::rotate270(&img),
+            _ => img,
+        };
+        let (width, height) = turned.dimensions();
+
+        Some(Self {
+            handle: Handle::from_pixels(width, height, turned.into_raw()),
+            width,
+            height,
+            rotation: (self.rotation + turns as u8) % 4,
+            offset: Vector::ZERO,
+            ..self.clone()
+        })
+    }
+}
+
+/// Upright size of an encoded image, read from its header
+pub fn original_size(bytes: &[u8]) -> Option<(u32, u32)> {
+    let (width, height) = ::image::ImageReader::new(Cursor::new(bytes))
+        .with_guessed_format()
+        .ok()?
+        .into_dimensions()
+        .ok()?;
+    let swaps = orientation_from_bytes(bytes).is_some_and(|o| o.swaps_dimensions());
+    Some(if swaps {
+        (height, width)
+    } else {
+        (width, height)
+    })
+}
+
+/// `bytes` decoded for zooming, or left as they are when they can't be
+pub async fn decode(bytes: Vec<u8>, original: Option<(u32, u32)>) -> PreviewData {
+    tokio::task::spawn_blocking(move || match ImageView::decode(&bytes, original) {
+        Some(view) => PreviewData::Viewer(view),
+        None => PreviewData::Image(bytes),
+    })
+    .await
+    .unwrap_or(PreviewData::None)
+}
+
+/// The image, zoomed by the wheel and panned by dragging
+pub fn viewer(view: &ImageView) -> Element<'_, Message> {
+    Element::new(Viewer { view })
+}
+
+struct Viewer<'a> {
+    view: &'a ImageView,
+}
+
+#[derive(Default)]
+struct State {
+    /// Where a drag started, and the pan then
+    grabbed: Option<(Point, Vector)>,
+}
+
+impl<'a, Theme, Renderer> Widget<Message, Theme, Renderer> for Viewer<'a>
+where
+    Renderer: raster::Renderer<Handle = Handle>,
+{
+    fn size(&self) -> Size<Length> {
+        Size::new(Length::Fill, Length::Fill)
+    }
+
+    fn layout(
+        &self,
+        _tree: &mut Tree,
+        _renderer: &Renderer,
+        limits: &layout::Limits,
+    ) -> layout::Node {
+        layout::Node::new(limits.resolve(Length::Fill, Length::Fill, Size::ZERO))
+    }
+
+    fn tag(&self) -> tree::Tag {
+        tree::Tag::of::<State>()
+    }
+
+    fn state(&self) -> tree::State {
+        tree::State::new(State::default())
+    }
+
+    fn on_event(
+        &mut self,
+        tree: &mut Tree,
+        event: Event,
+        layout: Layout<'_>,
+        cursor: mouse::Cursor,
+        _renderer: &Renderer,
+        _clipboard: &mut dyn Clipboard,
+        shell: &mut Shell<'_, Message>,
+        _viewport: &Rectangle,
+    ) -> event::Status {
+        let bounds = layout.bounds();
+        let state = tree.state.downcast_mut::<State>();
+        let view = self.view;
+
+        match event {
+            Event::Mouse(mouse::Event::WheelScrolled { delta }) => {
+                let Some(position) = cursor.position_over(bounds) else {
+                    return event::Status::Ignored;
+                };
+                let notches = match delta {
+                    mouse::ScrollDelta::Lines { y, .. } => y,
+                    mouse::ScrollDelta::Pixels { y, .. } => y / PIXELS_PER_NOTCH,
+                };
+                if notches == 0.0 {
+                    return event::Status::Ignored;
+                }
+
+                let scale = view.scale(bounds.size());
+                let zoomed = (scale * ZOOM_STEP.powf(notches))
+                    .clamp(MIN_SCALE * view.downscale, MAX_SCALE * view.downscale);
+                let factor = view.factor * zoomed / scale;
+
+                // Keep the point under the pointer where it is
+                let pointer = position - bounds.center();
+                let offset = pointer - (pointer - view.offset) * (zoomed / scale);
+                let offset = view.clamp_offset(offset, zoomed, bounds.size());
+                shell.publish(Message::PreviewViewChanged(factor, offset));
+                event::Status::Captured
+            }
+            Event::Mouse(mouse::Event::ButtonPressed(mouse::Button::Left)) => {
+                let Some(position) = cursor.position_over(bounds) else {
+                    return event::Status::Ignored;
+                };
+                let scale = view.scale(bounds.size());
+                state.grabbed = Some((
+                    position,
+                    view.clamp_offset(view.offset, scale, bounds.size()),
+                ));
+                event::Status::Captured
+            }
+            Event::Mouse(mouse::Event::ButtonReleased(mouse::Button::Left)) => {
+                if state.grabbed.take().is_some() {
+                    event::Status::Captured
+                } else {
+                    event::Status::Ignored
+                }
+            }
+            Event::Mouse(mouse::Event::CursorMoved { position }) => {
+                let Some((origin, start)) = state.grabbed else {
+                    return event::Status::Ignored;
+                };
+                let scale = view.scale(bounds.size());
+                let offset = view.clamp_offset(start + (position - origin), scale, bounds.size());
+                if offset != view.offset {
+                    shell.publish(Message::PreviewViewChanged(view.factor, offset));
+                }
+                event::Status::Captured
+            }
+            _ => event::Status::Ignored,
+        }
+    }
+
+    fn mouse_interaction(
+        &self,
+        tree: &Tree,
+        layout: Layout<'_>,
+        cursor: mouse::Cursor,
+        _viewport: &Rectangle,
+        _renderer: &Renderer,
+    ) -> mouse::Interaction {
+        let state = tree.state.downcast_ref::<State>();
+        if state.grabbed.is_some() {
+            mouse::Interaction::Grabbing
+        } else if cursor.is_over(layout.bounds()) {
+            mouse::Interaction::Grab
+        } else {
+            mouse::Interaction::Idle
+        }
+    }
+
+    fn draw(
+        &self,
+        _tree: &Tree,
+        renderer: &mut Renderer,
+        _theme: &Theme,
+        _style: &renderer::Style,
+        layout: Layout<'_>,
+        _cursor: mouse::Cursor,
+        _viewport: &Rectangle,
+    ) {
+        let bounds = layout.bounds();
+        let view = self.view;
+        let scale = view.scale(bounds.size());
+        let size = Size::new(view.width as f32 * scale, view.height as f32 * scale);
+        let centre = bounds.center() + view.clamp_offset(view.offset, scale, bounds.size());
+        let image = Rectangle::new(
+            Point::new(centre.x - size.width / 2.0, centre.y - size.height / 2.0),
+            size,
+        );
+
+        // Enlarged pixels stay sharp so detail can be checked
+        let filter = if scale > 1.0 {
+            FilterMethod::Nearest
+        } else {
+            FilterMethod::Linear
+        };
+        renderer.with_layer(bounds, |renderer| {
+            raster::Renderer::draw(renderer, view.handle.clone(), filter, image);
+        });
+    }
+}
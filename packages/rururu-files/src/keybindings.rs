@@ -0,0 +1,692 @@
+//! Keyboard shortcuts.
+//!
+//! Every [`Action`] has a set of key combos, written like `Ctrl+Shift+N`.
+//! Changes are saved to `~/.config/rururu-files/keybindings.toml`, which
+//! only lists the actions whose keys differ from the defaults:
+//!
+//! ```toml
+//! [bindings]
+//! new_folder = ["Ctrl+N"]
+//! toggle_dual_pane = ["F3"]
+//! refresh = []
+//! ```
+//!
+//! An empty list leaves the action unbound. Letters are matched whatever
+//! the Caps Lock state. Shift is part of every combo, also for characters
+//! that need it: `+` typed as Shift+= on a US layout is `Shift++`, while
+//! the keypad `+`, or `+` on layouts where it has its own key, is plain
+//! `+`. Keys typed into a text field never reach the bindings.
+
+use crate::app::Message;
+use crate::image_view::{Zoom, ZOOM_STEP};
+use iced::keyboard::{self, key::Named};
+use iced::widget::{button, column, container, row, scrollable, text, Space};
+use iced::{Element, Length};
+use serde::{Deserialize, Serialize};
+use std::collections::BTreeMap;
+use std::fmt;
+use std::path::PathBuf;
+use std::str::FromStr;
+use tracing::warn;
+
+/// Named keys that can be bound, with the names used in the file
+const NAMED_KEYS: &[(Named, &str)] = &[
+    (Named::Enter, "Enter"),
+    (Named::Tab, "Tab"),
+    (Named::Space, "Space"),
+    (Named::Backspace, "Backspace"),
+    (Named::Delete, "Delete"),
+    (Named::Insert, "Insert"),
+    (Named::Escape, "Escape"),
+    (Named::Home, "Home"),
+    (Named::End, "End"),
+    (Named::PageUp, "PageUp"),
+    (Named::PageDown, "PageDown"),
+    (Named::ArrowLeft, "Left"),
+    (Named::ArrowRight, "Right"),
+    (Named::ArrowUp, "Up"),
+    (Named::ArrowDown, "Down"),
+    (Named::F1, "F1"),
+    (Named::F2, "F2"),
+    (Named::F3, "F3"),
+    (Named::F4, "F4"),
+    (Named::F5, "F5"),
+    (Named::F6, "F6"),
+    (Named::F7, "F7"),
+    (Named::F8, "F8"),
+    (Named::F9, "F9"),
+    (Named::F10, "F10"),
+    (Named::F11, "F11"),
+    (Named::F12, "F12"),
+];
+
+/// Something a shortcut does, named in snake case in the file
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum Action {
+    Copy,
+    Cut,
+    Paste,
+    Delete,
+    Rename,
+    NewFolder,
+    SelectAll,
+    DeselectAll,
+    EditPath,
+    Back,
+    Forward,
+    Up,
+    Home,
+    Refresh,
+    ToggleHidden,
+    TogglePreview,
+    ListView,
+    GridView,
+    ToggleDualPane,
+    SwitchPane,
+    CopyToOtherPane,
+    MoveToOtherPane,
+    QuickLook,
+    CloseQuickLook,
+    QuickLookPrevious,
+    QuickLookNext,
+    ZoomIn,
+    ZoomOut,
+    ZoomToFit,
+    ZoomToActual,
+    RotateClockwise,
+    RotateAnticlockwise,
+    ShowTrash,
+    ShowKeybindings,
+}
+
+impl Action {
+    pub const ALL: [Action; 34] = [
+        Action::Copy,
+        Action::Cut,
+        Action::Paste,
+        Action::Delete,
+        Action::Rename,
+        Action::NewFolder,
+        Action::SelectAll,
+        Action::DeselectAll,
+        Action::EditPath,
+        Action::Back,
+        Action::Forward,
+        Action::Up,
+        Action::Home,
+        Action::Refresh,
+        Action::ToggleHidden,
+        Action::TogglePreview,
+        Action::ListView,
+        Action::GridView,
+        Action::ToggleDualPane,
+        Action::SwitchPane,
+        Action::CopyToOtherPane,
+        Action::MoveToOtherPane,
+        Action::QuickLook,
+        Action::CloseQuickLook,
+        Action::QuickLookPrevious,
+        Action::QuickLookNext,
+        Action::ZoomIn,
+        Action::ZoomOut,
+        Action::ZoomToFit,
+        Action::ZoomToActual,
+        Action::RotateClockwise,
+        Action::RotateAnticlockwise,
+        Action::ShowTrash,
+        Action::ShowKeybindings,
+    ];
+
+    pub fn message(self) -> Message {
+        match self {
+            Action::Copy => Message::CopySelected,
+            Action::Cut => Message::CutSelected,
+            Action::Paste => Message::Paste,
+            Action::Delete => Message::DeleteSelected,
+            Action::Rename => Message::RenameStart,
+            Action::NewFolder => Message::NewFolder,
+            Action::SelectAll => Message::BatchSelectAll,
+            Action::DeselectAll => Message::BatchDeselectAll,
+            Action::EditPath => Message::EditPath,
+            Action::Back => Message::NavigateBack,
+            Action::Forward => Message::NavigateForward,
+            Action::Up => Message::NavigateUp,
+            Action::Home => Message::NavigateHome,
+            Action::Refresh => Message::RefreshDirectory,
+            Action::ToggleHidden => Message::ToggleHiddenFiles,
+            Action::TogglePreview => Message::TogglePreview,
+            Action::ListView => Message::SetViewMode(crate::app::ViewMode::List),
+            Action::GridView => Message::SetViewMode(crate::app::ViewMode::Grid),
+            Action::ToggleDualPane => Message::ToggleDualPane,
+            Action::SwitchPane => Message::SwitchPane,
+            Action::CopyToOtherPane => Message::CopyToOtherPane,
+            Action::MoveToOtherPane => Message::MoveToOtherPane,
+            Action::QuickLook => Message::ToggleQuickLook,
+            Action::CloseQuickLook => Message::CloseQuickLook,
+            Action::QuickLookPrevious => Message::QuickLookStep(-1),
+            Action::QuickLookNext => Message::QuickLookStep(1),
+            Action::ZoomIn => Message::ZoomPreview(ZOOM_STEP),
+            Action::ZoomOut => Message::ZoomPreview(1.0 / ZOOM_STEP),
+            Action::ZoomToFit => Message::SetPreviewZoom(Zoom::Fit),
+            Action::ZoomToActual => Message::SetPreviewZoom(Zoom::Actual),
+            Action::RotateClockwise => Message::RotatePreview(1),
+            Action::RotateAnticlockwise => Message::RotatePreview(-1),
+            Action::ShowTrash => Message::ShowTrash,
+            Action::ShowKeybindings => Message::ToggleKeybindings,
+        }
+    }
+
+    pub fn default_keys(self) -> &'static [&'static str] {
+        match self {
+            Action::Copy => &["Ctrl+C"],
+            Action::Cut => &["Ctrl+X"],
+            Action::Paste => &["Ctrl+V"],
+            Action::Delete => &["Delete"],
+            Action::Rename => &["F2"],
+            Action::NewFolder => &["Ctrl+Shift+N"],
+            Action::SelectAll => &["Ctrl+A"],
+            Action::DeselectAll => &["Ctrl+Shift+A"],
+            Action::EditPath => &["Ctrl+L"],
+            Action::Back => &["Alt+Left"],
+            Action::Forward => &["Alt+Right"],
+            Action::Up => &["Alt+Up"],
+            Action::Home => &["Alt+Home"],
+            Action::Refresh => &["Ctrl+R"],
+            Action::ToggleHidden => &["Ctrl+H"],
+            Action::TogglePreview => &["F9"],
+            Action::ListView => &["Ctrl+1"],
+            Action::GridView => &["Ctrl+2"],
+            Action::ToggleDualPane => &[],
+            Action::SwitchPane => &["Tab"],
+            Action::CopyToOtherPane => &["F5"],
+            Action::MoveToOtherPane => &["F6"],
+            Action::QuickLook => &["Space"],
+            Action::CloseQuickLook => &["Escape"],
+            Action::QuickLookPrevious => &["Left", "Up"],
+            Action::QuickLookNext => &["Right", "Down"],
+            Action::ZoomIn => &["+", "Shift++", "="],
+            Action::ZoomOut => &["-"],
+            Action::ZoomToFit => &["0"],
+            Action::ZoomToActual => &["1"],
+            Action::RotateClockwise => &["R"],
+            Action::RotateAnticlockwise => &["Shift+R"],
+            Action::ShowTrash => &[],
+            Action::ShowKeybindings => &["F1"],
+        }
+    }
+
+    fn defaults(self) -> Vec<KeyCombo> {
+        self.default_keys()
+            .iter()
+            .filter_map(|keys| keys.parse().ok())
+            .collect()
+    }
+}
+
+impl fmt::Display for Action {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let label = match self {
+            Action::Copy => "Copy",
+            Action::Cut => "Cut",
+            Action::Paste => "Paste",
+            Action::Delete => "Move to Trash",
+            Action::Rename => "Rename",
+            Action::NewFolder => "New Folder",
+            Action::SelectAll => "Select All",
+            Action::DeselectAll => "Deselect All",
+            Action::EditPath => "Type a Path",
+            Action::Back => "Back",
+            Action::Forward => "Forward",
+            Action::Up => "Parent Folder",
+            Action::Home => "Home Folder",
+            Action::Refresh => "Refresh",
+            Action::ToggleHidden => "Show Hidden Files",
+            Action::TogglePreview => "Show Preview",
+            Action::ListView => "List View",
+            Action::GridView => "Grid View",
+            Action::ToggleDualPane => "Dual Pane",
+            Action::SwitchPane => "Switch Pane",
+            Action::CopyToOtherPane => "Copy to Other Pane",
+            Action::MoveToOtherPane => "Move to Other Pane",
+            Action::QuickLook => "Quick Look",
+            Action::CloseQuickLook => "Close Quick Look",
+            Action::QuickLookPrevious => "Quick Look Previous",
+            Action::QuickLookNext => "Quick Look Next",
+            Action::ZoomIn => "Zoom In",
+            Action::ZoomOut => "Zoom Out",
+            Action::ZoomToFit => "Zoom to Fit",
+            Action::ZoomToActual => "Actual Size",
+            Action::RotateClockwise => "Rotate Clockwise",
+            Action::RotateAnticlockwise => "Rotate Anticlockwise",
+            Action::ShowTrash => "Trash",
+            Action::ShowKeybindings => "Keyboard Shortcuts",
+        };
+        write!(f, "{}", label)
+    }
+}
+
+/// A key with the modifiers held down, such as `Ctrl+Shift+N`
+#[derive(Debug, Clone, PartialEq, Eq, Hash, Serialize, Deserialize)]
+#[serde(try_from = "String", into = "String")]
+pub struct KeyCombo {
+    pub ctrl: bool,
+    pub alt: bool,
+    pub shift: bool,
+    pub logo: bool,
+    /// A name from `NAMED_KEYS`, an uppercase letter or another character
+    pub key: String,
+}
+
+impl KeyCombo {
+    /// The combo for a key press; `None` for modifiers on their own and
+    /// keys that can't be bound
+    pub fn from_key(key: &keyboard::Key, modifiers: keyboard::Modifiers) -> Option<Self> {
+        let key = match key.as_ref() {
+            keyboard::Key::Named(named) => {
+                let (_, name) = NAMED_KEYS.iter().find(|(n, _)| *n == named)?;
+                name.to_string()
+            }
+            keyboard::Key::Character(c) => normalize_character(c)?,
+            keyboard::Key::Unidentified => return None,
+        };
+        Some(Self {
+            ctrl: modifiers.control(),
+            alt: modifiers.alt(),
+            shift: modifiers.shift(),
+            logo: modifiers.logo(),
+            key,
+        })
+    }
+}
+
+/// A single character as combos name it: letters in uppercase, so Caps
+/// Lock doesn't matter
+fn normalize_character(c: &str) -> Option<String> {
+    let mut chars = c.chars();
+    let first = chars.next()?;
+    if chars.next().is_some() {
+        return None;
+    }
+    Some(first.to_uppercase().collect())
+}
+
+impl fmt::Display for KeyCombo {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        for (held, name) in [
+            (self.ctrl, "Ctrl+"),
+            (self.alt, "Alt+"),
+            (self.shift, "Shift+"),
+            (self.logo, "Super+"),
+        ] {
+            if held {
+                f.write_str(name)?;
+            }
+        }
+        f.write_str(&self.key)
+    }
+}
+
+impl FromStr for KeyCombo {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, String> {
+        let s = s.trim();
+        // `Ctrl++` and `+` bind the plus key itself
+        let (modifiers, key) = match s.strip_suffix("++") {
+            Some(modifiers) => (modifiers, "+"),
+            None if s == "+" => ("", "+"),
+            None => match s.rsplit_once('+') {
+                Some((modifiers, key)) => (modifiers, key),
+                None => ("", s),
+            },
+        };
+
+        let mut combo = Self {
+            ctrl: false,
+            alt: false,
+            shift: false,
+            logo: false,
+            key: String::new(),
+        };
+        for modifier in modifiers.split('+').filter(|m| !m.is_empty()) {
+            match modifier.trim().to_lowercase().as_str() {
+                "ctrl" | "control" => combo.ctrl = true,
+                "alt" => combo.alt = true,
+                "shift" => combo.shift = true,
+                "super" | "logo" | "meta" => combo.logo = true,
+                other => return Err(format!("unknown modifier `{}` in `{}`", other, s)),
+            }
+        }
+
+        let key = key.trim();
+        if let Some((_, name)) = NAMED_KEYS
+            .iter()
+            .find(|(_, name)| name.eq_ignore_ascii_case(key))
+        {
+            combo.key = name.to_string();
+        } else {
+            combo.key = normalize_character(key)
+                .ok_or_else(|| format!("unknown key `{}` in `{}`", key, s))?;
+        }
+        Ok(combo)
+    }
+}
+
+impl TryFrom<String> for KeyCombo {
+    type Error = String;
+
+    fn try_from(s: String) -> Result<Self, String> {
+        s.parse()
+    }
+}
+
+impl From<KeyCombo> for String {
+    fn from(combo: KeyCombo) -> Self {
+        combo.to_string()
+    }
+}
+
+/// Keys for each action, saved to `~/.config/rururu-files/keybindings.toml`
+#[derive(Debug, Clone, PartialEq, Default, Serialize, Deserialize)]
+#[serde(default)]
+pub struct Keybindings {
+    /// Only actions whose keys differ from their defaults
+    bindings: BTreeMap<Action, Vec<KeyCombo>>,
+}
+
+impl Keybindings {
+    pub fn config_path() -> PathBuf {
+        dirs::config_dir()
+            .unwrap_or_else(|| PathBuf::from("."))
+            .join("rururu-files")
+            .join("keybindings.toml")
+    }
+
+    pub fn load() -> Self {
+        let path = Self::config_path();
+        let Ok(content) = std::fs::read_to_string(&path) else {
+            return Self::default();
+        };
+
+        match toml::from_str(&content) {
+            Ok(bindings) => bindings,
+            Err(e) => {
+                warn!("Ignoring invalid {:?}: {}", path, e);
+                Self::default()
+            }
+        }
+    }
+
+    pub fn save(&self) -> std::io::Result<()> {
+        let path = Self::config_path();
+        if let Some(parent) = path.parent() {
+            std::fs::create_dir_all(parent)?;
+        }
+        let content = toml::to_string(self).map_err(std::io::Error::other)?;
+        std::fs::write(path, content)
+    }
+
+    pub fn keys(&self, action: Action) -> Vec<KeyCombo> {
+        match self.bindings.get(&action) {
+            Some(keys) => keys.clone(),
+            None => action.defaults(),
+        }
+    }
+
+    pub fn is_default(&self, action: Action) -> bool {
+        !self.bindings.contains_key(&action)
+    }
+
+    /// The action `combo` triggers, in [`Action::ALL`] order if a
+    /// hand-edited file binds it twice
+    pub fn action_for(&self, combo: &KeyCombo) -> Option<Action> {
+        Action::ALL
+            .into_iter()
+            .find(|&action| self.keys(action).contains(combo))
+    }
+
+    /// Add `combo` to `action`, taking it from whichever action had it.
+    /// Returns that action.
+    pub fn bind(&mut self, action: Action, combo: KeyCombo) -> Option<Action> {
+        let previous = self.action_for(&combo).filter(|&a| a != action);
+        if let Some(previous) = previous {
+            self.unbind(previous, &combo);
+        }
+        let mut keys = self.keys(action);
+        if !keys.contains(&combo) {
+            keys.push(combo);
+        }
+        self.set(action, keys);
+        previous
+    }
+
+    pub fn unbind(&mut self, action: Action, combo: &KeyCombo) {
+        let mut keys = self.keys(action);
+        keys.retain(|k| k != combo);
+        self.set(action, keys);
+    }
+
+    pub fn reset(&mut self, action: Action) {
+        self.bindings.remove(&action);
+    }
+
+    pub fn reset_all(&mut self) {
+        self.bindings.clear();
+    }
+
+    fn set(&mut self, action: Action, keys: Vec<KeyCombo>) {
+        if keys == action.defaults() {
+            self.bindings.remove(&action);
+        } else {
+            self.bindings.insert(action, keys);
+        }
+    }
+}
+
+/// State of the shortcuts dialog
+#[derive(Debug, Clone, Default)]
+pub struct KeybindingEditor {
+    /// Action the next key press is bound to
+    pub capturing: Option<Action>,
+    /// What the last change did, such as taking a key from another action
+    pub status: Option<String>,
+}
+
+/// Every action with its keys; pressing a key's button unbinds it, and
+/// Add waits for the next key press
+pub fn view_editor<'a>(
+    bindings: &'a Keybindings,
+    editor: &'a KeybindingEditor,
+) -> Element<'a, Message> {
+    let small = |label: String| button(text(label).size(12)).padding([2, 8]);
+
+    let rows: Vec<Element<Message>> = Action::ALL
+        .into_iter()
+        .map(|action| {
+            let mut keys = row![].spacing(4).align_items(iced::Alignment::Center);
+            for combo in bindings.keys(action) {
+                let label = format!("{} ✕", combo);
+                keys = keys.push(
+                    small(label)
+                        .style(iced::theme::Button::Secondary)
+                        .on_press(Message::UnbindKey(action, combo)),
+                );
+            }
+
+            let add = if editor.capturing == Some(action) {
+                small("Press a key…".to_string())
+                    .style(iced::theme::Button::Primary)
+                    .on_press(Message::CaptureKeybinding(None))
+            } else {
+                small("Add".to_string())
+                    .style(iced::theme::Button::Text)
+                    .on_press(Message::CaptureKeybinding(Some(action)))
+            };
+            let reset = small("Reset".to_string())
+                .style(iced::theme::Button::Text)
+                .on_press_maybe(
+                    (!bindings.is_default(action)).then_some(Message::ResetKeybinding(action)),
+                );
+
+            row![
+                text(action.to_string())
+                    .size(13)
+                    .width(Length::Fixed(180.0)),
+                keys,
+                add,
+                Space::with_width(Length::Fill),
+                reset,
+            ]
+            .spacing(8)
+            .align_items(iced::Alignment::Center)
+            .into()
+        })
+        .collect();
+
+    let header = row![
+        text("Keyboard Shortcuts").size(14),
+        Space::with_width(Length::Fill),
+        button(text("Reset All").size(12))
+            .style(iced::theme::Button::Secondary)
+            .on_press(Message::ResetAllKeybindings),
+        button(text("✕"))
+            .style(iced::theme::Button::Text)
+            .on_press(Message::ToggleKeybindings),
+    ]
+    .spacing(8)
+    .align_items(iced::Alignment::Center);
+
+    let status = match (&editor.capturing, &editor.status) {
+        (Some(action), _) => format!("Press the keys for {}, or Escape to cancel", action),
+        (None, Some(status)) => status.clone(),
+        (None, None) => format!("Saved to {}", Keybindings::config_path().display()),
+    };
+
+    container(
+        column![
+            header,
+            scrollable(column(rows).spacing(4)).height(Length::Fixed(260.0)),
+            text(status).size(12),
+        ]
+        .spacing(8),
+    )
+    .width(Length::Fill)
+    .padding(8)
+    .style(iced::theme::Container::Box)
+    .into()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn combo(s: &str) -> KeyCombo {
+        s.parse().unwrap()
+    }
+
+    #[test]
+    fn test_parses_key_combos() {
+        let plus = combo("Ctrl++");
+        assert!(plus.ctrl && !plus.shift);
+        assert_eq!(plus.key, "+");
+        assert_eq!(combo("+").key, "+");
+        assert_eq!(combo("Shift++").to_string(), "Shift++");
+
+        let shift_r = combo("shift+r");
+        assert!(shift_r.shift && !shift_r.ctrl);
+        assert_eq!(shift_r.key, "R");
+        assert_eq!(shift_r.to_string(), "Shift+R");
+        assert_ne!(shift_r, combo("R"));
+
+        assert_eq!(combo("Control+Alt+pageup").to_string(), "Ctrl+Alt+PageUp");
+        assert_eq!(combo("Super+F1").to_string(), "Super+F1");
+
+        // Shift stays on characters other than letters
+        assert!(combo("Ctrl+Shift+/").shift);
+
+        assert!("Hyper+A".parse::<KeyCombo>().unwrap_err().contains("Hyper"));
+        assert!("Ctrl+Tab2".parse::<KeyCombo>().is_err());
+        assert!("".parse::<KeyCombo>().is_err());
+    }
+
+    #[test]
+    fn test_every_default_parses() {
+        for action in Action::ALL {
+            assert_eq!(
+                action.defaults().len(),
+                action.default_keys().len(),
+                "{:?}",
+                action
+            );
+        }
+    }
+
+    #[test]
+    fn test_key_presses_match_parsed_combos() {
+        let shift = keyboard::Modifiers::SHIFT;
+        let press = |c: &str, modifiers| {
+            KeyCombo::from_key(&keyboard::Key::Character(c.into()), modifiers).unwrap()
+        };
+        assert_eq!(press("r", shift), combo("Shift+R"));
+        assert_eq!(press("R", keyboard::Modifiers::empty()), combo("R"));
+        assert_eq!(press("+", shift), combo("Shift++"));
+        assert_eq!(
+            KeyCombo::from_key(
+                &keyboard::Key::Named(Named::F1),
+                keyboard::Modifiers::empty()
+            ),
+            Some(combo("F1"))
+        );
+
+        let bindings = Keybindings::default();
+        assert_eq!(
+            bindings.action_for(&press("+", shift)),
+            Some(Action::ZoomIn)
+        );
+        assert_eq!(
+            bindings.action_for(&combo("F1")),
+            Some(Action::ShowKeybindings)
+        );
+    }
+
+    #[test]
+    fn test_bind_moves_keys_and_defaults_drop_out() {
+        let mut bindings = Keybindings::default();
+
+        // Taking Ctrl+R from Refresh records both actions
+        assert_eq!(
+            bindings.bind(Action::ToggleDualPane, combo("Ctrl+R")),
+            Some(Action::Refresh)
+        );
+        assert_eq!(bindings.keys(Action::ToggleDualPane), [combo("Ctrl+R")]);
+        assert!(bindings.keys(Action::Refresh).is_empty());
+        let saved = toml::to_string(&bindings).unwrap();
+        assert!(saved.contains("toggle_dual_pane") && saved.contains("refresh = []"));
+
+        // Giving it back leaves nothing that differs from the defaults
+        assert_eq!(
+            bindings.bind(Action::Refresh, combo("Ctrl+R")),
+            Some(Action::ToggleDualPane)
+        );
+        assert!(bindings.is_default(Action::Refresh));
+        assert!(bindings.is_default(Action::ToggleDualPane));
+        assert_eq!(bindings, Keybindings::default());
+        assert!(!toml::to_string(&bindings).unwrap().contains("refresh"));
+
+        // Binding a key twice doesn't duplicate it
+        assert_eq!(bindings.bind(Action::Copy, combo("Ctrl+C")), None);
+        assert!(bindings.is_default(Action::Copy));
+
+        bindings.unbind(Action::ZoomIn, &combo("="));
+        assert_eq!(
+            bindings.keys(Action::ZoomIn),
+            [combo("+"), combo("Shift++")]
+        );
+        let loaded: Keybindings = toml::from_str(&toml::to_string(&bindings).unwrap()).unwrap();
+        assert_eq!(loaded, bindings);
+
+        bindings.reset_all();
+        assert_eq!(bindings, Keybindings::default());
+    }
+}
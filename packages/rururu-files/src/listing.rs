@@ -0,0 +1,123 @@
+//! Streaming listings of real folders.
+//!
+//! A folder is read in two passes so the file list fills in while a huge
+//! folder is still being read: names and kinds come straight from
+//! `read_dir`, sizes and modification times from a `stat` per file once
+//! everything is listed. The load runs as a subscription keyed on its id,
+//! so navigating elsewhere drops the subscription and stops the load; the
+//! id on every message lets the app ignore any still queued.
+
+use crate::app::Message;
+use crate::file_list::FileEntry;
+use futures::channel::mpsc::Sender;
+use futures::SinkExt;
+use iced::Subscription;
+use std::os::unix::fs::PermissionsExt;
+use std::path::{Path, PathBuf};
+
+/// Entries per `FilesAppended`
+const LIST_CHUNK: usize = 1000;
+/// Entries per `FileDetailsLoaded`; each one is a `stat`
+const DETAILS_CHUNK: usize = 2000;
+
+/// Subscriptions are told apart by the type of their id as well as its
+/// value; a type of its own keeps both panes' listings from colliding with
+/// checksum jobs numbered the same
+#[derive(Hash)]
+struct ListingId(u64);
+
+pub fn subscription(id: u64, path: PathBuf) -> Subscription<Message> {
+    iced::subscription::channel(ListingId(id), 4, move |mut output| async move {
+        if let Err(e) = stream(id, &path, &mut output).await {
+            let _ = output.send(Message::Error(e.to_string())).await;
+        }
+        // Finished; the app drops the subscription once it sees that
+        std::future::pending().await
+    })
+}
+
+async fn stream(id: u64, path: &Path, output: &mut Sender<Message>) -> std::io::Result<()> {
+    let mut read_dir = tokio::fs::read_dir(path).await?;
+    let mut paths = Vec::new();
+    let mut chunk = Vec::with_capacity(LIST_CHUNK);
+
+    while let Some(entry) = read_dir.next_entry().await? {
+        // `file_type` comes with the directory entry; only symlinks need a
+        // stat to tell whether they point at a folder
+        let kind = entry.file_type().await?;
+        let path = entry.path();
+        let is_dir = kind.is_dir() || (kind.is_symlink() && path.is_dir());
+
+        paths.push((path.clone(), kind.is_symlink()));
+        chunk.push(FileEntry {
+            name: entry.file_name().to_string_lossy().to_string(),
+            file_type: file_type(&path, is_dir),
+            path,
+            is_dir,
+            is_symlink: kind.is_symlink(),
+            executable: false,
+            size: 0,
+            modified: None,
+        });
+
+        if chunk.len() == LIST_CHUNK {
+            send(
+                output,
+                Message::FilesAppended(id, std::mem::take(&mut chunk)),
+            )
+            .await;
+        }
+    }
+    if !chunk.is_empty() {
+        send(output, Message::FilesAppended(id, chunk)).await;
+    }
+    send(output, Message::FilesListed(id)).await;
+
+    for paths in paths.chunks(DETAILS_CHUNK) {
+        let paths = paths.to_vec();
+        let details = tokio::task::spawn_blocking(move || {
+            paths
+                .into_iter()
+                .filter_map(|(path, is_symlink)| stat_entry(path, is_symlink))
+                .collect()
+        })
+        .await
+        .map_err(std::io::Error::other)?;
+        send(output, Message::FileDetailsLoaded(id, details)).await;
+    }
+    send(output, Message::DirectoryLoaded(id)).await;
+
+    Ok(())
+}
+
+/// The full entry for `path`, or `None` when it's gone
+pub fn stat_entry(path: PathBuf, is_symlink: bool) -> Option<FileEntry> {
+    let metadata = std::fs::metadata(&path).ok()?;
+    Some(FileEntry {
+        name: path.file_name()?.to_string_lossy().to_string(),
+        file_type: file_type(&path, metadata.is_dir()),
+        is_dir: metadata.is_dir(),
+        is_symlink,
+        executable: metadata.is_file() && metadata.permissions().mode() & 0o111 != 0,
+        size: metadata.len(),
+        modified: metadata.modified().ok(),
+        path,
+    })
+}
+
+/// The app only goes away by dropping the subscription, which also drops
+/// this future, so a failed send can be ignored
+async fn send(output: &mut Sender<Message>, message: Message) {
+    let _ = output.send(message).await;
+}
+
+fn file_type(path: &Path, is_dir: bool) -> String {
+    if is_dir {
+        "directory".to_string()
+    } else {
+        path.extension()
+            .and_then(|e| e.to_str())
+            .unwrap_or("file")
+            .to_string()
+    }
+}
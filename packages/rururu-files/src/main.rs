@@ -1,10 +1,31 @@
 mod app;
+mod archive;
+mod audio_tags;
 mod batch;
+mod checksums;
+mod columns;
+mod compare;
 mod file_list;
+mod folder_views;
+mod icons;
+mod image_view;
+mod keybindings;
+mod listing;
+mod open_with;
+mod panes;
 mod preview;
+mod quick_look;
+mod recents;
+mod remotes;
+mod scopes;
+mod selection;
 mod sidebar;
+mod smart_folders;
+mod sorting;
 mod tags;
 mod toolbar;
+mod tools;
+mod trash_view;
 
 use app::RururuFiles;
 use iced::{Application, Settings};
@@ -16,7 +37,7 @@ fn main() -> iced::Result {
         .with(tracing_subscriber::EnvFilter::from_default_env())
         .init();
 
-    RururuFiles::run(Settings {
+    let result = RururuFiles::run(Settings {
         window: iced::window::Settings {
             size: iced::Size::new(1200.0, 800.0),
             min_size: Some(iced::Size::new(800.0, 600.0)),
@@ -24,5 +45,7 @@ fn main() -> iced::Result {
         },
         antialiasing: true,
         ..Default::default()
-    })
+    });
+    archive::remove_temp_dir();
+    result
 }
@@ -0,0 +1,54 @@
+//! Opening creative project files in the app that made them.
+//!
+//! Desktop MIME associations rarely cover `.blend`, `.kra` or `.drp`, so
+//! project files are handed to their workflow app directly when it is
+//! installed, and everything else goes through the desktop's default.
+
+use rururu_file_handler::{FileCategory, FileDetector, ProjectFormat};
+use std::io::Read;
+use std::path::{Path, PathBuf};
+use std::process::{Command, Stdio};
+
+/// Enough of a file for the project magic checks; .blend files run to GBs
+const HEAD_SIZE: u64 = 64 * 1024;
+
+/// The installed app that opens `path`, if it is a project file
+pub fn project_app(detector: &FileDetector, path: &Path) -> Option<&'static ProjectFormat> {
+    let mut head = Vec::new();
+    std::fs::File::open(path)
+        .ok()?
+        .take(HEAD_SIZE)
+        .read_to_end(&mut head)
+        .ok()?;
+
+    let extension = path.extension().and_then(|e| e.to_str());
+    let info = detector.detect_from_bytes(&head, extension).ok()?;
+    if info.category != FileCategory::Project {
+        return None;
+    }
+
+    let format = ProjectFormat::for_app(info.codec.as_deref()?)?;
+    find_executable(format.executable).map(|_| format)
+}
+
+/// Start the project's app on `path` without waiting for it
+pub fn open_in_app(format: &ProjectFormat, path: &Path) -> std::io::Result<()> {
+    let mut child = Command::new(format.executable)
+        .arg(path)
+        .stdin(Stdio::null())
+        .stdout(Stdio::null())
+        .stderr(Stdio::null())
+        .spawn()?;
+
+    // Reap it when it exits
+    std::thread::spawn(move || {
+        let _ = child.wait();
+    });
+    Ok(())
+}
+
+fn find_executable(name: &str) -> Option<PathBuf> {
+    std::env::split_paths(&std::env::var_os("PATH")?)
+        .map(|dir| dir.join(name))
+        .find(|path| path.is_file())
+}
@@ -0,0 +1,122 @@
+//! Dual-pane (commander) mode.
+//!
+//! The focused pane lives in the browsing fields of `RururuFiles`, so every
+//! existing message acts on it unchanged; the other pane is parked in a
+//! [`Pane`] and swapped in when focus moves. Each pane keeps its own folder,
+//! history, listing and selection.
+
+use crate::app::{Listing, Message, ViewMode};
+use crate::archive::ArchivePath;
+use crate::file_list::FileEntry;
+use crate::recents::UsageView;
+use crate::selection::Selection;
+use crate::sorting::SortKey;
+use crate::trash_view::{TrashEntry, TrashPurge};
+use iced::widget::scrollable::Viewport;
+use iced::widget::{column, container, text};
+use iced::{Color, Element, Length};
+use std::ffi::OsString;
+use std::path::{Path, PathBuf};
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum PaneSide {
+    #[default]
+    Left,
+    Right,
+}
+
+impl PaneSide {
+    pub fn other(self) -> Self {
+        match self {
+            PaneSide::Left => PaneSide::Right,
+            PaneSide::Right => PaneSide::Left,
+        }
+    }
+}
+
+/// Browsing state of the pane without focus
+pub struct Pane {
+    pub current_path: PathBuf,
+    pub archive: Option<ArchivePath>,
+    pub history: Vec<PathBuf>,
+    pub history_index: usize,
+    pub files: Vec<FileEntry>,
+    pub selection: Selection,
+    pub listing: Option<Listing>,
+    pub loading: bool,
+    pub view_mode: ViewMode,
+    pub sort_by: SortKey,
+    pub show_hidden: bool,
+    pub search_query: String,
+    pub usage_view: Option<UsageView>,
+    pub smart_folder: Option<String>,
+    pub in_trash: bool,
+    pub trash_entries: Vec<TrashEntry>,
+    pub trash_selected: Option<OsString>,
+    pub trash_purge: Option<TrashPurge>,
+    pub list_viewport: Option<Viewport>,
+}
+
+impl Pane {
+    /// A pane on `path` with nothing listed yet
+    pub fn new(path: PathBuf, sort_by: SortKey) -> Self {
+        Self {
+            history: vec![path.clone()],
+            current_path: path,
+            archive: None,
+            history_index: 0,
+            files: Vec::new(),
+            selection: Selection::default(),
+            listing: None,
+            loading: true,
+            view_mode: ViewMode::List,
+            sort_by,
+            show_hidden: false,
+            search_query: String::new(),
+            usage_view: None,
+            smart_folder: None,
+            in_trash: false,
+            trash_entries: Vec::new(),
+            trash_selected: None,
+            trash_purge: None,
+            list_viewport: None,
+        }
+    }
+}
+
+/// What a pane's header shows
+pub fn title(
+    path: &Path,
+    in_trash: bool,
+    usage_view: Option<UsageView>,
+    smart_folder: Option<&str>,
+) -> String {
+    if in_trash {
+        return "Trash".to_string();
+    }
+    match (usage_view, smart_folder) {
+        (Some(view), _) => view.title().to_string(),
+        (None, Some(name)) => name.to_string(),
+        (None, None) => path.display().to_string(),
+    }
+}
+
+/// A pane's list under a header naming its folder, dimmed without focus
+pub fn view<'a>(title: String, list: Element<'a, Message>, focused: bool) -> Element<'a, Message> {
+    let header = text(title).size(13).style(if focused {
+        iced::theme::Text::Default
+    } else {
+        iced::theme::Text::Color(Color::from_rgb(0.5, 0.5, 0.5))
+    });
+
+    column![
+        container(header)
+            .padding(6)
+            .width(Length::Fill)
+            .style(iced::theme::Container::Box),
+        list,
+    ]
+    .spacing(4)
+    .width(Length::FillPortion(1))
+    .into()
+}
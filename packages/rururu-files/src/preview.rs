@@ -1,21 +1,146 @@
 use crate::app::{Message, PreviewData};
-use iced::widget::{column, container, image, scrollable, text, Space};
+use crate::image_view::{self, ImageView, Zoom, ZOOM_STEP};
+use crate::scopes::{self, ScopeKind};
+use ::image::ImageDecoder;
+use iced::widget::{
+    button, checkbox, column, container, image, pick_list, row, scrollable, text, Space,
+};
 use iced::{Element, Length};
+use rururu_color::{ColorConfig, IccManager, ProofOptions, Scopes, SoftProof};
+use rururu_file_handler::orientation::orientation_from_bytes;
+use rururu_wrappers::color::ColorSpace;
+use rururu_wrappers::{ColorManager, CvdType};
+use std::fmt;
+use std::io::Cursor;
 use std::path::PathBuf;
 
+/// Longest side of a simulated preview; the pane is far smaller
+const FILTERED_PREVIEW_SIZE: u32 = 1600;
+
+/// A printer or other output profile the image preview can be proofed on
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ProofProfile {
+    pub name: String,
+    pub path: PathBuf,
+}
+
+impl fmt::Display for ProofProfile {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(&self.name)
+    }
+}
+
+/// Soft proofing of the image preview
+#[derive(Debug, Clone, Default)]
+pub struct SoftProofing {
+    /// Installed output profiles, by name
+    pub profiles: Vec<ProofProfile>,
+    /// Profile the preview is proofed on; `None` shows the image as is
+    pub selected: Option<ProofProfile>,
+    /// Paint colors the output can't reproduce in magenta
+    pub gamut_warning: bool,
+}
+
+impl SoftProofing {
+    /// Scan the installed output profiles and start on the one the active
+    /// color workflow proofs on; this reads every profile's header, so it
+    /// runs off the UI thread
+    pub fn load() -> Self {
+        let icc = IccManager::new();
+        let mut profiles: Vec<_> = icc
+            .list_output_profiles()
+            .into_iter()
+            .map(|p| ProofProfile {
+                name: p.name.clone(),
+                path: p.path.clone(),
+            })
+            .collect();
+        profiles.sort_by(|a, b| a.name.cmp(&b.name));
+
+        let config = ColorConfig::load().unwrap_or_default();
+        let selected = config.soft_proof_profile().and_then(|path| {
+            profiles
+                .iter()
+                .find(|p| p.path == path)
+                .cloned()
+                .or_else(|| {
+                    let name = path.file_stem()?.to_string_lossy().into_owned();
+                    path.exists().then(|| ProofProfile {
+                        name,
+                        path: path.to_path_buf(),
+                    })
+                })
+        });
+        if let Some(ref profile) = selected {
+            if !profiles.contains(profile) {
+                profiles.push(profile.clone());
+            }
+        }
+
+        Self {
+            profiles,
+            selected,
+            ..Self::default()
+        }
+    }
+}
+
+/// [`SoftProofing::load`] on a blocking thread
+pub async fn load_soft_proofing() -> SoftProofing {
+    tokio::task::spawn_blocking(SoftProofing::load)
+        .await
+        .unwrap_or_default()
+}
+
+/// Simulations the image preview is rendered through
+#[derive(Debug, Clone, Default)]
+pub struct ImageFilter {
+    /// Output profile and whether to mark out-of-gamut colors
+    pub proof: Option<(PathBuf, bool)>,
+    pub cvd: Option<CvdType>,
+}
+
+impl ImageFilter {
+    pub fn is_none(&self) -> bool {
+        self.proof.is_none() && self.cvd.is_none()
+    }
+}
+
 pub struct Preview;
 
 impl Preview {
-    pub fn view<'a>(data: &'a PreviewData, selected: &'a Option<PathBuf>) -> Element<'a, Message> {
+    pub fn view<'a>(
+        data: &'a PreviewData,
+        selected: Option<&'a PathBuf>,
+        cvd: Option<CvdType>,
+        proofing: &'a SoftProofing,
+        scope: Option<ScopeKind>,
+        scope_data: Option<&'a Scopes>,
+    ) -> Element<'a, Message> {
         let content = match data {
             PreviewData::Image(bytes) => {
                 let handle = image::Handle::from_memory(bytes.clone());
                 column![
                     Self::header(selected),
                     image(handle).width(Length::Fill).height(Length::Fill),
+                    Self::cvd_toggle(cvd),
+                    Self::proof_controls(proofing),
+                    Self::scopes(scope, scope_data),
                 ]
                 .spacing(8)
             }
+            PreviewData::Viewer(view) => column![
+                Self::header(selected),
+                container(image_view::viewer(view))
+                    .width(Length::Fill)
+                    .height(Length::Fill)
+                    .clip(true),
+                Self::zoom_controls(view),
+                Self::cvd_toggle(cvd),
+                Self::proof_controls(proofing),
+                Self::scopes(scope, scope_data),
+            ]
+            .spacing(8),
             PreviewData::Text(content) => column![
                 Self::header(selected),
                 scrollable(text(content).font(iced::Font::MONOSPACE).size(12)).height(Length::Fill),
@@ -30,6 +155,16 @@ impl Preview {
                 ]
                 .spacing(8)
             }
+            PreviewData::Frames(frames) => {
+                let first: Element<Message> = match frames.first() {
+                    Some(handle) => image(handle.clone())
+                        .width(Length::Fill)
+                        .height(Length::Fill)
+                        .into(),
+                    None => Space::with_height(Length::Fill).into(),
+                };
+                column![Self::header(selected), first].spacing(8)
+            }
             PreviewData::None => {
                 if let Some(path) = selected {
                     column![
@@ -58,7 +193,102 @@ impl Preview {
             .into()
     }
 
-    fn header<'a>(selected: &'a Option<PathBuf>) -> Element<'a, Message> {
+    /// Zoom modes, zoom steps and rotation of the image
+    fn zoom_controls(view: &ImageView) -> Element<'_, Message> {
+        let mode = |zoom: Zoom| {
+            button(text(zoom.to_string()).size(11))
+                .style(if view.is_at(zoom) {
+                    iced::theme::Button::Primary
+                } else {
+                    iced::theme::Button::Text
+                })
+                .on_press(Message::SetPreviewZoom(zoom))
+        };
+        let step = |label, message| {
+            button(text(label).size(11))
+                .style(iced::theme::Button::Text)
+                .on_press(message)
+        };
+
+        row![
+            text("Zoom:").size(11),
+            mode(Zoom::Fit),
+            mode(Zoom::Actual),
+            mode(Zoom::Fill),
+            step("−", Message::ZoomPreview(1.0 / ZOOM_STEP)),
+            step("+", Message::ZoomPreview(ZOOM_STEP)),
+            text(view.label()).size(11),
+            Space::with_width(Length::Fill),
+            step("⟲", Message::RotatePreview(-1)),
+            step("⟳", Message::RotatePreview(1)),
+        ]
+        .spacing(4)
+        .align_items(iced::Alignment::Center)
+        .into()
+    }
+
+    /// Scope picker, with the picked scope under it
+    fn scopes<'a>(scope: Option<ScopeKind>, data: Option<&'a Scopes>) -> Element<'a, Message> {
+        let mut content = column![scopes::controls(scope)].spacing(4);
+        if let (Some(kind), Some(data)) = (scope, data) {
+            content = content.push(scopes::view(kind, data));
+        }
+        content.into()
+    }
+
+    /// Normal vision or one of the simulated deficiencies
+    fn cvd_toggle<'a>(cvd: Option<CvdType>) -> Element<'a, Message> {
+        let option = |label: &'a str, kind: Option<CvdType>| {
+            button(text(label).size(11))
+                .style(if cvd == kind {
+                    iced::theme::Button::Primary
+                } else {
+                    iced::theme::Button::Text
+                })
+                .on_press(Message::SetCvdPreview(kind))
+        };
+
+        let mut options = row![option("Normal", None)].spacing(4);
+        for kind in CvdType::ALL {
+            options = options.push(option(kind.name(), Some(kind)));
+        }
+        row![text("Vision:").size(11), options]
+            .spacing(8)
+            .align_items(iced::Alignment::Center)
+            .into()
+    }
+
+    /// Output profile to soft-proof on, with the gamut warning switch
+    fn proof_controls(proofing: &SoftProofing) -> Element<'_, Message> {
+        if proofing.profiles.is_empty() {
+            return text("Proof: no printer profiles installed").size(11).into();
+        }
+
+        let off = button(text("Off").size(11))
+            .style(if proofing.selected.is_none() {
+                iced::theme::Button::Primary
+            } else {
+                iced::theme::Button::Text
+            })
+            .on_press(Message::SetSoftProof(None));
+        let profiles = pick_list(
+            &proofing.profiles[..],
+            proofing.selected.clone(),
+            |profile| Message::SetSoftProof(Some(profile)),
+        )
+        .placeholder("Output profile")
+        .text_size(11);
+        let warning = checkbox("Gamut warning", proofing.gamut_warning)
+            .on_toggle(Message::SetGamutWarning)
+            .text_size(11);
+
+        row![text("Proof:").size(11), off, profiles, warning]
+            .spacing(8)
+            .align_items(iced::Alignment::Center)
+            .into()
+    }
+
+    fn header<'a>(selected: Option<&'a PathBuf>) -> Element<'a, Message> {
         if let Some(path) = selected {
             let name = path
                 .file_name()
@@ -73,3 +303,72 @@ impl Preview {
         }
     }
 }
+
+/// The image through `filter`, re-encoded as PNG. Falls back to the
+/// original when it can't be decoded or proofed.
+pub async fn filter_image(bytes: Vec<u8>, filter: ImageFilter) -> PreviewData {
+    tokio::task::spawn_blocking(move || {
+        let filtered = render_filtered(&bytes, &filter);
+        PreviewData::Image(filtered.unwrap_or(bytes))
+    })
+    .await
+    .unwrap_or(PreviewData::None)
+}
+
+fn render_filtered(bytes: &[u8], filter: &ImageFilter) -> Option<Vec<u8>> {
+    let mut decoder = ::image::ImageReader::new(Cursor::new(bytes))
+        .with_guessed_format()
+        .ok()?
+        .into_decoder()
+        .ok()?;
+    let embedded = decoder.icc_profile().ok().flatten();
+    let mut img = ::image::DynamicImage::from_decoder(decoder).ok()?;
+    if img.width().max(img.height()) > FILTERED_PREVIEW_SIZE {
+        img = img.thumbnail(FILTERED_PREVIEW_SIZE, FILTERED_PREVIEW_SIZE);
+    }
+    // Decoding drops the EXIF rotation the unfiltered preview gets
+    let img = orientation_from_bytes(bytes)
+        .unwrap_or_default()
+        .apply(img)
+        .to_rgba8();
+    let (width, height) = img.dimensions();
+    let mut rgba = img.into_raw();
+
+    // Untagged images, and those whose profile doesn't describe the decoded
+    // RGB (such as a CMYK JPEG's), are taken as sRGB; the preview pane shows
+    // sRGB
+    if let Some((profile, gamut_warning)) = &filter.proof {
+        let options = ProofOptions {
+            gamut_warning: *gamut_warning,
+            black_point_compensation: true,
+            ..ProofOptions::default()
+        };
+        let proof_path = profile.to_string_lossy();
+        let proof = embedded
+            .and_then(|icc| SoftProof::with_source_icc(&icc, &proof_path, "srgb", options).ok())
+            .map_or_else(|| SoftProof::new("srgb", &proof_path, "srgb", options), Ok);
+        if let Err(e) = proof.and_then(|proof| proof.apply(&mut rgba, 4)) {
+            tracing::warn!("Soft proof on {} failed: {}", profile.display(), e);
+            return None;
+        }
+    }
+
+    if let Some(kind) = filter.cvd {
+        let mut cm = ColorManager::new();
+        cm.set_working_space(ColorSpace::Linear);
+        let mut pixels = cm.decode_srgb_u8(&rgba, 4);
+        cm.simulate_cvd(&mut pixels, 4, kind, 1.0);
+        cm.transform_buffer(&mut pixels, 4, ColorSpace::Linear, ColorSpace::SRGB)
+            .ok()?;
+        rgba = pixels
+            .iter()
+            .map(|c| (c.clamp(0.0, 1.0) * 255.0).round() as u8)
+            .collect();
+    }
+
+    let mut png = Vec::new();
+    ::image::RgbaImage::from_raw(width, height, rgba)?
+        .write_to(&mut Cursor::new(&mut png), ::image::ImageFormat::Png)
+        .ok()?;
+    Some(png)
+}
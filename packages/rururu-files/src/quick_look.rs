@@ -0,0 +1,296 @@
+//! Space-bar full-window preview of the selected file.
+//!
+//! Content comes from `load_preview` at [`PreviewFidelity::Full`]: PDFs are
+//! rasterised through poppler's `pdftoppm`, and videos are shown as a strip of
+//! frames from the file handler that can be stepped through or played back.
+
+use crate::app::{Message, PreviewData};
+use crate::scopes::{self, ScopeKind};
+use iced::widget::{button, column, container, image, row, scrollable, slider, text, Space};
+use iced::{Element, Length};
+use rururu_color::Scopes;
+use rururu_file_handler::media::MediaHandler;
+use rururu_file_handler::thumbnail::ThumbnailSize;
+use std::path::{Path, PathBuf};
+
+/// Frames pulled from a video for Quick Look playback
+const VIDEO_FRAME_COUNT: u32 = 24;
+const VIDEO_FRAME_WIDTH: u32 = 960;
+
+pub struct QuickLook {
+    pub path: PathBuf,
+    pub data: Option<PreviewData>,
+    pub frame: usize,
+    pub playing: bool,
+}
+
+impl QuickLook {
+    pub fn new(path: PathBuf) -> Self {
+        Self {
+            path,
+            data: None,
+            frame: 0,
+            playing: false,
+        }
+    }
+
+    pub fn frame_count(&self) -> usize {
+        match self.data {
+            Some(PreviewData::Frames(ref frames)) => frames.len(),
+            _ => 0,
+        }
+    }
+
+    /// Advance playback by one frame, looping at the end
+    pub fn tick(&mut self) {
+        let count = self.frame_count();
+        if count > 0 {
+            self.frame = (self.frame + 1) % count;
+        }
+    }
+
+    /// `scope_data` is the analysis of what is shown, while `scope` is on
+    pub fn view<'a>(
+        &'a self,
+        position: usize,
+        total: usize,
+        scope: Option<ScopeKind>,
+        scope_data: Option<&'a Scopes>,
+    ) -> Element<'a, Message> {
+        let name = self
+            .path
+            .file_name()
+            .and_then(|n| n.to_str())
+            .unwrap_or("Unknown");
+
+        let header = row![
+            text(name).size(16),
+            Space::with_width(Length::Fill),
+            text(format!("{} / {}", position + 1, total)).size(12),
+            button(text("◀"))
+                .style(iced::theme::Button::Secondary)
+                .on_press(Message::QuickLookStep(-1)),
+            button(text("▶"))
+                .style(iced::theme::Button::Secondary)
+                .on_press(Message::QuickLookStep(1)),
+            button(text("✕"))
+                .style(iced::theme::Button::Text)
+                .on_press(Message::CloseQuickLook),
+        ]
+        .spacing(8)
+        .align_items(iced::Alignment::Center);
+
+        let body: Element<Message> = match self.data {
+            None => Self::centered(text("Loading…").size(14).into()),
+            Some(PreviewData::Image(ref bytes)) => image(image::Handle::from_memory(bytes.clone()))
+                .width(Length::Fill)
+                .height(Length::Fill)
+                .into(),
+            Some(PreviewData::Viewer(ref view)) => image(view.handle.clone())
+                .width(Length::Fill)
+                .height(Length::Fill)
+                .into(),
+            Some(PreviewData::Text(ref content)) => {
+                scrollable(text(content).font(iced::Font::MONOSPACE).size(14))
+                    .width(Length::Fill)
+                    .height(Length::Fill)
+                    .into()
+            }
+            Some(PreviewData::Metadata(ref json)) => {
+                let formatted = serde_json::to_string_pretty(json).unwrap_or_default();
+                scrollable(text(formatted).font(iced::Font::MONOSPACE).size(14))
+                    .width(Length::Fill)
+                    .height(Length::Fill)
+                    .into()
+            }
+            Some(PreviewData::Frames(ref frames)) => self.view_video(frames),
+            Some(PreviewData::None) => Self::centered(
+                column![
+                    text("No preview available").size(14),
+                    button(text("Open"))
+                        .style(iced::theme::Button::Secondary)
+                        .on_press(Message::OpenFile(self.path.clone())),
+                ]
+                .spacing(8)
+                .align_items(iced::Alignment::Center)
+                .into(),
+            ),
+        };
+
+        let mut content = column![header, body].spacing(12);
+        let pictures = matches!(
+            self.data,
+            Some(PreviewData::Image(_) | PreviewData::Viewer(_) | PreviewData::Frames(_))
+        );
+        if pictures {
+            content = content.push(scopes::controls(scope));
+            if let (Some(kind), Some(data)) = (scope, scope_data) {
+                content = content.push(scopes::view(kind, data));
+            }
+        }
+
+        container(content)
+            .width(Length::Fill)
+            .height(Length::Fill)
+            .padding(16)
+            .style(iced::theme::Container::Box)
+            .into()
+    }
+
+    fn view_video<'a>(&'a self, frames: &'a [image::Handle]) -> Element<'a, Message> {
+        let frame = frames.get(self.frame).or(frames.first());
+        let picture: Element<Message> = match frame {
+            Some(handle) => image(handle.clone())
+                .width(Length::Fill)
+                .height(Length::Fill)
+                .into(),
+            None => Self::centered(text("No frames").size(14).into()),
+        };
+
+        let last = frames.len().saturating_sub(1);
+        let controls = row![
+            button(text(if self.playing { "⏸" } else { "▶" }))
+                .style(iced::theme::Button::Primary)
+                .on_press(Message::QuickLookTogglePlay),
+            slider(0.0..=last as f32, self.frame as f32, |f| {
+                Message::QuickLookSeek(f as usize)
+            })
+            .width(Length::Fill),
+            text(format!("{} / {}", self.frame + 1, frames.len())).size(12),
+            button(text("Open in Player"))
+                .style(iced::theme::Button::Secondary)
+                .on_press(Message::OpenFile(self.path.clone())),
+        ]
+        .spacing(8)
+        .align_items(iced::Alignment::Center);
+
+        column![picture, controls].spacing(8).into()
+    }
+
+    fn centered(content: Element<'_, Message>) -> Element<'_, Message> {
+        container(content)
+            .width(Length::Fill)
+            .height(Length::Fill)
+            .center_x()
+            .center_y()
+            .into()
+    }
+}
+
+/// Rasterise the first page of a PDF with poppler. Without a root name,
+/// `pdftoppm` writes the single page to stdout.
+pub async fn render_pdf_page(path: &Path) -> std::io::Result<PreviewData> {
+    let output = tokio::process::Command::new("pdftoppm")
+        .args([
+            "-png",
+            "-f",
+            "1",
+            "-l",
+            "1",
+            "-singlefile",
+            "-scale-to",
+            "1600",
+        ])
+        .arg(path)
+        .output()
+        .await?;
+
+    if !output.status.success() || output.stdout.is_empty() {
+        return Ok(PreviewData::None);
+    }
+    Ok(PreviewData::Image(output.stdout))
+}
+
+/// Evenly spaced frames of a video, cut out of the file handler's filmstrip.
+/// Falls back to no preview when the handler was built without FFmpeg.
+pub async fn load_video_frames(path: PathBuf) -> PreviewData {
+    let frames = tokio::task::spawn_blocking(move || decode_video_frames(&path))
+        .await
+        .ok()
+        .flatten();
+
+    match frames {
+        Some(frames) if !frames.is_empty() => PreviewData::Frames(frames),
+        _ => PreviewData::None,
+    }
+}
+
+/// Stream details for the side pane, flagging HDR (PQ/HLG) clips so they
+/// aren't mistaken for SDR footage.
+pub async fn load_video_metadata(path: PathBuf) -> PreviewData {
+    let info = tokio::task::spawn_blocking(move || MediaHandler::new().ok()?.get_info(&path).ok())
+        .await
+        .ok()
+        .flatten();
+
+    let Some(info) = info else {
+        return PreviewData::None;
+    };
+
+    let hdr = info.video.as_ref().map(|v| v.is_hdr());
+    let mut json = serde_json::to_value(&info).unwrap_or_default();
+    if let (Some(hdr), Some(video)) = (hdr, json.get_mut("video").and_then(|v| v.as_object_mut())) {
+        video.insert("hdr".to_string(), hdr.into());
+    }
+
+    PreviewData::Metadata(json)
+}
+
+/// Audio stream details, plus EBU R128 loudness when `loudness` is set;
+/// measuring decodes the whole file, so only Quick Look asks for it.
+pub async fn load_audio_metadata(path: PathBuf, loudness: bool) -> PreviewData {
+    let info = tokio::task::spawn_blocking(move || {
+        let handler = MediaHandler::new().ok()?;
+        let info = handler.get_audio_metadata(&path).ok()?;
+        let measured = loudness
+            .then(|| handler.analyze_loudness(&path).ok())
+            .flatten();
+        Some((info, measured))
+    })
+    .await
+    .ok()
+    .flatten();
+
+    let Some((info, measured)) = info else {
+        return PreviewData::None;
+    };
+
+    let mut json = serde_json::json!({ "audio": info });
+    if let Some(measured) = measured {
+        json["loudness"] = serde_json::to_value(measured).unwrap_or_default();
+    }
+    PreviewData::Metadata(json)
+}
+
+fn decode_video_frames(path: &Path) -> Option<Vec<image::Handle>> {
+    let handler = MediaHandler::new().ok()?;
+
+    // Keep the source aspect ratio when the stream reports its size
+    let height = handler
+        .get_info(path)
+        .ok()
+        .and_then(|info| info.video)
+        .filter(|v| v.width > 0)
+        .map(|v| VIDEO_FRAME_WIDTH * v.height / v.width)
+        .unwrap_or(VIDEO_FRAME_WIDTH * 9 / 16)
+        .max(1);
+    let size = ThumbnailSize {
+        width: VIDEO_FRAME_WIDTH,
+        height,
+    };
+
+    let frames = handler
+        .filmstrip_frames(path, VIDEO_FRAME_COUNT, size)
+        .ok()?
+        .into_iter()
+        .map(|rgb| {
+            let rgba: Vec<u8> = rgb
+                .chunks_exact(3)
+                .flat_map(|px| [px[0], px[1], px[2], u8::MAX])
+                .collect();
+            image::Handle::from_pixels(size.width, size.height, rgba)
+        })
+        .collect();
+
+    Some(frames)
+}
@@ -0,0 +1,191 @@
+//! Recent and Frequent, the sidebar's computed locations.
+//!
+//! Every file opened from the file manager is counted in
+//! `~/.local/state/rururu-files/recents.json`: when it was last opened and
+//! how often. Recent lists by the former; Frequent by frecency, the open
+//! count weighted by how long ago the file was last opened, so something
+//! opened daily last year gives way to this week's project.
+
+use crate::file_list::FileEntry;
+use crate::listing;
+use serde::{Deserialize, Serialize};
+use std::collections::BTreeMap;
+use std::path::{Path, PathBuf};
+use std::time::{SystemTime, UNIX_EPOCH};
+use tracing::warn;
+
+/// Files remembered; the least frecent go first past this
+const MAX_ENTRIES: usize = 1000;
+/// Files shown in either view
+const VIEW_LIMIT: usize = 100;
+
+const DAY: u64 = 24 * 60 * 60;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum UsageView {
+    Recent,
+    Frequent,
+}
+
+impl UsageView {
+    pub fn title(&self) -> &'static str {
+        match self {
+            UsageView::Recent => "Recent",
+            UsageView::Frequent => "Frequent",
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, Serialize, Deserialize)]
+struct Usage {
+    /// Seconds since the epoch
+    last_opened: u64,
+    open_count: u32,
+}
+
+impl Usage {
+    fn frecency(&self, now: u64) -> u64 {
+        let age = now.saturating_sub(self.last_opened);
+        let weight = match age {
+            a if a < 4 * DAY => 100,
+            a if a < 14 * DAY => 70,
+            a if a < 31 * DAY => 50,
+            a if a < 90 * DAY => 30,
+            _ => 10,
+        };
+        self.open_count as u64 * weight
+    }
+}
+
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+#[serde(transparent)]
+pub struct UsageDatabase {
+    /// Keyed by path; non-UTF-8 paths are never remembered
+    entries: BTreeMap<String, Usage>,
+}
+
+impl UsageDatabase {
+    pub fn db_path() -> PathBuf {
+        dirs::state_dir()
+            .or_else(dirs::data_local_dir)
+            .unwrap_or_else(|| PathBuf::from("."))
+            .join("rururu-files")
+            .join("recents.json")
+    }
+
+    /// Load the usage counts, dropping files that no longer exist
+    pub fn load() -> Self {
+        let path = Self::db_path();
+        let Ok(content) = std::fs::read_to_string(&path) else {
+            return Self::default();
+        };
+
+        let mut db: Self = match serde_json::from_str(&content) {
+            Ok(db) => db,
+            Err(e) => {
+                warn!("Ignoring invalid {:?}: {}", path, e);
+                return Self::default();
+            }
+        };
+
+        let before = db.entries.len();
+        db.entries.retain(|file, _| Path::new(file).exists());
+        if db.entries.len() != before {
+            if let Err(e) = db.save() {
+                warn!("Failed to prune {:?}: {}", path, e);
+            }
+        }
+
+        db
+    }
+
+    pub fn save(&self) -> std::io::Result<()> {
+        let path = Self::db_path();
+        if let Some(parent) = path.parent() {
+            std::fs::create_dir_all(parent)?;
+        }
+        let content = serde_json::to_string(self)?;
+        std::fs::write(path, content)
+    }
+
+    /// Count an open of `path` and save
+    pub fn record_open(&mut self, path: &Path) {
+        // Copies unpacked from archives live in the temp dir and go away
+        if path.starts_with(std::env::temp_dir()) {
+            return;
+        }
+        let Some(key) = path.to_str() else {
+            return;
+        };
+
+        let now = now();
+        let usage = self.entries.entry(key.to_string()).or_insert(Usage {
+            last_opened: now,
+            open_count: 0,
+        });
+        usage.last_opened = now;
+        usage.open_count = usage.open_count.saturating_add(1);
+
+        if self.entries.len() > MAX_ENTRIES {
+            let mut by_frecency: Vec<(u64, String)> = self
+                .entries
+                .iter()
+                .map(|(file, usage)| (usage.frecency(now), file.clone()))
+                .collect();
+            by_frecency.sort();
+            let excess = self.entries.len() - MAX_ENTRIES;
+            for (_, file) in by_frecency.into_iter().take(excess) {
+                self.entries.remove(&file);
+            }
+        }
+
+        if let Err(e) = self.save() {
+            warn!("Failed to save recent files: {}", e);
+        }
+    }
+
+    /// Files for `view`, best first
+    pub fn paths(&self, view: UsageView) -> Vec<PathBuf> {
+        let now = now();
+        let mut entries: Vec<(&String, &Usage)> = self.entries.iter().collect();
+        match view {
+            UsageView::Recent => {
+                entries.sort_by_key(|(_, usage)| std::cmp::Reverse(usage.last_opened))
+            }
+            UsageView::Frequent => entries.sort_by(|a, b| {
+                b.1.frecency(now)
+                    .cmp(&a.1.frecency(now))
+                    .then(b.1.last_opened.cmp(&a.1.last_opened))
+            }),
+        }
+        entries
+            .into_iter()
+            .take(VIEW_LIMIT)
+            .map(|(file, _)| PathBuf::from(file))
+            .collect()
+    }
+}
+
+/// Listing entries for `paths`, in that order, skipping any deleted since
+pub async fn load_entries(paths: Vec<PathBuf>) -> Vec<FileEntry> {
+    tokio::task::spawn_blocking(move || {
+        paths
+            .into_iter()
+            .filter_map(|path| {
+                let is_symlink = std::fs::symlink_metadata(&path)
+                    .map(|m| m.is_symlink())
+                    .unwrap_or(false);
+                listing::stat_entry(path, is_symlink)
+            })
+            .collect()
+    })
+    .await
+    .unwrap_or_default()
+}
+
+fn now() -> u64 {
+    SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .map(|d| d.as_secs())
+        .unwrap_or(0)
+}
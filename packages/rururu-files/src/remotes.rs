@@ -0,0 +1,679 @@
+//! Remote locations, SFTP, SMB and WebDAV shares listed in the sidebar and
+//! mounted when opened.
+//!
+//! Mounting goes through gvfs (`gio mount`), answering its user, domain
+//! and password prompts from the sidebar's login form, and the share is
+//! browsed through the local path gvfs-fuse gives every mount, so listing
+//! works as on any folder. Without gio, SFTP falls back to sshfs with a
+//! mount point under `~/.cache/rururu-files/mounts`. Locations are saved to
+//! `~/.config/rururu-files/remotes.json`; passwords are never stored.
+
+use crate::app::Message;
+use iced::widget::{button, column, row, text, text_input, Space};
+use iced::{Element, Length};
+use serde::{Deserialize, Serialize};
+use std::collections::hash_map::DefaultHasher;
+use std::collections::HashMap;
+use std::fmt;
+use std::hash::{Hash, Hasher};
+use std::io::ErrorKind;
+use std::path::{Path, PathBuf};
+use std::process::Stdio;
+use std::time::Duration;
+use tokio::io::{AsyncReadExt, AsyncWriteExt};
+use tokio::process::Command;
+use tracing::warn;
+
+/// How long a mount may take, including prompts left unanswered
+const MOUNT_TIMEOUT: Duration = Duration::from_secs(60);
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Protocol {
+    Sftp,
+    Smb,
+    WebDav,
+}
+
+impl Protocol {
+    fn of(uri: &str) -> Option<Self> {
+        let (scheme, _) = uri.split_once("://")?;
+        match scheme.to_ascii_lowercase().as_str() {
+            "sftp" | "ssh" => Some(Protocol::Sftp),
+            "smb" => Some(Protocol::Smb),
+            "dav" | "davs" => Some(Protocol::WebDav),
+            _ => None,
+        }
+    }
+
+    fn icon(&self) -> &'static str {
+        match self {
+            Protocol::Sftp => "🔐",
+            Protocol::Smb => "🗄️",
+            Protocol::WebDav => "🌐",
+        }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct RemoteLocation {
+    pub name: String,
+    /// `sftp://`, `smb://`, `dav://` or `davs://` URI, optionally with a
+    /// user name and a folder
+    pub uri: String,
+}
+
+/// Parts of a location's URI
+struct Address<'a> {
+    user: Option<&'a str>,
+    host: &'a str,
+    port: Option<&'a str>,
+    path: &'a str,
+}
+
+impl RemoteLocation {
+    pub fn protocol(&self) -> Option<Protocol> {
+        Protocol::of(&self.uri)
+    }
+
+    /// User name given in the URI
+    pub fn user(&self) -> Option<&str> {
+        self.address().and_then(|a| a.user)
+    }
+
+    fn address(&self) -> Option<Address<'_>> {
+        let (_, rest) = self.uri.split_once("://")?;
+        let (authority, path) = match rest.find('/') {
+            Some(i) => rest.split_at(i),
+            None => (rest, ""),
+        };
+        let (user, host) = match authority.rsplit_once('@') {
+            Some((user, host)) => (Some(user), host),
+            None => (None, authority),
+        };
+        let (host, port) = match host.rsplit_once(':') {
+            Some((host, port)) if port.chars().all(|c| c.is_ascii_digit()) => (host, Some(port)),
+            _ => (host, None),
+        };
+        (!host.is_empty()).then_some(Address {
+            user: user.filter(|u| !u.is_empty()),
+            host,
+            port,
+            path,
+        })
+    }
+}
+
+/// `remotes.json`, in sidebar order
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+#[serde(transparent)]
+pub struct Remotes {
+    locations: Vec<RemoteLocation>,
+}
+
+impl Remotes {
+    pub fn config_path() -> PathBuf {
+        dirs::config_dir()
+            .unwrap_or_else(|| PathBuf::from("."))
+            .join("rururu-files")
+            .join("remotes.json")
+    }
+
+    pub fn load() -> Self {
+        let path = Self::config_path();
+        let Ok(content) = std::fs::read_to_string(&path) else {
+            return Self::default();
+        };
+        serde_json::from_str(&content).unwrap_or_else(|e| {
+            warn!("Ignoring invalid {:?}: {}", path, e);
+            Self::default()
+        })
+    }
+
+    pub fn save(&self) -> std::io::Result<()> {
+        let path = Self::config_path();
+        if let Some(parent) = path.parent() {
+            std::fs::create_dir_all(parent)?;
+        }
+        let content = serde_json::to_string_pretty(self)?;
+        std::fs::write(path, content)
+    }
+
+    pub fn get(&self, name: &str) -> Option<&RemoteLocation> {
+        self.locations.iter().find(|l| l.name == name)
+    }
+
+    /// Add `location`, replacing one with the same name
+    pub fn insert(&mut self, location: RemoteLocation) {
+        match self.locations.iter_mut().find(|l| l.name == location.name) {
+            Some(existing) => *existing = location,
+            None => self.locations.push(location),
+        }
+    }
+
+    pub fn remove(&mut self, name: &str) {
+        self.locations.retain(|l| l.name != name);
+    }
+}
+
+/// A remote location being added in the sidebar
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct RemoteDraft {
+    pub name: String,
+    pub uri: String,
+}
+
+impl RemoteDraft {
+    /// The location this describes, with `http(s)://` taken as WebDAV;
+    /// `None` until it has a name and a URI with a known scheme and a host
+    pub fn build(&self) -> Option<RemoteLocation> {
+        let name = self.name.trim();
+        let uri = self.uri.trim();
+        let uri = if let Some(rest) = uri.strip_prefix("https://") {
+            format!("davs://{}", rest)
+        } else if let Some(rest) = uri.strip_prefix("http://") {
+            format!("dav://{}", rest)
+        } else {
+            uri.to_string()
+        };
+
+        let location = RemoteLocation {
+            name: name.to_string(),
+            uri,
+        };
+        (!name.is_empty() && location.protocol().is_some() && location.address().is_some())
+            .then_some(location)
+    }
+}
+
+#[derive(Clone, Default, PartialEq, Eq)]
+pub struct Credentials {
+    /// Empty takes the URI's user name
+    pub username: String,
+    /// SMB workgroup or domain; empty takes the server's default
+    pub domain: String,
+    pub password: String,
+}
+
+impl fmt::Debug for Credentials {
+    // Messages are logged, so the password stays out
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("Credentials")
+            .field("username", &self.username)
+            .field("domain", &self.domain)
+            .finish_non_exhaustive()
+    }
+}
+
+/// The login form shown when a server asks for a password
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct AuthPrompt {
+    pub name: String,
+    pub credentials: Credentials,
+    pub protocol: Option<Protocol>,
+    /// Why the last attempt failed
+    pub error: Option<String>,
+}
+
+impl AuthPrompt {
+    pub fn new(location: &RemoteLocation, error: Option<String>) -> Self {
+        Self {
+            name: location.name.clone(),
+            credentials: Credentials {
+                username: location.user().unwrap_or_default().to_string(),
+                ..Credentials::default()
+            },
+            protocol: location.protocol(),
+            error,
+        }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum MountError {
+    /// The server wants a password and none was given
+    NeedsLogin,
+    /// The password or user name was refused
+    LoginFailed,
+    Failed(String),
+}
+
+/// Sidebar state of the remote locations
+#[derive(Debug, Default)]
+pub struct RemotePanel {
+    pub remotes: Remotes,
+    /// Local paths of the mounted locations, by name
+    pub mounts: HashMap<String, PathBuf>,
+    /// Location being mounted
+    pub connecting: Option<String>,
+    pub draft: Option<RemoteDraft>,
+    pub auth: Option<AuthPrompt>,
+}
+
+impl RemotePanel {
+    pub fn load() -> Self {
+        Self {
+            remotes: Remotes::load(),
+            ..Self::default()
+        }
+    }
+
+    /// Name of the mounted location `path` is in
+    pub fn location_of(&self, path: &Path) -> Option<&str> {
+        self.mounts
+            .iter()
+            .find(|(_, mount)| path.starts_with(mount))
+            .map(|(name, _)| name.as_str())
+    }
+}
+
+/// Mount `location` unless it already is, returning its local path
+pub async fn mount(
+    location: RemoteLocation,
+    credentials: Option<Credentials>,
+) -> Result<PathBuf, MountError> {
+    if let Some(path) = gvfs_path(&location.uri).await {
+        return Ok(path);
+    }
+
+    let child = Command::new("gio")
+        .args(["mount", &location.uri])
+        .stdin(Stdio::piped())
+        .stdout(Stdio::piped())
+        .stderr(Stdio::piped())
+        .kill_on_drop(true)
+        .spawn();
+    let child = match child {
+        Ok(child) => child,
+        Err(e) if e.kind() == ErrorKind::NotFound => {
+            return match location.protocol() {
+                Some(Protocol::Sftp) => sshfs_mount(&location, credentials).await,
+                _ => Err(MountError::Failed(
+                    "gio isn't installed; install gvfs to connect to shares".to_string(),
+                )),
+            };
+        }
+        Err(e) => return Err(MountError::Failed(e.to_string())),
+    };
+
+    match tokio::time::timeout(MOUNT_TIMEOUT, answer_prompts(child, credentials)).await {
+        Ok(Ok(())) => {}
+        Ok(Err(e)) => return Err(e),
+        Err(_) => return Err(MountError::Failed("The server didn't answer".to_string())),
+    }
+
+    gvfs_path(&location.uri).await.ok_or_else(|| {
+        MountError::Failed(
+            "Mounted, but gvfs-fuse isn't running to give it a local path".to_string(),
+        )
+    })
+}
+
+/// Answer `gio mount`'s prompts until it exits
+async fn answer_prompts(
+    mut child: tokio::process::Child,
+    credentials: Option<Credentials>,
+) -> Result<(), MountError> {
+    let mut stdin = child.stdin.take();
+    let mut stdout = child.stdout.take().ok_or(MountError::NeedsLogin)?;
+    let mut output = Vec::new();
+    let mut answered = 0;
+    let mut passwords_sent = 0;
+    let mut buf = [0u8; 1024];
+
+    loop {
+        let n = stdout
+            .read(&mut buf)
+            .await
+            .map_err(|e| MountError::Failed(e.to_string()))?;
+        if n == 0 {
+            break;
+        }
+        output.extend_from_slice(&buf[..n]);
+
+        // Prompts end the output without a newline, e.g. "User [bob]: "
+        let text = String::from_utf8_lossy(&output[answered..]).to_string();
+        let prompt = text.rsplit('\n').next().unwrap_or("").trim().to_lowercase();
+        if !prompt.ends_with(':') {
+            continue;
+        }
+        answered = output.len();
+
+        let answer = if prompt.starts_with("password") {
+            let Some(ref credentials) = credentials else {
+                return Err(MountError::NeedsLogin);
+            };
+            // Asked again after one was sent: it was refused
+            if passwords_sent > 0 {
+                return Err(MountError::LoginFailed);
+            }
+            passwords_sent += 1;
+            credentials.password.clone()
+        } else if prompt.starts_with("user") {
+            credentials
+                .as_ref()
+                .map(|c| c.username.clone())
+                .unwrap_or_default()
+        } else if prompt.starts_with("domain") {
+            credentials
+                .as_ref()
+                .map(|c| c.domain.clone())
+                .unwrap_or_default()
+        } else {
+            // A question such as an unknown host key; answered in a terminal
+            return Err(MountError::Failed(format!(
+                "{} Run `gio mount` in a terminal once to answer it.",
+                text.trim()
+            )));
+        };
+
+        if let Some(ref mut stdin) = stdin {
+            let line = format!("{}\n", answer);
+            stdin
+                .write_all(line.as_bytes())
+                .await
+                .map_err(|e| MountError::Failed(e.to_string()))?;
+        }
+    }
+
+    let result = child
+        .wait_with_output()
+        .await
+        .map_err(|e| MountError::Failed(e.to_string()))?;
+    if result.status.success() {
+        return Ok(());
+    }
+    let stderr = String::from_utf8_lossy(&result.stderr).trim().to_string();
+    if stderr.contains("already mounted") {
+        return Ok(());
+    }
+    if passwords_sent > 0 && stderr.to_lowercase().contains("permission denied") {
+        return Err(MountError::LoginFailed);
+    }
+    Err(MountError::Failed(
+        stderr.strip_prefix("gio: ").unwrap_or(&stderr).to_string(),
+    ))
+}
+
+/// FUSE path gvfs gives a mounted URI, from `gio info`'s "local path"
+async fn gvfs_path(uri: &str) -> Option<PathBuf> {
+    let output = Command::new("gio")
+        .args(["info", uri])
+        .output()
+        .await
+        .ok()?;
+    if !output.status.success() {
+        return None;
+    }
+    String::from_utf8_lossy(&output.stdout)
+        .lines()
+        .find_map(|line| line.strip_prefix("local path: "))
+        .map(|path| PathBuf::from(path.trim()))
+}
+
+/// The name keeps the folder recognisable, the URI's hash keeps names that
+/// only differ in punctuation, like "a b" and "a_b", apart
+fn sshfs_mount_point(location: &RemoteLocation) -> PathBuf {
+    let name: String = location
+        .name
+        .chars()
+        .map(|c| if c.is_alphanumeric() { c } else { '_' })
+        .collect();
+    let mut hasher = DefaultHasher::new();
+    location.uri.hash(&mut hasher);
+    dirs::cache_dir()
+        .unwrap_or_else(std::env::temp_dir)
+        .join("rururu-files")
+        .join("mounts")
+        .join(format!("{}-{:016x}", name, hasher.finish()))
+}
+
+fn is_mount_point(path: &Path) -> bool {
+    let Ok(mounts) = std::fs::read_to_string("/proc/self/mounts") else {
+        return false;
+    };
+    let path = path.to_string_lossy();
+    // Mount points escape spaces as \040
+    let path = path.replace(' ', "\\040");
+    mounts
+        .lines()
+        .any(|line| line.split_whitespace().nth(1) == Some(path.as_str()))
+}
+
+async fn sshfs_mount(
+    location: &RemoteLocation,
+    credentials: Option<Credentials>,
+) -> Result<PathBuf, MountError> {
+    let Some(address) = location.address() else {
+        return Err(MountError::Failed(format!("{} has no host", location.uri)));
+    };
+    let mount_point = sshfs_mount_point(location);
+    if is_mount_point(&mount_point) {
+        return Ok(mount_point);
+    }
+    std::fs::create_dir_all(&mount_point).map_err(|e| MountError::Failed(e.to_string()))?;
+
+    let user = credentials
+        .as_ref()
+        .map(|c| c.username.as_str())
+        .filter(|u| !u.is_empty())
+        .or(address.user);
+    let remote = match user {
+        Some(user) => format!("{}@{}:{}", user, address.host, address.path),
+        None => format!("{}:{}", address.host, address.path),
+    };
+
+    let mut command = Command::new("sshfs");
+    command.arg(&remote).arg(&mount_point);
+    if let Some(port) = address.port {
+        command.args(["-p", port]);
+    }
+    // Keys only until a password is given, so ssh never waits on a terminal
+    match credentials {
+        Some(_) => command.args(["-o", "password_stdin"]),
+        None => command.args(["-o", "BatchMode=yes"]),
+    };
+    let mut child = command
+        .stdin(Stdio::piped())
+        .stdout(Stdio::null())
+        .stderr(Stdio::piped())
+        .kill_on_drop(true)
+        .spawn()
+        .map_err(|e| match e.kind() {
+            ErrorKind::NotFound => MountError::Failed(
+                "Neither gio nor sshfs is installed; install gvfs or sshfs".to_string(),
+            ),
+            _ => MountError::Failed(e.to_string()),
+        })?;
+    if let (Some(mut stdin), Some(ref credentials)) = (child.stdin.take(), &credentials) {
+        let line = format!("{}\n", credentials.password);
+        let _ = stdin.write_all(line.as_bytes()).await;
+    }
+
+    let output = tokio::time::timeout(MOUNT_TIMEOUT, child.wait_with_output())
+        .await
+        .map_err(|_| MountError::Failed("The server didn't answer".to_string()))?
+        .map_err(|e| MountError::Failed(e.to_string()))?;
+    if output.status.success() {
+        return Ok(mount_point);
+    }
+    let stderr = String::from_utf8_lossy(&output.stderr).trim().to_string();
+    if stderr.contains("Permission denied") {
+        return Err(match credentials {
+            Some(_) => MountError::LoginFailed,
+            None => MountError::NeedsLogin,
+        });
+    }
+    Err(MountError::Failed(stderr))
+}
+
+/// Unmount `location`, mounted at `path`
+pub async fn unmount(location: RemoteLocation, path: PathBuf) -> Result<(), String> {
+    let output = if path.starts_with(sshfs_mount_point(&location)) {
+        let output = Command::new("fusermount3")
+            .args(["-u"])
+            .arg(&path)
+            .output()
+            .await;
+        match output {
+            Err(e) if e.kind() == ErrorKind::NotFound => {
+                Command::new("fusermount")
+                    .args(["-u"])
+                    .arg(&path)
+                    .output()
+                    .await
+            }
+            output => output,
+        }
+    } else {
+        Command::new("gio")
+            .args(["mount", "-u", &location.uri])
+            .output()
+            .await
+    };
+
+    let output = output.map_err(|e| e.to_string())?;
+    if output.status.success() {
+        Ok(())
+    } else {
+        Err(String::from_utf8_lossy(&output.stderr).trim().to_string())
+    }
+}
+
+/// The sidebar's Network section, with the add form and login form while
+/// they are open
+pub fn view<'a>(panel: &'a RemotePanel, current_path: &Path) -> Element<'a, Message> {
+    let mut items: Vec<Element<Message>> = vec![row![
+        text("Network").size(14),
+        Space::with_width(Length::Fill),
+        button(text("+").size(12))
+            .style(iced::theme::Button::Text)
+            .on_press(Message::EditRemote(Some(RemoteDraft::default()))),
+    ]
+    .align_items(iced::Alignment::Center)
+    .into()];
+
+    let shown = panel.location_of(current_path);
+    for location in &panel.remotes.locations {
+        let name = location.name.as_str();
+        let icon = location.protocol().map(|p| p.icon()).unwrap_or("🌐");
+        let label = if panel.connecting.as_deref() == Some(name) {
+            format!("{} {} …", icon, name)
+        } else {
+            format!("{} {}", icon, name)
+        };
+
+        let mut entry = row![button(text(label))
+            .style(if shown == Some(name) {
+                iced::theme::Button::Primary
+            } else {
+                iced::theme::Button::Text
+            })
+            .width(Length::Fill)
+            .on_press(Message::ConnectRemote(location.name.clone()))]
+        .align_items(iced::Alignment::Center);
+        if panel.mounts.contains_key(name) {
+            entry = entry.push(
+                button(text("⏏").size(10))
+                    .style(iced::theme::Button::Text)
+                    .on_press(Message::UnmountRemote(location.name.clone())),
+            );
+        }
+        entry = entry.push(
+            button(text("×").size(10))
+                .style(iced::theme::Button::Text)
+                .on_press(Message::RemoveRemote(location.name.clone())),
+        );
+        items.push(entry.into());
+
+        if let Some(ref auth) = panel.auth {
+            if auth.name == location.name {
+                items.push(login_form(auth));
+            }
+        }
+    }
+
+    if let Some(ref draft) = panel.draft {
+        items.push(editor(draft));
+    }
+
+    column(items).spacing(2).into()
+}
+
+fn editor(draft: &RemoteDraft) -> Element<'_, Message> {
+    let edit = |change: fn(&mut RemoteDraft, String)| {
+        move |value: String| {
+            let mut draft = draft.clone();
+            change(&mut draft, value);
+            Message::EditRemote(Some(draft))
+        }
+    };
+
+    column![
+        text_input("Name", &draft.name)
+            .on_input(edit(|d, v| d.name = v))
+            .size(12),
+        text_input("sftp://user@host/path", &draft.uri)
+            .on_input(edit(|d, v| d.uri = v))
+            .on_submit(Message::SaveRemote)
+            .size(12),
+        text("SFTP, SMB (smb://server/share) or WebDAV (davs://)").size(10),
+        row![
+            button(text("Add").size(12))
+                .style(iced::theme::Button::Primary)
+                .on_press_maybe(draft.build().map(|_| Message::SaveRemote)),
+            button(text("Cancel").size(12))
+                .style(iced::theme::Button::Text)
+                .on_press(Message::EditRemote(None)),
+        ]
+        .spacing(4),
+    ]
+    .spacing(4)
+    .padding([4, 0])
+    .into()
+}
+
+fn login_form(auth: &AuthPrompt) -> Element<'_, Message> {
+    let edit = |change: fn(&mut Credentials, String)| {
+        move |value: String| {
+            let mut auth = auth.clone();
+            change(&mut auth.credentials, value);
+            auth.error = None;
+            Message::EditRemoteAuth(Some(auth))
+        }
+    };
+
+    let mut form = column![
+        text(auth.error.as_deref().unwrap_or("Log in to connect")).size(11),
+        text_input("User name", &auth.credentials.username)
+            .on_input(edit(|c, v| c.username = v))
+            .size(12),
+    ]
+    .spacing(4)
+    .padding([4, 0]);
+    if auth.protocol == Some(Protocol::Smb) {
+        form = form.push(
+            text_input("Domain (optional)", &auth.credentials.domain)
+                .on_input(edit(|c, v| c.domain = v))
+                .size(12),
+        );
+    }
+    form.push(
+        text_input("Password", &auth.credentials.password)
+            .secure(true)
+            .on_input(edit(|c, v| c.password = v))
+            .on_submit(Message::SubmitRemoteAuth)
+            .size(12),
+    )
+    .push(
+        row![
+            button(text("Connect").size(12))
+                .style(iced::theme::Button::Primary)
+                .on_press(Message::SubmitRemoteAuth),
+            button(text("Cancel").size(12))
+                .style(iced::theme::Button::Text)
+                .on_press(Message::EditRemoteAuth(None)),
+        ]
+        .spacing(4),
+    )
+    .into()
+}
@@ -0,0 +1,303 @@
+//! Waveform, parade, vectorscope and histogram under the image preview
+//! and in Quick Look, drawn from rururu-color's scope analysis of what is
+//! shown.
+
+use crate::app::Message;
+use iced::advanced::image::Data;
+use iced::mouse;
+use iced::widget::canvas::{self, Frame, Geometry, Path, Stroke, Text};
+use iced::widget::image::Handle;
+use iced::widget::{button, row, text};
+use iced::{Color, Element, Length, Point, Rectangle, Renderer, Size, Theme};
+use rururu_color::scopes::{self, ScopeOptions, Scopes, Vectorscope, Waveform};
+use rururu_file_handler::orientation::orientation_from_bytes;
+use std::fmt;
+
+const SCOPE_HEIGHT: f32 = 160.0;
+
+/// Longest side encoded images are analyzed at
+const ANALYSIS_SIZE: u32 = 1024;
+
+const BACKGROUND: Color = Color::from_rgb(0.06, 0.06, 0.06);
+const GRATICULE: Color = Color::from_rgba(0.6, 0.6, 0.6, 0.35);
+const TRACE: Color = Color::from_rgb(0.75, 0.95, 0.75);
+const CHANNELS: [Color; 3] = [
+    Color::from_rgb(1.0, 0.3, 0.3),
+    Color::from_rgb(0.3, 1.0, 0.3),
+    Color::from_rgb(0.4, 0.5, 1.0),
+];
+
+/// Trace brightness of the faintest sample, so lone pixels stay visible
+const MIN_INTENSITY: f32 = 0.2;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ScopeKind {
+    Waveform,
+    Parade,
+    Vectorscope,
+    Histogram,
+}
+
+impl ScopeKind {
+    pub const ALL: [ScopeKind; 4] = [
+        ScopeKind::Waveform,
+        ScopeKind::Parade,
+        ScopeKind::Vectorscope,
+        ScopeKind::Histogram,
+    ];
+}
+
+impl fmt::Display for ScopeKind {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(match self {
+            ScopeKind::Waveform => "Waveform",
+            ScopeKind::Parade => "Parade",
+            ScopeKind::Vectorscope => "Vectorscope",
+            ScopeKind::Histogram => "Histogram",
+        })
+    }
+}
+
+/// Coarser than the analysis default: every counted cell is a rectangle
+/// drawn on each frame
+fn options() -> ScopeOptions {
+    ScopeOptions {
+        columns: 192,
+        resolution: 128,
+        ..ScopeOptions::default()
+    }
+}
+
+/// Scopes of decoded pixels, as the viewer and video frames hold them
+pub fn of_handle(handle: &Handle) -> Option<Scopes> {
+    match handle.data() {
+        Data::Rgba { width, pixels, .. } => {
+            scopes::analyze_u8(pixels, 4, *width as usize, options()).ok()
+        }
+        _ => None,
+    }
+}
+
+/// Scopes of an encoded image, turned upright as the preview shows it.
+/// Slow for large pictures, so keep it off the UI thread.
+pub fn of_encoded(bytes: &[u8]) -> Option<Scopes> {
+    // The scope reads a few hundred columns; shrinking first keeps
+    // turning the picture upright cheap
+    let img = ::image::load_from_memory(bytes)
+        .ok()?
+        .thumbnail(ANALYSIS_SIZE, ANALYSIS_SIZE);
+    let img = orientation_from_bytes(bytes)
+        .unwrap_or_default()
+        .apply(img)
+        .to_rgba8();
+    scopes::analyze_u8(img.as_raw(), 4, img.width() as usize, options()).ok()
+}
+
+/// Buttons picking the scope; the shown one turns it off again
+pub fn controls(shown: Option<ScopeKind>) -> Element<'static, Message> {
+    let mut options = row![].spacing(4);
+    for kind in ScopeKind::ALL {
+        let (style, message) = if shown == Some(kind) {
+            (iced::theme::Button::Primary, Message::SetScope(None))
+        } else {
+            (iced::theme::Button::Text, Message::SetScope(Some(kind)))
+        };
+        options = options.push(
+            button(text(kind.to_string()).size(11))
+                .style(style)
+                .on_press(message),
+        );
+    }
+    row![text("Scopes:").size(11), options]
+        .spacing(8)
+        .align_items(iced::Alignment::Center)
+        .into()
+}
+
+pub fn view(kind: ScopeKind, scopes: &Scopes) -> Element<'_, Message> {
+    canvas::Canvas::new(ScopeCanvas { kind, scopes })
+        .width(Length::Fill)
+        .height(Length::Fixed(SCOPE_HEIGHT))
+        .into()
+}
+
+struct ScopeCanvas<'a> {
+    kind: ScopeKind,
+    scopes: &'a Scopes,
+}
+
+impl canvas::Program<Message> for ScopeCanvas<'_> {
+    type State = ();
+
+    fn draw(
+        &self,
+        _state: &(),
+        renderer: &Renderer,
+        _theme: &Theme,
+        bounds: Rectangle,
+        _cursor: mouse::Cursor,
+    ) -> Vec<Geometry> {
+        let mut frame = Frame::new(renderer, bounds.size());
+        frame.fill_rectangle(Point::ORIGIN, bounds.size(), BACKGROUND);
+
+        let area = Rectangle::new(Point::ORIGIN, bounds.size());
+        match self.kind {
+            ScopeKind::Waveform => {
+                draw_levels(&mut frame, area);
+                draw_waveform(&mut frame, area, &self.scopes.waveform, TRACE);
+            }
+            ScopeKind::Parade => {
+                let width = area.width / 3.0;
+                for (i, (trace, color)) in self.scopes.parade.iter().zip(CHANNELS).enumerate() {
+                    let part = Rectangle {
+                        x: i as f32 * width,
+                        width: width - 2.0,
+                        ..area
+                    };
+                    draw_levels(&mut frame, part);
+                    draw_waveform(&mut frame, part, trace, color);
+                }
+            }
+            ScopeKind::Vectorscope => {
+                // Square, in the middle
+                let side = area.width.min(area.height);
+                let square = Rectangle {
+                    x: (area.width - side) / 2.0,
+                    y: (area.height - side) / 2.0,
+                    width: side,
+                    height: side,
+                };
+                draw_vectorscope(&mut frame, square, &self.scopes.vectorscope);
+            }
+            ScopeKind::Histogram => draw_histogram(&mut frame, area, self.scopes),
+        }
+
+        vec![frame.into_geometry()]
+    }
+}
+
+/// Graticule lines at every 25% of the signal
+fn draw_levels(frame: &mut Frame, area: Rectangle) {
+    for quarter in 0..=4 {
+        let y = area.y + area.height * (1.0 - quarter as f32 / 4.0);
+        let line = Path::line(Point::new(area.x, y), Point::new(area.x + area.width, y));
+        frame.stroke(&line, Stroke::default().with_color(GRATICULE));
+    }
+}
+
+fn draw_waveform(frame: &mut Frame, area: Rectangle, trace: &Waveform, color: Color) {
+    let peak = trace.peak();
+    let cell = Size::new(
+        area.width / trace.columns as f32,
+        area.height / trace.levels as f32,
+    );
+    for level in 0..trace.levels {
+        for column in 0..trace.columns {
+            let count = trace.count(column, level);
+            if count == 0 {
+                continue;
+            }
+            let point = Point::new(
+                area.x + column as f32 * cell.width,
+                area.y + area.height - (level + 1) as f32 * cell.height,
+            );
+            frame.fill_rectangle(point, cell, dimmed(color, count, peak));
+        }
+    }
+}
+
+fn draw_vectorscope(frame: &mut Frame, area: Rectangle, scope: &Vectorscope) {
+    let at = |(x, y): (f32, f32)| Point::new(area.x + x * area.width, area.y + y * area.height);
+
+    let center = at((0.5, 0.5));
+    frame.stroke(
+        &Path::circle(center, area.width / 2.0),
+        Stroke::default().with_color(GRATICULE),
+    );
+    frame.stroke(
+        &Path::line(at((0.0, 0.5)), at((1.0, 0.5))),
+        Stroke::default().with_color(GRATICULE),
+    );
+    frame.stroke(
+        &Path::line(at((0.5, 0.0)), at((0.5, 1.0))),
+        Stroke::default().with_color(GRATICULE),
+    );
+
+    let peak = scope.peak();
+    let cell = Size::new(
+        area.width / scope.size as f32,
+        area.height / scope.size as f32,
+    );
+    for y in 0..scope.size {
+        for x in 0..scope.size {
+            let count = scope.count(x, y);
+            if count > 0 {
+                let point = Point::new(
+                    area.x + x as f32 * cell.width,
+                    area.y + y as f32 * cell.height,
+                );
+                frame.fill_rectangle(point, cell, dimmed(TRACE, count, peak));
+            }
+        }
+    }
+
+    for (name, position) in Vectorscope::targets() {
+        let target = at(position);
+        let size = area.width / 24.0;
+        frame.stroke(
+            &Path::rectangle(
+                Point::new(target.x - size / 2.0, target.y - size / 2.0),
+                Size::new(size, size),
+            ),
+            Stroke::default().with_color(GRATICULE),
+        );
+        frame.fill_text(Text {
+            content: name.to_string(),
+            position: Point::new(target.x + size, target.y - size),
+            color: GRATICULE,
+            size: 10.0.into(),
+            ..Text::default()
+        });
+    }
+}
+
+/// Channels as outlines over a filled luma histogram
+fn draw_histogram(frame: &mut Frame, area: Rectangle, scopes: &Scopes) {
+    let histogram = &scopes.histogram;
+    let peak = histogram.peak().max(1) as f32;
+    let step = area.width / histogram.bins as f32;
+    let height = |count: u32| area.height * (1.0 - count as f32 / peak);
+
+    for (i, &count) in histogram.luma.iter().enumerate() {
+        if count > 0 {
+            let top = height(count);
+            frame.fill_rectangle(
+                Point::new(area.x + i as f32 * step, top),
+                Size::new(step, area.height - top),
+                GRATICULE,
+            );
+        }
+    }
+
+    let channels = [&histogram.red, &histogram.green, &histogram.blue];
+    for (bins, color) in channels.into_iter().zip(CHANNELS) {
+        let outline = Path::new(|path| {
+            path.move_to(Point::new(area.x, area.height));
+            for (i, &count) in bins.iter().enumerate() {
+                path.line_to(Point::new(area.x + (i as f32 + 0.5) * step, height(count)));
+            }
+            path.line_to(Point::new(area.x + area.width, area.height));
+        });
+        frame.stroke(&outline, Stroke::default().with_color(color));
+    }
+}
+
+/// `color` at a brightness growing with the log of `count`, the way a
+/// scope's phosphor saturates
+fn dimmed(color: Color, count: u32, peak: u32) -> Color {
+    let intensity = (count as f32).ln_1p() / (peak.max(1) as f32).ln_1p();
+    Color {
+        a: intensity.max(MIN_INTENSITY),
+        ..color
+    }
+}
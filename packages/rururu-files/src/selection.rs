@@ -0,0 +1,115 @@
+use iced::keyboard::Modifiers;
+use std::path::{Path, PathBuf};
+
+/// Ordered set of selected paths in the current directory listing.
+///
+/// Paths are kept in the order they were selected; the last one drives the
+/// preview. `anchor` is where a Shift+click range starts from.
+#[derive(Debug, Clone, Default)]
+pub struct Selection {
+    items: Vec<PathBuf>,
+    anchor: Option<PathBuf>,
+}
+
+impl Selection {
+    /// Apply a click on `path` in `files`, using the held modifiers to decide
+    /// between replace, toggle (Ctrl) and range (Shift) selection.
+    pub fn click(&mut self, files: &[PathBuf], path: PathBuf, modifiers: Modifiers) {
+        if modifiers.shift() {
+            self.select_range(files, path, modifiers.command());
+        } else if modifiers.command() {
+            self.toggle(path);
+        } else {
+            self.select_only(path);
+        }
+    }
+
+    pub fn select_only(&mut self, path: PathBuf) {
+        self.items.clear();
+        self.items.push(path.clone());
+        self.anchor = Some(path);
+    }
+
+    pub fn toggle(&mut self, path: PathBuf) {
+        if let Some(pos) = self.items.iter().position(|p| p == &path) {
+            self.items.remove(pos);
+        } else {
+            self.items.push(path.clone());
+        }
+        self.anchor = Some(path);
+    }
+
+    /// Select everything between the anchor and `path` in listing order.
+    /// With `extend` the range is added to the current selection instead of
+    /// replacing it.
+    pub fn select_range(&mut self, files: &[PathBuf], path: PathBuf, extend: bool) {
+        let start = self
+            .anchor
+            .as_ref()
+            .and_then(|a| files.iter().position(|f| f == a));
+        let end = files.iter().position(|f| f == &path);
+
+        let (start, end) = match (start, end) {
+            (Some(start), Some(end)) => (start, end),
+            _ => {
+                self.select_only(path);
+                return;
+            }
+        };
+
+        if !extend {
+            self.items.clear();
+        }
+
+        // Walk from the anchor towards the click so the clicked item ends
+        // up last and becomes the previewed one
+        let range: Box<dyn Iterator<Item = usize>> = if start <= end {
+            Box::new(start..=end)
+        } else {
+            Box::new((end..=start).rev())
+        };
+        for i in range {
+            let file = &files[i];
+            if let Some(pos) = self.items.iter().position(|p| p == file) {
+                self.items.remove(pos);
+            }
+            self.items.push(file.clone());
+        }
+    }
+
+    pub fn select_all(&mut self, files: &[PathBuf]) {
+        self.items = files.to_vec();
+        self.anchor = files.first().cloned();
+    }
+
+    pub fn clear(&mut self) {
+        self.items.clear();
+        self.anchor = None;
+    }
+
+    /// Drop paths that are no longer in the listing, e.g. after a refresh
+    pub fn retain_existing(&mut self, files: &[PathBuf]) {
+        self.items.retain(|p| files.contains(p));
+        if let Some(ref anchor) = self.anchor {
+            if !files.contains(anchor) {
+                self.anchor = self.items.last().cloned();
+            }
+        }
+    }
+
+    pub fn contains(&self, path: &Path) -> bool {
+        self.items.iter().any(|p| p == path)
+    }
+
+    pub fn last(&self) -> Option<&PathBuf> {
+        self.items.last()
+    }
+
+    pub fn paths(&self) -> &[PathBuf] {
+        &self.items
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.items.is_empty()
+    }
+}
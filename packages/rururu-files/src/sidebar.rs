@@ -1,4 +1,7 @@
 use crate::app::Message;
+use crate::recents::UsageView;
+use crate::remotes::{self, RemotePanel};
+use crate::smart_folders::{self, SmartFolderDraft, SmartFolders};
 use iced::widget::{button, column, container, scrollable, text, Space};
 use iced::{Element, Length};
 use std::path::PathBuf;
@@ -6,13 +9,39 @@ use std::path::PathBuf;
 pub struct Sidebar;
 
 impl Sidebar {
-    pub fn view<'a>(bookmarks: &'a [PathBuf], current_path: &'a PathBuf) -> Element<'a, Message> {
+    #[allow(clippy::too_many_arguments)]
+    pub fn view<'a>(
+        bookmarks: &'a [PathBuf],
+        current_path: &'a PathBuf,
+        in_trash: bool,
+        usage_view: Option<UsageView>,
+        smart_folders: &'a SmartFolders,
+        smart_folder: Option<&str>,
+        smart_folder_draft: Option<&'a SmartFolderDraft>,
+        remotes: &'a RemotePanel,
+    ) -> Element<'a, Message> {
         let mut items: Vec<Element<Message>> = Vec::new();
+        // Browsing `current_path` rather than a computed location
+        let in_folder = !in_trash && usage_view.is_none() && smart_folder.is_none();
 
         // Places header
         items.push(text("Places").size(14).into());
         items.push(Space::with_height(Length::Fixed(8.0)).into());
 
+        for (icon, view) in [("🕘", UsageView::Recent), ("⭐", UsageView::Frequent)] {
+            items.push(
+                button(text(format!("{} {}", icon, view.title())))
+                    .style(if usage_view == Some(view) {
+                        iced::theme::Button::Primary
+                    } else {
+                        iced::theme::Button::Text
+                    })
+                    .width(Length::Fill)
+                    .on_press(Message::ShowUsage(view))
+                    .into(),
+            );
+        }
+
         // Default locations
         let places = [
             ("🏠", "Home", dirs::home_dir()),
@@ -27,7 +56,7 @@ impl Sidebar {
         for (icon, name, path_opt) in places {
             if let Some(path) = path_opt {
                 if path.exists() {
-                    let is_current = &path == current_path;
+                    let is_current = in_folder && &path == current_path;
                     let path_clone = path.clone();
 
                     let style = if is_current {
@@ -47,6 +76,25 @@ impl Sidebar {
             }
         }
 
+        items.push(
+            button(text("🗑️ Trash"))
+                .style(if in_trash {
+                    iced::theme::Button::Primary
+                } else {
+                    iced::theme::Button::Text
+                })
+                .width(Length::Fill)
+                .on_press(Message::ShowTrash)
+                .into(),
+        );
+
+        items.push(Space::with_height(Length::Fixed(16.0)).into());
+        items.push(smart_folders::view(
+            smart_folders,
+            smart_folder,
+            smart_folder_draft,
+        ));
+
         // Separator
         items.push(Space::with_height(Length::Fixed(16.0)).into());
 
@@ -84,6 +132,10 @@ impl Sidebar {
             );
         }
 
+        // SFTP, SMB and WebDAV shares
+        items.push(Space::with_height(Length::Fixed(16.0)).into());
+        items.push(remotes::view(remotes, current_path));
+
         // Bookmarks section
         if !bookmarks.is_empty() {
             items.push(Space::with_height(Length::Fixed(16.0)).into());
@@ -97,7 +149,7 @@ impl Sidebar {
                         .and_then(|n| n.to_str())
                         .unwrap_or("Unknown");
 
-                    let is_current = bookmark == current_path;
+                    let is_current = in_folder && bookmark == current_path;
                     let path_clone = bookmark.clone();
 
                     let style = if is_current {
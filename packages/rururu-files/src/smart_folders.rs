@@ -0,0 +1,290 @@
+//! Smart folders, saved tag searches listed in the sidebar.
+//!
+//! A smart folder names tags a file must all carry, optionally narrowed to
+//! one folder tree, to names containing some text, or to one kind of file.
+//! Opening it lists the matches like a folder. They are saved to
+//! `~/.local/share/rururu-files/smart-folders.json`, next to the tag
+//! database.
+
+use crate::app::Message;
+use crate::file_list::FileEntry;
+use crate::listing;
+use crate::tags::TagDatabase;
+use crate::tools;
+use iced::widget::{button, checkbox, column, pick_list, row, text, text_input, Space};
+use iced::{Element, Length};
+use rururu_file_handler::FileDetector;
+use serde::{Deserialize, Serialize};
+use std::path::{Path, PathBuf};
+use tracing::warn;
+
+/// Kinds a smart folder can be narrowed to, named as in [`tools::category_name`]
+const KINDS: &[&str] = &[
+    "any",
+    "image",
+    "video",
+    "audio",
+    "document",
+    "model3d",
+    "archive",
+    "code",
+    "project",
+    "subtitle",
+    "edit-exchange",
+];
+
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct SmartFolder {
+    pub name: String,
+    /// A file is listed when it carries every one
+    pub tags: Vec<String>,
+    /// Only files under this folder
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub scope: Option<PathBuf>,
+    /// Text the file name contains, ignoring case
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub name_filter: Option<String>,
+    /// File category, as named by [`tools::category_name`]
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub kind: Option<String>,
+}
+
+impl SmartFolder {
+    /// Whether `path`, already known to carry the tags, passes the other
+    /// filters
+    fn matches(&self, path: &Path, detector: &FileDetector) -> bool {
+        if let Some(scope) = &self.scope {
+            if !path.starts_with(scope) {
+                return false;
+            }
+        }
+        if let Some(filter) = &self.name_filter {
+            let name = path
+                .file_name()
+                .map(|n| n.to_string_lossy().to_lowercase())
+                .unwrap_or_default();
+            if !name.contains(&filter.to_lowercase()) {
+                return false;
+            }
+        }
+        match &self.kind {
+            Some(kind) => detector
+                .detect(path)
+                .is_ok_and(|info| tools::category_name(info.category) == kind),
+            None => true,
+        }
+    }
+}
+
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+#[serde(transparent)]
+pub struct SmartFolders {
+    folders: Vec<SmartFolder>,
+}
+
+impl SmartFolders {
+    pub fn config_path() -> PathBuf {
+        dirs::data_dir()
+            .unwrap_or_else(|| PathBuf::from("."))
+            .join("rururu-files")
+            .join("smart-folders.json")
+    }
+
+    pub fn load() -> Self {
+        let path = Self::config_path();
+        let Ok(content) = std::fs::read_to_string(&path) else {
+            return Self::default();
+        };
+        serde_json::from_str(&content).unwrap_or_else(|e| {
+            warn!("Ignoring invalid {:?}: {}", path, e);
+            Self::default()
+        })
+    }
+
+    pub fn save(&self) -> std::io::Result<()> {
+        let path = Self::config_path();
+        if let Some(parent) = path.parent() {
+            std::fs::create_dir_all(parent)?;
+        }
+        let content = serde_json::to_string_pretty(self)?;
+        std::fs::write(path, content)
+    }
+
+    pub fn get(&self, name: &str) -> Option<&SmartFolder> {
+        self.folders.iter().find(|f| f.name == name)
+    }
+
+    /// Add `folder`, replacing one with the same name
+    pub fn insert(&mut self, folder: SmartFolder) {
+        match self.folders.iter_mut().find(|f| f.name == folder.name) {
+            Some(existing) => *existing = folder,
+            None => self.folders.push(folder),
+        }
+    }
+
+    pub fn remove(&mut self, name: &str) {
+        self.folders.retain(|f| f.name != name);
+    }
+}
+
+/// A smart folder being written in the sidebar
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct SmartFolderDraft {
+    pub name: String,
+    /// Separated by commas or spaces; a leading `#` is ignored
+    pub tags: String,
+    pub name_filter: String,
+    pub kind: Option<&'static str>,
+    /// Limit to the folder being browsed when the draft is saved
+    pub scoped: bool,
+}
+
+impl SmartFolderDraft {
+    /// The folder this describes, scoped to `current` if asked; `None`
+    /// until it has a name and a tag
+    pub fn build(&self, current: &Path) -> Option<SmartFolder> {
+        let tags: Vec<String> = self
+            .tags
+            .split(|c: char| c == ',' || c.is_whitespace())
+            .map(|t| t.trim().trim_start_matches('#'))
+            .filter(|t| !t.is_empty())
+            .map(str::to_string)
+            .collect();
+        let name = self.name.trim();
+        if name.is_empty() || tags.is_empty() {
+            return None;
+        }
+
+        let name_filter = self.name_filter.trim();
+        Some(SmartFolder {
+            name: name.to_string(),
+            tags,
+            scope: self.scoped.then(|| current.to_path_buf()),
+            name_filter: (!name_filter.is_empty()).then(|| name_filter.to_string()),
+            kind: self.kind.filter(|&k| k != "any").map(str::to_string),
+        })
+    }
+}
+
+/// Listing entries for the files in `folder`, checked against the tag
+/// database as it is on disk now
+pub async fn load_entries(folder: SmartFolder) -> Vec<FileEntry> {
+    tokio::task::spawn_blocking(move || {
+        let db = TagDatabase::load();
+        let tags: Vec<&str> = folder.tags.iter().map(String::as_str).collect();
+        let detector = FileDetector::new();
+
+        db.search_by_tags(&tags)
+            .into_iter()
+            .filter(|path| folder.matches(path, &detector))
+            .filter_map(|path| {
+                let is_symlink = std::fs::symlink_metadata(path)
+                    .map(|m| m.is_symlink())
+                    .unwrap_or(false);
+                listing::stat_entry(path.clone(), is_symlink)
+            })
+            .collect()
+    })
+    .await
+    .unwrap_or_default()
+}
+
+/// The sidebar's Smart Folders section, with the editor while one is being
+/// written
+pub fn view<'a>(
+    folders: &'a SmartFolders,
+    shown: Option<&str>,
+    draft: Option<&'a SmartFolderDraft>,
+) -> Element<'a, Message> {
+    let mut items: Vec<Element<Message>> = vec![row![
+        text("Smart Folders").size(14),
+        Space::with_width(Length::Fill),
+        button(text("+").size(12))
+            .style(iced::theme::Button::Text)
+            .on_press(Message::EditSmartFolder(Some(SmartFolderDraft::default()))),
+    ]
+    .align_items(iced::Alignment::Center)
+    .into()];
+
+    for folder in &folders.folders {
+        items.push(
+            row![
+                button(text(format!("🏷️ {}", folder.name)))
+                    .style(if shown == Some(folder.name.as_str()) {
+                        iced::theme::Button::Primary
+                    } else {
+                        iced::theme::Button::Text
+                    })
+                    .width(Length::Fill)
+                    .on_press(Message::ShowSmartFolder(folder.name.clone())),
+                button(text("×").size(10))
+                    .style(iced::theme::Button::Text)
+                    .on_press(Message::DeleteSmartFolder(folder.name.clone())),
+            ]
+            .align_items(iced::Alignment::Center)
+            .into(),
+        );
+    }
+
+    if let Some(draft) = draft {
+        items.push(editor(draft));
+    }
+
+    column(items).spacing(2).into()
+}
+
+fn editor(draft: &SmartFolderDraft) -> Element<'_, Message> {
+    let edit = |change: fn(&mut SmartFolderDraft, String)| {
+        move |value: String| {
+            let mut draft = draft.clone();
+            change(&mut draft, value);
+            Message::EditSmartFolder(Some(draft))
+        }
+    };
+
+    let kinds = pick_list(KINDS, Some(draft.kind.unwrap_or("any")), move |kind| {
+        Message::EditSmartFolder(Some(SmartFolderDraft {
+            kind: Some(kind),
+            ..draft.clone()
+        }))
+    })
+    .text_size(12);
+    let scoped = checkbox("Only in this folder", draft.scoped)
+        .on_toggle(move |scoped| {
+            Message::EditSmartFolder(Some(SmartFolderDraft {
+                scoped,
+                ..draft.clone()
+            }))
+        })
+        .text_size(12);
+
+    column![
+        text_input("Name", &draft.name)
+            .on_input(edit(|d, v| d.name = v))
+            .size(12),
+        text_input("Tags, e.g. client-a final", &draft.tags)
+            .on_input(edit(|d, v| d.tags = v))
+            .size(12),
+        text_input("Name contains (optional)", &draft.name_filter)
+            .on_input(edit(|d, v| d.name_filter = v))
+            .size(12),
+        kinds,
+        scoped,
+        row![
+            button(text("Save").size(12))
+                .style(iced::theme::Button::Primary)
+                .on_press_maybe(
+                    draft
+                        .build(Path::new("/"))
+                        .map(|_| Message::SaveSmartFolder)
+                ),
+            button(text("Cancel").size(12))
+                .style(iced::theme::Button::Text)
+                .on_press(Message::EditSmartFolder(None)),
+        ]
+        .spacing(4),
+    ]
+    .spacing(4)
+    .padding([4, 0])
+    .into()
+}
@@ -0,0 +1,192 @@
+use crate::file_list::FileEntry;
+use serde::{Deserialize, Serialize};
+use std::cmp::Ordering;
+use std::path::PathBuf;
+use tracing::warn;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default, Serialize, Deserialize)]
+pub enum SortKey {
+    #[default]
+    Name,
+    Size,
+    Modified,
+    Type,
+}
+
+impl SortKey {
+    pub const ALL: [SortKey; 4] = [
+        SortKey::Name,
+        SortKey::Size,
+        SortKey::Modified,
+        SortKey::Type,
+    ];
+}
+
+impl std::fmt::Display for SortKey {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        let label = match self {
+            SortKey::Name => "Name",
+            SortKey::Size => "Size",
+            SortKey::Modified => "Modified",
+            SortKey::Type => "Type",
+        };
+        write!(f, "{}", label)
+    }
+}
+
+/// Sort order, saved to `~/.config/rururu-files/sorting.toml`
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct SortSettings {
+    pub key: SortKey,
+    pub ascending: bool,
+    pub dirs_first: bool,
+}
+
+impl Default for SortSettings {
+    fn default() -> Self {
+        Self {
+            key: SortKey::Name,
+            ascending: true,
+            dirs_first: true,
+        }
+    }
+}
+
+impl SortSettings {
+    pub fn config_path() -> PathBuf {
+        dirs::config_dir()
+            .unwrap_or_else(|| PathBuf::from("."))
+            .join("rururu-files")
+            .join("sorting.toml")
+    }
+
+    pub fn load() -> Self {
+        let path = Self::config_path();
+        let Ok(content) = std::fs::read_to_string(&path) else {
+            return Self::default();
+        };
+
+        match toml::from_str(&content) {
+            Ok(settings) => settings,
+            Err(e) => {
+                warn!("Ignoring invalid {:?}: {}", path, e);
+                Self::default()
+            }
+        }
+    }
+
+    pub fn save(&self) -> std::io::Result<()> {
+        let path = Self::config_path();
+        if let Some(parent) = path.parent() {
+            std::fs::create_dir_all(parent)?;
+        }
+        let content = toml::to_string(self).map_err(std::io::Error::other)?;
+        std::fs::write(path, content)
+    }
+
+    /// Order `files` by the key, keeping directories on top when asked.
+    /// Ties fall back to the name so the order is stable between reloads.
+    pub fn sort(&self, files: &mut [FileEntry]) {
+        files.sort_by(|a, b| self.compare(a, b));
+    }
+
+    /// Sort `incoming` and merge it into the already sorted `files`, so a
+    /// listing arriving in chunks never re-sorts what it has shown
+    pub fn merge(&self, files: &mut Vec<FileEntry>, mut incoming: Vec<FileEntry>) {
+        self.sort(&mut incoming);
+
+        let existing = std::mem::take(files);
+        files.reserve(existing.len() + incoming.len());
+        let mut existing = existing.into_iter().peekable();
+        let mut incoming = incoming.into_iter().peekable();
+        while let (Some(old), Some(new)) = (existing.peek(), incoming.peek()) {
+            // Equal entries keep the ones already listed first
+            let next = if self.compare(new, old) == Ordering::Less {
+                incoming.next()
+            } else {
+                existing.next()
+            };
+            files.extend(next);
+        }
+        files.extend(existing);
+        files.extend(incoming);
+    }
+
+    fn compare(&self, a: &FileEntry, b: &FileEntry) -> Ordering {
+        if self.dirs_first && a.is_dir != b.is_dir {
+            return if a.is_dir {
+                Ordering::Less
+            } else {
+                Ordering::Greater
+            };
+        }
+
+        let by_name = || a.name.to_lowercase().cmp(&b.name.to_lowercase());
+        let ordering = match self.key {
+            SortKey::Name => by_name(),
+            SortKey::Size => a.size.cmp(&b.size).then_with(by_name),
+            SortKey::Modified => a.modified.cmp(&b.modified).then_with(by_name),
+            SortKey::Type => a
+                .file_type
+                .to_lowercase()
+                .cmp(&b.file_type.to_lowercase())
+                .then_with(by_name),
+        };
+
+        if self.ascending {
+            ordering
+        } else {
+            ordering.reverse()
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn entry(name: &str, is_dir: bool, size: u64) -> FileEntry {
+        FileEntry {
+            name: name.to_string(),
+            path: PathBuf::from("/tmp").join(name),
+            is_dir,
+            is_symlink: false,
+            executable: false,
+            size,
+            modified: None,
+            file_type: String::new(),
+        }
+    }
+
+    fn names(files: &[FileEntry]) -> Vec<&str> {
+        files.iter().map(|f| f.name.as_str()).collect()
+    }
+
+    #[test]
+    fn test_merging_chunks_matches_sorting_everything() {
+        let settings = SortSettings {
+            key: SortKey::Size,
+            ascending: false,
+            dirs_first: true,
+        };
+        let chunks = [
+            vec![entry("b.txt", false, 10), entry("src", true, 0)],
+            vec![entry("a.txt", false, 30), entry("docs", true, 0)],
+            vec![entry("c.txt", false, 10), entry("d.txt", false, 20)],
+        ];
+
+        let mut merged = Vec::new();
+        for chunk in chunks.clone() {
+            settings.merge(&mut merged, chunk);
+        }
+        let mut sorted: Vec<FileEntry> = chunks.concat();
+        settings.sort(&mut sorted);
+
+        assert_eq!(names(&merged), names(&sorted));
+        assert_eq!(
+            names(&merged),
+            ["src", "docs", "a.txt", "d.txt", "c.txt", "b.txt"]
+        );
+    }
+}
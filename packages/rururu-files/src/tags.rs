@@ -1,6 +1,9 @@
 use crate::app::Message;
 use iced::widget::{button, column, container, row, scrollable, text, text_input, Space};
 use iced::{Element, Length};
+use rururu_file_handler::media::MediaHandler;
+use rururu_file_handler::orientation::{read_orientation, Orientation};
+use rururu_file_handler::svg;
 use serde::{Deserialize, Serialize};
 use std::collections::{HashMap, HashSet};
 use std::path::{Path, PathBuf};
@@ -338,6 +341,8 @@ pub struct FileMetadata {
     pub modified: Option<u64>,
     pub mime_type: Option<String>,
     pub dimensions: Option<(u32, u32)>,
+    /// EXIF/XMP orientation of photos that record one
+    pub orientation: Option<Orientation>,
     pub duration: Option<f64>,
     pub author: Option<String>,
     pub title: Option<String>,
@@ -364,7 +369,19 @@ impl FileMetadata {
         let mime_type = infer::get_from_path(path)
             .ok()
             .flatten()
-            .map(|t| t.mime_type().to_string());
+            .map(|t| t.mime_type().to_string())
+            .filter(|mime| mime != "text/xml")
+            .or_else(|| {
+                // SVGs have no magic bytes, infer calls them XML at best
+                path.extension()
+                    .is_some_and(|ext| ext.eq_ignore_ascii_case("svg"))
+                    .then(|| "image/svg+xml".to_string())
+            });
+
+        let orientation = mime_type
+            .as_deref()
+            .filter(|mime| mime.starts_with("image/"))
+            .and_then(|_| read_orientation(path));
 
         Ok(Self {
             path: path.to_path_buf(),
@@ -373,6 +390,7 @@ impl FileMetadata {
             modified,
             mime_type,
             dimensions: None,
+            orientation,
             duration: None,
             author: None,
             title: None,
@@ -381,6 +399,44 @@ impl FileMetadata {
         })
     }
 
+    /// Fill in the dimensions of images and videos and the duration of
+    /// videos and audio; this opens the file, so it is only done on demand.
+    /// Photos turned by their EXIF orientation report the size as shown.
+    pub fn read_media(&mut self) {
+        let Some(mime) = self.mime_type.as_deref() else {
+            return;
+        };
+
+        if mime == "image/svg+xml" {
+            self.dimensions = std::fs::read(&self.path)
+                .ok()
+                .and_then(|data| svg::parse(&data))
+                .and_then(|info| info.dimensions());
+        } else if mime.starts_with("image/") {
+            self.dimensions =
+                ::image::image_dimensions(&self.path)
+                    .ok()
+                    .map(|(w, h)| match self.orientation {
+                        Some(o) if o.swaps_dimensions() => (h, w),
+                        _ => (w, h),
+                    });
+        } else if mime.starts_with("video/") {
+            let info = MediaHandler::new()
+                .ok()
+                .and_then(|h| h.get_info(&self.path).ok());
+            if let Some(video) = info.and_then(|info| info.video) {
+                self.dimensions = Some((video.width, video.height));
+                self.duration = video.duration.map(|d| d.as_secs_f64());
+            }
+        } else if mime.starts_with("audio/") {
+            self.duration = MediaHandler::new()
+                .ok()
+                .and_then(|h| h.get_audio_metadata(&self.path).ok())
+                .and_then(|audio| audio.duration)
+                .map(|d| d.as_secs_f64());
+        }
+    }
+
     pub fn format_size(&self) -> String {
         const KB: u64 = 1024;
         const MB: u64 = KB * 1024;
@@ -424,6 +480,10 @@ pub fn view_metadata<'a>(metadata: &'a FileMetadata) -> Element<'a, Message> {
         items.push(("Dimensions", format!("{}×{}", w, h)));
     }
 
+    if let Some(orientation) = metadata.orientation {
+        items.push(("Orientation", format!("{:?}", orientation)));
+    }
+
     if let Some(duration) = metadata.duration {
         let mins = (duration / 60.0) as u32;
         let secs = (duration % 60.0) as u32;
@@ -450,3 +510,24 @@ pub fn view_metadata<'a>(metadata: &'a FileMetadata) -> Element<'a, Message> {
     .spacing(4)
     .into()
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_svg_metadata_reports_declared_size() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("icon.svg");
+        std::fs::write(
+            &path,
+            r#"<?xml version="1.0"?><svg xmlns="http://www.w3.org/2000/svg" width="64" viewBox="0 0 32 16"/>"#,
+        )
+        .unwrap();
+
+        let mut metadata = FileMetadata::from_path(&path).unwrap();
+        assert_eq!(metadata.mime_type.as_deref(), Some("image/svg+xml"));
+        metadata.read_media();
+        assert_eq!(metadata.dimensions, Some((64, 32)));
+    }
+}
@@ -1,9 +1,14 @@
 use crate::app::{Message, RururuFiles, ViewMode};
-use iced::widget::{button, container, row, text, text_input, Space};
+use crate::sorting::SortKey;
+use iced::widget::{button, container, mouse_area, pick_list, row, text, text_input, Space};
 use iced::{Element, Length};
 
 pub struct Toolbar;
 
+pub fn path_input_id() -> text_input::Id {
+    text_input::Id::new("path")
+}
+
 impl Toolbar {
     pub fn view(app: &RururuFiles) -> Element<Message> {
         let nav_buttons = row![
@@ -25,10 +30,32 @@ impl Toolbar {
         ]
         .spacing(4);
 
-        let path_bar = container(text(app.current_path.to_string_lossy()).size(14))
-            .padding(8)
-            .style(iced::theme::Container::Box)
-            .width(Length::Fill);
+        // Clicking the path, or Ctrl+L, turns it into a field to type one
+        let path_bar: Element<Message> = match app.path_input {
+            Some(ref input) => row![
+                text_input("Path", input)
+                    .id(path_input_id())
+                    .on_input(Message::PathInputChanged)
+                    .on_submit(Message::PathInputSubmit)
+                    .size(14)
+                    .width(Length::Fill),
+                button(text("✕"))
+                    .on_press(Message::CancelPathEdit)
+                    .style(iced::theme::Button::Text),
+            ]
+            .spacing(4)
+            .align_items(iced::Alignment::Center)
+            .width(Length::Fill)
+            .into(),
+            None => mouse_area(
+                container(text(app.current_path.to_string_lossy()).size(14))
+                    .padding(8)
+                    .style(iced::theme::Container::Box)
+                    .width(Length::Fill),
+            )
+            .on_press(Message::EditPath)
+            .into(),
+        };
 
         let search = text_input("Search...", &app.search_query)
             .on_input(Message::SearchChanged)
@@ -53,6 +80,22 @@ impl Toolbar {
         ]
         .spacing(4);
 
+        let sorting = row![
+            pick_list(&SortKey::ALL[..], Some(app.sort_by), Message::SetSortKey).text_size(13),
+            button(text(if app.sort_ascending { "↑" } else { "↓" }))
+                .on_press(Message::ToggleSortDirection)
+                .style(iced::theme::Button::Secondary),
+            button(text("📁"))
+                .on_press(Message::ToggleDirsFirst)
+                .style(if app.dirs_first {
+                    iced::theme::Button::Primary
+                } else {
+                    iced::theme::Button::Secondary
+                }),
+        ]
+        .spacing(4)
+        .align_items(iced::Alignment::Center);
+
         let options = row![
             button(if app.show_hidden {
                 text("👁")
@@ -68,18 +111,69 @@ impl Toolbar {
             })
             .on_press(Message::TogglePreview)
             .style(iced::theme::Button::Secondary),
+            button(text("◫"))
+                .on_press(Message::ToggleDualPane)
+                .style(if app.is_dual_pane() {
+                    iced::theme::Button::Primary
+                } else {
+                    iced::theme::Button::Secondary
+                }),
+            button(text("⌨"))
+                .on_press(Message::ToggleKeybindings)
+                .style(if app.keybinding_editor.is_some() {
+                    iced::theme::Button::Primary
+                } else {
+                    iced::theme::Button::Secondary
+                }),
         ]
         .spacing(4);
 
+        // Unpacks the selection next to the archive
+        let extract: Element<Message> = if app.in_archive() {
+            button(text("Extract"))
+                .on_press(Message::ExtractSelected)
+                .style(iced::theme::Button::Primary)
+                .into()
+        } else {
+            Space::with_width(Length::Shrink).into()
+        };
+
+        let compare: Element<Message> = if app.can_compare() {
+            button(text("Compare"))
+                .on_press(Message::CompareSelected)
+                .style(iced::theme::Button::Secondary)
+                .into()
+        } else {
+            Space::with_width(Length::Shrink).into()
+        };
+
+        let edit_tags: Element<Message> = if app.can_edit_audio_tags() {
+            button(text("Tags"))
+                .on_press(Message::EditAudioTags)
+                .style(if app.audio_tag_editor.is_some() {
+                    iced::theme::Button::Primary
+                } else {
+                    iced::theme::Button::Secondary
+                })
+                .into()
+        } else {
+            Space::with_width(Length::Shrink).into()
+        };
+
         let toolbar = row![
             nav_buttons,
             Space::with_width(Length::Fixed(16.0)),
             path_bar,
+            extract,
+            compare,
+            edit_tags,
             Space::with_width(Length::Fixed(16.0)),
             search,
             Space::with_width(Length::Fixed(16.0)),
             view_buttons,
             Space::with_width(Length::Fixed(8.0)),
+            sorting,
+            Space::with_width(Length::Fixed(8.0)),
             options,
         ]
         .spacing(8)
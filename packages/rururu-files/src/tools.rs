@@ -0,0 +1,379 @@
+//! User-defined external tools, shown in the right-click menu.
+//!
+//! Tools live in `~/.config/rururu-files/tools.toml`:
+//!
+//! ```toml
+//! [[tool]]
+//! label = "Convert to ProRes"
+//! command = "ffmpeg -i {path} -c:v prores_ks {dir}/prores.mov"
+//! applies_to = ["video"]
+//! ```
+//!
+//! `{path}` is the selected file and `{dir}` its directory. `applies_to`
+//! takes MIME types (`image/png`), MIME wildcards (`image/*`) or categories
+//! (`image`, `video`, `audio`, `document`, `model3d`, `archive`, `code`,
+//! `project`, `subtitle`, `edit-exchange`, `directory`); an empty list
+//! matches everything. The command is split into words before substitution
+//! and run directly, not through a shell.
+
+use crate::app::Message;
+use crate::open_with;
+use iced::widget::{button, column, container, row, scrollable, text, Space};
+use iced::{Element, Length};
+use rururu_file_handler::{DetectorError, FileCategory, FileDetector, ProjectFormat};
+use rururu_utils::{ProcessManager, ProcessOutput};
+use serde::{Deserialize, Serialize};
+use std::path::{Path, PathBuf};
+use tracing::warn;
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ExternalTool {
+    pub label: String,
+    pub command: String,
+    #[serde(default)]
+    pub applies_to: Vec<String>,
+}
+
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct ToolsConfig {
+    #[serde(default, rename = "tool")]
+    pub tools: Vec<ExternalTool>,
+}
+
+impl ToolsConfig {
+    pub fn config_path() -> PathBuf {
+        dirs::config_dir()
+            .unwrap_or_else(|| PathBuf::from("."))
+            .join("rururu-files")
+            .join("tools.toml")
+    }
+
+    pub fn load() -> Self {
+        let path = Self::config_path();
+        let Ok(content) = std::fs::read_to_string(&path) else {
+            return Self::default();
+        };
+
+        match toml::from_str(&content) {
+            Ok(config) => config,
+            Err(e) => {
+                warn!("Ignoring invalid {:?}: {}", path, e);
+                Self::default()
+            }
+        }
+    }
+
+    /// Indices of the tools that apply to every one of `paths`
+    pub fn matching(&self, paths: &[PathBuf]) -> Vec<usize> {
+        let detector = FileDetector::new();
+        let kinds: Vec<(String, &'static str)> = paths.iter().map(|p| kind(&detector, p)).collect();
+
+        self.tools
+            .iter()
+            .enumerate()
+            .filter(|(_, tool)| {
+                !kinds.is_empty()
+                    && kinds
+                        .iter()
+                        .all(|(mime, category)| tool.applies_to(mime, category))
+            })
+            .map(|(i, _)| i)
+            .collect()
+    }
+}
+
+impl ExternalTool {
+    fn applies_to(&self, mime: &str, category: &str) -> bool {
+        self.applies_to.is_empty()
+            || self.applies_to.iter().any(|filter| {
+                let filter = filter.trim().to_lowercase();
+                match filter.strip_suffix("/*") {
+                    Some(prefix) => mime.split('/').next() == Some(prefix),
+                    None if filter.contains('/') => filter == mime,
+                    None => filter == category,
+                }
+            })
+    }
+
+    /// Program and arguments for `path`, or `None` for an empty command
+    pub fn command_for(&self, path: &Path) -> Option<(String, Vec<String>)> {
+        let dir = path.parent().unwrap_or(Path::new("/"));
+        let path = path.to_string_lossy();
+        let dir = dir.to_string_lossy();
+
+        let mut words = split_command(&self.command)
+            .into_iter()
+            .map(|word| word.replace("{path}", &path).replace("{dir}", &dir));
+        let program = words.next()?;
+        Some((program, words.collect()))
+    }
+}
+
+/// What the right-click menu offers for `paths`
+#[derive(Debug, Clone)]
+pub struct ToolsMenu {
+    pub paths: Vec<PathBuf>,
+    pub config: ToolsConfig,
+    /// Indices of the tools in `config` that apply
+    pub matching: Vec<usize>,
+    /// Project file and the installed app that opens it
+    pub project: Option<(PathBuf, &'static ProjectFormat)>,
+}
+
+/// Re-read tools.toml, so edits show up without a restart, and work out
+/// the menu for `paths`. Detection reads files, so it runs off the UI
+/// thread.
+pub async fn load_menu(paths: Vec<PathBuf>) -> Option<ToolsMenu> {
+    tokio::task::spawn_blocking(move || {
+        let config = ToolsConfig::load();
+        let matching = config.matching(&paths);
+        let project = match paths.as_slice() {
+            [path] => open_with::project_app(&FileDetector::new(), path)
+                .map(|format| (path.clone(), format)),
+            _ => None,
+        };
+        ToolsMenu {
+            paths,
+            config,
+            matching,
+            project,
+        }
+    })
+    .await
+    .ok()
+}
+
+/// Outcome of running a tool on one file
+#[derive(Debug, Clone)]
+pub struct ToolRun {
+    pub label: String,
+    pub path: PathBuf,
+    pub result: Result<ProcessOutput, String>,
+}
+
+/// Run `tool` once per path, one after another
+pub async fn run_tool(tool: ExternalTool, paths: Vec<PathBuf>) -> Vec<ToolRun> {
+    let label = tool.label.clone();
+    let task = tokio::task::spawn_blocking(move || {
+        paths
+            .into_iter()
+            .map(|path| {
+                let result = match tool.command_for(&path) {
+                    Some((program, args)) => {
+                        let args: Vec<&str> = args.iter().map(String::as_str).collect();
+                        ProcessManager::run(&program, &args).map_err(|e| e.to_string())
+                    }
+                    None => Err("Empty command".to_string()),
+                };
+                ToolRun {
+                    label: tool.label.clone(),
+                    path,
+                    result,
+                }
+            })
+            .collect()
+    });
+
+    task.await.unwrap_or_else(|e| {
+        vec![ToolRun {
+            label,
+            path: PathBuf::new(),
+            result: Err(e.to_string()),
+        }]
+    })
+}
+
+/// MIME type and category name matched against `applies_to`
+fn kind(detector: &FileDetector, path: &Path) -> (String, &'static str) {
+    if path.is_dir() {
+        return ("inode/directory".to_string(), "directory");
+    }
+
+    // Only a bounded prefix is read, so large media doesn't hold up the
+    // menu; the extension decides when the content isn't recognised
+    let extension = path.extension().and_then(|e| e.to_str());
+    let detected = std::fs::File::open(path)
+        .map_err(DetectorError::from)
+        .and_then(|file| detector.detect_from_reader(file))
+        .or_else(|_| detector.detect_from_bytes(&[], extension));
+    match detected {
+        Ok(info) => (info.mime_type.to_lowercase(), category_name(info.category)),
+        Err(_) => ("application/octet-stream".to_string(), "unknown"),
+    }
+}
+
+/// How `applies_to` and smart folders name a file category
+pub fn category_name(category: FileCategory) -> &'static str {
+    match category {
+        FileCategory::Video => "video",
+        FileCategory::Audio => "audio",
+        FileCategory::Image => "image",
+        FileCategory::Document => "document",
+        FileCategory::Model3D => "model3d",
+        FileCategory::Archive => "archive",
+        FileCategory::Code => "code",
+        FileCategory::Project => "project",
+        FileCategory::Subtitle => "subtitle",
+        FileCategory::EditExchange => "edit-exchange",
+        FileCategory::Unknown => "unknown",
+    }
+}
+
+/// Split on whitespace, keeping single- or double-quoted runs together
+fn split_command(command: &str) -> Vec<String> {
+    let mut words = Vec::new();
+    let mut current = String::new();
+    let mut quote: Option<char> = None;
+    let mut in_word = false;
+
+    for c in command.chars() {
+        match (quote, c) {
+            (Some(q), c) if c == q => quote = None,
+            (Some(_), c) => current.push(c),
+            (None, '"' | '\'') => {
+                quote = Some(c);
+                in_word = true;
+            }
+            (None, c) if c.is_whitespace() => {
+                if in_word {
+                    words.push(std::mem::take(&mut current));
+                    in_word = false;
+                }
+            }
+            (None, c) => {
+                current.push(c);
+                in_word = true;
+            }
+        }
+    }
+    if in_word {
+        words.push(current);
+    }
+
+    words
+}
+
+/// `open_in` offers the project app for a single selected project file,
+/// `sums_file` verification of a single selected sums file
+pub fn view_tools_menu<'a>(
+    tools: &'a [ExternalTool],
+    matching: &[usize],
+    open_in: Option<&(PathBuf, &'static ProjectFormat)>,
+    sums_file: Option<&PathBuf>,
+) -> Element<'a, Message> {
+    let mut items = column![text("Tools").size(16)].spacing(4);
+
+    if let Some((path, format)) = open_in {
+        items = items.push(
+            button(text(format!("Open in {}", format.app)).size(13))
+                .style(iced::theme::Button::Primary)
+                .width(Length::Fill)
+                .on_press(Message::OpenFile(path.clone())),
+        );
+    }
+
+    items = items.push(
+        button(text("Checksums…").size(13))
+            .style(iced::theme::Button::Secondary)
+            .width(Length::Fill)
+            .on_press(Message::ShowChecksums),
+    );
+    if let Some(sums) = sums_file {
+        items = items.push(
+            button(text("Verify checksums").size(13))
+                .style(iced::theme::Button::Secondary)
+                .width(Length::Fill)
+                .on_press(Message::VerifyChecksums(sums.clone())),
+        );
+    }
+
+    if tools.is_empty() {
+        items = items.push(
+            text(format!(
+                "No tools configured. Add them to {}",
+                ToolsConfig::config_path().display()
+            ))
+            .size(12),
+        );
+    } else if matching.is_empty() {
+        items = items.push(text("No tools apply to this selection").size(12));
+    }
+
+    for &index in matching {
+        items = items.push(
+            button(text(&tools[index].label).size(13))
+                .style(iced::theme::Button::Secondary)
+                .width(Length::Fill)
+                .on_press(Message::RunTool(index)),
+        );
+    }
+
+    items = items.push(
+        button(text("Cancel").size(13))
+            .style(iced::theme::Button::Text)
+            .on_press(Message::CloseToolsMenu),
+    );
+
+    container(items)
+        .width(Length::Fixed(220.0))
+        .padding(8)
+        .style(iced::theme::Container::Box)
+        .into()
+}
+
+pub fn view_tool_results<'a>(
+    runs: &'a [ToolRun],
+    running: Option<&'a str>,
+) -> Element<'a, Message> {
+    let title = match running {
+        Some(label) => format!("Running {}…", label),
+        None => "Tool output".to_string(),
+    };
+
+    let header = row![
+        text(title).size(14),
+        Space::with_width(Length::Fill),
+        button(text("✕"))
+            .style(iced::theme::Button::Text)
+            .on_press(Message::CloseToolResults),
+    ]
+    .align_items(iced::Alignment::Center);
+
+    let mut entries = column![].spacing(8);
+    for run in runs {
+        let name = run
+            .path
+            .file_name()
+            .map(|n| n.to_string_lossy().to_string())
+            .unwrap_or_default();
+
+        let (status, output) = match run.result {
+            Ok(ref output) => {
+                let status = if output.success() {
+                    "done".to_string()
+                } else {
+                    format!("exit {}", output.code)
+                };
+                let combined = [output.stdout.trim_end(), output.stderr.trim_end()]
+                    .iter()
+                    .filter(|s| !s.is_empty())
+                    .cloned()
+                    .collect::<Vec<_>>()
+                    .join("\n");
+                (status, combined)
+            }
+            Err(ref e) => ("failed".to_string(), e.clone()),
+        };
+
+        entries = entries.push(text(format!("{} — {} ({})", run.label, name, status)).size(12));
+        if !output.is_empty() {
+            entries = entries.push(text(output).font(iced::Font::MONOSPACE).size(11));
+        }
+    }
+
+    container(column![header, scrollable(entries).height(Length::Fixed(160.0))].spacing(8))
+        .width(Length::Fill)
+        .padding(8)
+        .style(iced::theme::Container::Box)
+        .into()
+}
@@ -0,0 +1,232 @@
+//! Trash location backed by the system trash.
+//!
+//! On Linux the `trash` crate reads the freedesktop trash spec directly: every
+//! item in `~/.local/share/Trash/files` (and per-mount `.Trash-$UID` dirs) has
+//! a matching `.trashinfo` file holding the percent-encoded original `Path`
+//! and the `DeletionDate`. Items whose info file lacks a deletion date are
+//! reported with a negative timestamp and shown without a date.
+
+use crate::app::Message;
+use iced::widget::{button, column, container, row, scrollable, text, Space};
+use iced::{Element, Length};
+use std::ffi::OsString;
+use trash::os_limited;
+use trash::TrashItem;
+
+#[derive(Debug, Clone)]
+pub struct TrashEntry {
+    pub item: TrashItem,
+    pub size: Option<u64>,
+    pub is_dir: bool,
+}
+
+impl TrashEntry {
+    pub fn name(&self) -> String {
+        self.item.name.to_string_lossy().to_string()
+    }
+
+    pub fn original_location(&self) -> String {
+        self.item.original_parent.to_string_lossy().to_string()
+    }
+
+    pub fn deleted_at(&self) -> String {
+        if self.item.time_deleted < 0 {
+            return "—".to_string();
+        }
+
+        chrono::DateTime::from_timestamp(self.item.time_deleted, 0)
+            .map(|dt| {
+                dt.with_timezone(&chrono::Local)
+                    .format("%Y-%m-%d %H:%M")
+                    .to_string()
+            })
+            .unwrap_or_else(|| "—".to_string())
+    }
+}
+
+pub async fn load_trash() -> Result<Vec<TrashEntry>, trash::Error> {
+    let mut entries: Vec<TrashEntry> = os_limited::list()?
+        .into_iter()
+        .map(|item| {
+            let metadata = os_limited::metadata(&item).ok();
+            TrashEntry {
+                size: metadata.and_then(|m| m.size.size()),
+                is_dir: metadata
+                    .map(|m| m.size.entries().is_some())
+                    .unwrap_or(false),
+                item,
+            }
+        })
+        .collect();
+
+    // Most recently deleted first
+    entries.sort_by_key(|e| std::cmp::Reverse(e.item.time_deleted));
+
+    Ok(entries)
+}
+
+pub async fn restore_items(items: Vec<TrashItem>) -> Result<(), trash::Error> {
+    os_limited::restore_all(items)
+}
+
+pub async fn purge_items(items: Vec<TrashItem>) -> Result<(), trash::Error> {
+    os_limited::purge_all(items)
+}
+
+pub async fn empty_trash() -> Result<(), trash::Error> {
+    let items = os_limited::list()?;
+    if items.is_empty() {
+        return Ok(());
+    }
+    os_limited::purge_all(items)
+}
+
+/// A permanent deletion waiting for the user to confirm it
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum TrashPurge {
+    /// The item with this trash id
+    Item(OsString),
+    /// Everything in the trash
+    All,
+}
+
+pub struct TrashView;
+
+impl TrashView {
+    pub fn view<'a>(
+        entries: &'a [TrashEntry],
+        selected: &'a Option<OsString>,
+        purge: &'a Option<TrashPurge>,
+    ) -> Element<'a, Message> {
+        let has_selection = selected
+            .as_ref()
+            .map(|id| entries.iter().any(|e| &e.item.id == id))
+            .unwrap_or(false);
+
+        let restore_btn = button(text("↩ Restore")).style(iced::theme::Button::Secondary);
+        let delete_btn = button(text("Delete Permanently")).style(iced::theme::Button::Destructive);
+        let empty_btn = button(text("🗑 Empty Trash")).style(iced::theme::Button::Destructive);
+
+        let actions = row![
+            text(format!("{} item(s) in trash", entries.len())).size(14),
+            Space::with_width(Length::Fill),
+            if has_selection {
+                restore_btn.on_press(Message::RestoreTrashItem)
+            } else {
+                restore_btn
+            },
+            if has_selection {
+                delete_btn.on_press(Message::PurgeTrashItem)
+            } else {
+                delete_btn
+            },
+            if entries.is_empty() {
+                empty_btn
+            } else {
+                empty_btn.on_press(Message::EmptyTrash)
+            },
+        ]
+        .spacing(8)
+        .padding(8)
+        .align_items(iced::Alignment::Center);
+
+        let header = row![
+            text("Name").width(Length::FillPortion(3)),
+            text("Original Location").width(Length::FillPortion(4)),
+            text("Deleted").width(Length::FillPortion(2)),
+            text("Size").width(Length::FillPortion(1)),
+        ]
+        .spacing(8)
+        .padding(8);
+
+        let rows: Vec<Element<Message>> = entries
+            .iter()
+            .map(|entry| {
+                let is_selected = selected.as_ref() == Some(&entry.item.id);
+                let icon = if entry.is_dir { "📁" } else { "📄" };
+
+                let size_str = entry
+                    .size
+                    .map(|s| humansize::format_size(s, humansize::BINARY))
+                    .unwrap_or_else(|| "—".to_string());
+
+                let row_content = row![
+                    text(format!("{} {}", icon, entry.name())).width(Length::FillPortion(3)),
+                    text(entry.original_location()).width(Length::FillPortion(4)),
+                    text(entry.deleted_at()).width(Length::FillPortion(2)),
+                    text(size_str).width(Length::FillPortion(1)),
+                ]
+                .spacing(8)
+                .padding(4);
+
+                let style = if is_selected {
+                    iced::theme::Button::Primary
+                } else {
+                    iced::theme::Button::Text
+                };
+
+                button(row_content)
+                    .style(style)
+                    .width(Length::Fill)
+                    .on_press(Message::TrashItemSelected(entry.item.id.clone()))
+                    .into()
+            })
+            .collect();
+
+        let list: Element<Message> = if rows.is_empty() {
+            container(text("Trash is empty").size(14))
+                .padding(16)
+                .into()
+        } else {
+            scrollable(column(rows).spacing(2)).into()
+        };
+
+        let mut content = column![actions].spacing(4);
+        if let Some(purge) = purge {
+            content = content.push(Self::view_confirm(entries, purge));
+        }
+        let content = content.push(header).push(list);
+
+        container(content)
+            .width(Length::FillPortion(3))
+            .height(Length::Fill)
+            .into()
+    }
+
+    /// Ask before deleting for good, since the trash can't give it back
+    fn view_confirm<'a>(entries: &'a [TrashEntry], purge: &TrashPurge) -> Element<'a, Message> {
+        let question = match purge {
+            TrashPurge::Item(id) => {
+                let name = entries
+                    .iter()
+                    .find(|e| &e.item.id == id)
+                    .map(|e| e.name())
+                    .unwrap_or_default();
+                format!("Permanently delete {}? This can't be undone.", name)
+            }
+            TrashPurge::All => format!(
+                "Permanently delete all {} item(s) in the trash? This can't be undone.",
+                entries.len()
+            ),
+        };
+
+        container(
+            row![
+                text(question).size(13),
+                Space::with_width(Length::Fill),
+                button(text("Delete Permanently").size(12))
+                    .style(iced::theme::Button::Destructive)
+                    .on_press(Message::ConfirmPurge),
+                button(text("Cancel").size(12))
+                    .style(iced::theme::Button::Secondary)
+                    .on_press(Message::CancelPurge),
+            ]
+            .spacing(8)
+            .align_items(iced::Alignment::Center),
+        )
+        .padding(8)
+        .width(Length::Fill)
+        .style(iced::theme::Container::Box)
+        .into()
+    }
+}
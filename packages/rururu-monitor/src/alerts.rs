@@ -0,0 +1,394 @@
+use serde::{Deserialize, Serialize};
+use std::collections::{HashMap, HashSet};
+use std::path::PathBuf;
+use std::time::{Duration, Instant};
+use sysinfo::{Disks, System};
+use zbus::{proxy, zvariant::Value, Connection};
+
+const GIB: f64 = 1024.0 * 1024.0 * 1024.0;
+
+/// Filesystems that are images, live in memory or expose firmware, so they
+/// are always full or their free space isn't the user's to manage
+const UNWATCHED_FILESYSTEMS: &[&str] = &[
+    "squashfs", "iso9660", "udf", "erofs", "tmpfs", "devtmpfs", "ramfs", "efivarfs",
+];
+
+/// Disks smaller than this many times the free space threshold, like
+/// `/boot` and the EFI partition, are left out; they would sit below it
+/// permanently
+const MIN_SIZE_THRESHOLD_MULTIPLE: f64 = 4.0;
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(default)]
+pub struct AlertThresholds {
+    pub enabled: bool,
+    pub memory_percent: f32,
+    pub swap_percent: f32,
+    pub disk_free_gb: f32,
+    /// Minimum time between repeated notifications for the same resource
+    pub cooldown_secs: u64,
+}
+
+impl Default for AlertThresholds {
+    fn default() -> Self {
+        Self {
+            enabled: true,
+            memory_percent: 95.0,
+            swap_percent: 90.0,
+            disk_free_gb: 2.0,
+            cooldown_secs: 300,
+        }
+    }
+}
+
+#[derive(Debug, Clone)]
+pub struct Alert {
+    pub key: String,
+    pub summary: String,
+    pub body: String,
+    /// Notification to replace, so reminders update the existing popup
+    pub replaces_id: u32,
+}
+
+/// One reading of the resources alerts watch
+#[derive(Debug, Clone, Default)]
+pub struct ResourceSample {
+    pub memory_used: u64,
+    pub memory_total: u64,
+    pub swap_used: u64,
+    pub swap_total: u64,
+    pub disks: Vec<DiskSample>,
+}
+
+#[derive(Debug, Clone, Default)]
+pub struct DiskSample {
+    pub mount_point: PathBuf,
+    pub file_system: String,
+    pub total_bytes: u64,
+    pub available_bytes: u64,
+    pub removable: bool,
+    pub read_only: bool,
+}
+
+impl ResourceSample {
+    pub fn collect(system: &System, disks: &Disks) -> Self {
+        let read_only = read_only_mounts();
+        Self {
+            memory_used: system.used_memory(),
+            memory_total: system.total_memory(),
+            swap_used: system.used_swap(),
+            swap_total: system.total_swap(),
+            disks: disks
+                .iter()
+                .map(|disk| DiskSample {
+                    mount_point: disk.mount_point().to_path_buf(),
+                    file_system: disk.file_system().to_string_lossy().to_string(),
+                    total_bytes: disk.total_space(),
+                    available_bytes: disk.available_space(),
+                    removable: disk.is_removable(),
+                    read_only: read_only.contains(disk.mount_point()),
+                })
+                .collect(),
+        }
+    }
+}
+
+/// Tracks which thresholds are currently crossed so each one notifies once
+/// per cooldown instead of on every tick. A resource that drops back below
+/// its threshold is re-armed and notifies immediately the next time.
+#[derive(Debug, Default)]
+pub struct AlertTracker {
+    last_fired: HashMap<String, Instant>,
+    notification_ids: HashMap<String, u32>,
+}
+
+impl AlertTracker {
+    pub fn check(
+        &mut self,
+        thresholds: &AlertThresholds,
+        system: &System,
+        disks: &Disks,
+    ) -> Vec<Alert> {
+        let sample = ResourceSample::collect(system, disks);
+        self.evaluate(thresholds, &sample, Instant::now())
+    }
+
+    /// The alerts due for `sample` taken at `now`
+    pub fn evaluate(
+        &mut self,
+        thresholds: &AlertThresholds,
+        sample: &ResourceSample,
+        now: Instant,
+    ) -> Vec<Alert> {
+        if !thresholds.enabled {
+            self.last_fired.clear();
+            return Vec::new();
+        }
+
+        let mut crossed = Vec::new();
+
+        if sample.memory_total > 0 {
+            let percent = sample.memory_used as f32 / sample.memory_total as f32 * 100.0;
+            if percent >= thresholds.memory_percent {
+                crossed.push((
+                    "memory".to_string(),
+                    format!("Memory usage {:.0}%", percent),
+                    format!(
+                        "{:.1} GB of {:.1} GB in use",
+                        sample.memory_used as f64 / GIB,
+                        sample.memory_total as f64 / GIB
+                    ),
+                ));
+            }
+        }
+
+        if sample.swap_total > 0 {
+            let percent = sample.swap_used as f32 / sample.swap_total as f32 * 100.0;
+            if percent >= thresholds.swap_percent {
+                crossed.push((
+                    "swap".to_string(),
+                    format!("Swap usage {:.0}%", percent),
+                    "The system is swapping heavily and may become unresponsive".to_string(),
+                ));
+            }
+        }
+
+        let watched = sample
+            .disks
+            .iter()
+            .filter(|d| is_watched(d, thresholds.disk_free_gb as f64));
+        for disk in watched {
+            let free_gb = disk.available_bytes as f64 / GIB;
+            if free_gb < thresholds.disk_free_gb as f64 {
+                let mount = disk.mount_point.to_string_lossy().to_string();
+                crossed.push((
+                    format!("disk:{}", mount),
+                    format!("{} has <{}GB free", mount, thresholds.disk_free_gb),
+                    format!("{:.2} GB available", free_gb),
+                ));
+            }
+        }
+
+        self.last_fired
+            .retain(|key, _| crossed.iter().any(|(k, _, _)| k == key));
+
+        let cooldown = Duration::from_secs(thresholds.cooldown_secs);
+        let mut alerts = Vec::new();
+
+        for (key, summary, body) in crossed {
+            let due = self
+                .last_fired
+                .get(&key)
+                .map(|fired| now.duration_since(*fired) >= cooldown)
+                .unwrap_or(true);
+
+            if due {
+                self.last_fired.insert(key.clone(), now);
+                alerts.push(Alert {
+                    replaces_id: self.notification_ids.get(&key).copied().unwrap_or(0),
+                    key,
+                    summary,
+                    body,
+                });
+            }
+        }
+
+        alerts
+    }
+
+    pub fn notification_sent(&mut self, key: String, id: u32) {
+        self.notification_ids.insert(key, id);
+    }
+}
+
+#[proxy(
+    interface = "org.freedesktop.Notifications",
+    default_service = "org.freedesktop.Notifications",
+    default_path = "/org/freedesktop/Notifications"
+)]
+trait Notifications {
+    #[allow(clippy::too_many_arguments)]
+    fn notify(
+        &self,
+        app_name: &str,
+        replaces_id: u32,
+        app_icon: &str,
+        summary: &str,
+        body: &str,
+        actions: &[&str],
+        hints: HashMap<&str, Value<'_>>,
+        expire_timeout: i32,
+    ) -> zbus::Result<u32>;
+}
+
+/// Show the alert as a desktop notification, returning its notification id.
+pub async fn send_notification(alert: Alert) -> zbus::Result<(String, u32)> {
+    let connection = Connection::session().await?;
+    let proxy = NotificationsProxy::new(&connection).await?;
+
+    // Critical urgency so the popup stays up during unattended work
+    let hints = HashMap::from([("urgency", Value::U8(2))]);
+
+    let id = proxy
+        .notify(
+            "RururuOS System Monitor",
+            alert.replaces_id,
+            "utilities-system-monitor",
+            &alert.summary,
+            &alert.body,
+            &[],
+            hints,
+            -1,
+        )
+        .await?;
+
+    Ok((alert.key, id))
+}
+
+/// Whether a disk's free space is worth alerting on: writable, fixed, holds
+/// a real filesystem and is big enough for the threshold to mean something
+fn is_watched(disk: &DiskSample, threshold_gb: f64) -> bool {
+    disk.total_bytes as f64 >= threshold_gb * MIN_SIZE_THRESHOLD_MULTIPLE * GIB
+        && !disk.removable
+        && !UNWATCHED_FILESYSTEMS.contains(&disk.file_system.as_str())
+        && !disk.read_only
+}
+
+/// Mount points mounted read-only, from `/proc/mounts`
+fn read_only_mounts() -> HashSet<PathBuf> {
+    let mounts = std::fs::read_to_string("/proc/mounts").unwrap_or_default();
+    mounts
+        .lines()
+        .filter_map(|line| {
+            let mut fields = line.split_whitespace();
+            let mount = fields.nth(1)?;
+            let options = fields.nth(1)?;
+            options
+                .split(',')
+                .any(|option| option == "ro")
+                // Spaces in mount points are escaped as \040
+                .then(|| PathBuf::from(mount.replace("\\040", " ")))
+        })
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const GB: u64 = 1024 * 1024 * 1024;
+
+    fn disk(mount: &str, total_gb: u64, free_gb: u64) -> DiskSample {
+        DiskSample {
+            mount_point: PathBuf::from(mount),
+            file_system: "ext4".to_string(),
+            total_bytes: total_gb * GB,
+            available_bytes: free_gb * GB,
+            removable: false,
+            read_only: false,
+        }
+    }
+
+    fn memory(percent: u64) -> ResourceSample {
+        ResourceSample {
+            memory_used: percent * GB,
+            memory_total: 100 * GB,
+            ..Default::default()
+        }
+    }
+
+    fn keys(alerts: &[Alert]) -> Vec<&str> {
+        alerts.iter().map(|a| a.key.as_str()).collect()
+    }
+
+    #[test]
+    fn test_fires_when_a_threshold_is_crossed() {
+        let mut tracker = AlertTracker::default();
+        let thresholds = AlertThresholds::default();
+        let now = Instant::now();
+
+        assert!(tracker.evaluate(&thresholds, &memory(50), now).is_empty());
+
+        let mut sample = memory(96);
+        sample.swap_used = 95;
+        sample.swap_total = 100;
+        sample.disks = vec![disk("/", 500, 1), disk("/home", 500, 100)];
+        let alerts = tracker.evaluate(&thresholds, &sample, now);
+        assert_eq!(keys(&alerts), ["memory", "swap", "disk:/"]);
+        assert_eq!(alerts[0].summary, "Memory usage 96%");
+        assert_eq!(alerts[0].replaces_id, 0);
+    }
+
+    #[test]
+    fn test_no_refire_during_cooldown() {
+        let mut tracker = AlertTracker::default();
+        let thresholds = AlertThresholds::default();
+        let cooldown = Duration::from_secs(thresholds.cooldown_secs);
+        let start = Instant::now();
+
+        assert_eq!(tracker.evaluate(&thresholds, &memory(96), start).len(), 1);
+        tracker.notification_sent("memory".to_string(), 7);
+
+        let during = start + cooldown - Duration::from_secs(1);
+        assert!(tracker
+            .evaluate(&thresholds, &memory(97), during)
+            .is_empty());
+
+        // A reminder replaces the popup already showing
+        let alerts = tracker.evaluate(&thresholds, &memory(97), start + cooldown);
+        assert_eq!(keys(&alerts), ["memory"]);
+        assert_eq!(alerts[0].replaces_id, 7);
+    }
+
+    #[test]
+    fn test_rearms_below_the_threshold() {
+        let mut tracker = AlertTracker::default();
+        let thresholds = AlertThresholds::default();
+        let start = Instant::now();
+
+        assert_eq!(tracker.evaluate(&thresholds, &memory(96), start).len(), 1);
+        let later = start + Duration::from_secs(10);
+        assert!(tracker.evaluate(&thresholds, &memory(80), later).is_empty());
+        // Well within the cooldown, but it dropped back in between
+        let alerts = tracker.evaluate(&thresholds, &memory(96), later);
+        assert_eq!(keys(&alerts), ["memory"]);
+    }
+
+    #[test]
+    fn test_skips_small_removable_and_read_only_disks() {
+        let mut tracker = AlertTracker::default();
+        let thresholds = AlertThresholds::default();
+
+        let boot = disk("/boot", 1, 0);
+        let usb = DiskSample {
+            removable: true,
+            ..disk("/run/media/usb", 64, 0)
+        };
+        let recovery = DiskSample {
+            read_only: true,
+            ..disk("/recovery", 64, 0)
+        };
+        let live = DiskSample {
+            file_system: "squashfs".to_string(),
+            ..disk("/run/live", 64, 0)
+        };
+        let sample = ResourceSample {
+            disks: vec![boot, usb, recovery, live, disk("/data", 64, 1)],
+            ..Default::default()
+        };
+        let alerts = tracker.evaluate(&thresholds, &sample, Instant::now());
+        assert_eq!(keys(&alerts), ["disk:/data"]);
+    }
+
+    #[test]
+    fn test_disabled_fires_nothing() {
+        let mut tracker = AlertTracker::default();
+        let thresholds = AlertThresholds {
+            enabled: false,
+            ..Default::default()
+        };
+        assert!(tracker
+            .evaluate(&thresholds, &memory(99), Instant::now())
+            .is_empty());
+    }
+}
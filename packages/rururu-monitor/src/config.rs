@@ -0,0 +1,82 @@
+use crate::alerts::AlertThresholds;
+use crate::{RefreshInterval, SortBy, Tab};
+use serde::{Deserialize, Serialize};
+use std::path::PathBuf;
+use tracing::warn;
+
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+#[serde(default)]
+pub struct MonitorConfig {
+    pub alerts: AlertThresholds,
+    pub ui: UiState,
+}
+
+/// Where the user left the window, restored on the next launch
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(default)]
+pub struct UiState {
+    pub tab: Tab,
+    pub sort_by: SortBy,
+    pub sort_ascending: bool,
+    pub window_width: f32,
+    pub window_height: f32,
+    pub refresh_interval: RefreshInterval,
+}
+
+impl Default for UiState {
+    fn default() -> Self {
+        Self {
+            tab: Tab::default(),
+            sort_by: SortBy::default(),
+            sort_ascending: false,
+            window_width: 1000.0,
+            window_height: 700.0,
+            refresh_interval: RefreshInterval::default(),
+        }
+    }
+}
+
+impl UiState {
+    pub const MIN_WINDOW_SIZE: iced::Size = iced::Size::new(800.0, 500.0);
+
+    /// Saved window size, kept at least the minimum size
+    pub fn window_size(&self) -> iced::Size {
+        let valid = |v: f32, min: f32| if v.is_finite() { v.max(min) } else { min };
+        iced::Size::new(
+            valid(self.window_width, Self::MIN_WINDOW_SIZE.width),
+            valid(self.window_height, Self::MIN_WINDOW_SIZE.height),
+        )
+    }
+}
+
+impl MonitorConfig {
+    pub fn load() -> Self {
+        let path = Self::config_path();
+
+        match std::fs::read_to_string(&path) {
+            Ok(content) => toml::from_str(&content).unwrap_or_else(|e| {
+                warn!("Invalid monitor config {:?}: {}", path, e);
+                Self::default()
+            }),
+            Err(_) => Self::default(),
+        }
+    }
+
+    pub fn save(&self) -> std::io::Result<()> {
+        let path = Self::config_path();
+
+        if let Some(parent) = path.parent() {
+            std::fs::create_dir_all(parent)?;
+        }
+
+        let content = toml::to_string_pretty(self).map_err(std::io::Error::other)?;
+        std::fs::write(path, content)
+    }
+
+    fn config_path() -> PathBuf {
+        dirs::config_dir()
+            .unwrap_or_else(|| PathBuf::from("."))
+            .join("rururu")
+            .join("monitor.toml")
+    }
+}
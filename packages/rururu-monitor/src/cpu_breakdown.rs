@@ -0,0 +1,240 @@
+//! Which processes used the CPU over the last minute or so.
+//!
+//! Every tick records each process's share of the whole machine, keyed by
+//! PID, for the last [`HISTORY_TICKS`] ticks. Processes that used nothing
+//! in that window are forgotten, so only the recently busy ones are kept,
+//! and a PID reused by a different program starts a new history. The chart
+//! stacks the busiest few over the window and groups the rest as "other".
+
+use crate::{Message, ProcessInfo};
+use iced::widget::{column, row, text, Space};
+use iced::{Color, Element, Length};
+use plotters_iced::{Chart, ChartBuilder, ChartWidget, DrawingBackend};
+use std::collections::{HashMap, VecDeque};
+use std::time::Duration;
+
+/// Ticks of history kept, as for the aggregate graphs
+pub const HISTORY_TICKS: usize = 60;
+/// Processes charted on their own; the rest are "other"
+pub const TOP_PROCESSES: usize = 5;
+
+/// One per top process, then "other"
+const PALETTE: [(u8, u8, u8); TOP_PROCESSES + 1] = [
+    (66, 165, 245),
+    (255, 167, 38),
+    (102, 187, 106),
+    (239, 83, 80),
+    (171, 71, 188),
+    (120, 120, 120),
+];
+
+struct ProcessHistory {
+    name: String,
+    /// Percent of all CPUs, oldest first, one per recorded tick
+    cpu: VecDeque<f32>,
+}
+
+#[derive(Default)]
+pub struct CpuBreakdown {
+    processes: HashMap<u32, ProcessHistory>,
+    /// Ticks recorded so far, up to [`HISTORY_TICKS`]
+    ticks: usize,
+}
+
+/// A charted process, or everything else when `pid` is `None`
+pub struct Series {
+    pub pid: Option<u32>,
+    pub name: String,
+    /// Percent of all CPUs per tick, oldest first
+    pub cpu: Vec<f32>,
+}
+
+impl Series {
+    pub fn average(&self) -> f32 {
+        self.cpu.iter().sum::<f32>() / self.cpu.len().max(1) as f32
+    }
+}
+
+impl CpuBreakdown {
+    /// Add a tick. sysinfo counts a fully busy core as 100%, so usage is
+    /// divided by `cpu_count` to make the stack add up to the whole machine.
+    pub fn record(&mut self, processes: &[ProcessInfo], cpu_count: usize) {
+        let scale = 1.0 / cpu_count.max(1) as f32;
+        let current: HashMap<u32, (&str, f32)> = processes
+            .iter()
+            .map(|p| (p.pid, (p.name.as_str(), p.cpu * scale)))
+            .collect();
+
+        self.ticks = (self.ticks + 1).min(HISTORY_TICKS);
+        for (pid, history) in &mut self.processes {
+            let usage = match current.get(pid) {
+                Some(&(name, usage)) if name == history.name => usage,
+                // Exited, or the PID now belongs to something else
+                _ => 0.0,
+            };
+            history.cpu.push_back(usage);
+            if history.cpu.len() > self.ticks {
+                history.cpu.pop_front();
+            }
+        }
+
+        for (&pid, &(name, usage)) in &current {
+            let reused = self.processes.get(&pid).is_some_and(|h| h.name != name);
+            if usage > 0.0 && (reused || !self.processes.contains_key(&pid)) {
+                let mut cpu = VecDeque::from(vec![0.0; self.ticks - 1]);
+                cpu.push_back(usage);
+                self.processes.insert(
+                    pid,
+                    ProcessHistory {
+                        name: name.to_string(),
+                        cpu,
+                    },
+                );
+            }
+        }
+
+        self.processes
+            .retain(|_, history| history.cpu.iter().any(|&c| c > 0.0));
+    }
+
+    /// The `n` processes that used the most CPU over the window, busiest
+    /// first, then "other" when anything else used some
+    pub fn top(&self, n: usize) -> Vec<Series> {
+        let total = |h: &ProcessHistory| h.cpu.iter().sum::<f32>();
+        let mut ranked: Vec<(&u32, &ProcessHistory)> = self.processes.iter().collect();
+        ranked.sort_by(|a, b| total(b.1).total_cmp(&total(a.1)).then(a.0.cmp(b.0)));
+
+        let (top, rest) = ranked.split_at(n.min(ranked.len()));
+        let mut series: Vec<Series> = top
+            .iter()
+            .map(|(&pid, history)| Series {
+                pid: Some(pid),
+                name: history.name.clone(),
+                cpu: history.cpu.iter().copied().collect(),
+            })
+            .collect();
+
+        let mut other = vec![0.0; self.ticks];
+        for (_, history) in rest {
+            for (sum, usage) in other.iter_mut().zip(&history.cpu) {
+                *sum += usage;
+            }
+        }
+        if other.iter().any(|&c| c > 0.0) {
+            series.push(Series {
+                pid: None,
+                name: "other".to_string(),
+                cpu: other,
+            });
+        }
+        series
+    }
+}
+
+/// Stacked area chart of the top processes, with a legend of their
+/// averages over the window. `interval` labels the time axis.
+pub fn view(breakdown: &CpuBreakdown, interval: Duration) -> Element<'_, Message> {
+    let series = breakdown.top(TOP_PROCESSES);
+    if series.is_empty() {
+        return text("Collecting samples…").size(12).into();
+    }
+
+    let legend =
+        series
+            .iter()
+            .zip(PALETTE)
+            .fold(row![].spacing(16), |legend, (series, (r, g, b))| {
+                let label = match series.pid {
+                    Some(pid) => format!("{} ({})", series.name, pid),
+                    None => series.name.clone(),
+                };
+                legend.push(
+                    row![
+                        text("■").style(Color::from_rgb8(r, g, b)),
+                        text(format!("{} {:.1}%", label, series.average())).size(12),
+                    ]
+                    .spacing(4)
+                    .align_items(iced::Alignment::Center),
+                )
+            });
+
+    let chart = StackedChart {
+        series,
+        ticks: breakdown.ticks,
+        interval_secs: interval.as_secs_f32(),
+    };
+
+    column![
+        ChartWidget::new(chart).height(Length::Fixed(180.0)),
+        Space::with_height(Length::Fixed(4.0)),
+        legend,
+        text("Average share of all CPUs over the window").size(11),
+    ]
+    .spacing(4)
+    .into()
+}
+
+struct StackedChart {
+    series: Vec<Series>,
+    ticks: usize,
+    interval_secs: f32,
+}
+
+impl Chart<Message> for StackedChart {
+    type State = ();
+
+    fn build_chart<DB: DrawingBackend>(&self, _state: &Self::State, mut builder: ChartBuilder<DB>) {
+        use plotters::prelude::*;
+
+        // Running totals, so each layer is drawn from the axis up to its top
+        let mut layers: Vec<Vec<f32>> = Vec::with_capacity(self.series.len());
+        for series in &self.series {
+            let below = layers
+                .last()
+                .cloned()
+                .unwrap_or_else(|| vec![0.0; self.ticks]);
+            layers.push(below.iter().zip(&series.cpu).map(|(b, c)| b + c).collect());
+        }
+        let peak = layers
+            .last()
+            .and_then(|top| top.iter().copied().reduce(f32::max))
+            .unwrap_or(0.0);
+        // Scaled to the busiest moment, in steps of 10%
+        let y_max = ((peak / 10.0).ceil() * 10.0).clamp(10.0, 100.0);
+
+        let span = (HISTORY_TICKS - 1) as f32 * self.interval_secs;
+        let x = |i: usize| -((self.ticks - 1 - i) as f32) * self.interval_secs;
+
+        let Ok(mut chart) = builder
+            .x_label_area_size(20)
+            .y_label_area_size(36)
+            .margin(8)
+            .build_cartesian_2d(-span..0.0f32, 0.0f32..y_max)
+        else {
+            return;
+        };
+
+        let label_style = ("sans-serif", 11)
+            .into_font()
+            .color(&RGBColor(170, 170, 170));
+        let _ = chart
+            .configure_mesh()
+            .disable_x_mesh()
+            .light_line_style(RGBColor(60, 60, 60).mix(0.3))
+            .bold_line_style(RGBColor(90, 90, 90).mix(0.5))
+            .axis_style(RGBColor(120, 120, 120))
+            .x_labels(7)
+            .y_labels(5)
+            .x_label_style(label_style.clone())
+            .y_label_style(label_style)
+            .x_label_formatter(&|secs| format!("{:.0}s", secs))
+            .y_label_formatter(&|percent| format!("{:.0}%", percent))
+            .draw();
+
+        // Topmost first, so each lower layer is painted over the one above
+        for (layer, (r, g, b)) in layers.iter().zip(PALETTE).rev() {
+            let points = layer.iter().enumerate().map(|(i, &v)| (x(i), v));
+            let _ = chart.draw_series(AreaSeries::new(points, 0.0, RGBColor(r, g, b).filled()));
+        }
+    }
+}
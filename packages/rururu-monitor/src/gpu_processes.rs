@@ -0,0 +1,563 @@
+//! Which processes are using the GPU.
+//!
+//! AMD and Intel drivers publish per-client statistics in the fdinfo of
+//! every open DRM file: cumulative busy time per engine (`drm-engine-*`, in
+//! ns, or `drm-cycles-*` against `drm-total-cycles-*` on Xe) and memory per
+//! region (`drm-memory-*`, `drm-resident-*`). Utilization is the change in
+//! busy time between two samples, like the disk I/O rates. Several fds can
+//! share a client, so clients are counted once per `drm-client-id`.
+//!
+//! The proprietary NVIDIA driver has no fdinfo statistics; NVML reports
+//! per-process framebuffer memory and SM, encoder and decoder use instead,
+//! the latter as samples the driver buffers between two calls. Walking
+//! every process's fds is slow, so sampling happens off the UI thread.
+//!
+//! Only processes whose `/proc/<pid>/fd` is readable show up, so without
+//! root other users' processes are missing.
+
+use nvml_wrapper::enums::device::UsedGpuMemory;
+use nvml_wrapper::struct_wrappers::device::{ProcessInfo, ProcessUtilizationSample};
+use nvml_wrapper::Nvml;
+use std::collections::HashMap;
+use std::fs;
+use std::path::Path;
+use std::time::Instant;
+
+#[derive(Debug, Clone)]
+pub struct GpuProcess {
+    pub pid: u32,
+    pub name: String,
+    /// Kernel driver, or "nvidia" for NVML figures
+    pub driver: String,
+    /// Dedicated memory, or for GPUs without any the system memory the
+    /// process has mapped to the GPU; `None` when the driver doesn't say
+    pub memory: Option<u64>,
+    /// Utilization per engine in percent, busiest first; empty until the
+    /// process has been seen in two samples
+    pub engines: Vec<(String, f32)>,
+}
+
+impl GpuProcess {
+    pub fn busiest(&self) -> Option<f32> {
+        self.engines.first().map(|(_, percent)| *percent)
+    }
+}
+
+/// Engine -> busy counter, with the total it is measured against for cycle
+/// counters; `None` means nanoseconds against wall time
+type BusyCounters = HashMap<String, (u64, Option<u64>)>;
+
+/// One DRM client as an fdinfo file describes it
+#[derive(Debug, Clone, Default, PartialEq)]
+struct DrmClient {
+    driver: String,
+    /// PCI address of the device, which with the client ID names the client
+    pdev: String,
+    client_id: u64,
+    engines: BusyCounters,
+    /// Engine -> instances, which share one busy counter
+    capacity: HashMap<String, u64>,
+    /// Region -> bytes
+    memory: HashMap<String, u64>,
+}
+
+/// Busy counters from the previous sample
+#[derive(Default)]
+pub struct GpuSampler {
+    /// Keyed by (pid, pdev, client ID)
+    busy: HashMap<(u32, String, u64), BusyCounters>,
+    taken_at: Option<Instant>,
+    /// Loaded only on machines with the proprietary driver
+    nvml: Option<Nvml>,
+    /// Driver timestamp of the newest NVML utilization sample, in µs
+    nvml_seen: u64,
+}
+
+impl GpuSampler {
+    pub fn new() -> Self {
+        Self {
+            nvml: Path::new("/proc/driver/nvidia")
+                .exists()
+                .then(Nvml::init)
+                .and_then(Result::ok),
+            ..Self::default()
+        }
+    }
+
+    /// Every process with an open GPU client, biggest memory user first
+    pub fn sample(&mut self) -> Vec<GpuProcess> {
+        let now = Instant::now();
+        let elapsed_ns = self
+            .taken_at
+            .map(|at| now.duration_since(at).as_nanos() as f64)
+            .filter(|ns| *ns > 0.0);
+
+        let mut busy = HashMap::new();
+        let mut processes: Vec<GpuProcess> = drm_clients()
+            .into_iter()
+            .map(|(pid, clients)| {
+                let mut engines: HashMap<String, f32> = HashMap::new();
+                let mut memory = None;
+                let driver = clients[0].driver.clone();
+
+                for client in clients {
+                    let key = (pid, client.pdev.clone(), client.client_id);
+                    if let (Some(previous), Some(wall)) = (self.busy.get(&key), elapsed_ns) {
+                        for (engine, percent) in client.utilization(previous, wall) {
+                            let total = engines.entry(engine).or_default();
+                            *total = (*total + percent).min(100.0);
+                        }
+                    }
+                    if let Some(bytes) = client.memory_used() {
+                        *memory.get_or_insert(0) += bytes;
+                    }
+                    busy.insert(key, client.engines);
+                }
+
+                let mut engines: Vec<(String, f32)> = engines.into_iter().collect();
+                engines.sort_by(|a, b| b.1.total_cmp(&a.1).then_with(|| a.0.cmp(&b.0)));
+                GpuProcess {
+                    pid,
+                    name: process_name(pid),
+                    driver,
+                    memory,
+                    engines,
+                }
+            })
+            .collect();
+
+        if let Some(ref nvml) = self.nvml {
+            processes.extend(nvidia_processes(nvml, &mut self.nvml_seen));
+        }
+        processes.sort_by(|a, b| b.memory.cmp(&a.memory).then_with(|| a.pid.cmp(&b.pid)));
+
+        self.busy = busy;
+        self.taken_at = Some(now);
+        processes
+    }
+}
+
+impl DrmClient {
+    /// Percent busy per engine since `previous`, over `wall` ns
+    fn utilization(&self, previous: &BusyCounters, wall: f64) -> Vec<(String, f32)> {
+        self.engines
+            .iter()
+            .filter_map(|(engine, &(busy, total))| {
+                let &(prev_busy, prev_total) = previous.get(engine)?;
+                let delta = busy.saturating_sub(prev_busy) as f64;
+                let span = match (total, prev_total) {
+                    (Some(total), Some(prev_total)) => total.saturating_sub(prev_total) as f64,
+                    _ => wall * self.capacity.get(engine).copied().unwrap_or(1) as f64,
+                };
+                (span > 0.0).then(|| (engine.clone(), (delta / span * 100.0).min(100.0) as f32))
+            })
+            .collect()
+    }
+
+    /// VRAM when the device has any, all regions otherwise. Resident
+    /// figures are preferred to totals, which include evicted buffers.
+    fn memory_used(&self) -> Option<u64> {
+        let dedicated = |region: &str| region.starts_with("vram") || region.starts_with("local");
+        let sum = |kind: &str, dedicated_only: bool| -> Option<u64> {
+            let mut regions = self
+                .memory
+                .iter()
+                .filter_map(|(key, bytes)| {
+                    let region = key.strip_prefix(kind)?;
+                    (!dedicated_only || dedicated(region)).then_some(*bytes)
+                })
+                .peekable();
+            regions.peek()?;
+            Some(regions.sum())
+        };
+
+        [true, false].into_iter().find_map(|dedicated_only| {
+            ["resident-", "memory-", "total-"]
+                .into_iter()
+                .find_map(|kind| sum(kind, dedicated_only))
+        })
+    }
+}
+
+/// Parse one `/proc/<pid>/fdinfo/<fd>`; `None` for files that aren't DRM
+/// clients or whose driver publishes no statistics
+fn parse_fdinfo(content: &str) -> Option<DrmClient> {
+    let mut client = DrmClient::default();
+    let mut cycles: HashMap<String, u64> = HashMap::new();
+    let mut total_cycles: HashMap<String, u64> = HashMap::new();
+    let mut has_id = false;
+
+    for line in content.lines() {
+        let Some((key, value)) = line.split_once(':') else {
+            continue;
+        };
+        let Some(key) = key.trim().strip_prefix("drm-") else {
+            continue;
+        };
+        let value = value.trim();
+
+        if key == "driver" {
+            client.driver = value.to_string();
+        } else if key == "pdev" {
+            client.pdev = value.to_string();
+        } else if key == "client-id" {
+            client.client_id = value.parse().ok()?;
+            has_id = true;
+        } else if let Some(engine) = key.strip_prefix("engine-capacity-") {
+            if let Ok(capacity) = value.parse() {
+                client.capacity.insert(engine.to_string(), capacity);
+            }
+        } else if let Some(engine) = key.strip_prefix("engine-") {
+            let ns = value.trim_end_matches("ns").trim();
+            if let Ok(ns) = ns.parse() {
+                client.engines.insert(engine.to_string(), (ns, None));
+            }
+        } else if let Some(engine) = key.strip_prefix("total-cycles-") {
+            if let Ok(total) = value.parse() {
+                total_cycles.insert(engine.to_string(), total);
+            }
+        } else if let Some(engine) = key.strip_prefix("cycles-") {
+            if let Ok(busy) = value.parse() {
+                cycles.insert(engine.to_string(), busy);
+            }
+        } else if let Some(bytes) = parse_size(value) {
+            // `memory-`, `total-`, `shared-`, `resident-`, `purgeable-`, `active-`
+            client.memory.insert(key.to_string(), bytes);
+        }
+    }
+
+    for (engine, busy) in cycles {
+        if let Some(&total) = total_cycles.get(&engine) {
+            client.engines.insert(engine, (busy, Some(total)));
+        }
+    }
+
+    (has_id && !client.driver.is_empty()).then_some(client)
+}
+
+/// "1024 KiB", "3 MiB" or plain bytes
+fn parse_size(value: &str) -> Option<u64> {
+    let mut parts = value.split_whitespace();
+    let number: u64 = parts.next()?.parse().ok()?;
+    let factor = match parts.next() {
+        None => 1,
+        Some("KiB") => 1024,
+        Some("MiB") => 1024 * 1024,
+        Some("GiB") => 1024 * 1024 * 1024,
+        Some(_) => return None,
+    };
+    number.checked_mul(factor)
+}
+
+/// DRM clients per process, each counted once however many fds share it
+fn drm_clients() -> HashMap<u32, Vec<DrmClient>> {
+    let mut clients: HashMap<u32, Vec<DrmClient>> = HashMap::new();
+    let Ok(proc_dir) = fs::read_dir("/proc") else {
+        return clients;
+    };
+
+    for entry in proc_dir.flatten() {
+        let Some(pid) = entry
+            .file_name()
+            .to_str()
+            .and_then(|s| s.parse::<u32>().ok())
+        else {
+            continue;
+        };
+        // Unreadable for other users' processes without root
+        let Ok(fds) = fs::read_dir(entry.path().join("fd")) else {
+            continue;
+        };
+
+        for fd in fds.flatten() {
+            let is_drm = fs::read_link(fd.path())
+                .map(|target| target.starts_with("/dev/dri"))
+                .unwrap_or(false);
+            if !is_drm {
+                continue;
+            }
+            let info = entry.path().join("fdinfo").join(fd.file_name());
+            let Some(client) = fs::read_to_string(info)
+                .ok()
+                .and_then(|content| parse_fdinfo(&content))
+            else {
+                continue;
+            };
+
+            let seen = clients.entry(pid).or_default();
+            if !seen
+                .iter()
+                .any(|c| c.client_id == client.client_id && c.pdev == client.pdev)
+            {
+                seen.push(client);
+            }
+        }
+    }
+    clients
+}
+
+/// What NVML reported for one GPU
+struct NvidiaDevice {
+    /// Graphics and compute clients; a process can be both
+    processes: Vec<ProcessInfo>,
+    utilization: Vec<ProcessUtilizationSample>,
+}
+
+/// Per-process use of every NVIDIA GPU. Only utilization samples newer than
+/// `last_seen` count, which then moves on to the newest one.
+fn nvidia_processes(nvml: &Nvml, last_seen: &mut u64) -> Vec<GpuProcess> {
+    let devices: Vec<NvidiaDevice> = (0..nvml.device_count().unwrap_or(0))
+        .filter_map(|index| {
+            let device = nvml.device_by_index(index).ok()?;
+            let mut processes = device.running_graphics_processes().unwrap_or_default();
+            processes.extend(device.running_compute_processes().unwrap_or_default());
+            // Fails with NotFound when the driver took no samples since
+            let utilization = device
+                .process_utilization_stats(*last_seen)
+                .unwrap_or_default();
+            Some(NvidiaDevice {
+                processes,
+                utilization,
+            })
+        })
+        .collect();
+
+    if let Some(newest) = devices
+        .iter()
+        .flat_map(|device| &device.utilization)
+        .map(|sample| sample.timestamp)
+        .max()
+    {
+        *last_seen = newest;
+    }
+    nvidia_usage(&devices)
+}
+
+/// Memory summed over GPUs, and each engine's utilization averaged over a
+/// GPU's samples and summed over GPUs
+fn nvidia_usage(devices: &[NvidiaDevice]) -> Vec<GpuProcess> {
+    let mut processes: HashMap<u32, GpuProcess> = HashMap::new();
+
+    for device in devices {
+        // A graphics and compute client of one GPU reports the same memory
+        let mut memory: HashMap<u32, Option<u64>> = HashMap::new();
+        for info in &device.processes {
+            let used = match info.used_gpu_memory {
+                UsedGpuMemory::Used(bytes) => Some(bytes),
+                UsedGpuMemory::Unavailable => None,
+            };
+            let seen = memory.entry(info.pid).or_default();
+            *seen = (*seen).max(used);
+        }
+        for (pid, used) in memory {
+            let process = processes.entry(pid).or_insert_with(|| nvidia_process(pid));
+            if let Some(bytes) = used {
+                *process.memory.get_or_insert(0) += bytes;
+            }
+        }
+
+        let mut samples: HashMap<u32, Vec<&ProcessUtilizationSample>> = HashMap::new();
+        for sample in &device.utilization {
+            samples.entry(sample.pid).or_default().push(sample);
+        }
+        for (pid, samples) in samples {
+            let average = |percent_of: fn(&ProcessUtilizationSample) -> u32| {
+                let sum: u32 = samples.iter().map(|s| percent_of(s)).sum();
+                sum as f32 / samples.len() as f32
+            };
+            let engines = [
+                ("3D/compute", average(|s| s.sm_util)),
+                ("encode", average(|s| s.enc_util)),
+                ("decode", average(|s| s.dec_util)),
+            ];
+
+            let process = processes.entry(pid).or_insert_with(|| nvidia_process(pid));
+            for (engine, percent) in engines {
+                match process.engines.iter_mut().find(|(name, _)| name == engine) {
+                    Some((_, total)) => *total = (*total + percent).min(100.0),
+                    None => process
+                        .engines
+                        .push((engine.to_string(), percent.min(100.0))),
+                }
+            }
+        }
+    }
+
+    processes
+        .into_values()
+        .map(|mut process| {
+            process
+                .engines
+                .sort_by(|a, b| b.1.total_cmp(&a.1).then_with(|| a.0.cmp(&b.0)));
+            process
+        })
+        .collect()
+}
+
+fn nvidia_process(pid: u32) -> GpuProcess {
+    GpuProcess {
+        pid,
+        name: process_name(pid),
+        driver: "nvidia".to_string(),
+        memory: None,
+        engines: Vec::new(),
+    }
+}
+
+fn process_name(pid: u32) -> String {
+    fs::read_to_string(format!("/proc/{}/comm", pid))
+        .map(|name| name.trim().to_string())
+        .unwrap_or_else(|_| "?".to_string())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const AMDGPU: &str = "pos:\t0
+flags:\t02100002
+drm-driver:\tamdgpu
+drm-client-id:\t42
+drm-pdev:\t0000:03:00.0
+drm-memory-vram:\t524288 KiB
+drm-memory-gtt:\t2048 KiB
+drm-engine-gfx:\t1500000 ns
+drm-engine-compute:\t0 ns
+";
+
+    const XE: &str = "drm-driver:\txe
+drm-client-id:\t7
+drm-pdev:\t0000:00:02.0
+drm-resident-system:\t64 MiB
+drm-total-system:\t128 MiB
+drm-cycles-rcs:\t500
+drm-total-cycles-rcs:\t1000
+drm-cycles-bcs:\t10
+drm-engine-capacity-vcs:\t2
+";
+
+    fn sample(pid: u32, timestamp: u64, sm: u32, enc: u32) -> ProcessUtilizationSample {
+        ProcessUtilizationSample {
+            pid,
+            timestamp,
+            sm_util: sm,
+            mem_util: 0,
+            enc_util: enc,
+            dec_util: 0,
+        }
+    }
+
+    fn process(pid: u32, used: UsedGpuMemory) -> ProcessInfo {
+        ProcessInfo {
+            pid,
+            used_gpu_memory: used,
+            gpu_instance_id: None,
+            compute_instance_id: None,
+        }
+    }
+
+    fn engine(process: &GpuProcess, name: &str) -> Option<f32> {
+        process
+            .engines
+            .iter()
+            .find(|(engine, _)| engine == name)
+            .map(|(_, percent)| *percent)
+    }
+
+    #[test]
+    fn test_parse_fdinfo_amdgpu() {
+        let client = parse_fdinfo(AMDGPU).unwrap();
+        assert_eq!(client.driver, "amdgpu");
+        assert_eq!(client.client_id, 42);
+        assert_eq!(client.pdev, "0000:03:00.0");
+        assert_eq!(client.engines["gfx"], (1_500_000, None));
+        assert_eq!(client.memory["memory-vram"], 512 * 1024 * 1024);
+        // VRAM only, when the device has any
+        assert_eq!(client.memory_used(), Some(512 * 1024 * 1024));
+    }
+
+    #[test]
+    fn test_parse_fdinfo_cycle_counters() {
+        let client = parse_fdinfo(XE).unwrap();
+        assert_eq!(client.engines["rcs"], (500, Some(1000)));
+        // Busy cycles without a total can't be turned into a percentage
+        assert!(!client.engines.contains_key("bcs"));
+        assert_eq!(client.capacity["vcs"], 2);
+        // Resident beats total
+        assert_eq!(client.memory_used(), Some(64 * 1024 * 1024));
+    }
+
+    #[test]
+    fn test_parse_fdinfo_skips_other_files() {
+        assert!(parse_fdinfo("pos:\t0\nflags:\t02\nmnt_id:\t25\n").is_none());
+        // No client ID to tell clients apart
+        assert!(parse_fdinfo("drm-driver:\ti915\ndrm-engine-render:\t5 ns\n").is_none());
+    }
+
+    #[test]
+    fn test_utilization_between_samples() {
+        let client = parse_fdinfo(XE).unwrap();
+        let previous = BusyCounters::from([("rcs".to_string(), (300, Some(600)))]);
+        // 200 busy of 400 cycles
+        assert_eq!(
+            client.utilization(&previous, 1e9),
+            [("rcs".to_string(), 50.0)]
+        );
+
+        let amd = parse_fdinfo(AMDGPU).unwrap();
+        let previous = BusyCounters::from([("gfx".to_string(), (1_000_000, None))]);
+        // 0.5 ms busy in 1 ms
+        let gfx = amd.utilization(&previous, 1_000_000.0);
+        assert_eq!(gfx, [("gfx".to_string(), 50.0)]);
+    }
+
+    #[test]
+    fn test_parse_size() {
+        assert_eq!(parse_size("4096"), Some(4096));
+        assert_eq!(parse_size("2 KiB"), Some(2048));
+        assert_eq!(parse_size("3 MiB"), Some(3 * 1024 * 1024));
+        assert_eq!(parse_size("1 GiB"), Some(1024 * 1024 * 1024));
+        assert_eq!(parse_size("1 TiB"), None);
+        assert_eq!(parse_size("ns"), None);
+        assert_eq!(parse_size(&format!("{} GiB", u64::MAX)), None);
+    }
+
+    #[test]
+    fn test_nvidia_usage_counts_memory_once_per_gpu() {
+        let devices = [
+            NvidiaDevice {
+                processes: vec![
+                    process(10, UsedGpuMemory::Used(100)),
+                    process(10, UsedGpuMemory::Used(100)),
+                    process(20, UsedGpuMemory::Unavailable),
+                ],
+                utilization: Vec::new(),
+            },
+            NvidiaDevice {
+                processes: vec![process(10, UsedGpuMemory::Used(50))],
+                utilization: Vec::new(),
+            },
+        ];
+        let processes = nvidia_usage(&devices);
+
+        let blender = processes.iter().find(|p| p.pid == 10).unwrap();
+        assert_eq!(blender.memory, Some(150));
+        assert!(blender.engines.is_empty());
+        let other = processes.iter().find(|p| p.pid == 20).unwrap();
+        assert_eq!(other.memory, None);
+    }
+
+    #[test]
+    fn test_nvidia_usage_averages_samples() {
+        let devices = [NvidiaDevice {
+            processes: vec![process(10, UsedGpuMemory::Used(100))],
+            utilization: vec![sample(10, 1, 40, 0), sample(10, 2, 60, 10)],
+        }];
+        let processes = nvidia_usage(&devices);
+
+        let process = &processes[0];
+        assert_eq!(engine(process, "3D/compute"), Some(50.0));
+        assert_eq!(engine(process, "encode"), Some(5.0));
+        assert_eq!(process.busiest(), Some(50.0));
+    }
+}
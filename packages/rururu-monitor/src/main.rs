@@ -1,17 +1,44 @@
-use iced::widget::{button, column, container, progress_bar, row, scrollable, text, Space};
-use iced::{Application, Command, Element, Length, Settings, Subscription, Theme};
+mod alerts;
+mod config;
+mod cpu_breakdown;
+mod gpu_processes;
+mod process_io;
+mod sensors;
+mod top_consumers;
+
+use alerts::AlertTracker;
+use config::{MonitorConfig, UiState};
+use cpu_breakdown::CpuBreakdown;
+use gpu_processes::{GpuProcess, GpuSampler};
+use iced::widget::scrollable::RelativeOffset;
+use iced::widget::{
+    button, checkbox, column, container, pick_list, progress_bar, row, scrollable, slider, text,
+    Space,
+};
+use iced::{
+    event, window, Application, Color, Command, Element, Event, Length, Settings, Subscription,
+    Theme,
+};
+use process_io::{IoRate, IoSampler};
+use sensors::{Sensors, Temperature};
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+use std::sync::{Arc, Mutex};
 use std::time::Duration;
-use sysinfo::{Pid, System};
+use sysinfo::{Disks, Pid, System};
+use tracing::warn;
 
 fn main() -> iced::Result {
+    let config = MonitorConfig::load();
+
     MonitorApp::run(Settings {
         window: iced::window::Settings {
-            size: iced::Size::new(1000.0, 700.0),
-            min_size: Some(iced::Size::new(800.0, 500.0)),
+            size: config.ui.window_size(),
+            min_size: Some(UiState::MIN_WINDOW_SIZE),
             ..Default::default()
         },
         antialiasing: true,
-        ..Default::default()
+        ..Settings::with_flags(config)
     })
 }
 
@@ -20,35 +47,100 @@ pub enum Message {
     Tick,
     SelectTab(Tab),
     SelectProcess(u32),
+    /// Select a process and show it in the Processes tab
+    JumpToProcess(u32),
     KillProcess(u32),
     SortProcesses(SortBy),
     ToggleSortOrder,
     RefreshProcesses,
+    AlertsToggled(bool),
+    MemoryThresholdChanged(f32),
+    SwapThresholdChanged(f32),
+    DiskThresholdChanged(f32),
+    /// A threshold slider was let go; saved then rather than on every step
+    ThresholdReleased,
+    NotificationSent(String, u32),
+    NotificationFailed(String),
+    WindowResized(iced::Size),
+    TogglePause,
+    RefreshIntervalChanged(RefreshInterval),
+    GpuProcessesSampled(Vec<GpuProcess>),
 }
 
-#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default, Serialize, Deserialize)]
 pub enum Tab {
     #[default]
     Overview,
     Processes,
+    Gpu,
     Resources,
+    Alerts,
 }
 
-#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default, Serialize, Deserialize)]
 pub enum SortBy {
     #[default]
     Cpu,
     Memory,
     Name,
     Pid,
+    /// Read plus write rate
+    DiskIo,
+}
+
+/// How often the monitor samples the system
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default, Serialize, Deserialize)]
+pub enum RefreshInterval {
+    HalfSecond,
+    #[default]
+    OneSecond,
+    TwoSeconds,
+    FiveSeconds,
+}
+
+impl RefreshInterval {
+    pub const ALL: [RefreshInterval; 4] = [
+        RefreshInterval::HalfSecond,
+        RefreshInterval::OneSecond,
+        RefreshInterval::TwoSeconds,
+        RefreshInterval::FiveSeconds,
+    ];
+
+    pub fn duration(&self) -> Duration {
+        match self {
+            RefreshInterval::HalfSecond => Duration::from_millis(500),
+            RefreshInterval::OneSecond => Duration::from_secs(1),
+            RefreshInterval::TwoSeconds => Duration::from_secs(2),
+            RefreshInterval::FiveSeconds => Duration::from_secs(5),
+        }
+    }
 }
 
+impl std::fmt::Display for RefreshInterval {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            RefreshInterval::HalfSecond => write!(f, "0.5 s"),
+            RefreshInterval::OneSecond => write!(f, "1 s"),
+            RefreshInterval::TwoSeconds => write!(f, "2 s"),
+            RefreshInterval::FiveSeconds => write!(f, "5 s"),
+        }
+    }
+}
+
+/// Rows the Processes tab lists, besides a selected process further down
+const PROCESS_ROWS: usize = 100;
+
+/// Scrollable of the Processes tab, which jumping to a process scrolls
+const PROCESS_LIST: &str = "process-list";
+
 #[derive(Debug, Clone)]
 pub struct ProcessInfo {
     pub pid: u32,
     pub name: String,
     pub cpu: f32,
     pub memory: u64,
+    /// `None` until the process has been seen in two samples
+    pub disk_io: Option<IoRate>,
     pub status: String,
 }
 
@@ -61,33 +153,66 @@ pub struct MonitorApp {
     sort_ascending: bool,
     cpu_history: Vec<f32>,
     memory_history: Vec<f32>,
+    /// Per-process CPU over the same ticks as the history graphs
+    cpu_breakdown: CpuBreakdown,
+    disks: Disks,
+    io_sampler: IoSampler,
+    config: MonitorConfig,
+    alerts: AlertTracker,
+    sensors: Sensors,
+    temperatures: Vec<Temperature>,
+    /// Window resized since the config was last written
+    window_size_dirty: bool,
+    /// Ticks leave the last snapshot on screen instead of refreshing it
+    paused: bool,
+    /// Shared with the sampling task, which runs off the UI thread
+    gpu_sampler: Arc<Mutex<GpuSampler>>,
+    gpu_processes: Vec<GpuProcess>,
+    /// A GPU sample is in flight; ticks don't start another
+    gpu_sampling: bool,
 }
 
 impl Application for MonitorApp {
     type Executor = iced::executor::Default;
     type Message = Message;
     type Theme = Theme;
-    type Flags = ();
+    type Flags = MonitorConfig;
 
-    fn new(_flags: ()) -> (Self, Command<Message>) {
+    fn new(config: MonitorConfig) -> (Self, Command<Message>) {
         let mut system = System::new_all();
         system.refresh_all();
 
-        let processes = collect_processes(&system);
-
-        (
-            Self {
-                system,
-                current_tab: Tab::default(),
-                selected_process: None,
-                processes,
-                sort_by: SortBy::Cpu,
-                sort_ascending: false,
-                cpu_history: vec![0.0; 60],
-                memory_history: vec![0.0; 60],
-            },
-            Command::none(),
-        )
+        let mut io_sampler = IoSampler::default();
+        let processes = collect_processes(&system, &io_sampler.sample(&system));
+        let sensors = Sensors::discover();
+        let temperatures = sensors.read();
+
+        let mut app = Self {
+            system,
+            current_tab: config.ui.tab,
+            selected_process: None,
+            processes,
+            sort_by: config.ui.sort_by,
+            sort_ascending: config.ui.sort_ascending,
+            cpu_history: vec![0.0; 60],
+            memory_history: vec![0.0; 60],
+            cpu_breakdown: CpuBreakdown::default(),
+            disks: Disks::new_with_refreshed_list(),
+            io_sampler,
+            config,
+            alerts: AlertTracker::default(),
+            sensors,
+            temperatures,
+            window_size_dirty: false,
+            paused: false,
+            gpu_sampler: Arc::new(Mutex::new(GpuSampler::new())),
+            gpu_processes: Vec::new(),
+            gpu_sampling: false,
+        };
+        app.sort_processes();
+        let sample = app.sample_gpu_processes();
+
+        (app, sample)
     }
 
     fn title(&self) -> String {
@@ -97,9 +222,16 @@ impl Application for MonitorApp {
     fn update(&mut self, message: Message) -> Command<Message> {
         match message {
             Message::Tick => {
-                self.system.refresh_all();
-                self.processes = collect_processes(&self.system);
-                self.sort_processes();
+                // Resizes arrive continuously while dragging; write at most once a tick
+                if self.window_size_dirty {
+                    self.window_size_dirty = false;
+                    self.save_config();
+                }
+
+                if self.paused {
+                    return Command::none();
+                }
+                self.refresh_processes();
 
                 // Update history
                 let cpu = self.system.global_cpu_usage();
@@ -115,19 +247,57 @@ impl Application for MonitorApp {
                 if self.memory_history.len() > 60 {
                     self.memory_history.remove(0);
                 }
+                self.cpu_breakdown
+                    .record(&self.processes, self.system.cpus().len());
+
+                self.disks.refresh_list();
+                if !self.sensors.is_empty() {
+                    self.temperatures = self.sensors.read();
+                }
+                let alerts = self
+                    .alerts
+                    .check(&self.config.alerts, &self.system, &self.disks);
+
+                let notifications = alerts.into_iter().map(|alert| {
+                    Command::perform(alerts::send_notification(alert), |result| match result {
+                        Ok((key, id)) => Message::NotificationSent(key, id),
+                        Err(e) => Message::NotificationFailed(e.to_string()),
+                    })
+                });
+                return Command::batch(notifications.chain([self.sample_gpu_processes()]));
             }
             Message::SelectTab(tab) => {
                 self.current_tab = tab;
+                self.config.ui.tab = tab;
+                self.save_config();
+                return self.sample_gpu_processes();
             }
             Message::SelectProcess(pid) => {
                 self.selected_process = Some(pid);
             }
+            Message::JumpToProcess(pid) => {
+                self.selected_process = Some(pid);
+                self.current_tab = Tab::Processes;
+                self.config.ui.tab = Tab::Processes;
+                self.save_config();
+
+                let Some(index) = self.processes.iter().position(|p| p.pid == pid) else {
+                    return Command::none();
+                };
+                let last = self.listed_processes().saturating_sub(1).max(1);
+                return scrollable::snap_to(
+                    scrollable::Id::new(PROCESS_LIST),
+                    RelativeOffset {
+                        x: 0.0,
+                        y: index as f32 / last as f32,
+                    },
+                );
+            }
             Message::KillProcess(pid) => {
                 if let Some(process) = self.system.process(Pid::from_u32(pid)) {
                     process.kill();
                 }
-                self.system.refresh_all();
-                self.processes = collect_processes(&self.system);
+                self.refresh_processes();
             }
             Message::SortProcesses(sort_by) => {
                 if self.sort_by == sort_by {
@@ -137,15 +307,53 @@ impl Application for MonitorApp {
                     self.sort_ascending = false;
                 }
                 self.sort_processes();
+                self.sorting_changed();
             }
             Message::ToggleSortOrder => {
                 self.sort_ascending = !self.sort_ascending;
                 self.sort_processes();
+                self.sorting_changed();
             }
             Message::RefreshProcesses => {
-                self.system.refresh_all();
-                self.processes = collect_processes(&self.system);
-                self.sort_processes();
+                self.refresh_processes();
+            }
+            Message::AlertsToggled(enabled) => {
+                self.config.alerts.enabled = enabled;
+                self.save_config();
+            }
+            Message::MemoryThresholdChanged(val) => {
+                self.config.alerts.memory_percent = val;
+            }
+            Message::SwapThresholdChanged(val) => {
+                self.config.alerts.swap_percent = val;
+            }
+            Message::DiskThresholdChanged(val) => {
+                self.config.alerts.disk_free_gb = val;
+            }
+            Message::ThresholdReleased => {
+                self.save_config();
+            }
+            Message::NotificationSent(key, id) => {
+                self.alerts.notification_sent(key, id);
+            }
+            Message::NotificationFailed(e) => {
+                warn!("Failed to send notification: {}", e);
+            }
+            Message::WindowResized(size) => {
+                self.config.ui.window_width = size.width;
+                self.config.ui.window_height = size.height;
+                self.window_size_dirty = true;
+            }
+            Message::TogglePause => {
+                self.paused = !self.paused;
+            }
+            Message::RefreshIntervalChanged(interval) => {
+                self.config.ui.refresh_interval = interval;
+                self.save_config();
+            }
+            Message::GpuProcessesSampled(processes) => {
+                self.gpu_processes = processes;
+                self.gpu_sampling = false;
             }
         }
         Command::none()
@@ -155,14 +363,21 @@ impl Application for MonitorApp {
         let tabs = row![
             tab_button("Overview", Tab::Overview, self.current_tab),
             tab_button("Processes", Tab::Processes, self.current_tab),
+            tab_button("GPU", Tab::Gpu, self.current_tab),
             tab_button("Resources", Tab::Resources, self.current_tab),
+            tab_button("Alerts", Tab::Alerts, self.current_tab),
+            Space::with_width(Length::Fill),
+            self.view_refresh_controls(),
         ]
-        .spacing(4);
+        .spacing(4)
+        .align_items(iced::Alignment::Center);
 
         let content: Element<Message> = match self.current_tab {
             Tab::Overview => self.view_overview(),
             Tab::Processes => self.view_processes(),
+            Tab::Gpu => self.view_gpu_processes(),
             Tab::Resources => self.view_resources(),
+            Tab::Alerts => self.view_alerts(),
         };
 
         container(column![tabs, Space::with_height(Length::Fixed(16.0)), content,].padding(16))
@@ -172,7 +387,17 @@ impl Application for MonitorApp {
     }
 
     fn subscription(&self) -> Subscription<Message> {
-        iced::time::every(Duration::from_secs(1)).map(|_| Message::Tick)
+        let resized = event::listen_with(|event, _status| match event {
+            Event::Window(_, window::Event::Resized { width, height }) => Some(
+                Message::WindowResized(iced::Size::new(width as f32, height as f32)),
+            ),
+            _ => None,
+        });
+
+        Subscription::batch([
+            iced::time::every(self.config.ui.refresh_interval.duration()).map(|_| Message::Tick),
+            resized,
+        ])
     }
 
     fn theme(&self) -> Theme {
@@ -181,6 +406,83 @@ impl Application for MonitorApp {
 }
 
 impl MonitorApp {
+    fn save_config(&self) {
+        if let Err(e) = self.config.save() {
+            warn!("Failed to save monitor config: {}", e);
+        }
+    }
+
+    fn view_refresh_controls(&self) -> Element<'_, Message> {
+        let mut controls = row![].spacing(8).align_items(iced::Alignment::Center);
+        if self.paused {
+            controls = controls.push(text("Paused").style(Color::from_rgb(1.0, 0.8, 0.2)));
+        }
+
+        controls
+            .push(text("Refresh every").size(12))
+            .push(
+                pick_list(
+                    RefreshInterval::ALL,
+                    Some(self.config.ui.refresh_interval),
+                    Message::RefreshIntervalChanged,
+                )
+                .text_size(12),
+            )
+            .push(
+                button(
+                    text(if self.paused {
+                        "▶ Resume"
+                    } else {
+                        "⏸ Pause"
+                    })
+                    .size(12),
+                )
+                .on_press(Message::TogglePause),
+            )
+            .into()
+    }
+
+    fn sorting_changed(&mut self) {
+        self.config.ui.sort_by = self.sort_by;
+        self.config.ui.sort_ascending = self.sort_ascending;
+        self.save_config();
+    }
+
+    fn refresh_processes(&mut self) {
+        self.system.refresh_all();
+        let io_rates = self.io_sampler.sample(&self.system);
+        self.processes = collect_processes(&self.system, &io_rates);
+        self.sort_processes();
+    }
+
+    /// Sample GPU clients in the background while the GPU tab, or the
+    /// Overview with its top GPU memory user, is open
+    fn sample_gpu_processes(&mut self) -> Command<Message> {
+        if !matches!(self.current_tab, Tab::Gpu | Tab::Overview) || self.gpu_sampling {
+            return Command::none();
+        }
+        self.gpu_sampling = true;
+
+        let sampler = Arc::clone(&self.gpu_sampler);
+        Command::perform(
+            async move {
+                tokio::task::spawn_blocking(move || sampler.lock().unwrap().sample())
+                    .await
+                    .unwrap_or_default()
+            },
+            Message::GpuProcessesSampled,
+        )
+    }
+
+    /// Rows in the Processes tab: the first [`PROCESS_ROWS`], extended to
+    /// reach the selected process
+    fn listed_processes(&self) -> usize {
+        let selected = self
+            .selected_process
+            .and_then(|pid| self.processes.iter().position(|p| p.pid == pid));
+        selected.map_or(PROCESS_ROWS, |index| (index + 1).max(PROCESS_ROWS))
+    }
+
     fn sort_processes(&mut self) {
         match self.sort_by {
             SortBy::Cpu => {
@@ -219,6 +521,16 @@ impl MonitorApp {
                     }
                 });
             }
+            SortBy::DiskIo => {
+                let total = |p: &ProcessInfo| p.disk_io.map_or(0.0, |io| io.total());
+                self.processes.sort_by(|a, b| {
+                    if self.sort_ascending {
+                        total(a).total_cmp(&total(b))
+                    } else {
+                        total(b).total_cmp(&total(a))
+                    }
+                });
+            }
         }
     }
 
@@ -238,7 +550,7 @@ impl MonitorApp {
 
         let process_count = self.processes.len();
 
-        column![
+        let mut overview = column![
             // CPU
             text("CPU").size(18),
             row![
@@ -275,23 +587,56 @@ impl MonitorApp {
             .align_items(iced::Alignment::Center)
             .padding(8),
             Space::with_height(Length::Fixed(16.0)),
-            // Stats
-            text("System").size(18),
-            row![
-                text("Processes:"),
-                Space::with_width(Length::Fixed(8.0)),
-                text(format!("{}", process_count)),
-            ]
-            .padding(8),
-            row![
-                text("Uptime:"),
-                Space::with_width(Length::Fixed(8.0)),
-                text(format_uptime(System::uptime())),
-            ]
-            .padding(8),
         ]
-        .spacing(4)
-        .into()
+        .spacing(4);
+
+        // Hidden entirely when no sensor could be found
+        if !self.temperatures.is_empty() {
+            overview = overview.push(text("Temperatures").size(18));
+            for temp in &self.temperatures {
+                let reading = text(format!("{:.0}°C", temp.celsius));
+                let reading = match temperature_color(temp) {
+                    Some(color) => reading.style(iced::theme::Text::Color(color)),
+                    None => reading,
+                };
+
+                overview = overview.push(
+                    row![
+                        text(format!("{}:", temp.label)),
+                        Space::with_width(Length::Fixed(8.0)),
+                        reading,
+                        Space::with_width(Length::Fixed(8.0)),
+                        text(format!("(throttles at {:.0}°C)", temp.throttle_celsius)).size(12),
+                    ]
+                    .align_items(iced::Alignment::Center)
+                    .padding(8),
+                );
+            }
+            overview = overview.push(Space::with_height(Length::Fixed(16.0)));
+        }
+
+        overview
+            .push(text("Top Consumers").size(18))
+            .push(container(top_consumers::view(&self.processes, &self.gpu_processes)).padding(8))
+            .push(Space::with_height(Length::Fixed(16.0)))
+            .push(text("System").size(18))
+            .push(
+                row![
+                    text("Processes:"),
+                    Space::with_width(Length::Fixed(8.0)),
+                    text(format!("{}", process_count)),
+                ]
+                .padding(8),
+            )
+            .push(
+                row![
+                    text("Uptime:"),
+                    Space::with_width(Length::Fixed(8.0)),
+                    text(format_uptime(System::uptime())),
+                ]
+                .padding(8),
+            )
+            .into()
     }
 
     fn view_processes(&self) -> Element<'_, Message> {
@@ -312,6 +657,10 @@ impl MonitorApp {
                 .style(iced::theme::Button::Text)
                 .on_press(Message::SortProcesses(SortBy::Memory))
                 .width(Length::Fixed(100.0)),
+            button(text("Disk read / write").size(12))
+                .style(iced::theme::Button::Text)
+                .on_press(Message::SortProcesses(SortBy::DiskIo))
+                .width(Length::Fixed(170.0)),
             text("Status").size(12).width(Length::Fixed(80.0)),
         ]
         .spacing(8)
@@ -320,10 +669,18 @@ impl MonitorApp {
         let processes: Vec<Element<Message>> = self
             .processes
             .iter()
-            .take(100)
+            .take(self.listed_processes())
             .map(|p| {
                 let is_selected = self.selected_process == Some(p.pid);
                 let mem_mb = p.memory as f64 / 1024.0 / 1024.0;
+                let disk_io = match p.disk_io {
+                    Some(io) => format!(
+                        "{} / {}",
+                        process_io::format_rate(io.read),
+                        process_io::format_rate(io.write)
+                    ),
+                    None => "—".to_string(),
+                };
 
                 let row_content = row![
                     text(format!("{}", p.pid))
@@ -336,6 +693,7 @@ impl MonitorApp {
                     text(format!("{:.1} MB", mem_mb))
                         .size(12)
                         .width(Length::Fixed(100.0)),
+                    text(disk_io).size(12).width(Length::Fixed(170.0)),
                     text(&p.status).size(12).width(Length::Fixed(80.0)),
                 ]
                 .spacing(8)
@@ -375,7 +733,107 @@ impl MonitorApp {
             actions,
             Space::with_height(Length::Fixed(8.0)),
             header,
-            scrollable(column(processes).spacing(2)).height(Length::Fill),
+            scrollable(column(processes).spacing(2))
+                .id(scrollable::Id::new(PROCESS_LIST))
+                .height(Length::Fill),
+        ]
+        .spacing(4)
+        .into()
+    }
+
+    fn view_gpu_processes(&self) -> Element<'_, Message> {
+        let header = row![
+            text("PID").size(12).width(Length::Fixed(70.0)),
+            text("Name").size(12).width(Length::FillPortion(2)),
+            text("Driver").size(12).width(Length::Fixed(80.0)),
+            text("GPU memory").size(12).width(Length::Fixed(100.0)),
+            text("Busiest").size(12).width(Length::Fixed(70.0)),
+            text("Engines").size(12).width(Length::FillPortion(3)),
+        ]
+        .spacing(8)
+        .padding(8);
+
+        let processes: Vec<Element<Message>> = self
+            .gpu_processes
+            .iter()
+            .map(|p| {
+                let memory = p.memory.map_or("—".to_string(), |bytes| {
+                    format!("{:.1} MB", bytes as f64 / 1024.0 / 1024.0)
+                });
+                let busiest = p
+                    .busiest()
+                    .map_or("—".to_string(), |percent| format!("{:.0}%", percent));
+                let engines: Vec<String> = p
+                    .engines
+                    .iter()
+                    .filter(|(_, percent)| *percent >= 0.5)
+                    .map(|(engine, percent)| format!("{} {:.0}%", engine, percent))
+                    .collect();
+
+                let row_content = row![
+                    text(format!("{}", p.pid))
+                        .size(12)
+                        .width(Length::Fixed(70.0)),
+                    text(&p.name).size(12).width(Length::FillPortion(2)),
+                    text(&p.driver).size(12).width(Length::Fixed(80.0)),
+                    text(memory).size(12).width(Length::Fixed(100.0)),
+                    text(busiest).size(12).width(Length::Fixed(70.0)),
+                    text(engines.join(", "))
+                        .size(12)
+                        .width(Length::FillPortion(3)),
+                ]
+                .spacing(8)
+                .padding(4);
+
+                let style = if self.selected_process == Some(p.pid) {
+                    iced::theme::Button::Primary
+                } else {
+                    iced::theme::Button::Text
+                };
+
+                button(row_content)
+                    .style(style)
+                    .width(Length::Fill)
+                    .on_press(Message::SelectProcess(p.pid))
+                    .into()
+            })
+            .collect();
+
+        let mut actions = row![].spacing(8).align_items(iced::Alignment::Center);
+        if let Some(pid) = self
+            .selected_process
+            .filter(|pid| self.gpu_processes.iter().any(|p| p.pid == *pid))
+        {
+            actions = actions.push(
+                button(text("Kill Process"))
+                    .style(iced::theme::Button::Destructive)
+                    .on_press(Message::KillProcess(pid)),
+            );
+        }
+        let actions = actions.push(
+            text("Processes holding a GPU context, by memory. Without root, other users' processes are not listed.")
+                .size(12),
+        );
+
+        let list: Element<Message> = if processes.is_empty() {
+            text(if self.gpu_sampling {
+                "Sampling…"
+            } else {
+                "No processes are using the GPU."
+            })
+            .size(12)
+            .into()
+        } else {
+            scrollable(column(processes).spacing(2))
+                .height(Length::Fill)
+                .into()
+        };
+
+        column![
+            actions,
+            Space::with_height(Length::Fixed(8.0)),
+            header,
+            list,
         ]
         .spacing(4)
         .into()
@@ -406,6 +864,13 @@ impl MonitorApp {
             .collect();
 
         column![
+            text("CPU by Process").size(18),
+            Space::with_height(Length::Fixed(8.0)),
+            cpu_breakdown::view(
+                &self.cpu_breakdown,
+                self.config.ui.refresh_interval.duration()
+            ),
+            Space::with_height(Length::Fixed(24.0)),
             text("CPU Cores").size(18),
             Space::with_height(Length::Fixed(8.0)),
             column(cpu_items).spacing(4),
@@ -451,6 +916,64 @@ impl MonitorApp {
 
         column(disk_items).spacing(8).into()
     }
+
+    fn view_alerts(&self) -> Element<'_, Message> {
+        let thresholds = &self.config.alerts;
+
+        column![
+            text("Alerts").size(18),
+            text("Send a desktop notification when a resource crosses its threshold.").size(12),
+            Space::with_height(Length::Fixed(8.0)),
+            checkbox("Enable alerts", thresholds.enabled).on_toggle(Message::AlertsToggled),
+            Space::with_height(Length::Fixed(16.0)),
+            row![
+                text("Memory usage").width(Length::Fixed(140.0)),
+                slider(
+                    50.0..=100.0,
+                    thresholds.memory_percent,
+                    Message::MemoryThresholdChanged
+                )
+                .on_release(Message::ThresholdReleased),
+                text(format!("{:.0}%", thresholds.memory_percent)).width(Length::Fixed(60.0)),
+            ]
+            .spacing(8)
+            .align_items(iced::Alignment::Center),
+            row![
+                text("Swap usage").width(Length::Fixed(140.0)),
+                slider(
+                    10.0..=100.0,
+                    thresholds.swap_percent,
+                    Message::SwapThresholdChanged
+                )
+                .on_release(Message::ThresholdReleased),
+                text(format!("{:.0}%", thresholds.swap_percent)).width(Length::Fixed(60.0)),
+            ]
+            .spacing(8)
+            .align_items(iced::Alignment::Center),
+            row![
+                text("Disk free below").width(Length::Fixed(140.0)),
+                slider(
+                    0.5..=50.0,
+                    thresholds.disk_free_gb,
+                    Message::DiskThresholdChanged
+                )
+                .step(0.5)
+                .on_release(Message::ThresholdReleased),
+                text(format!("{:.1} GB", thresholds.disk_free_gb)).width(Length::Fixed(60.0)),
+            ]
+            .spacing(8)
+            .align_items(iced::Alignment::Center),
+            text("Removable, read-only and small disks like /boot are not watched.").size(11),
+            Space::with_height(Length::Fixed(8.0)),
+            text(format!(
+                "Repeated alerts for the same resource are suppressed for {} minutes.",
+                thresholds.cooldown_secs / 60
+            ))
+            .size(11),
+        ]
+        .spacing(8)
+        .into()
+    }
 }
 
 fn tab_button(label: &str, tab: Tab, current: Tab) -> Element<'_, Message> {
@@ -467,7 +990,7 @@ fn tab_button(label: &str, tab: Tab, current: Tab) -> Element<'_, Message> {
         .into()
 }
 
-fn collect_processes(system: &System) -> Vec<ProcessInfo> {
+fn collect_processes(system: &System, io_rates: &HashMap<u32, IoRate>) -> Vec<ProcessInfo> {
     system
         .processes()
         .iter()
@@ -476,11 +999,24 @@ fn collect_processes(system: &System) -> Vec<ProcessInfo> {
             name: process.name().to_string_lossy().to_string(),
             cpu: process.cpu_usage(),
             memory: process.memory(),
+            disk_io: io_rates.get(&pid.as_u32()).copied(),
             status: format!("{:?}", process.status()),
         })
         .collect()
 }
 
+/// Orange within 15°C of the throttle point, red within 5°C
+fn temperature_color(temp: &Temperature) -> Option<Color> {
+    let margin = temp.margin();
+    if margin <= 5.0 {
+        Some(Color::from_rgb(0.9, 0.2, 0.2))
+    } else if margin <= 15.0 {
+        Some(Color::from_rgb(0.95, 0.6, 0.1))
+    } else {
+        None
+    }
+}
+
 fn format_uptime(seconds: u64) -> String {
     let days = seconds / 86400;
     let hours = (seconds % 86400) / 3600;
@@ -0,0 +1,92 @@
+//! Per-process disk I/O rates.
+//!
+//! sysinfo reports cumulative bytes per process; a rate is the change
+//! between two samples over the time between them. Processes are keyed by
+//! PID and start time, so a recycled PID starts over instead of showing a
+//! bogus delta, and ones that exited are forgotten at the next sample.
+
+use std::collections::HashMap;
+use std::time::Instant;
+use sysinfo::System;
+
+#[derive(Debug, Clone, Copy, Default, PartialEq)]
+pub struct IoRate {
+    /// Bytes per second
+    pub read: f64,
+    pub write: f64,
+}
+
+impl IoRate {
+    pub fn total(&self) -> f64 {
+        self.read + self.write
+    }
+}
+
+#[derive(Default)]
+pub struct IoSampler {
+    /// (pid, start time) -> (bytes read, bytes written) at `taken_at`
+    totals: HashMap<(u32, u64), (u64, u64)>,
+    taken_at: Option<Instant>,
+}
+
+impl IoSampler {
+    /// Rates since the previous sample for every process seen in both;
+    /// empty on the first call
+    pub fn sample(&mut self, system: &System) -> HashMap<u32, IoRate> {
+        let now = Instant::now();
+        let elapsed = self
+            .taken_at
+            .map(|at| now.duration_since(at).as_secs_f64())
+            .filter(|secs| *secs > 0.0);
+
+        let totals: HashMap<(u32, u64), (u64, u64)> = system
+            .processes()
+            .iter()
+            .map(|(pid, process)| {
+                let usage = process.disk_usage();
+                (
+                    (pid.as_u32(), process.start_time()),
+                    (usage.total_read_bytes, usage.total_written_bytes),
+                )
+            })
+            .collect();
+
+        let rates = match elapsed {
+            Some(secs) => totals
+                .iter()
+                .filter_map(|(key, (read, written))| {
+                    let (prev_read, prev_written) = self.totals.get(key)?;
+                    Some((
+                        key.0,
+                        IoRate {
+                            read: read.saturating_sub(*prev_read) as f64 / secs,
+                            write: written.saturating_sub(*prev_written) as f64 / secs,
+                        },
+                    ))
+                })
+                .collect(),
+            None => HashMap::new(),
+        };
+
+        self.totals = totals;
+        self.taken_at = Some(now);
+        rates
+    }
+}
+
+/// "12.3 MB/s"
+pub fn format_rate(bytes_per_sec: f64) -> String {
+    const KB: f64 = 1024.0;
+    const MB: f64 = KB * 1024.0;
+    const GB: f64 = MB * 1024.0;
+
+    if bytes_per_sec >= GB {
+        format!("{:.1} GB/s", bytes_per_sec / GB)
+    } else if bytes_per_sec >= MB {
+        format!("{:.1} MB/s", bytes_per_sec / MB)
+    } else if bytes_per_sec >= KB {
+        format!("{:.0} KB/s", bytes_per_sec / KB)
+    } else {
+        format!("{:.0} B/s", bytes_per_sec)
+    }
+}
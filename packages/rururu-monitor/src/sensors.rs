@@ -0,0 +1,226 @@
+use nvml_wrapper::enum_wrappers::device::{TemperatureSensor, TemperatureThreshold};
+use nvml_wrapper::Nvml;
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::sync::Arc;
+
+/// Used when the sensor doesn't publish a max/crit limit
+const DEFAULT_THROTTLE_CELSIUS: f32 = 95.0;
+
+/// Typical GeForce/RTX slowdown point, for GPUs that don't report theirs
+const NVIDIA_THROTTLE_CELSIUS: f32 = 90.0;
+
+#[derive(Debug, Clone)]
+pub struct Temperature {
+    pub label: String,
+    pub celsius: f32,
+    pub throttle_celsius: f32,
+}
+
+impl Temperature {
+    /// Degrees left before the sensor's throttle point
+    pub fn margin(&self) -> f32 {
+        self.throttle_celsius - self.celsius
+    }
+}
+
+/// One `tempN_*` channel of a hwmon device
+#[derive(Debug, Clone)]
+struct HwmonChannel {
+    label: String,
+    input: PathBuf,
+    throttle_celsius: f32,
+}
+
+impl HwmonChannel {
+    fn read(&self) -> Option<Temperature> {
+        Some(Temperature {
+            label: self.label.clone(),
+            celsius: read_millidegrees(&self.input)?,
+            throttle_celsius: self.throttle_celsius,
+        })
+    }
+}
+
+#[derive(Debug, Clone)]
+enum GpuSensor {
+    Hwmon(HwmonChannel),
+    /// The proprietary driver has no hwmon node; NVML reads the GPU at
+    /// `index` instead
+    Nvidia {
+        nvml: Arc<Nvml>,
+        index: u32,
+        label: String,
+        throttle_celsius: f32,
+    },
+}
+
+/// Temperature sources found at startup. Discovery walks
+/// `/sys/class/hwmon` once so each tick only reads the input files.
+#[derive(Debug, Clone, Default)]
+pub struct Sensors {
+    cpu: Option<HwmonChannel>,
+    gpus: Vec<GpuSensor>,
+}
+
+impl Sensors {
+    pub fn discover() -> Self {
+        let mut sensors = Self::default();
+
+        if let Ok(entries) = fs::read_dir("/sys/class/hwmon") {
+            let mut devices: Vec<PathBuf> = entries.flatten().map(|e| e.path()).collect();
+            devices.sort();
+
+            for device in devices {
+                let name = fs::read_to_string(device.join("name")).unwrap_or_default();
+                match name.trim() {
+                    // Intel package, AMD control/die temperature, ARM SoC zone
+                    "coretemp" | "k10temp" | "zenpower" | "cpu_thermal"
+                        if sensors.cpu.is_none() =>
+                    {
+                        sensors.cpu = cpu_channel(&device);
+                    }
+                    "amdgpu" | "nouveau" | "i915" | "xe" => {
+                        if let Some(channel) = gpu_channel(&device, name.trim()) {
+                            sensors.gpus.push(GpuSensor::Hwmon(channel));
+                        }
+                    }
+                    _ => {}
+                }
+            }
+        }
+
+        if Path::new("/proc/driver/nvidia").exists() {
+            sensors.gpus.extend(nvidia_sensors());
+        }
+
+        sensors
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.cpu.is_none() && self.gpus.is_empty()
+    }
+
+    pub fn read(&self) -> Vec<Temperature> {
+        let mut temps: Vec<Temperature> = self.cpu.iter().filter_map(|c| c.read()).collect();
+
+        for gpu in &self.gpus {
+            match gpu {
+                GpuSensor::Hwmon(channel) => temps.extend(channel.read()),
+                GpuSensor::Nvidia {
+                    nvml,
+                    index,
+                    label,
+                    throttle_celsius,
+                } => {
+                    let celsius = nvml
+                        .device_by_index(*index)
+                        .and_then(|device| device.temperature(TemperatureSensor::Gpu));
+                    if let Ok(celsius) = celsius {
+                        temps.push(Temperature {
+                            label: label.clone(),
+                            celsius: celsius as f32,
+                            throttle_celsius: *throttle_celsius,
+                        });
+                    }
+                }
+            }
+        }
+
+        temps
+    }
+}
+
+/// Channels of a hwmon device as (index, label)
+fn channels(device: &Path) -> Vec<(u32, String)> {
+    let mut found = Vec::new();
+
+    if let Ok(entries) = fs::read_dir(device) {
+        for entry in entries.flatten() {
+            let file = entry.file_name().to_string_lossy().to_string();
+            let Some(index) = file
+                .strip_prefix("temp")
+                .and_then(|s| s.strip_suffix("_input"))
+                .and_then(|s| s.parse::<u32>().ok())
+            else {
+                continue;
+            };
+
+            let label = fs::read_to_string(device.join(format!("temp{}_label", index)))
+                .map(|l| l.trim().to_string())
+                .unwrap_or_default();
+            found.push((index, label));
+        }
+    }
+
+    found.sort();
+    found
+}
+
+fn channel(device: &Path, index: u32, label: String) -> HwmonChannel {
+    // Prefer the throttle point ("max") over the shutdown point ("crit")
+    let throttle_celsius = ["max", "crit"]
+        .iter()
+        .find_map(|limit| read_millidegrees(&device.join(format!("temp{}_{}", index, limit))))
+        .filter(|t| *t > 0.0)
+        .unwrap_or(DEFAULT_THROTTLE_CELSIUS);
+
+    HwmonChannel {
+        label,
+        input: device.join(format!("temp{}_input", index)),
+        throttle_celsius,
+    }
+}
+
+fn cpu_channel(device: &Path) -> Option<HwmonChannel> {
+    let channels = channels(device);
+
+    let (index, _) = channels
+        .iter()
+        .find(|(_, label)| label.starts_with("Package id") || label == "Tdie" || label == "Tctl")
+        .or(channels.first())?;
+
+    Some(channel(device, *index, "CPU Package".to_string()))
+}
+
+fn gpu_channel(device: &Path, driver: &str) -> Option<HwmonChannel> {
+    let channels = channels(device);
+
+    // amdgpu's hotspot sensor is what its throttling logic watches
+    let (index, _) = channels
+        .iter()
+        .find(|(_, label)| label == "junction")
+        .or(channels.first())?;
+
+    Some(channel(device, *index, format!("GPU ({})", driver)))
+}
+
+fn read_millidegrees(path: &Path) -> Option<f32> {
+    let raw = fs::read_to_string(path).ok()?;
+    raw.trim().parse::<f32>().ok().map(|m| m / 1000.0)
+}
+
+/// One sensor per GPU NVML knows, with the slowdown point it reports.
+/// Loading libnvidia-ml happens once here; reads are library calls.
+fn nvidia_sensors() -> Vec<GpuSensor> {
+    let Ok(nvml) = Nvml::init() else {
+        return Vec::new();
+    };
+    let nvml = Arc::new(nvml);
+
+    (0..nvml.device_count().unwrap_or(0))
+        .filter_map(|index| {
+            let device = nvml.device_by_index(index).ok()?;
+            let throttle_celsius = device
+                .temperature_threshold(TemperatureThreshold::Slowdown)
+                .map(|t| t as f32)
+                .unwrap_or(NVIDIA_THROTTLE_CELSIUS);
+            Some(GpuSensor::Nvidia {
+                label: device.name().unwrap_or_else(|_| "NVIDIA GPU".to_string()),
+                nvml: Arc::clone(&nvml),
+                index,
+                throttle_celsius,
+            })
+        })
+        .collect()
+}
@@ -0,0 +1,107 @@
+//! The biggest user of CPU, memory and GPU memory, for a glance at what's
+//! loading the machine without going through the process list.
+//!
+//! Everything comes from the last tick's samples. GPU memory is left out
+//! until a GPU sample has arrived, and for drivers that don't report it.
+//! Each row jumps to its process in the Processes tab.
+
+use crate::gpu_processes::GpuProcess;
+use crate::{Message, ProcessInfo};
+use iced::widget::{button, column, row, text, Space};
+use iced::{Element, Length};
+
+/// Below this a process isn't really using the CPU, and the top spot is
+/// down to sampling noise
+const IDLE_CPU_PERCENT: f32 = 0.5;
+
+struct Consumer<'a> {
+    resource: &'static str,
+    pid: u32,
+    name: &'a str,
+    usage: String,
+}
+
+fn consumers<'a>(
+    processes: &'a [ProcessInfo],
+    gpu_processes: &'a [GpuProcess],
+) -> Vec<Consumer<'a>> {
+    let mut consumers = Vec::new();
+
+    if let Some(p) = processes
+        .iter()
+        .filter(|p| p.cpu >= IDLE_CPU_PERCENT)
+        .max_by(|a, b| a.cpu.total_cmp(&b.cpu))
+    {
+        consumers.push(Consumer {
+            resource: "CPU",
+            pid: p.pid,
+            name: &p.name,
+            usage: format!("{:.1}%", p.cpu),
+        });
+    }
+    if let Some(p) = processes.iter().max_by_key(|p| p.memory) {
+        consumers.push(Consumer {
+            resource: "Memory",
+            pid: p.pid,
+            name: &p.name,
+            usage: format_mb(p.memory),
+        });
+    }
+    if let Some((p, bytes)) = gpu_processes
+        .iter()
+        .filter_map(|p| Some((p, p.memory?)))
+        .max_by_key(|(_, bytes)| *bytes)
+    {
+        consumers.push(Consumer {
+            resource: "GPU memory",
+            pid: p.pid,
+            name: &p.name,
+            usage: format_mb(bytes),
+        });
+    }
+
+    consumers
+}
+
+pub fn view<'a>(
+    processes: &'a [ProcessInfo],
+    gpu_processes: &'a [GpuProcess],
+) -> Element<'a, Message> {
+    let rows: Vec<Element<Message>> = consumers(processes, gpu_processes)
+        .into_iter()
+        .map(|consumer| {
+            button(
+                row![
+                    text(format!("{}:", consumer.resource)).width(Length::Fixed(110.0)),
+                    text(consumer.name).width(Length::Fill),
+                    text(format!("PID {}", consumer.pid))
+                        .size(12)
+                        .width(Length::Fixed(80.0)),
+                    text(consumer.usage).width(Length::Fixed(90.0)),
+                ]
+                .spacing(8)
+                .align_items(iced::Alignment::Center),
+            )
+            .style(iced::theme::Button::Text)
+            .width(Length::Fill)
+            .on_press(Message::JumpToProcess(consumer.pid))
+            .into()
+        })
+        .collect();
+
+    if rows.is_empty() {
+        return text("Collecting samples…").size(12).into();
+    }
+
+    column![
+        column(rows).spacing(2),
+        Space::with_height(Length::Fixed(4.0)),
+        text("Click a process to show it in the Processes tab").size(11),
+    ]
+    .spacing(4)
+    .into()
+}
+
+fn format_mb(bytes: u64) -> String {
+    format!("{:.1} MB", bytes as f64 / 1024.0 / 1024.0)
+}
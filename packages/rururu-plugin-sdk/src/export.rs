@@ -0,0 +1,198 @@
+//! What [`export_plugin!`](crate::export_plugin) expands to. Not part of the
+//! API; only public so the generated code can reach it.
+
+use crate::{ffi, Metadata, Plugin, PluginError, PluginInfo};
+use std::ffi::{c_char, CStr, CString};
+use std::panic::{self, AssertUnwindSafe};
+use std::path::PathBuf;
+use std::ptr;
+use std::sync::{OnceLock, PoisonError, RwLock};
+
+/// `rururu_plugin_info` hands out pointers, so its strings are built once
+/// and live as long as the library
+struct InfoStrings {
+    name: CString,
+    version: CString,
+    description: CString,
+    _extensions: Vec<CString>,
+    extension_ptrs: Vec<*const c_char>,
+}
+
+// The pointers are into `_extensions` and never written through
+unsafe impl Send for InfoStrings {}
+unsafe impl Sync for InfoStrings {}
+
+/// The plugin's state, from `rururu_plugin_init` to `rururu_plugin_deinit`
+pub struct Instance<P> {
+    info: OnceLock<InfoStrings>,
+    plugin: RwLock<Option<P>>,
+}
+
+impl<P: Plugin> Default for Instance<P> {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl<P: Plugin> Instance<P> {
+    pub const fn new() -> Self {
+        Self {
+            info: OnceLock::new(),
+            plugin: RwLock::new(None),
+        }
+    }
+
+    pub fn info(&self) -> ffi::PluginInfo {
+        let info = self.info.get_or_init(|| {
+            // There's no way to report a failure here; a plugin without
+            // extensions is never handed a file
+            let info = panic::catch_unwind(P::info).unwrap_or_else(|_| PluginInfo {
+                name: String::new(),
+                version: String::new(),
+                description: String::new(),
+                extensions: Vec::new(),
+            });
+            let extensions: Vec<CString> = info.extensions.iter().map(|e| to_cstring(e)).collect();
+            InfoStrings {
+                name: to_cstring(&info.name),
+                version: to_cstring(&info.version),
+                description: to_cstring(&info.description),
+                extension_ptrs: extensions.iter().map(|e| e.as_ptr()).collect(),
+                _extensions: extensions,
+            }
+        });
+
+        ffi::PluginInfo {
+            name: info.name.as_ptr(),
+            version: info.version.as_ptr(),
+            description: info.description.as_ptr(),
+            supported_extensions: info.extension_ptrs.as_ptr(),
+            extension_count: info.extension_ptrs.len(),
+        }
+    }
+
+    pub fn init(&self) -> i32 {
+        guard(ffi::PANICKED, || match P::init() {
+            Ok(plugin) => {
+                *self.plugin.write().unwrap_or_else(PoisonError::into_inner) = Some(plugin);
+                ffi::OK
+            }
+            Err(e) => error_code(&e),
+        })
+    }
+
+    pub fn deinit(&self) {
+        guard((), || {
+            drop(
+                self.plugin
+                    .write()
+                    .unwrap_or_else(PoisonError::into_inner)
+                    .take(),
+            )
+        })
+    }
+
+    /// # Safety
+    /// `path` must be null or a NUL-terminated string.
+    pub unsafe fn metadata(&self, path: *const c_char) -> *mut ffi::FileMetadata {
+        let Some(path) = (unsafe { path_arg(path) }) else {
+            return ptr::null_mut();
+        };
+        guard(ptr::null_mut(), || {
+            let plugin = self.plugin.read().unwrap_or_else(PoisonError::into_inner);
+            match plugin.as_ref().map(|p| p.metadata(&path)) {
+                Some(Ok(metadata)) => into_raw(metadata),
+                _ => ptr::null_mut(),
+            }
+        })
+    }
+
+    /// # Safety
+    /// `source` and `dest` must be null or NUL-terminated strings.
+    pub unsafe fn thumbnail(
+        &self,
+        source: *const c_char,
+        dest: *const c_char,
+        width: u32,
+        height: u32,
+    ) -> i32 {
+        let (Some(source), Some(dest)) = (unsafe { path_arg(source) }, unsafe { path_arg(dest) })
+        else {
+            return ffi::INVALID_ARGUMENT;
+        };
+        guard(ffi::PANICKED, || {
+            let plugin = self.plugin.read().unwrap_or_else(PoisonError::into_inner);
+            match plugin
+                .as_ref()
+                .map(|p| p.thumbnail(&source, &dest, width, height))
+            {
+                Some(Ok(())) => ffi::OK,
+                Some(Err(e)) => error_code(&e),
+                // Not initialized
+                None => ffi::FAILED,
+            }
+        })
+    }
+}
+
+/// # Safety
+/// `metadata` must be null or returned by [`Instance::metadata`], and is
+/// only freed once.
+pub unsafe fn free_metadata(metadata: *mut ffi::FileMetadata) {
+    if metadata.is_null() {
+        return;
+    }
+    let metadata = unsafe { Box::from_raw(metadata) };
+    for string in [metadata.mime_type, metadata.extra_json] {
+        if !string.is_null() {
+            drop(unsafe { CString::from_raw(string as *mut c_char) });
+        }
+    }
+}
+
+fn into_raw(metadata: Metadata) -> *mut ffi::FileMetadata {
+    let string =
+        |s: Option<String>| s.map_or(ptr::null(), |s| to_cstring(&s).into_raw().cast_const());
+    Box::into_raw(Box::new(ffi::FileMetadata {
+        mime_type: string(metadata.mime_type),
+        width: metadata.width,
+        height: metadata.height,
+        duration_ms: metadata.duration_ms,
+        extra_json: string(metadata.extra.map(|extra| extra.to_string())),
+    }))
+}
+
+/// Paths are bytes on Unix; elsewhere they have to be UTF-8
+unsafe fn path_arg(ptr: *const c_char) -> Option<PathBuf> {
+    if ptr.is_null() {
+        return None;
+    }
+    let bytes = unsafe { CStr::from_ptr(ptr) }.to_bytes();
+
+    #[cfg(unix)]
+    {
+        use std::os::unix::ffi::OsStrExt;
+        Some(PathBuf::from(std::ffi::OsStr::from_bytes(bytes)))
+    }
+    #[cfg(not(unix))]
+    {
+        std::str::from_utf8(bytes).ok().map(PathBuf::from)
+    }
+}
+
+/// C strings end at the first NUL, so drop any inside `s`
+fn to_cstring(s: &str) -> CString {
+    CString::new(s.replace('\0', "")).unwrap_or_default()
+}
+
+fn error_code(error: &PluginError) -> i32 {
+    match error {
+        PluginError::Unsupported => ffi::UNSUPPORTED,
+        _ => ffi::FAILED,
+    }
+}
+
+/// Unwinding out of an `extern "C"` function aborts the host
+fn guard<T>(fallback: T, f: impl FnOnce() -> T) -> T {
+    panic::catch_unwind(AssertUnwindSafe(f)).unwrap_or(fallback)
+}
@@ -0,0 +1,48 @@
+//! The C ABI between the file handler and plugin libraries.
+//!
+//! A plugin is a shared library exporting these symbols; only
+//! `rururu_plugin_info` is required:
+//!
+//! - `rururu_plugin_abi_version() -> u32`, [`ABI_VERSION`] it was built for
+//! - `rururu_plugin_info() -> PluginInfo`, the strings staying valid until unload
+//! - `rururu_plugin_init() -> i32` and `rururu_plugin_deinit()`
+//! - `rururu_get_metadata(path) -> *mut FileMetadata`, null on failure
+//! - `rururu_free_metadata(*mut FileMetadata)` for whatever the above returned
+//! - `rururu_generate_thumbnail(source, dest, width, height) -> i32`
+//!
+//! Plugins written with [`export_plugin!`](crate::export_plugin) get all of
+//! them; this module is for the loader and for plugins in other languages.
+
+use std::ffi::c_char;
+
+/// Bumped whenever a struct or signature above changes. Libraries without
+/// `rururu_plugin_abi_version` predate it and speak version 1.
+pub const ABI_VERSION: u32 = 1;
+
+/// `rururu_plugin_init` and `rururu_generate_thumbnail` results
+pub const OK: i32 = 0;
+pub const UNSUPPORTED: i32 = -1;
+pub const FAILED: i32 = 1;
+/// Null or otherwise unusable arguments
+pub const INVALID_ARGUMENT: i32 = 2;
+/// The plugin panicked; caught before it could unwind into the host
+pub const PANICKED: i32 = 3;
+
+#[repr(C)]
+pub struct PluginInfo {
+    pub name: *const c_char,
+    pub version: *const c_char,
+    pub description: *const c_char,
+    pub supported_extensions: *const *const c_char,
+    pub extension_count: usize,
+}
+
+#[repr(C)]
+pub struct FileMetadata {
+    pub mime_type: *const c_char,
+    pub width: u32,
+    pub height: u32,
+    pub duration_ms: u64,
+    /// JSON object with anything format-specific, or null
+    pub extra_json: *const c_char,
+}
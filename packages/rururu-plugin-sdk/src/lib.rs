@@ -0,0 +1,248 @@
+//! Safe Rust API for RururuOS file handler plugins.
+//!
+//! A plugin implements [`Plugin`] and hands the type to [`export_plugin!`],
+//! which generates the `extern "C"` functions described in [`ffi`]. Strings
+//! are copied across the boundary for you, and a panic fails the call
+//! instead of unwinding into the file handler.
+//!
+//! ```
+//! use rururu_plugin_sdk::{export_plugin, Metadata, Plugin, PluginInfo, Result};
+//! use std::path::Path;
+//!
+//! struct Scene;
+//!
+//! impl Plugin for Scene {
+//!     fn info() -> PluginInfo {
+//!         PluginInfo::new("Scene", env!("CARGO_PKG_VERSION"), "Scene files", &["scene"])
+//!     }
+//!
+//!     fn init() -> Result<Self> {
+//!         Ok(Scene)
+//!     }
+//!
+//!     fn metadata(&self, _path: &Path) -> Result<Metadata> {
+//!         Ok(Metadata {
+//!             mime_type: Some("application/x-scene".into()),
+//!             ..Default::default()
+//!         })
+//!     }
+//! }
+//!
+//! export_plugin!(Scene);
+//! # fn main() {}
+//! ```
+
+#[doc(hidden)]
+pub mod export;
+pub mod ffi;
+
+pub use ffi::ABI_VERSION;
+
+use std::path::Path;
+use thiserror::Error;
+
+#[derive(Error, Debug)]
+pub enum PluginError {
+    #[error("Not supported by this plugin")]
+    Unsupported,
+    #[error("Invalid file: {0}")]
+    InvalidFile(String),
+    #[error("IO error: {0}")]
+    IoError(#[from] std::io::Error),
+}
+
+pub type Result<T> = std::result::Result<T, PluginError>;
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct PluginInfo {
+    pub name: String,
+    pub version: String,
+    pub description: String,
+    /// Without the dot; matched case-insensitively
+    pub extensions: Vec<String>,
+}
+
+impl PluginInfo {
+    pub fn new(name: &str, version: &str, description: &str, extensions: &[&str]) -> Self {
+        Self {
+            name: name.to_string(),
+            version: version.to_string(),
+            description: description.to_string(),
+            extensions: extensions.iter().map(|e| e.to_string()).collect(),
+        }
+    }
+}
+
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct Metadata {
+    pub mime_type: Option<String>,
+    pub width: u32,
+    pub height: u32,
+    pub duration_ms: u64,
+    /// Anything format-specific; shows up as `extra` in the file handler
+    pub extra: Option<serde_json::Value>,
+}
+
+pub trait Plugin: Sized + Send + Sync + 'static {
+    /// Read once, before `init`
+    fn info() -> PluginInfo;
+
+    /// Set up when the library is loaded; dropped again on unload
+    fn init() -> Result<Self>;
+
+    /// Only called for existing files with one of `info().extensions`
+    fn metadata(&self, path: &Path) -> Result<Metadata>;
+
+    /// Write a thumbnail of `source` no larger than `width`x`height` to `dest`
+    fn thumbnail(&self, source: &Path, dest: &Path, width: u32, height: u32) -> Result<()> {
+        let _ = (source, dest, width, height);
+        Err(PluginError::Unsupported)
+    }
+}
+
+/// Export `$plugin`, a [`Plugin`], as the library's plugin. Use it once, at
+/// the crate root of a `cdylib`.
+#[macro_export]
+macro_rules! export_plugin {
+    ($plugin:ty) => {
+        static __RURURU_PLUGIN: $crate::export::Instance<$plugin> = $crate::export::Instance::new();
+
+        #[no_mangle]
+        pub extern "C" fn rururu_plugin_abi_version() -> u32 {
+            $crate::ABI_VERSION
+        }
+
+        #[no_mangle]
+        pub extern "C" fn rururu_plugin_info() -> $crate::ffi::PluginInfo {
+            __RURURU_PLUGIN.info()
+        }
+
+        #[no_mangle]
+        pub extern "C" fn rururu_plugin_init() -> i32 {
+            __RURURU_PLUGIN.init()
+        }
+
+        #[no_mangle]
+        pub extern "C" fn rururu_plugin_deinit() {
+            __RURURU_PLUGIN.deinit()
+        }
+
+        /// # Safety
+        /// `path` must be null or a NUL-terminated string; the result is
+        /// freed with `rururu_free_metadata`.
+        #[no_mangle]
+        pub unsafe extern "C" fn rururu_get_metadata(
+            path: *const ::std::ffi::c_char,
+        ) -> *mut $crate::ffi::FileMetadata {
+            unsafe { __RURURU_PLUGIN.metadata(path) }
+        }
+
+        /// # Safety
+        /// `metadata` must be null or from `rururu_get_metadata`, and is only
+        /// freed once.
+        #[no_mangle]
+        pub unsafe extern "C" fn rururu_free_metadata(metadata: *mut $crate::ffi::FileMetadata) {
+            unsafe { $crate::export::free_metadata(metadata) }
+        }
+
+        /// # Safety
+        /// `source` and `dest` must be null or NUL-terminated strings.
+        #[no_mangle]
+        pub unsafe extern "C" fn rururu_generate_thumbnail(
+            source: *const ::std::ffi::c_char,
+            dest: *const ::std::ffi::c_char,
+            width: u32,
+            height: u32,
+        ) -> i32 {
+            unsafe { __RURURU_PLUGIN.thumbnail(source, dest, width, height) }
+        }
+    };
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::ffi::{CStr, CString};
+
+    struct TestPlugin;
+
+    impl Plugin for TestPlugin {
+        fn info() -> PluginInfo {
+            PluginInfo::new("Test\0Plugin", "1.0", "For tests", &["tst", "tst2"])
+        }
+
+        fn init() -> Result<Self> {
+            Ok(TestPlugin)
+        }
+
+        fn metadata(&self, path: &Path) -> Result<Metadata> {
+            if path.ends_with("panic.tst") {
+                panic!("bad file");
+            }
+            Ok(Metadata {
+                mime_type: Some("application/x-test".into()),
+                width: 64,
+                height: 32,
+                duration_ms: 0,
+                extra: Some(serde_json::json!({ "path": path })),
+            })
+        }
+    }
+
+    export_plugin!(TestPlugin);
+
+    #[test]
+    fn test_info_strings() {
+        assert_eq!(rururu_plugin_abi_version(), ABI_VERSION);
+
+        let info = rururu_plugin_info();
+        let name = unsafe { CStr::from_ptr(info.name) };
+        assert_eq!(name.to_str().unwrap(), "TestPlugin");
+        assert_eq!(info.extension_count, 2);
+        let second = unsafe { CStr::from_ptr(*info.supported_extensions.add(1)) };
+        assert_eq!(second.to_str().unwrap(), "tst2");
+
+        // Same storage every call
+        assert_eq!(rururu_plugin_info().name, info.name);
+    }
+
+    #[test]
+    fn test_metadata_round_trip() {
+        assert_eq!(rururu_plugin_init(), ffi::OK);
+        let path = CString::new("/tmp/file.tst").unwrap();
+
+        unsafe {
+            assert!(rururu_get_metadata(std::ptr::null()).is_null());
+
+            let metadata = rururu_get_metadata(path.as_ptr());
+            assert!(!metadata.is_null());
+            assert_eq!((*metadata).width, 64);
+            let mime = CStr::from_ptr((*metadata).mime_type);
+            assert_eq!(mime.to_str().unwrap(), "application/x-test");
+            let extra: serde_json::Value =
+                serde_json::from_slice(CStr::from_ptr((*metadata).extra_json).to_bytes()).unwrap();
+            assert_eq!(extra["path"], "/tmp/file.tst");
+            rururu_free_metadata(metadata);
+            rururu_free_metadata(std::ptr::null_mut());
+        }
+    }
+
+    #[test]
+    fn test_panics_and_defaults() {
+        assert_eq!(rururu_plugin_init(), ffi::OK);
+        let panics = CString::new("/tmp/panic.tst").unwrap();
+        let dest = CString::new("/tmp/thumb.png").unwrap();
+
+        unsafe {
+            assert!(rururu_get_metadata(panics.as_ptr()).is_null());
+            assert_eq!(
+                rururu_generate_thumbnail(panics.as_ptr(), dest.as_ptr(), 64, 64),
+                ffi::UNSUPPORTED
+            );
+            assert_eq!(
+                rururu_generate_thumbnail(std::ptr::null(), dest.as_ptr(), 64, 64),
+                ffi::INVALID_ARGUMENT
+            );
+        }
+    }
+}
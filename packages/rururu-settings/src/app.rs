@@ -1,9 +1,12 @@
 use crate::pages::{
-    about::AboutPage, appearance::AppearancePage, audio::AudioPage, displays::DisplaysPage,
-    keyboard::KeyboardPage, network::NetworkPage, power::PowerPage, storage::StoragePage,
+    self, about::AboutPage, appearance::AppearancePage, audio::AudioPage, color::ColorPage,
+    displays::DisplaysPage, keyboard::KeyboardPage, network::NetworkPage, power::PowerPage,
+    storage::StoragePage, workflows::WorkflowsPage,
 };
 use iced::widget::{button, column, container, row, scrollable, text, Space};
 use iced::{Application, Command, Element, Length, Theme};
+use rururu_workflows::WorkflowType;
+use std::path::PathBuf;
 
 #[derive(Debug, Clone)]
 #[allow(dead_code)]
@@ -19,6 +22,23 @@ pub enum Message {
     RefreshRateChanged(u32),
     ScaleChanged(f32),
     NightLightToggled(bool),
+    // Color
+    ColorProfileAssigned(String, String),
+    ColorProfileUnassigned(String),
+    ColorAssignmentDone(String, Option<PathBuf>, Result<(), String>),
+    ColorHdrToggled(String, bool),
+    ColorHdrDone(String, bool, Result<(), String>),
+    ColorHdrStates(Vec<(String, bool)>),
+    LaunchColorCal(String),
+    RefreshColor,
+    // Workflows
+    WorkflowsLoaded(pages::workflows::WorkflowsState),
+    WorkflowPlanToggled(WorkflowType),
+    ActivateWorkflow(WorkflowType),
+    DeactivateWorkflow,
+    InstallWorkflowApps(WorkflowType),
+    WorkflowCommandDone(Result<(), String>),
+    RefreshWorkflows,
     // Audio
     OutputVolumeChanged(f32),
     InputVolumeChanged(f32),
@@ -37,6 +57,7 @@ pub enum Message {
     // Storage
     RefreshStorage,
     // About
+    AboutReportLoaded(pages::about::SystemReport),
     CopySystemInfo,
 }
 
@@ -45,6 +66,8 @@ pub enum Page {
     #[default]
     Appearance,
     Displays,
+    Color,
+    Workflows,
     Audio,
     Keyboard,
     Network,
@@ -58,6 +81,8 @@ impl Page {
         match self {
             Page::Appearance => "Appearance",
             Page::Displays => "Displays",
+            Page::Color => "Color",
+            Page::Workflows => "Workflows",
             Page::Audio => "Audio",
             Page::Keyboard => "Keyboard",
             Page::Network => "Network",
@@ -71,6 +96,8 @@ impl Page {
         match self {
             Page::Appearance => "🎨",
             Page::Displays => "🖥️",
+            Page::Color => "🌈",
+            Page::Workflows => "🎬",
             Page::Audio => "🔊",
             Page::Keyboard => "⌨️",
             Page::Network => "🌐",
@@ -84,6 +111,8 @@ impl Page {
         &[
             Page::Appearance,
             Page::Displays,
+            Page::Color,
+            Page::Workflows,
             Page::Audio,
             Page::Keyboard,
             Page::Network,
@@ -98,6 +127,8 @@ pub struct SettingsApp {
     current_page: Page,
     appearance: AppearancePage,
     displays: DisplaysPage,
+    color: ColorPage,
+    workflows: WorkflowsPage,
     audio: AudioPage,
     keyboard: KeyboardPage,
     network: NetworkPage,
@@ -113,11 +144,25 @@ impl Application for SettingsApp {
     type Flags = ();
 
     fn new(_flags: ()) -> (Self, Command<Message>) {
+        let color = ColorPage::new();
+        let load_hdr = Command::perform(
+            pages::color::load_hdr_states(color.monitor_names()),
+            Message::ColorHdrStates,
+        );
+        let load_report = Command::perform(
+            pages::about::load_system_report(),
+            Message::AboutReportLoaded,
+        );
+        let load_workflows =
+            Command::perform(pages::workflows::load_workflows(), Message::WorkflowsLoaded);
+
         (
             Self {
                 current_page: Page::default(),
                 appearance: AppearancePage::new(),
                 displays: DisplaysPage::new(),
+                color,
+                workflows: WorkflowsPage::new(),
                 audio: AudioPage::new(),
                 keyboard: KeyboardPage::new(),
                 network: NetworkPage::new(),
@@ -125,7 +170,7 @@ impl Application for SettingsApp {
                 storage: StoragePage::new(),
                 about: AboutPage::new(),
             },
-            Command::none(),
+            Command::batch([load_hdr, load_report, load_workflows]),
         )
     }
 
@@ -137,6 +182,9 @@ impl Application for SettingsApp {
         match message {
             Message::SelectPage(page) => {
                 self.current_page = page;
+                if page == Page::Power {
+                    self.power.refresh_battery();
+                }
             }
             Message::ThemeChanged(theme) => {
                 self.appearance.set_theme(&theme);
@@ -162,12 +210,120 @@ impl Application for SettingsApp {
             Message::ScaleChanged(scale) => {
                 self.displays.set_scale(scale);
             }
+            Message::ColorProfileAssigned(monitor, profile) => {
+                let Some(path) = self.color.profile_path(&profile) else {
+                    return Command::none();
+                };
+                return Command::perform(
+                    pages::color::assign_profile(monitor.clone(), Some(path.clone())),
+                    move |result| {
+                        Message::ColorAssignmentDone(monitor.clone(), Some(path.clone()), result)
+                    },
+                );
+            }
+            Message::ColorProfileUnassigned(monitor) => {
+                return Command::perform(
+                    pages::color::assign_profile(monitor.clone(), None),
+                    move |result| Message::ColorAssignmentDone(monitor.clone(), None, result),
+                );
+            }
+            Message::ColorAssignmentDone(monitor, profile, result) => match result {
+                Ok(()) => {
+                    self.color.set_assignment(&monitor, profile);
+                    self.color.set_status(None);
+                }
+                Err(e) => self.color.set_status(Some(e)),
+            },
+            Message::ColorHdrToggled(monitor, enabled) => {
+                return Command::perform(
+                    pages::color::set_hdr(monitor.clone(), enabled),
+                    move |result| Message::ColorHdrDone(monitor.clone(), enabled, result),
+                );
+            }
+            Message::ColorHdrDone(monitor, enabled, result) => match result {
+                Ok(()) => {
+                    self.color.set_hdr(&monitor, enabled);
+                    self.color.set_status(None);
+                }
+                Err(e) => self.color.set_status(Some(e)),
+            },
+            Message::ColorHdrStates(states) => {
+                for (monitor, active) in states {
+                    self.color.set_hdr(&monitor, active);
+                }
+            }
+            Message::LaunchColorCal(monitor) => {
+                if let Err(e) = pages::color::launch_colorcal(&monitor) {
+                    self.color
+                        .set_status(Some(format!("Could not start rururu-colorcal: {}", e)));
+                }
+            }
+            Message::RefreshColor => {
+                self.color.refresh();
+                self.color.set_status(None);
+                return Command::perform(
+                    pages::color::load_hdr_states(self.color.monitor_names()),
+                    Message::ColorHdrStates,
+                );
+            }
+            Message::WorkflowsLoaded(state) => {
+                self.workflows.set_state(state);
+            }
+            Message::WorkflowPlanToggled(workflow) => {
+                self.workflows.toggle_plan(workflow);
+            }
+            Message::ActivateWorkflow(workflow) => {
+                self.workflows
+                    .set_status(Some(format!("Activating {}…", workflow.name())));
+                return Command::perform(
+                    pages::workflows::activate(workflow),
+                    Message::WorkflowCommandDone,
+                );
+            }
+            Message::DeactivateWorkflow => {
+                return Command::perform(
+                    pages::workflows::deactivate(),
+                    Message::WorkflowCommandDone,
+                );
+            }
+            Message::InstallWorkflowApps(workflow) => {
+                self.workflows
+                    .set_status(Some(format!("Installing apps for {}…", workflow.name())));
+                return Command::perform(
+                    pages::workflows::install_apps(workflow),
+                    Message::WorkflowCommandDone,
+                );
+            }
+            Message::WorkflowCommandDone(result) => {
+                self.workflows.set_status(result.err());
+                return Command::perform(
+                    pages::workflows::load_workflows(),
+                    Message::WorkflowsLoaded,
+                );
+            }
+            Message::RefreshWorkflows => {
+                self.workflows.set_status(None);
+                return Command::perform(
+                    pages::workflows::load_workflows(),
+                    Message::WorkflowsLoaded,
+                );
+            }
             Message::PowerProfileChanged(profile) => {
                 self.power.set_profile(&profile);
             }
             Message::RefreshStorage => {
                 self.storage.refresh();
             }
+            Message::AboutReportLoaded(report) => {
+                self.about.set_report(report);
+            }
+            Message::CopySystemInfo => {
+                if let Some(report) = self.about.report_text() {
+                    self.about
+                        .set_status(Some("Copied to clipboard".to_string()));
+                    return iced::clipboard::write(report);
+                }
+            }
             _ => {}
         }
         Command::none()
@@ -231,6 +387,8 @@ impl SettingsApp {
         let page_content: Element<Message> = match self.current_page {
             Page::Appearance => self.appearance.view(),
             Page::Displays => self.displays.view(),
+            Page::Color => self.color.view(),
+            Page::Workflows => self.workflows.view(),
             Page::Audio => self.audio.view(),
             Page::Keyboard => self.keyboard.view(),
             Page::Network => self.network.view(),
@@ -1,39 +1,175 @@
 use crate::app::Message;
 use iced::widget::{button, column, row, text, Space};
 use iced::{Element, Length};
+use rururu_utils::SystemInfo;
+use std::collections::HashMap;
+use std::fmt::Write;
+use std::path::Path;
+use std::process::Command;
 
-pub struct AboutPage {
+/// Binaries that make up a RururuOS install
+const COMPONENTS: &[&str] = &[
+    "rururu-settings",
+    "rururu-files",
+    "rururu-monitor",
+    "rururu-colorcal",
+    "rururu-color-daemon",
+    "rururu-workflow",
+    "rururu-file-handler",
+];
+
+/// FFmpeg decoder names and how to show them
+const CODECS: &[(&str, &str)] = &[
+    ("h264", "H.264"),
+    ("hevc", "HEVC"),
+    ("av1", "AV1"),
+    ("vp9", "VP9"),
+    ("prores", "ProRes"),
+    ("dnxhd", "DNxHD"),
+    ("exr", "OpenEXR"),
+];
+
+/// Everything the About page shows, gathered off the UI thread since it
+/// runs `lspci` and `ffmpeg`
+#[derive(Debug, Clone, Default)]
+pub struct SystemReport {
     pub os_name: String,
     pub os_version: String,
     pub kernel: String,
     pub desktop: String,
+    pub session: String,
     pub cpu: String,
     pub memory: String,
-    pub gpu: String,
+    pub gpus: Vec<String>,
+    /// Component name and installed version, or "Not installed"
+    pub components: Vec<(String, String)>,
+    pub capabilities: Vec<(String, String)>,
+}
+
+impl SystemReport {
+    pub fn collect() -> Self {
+        let os_release = read_os_release();
+        let os_name = os_release
+            .get("NAME")
+            .cloned()
+            .unwrap_or_else(|| "RururuOS".to_string());
+        let os_version = ["VERSION", "VERSION_ID", "BUILD_ID"]
+            .iter()
+            .find_map(|key| os_release.get(*key).cloned())
+            .unwrap_or_else(|| "Rolling".to_string());
+
+        let info = SystemInfo::new();
+        let cpu = info.cpu_info();
+        let memory_gb = info.memory_info().total_bytes as f64 / (1024.0 * 1024.0 * 1024.0);
+
+        Self {
+            os_name,
+            os_version,
+            kernel: format!("Linux {}", info.kernel_version()),
+            desktop: env_or("XDG_CURRENT_DESKTOP", "Unknown"),
+            session: session_type(),
+            cpu: format!("{} ({} threads)", cpu.name.trim(), cpu.core_count),
+            memory: format!("{:.1} GB", memory_gb),
+            gpus: detect_gpus(),
+            components: COMPONENTS
+                .iter()
+                .map(|name| (name.to_string(), component_version(name)))
+                .collect(),
+            capabilities: detect_capabilities(),
+        }
+    }
+
+    /// Plain-text block for pasting into bug reports
+    pub fn to_text(&self) -> String {
+        let mut out = String::from("RururuOS System Information\n");
+        let _ = writeln!(out, "OS: {} {}", self.os_name, self.os_version);
+        let _ = writeln!(out, "Kernel: {}", self.kernel);
+        let _ = writeln!(out, "Desktop: {} ({})", self.desktop, self.session);
+        let _ = writeln!(out, "CPU: {}", self.cpu);
+        let _ = writeln!(out, "Memory: {}", self.memory);
+        for gpu in &self.gpus {
+            let _ = writeln!(out, "GPU: {}", gpu);
+        }
+
+        out.push_str("\nComponents:\n");
+        for (name, version) in &self.components {
+            let _ = writeln!(out, "  {}: {}", name, version);
+        }
+
+        out.push_str("\nCapabilities:\n");
+        for (name, value) in &self.capabilities {
+            let _ = writeln!(out, "  {}: {}", name, value);
+        }
+        out
+    }
+}
+
+pub async fn load_system_report() -> SystemReport {
+    tokio::task::spawn_blocking(SystemReport::collect)
+        .await
+        .unwrap_or_default()
+}
+
+pub struct AboutPage {
+    pub report: Option<SystemReport>,
+    pub status: Option<String>,
 }
 
 impl AboutPage {
     pub fn new() -> Self {
         Self {
-            os_name: "RururuOS".to_string(),
-            os_version: "0.1.0 (Alpha)".to_string(),
-            kernel: "Linux 6.7.0-rururu".to_string(),
-            desktop: "Sway 1.9".to_string(),
-            cpu: "AMD Ryzen 9 7950X".to_string(),
-            memory: "64 GB DDR5-6000".to_string(),
-            gpu: "NVIDIA RTX 4090".to_string(),
+            report: None,
+            status: None,
         }
     }
 
+    pub fn set_report(&mut self, report: SystemReport) {
+        self.report = Some(report);
+    }
+
+    pub fn set_status(&mut self, status: Option<String>) {
+        self.status = status;
+    }
+
+    pub fn report_text(&self) -> Option<String> {
+        self.report.as_ref().map(SystemReport::to_text)
+    }
+
     pub fn view(&self) -> Element<'_, Message> {
+        let Some(report) = &self.report else {
+            return column![text("Collecting system information…").size(14)]
+                .padding(16)
+                .into();
+        };
+
+        let gpus: Vec<Element<Message>> = if report.gpus.is_empty() {
+            vec![Self::info_row("Graphics", "Not detected")]
+        } else {
+            report
+                .gpus
+                .iter()
+                .map(|gpu| Self::info_row("Graphics", gpu))
+                .collect()
+        };
+        let components: Vec<Element<Message>> = report
+            .components
+            .iter()
+            .map(|(name, version)| Self::info_row(name, version))
+            .collect();
+        let capabilities: Vec<Element<Message>> = report
+            .capabilities
+            .iter()
+            .map(|(name, value)| Self::info_row(name, value))
+            .collect();
+
         column![
             // Logo and name
             row![
                 text("🦊").size(64),
                 Space::with_width(Length::Fixed(16.0)),
                 column![
-                    text(&self.os_name).size(32),
-                    text(&self.os_version).size(14),
+                    text(&report.os_name).size(32),
+                    text(&report.os_version).size(14),
                     text("Creative Workstation OS")
                         .size(12)
                         .style(iced::theme::Text::Color(iced::Color::from_rgb(
@@ -47,17 +183,27 @@ impl AboutPage {
             // System info
             text("System Information").size(16),
             Space::with_height(Length::Fixed(8.0)),
-            Self::info_row("Operating System", &self.os_name),
-            Self::info_row("Version", &self.os_version),
-            Self::info_row("Kernel", &self.kernel),
-            Self::info_row("Desktop", &self.desktop),
+            Self::info_row("Operating System", &report.os_name),
+            Self::info_row("Version", &report.os_version),
+            Self::info_row("Kernel", &report.kernel),
+            Self::info_row("Desktop", &report.desktop),
+            Self::info_row("Session", &report.session),
             Space::with_height(Length::Fixed(24.0)),
             // Hardware
             text("Hardware").size(16),
             Space::with_height(Length::Fixed(8.0)),
-            Self::info_row("Processor", &self.cpu),
-            Self::info_row("Memory", &self.memory),
-            Self::info_row("Graphics", &self.gpu),
+            Self::info_row("Processor", &report.cpu),
+            Self::info_row("Memory", &report.memory),
+            column(gpus),
+            Space::with_height(Length::Fixed(24.0)),
+            // Software
+            text("Components").size(16),
+            Space::with_height(Length::Fixed(8.0)),
+            column(components),
+            Space::with_height(Length::Fixed(24.0)),
+            text("Capabilities").size(16),
+            Space::with_height(Length::Fixed(8.0)),
+            column(capabilities),
             Space::with_height(Length::Fixed(24.0)),
             // Actions
             row![
@@ -66,7 +212,10 @@ impl AboutPage {
                     .on_press(Message::CopySystemInfo),
                 Space::with_width(Length::Fixed(8.0)),
                 button(text("Check for Updates")).style(iced::theme::Button::Primary),
-            ],
+                Space::with_width(Length::Fixed(16.0)),
+                text(self.status.as_deref().unwrap_or("")).size(12),
+            ]
+            .align_items(iced::Alignment::Center),
             Space::with_height(Length::Fixed(24.0)),
             // Credits
             text("Credits").size(16),
@@ -97,3 +246,179 @@ impl AboutPage {
             .into()
     }
 }
+
+/// `KEY=value` pairs from os-release, quotes stripped
+fn read_os_release() -> HashMap<String, String> {
+    let content = std::fs::read_to_string("/etc/os-release")
+        .or_else(|_| std::fs::read_to_string("/usr/lib/os-release"))
+        .unwrap_or_default();
+
+    content
+        .lines()
+        .filter_map(|line| line.split_once('='))
+        .map(|(key, value)| {
+            (
+                key.trim().to_string(),
+                value
+                    .trim()
+                    .trim_matches(|c| c == '"' || c == '\'')
+                    .to_string(),
+            )
+        })
+        .collect()
+}
+
+fn env_or(key: &str, default: &str) -> String {
+    std::env::var(key)
+        .ok()
+        .filter(|v| !v.is_empty())
+        .unwrap_or_else(|| default.to_string())
+}
+
+fn session_type() -> String {
+    match std::env::var("XDG_SESSION_TYPE")
+        .ok()
+        .filter(|v| !v.is_empty())
+    {
+        Some(session) => session,
+        None if std::env::var_os("WAYLAND_DISPLAY").is_some() => "wayland".to_string(),
+        None if std::env::var_os("DISPLAY").is_some() => "x11".to_string(),
+        None => "Unknown".to_string(),
+    }
+}
+
+fn command_output(program: &str, args: &[&str]) -> Option<String> {
+    let output = Command::new(program).args(args).output().ok()?;
+    output
+        .status
+        .success()
+        .then(|| String::from_utf8_lossy(&output.stdout).into_owned())
+}
+
+/// Display controllers from `lspci`, without the bus address and class
+fn detect_gpus() -> Vec<String> {
+    let Some(out) = command_output("lspci", &[]) else {
+        return Vec::new();
+    };
+
+    out.lines()
+        .filter(|line| {
+            line.contains("VGA compatible controller")
+                || line.contains("3D controller")
+                || line.contains("Display controller")
+        })
+        .filter_map(|line| {
+            line.split_once(": ")
+                .map(|(_, name)| name.trim().to_string())
+        })
+        .collect()
+}
+
+/// Package version from pacman, falling back to whether the binary is on PATH
+fn component_version(name: &str) -> String {
+    if name == env!("CARGO_PKG_NAME") {
+        return env!("CARGO_PKG_VERSION").to_string();
+    }
+
+    if let Some(version) = command_output("pacman", &["-Q", name])
+        .and_then(|out| out.split_whitespace().nth(1).map(str::to_string))
+    {
+        return version;
+    }
+
+    let on_path = std::env::var_os("PATH")
+        .map(|paths| std::env::split_paths(&paths).any(|dir| dir.join(name).is_file()))
+        .unwrap_or(false);
+    if on_path {
+        "Installed".to_string()
+    } else {
+        "Not installed".to_string()
+    }
+}
+
+fn detect_capabilities() -> Vec<(String, String)> {
+    let mut caps = Vec::new();
+
+    // "ffmpeg version 6.1.1 Copyright ..."
+    let ffmpeg = command_output("ffmpeg", &["-version"])
+        .and_then(|out| out.split_whitespace().nth(2).map(str::to_string));
+    match ffmpeg {
+        Some(version) => {
+            caps.push(("FFmpeg".to_string(), version));
+            caps.push(("Decoders".to_string(), ffmpeg_decoders()));
+        }
+        None => caps.push(("FFmpeg".to_string(), "Not installed".to_string())),
+    }
+
+    let mut hw_decode = Vec::new();
+    if Path::new("/dev/dri/renderD128").exists() {
+        hw_decode.push("VA-API");
+    }
+    if Path::new("/dev/nvidia0").exists() {
+        hw_decode.push("NVDEC");
+    }
+    caps.push(("Hardware Decoding".to_string(), join_or_none(&hw_decode)));
+
+    caps.push(("Vulkan".to_string(), vulkan_drivers()));
+    caps.push(("OpenColorIO".to_string(), env_or("OCIO", "No config set")));
+
+    let realtime = Path::new("/etc/security/limits.d/99-realtime.conf").exists();
+    caps.push((
+        "Realtime Audio".to_string(),
+        if realtime {
+            "Configured"
+        } else {
+            "Not configured"
+        }
+        .to_string(),
+    ));
+
+    caps
+}
+
+/// Which of `CODECS` this FFmpeg build can decode
+fn ffmpeg_decoders() -> String {
+    let Some(out) = command_output("ffmpeg", &["-hide_banner", "-decoders"]) else {
+        return "Unknown".to_string();
+    };
+
+    // Decoder lines are " V....D h264                 H.264 / AVC ..."
+    let available: Vec<&str> = out
+        .lines()
+        .filter_map(|line| line.split_whitespace().nth(1))
+        .collect();
+    let found: Vec<&str> = CODECS
+        .iter()
+        .filter(|(name, _)| available.contains(name))
+        .map(|(_, label)| *label)
+        .collect();
+    join_or_none(&found)
+}
+
+/// Installed Vulkan ICDs, e.g. `radeon_icd.x86_64.json` as "radeon"
+fn vulkan_drivers() -> String {
+    let Ok(entries) = std::fs::read_dir("/usr/share/vulkan/icd.d") else {
+        return "Not available".to_string();
+    };
+
+    let mut drivers: Vec<String> = entries
+        .filter_map(|e| e.ok())
+        .filter_map(|e| {
+            let name = e.file_name().to_string_lossy().into_owned();
+            name.split(['_', '.']).next().map(str::to_string)
+        })
+        .collect();
+    drivers.sort();
+    drivers.dedup();
+
+    let drivers: Vec<&str> = drivers.iter().map(String::as_str).collect();
+    join_or_none(&drivers)
+}
+
+fn join_or_none(items: &[&str]) -> String {
+    if items.is_empty() {
+        "None detected".to_string()
+    } else {
+        items.join(", ")
+    }
+}
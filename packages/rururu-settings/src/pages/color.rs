@@ -0,0 +1,398 @@
+use crate::app::Message;
+use iced::widget::{button, column, container, pick_list, row, text, toggler, Space};
+use iced::{Element, Length};
+use rururu_color::dbus::ColorManagementProxy;
+use rururu_color::monitor::{detect_monitors, ColorGamut, HdrCapability, MonitorProfile};
+use rururu_color::validation::Severity;
+use rururu_color::{IccManager, IccProfileInfo, IccReport};
+use std::collections::HashMap;
+use std::path::PathBuf;
+
+pub struct ColorPage {
+    pub monitors: Vec<MonitorProfile>,
+    /// Display-class ICC profiles by name
+    pub profiles: Vec<(String, PathBuf)>,
+    pub hdr_active: HashMap<String, bool>,
+    /// Mismatches between each monitor and its assigned profile
+    pub warnings: HashMap<String, Vec<String>>,
+    /// Share of each standard gamut's area each monitor covers, for those
+    /// with known primaries
+    pub coverage: HashMap<String, Vec<(ColorGamut, f32)>>,
+    /// Validation of each display profile, by name
+    pub quality: HashMap<String, IccReport>,
+    pub status: Option<String>,
+}
+
+impl ColorPage {
+    pub fn new() -> Self {
+        let mut page = Self {
+            monitors: Vec::new(),
+            profiles: Vec::new(),
+            hdr_active: HashMap::new(),
+            warnings: HashMap::new(),
+            coverage: HashMap::new(),
+            quality: HashMap::new(),
+            status: None,
+        };
+        page.refresh();
+        page
+    }
+
+    pub fn refresh(&mut self) {
+        self.monitors = detect_monitors().unwrap_or_default();
+
+        let manager = IccManager::new();
+        self.profiles = manager
+            .list_display_profiles()
+            .iter()
+            .map(|p| (p.name.clone(), p.path.clone()))
+            .collect();
+        self.profiles.sort();
+        self.quality = self
+            .profiles
+            .iter()
+            .map(|(name, path)| (name.clone(), IccManager::report(path)))
+            .collect();
+
+        self.warnings.clear();
+        self.coverage.clear();
+        for monitor in self.monitor_names() {
+            self.check_profile(&monitor);
+        }
+    }
+
+    pub fn monitor_names(&self) -> Vec<String> {
+        self.monitors.iter().map(|m| m.name.clone()).collect()
+    }
+
+    pub fn profile_path(&self, name: &str) -> Option<PathBuf> {
+        self.profiles
+            .iter()
+            .find(|(n, _)| n == name)
+            .map(|(_, path)| path.clone())
+    }
+
+    pub fn set_assignment(&mut self, monitor: &str, profile: Option<PathBuf>) {
+        if let Some(m) = self.monitors.iter_mut().find(|m| m.name == monitor) {
+            m.icc_profile = profile;
+        }
+        self.check_profile(monitor);
+    }
+
+    fn check_profile(&mut self, monitor: &str) {
+        let Some(m) = self.monitors.iter().find(|m| m.name == monitor) else {
+            return;
+        };
+        let mut warnings = match m.icc_profile.as_deref().map(IccProfileInfo::from_file) {
+            Some(Ok(info)) => m
+                .check_profile_match(&info)
+                .iter()
+                .map(|w| w.to_string())
+                .collect(),
+            Some(Err(e)) => vec![format!("Could not read the profile: {}", e)],
+            None => Vec::new(),
+        };
+        if let Some(path) = &m.icc_profile {
+            let report = IccManager::report(path);
+            if !report.is_trustworthy() {
+                let worst = report.issues.iter().max_by_key(|i| i.severity());
+                warnings.push(match worst {
+                    Some(issue) => format!("Profile scores {}/100: {}", report.score, issue),
+                    None => format!("Profile scores {}/100", report.score),
+                });
+            }
+        }
+        self.warnings.insert(monitor.to_string(), warnings);
+
+        // A measured profile replaces the EDID's primaries
+        if m.primaries().is_some() {
+            let coverage = [
+                ColorGamut::Srgb,
+                ColorGamut::AdobeRgb,
+                ColorGamut::DciP3,
+                ColorGamut::Bt2020,
+            ]
+            .into_iter()
+            .map(|gamut| (gamut, m.gamut_coverage(gamut)))
+            .collect();
+            self.coverage.insert(monitor.to_string(), coverage);
+        } else {
+            self.coverage.remove(monitor);
+        }
+    }
+
+    pub fn set_hdr(&mut self, monitor: &str, active: bool) {
+        self.hdr_active.insert(monitor.to_string(), active);
+    }
+
+    pub fn set_status(&mut self, status: Option<String>) {
+        self.status = status;
+    }
+
+    pub fn view(&self) -> Element<'_, Message> {
+        let profile_names: Vec<String> = self.profiles.iter().map(|(n, _)| n.clone()).collect();
+
+        let mut content = column![
+            text("Monitors").size(16),
+            Space::with_height(Length::Fixed(8.0)),
+        ]
+        .spacing(4);
+
+        if self.monitors.is_empty() {
+            content = content.push(text("No monitors detected").size(12));
+        }
+
+        for monitor in &self.monitors {
+            let name = monitor.name.clone();
+
+            let assigned = monitor.icc_profile.as_ref().and_then(|path| {
+                self.profiles
+                    .iter()
+                    .find(|(_, p)| p == path)
+                    .map(|(n, _)| n.clone())
+                    .or_else(|| Some(path.display().to_string()))
+            });
+
+            let hdr: Element<Message> = if monitor.capabilities.hdr_support == HdrCapability::None {
+                text("Not supported").size(12).into()
+            } else {
+                let monitor_name = name.clone();
+                toggler(
+                    None,
+                    self.hdr_active.get(&name).copied().unwrap_or(false),
+                    move |enabled| Message::ColorHdrToggled(monitor_name.clone(), enabled),
+                )
+                .width(Length::Shrink)
+                .into()
+            };
+
+            let assign_name = name.clone();
+            let unassign = button(text("Unassign").size(12)).style(iced::theme::Button::Secondary);
+            let unassign = if monitor.icc_profile.is_some() {
+                unassign.on_press(Message::ColorProfileUnassigned(name.clone()))
+            } else {
+                unassign
+            };
+
+            let mut card = column![
+                row![
+                    column![
+                        text(&monitor.name).size(14),
+                        text(format!(
+                            "{} {} · {}×{}",
+                            monitor.edid.manufacturer,
+                            monitor.edid.model,
+                            monitor.edid.resolution.0,
+                            monitor.edid.resolution.1
+                        ))
+                        .size(11),
+                    ]
+                    .spacing(2),
+                    Space::with_width(Length::Fill),
+                    button(text("Calibrate…").size(12))
+                        .style(iced::theme::Button::Primary)
+                        .on_press(Message::LaunchColorCal(name.clone())),
+                ]
+                .align_items(iced::Alignment::Center),
+                row![
+                    text("ICC profile"),
+                    Space::with_width(Length::Fill),
+                    pick_list(profile_names.clone(), assigned, move |profile| {
+                        Message::ColorProfileAssigned(assign_name.clone(), profile)
+                    })
+                    .placeholder("None"),
+                    Space::with_width(Length::Fixed(8.0)),
+                    unassign,
+                ]
+                .align_items(iced::Alignment::Center),
+                row![text("HDR"), Space::with_width(Length::Fill), hdr]
+                    .align_items(iced::Alignment::Center),
+            ]
+            .spacing(8);
+
+            if let Some(coverage) = self.coverage.get(&name) {
+                let summary = coverage
+                    .iter()
+                    .map(|(gamut, percent)| format!("{:.0}% {}", percent, gamut))
+                    .collect::<Vec<_>>()
+                    .join(" · ");
+                card = card.push(
+                    row![
+                        text("Gamut coverage"),
+                        Space::with_width(Length::Fill),
+                        text(summary).size(12),
+                    ]
+                    .align_items(iced::Alignment::Center),
+                );
+            }
+
+            for warning in self.warnings.get(&name).into_iter().flatten() {
+                card = card.push(text(format!("⚠ {}", warning)).size(12));
+            }
+
+            content = content.push(
+                container(card)
+                    .padding(12)
+                    .width(Length::Fill)
+                    .style(iced::theme::Container::Box),
+            );
+        }
+
+        content = content.push(self.view_profile_quality());
+
+        content = content.push(Space::with_height(Length::Fixed(16.0)));
+        content = content.push(
+            row![
+                button(text("Refresh"))
+                    .style(iced::theme::Button::Secondary)
+                    .on_press(Message::RefreshColor),
+                Space::with_width(Length::Fixed(16.0)),
+                text(self.status.as_deref().unwrap_or("")).size(12),
+            ]
+            .align_items(iced::Alignment::Center),
+        );
+
+        content.into()
+    }
+
+    /// Each display profile's score, with what lowered it for those that
+    /// aren't trustworthy
+    fn view_profile_quality(&self) -> Element<'_, Message> {
+        let mut list = column![
+            Space::with_height(Length::Fixed(16.0)),
+            text("Display Profiles").size(16),
+            Space::with_height(Length::Fixed(8.0)),
+        ]
+        .spacing(4);
+
+        if self.profiles.is_empty() {
+            list = list.push(text("No display profiles installed").size(12));
+        }
+
+        for (name, _) in &self.profiles {
+            let Some(report) = self.quality.get(name) else {
+                continue;
+            };
+            let verdict = if report.is_trustworthy() {
+                "✓ Trustworthy"
+            } else {
+                "⚠ Check before use"
+            };
+            let gamut = report
+                .gamut_volume
+                .map(|v| format!(" · {:.0}% of sRGB volume", v))
+                .unwrap_or_default();
+
+            let mut entry = column![row![
+                text(name).size(13),
+                Space::with_width(Length::Fill),
+                text(format!("{}/100 · {}{}", report.score, verdict, gamut)).size(12),
+            ]
+            .align_items(iced::Alignment::Center)]
+            .spacing(2);
+            if !report.is_trustworthy() {
+                for issue in report
+                    .issues
+                    .iter()
+                    .filter(|i| i.severity() > Severity::Info)
+                {
+                    entry = entry.push(text(format!("  {}", issue)).size(11));
+                }
+            }
+            list = list.push(
+                container(entry)
+                    .padding(8)
+                    .width(Length::Fill)
+                    .style(iced::theme::Container::Box),
+            );
+        }
+
+        list.into()
+    }
+}
+
+/// Assign (or with `None`, unassign) a profile through the color service,
+/// which applies it and remembers it by EDID. Without the service running
+/// the assignment is still saved so the daemon picks it up on start.
+pub async fn assign_profile(monitor: String, profile: Option<PathBuf>) -> Result<(), String> {
+    let path = profile
+        .as_ref()
+        .map(|p| p.to_string_lossy().to_string())
+        .unwrap_or_default();
+
+    // The proxy connects lazily, so a missing service only shows up as an
+    // error from the call itself
+    let assigned = match color_service().await {
+        Ok(proxy) => proxy.set_monitor_profile(&monitor, &path).await,
+        Err(e) => Err(e),
+    };
+
+    match assigned {
+        Ok(true) => Ok(()),
+        Ok(false) => Err(format!(
+            "The color service rejected the profile for {}",
+            monitor
+        )),
+        Err(_) => {
+            let mut monitors = detect_monitors().map_err(|e| e.to_string())?;
+            let detected = monitors
+                .iter_mut()
+                .find(|m| m.name == monitor)
+                .ok_or_else(|| format!("Monitor not found: {}", monitor))?;
+            detected.icc_profile = profile;
+            detected.save().map_err(|e| e.to_string())
+        }
+    }
+}
+
+pub async fn set_hdr(monitor: String, enabled: bool) -> Result<(), String> {
+    let proxy = color_service()
+        .await
+        .map_err(|_| "HDR needs the color service (rururu-color-daemon)".to_string())?;
+
+    let result = if enabled {
+        proxy.enable_hdr(&monitor).await
+    } else {
+        proxy.disable_hdr(&monitor).await
+    };
+
+    match result {
+        Ok(true) => Ok(()),
+        Ok(false) => Err(format!("Could not change HDR on {}", monitor)),
+        Err(e) => Err(e.to_string()),
+    }
+}
+
+/// Current HDR state of each monitor; empty when the service isn't running
+pub async fn load_hdr_states(monitors: Vec<String>) -> Vec<(String, bool)> {
+    let Ok(proxy) = color_service().await else {
+        return Vec::new();
+    };
+
+    let mut states = Vec::new();
+    for monitor in monitors {
+        if let Ok(active) = proxy.is_hdr_active(&monitor).await {
+            states.push((monitor, active));
+        }
+    }
+    states
+}
+
+/// Start the calibration tool on `monitor`. DRM names the connector
+/// `card0-DP-1`; colorcal only knows the `DP-1` part.
+pub fn launch_colorcal(monitor: &str) -> std::io::Result<()> {
+    let connector = match monitor.split_once('-') {
+        Some((card, rest)) if card.starts_with("card") => rest,
+        _ => monitor,
+    };
+
+    std::process::Command::new("rururu-colorcal")
+        .args(["--display", connector])
+        .spawn()
+        .map(|_| ())
+}
+
+async fn color_service() -> zbus::Result<ColorManagementProxy<'static>> {
+    let connection = zbus::Connection::session().await?;
+    ColorManagementProxy::new(&connection).await
+}
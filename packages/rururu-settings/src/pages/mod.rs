@@ -1,8 +1,10 @@
 pub mod about;
 pub mod appearance;
 pub mod audio;
+pub mod color;
 pub mod displays;
 pub mod keyboard;
 pub mod network;
 pub mod power;
 pub mod storage;
+pub mod workflows;
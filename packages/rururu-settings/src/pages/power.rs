@@ -1,11 +1,12 @@
 use crate::app::Message;
 use iced::widget::{column, pick_list, row, text, Space};
 use iced::{Element, Length};
+use rururu_utils::{BatteryInfo, SystemInfo};
 
 pub struct PowerPage {
     pub profile: String,
-    pub battery_level: u8,
-    pub charging: bool,
+    /// `None` on machines without a battery
+    pub battery: Option<BatteryInfo>,
     pub auto_suspend: u32,
     pub screen_off: u32,
 }
@@ -14,13 +15,16 @@ impl PowerPage {
     pub fn new() -> Self {
         Self {
             profile: "Balanced".to_string(),
-            battery_level: 85,
-            charging: true,
+            battery: SystemInfo::battery(),
             auto_suspend: 30,
             screen_off: 10,
         }
     }
 
+    pub fn refresh_battery(&mut self) {
+        self.battery = SystemInfo::battery();
+    }
+
     pub fn set_profile(&mut self, profile: &str) {
         self.profile = profile.to_string();
     }
@@ -32,34 +36,11 @@ impl PowerPage {
             "Power Saver".to_string(),
         ];
 
-        let battery_icon = match (self.battery_level, self.charging) {
-            (_, true) => "🔌",
-            (80..=100, _) => "🔋",
-            (50..=79, _) => "🔋",
-            (20..=49, _) => "🪫",
-            _ => "🪫",
-        };
-
         column![
             // Battery status
             text("Battery").size(16),
             Space::with_height(Length::Fixed(8.0)),
-            row![
-                text(battery_icon).size(32),
-                Space::with_width(Length::Fixed(16.0)),
-                column![
-                    text(format!("{}%", self.battery_level)).size(24),
-                    text(if self.charging {
-                        "Charging"
-                    } else {
-                        "On battery"
-                    })
-                    .size(12),
-                ],
-                Space::with_width(Length::Fill),
-            ]
-            .align_items(iced::Alignment::Center)
-            .padding(8),
+            self.battery_status(),
             Space::with_height(Length::Fixed(24.0)),
             // Power profile
             text("Power Profile").size(16),
@@ -125,4 +106,69 @@ impl PowerPage {
         .spacing(4)
         .into()
     }
+
+    fn battery_status(&self) -> Element<'_, Message> {
+        let Some(battery) = &self.battery else {
+            return row![
+                text("🔌").size(32),
+                text("No battery — running on mains power")
+            ]
+            .spacing(16)
+            .align_items(iced::Alignment::Center)
+            .padding(8)
+            .into();
+        };
+
+        let battery_icon = match (battery.percentage as u8, battery.charging) {
+            (_, true) => "🔌",
+            (50..=100, _) => "🔋",
+            _ => "🪫",
+        };
+        let state = match (battery.charging, battery.on_ac) {
+            (true, _) => "Charging",
+            (false, true) => "Plugged in, not charging",
+            (false, false) => "On battery",
+        };
+        let mut details = vec![state.to_string()];
+        if let Some(remaining) = battery.time_remaining {
+            let minutes = remaining.as_secs() / 60;
+            details.push(format!(
+                "{}:{:02} {}",
+                minutes / 60,
+                minutes % 60,
+                if battery.charging {
+                    "until full"
+                } else {
+                    "left"
+                }
+            ));
+        }
+        if battery.battery_count > 1 {
+            details.push(format!("{} batteries", battery.battery_count));
+        }
+
+        let mut info = column![
+            text(format!("{:.0}%", battery.percentage)).size(24),
+            text(details.join(" · ")).size(12),
+        ];
+        if let Some(health) = battery.health {
+            info = info.push(
+                text(format!("Health: {:.0}% of design capacity", health))
+                    .size(12)
+                    .style(iced::theme::Text::Color(iced::Color::from_rgb(
+                        0.6, 0.6, 0.6,
+                    ))),
+            );
+        }
+
+        row![
+            text(battery_icon).size(32),
+            Space::with_width(Length::Fixed(16.0)),
+            info,
+            Space::with_width(Length::Fill),
+        ]
+        .align_items(iced::Alignment::Center)
+        .padding(8)
+        .into()
+    }
 }
@@ -0,0 +1,296 @@
+use crate::app::Message;
+use iced::widget::{button, column, container, row, text, Space};
+use iced::{Element, Length};
+use rururu_workflows::apps::is_app_installed;
+use rururu_workflows::environment;
+use rururu_workflows::system::get_system_info;
+use rururu_workflows::{Readiness, WorkflowConfig, WorkflowProfile, WorkflowType};
+use std::process::Command;
+
+/// Terminals tried for commands that may ask for a sudo password, with the
+/// flag that precedes the command
+const TERMINALS: &[(&str, Option<&str>)] = &[
+    ("foot", None),
+    ("gnome-terminal", Some("--")),
+    ("konsole", Some("-e")),
+    ("xterm", Some("-e")),
+];
+
+#[derive(Debug, Clone)]
+pub struct WorkflowEntry {
+    pub profile: WorkflowProfile,
+    pub readiness: Readiness,
+    /// Steps `rururu-workflow activate` would take
+    pub plan: Vec<String>,
+    pub missing_apps: Vec<String>,
+}
+
+/// Workflows and the active one, gathered off the UI thread since scoring
+/// readiness looks up every app
+#[derive(Debug, Clone)]
+pub struct WorkflowsState {
+    pub entries: Vec<WorkflowEntry>,
+    pub active: WorkflowType,
+    /// Settings of the active workflow the system no longer matches
+    pub drift: Vec<String>,
+    pub error: Option<String>,
+}
+
+impl WorkflowsState {
+    pub fn collect() -> Self {
+        let (config, error) = match WorkflowConfig::load() {
+            Ok(config) => (config, None),
+            Err(e) => (
+                WorkflowConfig::default(),
+                Some(format!("Could not load the workflow config: {}", e)),
+            ),
+        };
+
+        let info = get_system_info();
+        let entries = WorkflowType::all()
+            .iter()
+            .map(|t| (*t, WorkflowProfile::get_profile(*t)))
+            // Types without a profile of their own fall back to General
+            .filter(|(t, profile)| profile.workflow_type == *t)
+            .map(|(_, profile)| WorkflowEntry {
+                readiness: profile.readiness(&info),
+                plan: profile.activation_plan(),
+                missing_apps: profile
+                    .applications
+                    .iter()
+                    .filter(|a| !is_app_installed(a))
+                    .map(|a| a.name.clone())
+                    .collect(),
+                profile,
+            })
+            .collect();
+
+        Self {
+            entries,
+            active: config.active_workflow,
+            drift: config.check_drift().iter().map(|d| d.to_string()).collect(),
+            error,
+        }
+    }
+}
+
+pub async fn load_workflows() -> WorkflowsState {
+    tokio::task::spawn_blocking(WorkflowsState::collect)
+        .await
+        .unwrap_or_else(|e| WorkflowsState {
+            entries: Vec::new(),
+            active: WorkflowType::General,
+            drift: Vec::new(),
+            error: Some(e.to_string()),
+        })
+}
+
+pub struct WorkflowsPage {
+    pub state: Option<WorkflowsState>,
+    /// Workflow whose activation plan is shown
+    pub expanded: Option<WorkflowType>,
+    pub status: Option<String>,
+}
+
+impl WorkflowsPage {
+    pub fn new() -> Self {
+        Self {
+            state: None,
+            expanded: None,
+            status: None,
+        }
+    }
+
+    pub fn set_state(&mut self, state: WorkflowsState) {
+        self.state = Some(state);
+    }
+
+    pub fn toggle_plan(&mut self, workflow: WorkflowType) {
+        self.expanded = (self.expanded != Some(workflow)).then_some(workflow);
+    }
+
+    pub fn set_status(&mut self, status: Option<String>) {
+        self.status = status;
+    }
+
+    pub fn view(&self) -> Element<'_, Message> {
+        let Some(state) = &self.state else {
+            return column![text("Checking workflows…").size(14)]
+                .padding(16)
+                .into();
+        };
+
+        let mut content = column![
+            text("Creative Workflows").size(16),
+            text("Readiness is scored against this machine's hardware and installed apps.")
+                .size(12),
+            Space::with_height(Length::Fixed(8.0)),
+        ]
+        .spacing(4);
+
+        if let Some(ref error) = state.error {
+            content = content.push(text(format!("⚠ {}", error)).size(12));
+        }
+
+        for entry in &state.entries {
+            let workflow = entry.profile.workflow_type;
+            let active = workflow == state.active;
+
+            let title = if active {
+                format!("{} · Active", entry.profile.name)
+            } else {
+                entry.profile.name.clone()
+            };
+
+            let plan_label = if self.expanded == Some(workflow) {
+                "Hide plan"
+            } else {
+                "Show plan"
+            };
+            let mut actions = row![button(text(plan_label).size(12))
+                .style(iced::theme::Button::Text)
+                .on_press(Message::WorkflowPlanToggled(workflow))]
+            .spacing(8)
+            .align_items(iced::Alignment::Center);
+            if !entry.missing_apps.is_empty() {
+                actions = actions.push(
+                    button(text("Install apps…").size(12))
+                        .style(iced::theme::Button::Secondary)
+                        .on_press(Message::InstallWorkflowApps(workflow)),
+                );
+            }
+            actions = actions.push(if active && workflow != WorkflowType::General {
+                button(text("Deactivate").size(12))
+                    .style(iced::theme::Button::Secondary)
+                    .on_press(Message::DeactivateWorkflow)
+            } else if active {
+                button(text("Active").size(12)).style(iced::theme::Button::Secondary)
+            } else {
+                button(text("Activate…").size(12))
+                    .style(iced::theme::Button::Primary)
+                    .on_press(Message::ActivateWorkflow(workflow))
+            });
+
+            let mut card = column![row![
+                column![
+                    text(title).size(14),
+                    text(&entry.profile.description).size(11),
+                ]
+                .spacing(2),
+                Space::with_width(Length::Fill),
+                text(format!("{}% ready", entry.readiness.score)).size(14),
+            ]
+            .align_items(iced::Alignment::Center),]
+            .spacing(8);
+
+            for shortfall in &entry.readiness.shortfalls {
+                card = card.push(text(format!("• {}", shortfall)).size(12));
+            }
+            if active {
+                for drift in &state.drift {
+                    card = card.push(text(format!("⚠ {}", drift)).size(12));
+                }
+            }
+            card = card.push(actions);
+
+            if self.expanded == Some(workflow) {
+                let mut plan = column![text("Activating would:").size(12)].spacing(2);
+                for (i, step) in entry.plan.iter().enumerate() {
+                    plan = plan.push(text(format!("{}. {}", i + 1, step)).size(12));
+                }
+                card = card.push(plan);
+            }
+
+            content = content.push(
+                container(card)
+                    .padding(12)
+                    .width(Length::Fill)
+                    .style(iced::theme::Container::Box),
+            );
+        }
+
+        content = content.push(Space::with_height(Length::Fixed(16.0)));
+        content = content.push(
+            row![
+                button(text("Refresh"))
+                    .style(iced::theme::Button::Secondary)
+                    .on_press(Message::RefreshWorkflows),
+                Space::with_width(Length::Fixed(16.0)),
+                text(self.status.as_deref().unwrap_or("")).size(12),
+            ]
+            .align_items(iced::Alignment::Center),
+        );
+
+        content.into()
+    }
+}
+
+/// The name `rururu-workflow` accepts for `workflow`
+fn cli_name(workflow: WorkflowType) -> &'static str {
+    match workflow {
+        WorkflowType::VideoEditor => "video",
+        WorkflowType::ThreeDArtist => "3d",
+        WorkflowType::TwoDDesigner => "2d",
+        WorkflowType::AudioProducer => "audio",
+        WorkflowType::Photographer => "photo",
+        WorkflowType::Developer => "dev",
+        WorkflowType::General => "general",
+    }
+}
+
+/// Activate `workflow` with `rururu-workflow` in a terminal, where it can
+/// ask for sudo and about conflicting processes
+pub async fn activate(workflow: WorkflowType) -> Result<(), String> {
+    run_in_terminal(vec!["activate".into(), cli_name(workflow).into()]).await
+}
+
+/// Install `workflow`'s missing applications in a terminal
+pub async fn install_apps(workflow: WorkflowType) -> Result<(), String> {
+    run_in_terminal(vec!["install".into(), cli_name(workflow).into()]).await
+}
+
+/// Return to the General workflow, as `rururu-workflow deactivate` does;
+/// nothing here needs root
+pub async fn deactivate() -> Result<(), String> {
+    tokio::task::spawn_blocking(|| {
+        environment::clear().map_err(|e| e.to_string())?;
+        let mut config = WorkflowConfig::load().map_err(|e| e.to_string())?;
+        config.set_active_workflow(WorkflowType::General);
+        config.save().map_err(|e| e.to_string())
+    })
+    .await
+    .map_err(|e| e.to_string())?
+}
+
+/// Run `rururu-workflow args…` in `$TERMINAL` or the first terminal found,
+/// keeping the window open until Enter so the output can be read, and wait
+/// for it to close
+async fn run_in_terminal(args: Vec<String>) -> Result<(), String> {
+    tokio::task::spawn_blocking(move || {
+        let script = r#"rururu-workflow "$@"; printf '\nPress Enter to close '; read _"#;
+        let mut command = vec!["sh".to_string(), "-c".into(), script.into(), "sh".into()];
+        command.extend(args);
+
+        let configured = std::env::var("TERMINAL").ok().filter(|t| !t.is_empty());
+        let candidates: Vec<(String, Option<&str>)> = configured
+            .map(|t| (t, Some("-e")))
+            .into_iter()
+            .chain(TERMINALS.iter().map(|(t, flag)| (t.to_string(), *flag)))
+            .collect();
+
+        for (terminal, flag) in candidates {
+            let mut child = Command::new(&terminal);
+            child.args(flag).args(&command);
+            match child.spawn() {
+                Ok(mut child) => {
+                    return child.wait().map(|_| ()).map_err(|e| e.to_string());
+                }
+                Err(e) if e.kind() == std::io::ErrorKind::NotFound => continue,
+                Err(e) => return Err(format!("Could not start {}: {}", terminal, e)),
+            }
+        }
+        Err("No terminal found to run rururu-workflow in".to_string())
+    })
+    .await
+    .map_err(|e| e.to_string())?
+}
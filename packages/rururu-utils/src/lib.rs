@@ -4,8 +4,10 @@ pub mod system;
 #[cfg(feature = "systemd")]
 pub mod systemd;
 
-pub use process::ProcessManager;
-pub use system::SystemInfo;
+pub use process::{
+    ProcessManager, ProcessOutput, ProcessPriority, RestartPolicy, SupervisorHandle,
+};
+pub use system::{BatteryInfo, DriveSpeed, LocalDrive, SystemInfo};
 
 #[cfg(feature = "systemd")]
 pub use systemd::SystemdManager;
@@ -1,6 +1,11 @@
 use nix::sys::signal::{self, Signal};
 use nix::unistd::Pid;
-use std::process::{Child, Command, Stdio};
+use std::os::unix::process::CommandExt;
+use std::process::{Child, Command, ExitStatus, Stdio};
+use std::sync::atomic::{AtomicBool, AtomicU32, Ordering};
+use std::sync::{Arc, Condvar, Mutex};
+use std::thread::JoinHandle;
+use std::time::{Duration, Instant};
 use thiserror::Error;
 use tracing::{debug, info, warn};
 
@@ -37,6 +42,219 @@ impl ProcessPriority {
     }
 }
 
+/// Captured result of [`ProcessManager::run`]
+#[derive(Debug, Clone)]
+pub struct ProcessOutput {
+    pub code: i32,
+    pub stdout: String,
+    pub stderr: String,
+}
+
+impl ProcessOutput {
+    pub fn success(&self) -> bool {
+        self.code == 0
+    }
+}
+
+/// When and how often [`ProcessManager::supervise`] restarts its child
+#[derive(Debug, Clone)]
+pub struct RestartPolicy {
+    /// Restart after a clean exit too, not only after a crash
+    pub restart_on_success: bool,
+    /// Consecutive restarts before giving up; `None` retries forever
+    pub max_retries: Option<u32>,
+    /// Delay before the first restart, doubled after each further one
+    pub initial_backoff: Duration,
+    pub max_backoff: Duration,
+    /// A run lasting this long counts as healthy and resets the backoff
+    /// and the retry count
+    pub reset_after: Duration,
+}
+
+impl Default for RestartPolicy {
+    fn default() -> Self {
+        Self {
+            restart_on_success: false,
+            max_retries: Some(5),
+            initial_backoff: Duration::from_secs(1),
+            max_backoff: Duration::from_secs(60),
+            reset_after: Duration::from_secs(60),
+        }
+    }
+}
+
+/// How long a stopped child gets to exit after SIGTERM before SIGKILL
+const STOP_GRACE: Duration = Duration::from_secs(2);
+const SUPERVISE_POLL: Duration = Duration::from_millis(100);
+
+struct SupervisorState {
+    stop: Mutex<bool>,
+    wake: Condvar,
+    restarts: AtomicU32,
+    /// PID of the running child, 0 between runs
+    pid: AtomicU32,
+    finished: AtomicBool,
+}
+
+impl SupervisorState {
+    /// Sleep for `timeout` or until stopped; returns whether stop was requested
+    fn wait_stop(&self, timeout: Duration) -> bool {
+        let stop = self.stop.lock().unwrap_or_else(|e| e.into_inner());
+        let (stop, _) = self
+            .wake
+            .wait_timeout_while(stop, timeout, |stop| !*stop)
+            .unwrap_or_else(|e| e.into_inner());
+        *stop
+    }
+}
+
+/// Handle to a supervision loop started by [`ProcessManager::supervise`].
+/// Dropping it stops the loop and the child.
+pub struct SupervisorHandle {
+    name: String,
+    state: Arc<SupervisorState>,
+    thread: Option<JoinHandle<()>>,
+}
+
+impl SupervisorHandle {
+    pub fn name(&self) -> &str {
+        &self.name
+    }
+
+    /// Times the child has been restarted so far
+    pub fn restart_count(&self) -> u32 {
+        self.state.restarts.load(Ordering::SeqCst)
+    }
+
+    /// PID of the current child, if one is running
+    pub fn pid(&self) -> Option<u32> {
+        match self.state.pid.load(Ordering::SeqCst) {
+            0 => None,
+            pid => Some(pid),
+        }
+    }
+
+    /// Whether the loop is still supervising, i.e. it hasn't given up, seen
+    /// a clean exit it shouldn't restart, or been stopped
+    pub fn is_running(&self) -> bool {
+        !self.state.finished.load(Ordering::SeqCst)
+    }
+
+    /// Stop supervising and terminate the child, waiting for both
+    pub fn stop(&mut self) {
+        *self.state.stop.lock().unwrap_or_else(|e| e.into_inner()) = true;
+        self.state.wake.notify_all();
+
+        if let Some(thread) = self.thread.take() {
+            let _ = thread.join();
+        }
+    }
+}
+
+impl Drop for SupervisorHandle {
+    fn drop(&mut self) {
+        self.stop();
+    }
+}
+
+fn spawn_supervised(program: &str, args: &[String]) -> Result<Child, ProcessError> {
+    Command::new(program)
+        .args(args)
+        .stdin(Stdio::null())
+        .spawn()
+        .map_err(|e| ProcessError::SpawnError(format!("{}: {}", program, e)))
+}
+
+/// Wait for `child` to exit, or stop it when asked. `None` means stopped.
+fn wait_supervised(child: &mut Child, state: &SupervisorState) -> Option<ExitStatus> {
+    loop {
+        match child.try_wait() {
+            Ok(Some(status)) => return Some(status),
+            Ok(None) => {}
+            Err(e) => {
+                warn!("Failed to wait for supervised process: {}", e);
+                let _ = child.kill();
+                return child.wait().ok();
+            }
+        }
+
+        if state.wait_stop(SUPERVISE_POLL) {
+            let _ = signal::kill(Pid::from_raw(child.id() as i32), Signal::SIGTERM);
+            let deadline = Instant::now() + STOP_GRACE;
+            while Instant::now() < deadline {
+                if let Ok(Some(_)) = child.try_wait() {
+                    return None;
+                }
+                std::thread::sleep(Duration::from_millis(20));
+            }
+            let _ = child.kill();
+            let _ = child.wait();
+            return None;
+        }
+    }
+}
+
+fn supervise_loop(
+    name: String,
+    program: String,
+    args: Vec<String>,
+    policy: RestartPolicy,
+    mut child: Child,
+    state: Arc<SupervisorState>,
+) {
+    let mut backoff = policy.initial_backoff;
+    let mut retries = 0;
+
+    loop {
+        let started = Instant::now();
+        state.pid.store(child.id(), Ordering::SeqCst);
+        let status = wait_supervised(&mut child, &state);
+        state.pid.store(0, Ordering::SeqCst);
+
+        let Some(status) = status else {
+            debug!("Supervision of {} stopped", name);
+            break;
+        };
+        if status.success() && !policy.restart_on_success {
+            info!("Supervised process {} exited cleanly", name);
+            break;
+        }
+        warn!("Supervised process {} exited with {}", name, status);
+
+        if started.elapsed() >= policy.reset_after {
+            backoff = policy.initial_backoff;
+            retries = 0;
+        }
+
+        // Keep retrying spawns that fail until the cap, like crashes
+        let next = loop {
+            if policy.max_retries.is_some_and(|max| retries >= max) {
+                warn!("Giving up on {} after {} restarts", name, retries);
+                break None;
+            }
+            if state.wait_stop(backoff) {
+                break None;
+            }
+            retries += 1;
+            backoff = (backoff * 2).min(policy.max_backoff);
+            state.restarts.fetch_add(1, Ordering::SeqCst);
+
+            info!("Restarting {} (attempt {})", name, retries);
+            match spawn_supervised(&program, &args) {
+                Ok(child) => break Some(child),
+                Err(e) => warn!("Failed to restart {}: {}", name, e),
+            }
+        };
+
+        match next {
+            Some(next) => child = next,
+            None => break,
+        }
+    }
+
+    state.finished.store(true, Ordering::SeqCst);
+}
+
 pub struct ManagedProcess {
     child: Child,
     name: String,
@@ -167,6 +385,98 @@ impl ProcessManager {
         Ok(terminated)
     }
 
+    /// Run a program to completion and capture its output. The process is
+    /// not tracked; this blocks until it exits.
+    pub fn run(program: &str, args: &[&str]) -> Result<ProcessOutput, ProcessError> {
+        info!("Running process: {} {}", program, args.join(" "));
+
+        let output = Command::new(program)
+            .args(args)
+            .stdin(Stdio::null())
+            .output()
+            .map_err(|e| ProcessError::SpawnError(format!("{}: {}", program, e)))?;
+
+        Ok(ProcessOutput {
+            code: output.status.code().unwrap_or(-1),
+            stdout: String::from_utf8_lossy(&output.stdout).into_owned(),
+            stderr: String::from_utf8_lossy(&output.stderr).into_owned(),
+        })
+    }
+
+    /// Start `program` detached from this process, with `env` added to the
+    /// inherited environment, and move it to `priority`. It runs in its own
+    /// process group with no stdio, and is not tracked. Failing to change
+    /// the priority, as unprivileged users can't raise it, is only logged.
+    pub fn launch(
+        program: &str,
+        args: &[&str],
+        env: &[(String, String)],
+        priority: ProcessPriority,
+    ) -> Result<u32, ProcessError> {
+        info!("Launching process: {} {}", program, args.join(" "));
+
+        let child = Command::new(program)
+            .args(args)
+            .envs(env.iter().map(|(k, v)| (k, v)))
+            .stdin(Stdio::null())
+            .stdout(Stdio::null())
+            .stderr(Stdio::null())
+            .process_group(0)
+            .spawn()
+            .map_err(|e| ProcessError::SpawnError(format!("{}: {}", program, e)))?;
+
+        let pid = child.id();
+        if priority != ProcessPriority::Normal {
+            if let Err(e) = Self::set_priority(pid as i32, priority) {
+                warn!("Keeping default CPU priority for {}: {}", program, e);
+            }
+            if let Err(e) = Self::set_io_priority(pid as i32, priority) {
+                warn!("Keeping default I/O priority for {}: {}", program, e);
+            }
+        }
+        debug!("Launched {} with PID {}", program, pid);
+
+        Ok(pid)
+    }
+
+    /// Start `program` and keep it running according to `policy` on a
+    /// background thread. Fails only if the first spawn does; later spawn
+    /// failures count against the retry cap like crashes.
+    pub fn supervise(
+        program: &str,
+        args: &[&str],
+        policy: RestartPolicy,
+    ) -> Result<SupervisorHandle, ProcessError> {
+        info!("Supervising process: {} {}", program, args.join(" "));
+
+        let name = program.to_string();
+        let args: Vec<String> = args.iter().map(|a| a.to_string()).collect();
+        let child = spawn_supervised(program, &args)?;
+
+        let state = Arc::new(SupervisorState {
+            stop: Mutex::new(false),
+            wake: Condvar::new(),
+            restarts: AtomicU32::new(0),
+            pid: AtomicU32::new(child.id()),
+            finished: AtomicBool::new(false),
+        });
+
+        let thread = {
+            let name = name.clone();
+            let program = program.to_string();
+            let state = Arc::clone(&state);
+            std::thread::Builder::new()
+                .name(format!("supervise-{}", name))
+                .spawn(move || supervise_loop(name, program, args, policy, child, state))?
+        };
+
+        Ok(SupervisorHandle {
+            name,
+            state,
+            thread: Some(thread),
+        })
+    }
+
     pub fn kill_pid(pid: i32) -> Result<(), ProcessError> {
         signal::kill(Pid::from_raw(pid), Signal::SIGKILL)
             .map_err(|e| ProcessError::SignalError(e.to_string()))
@@ -199,6 +509,32 @@ impl ProcessManager {
         Ok(())
     }
 
+    /// Move `pid` to the `ionice` class matching `priority`: real-time for
+    /// [`ProcessPriority::RealTime`], idle for [`ProcessPriority::Idle`],
+    /// best-effort levels otherwise
+    pub fn set_io_priority(pid: i32, priority: ProcessPriority) -> Result<(), ProcessError> {
+        let class: &[&str] = match priority {
+            ProcessPriority::RealTime => &["-c", "1", "-n", "4"],
+            ProcessPriority::High => &["-c", "2", "-n", "0"],
+            ProcessPriority::Normal => &["-c", "2", "-n", "4"],
+            ProcessPriority::Low => &["-c", "2", "-n", "7"],
+            ProcessPriority::Idle => &["-c", "3"],
+        };
+        let pid = pid.to_string();
+        let mut args = class.to_vec();
+        args.extend(["-p", &pid]);
+
+        let output = Self::run("ionice", &args)?;
+        if !output.success() {
+            return Err(ProcessError::SignalError(format!(
+                "Failed to set I/O priority: {}",
+                output.stderr.trim()
+            )));
+        }
+
+        Ok(())
+    }
+
     pub fn list_managed(&self) -> Vec<(&str, u32)> {
         self.managed
             .iter()
@@ -265,4 +601,93 @@ mod tests {
         let result = pm.spawn("test", "echo", &["hello"]);
         assert!(result.is_ok());
     }
+
+    #[test]
+    fn test_run_captures_output() {
+        let output = ProcessManager::run("sh", &["-c", "echo out; echo err >&2; exit 3"]).unwrap();
+        assert_eq!(output.code, 3);
+        assert!(!output.success());
+        assert_eq!(output.stdout.trim(), "out");
+        assert_eq!(output.stderr.trim(), "err");
+
+        assert!(matches!(
+            ProcessManager::run("rururu-no-such-program", &[]),
+            Err(ProcessError::SpawnError(_))
+        ));
+    }
+
+    #[test]
+    fn test_launch_passes_environment() {
+        let dir = tempfile::tempdir().unwrap();
+        let out = dir.path().join("env");
+
+        let script = format!("echo \"$RURURU_LAUNCH_TEST\" > {}", out.display());
+        let env = [("RURURU_LAUNCH_TEST".to_string(), "tuned".to_string())];
+        ProcessManager::launch("sh", &["-c", &script], &env, ProcessPriority::Normal).unwrap();
+
+        let deadline = Instant::now() + Duration::from_secs(5);
+        let content = loop {
+            match std::fs::read_to_string(&out) {
+                Ok(content) if content.ends_with('\n') => break content,
+                _ if Instant::now() > deadline => panic!("launched process never wrote"),
+                _ => std::thread::sleep(Duration::from_millis(10)),
+            }
+        };
+        assert_eq!(content.trim(), "tuned");
+    }
+
+    fn quick_policy(max_retries: Option<u32>) -> RestartPolicy {
+        RestartPolicy {
+            max_retries,
+            initial_backoff: Duration::from_millis(10),
+            max_backoff: Duration::from_millis(40),
+            ..Default::default()
+        }
+    }
+
+    fn wait_finished(handle: &SupervisorHandle) {
+        let deadline = Instant::now() + Duration::from_secs(10);
+        while handle.is_running() && Instant::now() < deadline {
+            std::thread::sleep(Duration::from_millis(10));
+        }
+    }
+
+    #[test]
+    fn test_supervise_restarts_until_cap() {
+        let handle =
+            ProcessManager::supervise("sh", &["-c", "exit 1"], quick_policy(Some(3))).unwrap();
+        wait_finished(&handle);
+        assert!(!handle.is_running());
+        assert_eq!(handle.restart_count(), 3);
+        assert_eq!(handle.pid(), None);
+    }
+
+    #[test]
+    fn test_supervise_clean_exit_is_not_restarted() {
+        let handle = ProcessManager::supervise("true", &[], quick_policy(None)).unwrap();
+        wait_finished(&handle);
+        assert_eq!(handle.restart_count(), 0);
+    }
+
+    #[test]
+    fn test_supervise_stop_terminates_child() {
+        let mut handle = ProcessManager::supervise("sleep", &["30"], quick_policy(None)).unwrap();
+        let pid = handle.pid().unwrap();
+        assert!(handle.is_running());
+
+        let start = Instant::now();
+        handle.stop();
+        assert!(start.elapsed() < STOP_GRACE);
+        assert!(!handle.is_running());
+        assert_eq!(handle.pid(), None);
+        assert!(signal::kill(Pid::from_raw(pid as i32), None).is_err());
+    }
+
+    #[test]
+    fn test_supervise_spawn_failure() {
+        assert!(matches!(
+            ProcessManager::supervise("rururu-no-such-program", &[], RestartPolicy::default()),
+            Err(ProcessError::SpawnError(_))
+        ));
+    }
 }
@@ -1,4 +1,6 @@
 use serde::{Deserialize, Serialize};
+use std::fs;
+use std::path::Path;
 use std::time::Duration;
 use sysinfo::{CpuRefreshKind, MemoryRefreshKind, RefreshKind, System};
 use thiserror::Error;
@@ -61,6 +63,40 @@ pub struct SystemSummary {
     pub disks: Vec<DiskInfo>,
 }
 
+/// Combined state of the system's batteries
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct BatteryInfo {
+    /// Charge across all batteries, 0–100
+    pub percentage: f32,
+    pub charging: bool,
+    /// Whether a mains or USB supply is connected
+    pub on_ac: bool,
+    /// Until empty when discharging, until full when charging; `None` when
+    /// the drivers don't report a rate
+    pub time_remaining: Option<Duration>,
+    /// Full capacity as a percentage of the design capacity
+    pub health: Option<f32>,
+    pub battery_count: usize,
+}
+
+/// Speed class of a fixed local drive; sorts fastest first
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Serialize, Deserialize)]
+pub enum DriveSpeed {
+    Nvme,
+    Ssd,
+    Hdd,
+}
+
+/// A whole drive as named in `/sys/block`, e.g. `nvme0n1` or `sda`
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct LocalDrive {
+    pub name: String,
+    pub speed: DriveSpeed,
+}
+
+const POWER_SUPPLY_DIR: &str = "/sys/class/power_supply";
+const BLOCK_DIR: &str = "/sys/block";
+
 pub struct SystemInfo {
     sys: System,
 }
@@ -182,7 +218,7 @@ impl SystemInfo {
 
     pub fn top_processes_by_memory(&self, count: usize) -> Vec<ProcessInfo> {
         let mut procs = self.process_list();
-        procs.sort_by(|a, b| b.memory_bytes.cmp(&a.memory_bytes));
+        procs.sort_by_key(|p| std::cmp::Reverse(p.memory_bytes));
         procs.truncate(count);
         procs
     }
@@ -200,6 +236,17 @@ impl SystemInfo {
         }
     }
 
+    /// The laptop batteries, combined; `None` on machines without one
+    pub fn battery() -> Option<BatteryInfo> {
+        battery_from(Path::new(POWER_SUPPLY_DIR))
+    }
+
+    /// Fixed local drives, fastest first. Removable media, SD cards and
+    /// virtual devices (loop, RAM, device-mapper) are left out.
+    pub fn local_drives() -> Vec<LocalDrive> {
+        local_drives_in(Path::new(BLOCK_DIR))
+    }
+
     pub fn is_low_memory(&self) -> bool {
         let mem = self.memory_info();
         let usage = (mem.used_bytes as f64) / (mem.total_bytes as f64);
@@ -218,6 +265,153 @@ impl Default for SystemInfo {
     }
 }
 
+fn local_drives_in(dir: &Path) -> Vec<LocalDrive> {
+    let Ok(entries) = fs::read_dir(dir) else {
+        return Vec::new();
+    };
+
+    let mut drives: Vec<LocalDrive> = entries
+        .flatten()
+        .filter_map(|entry| {
+            let name = entry.file_name().to_string_lossy().to_string();
+            if ["loop", "ram", "zram", "dm-", "mmcblk"]
+                .iter()
+                .any(|prefix| name.starts_with(prefix))
+            {
+                return None;
+            }
+
+            let device = entry.path();
+            let number = |file: &str| {
+                fs::read_to_string(device.join(file))
+                    .ok()
+                    .and_then(|v| v.trim().parse::<u64>().ok())
+            };
+            if number("size").unwrap_or(0) == 0 || number("removable") == Some(1) {
+                return None;
+            }
+
+            let speed = if name.starts_with("nvme") {
+                DriveSpeed::Nvme
+            } else if number("queue/rotational") == Some(0) {
+                DriveSpeed::Ssd
+            } else {
+                DriveSpeed::Hdd
+            };
+            Some(LocalDrive { name, speed })
+        })
+        .collect();
+    drives.sort_by(|a, b| a.speed.cmp(&b.speed).then_with(|| a.name.cmp(&b.name)));
+    drives
+}
+
+/// One battery's readings, in µWh and µW where the driver gives energy, or
+/// in µAh and µA when it only gives charge and no voltage to convert with
+#[derive(Debug, Default)]
+struct BatteryReading {
+    now: Option<f64>,
+    full: Option<f64>,
+    design: Option<f64>,
+    rate: Option<f64>,
+    capacity: Option<f32>,
+    status: String,
+}
+
+fn battery_from(dir: &Path) -> Option<BatteryInfo> {
+    let mut batteries = Vec::new();
+    let mut on_ac = false;
+
+    for entry in fs::read_dir(dir).ok()?.flatten() {
+        let supply = entry.path();
+        let read = |name: &str| {
+            fs::read_to_string(supply.join(name))
+                .ok()
+                .map(|v| v.trim().to_string())
+        };
+        match read("type").as_deref() {
+            // Mice and headsets report "Device" scope; only system batteries count
+            Some("Battery") if read("scope").as_deref() != Some("Device") => {
+                batteries.push(read_battery(&read));
+            }
+            Some("Mains") | Some("USB") => on_ac |= read("online").as_deref() == Some("1"),
+            _ => {}
+        }
+    }
+    if batteries.is_empty() {
+        return None;
+    }
+
+    let charging = batteries.iter().any(|b| b.status == "Charging");
+    let total = |field: fn(&BatteryReading) -> Option<f64>| -> Option<f64> {
+        batteries.iter().map(field).sum()
+    };
+    let (now, full) = (total(|b| b.now), total(|b| b.full));
+
+    let percentage = match (now, full) {
+        (Some(now), Some(full)) if full > 0.0 => (now / full * 100.0) as f32,
+        _ => {
+            let capacities: Vec<f32> = batteries.iter().filter_map(|b| b.capacity).collect();
+            if capacities.is_empty() {
+                return None;
+            }
+            capacities.iter().sum::<f32>() / capacities.len() as f32
+        }
+    };
+
+    let time_remaining = match (now, full, total(|b| b.rate)) {
+        (Some(now), Some(full), Some(rate)) if rate > 0.0 => {
+            let left = if charging { full - now } else { now };
+            Some(Duration::from_secs_f64(left.max(0.0) / rate * 3600.0))
+        }
+        _ => None,
+    }
+    .filter(|_| charging || batteries.iter().any(|b| b.status == "Discharging"));
+
+    let health = match (full, total(|b| b.design)) {
+        (Some(full), Some(design)) if design > 0.0 => Some((full / design * 100.0) as f32),
+        _ => None,
+    };
+
+    Some(BatteryInfo {
+        percentage: percentage.clamp(0.0, 100.0),
+        charging,
+        on_ac: on_ac || charging,
+        time_remaining,
+        health,
+        battery_count: batteries.len(),
+    })
+}
+
+fn read_battery(read: &dyn Fn(&str) -> Option<String>) -> BatteryReading {
+    let number = |name: &str| read(name).and_then(|v| v.parse::<f64>().ok());
+
+    let mut reading = BatteryReading {
+        capacity: read("capacity").and_then(|v| v.parse().ok()),
+        status: read("status").unwrap_or_default(),
+        ..BatteryReading::default()
+    };
+    if number("energy_full").is_some() {
+        reading.now = number("energy_now");
+        reading.full = number("energy_full");
+        reading.design = number("energy_full_design");
+        reading.rate = number("power_now");
+    } else {
+        // Charge times voltage gives energy, so batteries reporting either
+        // way add up
+        let volts = number("voltage_min_design")
+            .or_else(|| number("voltage_now"))
+            .map_or(1.0, |uv| uv / 1_000_000.0);
+        let energy = |name: &str| number(name).map(|v| v * volts);
+        reading.now = energy("charge_now");
+        reading.full = energy("charge_full");
+        reading.design = energy("charge_full_design");
+        reading.rate = energy("current_now");
+    }
+    // Some drivers report a negative rate while discharging
+    reading.rate = reading.rate.map(f64::abs);
+    reading
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
@@ -235,6 +429,106 @@ mod tests {
         assert!(mem.total_bytes > 0);
     }
 
+    #[test]
+    fn test_battery_from_sysfs() {
+        let dir = tempfile::tempdir().unwrap();
+        let supply = |name: &str, files: &[(&str, &str)]| {
+            let path = dir.path().join(name);
+            fs::create_dir_all(&path).unwrap();
+            for (file, value) in files {
+                fs::write(path.join(file), format!("{}\n", value)).unwrap();
+            }
+        };
+        supply("AC", &[("type", "Mains"), ("online", "0")]);
+        supply(
+            "BAT0",
+            &[
+                ("type", "Battery"),
+                ("status", "Discharging"),
+                ("energy_now", "30000000"),
+                ("energy_full", "40000000"),
+                ("energy_full_design", "50000000"),
+                ("power_now", "10000000"),
+            ],
+        );
+        supply(
+            "BAT1",
+            &[
+                ("type", "Battery"),
+                ("status", "Discharging"),
+                ("charge_now", "1000000"),
+                ("charge_full", "2000000"),
+                ("charge_full_design", "2000000"),
+                ("current_now", "0"),
+                ("voltage_min_design", "10000000"),
+            ],
+        );
+        supply(
+            "hidpp_battery_0",
+            &[("type", "Battery"), ("scope", "Device"), ("capacity", "5")],
+        );
+
+        let battery = battery_from(dir.path()).unwrap();
+        assert_eq!(battery.battery_count, 2);
+        assert!(!battery.charging && !battery.on_ac);
+        // 40 of 60 Wh, draining at 10 W
+        assert!((battery.percentage - 66.7).abs() < 0.1);
+        assert_eq!(battery.time_remaining, Some(Duration::from_secs(4 * 3600)));
+        assert!((battery.health.unwrap() - 85.7).abs() < 0.1);
+
+        for name in ["BAT0", "BAT1", "hidpp_battery_0"] {
+            fs::remove_dir_all(dir.path().join(name)).unwrap();
+        }
+        supply("AC", &[("type", "Mains"), ("online", "1")]);
+        assert!(battery_from(dir.path()).is_none());
+    }
+
+    #[test]
+    fn test_local_drives_from_sysfs() {
+        let dir = tempfile::tempdir().unwrap();
+        let block = |name: &str, files: &[(&str, &str)]| {
+            let path = dir.path().join(name);
+            fs::create_dir_all(path.join("queue")).unwrap();
+            for (file, value) in files {
+                fs::write(path.join(file), format!("{}\n", value)).unwrap();
+            }
+        };
+        let fixed = |rotational| {
+            [
+                ("size", "1000215216"),
+                ("removable", "0"),
+                ("queue/rotational", rotational),
+            ]
+        };
+        block("sda", &fixed("1"));
+        block("sdb", &fixed("0"));
+        block("nvme0n1", &fixed("0"));
+        block(
+            "sdc",
+            &[
+                ("size", "61440000"),
+                ("removable", "1"),
+                ("queue/rotational", "0"),
+            ],
+        );
+        block("mmcblk0", &fixed("0"));
+        block("loop0", &fixed("0"));
+        block("sr0", &[("size", "0"), ("removable", "1")]);
+
+        let drives: Vec<(String, DriveSpeed)> = local_drives_in(dir.path())
+            .into_iter()
+            .map(|d| (d.name, d.speed))
+            .collect();
+        assert_eq!(
+            drives,
+            vec![
+                ("nvme0n1".to_string(), DriveSpeed::Nvme),
+                ("sdb".to_string(), DriveSpeed::Ssd),
+                ("sda".to_string(), DriveSpeed::Hdd),
+            ]
+        );
+    }
+
     #[test]
     fn test_cpu_info() {
         let info = SystemInfo::new();
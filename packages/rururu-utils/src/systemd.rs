@@ -1,6 +1,8 @@
 #![allow(clippy::type_complexity)]
 
 use std::collections::HashMap;
+use std::process::Command;
+use std::time::{Duration, SystemTime};
 use thiserror::Error;
 use tracing::{debug, info};
 use zbus::{blocking::Connection, proxy};
@@ -13,6 +15,8 @@ pub enum SystemdError {
     UnitNotFound(String),
     #[error("Operation failed: {0}")]
     OperationFailed(String),
+    #[error("Permission denied: {0}")]
+    PermissionDenied(String),
 }
 
 impl From<zbus::Error> for SystemdError {
@@ -46,6 +50,21 @@ impl From<&str> for UnitState {
     }
 }
 
+impl UnitState {
+    /// The `ActiveState` string systemd uses for this state.
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            UnitState::Active => "active",
+            UnitState::Inactive => "inactive",
+            UnitState::Failed => "failed",
+            UnitState::Activating => "activating",
+            UnitState::Deactivating => "deactivating",
+            UnitState::Reloading => "reloading",
+            UnitState::Unknown => "unknown",
+        }
+    }
+}
+
 #[derive(Debug, Clone)]
 pub struct UnitInfo {
     pub name: String,
@@ -55,6 +74,22 @@ pub struct UnitInfo {
     pub sub_state: String,
 }
 
+/// One entry of `ListUnits`/`ListUnitsByPatterns`: name, description, load
+/// state, active state, sub state, followed unit, object path, job id, job
+/// type, job path.
+type RawUnit = (
+    String,
+    String,
+    String,
+    String,
+    String,
+    String,
+    zbus::zvariant::OwnedObjectPath,
+    u32,
+    String,
+    zbus::zvariant::OwnedObjectPath,
+);
+
 #[proxy(
     interface = "org.freedesktop.systemd1.Manager",
     default_service = "org.freedesktop.systemd1",
@@ -78,40 +113,74 @@ trait SystemdManager {
         runtime: bool,
     ) -> zbus::Result<Vec<(String, String, String)>>;
     fn get_unit(&self, name: &str) -> zbus::Result<zbus::zvariant::OwnedObjectPath>;
-    fn list_units(
+    fn list_units(&self) -> zbus::Result<Vec<RawUnit>>;
+    fn list_units_by_patterns(
         &self,
-    ) -> zbus::Result<
-        Vec<(
-            String,
-            String,
-            String,
-            String,
-            String,
-            String,
-            zbus::zvariant::OwnedObjectPath,
-            u32,
-            String,
-            zbus::zvariant::OwnedObjectPath,
-        )>,
-    >;
+        states: &[&str],
+        patterns: &[&str],
+    ) -> zbus::Result<Vec<RawUnit>>;
     fn reload(&self) -> zbus::Result<()>;
 }
 
+/// Syslog priority of a journal entry, most severe first.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord)]
+pub enum LogPriority {
+    Emergency,
+    Alert,
+    Critical,
+    Error,
+    Warning,
+    Notice,
+    Info,
+    Debug,
+}
+
+impl From<u8> for LogPriority {
+    fn from(level: u8) -> Self {
+        match level {
+            0 => LogPriority::Emergency,
+            1 => LogPriority::Alert,
+            2 => LogPriority::Critical,
+            3 => LogPriority::Error,
+            4 => LogPriority::Warning,
+            5 => LogPriority::Notice,
+            6 => LogPriority::Info,
+            _ => LogPriority::Debug,
+        }
+    }
+}
+
+#[derive(Debug, Clone)]
+pub struct LogLine {
+    pub timestamp: SystemTime,
+    pub priority: LogPriority,
+    pub message: String,
+}
+
 pub struct SystemdManager {
     connection: Connection,
+    /// Whether this talks to the user instance, whose units log to the
+    /// user's own journal
+    user: bool,
 }
 
 impl SystemdManager {
     pub fn new() -> Result<Self, SystemdError> {
         let connection = Connection::system()?;
         debug!("Connected to systemd via D-Bus");
-        Ok(Self { connection })
+        Ok(Self {
+            connection,
+            user: false,
+        })
     }
 
     pub fn new_user() -> Result<Self, SystemdError> {
         let connection = Connection::session()?;
         debug!("Connected to user systemd via D-Bus");
-        Ok(Self { connection })
+        Ok(Self {
+            connection,
+            user: true,
+        })
     }
 
     fn get_proxy(&self) -> Result<SystemdManagerProxyBlocking<'_>, SystemdError> {
@@ -171,24 +240,28 @@ impl SystemdManager {
         let proxy = self.get_proxy()?;
         let units = proxy.list_units()?;
 
-        Ok(units
-            .into_iter()
-            .map(
-                |(name, description, load_state, active_state, sub_state, _, _, _, _, _)| {
-                    UnitInfo {
-                        name,
-                        description,
-                        load_state,
-                        active_state: UnitState::from(active_state.as_str()),
-                        sub_state,
-                    }
-                },
-            )
-            .collect())
+        Ok(units.into_iter().map(unit_info).collect())
+    }
+
+    /// List units matching any of the glob `patterns` and in any of `states`,
+    /// filtered by systemd itself. Empty slices match everything;
+    /// `UnitState::Unknown` matches nothing, since systemd has no such state.
+    pub fn list_units_filtered(
+        &self,
+        patterns: &[&str],
+        states: &[UnitState],
+    ) -> Result<Vec<UnitInfo>, SystemdError> {
+        let Some(states) = state_filter(states) else {
+            return Ok(Vec::new());
+        };
+        let proxy = self.get_proxy()?;
+        let units = proxy.list_units_by_patterns(&states, patterns)?;
+
+        Ok(units.into_iter().map(unit_info).collect())
     }
 
     pub fn get_unit_state(&self, unit: &str) -> Result<UnitState, SystemdError> {
-        let units = self.list_units()?;
+        let units = self.list_units_filtered(&[unit], &[])?;
         units
             .iter()
             .find(|u| u.name == unit)
@@ -205,11 +278,106 @@ impl SystemdManager {
     }
 
     pub fn list_rururu_services(&self) -> Result<Vec<UnitInfo>, SystemdError> {
-        let units = self.list_units()?;
-        Ok(units
-            .into_iter()
-            .filter(|u| u.name.starts_with("rururu"))
-            .collect())
+        self.list_units_filtered(&["rururu*"], &[])
+    }
+
+    /// The last `lines` journal entries of `unit`, oldest first.
+    ///
+    /// Users outside the `systemd-journal`/`adm` groups can read their own
+    /// journal only, so a system unit's logs come back as
+    /// `PermissionDenied` rather than as an empty list.
+    pub fn unit_logs(&self, unit: &str, lines: usize) -> Result<Vec<LogLine>, SystemdError> {
+        let mut command = Command::new("journalctl");
+        if self.user {
+            command.args(["--user", "--user-unit", unit]);
+        } else {
+            command.args(["--unit", unit]);
+        }
+        let output = command
+            .args([
+                "--lines",
+                &lines.to_string(),
+                "--output",
+                "json",
+                "--no-pager",
+            ])
+            .output()
+            .map_err(|e| SystemdError::OperationFailed(format!("journalctl: {}", e)))?;
+
+        let stderr = String::from_utf8_lossy(&output.stderr);
+        if !output.status.success() {
+            return Err(journal_error(unit, &stderr));
+        }
+
+        let logs: Vec<LogLine> = String::from_utf8_lossy(&output.stdout)
+            .lines()
+            .filter_map(parse_log_line)
+            .collect();
+
+        // journalctl still succeeds when it can only open some of the
+        // journal files; it just says so on stderr
+        if logs.is_empty() && is_permission_hint(&stderr) {
+            return Err(journal_error(unit, &stderr));
+        }
+
+        Ok(logs)
+    }
+}
+
+fn is_permission_hint(stderr: &str) -> bool {
+    stderr.contains("insufficient permissions") || stderr.contains("not seeing messages")
+}
+
+fn journal_error(unit: &str, stderr: &str) -> SystemdError {
+    if is_permission_hint(stderr) || stderr.contains("Permission denied") {
+        SystemdError::PermissionDenied(format!(
+            "can't read the journal for {}; join the systemd-journal group to see system logs",
+            unit
+        ))
+    } else {
+        SystemdError::OperationFailed(format!("journalctl: {}", stderr.trim()))
+    }
+}
+
+/// One `journalctl -o json` entry. Fields are strings, except that a message
+/// that isn't valid UTF-8 comes as an array of bytes.
+fn parse_log_line(line: &str) -> Option<LogLine> {
+    let entry: serde_json::Value = serde_json::from_str(line).ok()?;
+
+    let micros: u64 = entry.get("__REALTIME_TIMESTAMP")?.as_str()?.parse().ok()?;
+    let priority = entry
+        .get("PRIORITY")
+        .and_then(|p| p.as_str())
+        .and_then(|p| p.parse::<u8>().ok())
+        .map(LogPriority::from)
+        .unwrap_or(LogPriority::Info);
+    let message = match entry.get("MESSAGE")? {
+        serde_json::Value::String(message) => message.clone(),
+        serde_json::Value::Array(bytes) => {
+            let bytes: Vec<u8> = bytes
+                .iter()
+                .filter_map(|b| b.as_u64().map(|b| b as u8))
+                .collect();
+            String::from_utf8_lossy(&bytes).into_owned()
+        }
+        _ => return None,
+    };
+
+    Some(LogLine {
+        timestamp: SystemTime::UNIX_EPOCH + Duration::from_micros(micros),
+        priority,
+        message,
+    })
+}
+
+fn unit_info(raw: RawUnit) -> UnitInfo {
+    let (name, description, load_state, active_state, sub_state, _, _, _, _, _) = raw;
+    UnitInfo {
+        name,
+        description,
+        load_state,
+        active_state: UnitState::from(active_state.as_str()),
+        sub_state,
     }
 }
 
@@ -247,6 +415,17 @@ WantedBy=multi-user.target
     unit
 }
 
+/// `states` as systemd's filter, where an empty list means any state;
+/// `None` when only `Unknown` was asked for, which no unit can be in
+fn state_filter(states: &[UnitState]) -> Option<Vec<&'static str>> {
+    let known: Vec<&str> = states
+        .iter()
+        .filter(|s| **s != UnitState::Unknown)
+        .map(UnitState::as_str)
+        .collect();
+    (states.is_empty() || !known.is_empty()).then_some(known)
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
@@ -259,6 +438,54 @@ mod tests {
         assert_eq!(UnitState::from("unknown_state"), UnitState::Unknown);
     }
 
+    #[test]
+    fn test_unit_state_as_str_round_trip() {
+        for state in [
+            UnitState::Active,
+            UnitState::Inactive,
+            UnitState::Failed,
+            UnitState::Activating,
+            UnitState::Deactivating,
+            UnitState::Reloading,
+        ] {
+            assert_eq!(UnitState::from(state.as_str()), state);
+        }
+    }
+
+    #[test]
+    fn test_state_filter() {
+        assert_eq!(state_filter(&[]), Some(vec![]));
+        assert_eq!(
+            state_filter(&[UnitState::Failed, UnitState::Unknown]),
+            Some(vec!["failed"])
+        );
+        // An empty filter would match every unit
+        assert_eq!(state_filter(&[UnitState::Unknown]), None);
+    }
+
+    #[test]
+    fn test_parse_log_line() {
+        let line = parse_log_line(
+            r#"{"__REALTIME_TIMESTAMP":"1700000000123456","PRIORITY":"3","MESSAGE":"rururu-colord: no monitors found","_SYSTEMD_UNIT":"rururu-colord.service"}"#,
+        )
+        .unwrap();
+        assert_eq!(line.priority, LogPriority::Error);
+        assert_eq!(line.message, "rururu-colord: no monitors found");
+        assert_eq!(
+            line.timestamp,
+            SystemTime::UNIX_EPOCH + Duration::from_micros(1_700_000_000_123_456)
+        );
+
+        // Non-UTF-8 messages come as byte arrays
+        let line = parse_log_line(
+            r#"{"__REALTIME_TIMESTAMP":"1","PRIORITY":"6","MESSAGE":[104,105,255]}"#,
+        )
+        .unwrap();
+        assert_eq!(line.message, "hi\u{fffd}");
+
+        assert!(parse_log_line(r#"{"__REALTIME_TIMESTAMP":"1","MESSAGE":null}"#).is_none());
+    }
+
     #[test]
     fn test_create_service_unit() {
         let mut opts = HashMap::new();
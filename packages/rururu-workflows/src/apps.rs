@@ -1,6 +1,10 @@
 use crate::config::PackageManager;
-use crate::profiles::AppConfig;
-use crate::{Result, WorkflowError};
+use crate::profiles::{AppConfig, WorkflowProfile};
+use crate::{cgroups, environment};
+use crate::{expand_home, Result, WorkflowError};
+use rururu_utils::{ProcessManager, ProcessPriority};
+use std::fs;
+use std::path::{Path, PathBuf};
 use std::process::Command;
 
 pub fn is_app_installed(app: &AppConfig) -> bool {
@@ -94,6 +98,80 @@ pub fn launch_app(app: &AppConfig) -> Result<()> {
     Err(WorkflowError::AppNotFound(app.name.clone()))
 }
 
+/// Launch `app` detached, tuned for `profile`: it gets the profile's
+/// environment (including `OCIO` and scratch variables), any of its own
+/// settings named like environment variables, e.g. `PIPEWIRE_LATENCY`, and a
+/// raised CPU and I/O priority when the profile lists it as high priority,
+/// in the workflow's cgroup slice if the profile sets resource limits.
+/// Returns the PID of the launched process.
+pub fn launch_app_in_workflow(app: &AppConfig, profile: &WorkflowProfile) -> Result<u32> {
+    let mut env = environment::profile_environment(profile);
+    env.extend(
+        app.settings
+            .iter()
+            .filter(|(key, _)| is_env_name(key))
+            .map(|(k, v)| (k.clone(), v.clone())),
+    );
+    let priority = launch_priority(app, profile);
+
+    let pid = if Command::new("which")
+        .arg(&app.executable)
+        .output()
+        .map(|o| o.status.success())
+        .unwrap_or(false)
+    {
+        ProcessManager::launch(&app.executable, &[], &env, priority)
+    } else if let Some(ref flatpak_id) = app.flatpak_id {
+        // The sandbox starts from its own environment, so pass ours in
+        let mut args: Vec<String> = env
+            .iter()
+            .map(|(k, v)| format!("--env={}={}", k, v))
+            .collect();
+        args.insert(0, "run".to_string());
+        args.push(flatpak_id.clone());
+        let args: Vec<&str> = args.iter().map(String::as_str).collect();
+        ProcessManager::launch("flatpak", &args, &[], priority)
+    } else {
+        return Err(WorkflowError::AppNotFound(app.name.clone()));
+    };
+
+    let pid = pid.map_err(|e| WorkflowError::System(e.to_string()))?;
+
+    // Into the workflow slice with the processes activation moved there
+    let limited =
+        profile.system_settings.resource_limits.is_some() && cgroups::cgroup_v2_available();
+    if limited && matches!(priority, ProcessPriority::High) {
+        if let Err(e) = cgroups::move_to_slice(&app.executable, pid) {
+            tracing::warn!("Couldn't move {} into the workflow slice: {}", app.name, e);
+        }
+    }
+    Ok(pid)
+}
+
+/// High for apps in the profile's `high_priority_processes`, matched by
+/// executable or name
+fn launch_priority(app: &AppConfig, profile: &WorkflowProfile) -> ProcessPriority {
+    let listed = profile
+        .system_settings
+        .high_priority_processes
+        .iter()
+        .any(|p| p == &app.executable || p.eq_ignore_ascii_case(&app.name));
+    if listed {
+        ProcessPriority::High
+    } else {
+        ProcessPriority::Normal
+    }
+}
+
+/// `SCREAMING_SNAKE` keys; other settings go in the app's own config
+fn is_env_name(key: &str) -> bool {
+    !key.is_empty()
+        && !key.starts_with(|c: char| c.is_ascii_digit())
+        && key
+            .chars()
+            .all(|c| c.is_ascii_uppercase() || c.is_ascii_digit() || c == '_')
+}
+
 pub fn get_app_version(app: &AppConfig) -> Option<String> {
     // Try --version
     if let Ok(output) = Command::new(&app.executable).arg("--version").output() {
@@ -136,3 +214,380 @@ pub fn list_installed_creative_apps() -> Vec<String> {
         .map(|s| s.to_string())
         .collect()
 }
+
+/// Startup script holding Blender's workflow settings, wholly generated
+const BLENDER_SCRIPT: &str = "rururu_workflow.py";
+
+/// Config file formats we know how to write an app's `settings` into.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ConfigAdapter {
+    /// Startup script in Blender's user scripts dir (userpref.blend is binary)
+    Blender,
+    /// `<Option name=".." value=".."/>` entries in Ardour's XML `config`
+    Ardour,
+    /// SPA-JSON drop-in under `pipewire.conf.d`
+    PipeWire,
+    /// Plain `key=value` lines
+    KeyValue,
+}
+
+impl ConfigAdapter {
+    pub fn for_app(app: &AppConfig) -> Self {
+        let exe = app.executable.to_lowercase();
+        if exe == "blender" {
+            ConfigAdapter::Blender
+        } else if exe.starts_with("ardour") {
+            ConfigAdapter::Ardour
+        } else if exe == "pipewire" {
+            ConfigAdapter::PipeWire
+        } else {
+            ConfigAdapter::KeyValue
+        }
+    }
+}
+
+/// Write the app's workflow `settings` into its real config at `config_path`.
+///
+/// Existing files are copied to `<file>.rururu-backup` the first time they are
+/// touched, so the user's original config can always be restored. Returns the
+/// files that were written; nothing is done if the app has no settings or no
+/// config path, except that Blender's startup script from an earlier
+/// activation is removed so it stops applying its settings.
+pub fn apply_app_settings(app: &AppConfig) -> Result<Vec<PathBuf>> {
+    let Some(ref config_path) = app.config_path else {
+        return Ok(Vec::new());
+    };
+    let Some(base) = expand_home(config_path) else {
+        return Err(WorkflowError::Config(format!(
+            "No home directory to expand {}",
+            config_path.display()
+        )));
+    };
+    if app.settings.is_empty() {
+        if ConfigAdapter::for_app(app) == ConfigAdapter::Blender {
+            for dir in blender_script_dirs(&base) {
+                let file = dir.join(BLENDER_SCRIPT);
+                if file.exists() {
+                    fs::remove_file(file)?;
+                }
+            }
+        }
+        return Ok(Vec::new());
+    }
+
+    let mut settings: Vec<(&String, &String)> = app.settings.iter().collect();
+    settings.sort();
+
+    let mut written = Vec::new();
+
+    match ConfigAdapter::for_app(app) {
+        ConfigAdapter::Blender => {
+            let script = blender_startup_script(&settings);
+            for dir in blender_script_dirs(&base) {
+                let file = dir.join(BLENDER_SCRIPT);
+                write_with_backup(&file, &script)?;
+                written.push(file);
+            }
+        }
+        ConfigAdapter::Ardour => {
+            let file = config_file(app, base, "config")?;
+            let existing = fs::read_to_string(&file).unwrap_or_default();
+            write_with_backup(&file, &ardour_config(&existing, &settings))?;
+            written.push(file);
+        }
+        ConfigAdapter::PipeWire => {
+            let file = base.join("pipewire.conf.d").join("99-rururu-workflow.conf");
+            write_with_backup(&file, &pipewire_drop_in(&settings))?;
+            written.push(file);
+        }
+        ConfigAdapter::KeyValue => {
+            let file = config_file(app, base, &format!("{}rc", app.executable))?;
+            let existing = fs::read_to_string(&file).unwrap_or_default();
+            write_with_backup(&file, &key_value_config(&existing, &settings))?;
+            written.push(file);
+        }
+    }
+
+    Ok(written)
+}
+
+/// `name` in the app's config directory, created if need be, or `base`
+/// itself when it names the config file
+fn config_file(app: &AppConfig, base: PathBuf, name: &str) -> Result<PathBuf> {
+    if app.config_dir || base.is_dir() {
+        fs::create_dir_all(&base)?;
+        Ok(base.join(name))
+    } else {
+        Ok(base)
+    }
+}
+
+fn write_with_backup(file: &Path, contents: &str) -> Result<()> {
+    if file.exists() {
+        let mut backup = file.as_os_str().to_owned();
+        backup.push(".rururu-backup");
+        let backup = PathBuf::from(backup);
+        if !backup.exists() {
+            fs::copy(file, &backup)?;
+        }
+    }
+
+    if let Some(parent) = file.parent() {
+        fs::create_dir_all(parent)?;
+    }
+    fs::write(file, contents)?;
+    Ok(())
+}
+
+/// Blender keeps per-version user dirs (`~/.config/blender/4.1/`); write the
+/// startup script into each one, or into an unversioned dir if none exist yet.
+fn blender_script_dirs(base: &Path) -> Vec<PathBuf> {
+    let mut dirs: Vec<PathBuf> = fs::read_dir(base)
+        .map(|entries| {
+            entries
+                .flatten()
+                .map(|e| e.path())
+                .filter(|p| {
+                    p.is_dir()
+                        && p.file_name()
+                            .and_then(|n| n.to_str())
+                            .map(|n| n.chars().all(|c| c.is_ascii_digit() || c == '.'))
+                            .unwrap_or(false)
+                })
+                .map(|p| p.join("scripts").join("startup"))
+                .collect()
+        })
+        .unwrap_or_default();
+
+    if dirs.is_empty() {
+        dirs.push(base.join("scripts").join("startup"));
+    }
+    dirs.sort();
+    dirs
+}
+
+fn python_value(value: &str) -> String {
+    match value {
+        "true" | "True" => "True".to_string(),
+        "false" | "False" => "False".to_string(),
+        v if v.parse::<f64>().is_ok() => v.to_string(),
+        v => format!("{:?}", v),
+    }
+}
+
+fn blender_startup_script(settings: &[(&String, &String)]) -> String {
+    let mut script = String::from(
+        "# Generated by rururu-workflow. Applies workflow settings to the preferences\n\
+         # and to every loaded scene.\n\
+         import bpy\n\
+         from bpy.app.handlers import persistent\n\n\n\
+         @persistent\n\
+         def _rururu_apply(*_args):\n\
+         \x20   for scene in bpy.data.scenes:\n",
+    );
+
+    // `preferences.*` keys go to the user preferences, the rest to scenes
+    let (preferences, scene): (Vec<_>, Vec<_>) = settings
+        .iter()
+        .partition(|(key, _)| key.starts_with("preferences."));
+
+    for (key, value) in &scene {
+        script.push_str(&format!(
+            "        try:\n            scene.{} = {}\n        except (AttributeError, TypeError):\n            pass\n",
+            key,
+            python_value(value)
+        ));
+    }
+
+    if scene.is_empty() {
+        script.push_str("        pass\n");
+    }
+
+    script.push_str("\n\ndef register():\n");
+    for (key, value) in &preferences {
+        script.push_str(&format!(
+            "    try:\n        bpy.context.{} = {}\n    except (AttributeError, TypeError):\n        pass\n",
+            key,
+            python_value(value)
+        ));
+    }
+    script.push_str(
+        "    bpy.app.handlers.load_post.append(_rururu_apply)\n\n\n\
+         def unregister():\n\
+         \x20   bpy.app.handlers.load_post.remove(_rururu_apply)\n",
+    );
+    script
+}
+
+fn xml_escape(value: &str) -> String {
+    value
+        .replace('&', "&amp;")
+        .replace('"', "&quot;")
+        .replace('<', "&lt;")
+        .replace('>', "&gt;")
+}
+
+fn ardour_config(existing: &str, settings: &[(&String, &String)]) -> String {
+    let mut config = if existing.contains("</Config>") {
+        existing.to_string()
+    } else {
+        "<?xml version=\"1.0\" encoding=\"UTF-8\"?>\n<Ardour>\n  <Config>\n  </Config>\n</Ardour>\n"
+            .to_string()
+    };
+
+    for (key, value) in settings {
+        let option = format!(
+            "<Option name=\"{}\" value=\"{}\"/>",
+            xml_escape(key),
+            xml_escape(value)
+        );
+        let marker = format!("<Option name=\"{}\"", xml_escape(key));
+
+        if let Some(start) = config.find(&marker) {
+            let end = config[start..]
+                .find("/>")
+                .map(|i| start + i + 2)
+                .unwrap_or(config.len());
+            config.replace_range(start..end, &option);
+        } else if let Some(pos) = config.find("</Config>") {
+            let line_start = config[..pos].rfind('\n').map(|i| i + 1).unwrap_or(pos);
+            config.insert_str(line_start, &format!("    {}\n", option));
+        }
+    }
+
+    config
+}
+
+fn pipewire_drop_in(settings: &[(&String, &String)]) -> String {
+    let mut conf = String::from("# Generated by rururu-workflow\ncontext.properties = {\n");
+    for (key, value) in settings {
+        conf.push_str(&format!("    {} = {}\n", key, value));
+    }
+    conf.push_str("}\n");
+    conf
+}
+
+fn key_value_config(existing: &str, settings: &[(&String, &String)]) -> String {
+    let mut lines: Vec<String> = existing.lines().map(String::from).collect();
+
+    for (key, value) in settings {
+        let entry = format!("{}={}", key, value);
+        let found = lines.iter_mut().find(|line| {
+            line.split_once('=')
+                .map(|(k, _)| k.trim() == key.as_str())
+                .unwrap_or(false)
+        });
+
+        match found {
+            Some(line) => *line = entry,
+            None => lines.push(entry),
+        }
+    }
+
+    let mut config = lines.join("\n");
+    config.push('\n');
+    config
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::profiles::WorkflowType;
+
+    fn blender_in(dir: &Path) -> AppConfig {
+        let profile = WorkflowProfile::get_profile(WorkflowType::ThreeDArtist);
+        let mut blender = profile
+            .applications
+            .into_iter()
+            .find(|app| app.executable == "blender")
+            .unwrap();
+        blender.config_path = Some(dir.to_path_buf());
+        blender
+    }
+
+    #[test]
+    fn test_cpu_fallback_rewrites_earlier_script() {
+        let tmp = tempfile::tempdir().unwrap();
+        let dir = tmp.path();
+        fs::create_dir_all(dir.join("4.1")).unwrap();
+        let script = dir.join("4.1/scripts/startup").join(BLENDER_SCRIPT);
+
+        apply_app_settings(&blender_in(dir)).unwrap();
+        assert!(fs::read_to_string(&script).unwrap().contains("\"GPU\""));
+
+        let mut profile = WorkflowProfile::get_profile(WorkflowType::ThreeDArtist);
+        assert_eq!(profile.disable_gpu_rendering(), ["Blender"]);
+        let mut blender = blender_in(dir);
+        blender.settings = profile
+            .applications
+            .iter()
+            .find(|app| app.executable == "blender")
+            .unwrap()
+            .settings
+            .clone();
+        apply_app_settings(&blender).unwrap();
+        let rewritten = fs::read_to_string(&script).unwrap();
+        assert!(rewritten.contains("\"CPU\"") && !rewritten.contains("\"GPU\""));
+
+        // No settings at all leaves no script behind
+        blender.settings.clear();
+        apply_app_settings(&blender).unwrap();
+        assert!(!script.exists());
+    }
+
+    fn tool_in(config_path: PathBuf, config_dir: bool) -> AppConfig {
+        AppConfig {
+            name: "Tool".to_string(),
+            executable: "tool".to_string(),
+            package: "tool".to_string(),
+            flatpak_id: None,
+            config_path: Some(config_path),
+            config_dir,
+            priority: crate::profiles::AppPriority::Optional,
+            settings: [("threads".to_string(), "8".to_string())].into(),
+        }
+    }
+
+    #[test]
+    fn test_config_path_file_or_dir() {
+        let dir = tempfile::tempdir().unwrap();
+
+        // An existing directory is one whatever the profile says
+        let app = tool_in(dir.path().to_path_buf(), false);
+        assert_eq!(
+            apply_app_settings(&app).unwrap(),
+            [dir.path().join("toolrc")]
+        );
+
+        // An extensionless file is written as it is named
+        let file = dir.path().join("config");
+        let app = tool_in(file.clone(), false);
+        assert_eq!(apply_app_settings(&app).unwrap(), vec![file.clone()]);
+        assert!(fs::read_to_string(&file).unwrap().contains("threads"));
+    }
+
+    #[test]
+    fn test_config_dir_created_on_first_install() {
+        let dir = tempfile::tempdir().unwrap();
+        let config = dir.path().join(".config/tool");
+
+        let app = tool_in(config.clone(), true);
+        assert_eq!(apply_app_settings(&app).unwrap(), [config.join("toolrc")]);
+        assert!(config.is_dir());
+
+        let mut ardour = WorkflowProfile::get_profile(WorkflowType::AudioProducer)
+            .applications
+            .into_iter()
+            .find(|app| app.executable.starts_with("ardour"))
+            .unwrap();
+        assert!(ardour.config_dir);
+        let config = dir.path().join(".config/ardour8");
+        ardour.config_path = Some(config.clone());
+        ardour.settings = [("periods".to_string(), "2".to_string())].into();
+        assert_eq!(
+            apply_app_settings(&ardour).unwrap(),
+            [config.join("config")]
+        );
+        assert!(config.is_dir());
+    }
+}
@@ -0,0 +1,183 @@
+//! Short micro-benchmarks to check whether a workflow's performance
+//! settings made a difference.
+//!
+//! The CPU benchmark runs a fixed amount of integer work on every hardware
+//! thread and takes the median of a few rounds, so repeated runs on an idle
+//! machine land within a few percent of each other. The GPU benchmark runs a
+//! fixed set of glmark2 scenes off-screen when glmark2 is installed.
+//!
+//! Results are appended to `workflow-benchmarks.jsonl` next to the activation
+//! log, each with the settings snapshot it ran under. A run marked as a
+//! baseline is what later runs are compared against.
+
+use crate::history::{self, Snapshot};
+use crate::{Result, WorkflowError, WorkflowType};
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+use std::fs::{self, OpenOptions};
+use std::io::Write;
+use std::path::PathBuf;
+use std::process::Command;
+use std::time::Instant;
+
+/// Rounds of CPU work; the median is the score
+const CPU_ROUNDS: usize = 5;
+
+/// Mixing steps per thread per round, about a tenth of a second on a
+/// current desktop core
+const CPU_STEPS: u64 = 100_000_000;
+
+/// The glmark2 scenes run, each for a fixed time
+const GLMARK2_SCENES: &[&str] = &[
+    "build:use-vbo=true:duration=3",
+    "texture:texture-filter=linear:duration=3",
+    "shading:shading=phong:duration=3",
+    "terrain:duration=3",
+];
+
+/// Deltas smaller than this are within run-to-run noise
+pub const NOISE_PERCENT: f64 = 3.0;
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct BenchmarkResult {
+    pub timestamp: DateTime<Utc>,
+    /// Active workflow when the benchmark ran
+    pub workflow: WorkflowType,
+    /// Whether later runs are compared against this one
+    pub baseline: bool,
+    /// Million mixing steps per second across all threads
+    pub cpu_score: f64,
+    pub cpu_threads: usize,
+    /// glmark2 score; `None` when glmark2 isn't installed or failed
+    pub gpu_score: Option<u64>,
+    /// System settings the benchmark ran under
+    pub settings: Snapshot,
+}
+
+/// Change from a baseline, in percent
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Delta {
+    pub cpu_percent: f64,
+    /// `None` unless both runs have a GPU score
+    pub gpu_percent: Option<f64>,
+}
+
+impl BenchmarkResult {
+    /// Run the CPU and GPU benchmarks under the current settings
+    pub fn run(workflow: WorkflowType, baseline: bool) -> Self {
+        let (cpu_score, cpu_threads) = cpu_benchmark();
+        Self {
+            timestamp: Utc::now(),
+            workflow,
+            baseline,
+            cpu_score,
+            cpu_threads,
+            gpu_score: gpu_benchmark(),
+            settings: history::system_snapshot(),
+        }
+    }
+
+    pub fn compare(&self, baseline: &BenchmarkResult) -> Delta {
+        let percent = |now: f64, then: f64| (now - then) / then * 100.0;
+        Delta {
+            cpu_percent: percent(self.cpu_score, baseline.cpu_score),
+            gpu_percent: self
+                .gpu_score
+                .zip(baseline.gpu_score)
+                .filter(|&(_, then)| then > 0)
+                .map(|(now, then)| percent(now as f64, then as f64)),
+        }
+    }
+
+    /// Append to the benchmark log
+    pub fn append(&self) -> Result<()> {
+        let path = log_path().ok_or_else(|| {
+            WorkflowError::Config("No home directory for the benchmark log".into())
+        })?;
+        if let Some(parent) = path.parent() {
+            fs::create_dir_all(parent)?;
+        }
+
+        let line = serde_json::to_string(self).map_err(|e| WorkflowError::Config(e.to_string()))?;
+        let mut file = OpenOptions::new().create(true).append(true).open(&path)?;
+        writeln!(file, "{}", line)?;
+        Ok(())
+    }
+}
+
+pub fn log_path() -> Option<PathBuf> {
+    Some(history::log_path()?.with_file_name("workflow-benchmarks.jsonl"))
+}
+
+/// Every logged benchmark, oldest first; empty if none ran yet
+pub fn read_results() -> Result<Vec<BenchmarkResult>> {
+    let Some(path) = log_path() else {
+        return Ok(Vec::new());
+    };
+    let content = match fs::read_to_string(&path) {
+        Ok(content) => content,
+        Err(e) if e.kind() == std::io::ErrorKind::NotFound => return Ok(Vec::new()),
+        Err(e) => return Err(e.into()),
+    };
+
+    Ok(content
+        .lines()
+        .filter_map(|line| serde_json::from_str(line).ok())
+        .collect())
+}
+
+/// The most recent baseline, if one was captured
+pub fn latest_baseline() -> Result<Option<BenchmarkResult>> {
+    Ok(read_results()?.into_iter().rev().find(|r| r.baseline))
+}
+
+/// Median throughput over [`CPU_ROUNDS`], with the thread count used
+fn cpu_benchmark() -> (f64, usize) {
+    let threads = std::thread::available_parallelism().map_or(1, |n| n.get());
+
+    let mut rounds: Vec<f64> = (0..CPU_ROUNDS)
+        .map(|_| {
+            let start = Instant::now();
+            std::thread::scope(|scope| {
+                for seed in 0..threads as u64 {
+                    scope.spawn(move || std::hint::black_box(mix(seed + 1, CPU_STEPS)));
+                }
+            });
+            let steps = CPU_STEPS as f64 * threads as f64;
+            steps / start.elapsed().as_secs_f64() / 1_000_000.0
+        })
+        .collect();
+    rounds.sort_by(f64::total_cmp);
+    (rounds[CPU_ROUNDS / 2], threads)
+}
+
+/// xorshift with a multiply folded in: integer ALU work with a serial
+/// dependency, so the compiler can't vectorize it away
+fn mix(mut state: u64, steps: u64) -> u64 {
+    let mut acc = 0u64;
+    for _ in 0..steps {
+        state ^= state << 13;
+        state ^= state >> 7;
+        state ^= state << 17;
+        acc = acc.wrapping_mul(0x9E37_79B9_7F4A_7C15).wrapping_add(state);
+    }
+    acc
+}
+
+/// glmark2's score over [`GLMARK2_SCENES`], off-screen so no window opens
+fn gpu_benchmark() -> Option<u64> {
+    let mut command = Command::new("glmark2");
+    command.arg("--off-screen");
+    for scene in GLMARK2_SCENES {
+        command.args(["-b", scene]);
+    }
+    let output = command.output().ok()?;
+    if !output.status.success() {
+        return None;
+    }
+
+    String::from_utf8_lossy(&output.stdout)
+        .lines()
+        .find_map(|line| line.trim().strip_prefix("glmark2 Score:"))
+        .and_then(|score| score.trim().parse().ok())
+}
@@ -1,17 +1,40 @@
-use rururu_workflows::apps::{install_app, is_app_installed, list_installed_creative_apps};
-use rururu_workflows::system::{apply_system_settings, get_system_info};
+use nix::sys::termios::{self, LocalFlags, SetArg, SpecialCharacterIndices};
+use rururu_workflows::apps::{
+    apply_app_settings, install_app, is_app_installed, launch_app_in_workflow,
+    list_installed_creative_apps,
+};
+use rururu_workflows::benchmark::{self, BenchmarkResult};
+use rururu_workflows::color;
+use rururu_workflows::conflicts::running_processes;
+use rururu_workflows::environment;
+use rururu_workflows::history::{self, ActivationRecord};
+use rururu_workflows::migrate::{self, ConflictPolicy, MigrationSource};
+use rururu_workflows::scratch;
+use rururu_workflows::system::{apply_system_settings, get_system_info, validate_gpu_compute};
 use rururu_workflows::{WorkflowConfig, WorkflowProfile, WorkflowType};
 use std::env;
+use std::io::{self, BufRead, IsTerminal, Read, Write};
 
 fn main() {
     let args: Vec<String> = env::args().collect();
 
     if args.len() < 2 {
-        print_usage();
+        if io::stdin().is_terminal() && io::stdout().is_terminal() {
+            run_menu();
+        } else {
+            print_usage();
+        }
         return;
     }
 
     match args[1].as_str() {
+        "menu" => {
+            if !io::stdin().is_terminal() {
+                eprintln!("rururu-workflow menu needs a terminal");
+                return;
+            }
+            run_menu();
+        }
         "list" => list_workflows(),
         "info" => {
             if args.len() < 3 {
@@ -22,12 +45,19 @@ fn main() {
         }
         "activate" => {
             if args.len() < 3 {
-                println!("Usage: rururu-workflow activate <workflow>");
+                println!(
+                    "Usage: rururu-workflow activate <workflow> [--force] [--dry-run] [--benchmark]"
+                );
                 return;
             }
-            activate_workflow(&args[2]);
+            let force = args[3..].iter().any(|a| a == "--force");
+            let dry_run = args[3..].iter().any(|a| a == "--dry-run");
+            let bench = args[3..].iter().any(|a| a == "--benchmark");
+            activate_workflow(&args[2], force, dry_run, bench);
         }
+        "deactivate" => deactivate_workflow(),
         "status" => show_status(),
+        "reapply" => reapply_workflow(),
         "apps" => list_apps(),
         "install" => {
             if args.len() < 3 {
@@ -36,7 +66,46 @@ fn main() {
             }
             install_workflow_apps(&args[2]);
         }
+        "launch" => {
+            if args.len() < 3 {
+                println!("Usage: rururu-workflow launch <app>");
+                return;
+            }
+            launch_in_active_workflow(&args[2]);
+        }
         "system" => show_system_info(),
+        "history" => {
+            let json = args[2..].iter().any(|a| a == "--json");
+            let limit = args[2..].iter().find_map(|a| a.parse().ok()).unwrap_or(10);
+            show_history(limit, json);
+        }
+        "benchmark" => {
+            let baseline = args[2..].iter().any(|a| a == "--baseline");
+            let json = args[2..].iter().any(|a| a == "--json");
+            run_benchmark(baseline, json);
+        }
+        "migrate" => {
+            let flag_value = |flag: &str| {
+                args.iter()
+                    .position(|a| a == flag)
+                    .and_then(|i| args.get(i + 1))
+                    .cloned()
+            };
+            let user = flag_value("--user");
+            let path = args[2..]
+                .iter()
+                .find(|a| !a.starts_with("--") && Some(*a) != user.as_ref())
+                .cloned();
+            let policy = if args.iter().any(|a| a == "--overwrite") {
+                ConflictPolicy::Overwrite
+            } else if args.iter().any(|a| a == "--keep-existing") {
+                ConflictPolicy::Skip
+            } else {
+                ConflictPolicy::Backup
+            };
+            let apply = args[2..].iter().any(|a| a == "--apply");
+            migrate_settings(path, user, apply, policy);
+        }
         _ => print_usage(),
     }
 }
@@ -47,13 +116,28 @@ fn print_usage() {
     println!("Usage: rururu-workflow <command> [args]");
     println!();
     println!("Commands:");
+    println!("  menu              Pick a workflow to activate (default on a terminal)");
     println!("  list              List available workflows");
     println!("  info <workflow>   Show workflow details");
-    println!("  activate <name>   Activate a workflow");
-    println!("  status            Show current workflow status");
+    println!("  activate <name>   Activate a workflow (--force skips the conflict check,");
+    println!("                    --dry-run only shows what would change, --benchmark");
+    println!("                    measures before and after)");
+    println!("  deactivate        Return to the General workflow");
+    println!("  status            Show current workflow status and drift");
+    println!("  reapply           Re-apply the active workflow's settings");
     println!("  apps              List installed creative apps");
     println!("  install <name>    Install workflow applications");
+    println!("  launch <app>      Start an app with the active workflow's environment");
+    println!("                    and priority");
     println!("  system            Show system information");
+    println!("  history [n]       Show the last n activations and what they changed");
+    println!("                    (--json prints the raw log entries)");
+    println!("  benchmark         Measure CPU and GPU performance against the baseline");
+    println!("                    (--baseline records a new baseline, --json prints the result)");
+    println!("  migrate [path]    List app settings to import from an old system or backup");
+    println!("                    (--apply copies them, --user picks the old account,");
+    println!("                    --keep-existing or --overwrite instead of backing up");
+    println!("                    files that are already here)");
 }
 
 fn list_workflows() {
@@ -98,15 +182,28 @@ fn show_workflow_info(name: &str) {
         "  Realtime Audio: {}",
         profile.system_settings.realtime_audio
     );
+    if let Some(ref limits) = profile.system_settings.resource_limits {
+        for line in limits.summary() {
+            println!("  Resource Limits: {}", line);
+        }
+    }
     println!();
     println!("Color Config:");
     println!("  Working Space: {}", profile.color_config.working_space);
     if let Some(ref ocio) = profile.color_config.ocio_config {
         println!("  OCIO Config: {}", ocio.display());
     }
+    if let Some(dir) = profile
+        .scratch_config
+        .as_ref()
+        .and_then(scratch::scratch_dir)
+    {
+        println!();
+        println!("Scratch: {}", dir.display());
+    }
 }
 
-fn activate_workflow(name: &str) {
+fn activate_workflow(name: &str, force: bool, dry_run: bool, bench: bool) {
     let workflow_type = match name.to_lowercase().as_str() {
         "video" | "videoeditor" => WorkflowType::VideoEditor,
         "3d" | "3dartist" => WorkflowType::ThreeDArtist,
@@ -117,40 +214,539 @@ fn activate_workflow(name: &str) {
         _ => WorkflowType::General,
     };
 
-    let profile = WorkflowProfile::get_profile(workflow_type);
+    if dry_run {
+        print_plan(&profile_for_machine(workflow_type));
+        return;
+    }
+
+    activate(workflow_type, force, bench);
+}
+
+fn activate(workflow_type: WorkflowType, force: bool, bench: bool) {
+    if !force && !confirm_conflicts(&WorkflowProfile::get_profile(workflow_type)) {
+        println!("Activation cancelled.");
+        return;
+    }
+
+    if bench {
+        println!("Measuring a baseline before activation...");
+        run_benchmark(true, false);
+        println!();
+    }
+    apply_workflow(workflow_type);
+    if bench {
+        println!();
+        println!("Measuring with the new settings...");
+        run_benchmark(false, false);
+    }
+}
+
+fn print_plan(profile: &WorkflowProfile) {
+    println!("Activating {} would:", profile.name);
+    for (i, step) in profile.activation_plan().iter().enumerate() {
+        println!("  {}. {}", i + 1, step);
+    }
+}
+
+/// List the workflows with their readiness on this machine, let the user
+/// pick one and activate it after showing the plan.
+fn run_menu() {
+    let info = get_system_info();
+    let entries: Vec<_> = WorkflowType::all()
+        .iter()
+        .map(|t| (*t, WorkflowProfile::get_profile(*t)))
+        // Types without a profile of their own fall back to General
+        .filter(|(t, profile)| profile.workflow_type == *t)
+        .map(|(_, profile)| {
+            let readiness = profile.readiness(&info);
+            (profile, readiness)
+        })
+        .collect();
+
+    let lines: Vec<String> = entries
+        .iter()
+        .map(|(profile, readiness)| {
+            format!(
+                "{:<15} {:>3}%  {}",
+                profile.name, readiness.score, profile.description
+            )
+        })
+        .collect();
+
+    println!("Choose a workflow (arrows or j/k, Enter to select, q to quit)");
+    println!("Readiness is scored against this machine's hardware and installed apps.");
+    println!();
+    let Some(index) = select(&lines) else {
+        println!("Nothing activated.");
+        return;
+    };
+
+    let (profile, readiness) = &entries[index];
+    println!();
+    println!("{}: {}% ready", profile.name, readiness.score);
+    for shortfall in &readiness.shortfalls {
+        println!("  - {}", shortfall);
+    }
+    println!();
+    print_plan(profile);
+    println!();
+
+    if !ask(&format!("Activate {}?", profile.name)) {
+        println!("Activation cancelled.");
+        return;
+    }
+    activate(profile.workflow_type, false, false);
+}
+
+enum Key {
+    Up,
+    Down,
+    Number(usize),
+    Enter,
+    Quit,
+    Other,
+}
+
+/// Arrow-key selector over `items`; `None` if the user quits
+fn select(items: &[String]) -> Option<usize> {
+    let _raw = UnbufferedInput::enable()
+        .map_err(|e| eprintln!("Failed to set up the terminal: {}", e))
+        .ok()?;
+
+    let mut selected = 0;
+    draw_items(items, selected, false);
+    loop {
+        match read_key()? {
+            Key::Up => selected = selected.checked_sub(1).unwrap_or(items.len() - 1),
+            Key::Down => selected = (selected + 1) % items.len(),
+            Key::Number(n) if (1..=items.len()).contains(&n) => selected = n - 1,
+            Key::Enter => return Some(selected),
+            Key::Quit => return None,
+            Key::Number(_) | Key::Other => continue,
+        }
+        draw_items(items, selected, true);
+    }
+}
+
+fn draw_items(items: &[String], selected: usize, redraw: bool) {
+    let mut out = io::stdout().lock();
+    if redraw {
+        // Back to the first item
+        let _ = write!(out, "\x1b[{}A", items.len());
+    }
+    for (i, item) in items.iter().enumerate() {
+        if i == selected {
+            let _ = writeln!(out, "\r\x1b[2K> \x1b[7m{}. {}\x1b[0m", i + 1, item);
+        } else {
+            let _ = writeln!(out, "\r\x1b[2K  {}. {}", i + 1, item);
+        }
+    }
+    let _ = out.flush();
+}
+
+fn read_key() -> Option<Key> {
+    let mut stdin = io::stdin().lock();
+    let mut byte = || {
+        let mut b = [0u8; 1];
+        stdin.read_exact(&mut b).ok().map(|_| b[0])
+    };
+
+    Some(match byte()? {
+        b'\r' | b'\n' => Key::Enter,
+        // Ctrl-C and Ctrl-D arrive as bytes while ISIG is off
+        b'q' | 0x03 | 0x04 => Key::Quit,
+        b'k' => Key::Up,
+        b'j' => Key::Down,
+        d @ b'1'..=b'9' => Key::Number((d - b'0') as usize),
+        0x1b => match byte()? {
+            b'[' => match byte()? {
+                b'A' => Key::Up,
+                b'B' => Key::Down,
+                _ => Key::Other,
+            },
+            // A lone Escape
+            _ => Key::Quit,
+        },
+        _ => Key::Other,
+    })
+}
+
+/// Puts the terminal in non-canonical, no-echo mode until dropped, so keys
+/// arrive one at a time
+struct UnbufferedInput {
+    original: termios::Termios,
+}
+
+impl UnbufferedInput {
+    fn enable() -> nix::Result<Self> {
+        let original = termios::tcgetattr(io::stdin())?;
+
+        let mut raw = original.clone();
+        raw.local_flags
+            .remove(LocalFlags::ICANON | LocalFlags::ECHO | LocalFlags::ISIG);
+        raw.control_chars[SpecialCharacterIndices::VMIN as usize] = 1;
+        raw.control_chars[SpecialCharacterIndices::VTIME as usize] = 0;
+        termios::tcsetattr(io::stdin(), SetArg::TCSANOW, &raw)?;
+
+        Ok(Self { original })
+    }
+}
+
+impl Drop for UnbufferedInput {
+    fn drop(&mut self) {
+        let _ = termios::tcsetattr(io::stdin(), SetArg::TCSANOW, &self.original);
+    }
+}
+
+/// Warn about running processes the profile would deprioritize. Returns
+/// whether to go ahead; without a terminal to ask on, conflicts abort.
+fn confirm_conflicts(profile: &WorkflowProfile) -> bool {
+    let conflicts = profile.detect_conflicts(&running_processes());
+    if conflicts.is_empty() {
+        return true;
+    }
+
+    println!("Activating {} may slow down running work:", profile.name);
+    for conflict in &conflicts {
+        println!("  ! {}", conflict);
+    }
+
+    if !io::stdin().is_terminal() {
+        println!("Re-run with --force to activate anyway.");
+        return false;
+    }
+
+    ask("Continue anyway?")
+}
+
+fn ask(question: &str) -> bool {
+    print!("{} [y/N] ", question);
+    let _ = io::stdout().flush();
+    let mut answer = String::new();
+    if io::stdin().lock().read_line(&mut answer).is_err() {
+        return false;
+    }
+    matches!(answer.trim().to_lowercase().as_str(), "y" | "yes")
+}
+
+fn reapply_workflow() {
+    match WorkflowConfig::load() {
+        Ok(config) => apply_workflow(config.active_workflow),
+        Err(e) => eprintln!("Failed to load config: {}", e),
+    }
+}
+
+fn deactivate_workflow() {
+    if let Err(e) = environment::clear() {
+        eprintln!("Warning: Failed to remove workflow environment: {}", e);
+    }
+    if let Err(e) = color::deactivate() {
+        eprintln!("Warning: Failed to reset color settings: {}", e);
+    }
+
+    match WorkflowConfig::load() {
+        Ok(mut config) => {
+            config.set_active_workflow(WorkflowType::General);
+            if let Err(e) = config.save() {
+                eprintln!("Warning: Failed to save config: {}", e);
+            }
+        }
+        Err(e) => eprintln!("Failed to load config: {}", e),
+    }
+
+    println!("Workflow deactivated. Log out and back in to clear its environment.");
+}
+
+/// The profile as it applies here: when GPU compute is broken, say what is
+/// wrong and how to fix it, and leave rendering on the CPU
+fn profile_for_machine(workflow_type: WorkflowType) -> WorkflowProfile {
+    let mut profile = WorkflowProfile::get_profile(workflow_type);
+    if !profile.uses_gpu_compute() {
+        return profile;
+    }
+
+    let compute = validate_gpu_compute();
+    for problem in &compute.problems {
+        eprintln!("Warning: {}", problem);
+    }
+    if let Some(fix) = compute.recommendation() {
+        eprintln!("  To fix: {}", fix);
+    }
+    if !compute.supports_gpu_rendering() {
+        for app in profile.disable_gpu_rendering() {
+            eprintln!("  {} will render on the CPU", app);
+        }
+    }
+    profile
+}
+
+fn apply_workflow(workflow_type: WorkflowType) {
+    let profile = profile_for_machine(workflow_type);
+    let mut record = ActivationRecord::new(workflow_type);
 
     println!("Activating workflow: {}", profile.name);
 
     // Apply system settings
+    let before = history::system_snapshot();
     if let Err(e) = apply_system_settings(&profile.system_settings) {
-        eprintln!("Warning: Failed to apply system settings: {}", e);
+        warn(
+            &mut record,
+            format!("Failed to apply system settings: {}", e),
+        );
     }
 
-    // Set environment variables
-    for (key, value) in &profile.environment {
-        println!("  Setting {} = {}", key, value);
-        env::set_var(key, value);
+    // Scratch space has to exist before TMPDIR and friends point into it,
+    // and before app settings so those can carry its cache directories
+    let mut prepared = None;
+    if let Some(ref config) = profile.scratch_config {
+        match scratch::prepare(config) {
+            Ok(Some(scratch)) => {
+                println!("  Scratch directory: {}", scratch.dir.display());
+                for (app, dir) in &scratch.app_dirs {
+                    if app == scratch::OCIO_CACHE {
+                        let Some(ref ocio) = profile.color_config.ocio_config else {
+                            continue;
+                        };
+                        match scratch::cache_ocio_config(ocio, dir) {
+                            Ok(copy) => println!("  OCIO config cached at {}", copy.display()),
+                            Err(e) => warn(
+                                &mut record,
+                                format!("Failed to cache the OCIO config: {}", e),
+                            ),
+                        }
+                    } else if !scratch::cache_setting_known(&profile, app) {
+                        println!(
+                            "  Point the {} cache at {} in its preferences",
+                            app,
+                            dir.display()
+                        );
+                    }
+                }
+                prepared = Some(scratch);
+            }
+            Ok(None) => warn(
+                &mut record,
+                "No local drive found for scratch storage".to_string(),
+            ),
+            Err(e) => warn(
+                &mut record,
+                format!("Failed to create the scratch directory: {}", e),
+            ),
+        }
     }
 
-    // Set OCIO config if specified
-    if let Some(ref ocio_path) = profile.color_config.ocio_config {
-        if ocio_path.exists() {
-            env::set_var("OCIO", ocio_path);
-            println!("  OCIO config: {}", ocio_path.display());
+    // Write per-app settings for installed apps
+    for app in profile.applications.iter().filter(|a| is_app_installed(a)) {
+        let app = match prepared {
+            Some(ref scratch) => scratch::with_cache_setting(app, scratch),
+            None => app.clone(),
+        };
+        match apply_app_settings(&app) {
+            Ok(files) => {
+                for file in &files {
+                    println!("  Configured {}: {}", app.name, file.display());
+                }
+                if !files.is_empty() {
+                    record.apps_configured.push((app.name.clone(), files));
+                }
+            }
+            Err(e) => warn(
+                &mut record,
+                format!("Failed to configure {}: {}", app.name, e),
+            ),
         }
     }
 
+    // Conversions outside any app follow the workflow's rendering intent
+    if let Err(e) = color::activate(&profile) {
+        warn(
+            &mut record,
+            format!("Failed to apply color settings: {}", e),
+        );
+    }
+
+    // Persist environment variables for the login session
+    record.environment = environment::profile_environment(&profile);
+    for (key, value) in &record.environment {
+        println!("  Setting {} = {}", key, value);
+    }
+    match environment::persist(&profile) {
+        Ok(Some(path)) => {
+            println!("  Environment written to {}", path.display());
+            println!("  Log out and back in for running apps to pick it up.");
+        }
+        Ok(None) => {}
+        Err(e) => warn(&mut record, format!("Failed to persist environment: {}", e)),
+    }
+    record.record_settings(before, history::system_snapshot());
+
     // Save config
-    if let Ok(mut config) = WorkflowConfig::load() {
-        config.set_active_workflow(workflow_type);
-        if let Err(e) = config.save() {
-            eprintln!("Warning: Failed to save config: {}", e);
+    match WorkflowConfig::load() {
+        Ok(mut config) => {
+            config.set_active_workflow(workflow_type);
+            if let Err(e) = config.save() {
+                warn(&mut record, format!("Failed to save config: {}", e));
+            }
         }
+        Err(e) => warn(&mut record, format!("Failed to load config: {}", e)),
+    }
+
+    if let Err(e) = record.append() {
+        eprintln!("Warning: Failed to write the activation log: {}", e);
     }
 
     println!("Workflow activated successfully!");
 }
 
+/// Print a non-fatal activation problem and keep it for the log
+fn warn(record: &mut ActivationRecord, message: String) {
+    eprintln!("Warning: {}", message);
+    record.errors.push(message);
+}
+
+fn show_history(limit: usize, json: bool) {
+    let records = match WorkflowConfig::activation_history(limit) {
+        Ok(records) => records,
+        Err(e) => {
+            eprintln!("Failed to read the activation log: {}", e);
+            return;
+        }
+    };
+
+    if json {
+        for record in &records {
+            println!("{}", serde_json::to_string(record).unwrap_or_default());
+        }
+        return;
+    }
+    if records.is_empty() {
+        println!("No workflow activations recorded yet.");
+        return;
+    }
+
+    for record in &records {
+        println!(
+            "{}  {}",
+            record
+                .timestamp
+                .with_timezone(&chrono::Local)
+                .format("%Y-%m-%d %H:%M:%S"),
+            record.workflow.name()
+        );
+        let changes: Vec<_> = record.settings.iter().filter(|c| c.changed()).collect();
+        if changes.is_empty() && record.environment.is_empty() && record.apps_configured.is_empty()
+        {
+            println!("  No changes");
+        }
+        for change in changes {
+            println!(
+                "  {}: {} -> {}",
+                change.setting,
+                change.before.as_deref().unwrap_or("unavailable"),
+                change.after.as_deref().unwrap_or("unavailable")
+            );
+        }
+        for (key, value) in &record.environment {
+            println!("  Set {} = {}", key, value);
+        }
+        for (app, files) in &record.apps_configured {
+            println!("  Configured {} ({} file(s))", app, files.len());
+        }
+        for error in &record.errors {
+            println!("  ! {}", error);
+        }
+        println!();
+    }
+    if let Some(path) = history::log_path() {
+        println!("Full log: {}", path.display());
+    }
+}
+
+/// Run the benchmarks, log the result and compare it with the latest
+/// baseline, along with the settings that changed since. A new baseline
+/// only reports its scores.
+fn run_benchmark(baseline: bool, json: bool) {
+    let workflow = WorkflowConfig::load()
+        .map(|config| config.active_workflow)
+        .unwrap_or(WorkflowType::General);
+    let previous = match benchmark::latest_baseline() {
+        Ok(previous) => previous,
+        Err(e) => {
+            eprintln!("Warning: Failed to read the benchmark log: {}", e);
+            None
+        }
+    };
+
+    if !json {
+        println!("Running benchmarks...");
+    }
+    let result = BenchmarkResult::run(workflow, baseline);
+    if let Err(e) = result.append() {
+        eprintln!("Warning: Failed to write the benchmark log: {}", e);
+    }
+
+    if json {
+        println!("{}", serde_json::to_string(&result).unwrap_or_default());
+        return;
+    }
+
+    println!(
+        "  CPU: {:.0} Mops/s ({} threads)",
+        result.cpu_score, result.cpu_threads
+    );
+    match result.gpu_score {
+        Some(score) => println!("  GPU: {} (glmark2)", score),
+        None => println!("  GPU: not measured (install glmark2)"),
+    }
+
+    if baseline {
+        println!("Recorded as the baseline for later runs.");
+        return;
+    }
+    let Some(previous) = previous else {
+        println!("No baseline yet; run `rururu-workflow benchmark --baseline` before activating.");
+        return;
+    };
+
+    let delta = result.compare(&previous);
+    println!();
+    println!(
+        "Compared with the baseline from {} ({}):",
+        previous
+            .timestamp
+            .with_timezone(&chrono::Local)
+            .format("%Y-%m-%d %H:%M:%S"),
+        previous.workflow.name()
+    );
+    println!("  CPU: {}", describe_delta(delta.cpu_percent));
+    if let Some(gpu) = delta.gpu_percent {
+        println!("  GPU: {}", describe_delta(gpu));
+    }
+    for (setting, before) in &previous.settings {
+        let after = result
+            .settings
+            .iter()
+            .find(|(name, _)| name == setting)
+            .and_then(|(_, value)| value.as_ref());
+        if before.as_ref() != after {
+            println!(
+                "  {}: {} -> {}",
+                setting,
+                before.as_deref().unwrap_or("unavailable"),
+                after.map_or("unavailable", |v| v.as_str())
+            );
+        }
+    }
+}
+
+fn describe_delta(percent: f64) -> String {
+    if percent.abs() < benchmark::NOISE_PERCENT {
+        format!("{:+.1}% (within noise)", percent)
+    } else {
+        format!("{:+.1}%", percent)
+    }
+}
+
 fn show_status() {
     match WorkflowConfig::load() {
         Ok(config) => {
@@ -166,6 +762,19 @@ fn show_status() {
                     }
                 }
             }
+
+            let drift = config.check_drift();
+            println!();
+            if drift.is_empty() {
+                println!("No drift from the active profile.");
+            } else {
+                println!("Drift from the active profile:");
+                for item in &drift {
+                    println!("  ! {}", item);
+                }
+                println!();
+                println!("Run `rururu-workflow reapply` to restore these settings.");
+            }
         }
         Err(e) => {
             eprintln!("Failed to load config: {}", e);
@@ -213,13 +822,63 @@ fn install_workflow_apps(name: &str) {
         } else {
             println!("  Installing {}...", app.name);
             match install_app(app, config.package_manager) {
-                Ok(_) => println!("    ✓ Installed successfully"),
+                Ok(_) => {
+                    println!("    ✓ Installed successfully");
+                    apply_settings_verbose(app);
+                }
                 Err(e) => println!("    ✗ Failed: {}", e),
             }
         }
     }
 }
 
+fn apply_settings_verbose(app: &rururu_workflows::profiles::AppConfig) {
+    match apply_app_settings(app) {
+        Ok(files) => {
+            for file in files {
+                println!("  Configured {}: {}", app.name, file.display());
+            }
+        }
+        Err(e) => eprintln!("Warning: Failed to configure {}: {}", app.name, e),
+    }
+}
+
+fn launch_in_active_workflow(name: &str) {
+    let config = match WorkflowConfig::load() {
+        Ok(config) => config,
+        Err(e) => {
+            eprintln!("Failed to load config: {}", e);
+            return;
+        }
+    };
+    let profile = config
+        .get_active_profile()
+        .cloned()
+        .unwrap_or_else(|| WorkflowProfile::get_profile(config.active_workflow));
+
+    let Some(app) = profile
+        .applications
+        .iter()
+        .find(|a| a.executable == name || a.name.eq_ignore_ascii_case(name))
+    else {
+        let names: Vec<&str> = profile
+            .applications
+            .iter()
+            .map(|a| a.executable.as_str())
+            .collect();
+        eprintln!("{} is not part of the {} workflow", name, profile.name);
+        if !names.is_empty() {
+            eprintln!("  Its apps: {}", names.join(", "));
+        }
+        return;
+    };
+
+    match launch_app_in_workflow(app, &profile) {
+        Ok(pid) => println!("Launched {} (PID {})", app.name, pid),
+        Err(e) => eprintln!("Failed to launch {}: {}", app.name, e),
+    }
+}
+
 fn show_system_info() {
     let info = get_system_info();
 
@@ -233,4 +892,96 @@ fn show_system_info() {
         if info.has_nvidia { "Yes" } else { "No" }
     );
     println!("  AMD GPU: {}", if info.has_amd { "Yes" } else { "No" });
+
+    let compute = validate_gpu_compute();
+    let backends: Vec<&str> = compute.available.iter().map(|b| b.name()).collect();
+    println!(
+        "  GPU Compute: {}",
+        if backends.is_empty() {
+            "None".to_string()
+        } else {
+            backends.join(", ")
+        }
+    );
+    if let Some(ref cuda) = compute.cuda_toolkit {
+        println!("  CUDA Toolkit: {}", cuda);
+    }
+    for problem in &compute.problems {
+        println!("    ! {}", problem);
+    }
+    if let Some(fix) = compute.recommendation() {
+        println!("    To fix: {}", fix);
+    }
+}
+
+fn migrate_settings(
+    path: Option<String>,
+    user: Option<String>,
+    apply: bool,
+    policy: ConflictPolicy,
+) {
+    let source = match (path, user) {
+        (Some(root), Some(user)) => MigrationSource::MountedSystem {
+            root: root.into(),
+            user: Some(user),
+        },
+        (Some(dir), None) => MigrationSource::Backup(dir.into()),
+        (None, _) => {
+            let sources = migrate::find_sources();
+            if sources.is_empty() {
+                println!("No previous system found on the mounted drives.");
+                println!("Usage: rururu-workflow migrate <path> [--user <name>] [--apply]");
+            } else {
+                println!("Previous systems found:");
+                for source in &sources {
+                    println!("  {}", source);
+                }
+                println!();
+                println!("Run rururu-workflow migrate <path> to see what would be imported.");
+            }
+            return;
+        }
+    };
+
+    let plan = match migrate::plan(&source) {
+        Ok(plan) => plan,
+        Err(e) => {
+            eprintln!("Can't read {}: {}", source, e);
+            return;
+        }
+    };
+    if plan.is_empty() {
+        println!("No known app settings in {}", plan.source_home.display());
+        return;
+    }
+
+    println!("From {}:", plan.source_home.display());
+    for line in plan.listing() {
+        println!("  {}", line);
+    }
+    if !apply {
+        println!();
+        if plan.conflict_count() > 0 {
+            println!(
+                "{} file(s) already exist here; they will be backed up unless",
+                plan.conflict_count()
+            );
+            println!("--keep-existing or --overwrite is given.");
+        }
+        println!("Nothing copied yet; run again with --apply to import.");
+        return;
+    }
+
+    let report = plan.apply(policy);
+    println!();
+    println!("Copied {} file(s)", report.copied.len());
+    if !report.backed_up.is_empty() {
+        println!("Backed up {} replaced file(s)", report.backed_up.len());
+    }
+    if !report.skipped.is_empty() {
+        println!("Kept {} existing file(s)", report.skipped.len());
+    }
+    for (file, error) in &report.failed {
+        eprintln!("  Failed {}: {}", file.display(), error);
+    }
 }
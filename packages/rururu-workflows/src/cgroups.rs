@@ -0,0 +1,397 @@
+//! cgroup v2 resource limits for the active workflow, through the user's
+//! systemd manager.
+//!
+//! The workflow's limits go into `rururu-workflow.slice`, a unit file under
+//! `~/.config/systemd/user` that is rewritten on every activation, so a
+//! workflow without limits leaves the slice at systemd's defaults after the
+//! reload. Running `high_priority_processes` move into it as transient
+//! scopes. Processes a service runs (PipeWire, WirePlumber) stay in their
+//! service, since systemd treats a service whose main process left as
+//! stopped; they are reniced instead. Background limits are runtime
+//! properties of `background.slice`; each activation clears the ones this
+//! module sets before applying new ones, leaving the user's own drop-ins
+//! for the slice alone.
+
+use crate::profiles::{ResourceLimits, SliceLimits, SystemSettings};
+use crate::system::{set_process_priority, HIGH_PRIORITY_NICE};
+use crate::{Result, WorkflowError};
+use std::fs;
+use std::os::unix::fs::MetadataExt;
+use std::path::{Path, PathBuf};
+use std::process::Command;
+
+pub const WORKFLOW_SLICE: &str = "rururu-workflow.slice";
+pub const BACKGROUND_SLICE: &str = "background.slice";
+
+/// Present only on the unified (v2) hierarchy
+const CGROUP_CONTROLLERS: &str = "/sys/fs/cgroup/cgroup.controllers";
+
+/// Every property [`properties`] can set
+const PROPERTIES: [&str; 4] = ["CPUWeight", "MemoryHigh", "MemoryMax", "IOWeight"];
+
+/// Prefix of the transient scopes [`move_to_slice`] starts
+const SCOPE_PREFIX: &str = "rururu-workflow-";
+
+/// Location of the slice unit written by [`apply`]
+pub fn slice_unit_path() -> Option<PathBuf> {
+    dirs::config_dir().map(|dir| dir.join("systemd/user").join(WORKFLOW_SLICE))
+}
+
+pub fn cgroup_v2_available() -> bool {
+    Path::new(CGROUP_CONTROLLERS).exists()
+}
+
+/// Apply the profile's limits, or reset what a previous workflow set, then
+/// prioritize `high_priority_processes`. Every step is tried; the ones that
+/// failed are reported together.
+pub fn apply(settings: &SystemSettings) -> Result<()> {
+    let limits = settings
+        .resource_limits
+        .as_ref()
+        .filter(|_| cgroup_v2_available());
+    let Some(limits) = limits else {
+        reset()?;
+        for process in &settings.high_priority_processes {
+            set_process_priority(process, HIGH_PRIORITY_NICE)?;
+        }
+        return Ok(());
+    };
+
+    write_slice(&limits.workflow)?;
+    let mut failures = Failures::default();
+    failures.note(systemctl(&["daemon-reload"]));
+    failures.note(systemctl(&["start", WORKFLOW_SLICE]));
+    failures.note(set_background(limits.background.as_ref()));
+
+    for process in &settings.high_priority_processes {
+        for pid in pids(process) {
+            let result = match unit_of(pid) {
+                Some(unit) if unit.ends_with(".service") => renice(pid),
+                // Moved by an earlier activation
+                Some(unit) if unit.starts_with(SCOPE_PREFIX) => Ok(()),
+                _ => move_to_slice(process, pid),
+            };
+            failures.note(result);
+        }
+    }
+
+    failures.into_result()
+}
+
+/// Drop the workflow slice's limits and the background.slice properties
+/// set here. Processes already in the slice keep running there.
+pub fn reset() -> Result<()> {
+    let mut failures = Failures::default();
+    if let Some(path) = slice_unit_path() {
+        if clear_slice(&path)? {
+            failures.note(systemctl(&["daemon-reload"]));
+        }
+    }
+    failures.note(set_background(None));
+    failures.into_result()
+}
+
+/// `systemctl set-property`-style assignments for the limits that are set
+pub fn properties(limits: &SliceLimits) -> Vec<String> {
+    let mut properties = Vec::new();
+    if let Some(weight) = limits.cpu_weight {
+        properties.push(format!("CPUWeight={}", weight.clamp(1, 10000)));
+    }
+    if let Some(high) = limits.memory_high_mb {
+        properties.push(format!("MemoryHigh={}M", high));
+    }
+    if let Some(max) = limits.memory_max_mb {
+        properties.push(format!("MemoryMax={}M", max));
+    }
+    if let Some(weight) = limits.io_weight {
+        properties.push(format!("IOWeight={}", weight.clamp(1, 10000)));
+    }
+    properties
+}
+
+fn write_slice(limits: &SliceLimits) -> Result<()> {
+    match slice_unit_path() {
+        Some(path) => write_slice_at(&path, limits),
+        None => Ok(()),
+    }
+}
+
+fn write_slice_at(path: &Path, limits: &SliceLimits) -> Result<()> {
+    if let Some(parent) = path.parent() {
+        fs::create_dir_all(parent)?;
+    }
+
+    let mut unit = String::from(
+        "# Written by rururu-workflow on activation; changes are overwritten\n\
+         [Unit]\n\
+         Description=RururuOS workflow applications\n\
+         \n\
+         [Slice]\n",
+    );
+    for property in properties(limits) {
+        unit.push_str(&property);
+        unit.push('\n');
+    }
+    fs::write(path, unit)?;
+    Ok(())
+}
+
+/// Rewrite the slice unit at `path` without limits, if there is one.
+/// Whether systemd has to reload it.
+fn clear_slice(path: &Path) -> Result<bool> {
+    if !path.exists() {
+        return Ok(false);
+    }
+    write_slice_at(path, &SliceLimits::default())?;
+    Ok(true)
+}
+
+fn set_background(limits: Option<&SliceLimits>) -> Result<()> {
+    let args = background_args(limits);
+    let args: Vec<&str> = args.iter().map(String::as_str).collect();
+    systemctl(&args)
+}
+
+/// `systemctl --user` arguments putting `limits` on background.slice. Each
+/// property is emptied first, which puts back systemd's default for the
+/// ones `limits` leaves unset; unlike `systemctl revert`, that keeps the
+/// user's own drop-ins and overrides for the slice.
+fn background_args(limits: Option<&SliceLimits>) -> Vec<String> {
+    let mut args: Vec<String> = ["set-property", "--runtime", BACKGROUND_SLICE]
+        .map(String::from)
+        .into();
+    args.extend(PROPERTIES.iter().map(|property| format!("{}=", property)));
+    args.extend(limits.map(properties).unwrap_or_default());
+    args
+}
+
+/// Start a transient scope in the workflow slice holding `pid`. Fails
+/// when a scope of that name already exists.
+pub fn move_to_slice(process: &str, pid: u32) -> Result<()> {
+    let name: String = process
+        .chars()
+        .map(|c| if c.is_ascii_alphanumeric() { c } else { '_' })
+        .collect();
+    let scope = format!("{}{}-{}.scope", SCOPE_PREFIX, name, pid);
+    let pid = pid.to_string();
+    run(Command::new("busctl").args([
+        "--user",
+        "call",
+        "org.freedesktop.systemd1",
+        "/org/freedesktop/systemd1",
+        "org.freedesktop.systemd1.Manager",
+        "StartTransientUnit",
+        "ssa(sv)a(sa(sv))",
+        &scope,
+        "fail",
+        "2",
+        "PIDs",
+        "au",
+        "1",
+        &pid,
+        "Slice",
+        "s",
+        WORKFLOW_SLICE,
+        "0",
+    ]))
+}
+
+fn renice(pid: u32) -> Result<()> {
+    run(Command::new("renice").args([&HIGH_PRIORITY_NICE.to_string(), "-p", &pid.to_string()]))
+}
+
+/// Unit a process runs in, from the last component of its cgroup path
+fn unit_of(pid: u32) -> Option<String> {
+    let cgroup = fs::read_to_string(format!("/proc/{}/cgroup", pid)).ok()?;
+    // "0::/user.slice/user-1000.slice/user@1000.service/app.slice/app-ardour.scope"
+    let path = cgroup.lines().find_map(|line| line.strip_prefix("0::"))?;
+    path.rsplit('/').next().map(str::to_string)
+}
+
+/// This user's processes named exactly `process`. pgrep alone matches
+/// substrings, so `pipewire` would catch `pipewire-pulse`, and every user's
+/// processes, which can't be moved into this user's slice.
+fn pids(process: &str) -> Vec<u32> {
+    let Ok(uid) = fs::metadata("/proc/self").map(|meta| meta.uid()) else {
+        return Vec::new();
+    };
+    Command::new("pgrep")
+        .args(["-x", "-u", &uid.to_string(), process])
+        .output()
+        .map(|output| {
+            String::from_utf8_lossy(&output.stdout)
+                .lines()
+                .filter_map(|pid| pid.trim().parse().ok())
+                .collect()
+        })
+        .unwrap_or_default()
+}
+
+fn systemctl(args: &[&str]) -> Result<()> {
+    run(Command::new("systemctl").arg("--user").args(args))
+}
+
+/// Run `command`, failing with what it printed when it exits non-zero
+fn run(command: &mut Command) -> Result<()> {
+    let output = command.output()?;
+    if output.status.success() {
+        return Ok(());
+    }
+    let program = command.get_program().to_string_lossy().into_owned();
+    let args: Vec<_> = command.get_args().map(|a| a.to_string_lossy()).collect();
+    Err(WorkflowError::System(format!(
+        "{} {} failed: {}",
+        program,
+        args.join(" "),
+        String::from_utf8_lossy(&output.stderr).trim()
+    )))
+}
+
+/// Errors of steps that are all tried, reported once at the end
+#[derive(Default)]
+struct Failures(Vec<String>);
+
+impl Failures {
+    fn note(&mut self, result: Result<()>) {
+        if let Err(e) = result {
+            self.0.push(e.to_string());
+        }
+    }
+
+    fn into_result(self) -> Result<()> {
+        if self.0.is_empty() {
+            Ok(())
+        } else {
+            Err(WorkflowError::System(self.0.join("; ")))
+        }
+    }
+}
+
+impl ResourceLimits {
+    /// One line per slice with limits, e.g.
+    /// "rururu-workflow.slice: CPUWeight=1000 IOWeight=1000"
+    pub fn summary(&self) -> Vec<String> {
+        let mut lines = Vec::new();
+        let slices = [
+            (WORKFLOW_SLICE, Some(&self.workflow)),
+            (BACKGROUND_SLICE, self.background.as_ref()),
+        ];
+        for (slice, limits) in slices {
+            let properties = limits.map(properties).unwrap_or_default();
+            if !properties.is_empty() {
+                lines.push(format!("{}: {}", slice, properties.join(" ")));
+            }
+        }
+        lines
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_properties_of_set_limits_only() {
+        let limits = SliceLimits {
+            cpu_weight: Some(1000),
+            memory_high_mb: None,
+            memory_max_mb: Some(4096),
+            io_weight: None,
+        };
+        assert_eq!(properties(&limits), ["CPUWeight=1000", "MemoryMax=4096M"]);
+        assert!(properties(&SliceLimits::default()).is_empty());
+    }
+
+    #[test]
+    fn test_properties_clamp_weights() {
+        let limits = SliceLimits {
+            cpu_weight: Some(0),
+            io_weight: Some(50_000),
+            ..Default::default()
+        };
+        assert_eq!(properties(&limits), ["CPUWeight=1", "IOWeight=10000"]);
+    }
+
+    #[test]
+    fn test_summary_skips_slices_without_limits() {
+        let limits = ResourceLimits {
+            workflow: SliceLimits {
+                cpu_weight: Some(1000),
+                io_weight: Some(1000),
+                ..Default::default()
+            },
+            background: Some(SliceLimits::default()),
+        };
+        assert_eq!(
+            limits.summary(),
+            ["rururu-workflow.slice: CPUWeight=1000 IOWeight=1000"]
+        );
+
+        let background = ResourceLimits {
+            workflow: SliceLimits::default(),
+            background: Some(SliceLimits {
+                memory_high_mb: Some(2048),
+                ..Default::default()
+            }),
+        };
+        assert_eq!(background.summary(), ["background.slice: MemoryHigh=2048M"]);
+        assert!(ResourceLimits::default().summary().is_empty());
+    }
+
+    #[test]
+    fn test_reset_clears_the_slice_unit() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join(WORKFLOW_SLICE);
+        let limits = SliceLimits {
+            cpu_weight: Some(1000),
+            memory_max_mb: Some(4096),
+            ..Default::default()
+        };
+        write_slice_at(&path, &limits).unwrap();
+        assert!(fs::read_to_string(&path)
+            .unwrap()
+            .contains("CPUWeight=1000"));
+
+        assert!(clear_slice(&path).unwrap());
+        let unit = fs::read_to_string(&path).unwrap();
+        assert!(unit.ends_with("[Slice]\n"));
+        assert!(PROPERTIES.iter().all(|property| !unit.contains(property)));
+    }
+
+    #[test]
+    fn test_reset_leaves_a_missing_slice_unit_alone() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join(WORKFLOW_SLICE);
+        assert!(!clear_slice(&path).unwrap());
+        assert!(!path.exists());
+    }
+
+    #[test]
+    fn test_reset_empties_only_the_background_properties_set_here() {
+        let args = background_args(None);
+        assert_eq!(
+            args,
+            [
+                "set-property",
+                "--runtime",
+                "background.slice",
+                "CPUWeight=",
+                "MemoryHigh=",
+                "MemoryMax=",
+                "IOWeight=",
+            ]
+        );
+        assert!(!args.iter().any(|arg| arg == "revert"));
+    }
+
+    #[test]
+    fn test_background_limits_follow_the_cleared_properties() {
+        let limits = SliceLimits {
+            cpu_weight: Some(20),
+            ..Default::default()
+        };
+        let args = background_args(Some(&limits));
+        assert_eq!(args.last().map(String::as_str), Some("CPUWeight=20"));
+        assert_eq!(args.len(), 8);
+    }
+}
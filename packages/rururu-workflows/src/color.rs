@@ -0,0 +1,101 @@
+//! The workflow's color settings, handed to the color service.
+//!
+//! `rururu-color` reads them from its own config, so activation records the
+//! workflow there as the active one: conversions that don't name a workflow
+//! then use its rendering intent. Deactivating clears it again.
+
+use crate::profiles::{ColorWorkflowConfig, WorkflowProfile};
+use crate::{Result, WorkflowError};
+use rururu_color::config::{RenderingIntent, WorkflowColorConfig};
+use rururu_color::{ColorConfig, TransformOptions};
+
+impl ColorWorkflowConfig {
+    /// `default_intent` as an intent; names the color service doesn't know
+    /// are an error rather than quietly perceptual
+    pub fn rendering_intent(&self) -> Result<RenderingIntent> {
+        self.default_intent.parse().map_err(|_| {
+            WorkflowError::Config(format!(
+                "Unknown rendering intent {:?}",
+                self.default_intent
+            ))
+        })
+    }
+
+    /// Options for the workflow's conversions
+    pub fn transform_options(&self) -> Result<TransformOptions> {
+        self.rendering_intent().map(TransformOptions::new)
+    }
+}
+
+/// Record `profile`'s color settings as the active workflow's
+pub fn activate(profile: &WorkflowProfile) -> Result<()> {
+    let color = &profile.color_config;
+    let intent = color.rendering_intent()?;
+
+    let mut config = ColorConfig::load().map_err(config_error)?;
+    config.workflows.insert(
+        profile.name.clone(),
+        WorkflowColorConfig {
+            name: profile.name.clone(),
+            working_space: color.working_space.clone(),
+            ocio_config: color.ocio_config.clone(),
+            default_intent: intent,
+            soft_proof_profile: color.soft_proof_profile.clone(),
+        },
+    );
+    config.active_workflow = Some(profile.name.clone());
+    config.save().map_err(config_error)
+}
+
+/// Stop conversions following a workflow
+pub fn deactivate() -> Result<()> {
+    let mut config = ColorConfig::load().map_err(config_error)?;
+    if config.active_workflow.take().is_some() {
+        config.save().map_err(config_error)?;
+    }
+    Ok(())
+}
+
+fn config_error(e: rururu_color::ColorError) -> WorkflowError {
+    WorkflowError::Config(format!("Color config: {}", e))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::profiles::WorkflowType;
+
+    #[test]
+    fn test_builtin_intents_parse() {
+        for workflow_type in WorkflowType::all() {
+            let profile = WorkflowProfile::get_profile(*workflow_type);
+            assert!(
+                profile.color_config.rendering_intent().is_ok(),
+                "{}",
+                profile.name
+            );
+        }
+
+        let video = WorkflowProfile::get_profile(WorkflowType::VideoEditor);
+        let options = video.color_config.transform_options().unwrap();
+        assert_eq!(options.intent, RenderingIntent::RelativeColorimetric);
+        assert!(options.black_point_compensation);
+    }
+
+    #[test]
+    fn test_unknown_intent_is_an_error() {
+        let mut color = WorkflowProfile::get_profile(WorkflowType::Photographer).color_config;
+        color.default_intent = "relative-colorimetric".to_string();
+        assert_eq!(
+            color.rendering_intent().unwrap(),
+            RenderingIntent::RelativeColorimetric
+        );
+
+        color.default_intent = "Colorimetric".to_string();
+        assert!(matches!(
+            color.rendering_intent(),
+            Err(WorkflowError::Config(_))
+        ));
+        assert!(color.transform_options().is_err());
+    }
+}
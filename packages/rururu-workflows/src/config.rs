@@ -1,4 +1,7 @@
-use crate::{Result, WorkflowError, WorkflowProfile, WorkflowType};
+use crate::{
+    drift, history, inheritance, ActivationRecord, Drift, Result, WorkflowError, WorkflowProfile,
+    WorkflowType,
+};
 use serde::{Deserialize, Serialize};
 use std::collections::HashMap;
 use std::path::PathBuf;
@@ -82,7 +85,14 @@ impl WorkflowConfig {
 
         if config_path.exists() {
             let content = std::fs::read_to_string(&config_path)?;
-            toml::from_str(&content).map_err(|e| WorkflowError::Config(e.to_string()))
+            let mut table: toml::Table =
+                toml::from_str(&content).map_err(|e| WorkflowError::Config(e.to_string()))?;
+            if let Some(toml::Value::Table(profiles)) = table.get_mut("profiles") {
+                inheritance::resolve(profiles)?;
+            }
+            table
+                .try_into()
+                .map_err(|e: toml::de::Error| WorkflowError::Config(e.to_string()))
         } else {
             Ok(Self::default())
         }
@@ -95,8 +105,14 @@ impl WorkflowConfig {
             std::fs::create_dir_all(parent)?;
         }
 
+        // Profiles that extend another keep only their overrides
+        let mut table =
+            toml::Table::try_from(self).map_err(|e| WorkflowError::Config(e.to_string()))?;
+        if let Some(toml::Value::Table(profiles)) = table.get_mut("profiles") {
+            inheritance::strip_inherited(profiles)?;
+        }
         let content =
-            toml::to_string_pretty(self).map_err(|e| WorkflowError::Config(e.to_string()))?;
+            toml::to_string_pretty(&table).map_err(|e| WorkflowError::Config(e.to_string()))?;
 
         std::fs::write(config_path, content)?;
         Ok(())
@@ -120,6 +136,18 @@ impl WorkflowConfig {
     pub fn get_profile(&self, name: &str) -> Option<&WorkflowProfile> {
         self.profiles.get(name)
     }
+
+    /// Settings of the active profile that the live system no longer matches
+    pub fn check_drift(&self) -> Vec<Drift> {
+        self.get_active_profile()
+            .map(drift::check_profile)
+            .unwrap_or_default()
+    }
+
+    /// The last `limit` entries of the activation log, oldest first
+    pub fn activation_history(limit: usize) -> Result<Vec<ActivationRecord>> {
+        history::read_history(limit)
+    }
 }
 
 fn detect_package_manager() -> PackageManager {
@@ -0,0 +1,162 @@
+//! Running processes a workflow's activation would compete with.
+//!
+//! Activation renices the profile's high-priority processes and may enable
+//! realtime audio scheduling. Either can starve a long render or encode that
+//! is already running, so `rururu-workflow activate` looks for busy processes
+//! outside the profile first and asks before switching.
+
+use crate::profiles::WorkflowProfile;
+use rururu_utils::SystemInfo;
+use std::fmt;
+use std::time::Duration;
+
+pub use rururu_utils::system::ProcessInfo;
+
+/// CPU use (percent of one core) from which a process counts as busy
+const HIGH_LOAD_CPU: f32 = 50.0;
+
+/// Renderers and encoders are flagged at a lower load: a GPU render keeps
+/// only a feeder thread busy but still stalls when that thread is preempted.
+const RENDER_LOAD_CPU: f32 = 10.0;
+
+const RENDERERS: &[&str] = &[
+    "blender",
+    "resolve",
+    "ffmpeg",
+    "handbrakecli",
+    "ghb",
+    "melt",
+    "kdenlive",
+    "natron",
+    "houdini",
+    "darktable-cli",
+];
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ConflictKind {
+    /// Realtime audio threads will run ahead of the process
+    Preempted,
+    /// Reniced profile processes will be scheduled ahead of it
+    Deprioritized,
+}
+
+impl ConflictKind {
+    pub fn name(&self) -> &'static str {
+        match self {
+            ConflictKind::Preempted => "Preempted",
+            ConflictKind::Deprioritized => "Deprioritized",
+        }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct Conflict {
+    pub kind: ConflictKind,
+    pub pid: u32,
+    pub process: String,
+    pub cpu_usage: f32,
+    pub reason: String,
+}
+
+impl fmt::Display for Conflict {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(
+            f,
+            "[{}] {} (pid {}, {:.0}% CPU): {}",
+            self.kind.name(),
+            self.process,
+            self.pid,
+            self.cpu_usage,
+            self.reason
+        )
+    }
+}
+
+impl WorkflowProfile {
+    /// Busy processes outside this profile that activating it would
+    /// deprioritize, at most one conflict per process.
+    pub fn detect_conflicts(&self, running: &[ProcessInfo]) -> Vec<Conflict> {
+        let settings = &self.system_settings;
+        if !settings.realtime_audio && settings.high_priority_processes.is_empty() {
+            return Vec::new();
+        }
+
+        let mut conflicts: Vec<Conflict> = running
+            .iter()
+            .filter(|p| !self.owns_process(&p.name))
+            .filter_map(|p| {
+                let render = is_renderer(&p.name);
+                let threshold = if render { RENDER_LOAD_CPU } else { HIGH_LOAD_CPU };
+                if p.cpu_usage < threshold {
+                    return None;
+                }
+
+                let task = if render { "render/encode" } else { "busy process" };
+                let (kind, reason) = if settings.realtime_audio {
+                    (
+                        ConflictKind::Preempted,
+                        format!(
+                            "{} enables realtime audio; its audio threads preempt this {} and can stall it",
+                            self.name, task
+                        ),
+                    )
+                } else {
+                    (
+                        ConflictKind::Deprioritized,
+                        format!(
+                            "{} raises the priority of {}, ahead of this {}",
+                            self.name,
+                            settings.high_priority_processes.join(", "),
+                            task
+                        ),
+                    )
+                };
+
+                Some(Conflict {
+                    kind,
+                    pid: p.pid,
+                    process: p.name.clone(),
+                    cpu_usage: p.cpu_usage,
+                    reason,
+                })
+            })
+            .collect();
+
+        conflicts.sort_by(|a, b| b.cpu_usage.total_cmp(&a.cpu_usage));
+        conflicts
+    }
+
+    /// Whether activation treats `name` as one of the profile's own processes
+    fn owns_process(&self, name: &str) -> bool {
+        let name = name.to_lowercase();
+
+        // `set_process_priority` matches with pgrep, i.e. by substring
+        self.system_settings
+            .high_priority_processes
+            .iter()
+            .any(|p| name.contains(&p.to_lowercase()))
+            || self
+                .applications
+                .iter()
+                .any(|a| name == a.executable.to_lowercase())
+    }
+}
+
+fn is_renderer(name: &str) -> bool {
+    let name = name.to_lowercase();
+    RENDERERS
+        .iter()
+        .any(|r| name == *r || name.starts_with(&format!("{}.", r)))
+}
+
+/// Current processes with CPU usage measured over a short interval
+pub fn running_processes() -> Vec<ProcessInfo> {
+    let mut info = SystemInfo::new();
+    info.refresh();
+
+    // Per-process CPU usage is the delta between two refreshes
+    std::thread::sleep(Duration::from_millis(300));
+    info.refresh();
+
+    info.process_list()
+}
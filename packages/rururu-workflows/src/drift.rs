@@ -0,0 +1,161 @@
+//! Compare an activated profile against the live system.
+//!
+//! Only what activation actually changes is checked: the CPU governor,
+//! swappiness, GPU performance level, the realtime PipeWire drop-in, the
+//! profile environment and the primary applications. Keyboard shortcuts are
+//! not checked because activation never binds them.
+
+use crate::apps::is_app_installed;
+use crate::environment;
+use crate::profiles::{AppPriority, WorkflowProfile};
+use crate::system;
+use std::fmt;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum DriftCategory {
+    System,
+    Environment,
+    Application,
+}
+
+impl DriftCategory {
+    pub fn name(&self) -> &'static str {
+        match self {
+            DriftCategory::System => "System",
+            DriftCategory::Environment => "Environment",
+            DriftCategory::Application => "Application",
+        }
+    }
+}
+
+/// One setting whose live value no longer matches the profile
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Drift {
+    pub category: DriftCategory,
+    pub setting: String,
+    pub expected: String,
+    /// `None` when the setting is missing entirely
+    pub actual: Option<String>,
+}
+
+impl Drift {
+    fn new(
+        category: DriftCategory,
+        setting: impl Into<String>,
+        expected: impl Into<String>,
+        actual: Option<String>,
+    ) -> Self {
+        Self {
+            category,
+            setting: setting.into(),
+            expected: expected.into(),
+            actual,
+        }
+    }
+}
+
+impl fmt::Display for Drift {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(
+            f,
+            "[{}] {}: expected {}, found {}",
+            self.category.name(),
+            self.setting,
+            self.expected,
+            self.actual.as_deref().unwrap_or("nothing")
+        )
+    }
+}
+
+pub fn check_profile(profile: &WorkflowProfile) -> Vec<Drift> {
+    let mut drift = Vec::new();
+
+    check_system(profile, &mut drift);
+    check_environment(profile, &mut drift);
+    check_applications(profile, &mut drift);
+
+    drift
+}
+
+fn check_system(profile: &WorkflowProfile, drift: &mut Vec<Drift>) {
+    let settings = &profile.system_settings;
+
+    // No cpufreq means the governor was never applied either
+    let expected = settings.cpu_governor.as_str();
+    let governors = system::current_cpu_governors();
+    if !governors.is_empty() && governors.iter().any(|g| g != expected) {
+        drift.push(Drift::new(
+            DriftCategory::System,
+            "CPU governor",
+            expected,
+            Some(governors.join(", ")),
+        ));
+    }
+
+    let expected = settings.swap_usage.swappiness();
+    if let Some(actual) = system::current_swappiness() {
+        if actual != expected {
+            drift.push(Drift::new(
+                DriftCategory::System,
+                "vm.swappiness",
+                expected.to_string(),
+                Some(actual.to_string()),
+            ));
+        }
+    }
+
+    let expected = if settings.gpu_performance_mode {
+        "high"
+    } else {
+        "auto"
+    };
+    if let Some(actual) = system::current_amd_performance_level() {
+        if actual != expected {
+            drift.push(Drift::new(
+                DriftCategory::System,
+                "GPU performance level",
+                expected,
+                Some(actual),
+            ));
+        }
+    }
+
+    if settings.realtime_audio {
+        if let Some(path) = system::realtime_audio_config_path() {
+            if !path.exists() {
+                drift.push(Drift::new(
+                    DriftCategory::System,
+                    "Realtime audio config",
+                    path.display().to_string(),
+                    None,
+                ));
+            }
+        }
+    }
+}
+
+fn check_environment(profile: &WorkflowProfile, drift: &mut Vec<Drift>) {
+    for (key, value) in environment::profile_environment(profile) {
+        let actual = std::env::var(&key).ok();
+        if actual.as_deref() != Some(value.as_str()) {
+            drift.push(Drift::new(DriftCategory::Environment, key, value, actual));
+        }
+    }
+}
+
+fn check_applications(profile: &WorkflowProfile, drift: &mut Vec<Drift>) {
+    for app in profile
+        .applications
+        .iter()
+        .filter(|a| a.priority == AppPriority::Primary)
+    {
+        if !is_app_installed(app) {
+            drift.push(Drift::new(
+                DriftCategory::Application,
+                &app.name,
+                "installed",
+                None,
+            ));
+        }
+    }
+}
@@ -0,0 +1,159 @@
+//! Login-session environment for the active workflow.
+//!
+//! Setting variables in the CLI process only reaches its own children, so the
+//! profile environment is written to a systemd `environment.d` drop-in that the
+//! user manager and the desktop session read at login. Apps started before the
+//! next login keep their old environment; the running user manager is updated
+//! as well so newly activated services see the change right away.
+
+use crate::{expand_home, scratch, Result, WorkflowProfile};
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::process::Command;
+
+/// File name inside `~/.config/environment.d`
+const ENVIRONMENT_FILE: &str = "rururu-workflow.conf";
+
+/// Location of the drop-in written by [`persist`]
+pub fn environment_file_path() -> Option<PathBuf> {
+    dirs::config_dir().map(|dir| dir.join("environment.d").join(ENVIRONMENT_FILE))
+}
+
+/// Variables a profile sets, including `OCIO` when its config exists (the
+/// copy on scratch once there is one) and
+/// the ones pointing into its scratch directory. A leading `~` is expanded
+/// since `environment.d` doesn't do it; without a home directory to expand
+/// it into, the variable is left out.
+pub fn profile_environment(profile: &WorkflowProfile) -> Vec<(String, String)> {
+    let mut vars: Vec<(String, String)> = profile
+        .environment
+        .iter()
+        .filter_map(|(k, v)| match expand_home(Path::new(v)) {
+            Some(value) => Some((k.clone(), value.display().to_string())),
+            None => {
+                tracing::warn!("Leaving out {}: no home directory to expand {}", k, v);
+                None
+            }
+        })
+        .collect();
+
+    if let Some(ref config) = profile.scratch_config {
+        vars.extend(scratch::scratch_environment(config));
+    }
+
+    if let Some(ocio) = scratch::ocio_config(profile) {
+        vars.push(("OCIO".to_string(), ocio.display().to_string()));
+    }
+
+    vars.sort();
+    vars
+}
+
+/// Write the profile environment for the next login, replacing whatever a
+/// previous workflow left behind. Profiles without variables remove the file.
+pub fn persist(profile: &WorkflowProfile) -> Result<Option<PathBuf>> {
+    let vars = profile_environment(profile);
+    let previous = persisted_keys();
+
+    if vars.is_empty() {
+        clear()?;
+        return Ok(None);
+    }
+
+    let Some(path) = environment_file_path() else {
+        return Ok(None);
+    };
+    if let Some(parent) = path.parent() {
+        fs::create_dir_all(parent)?;
+    }
+
+    let mut content = format!(
+        "# Written by rururu-workflow for the {} workflow; do not edit.\n\
+         # Takes full effect at the next login.\n",
+        profile.name
+    );
+    for (key, value) in &vars {
+        content.push_str(&format!("{}={}\n", key, quote(value)));
+    }
+    fs::write(&path, content)?;
+
+    // Drop variables only the previous workflow set from the running session
+    let stale: Vec<String> = previous
+        .into_iter()
+        .filter(|key| !vars.iter().any(|(k, _)| k == key))
+        .collect();
+    unset_session_environment(&stale);
+    set_session_environment(&vars);
+
+    Ok(Some(path))
+}
+
+/// Remove the persisted environment, e.g. when deactivating a workflow
+pub fn clear() -> Result<()> {
+    let keys = persisted_keys();
+
+    if let Some(path) = environment_file_path() {
+        if path.exists() {
+            fs::remove_file(path)?;
+        }
+    }
+
+    unset_session_environment(&keys);
+    Ok(())
+}
+
+/// Variable names currently in the drop-in
+fn persisted_keys() -> Vec<String> {
+    environment_file_path()
+        .and_then(|path| fs::read_to_string(path).ok())
+        .map(|content| {
+            content
+                .lines()
+                .map(str::trim)
+                .filter(|line| !line.starts_with('#'))
+                .filter_map(|line| line.split_once('=').map(|(k, _)| k.to_string()))
+                .collect()
+        })
+        .unwrap_or_default()
+}
+
+fn set_session_environment(vars: &[(String, String)]) {
+    if vars.is_empty() {
+        return;
+    }
+
+    let _ = Command::new("systemctl")
+        .args(["--user", "set-environment"])
+        .args(vars.iter().map(|(k, v)| format!("{}={}", k, v)))
+        .status();
+}
+
+fn unset_session_environment(keys: &[String]) {
+    if keys.is_empty() {
+        return;
+    }
+
+    let _ = Command::new("systemctl")
+        .args(["--user", "unset-environment"])
+        .args(keys)
+        .status();
+}
+
+/// Quote values containing whitespace or quotes; `environment.d` follows
+/// shell-style quoting rules.
+fn quote(value: &str) -> String {
+    if value
+        .chars()
+        .any(|c| c.is_whitespace() || matches!(c, '"' | '\'' | '\\' | '$'))
+    {
+        format!(
+            "\"{}\"",
+            value
+                .replace('\\', "\\\\")
+                .replace('"', "\\\"")
+                .replace('$', "\\$")
+        )
+    } else {
+        value.to_string()
+    }
+}
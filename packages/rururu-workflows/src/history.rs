@@ -0,0 +1,149 @@
+//! Audit trail of workflow activations.
+//!
+//! Every activation appends one JSON line to
+//! `~/.local/state/rururu/workflow-activations.jsonl` with what it set, what
+//! those settings were before, and what failed, so "what did the workflow
+//! system do to my machine" has an answer. Lines that don't parse, say from
+//! a newer version, are skipped when reading.
+
+use crate::environment;
+use crate::system;
+use crate::{Result, WorkflowError, WorkflowType};
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+use std::fs::{self, OpenOptions};
+use std::io::Write;
+use std::path::PathBuf;
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ActivationRecord {
+    pub timestamp: DateTime<Utc>,
+    pub workflow: WorkflowType,
+    pub settings: Vec<SettingChange>,
+    /// Variables written for the next login session
+    pub environment: Vec<(String, String)>,
+    /// Apps whose config files were written, with the files
+    pub apps_configured: Vec<(String, Vec<PathBuf>)>,
+    pub errors: Vec<String>,
+}
+
+/// A system setting activation applies, as read before and after
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct SettingChange {
+    pub setting: String,
+    /// `None` when the setting isn't available on this machine
+    pub before: Option<String>,
+    pub after: Option<String>,
+}
+
+impl SettingChange {
+    pub fn changed(&self) -> bool {
+        self.before != self.after
+    }
+}
+
+impl ActivationRecord {
+    pub fn new(workflow: WorkflowType) -> Self {
+        Self {
+            timestamp: Utc::now(),
+            workflow,
+            settings: Vec::new(),
+            environment: Vec::new(),
+            apps_configured: Vec::new(),
+            errors: Vec::new(),
+        }
+    }
+
+    /// Pair up snapshots taken around applying the system settings
+    pub fn record_settings(&mut self, before: Snapshot, after: Snapshot) {
+        self.settings = before
+            .into_iter()
+            .map(|(setting, before)| {
+                let after = after
+                    .iter()
+                    .find(|(name, _)| *name == setting)
+                    .and_then(|(_, value)| value.clone());
+                SettingChange {
+                    setting,
+                    before,
+                    after,
+                }
+            })
+            .collect();
+    }
+
+    /// Append to the activation log
+    pub fn append(&self) -> Result<()> {
+        let path = log_path().ok_or_else(|| {
+            WorkflowError::Config("No home directory for the activation log".into())
+        })?;
+        if let Some(parent) = path.parent() {
+            fs::create_dir_all(parent)?;
+        }
+
+        let line = serde_json::to_string(self).map_err(|e| WorkflowError::Config(e.to_string()))?;
+        let mut file = OpenOptions::new().create(true).append(true).open(&path)?;
+        writeln!(file, "{}", line)?;
+        Ok(())
+    }
+}
+
+/// Live values of the settings activation touches, by name
+pub type Snapshot = Vec<(String, Option<String>)>;
+
+pub fn system_snapshot() -> Snapshot {
+    let governors = system::current_cpu_governors();
+    let realtime_audio = system::realtime_audio_config_path().map(|path| {
+        if path.exists() {
+            format!("{} present", path.display())
+        } else {
+            "absent".to_string()
+        }
+    });
+    let environment_file = environment::environment_file_path().map(|path| {
+        fs::read_to_string(&path)
+            .map(|content| content.trim().replace('\n', "; "))
+            .unwrap_or_else(|_| "absent".to_string())
+    });
+
+    vec![
+        (
+            "CPU governor".to_string(),
+            (!governors.is_empty()).then(|| governors.join(", ")),
+        ),
+        (
+            "vm.swappiness".to_string(),
+            system::current_swappiness().map(|s| s.to_string()),
+        ),
+        (
+            "GPU performance level".to_string(),
+            system::current_amd_performance_level(),
+        ),
+        ("Realtime audio config".to_string(), realtime_audio),
+        ("Workflow environment".to_string(), environment_file),
+    ]
+}
+
+pub fn log_path() -> Option<PathBuf> {
+    let state = dirs::state_dir().or_else(|| dirs::home_dir().map(|h| h.join(".local/state")))?;
+    Some(state.join("rururu").join("workflow-activations.jsonl"))
+}
+
+/// The last `limit` activations, oldest first; empty if nothing was logged yet
+pub fn read_history(limit: usize) -> Result<Vec<ActivationRecord>> {
+    let Some(path) = log_path() else {
+        return Ok(Vec::new());
+    };
+    let content = match fs::read_to_string(&path) {
+        Ok(content) => content,
+        Err(e) if e.kind() == std::io::ErrorKind::NotFound => return Ok(Vec::new()),
+        Err(e) => return Err(e.into()),
+    };
+
+    let records: Vec<ActivationRecord> = content
+        .lines()
+        .filter_map(|line| serde_json::from_str(line).ok())
+        .collect();
+    let skip = records.len().saturating_sub(limit);
+    Ok(records.into_iter().skip(skip).collect())
+}
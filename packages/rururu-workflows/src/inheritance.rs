@@ -0,0 +1,343 @@
+//! `extends` in the profile TOML: a profile names a base and lists only
+//! what it changes.
+//!
+//! The base is another profile in the file or, when there is none by that
+//! name (or a profile names itself), the built-in workflow of that name.
+//! Overrides win key by key: tables such as `environment`, `system_settings`
+//! and `color_config` merge recursively, `applications` and
+//! `keyboard_shortcuts` merge entry by entry on their `name` and `action`
+//! (so an app's `settings` merge too) with new entries appended, and any
+//! other value, including plain lists, replaces the base's.
+
+use crate::{Result, WorkflowError, WorkflowProfile, WorkflowType};
+use toml::{Table, Value};
+
+/// Lists of tables merged per entry, and the field identifying an entry
+const KEYED_LISTS: &[(&str, &str)] = &[("applications", "name"), ("keyboard_shortcuts", "action")];
+
+const EXTENDS: &str = "extends";
+
+/// Replace every profile in `profiles` that extends another with the merged
+/// result, base first
+pub fn resolve(profiles: &mut Table) -> Result<()> {
+    let names: Vec<String> = profiles.keys().cloned().collect();
+    let mut resolved = Table::new();
+    for name in &names {
+        resolve_one(profiles, name, &mut resolved, &mut Vec::new())?;
+    }
+    *profiles = resolved;
+    Ok(())
+}
+
+/// Reduce every profile that extends another to what differs from its base,
+/// the inverse of [`resolve`] for saving
+pub fn strip_inherited(profiles: &mut Table) -> Result<()> {
+    let full = profiles.clone();
+    for (name, profile) in profiles.iter_mut() {
+        let Some(table) = profile.as_table_mut() else {
+            continue;
+        };
+        let Some(base_name) = table.get(EXTENDS).and_then(Value::as_str) else {
+            continue;
+        };
+        let base = match full.get(base_name).and_then(Value::as_table) {
+            Some(base) if base_name != name => base.clone(),
+            _ => builtin(base_name).ok_or_else(|| unknown_base(name, base_name))?,
+        };
+        remove_inherited(table, &base);
+    }
+    Ok(())
+}
+
+fn resolve_one(
+    profiles: &Table,
+    name: &str,
+    resolved: &mut Table,
+    chain: &mut Vec<String>,
+) -> Result<Table> {
+    if let Some(Value::Table(done)) = resolved.get(name) {
+        return Ok(done.clone());
+    }
+    if chain.iter().any(|n| n == name) {
+        chain.push(name.to_string());
+        return Err(WorkflowError::Config(format!(
+            "Profile inheritance cycle: {}",
+            chain.join(" → ")
+        )));
+    }
+
+    let Some(Value::Table(profile)) = profiles.get(name) else {
+        return Err(WorkflowError::Config(format!(
+            "Profile `{}` must be a table",
+            name
+        )));
+    };
+    let Some(base_name) = profile.get(EXTENDS) else {
+        resolved.insert(name.to_string(), Value::Table(profile.clone()));
+        return Ok(profile.clone());
+    };
+    let base_name = base_name.as_str().ok_or_else(|| {
+        WorkflowError::Config(format!("`extends` of profile `{}` must be a name", name))
+    })?;
+
+    chain.push(name.to_string());
+    let mut merged = if base_name != name && profiles.contains_key(base_name) {
+        resolve_one(profiles, base_name, resolved, chain)?
+    } else {
+        builtin(base_name).ok_or_else(|| unknown_base(name, base_name))?
+    };
+    chain.pop();
+
+    // A base's own `extends` is not inherited
+    merged.remove(EXTENDS);
+    merge(&mut merged, profile);
+    resolved.insert(name.to_string(), Value::Table(merged.clone()));
+    Ok(merged)
+}
+
+/// The field identifying entries of `key`, if it is a keyed list
+fn keyed_list(key: &str) -> Option<&'static str> {
+    KEYED_LISTS
+        .iter()
+        .find(|(list, _)| *list == key)
+        .map(|(_, id)| *id)
+}
+
+fn builtin(name: &str) -> Option<Table> {
+    let workflow = WorkflowType::all().iter().find(|w| w.name() == name)?;
+    let profile = WorkflowProfile::get_profile(*workflow);
+    Value::try_from(profile).ok()?.as_table().cloned()
+}
+
+fn unknown_base(name: &str, base: &str) -> WorkflowError {
+    WorkflowError::Config(format!(
+        "Profile `{}` extends `{}`, which is neither a profile nor a built-in workflow",
+        name, base
+    ))
+}
+
+/// Apply `overrides` on top of `base`
+fn merge(base: &mut Table, overrides: &Table) {
+    for (key, value) in overrides {
+        let keyed = keyed_list(key);
+
+        match (base.get_mut(key), value, keyed) {
+            (Some(Value::Table(base_table)), Value::Table(table), _) => merge(base_table, table),
+            (Some(Value::Array(base_list)), Value::Array(list), Some(id)) => {
+                merge_entries(base_list, list, id)
+            }
+            _ => {
+                base.insert(key.clone(), value.clone());
+            }
+        }
+    }
+}
+
+/// Merge each entry of `overrides` into the entry of `base` with the same
+/// `id`, appending those without one
+fn merge_entries(base: &mut Vec<Value>, overrides: &[Value], id: &str) {
+    for entry in overrides {
+        let existing = base
+            .iter_mut()
+            .find(|e| e.get(id).is_some() && e.get(id) == entry.get(id));
+        match (existing, entry) {
+            (Some(Value::Table(existing)), Value::Table(entry)) => merge(existing, entry),
+            _ => base.push(entry.clone()),
+        }
+    }
+}
+
+/// Drop from `profile` what `base` already provides
+fn remove_inherited(profile: &mut Table, base: &Table) {
+    profile.retain(|key, value| {
+        if key == EXTENDS {
+            return true;
+        }
+        let Some(base_value) = base.get(key) else {
+            return true;
+        };
+        let keyed = keyed_list(key);
+
+        match (&mut *value, base_value, keyed) {
+            (Value::Table(table), Value::Table(base_table), _) => {
+                remove_inherited(table, base_table);
+                !table.is_empty()
+            }
+            (Value::Array(list), Value::Array(base_list), Some(id)) => {
+                remove_inherited_entries(list, base_list, id);
+                !list.is_empty()
+            }
+            _ => value != base_value,
+        }
+    });
+}
+
+/// Reduce entries that are also in `base` to their `id` and what changed,
+/// dropping those left unchanged
+fn remove_inherited_entries(list: &mut Vec<Value>, base: &[Value], id: &str) {
+    list.retain_mut(|entry| {
+        let Some(entry_id) = entry.get(id).cloned() else {
+            return true;
+        };
+        let base_entry = base.iter().find(|e| e.get(id) == Some(&entry_id));
+        match (entry, base_entry) {
+            (Value::Table(entry), Some(Value::Table(base_entry))) => {
+                remove_inherited(entry, base_entry);
+                if entry.is_empty() {
+                    return false;
+                }
+                entry.insert(id.to_string(), entry_id);
+                true
+            }
+            _ => true,
+        }
+    });
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn profiles(toml: &str) -> Table {
+        toml.parse().unwrap()
+    }
+
+    #[test]
+    fn test_cycle_is_an_error() {
+        let mut table = profiles(
+            r#"
+            [a]
+            extends = "b"
+            [b]
+            extends = "c"
+            [c]
+            extends = "a"
+            "#,
+        );
+        let err = resolve(&mut table).unwrap_err().to_string();
+        assert!(err.contains("cycle"), "{}", err);
+        assert!(err.contains("a → b → c → a"), "{}", err);
+    }
+
+    #[test]
+    fn test_unknown_base_is_an_error() {
+        let mut table = profiles("[a]\nextends = \"Nowhere\"\n");
+        let err = resolve(&mut table).unwrap_err().to_string();
+        assert!(err.contains("Nowhere"), "{}", err);
+    }
+
+    #[test]
+    fn test_tables_merge_key_by_key() {
+        let mut table = profiles(
+            r#"
+            [base]
+            description = "Base"
+            startup_apps = ["a", "b"]
+            [base.environment]
+            KEEP = "1"
+            CHANGE = "old"
+            [base.color_config]
+            working_space = "sRGB"
+            hdr_enabled = false
+
+            [[base.applications]]
+            name = "Krita"
+            priority = "Primary"
+            [base.applications.settings]
+            canvas = "opengl"
+            theme = "dark"
+
+            [child]
+            extends = "base"
+            startup_apps = ["c"]
+            [child.environment]
+            CHANGE = "new"
+            ADD = "2"
+            [child.color_config]
+            hdr_enabled = true
+
+            [[child.applications]]
+            name = "Krita"
+            [child.applications.settings]
+            theme = "light"
+
+            [[child.applications]]
+            name = "Inkscape"
+            "#,
+        );
+        resolve(&mut table).unwrap();
+
+        let child = table["child"].as_table().unwrap();
+        assert_eq!(child["description"].as_str(), Some("Base"));
+        // Plain lists are replaced, not appended to
+        assert_eq!(child["startup_apps"], Value::from(vec!["c"]));
+
+        let env = child["environment"].as_table().unwrap();
+        assert_eq!(env["KEEP"].as_str(), Some("1"));
+        assert_eq!(env["CHANGE"].as_str(), Some("new"));
+        assert_eq!(env["ADD"].as_str(), Some("2"));
+
+        let color = child["color_config"].as_table().unwrap();
+        assert_eq!(color["working_space"].as_str(), Some("sRGB"));
+        assert_eq!(color["hdr_enabled"].as_bool(), Some(true));
+
+        let apps = child["applications"].as_array().unwrap();
+        assert_eq!(apps.len(), 2);
+        assert_eq!(apps[0]["priority"].as_str(), Some("Primary"));
+        assert_eq!(apps[0]["settings"]["canvas"].as_str(), Some("opengl"));
+        assert_eq!(apps[0]["settings"]["theme"].as_str(), Some("light"));
+        assert_eq!(apps[1]["name"].as_str(), Some("Inkscape"));
+
+        // The base is left as it was
+        let base = table["base"].as_table().unwrap();
+        assert_eq!(base["environment"]["CHANGE"].as_str(), Some("old"));
+        assert_eq!(child[EXTENDS].as_str(), Some("base"));
+    }
+
+    #[test]
+    fn test_profile_extends_builtin_of_same_name() {
+        let mut table = profiles(
+            r#"
+            [General]
+            extends = "General"
+            description = "Mine"
+            [General.system_settings]
+            cpu_governor = "Performance"
+            "#,
+        );
+        resolve(&mut table).unwrap();
+
+        let profile: WorkflowProfile = table["General"].clone().try_into().unwrap();
+        let builtin = WorkflowProfile::get_profile(WorkflowType::General);
+        assert_eq!(profile.description, "Mine");
+        assert_eq!(
+            profile.system_settings.cpu_governor,
+            crate::profiles::CpuGovernor::Performance
+        );
+        assert_eq!(
+            profile.system_settings.memory_pressure_threshold,
+            builtin.system_settings.memory_pressure_threshold
+        );
+        assert_eq!(profile.applications.len(), builtin.applications.len());
+        assert_eq!(
+            profile.color_config.working_space,
+            builtin.color_config.working_space
+        );
+    }
+
+    #[test]
+    fn test_strip_inherited_undoes_resolve() {
+        let original = profiles(
+            r#"
+            [General]
+            extends = "General"
+            [General.environment]
+            EDITOR = "vim"
+            "#,
+        );
+        let mut table = original.clone();
+        resolve(&mut table).unwrap();
+        strip_inherited(&mut table).unwrap();
+        assert_eq!(table, original);
+    }
+}
@@ -1,11 +1,30 @@
 pub mod apps;
+pub mod benchmark;
+pub mod cgroups;
+pub mod color;
 pub mod config;
+pub mod conflicts;
+pub mod drift;
+pub mod environment;
+pub mod history;
+pub mod inheritance;
+pub mod migrate;
+pub mod plan;
 pub mod profiles;
+pub mod readiness;
+pub mod scratch;
 pub mod system;
 
+pub use benchmark::BenchmarkResult;
 pub use config::WorkflowConfig;
+pub use conflicts::Conflict;
+pub use drift::Drift;
+pub use history::ActivationRecord;
+pub use migrate::{MigrationPlan, MigrationSource};
 pub use profiles::{WorkflowProfile, WorkflowType};
+pub use readiness::Readiness;
 
+use std::path::{Path, PathBuf};
 use thiserror::Error;
 
 #[derive(Error, Debug)]
@@ -27,3 +46,13 @@ pub enum WorkflowError {
 }
 
 pub type Result<T> = std::result::Result<T, WorkflowError>;
+
+/// `path` with a leading `~` replaced by the home directory; `None` when
+/// there is none, rather than leaving a literal `~` to be taken relative to
+/// the working directory
+pub(crate) fn expand_home(path: &Path) -> Option<PathBuf> {
+    match path.strip_prefix("~") {
+        Ok(rest) => dirs::home_dir().map(|home| home.join(rest)),
+        Err(_) => Some(path.to_path_buf()),
+    }
+}
@@ -0,0 +1,766 @@
+//! Bring creative-app settings over from a previous system.
+//!
+//! The source is either the root of an old installation mounted somewhere
+//! (another distro, or a Windows or macOS partition) or a backup directory
+//! holding a copy of an old home directory. Each known app config is looked
+//! for where Linux, its Flatpak, Windows and macOS keep it, in that order,
+//! and copied to where the app reads it here: the `config_path` the workflow
+//! profiles give it, or its usual place under the home directory.
+//!
+//! [`plan`] only looks; nothing is written until [`MigrationPlan::apply`].
+//! Files that already exist here with different contents are conflicts,
+//! handled by a [`ConflictPolicy`]. Lock files are never copied, since a
+//! stale darktable lock would keep it from starting.
+
+use crate::{Result, WorkflowConfig, WorkflowError};
+use std::fmt;
+use std::fs;
+use std::path::{Path, PathBuf};
+
+/// Suffix of the copy kept of a file a migration replaced, as for
+/// workflow settings written into app configs
+const BACKUP_SUFFIX: &str = ".rururu-backup";
+
+/// Mount points that never hold a previous system: this one's own, and
+/// anything below the prefixes
+const SYSTEM_MOUNTS: &[&str] = &["/", "/boot", "/efi", "/home"];
+const SYSTEM_MOUNT_PREFIXES: &[&str] = &[
+    "/boot/",
+    "/proc",
+    "/sys",
+    "/dev",
+    "/run/user",
+    "/var",
+    "/tmp",
+];
+
+/// Filesystems a previous system can't be on
+const VIRTUAL_FILESYSTEMS: &[&str] = &[
+    "proc", "sysfs", "tmpfs", "devtmpfs", "devpts", "cgroup2", "overlay", "squashfs", "efivarfs",
+];
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum MigrationSource {
+    /// Root of a previous system, such as `/mnt/old`. Its home directories
+    /// are looked for under `home/` or `Users/`; without a user, the one
+    /// named like the current user, or the only one there is.
+    MountedSystem { root: PathBuf, user: Option<String> },
+    /// An old home directory, or a backup of one
+    Backup(PathBuf),
+}
+
+impl MigrationSource {
+    /// Old home directory the configs are read from
+    pub fn home_dir(&self) -> Result<PathBuf> {
+        match self {
+            MigrationSource::MountedSystem { root, user } => home_under(root, user.as_deref()),
+            MigrationSource::Backup(dir) if looks_like_home(dir) => Ok(dir.clone()),
+            // A backup of the whole system rather than just the home directory
+            MigrationSource::Backup(dir) => home_under(dir, None),
+        }
+    }
+}
+
+impl fmt::Display for MigrationSource {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            MigrationSource::MountedSystem { root, user: None } => write!(f, "{}", root.display()),
+            MigrationSource::MountedSystem {
+                root,
+                user: Some(user),
+            } => write!(f, "{} ({})", root.display(), user),
+            MigrationSource::Backup(dir) => write!(f, "{}", dir.display()),
+        }
+    }
+}
+
+/// Where a config goes on this system
+#[derive(Debug, Clone, Copy)]
+enum Target {
+    /// The app's `config_path` in the workflow profiles, or this path under
+    /// the home directory when none of them gives one
+    ConfigPath(&'static str),
+    /// Under the home directory, for configs the profiles don't point at
+    Home(&'static str),
+}
+
+struct KnownConfig {
+    app: &'static str,
+    /// What is copied, when an app has more than one config
+    kind: &'static str,
+    target: Target,
+    /// Places under the old home directory, most likely first
+    sources: &'static [&'static str],
+}
+
+const KNOWN_CONFIGS: &[KnownConfig] = &[
+    KnownConfig {
+        app: "Blender",
+        kind: "preferences",
+        target: Target::ConfigPath(".config/blender"),
+        sources: &[
+            ".config/blender",
+            ".var/app/org.blender.Blender/config/blender",
+            "AppData/Roaming/Blender Foundation/Blender",
+            "Library/Application Support/Blender",
+        ],
+    },
+    KnownConfig {
+        app: "Krita",
+        kind: "settings",
+        target: Target::Home(".config/kritarc"),
+        sources: &[
+            ".config/kritarc",
+            ".var/app/org.kde.krita/config/kritarc",
+            "AppData/Local/kritarc",
+            "Library/Preferences/kritarc",
+        ],
+    },
+    KnownConfig {
+        app: "Krita",
+        kind: "resources",
+        target: Target::Home(".local/share/krita"),
+        sources: &[
+            ".local/share/krita",
+            ".var/app/org.kde.krita/data/krita",
+            "AppData/Roaming/krita",
+            "Library/Application Support/krita",
+        ],
+    },
+    KnownConfig {
+        app: "GIMP",
+        kind: "settings",
+        target: Target::Home(".config/GIMP"),
+        sources: &[
+            ".config/GIMP",
+            ".var/app/org.gimp.GIMP/config/GIMP",
+            "AppData/Roaming/GIMP",
+            "Library/Application Support/GIMP",
+        ],
+    },
+    KnownConfig {
+        app: "Darktable",
+        kind: "library",
+        target: Target::ConfigPath(".config/darktable"),
+        sources: &[
+            ".config/darktable",
+            ".var/app/org.darktable.Darktable/config/darktable",
+            "AppData/Local/darktable",
+        ],
+    },
+    KnownConfig {
+        app: "Ardour",
+        kind: "settings",
+        target: Target::ConfigPath(".config/ardour8"),
+        // Ardour takes over the settings of the previous major version
+        sources: &[
+            ".config/ardour8",
+            ".config/ardour7",
+            ".config/ardour6",
+            "AppData/Local/Ardour8",
+            "AppData/Local/Ardour7",
+            "Library/Preferences/Ardour8",
+            "Library/Preferences/Ardour7",
+        ],
+    },
+];
+
+/// What to do with a file that exists here with different contents
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum ConflictPolicy {
+    /// Keep the file here
+    Skip,
+    /// Replace it
+    Overwrite,
+    /// Replace it, keeping a copy next to it the first time
+    #[default]
+    Backup,
+}
+
+impl ConflictPolicy {
+    pub const ALL: [ConflictPolicy; 3] = [
+        ConflictPolicy::Backup,
+        ConflictPolicy::Skip,
+        ConflictPolicy::Overwrite,
+    ];
+}
+
+impl fmt::Display for ConflictPolicy {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let label = match self {
+            ConflictPolicy::Skip => "Keep existing files",
+            ConflictPolicy::Overwrite => "Overwrite existing files",
+            ConflictPolicy::Backup => "Back up and replace existing files",
+        };
+        write!(f, "{}", label)
+    }
+}
+
+/// One app config found in the source
+#[derive(Debug, Clone)]
+pub struct MigrationItem {
+    pub app: String,
+    pub kind: String,
+    pub source: PathBuf,
+    pub target: PathBuf,
+    /// Files under `source`, relative to it; empty when `source` is a file
+    pub files: Vec<PathBuf>,
+    pub bytes: u64,
+    /// Files that exist at the target with different contents
+    pub conflicts: Vec<PathBuf>,
+    /// Files already at the target as they are in the source
+    pub unchanged: usize,
+}
+
+impl MigrationItem {
+    pub fn file_count(&self) -> usize {
+        self.files.len().max(1)
+    }
+}
+
+/// What a migration would copy; nothing has been written yet
+#[derive(Debug, Clone)]
+pub struct MigrationPlan {
+    pub source_home: PathBuf,
+    pub items: Vec<MigrationItem>,
+}
+
+/// What applying a plan did
+#[derive(Debug, Clone, Default)]
+pub struct MigrationReport {
+    pub copied: Vec<PathBuf>,
+    /// Conflicting files left alone under [`ConflictPolicy::Skip`]
+    pub skipped: Vec<PathBuf>,
+    /// Copies kept of replaced files
+    pub backed_up: Vec<PathBuf>,
+    /// Files that couldn't be copied, with why
+    pub failed: Vec<(PathBuf, String)>,
+}
+
+/// Find the known app configs in `source` without copying anything
+pub fn plan(source: &MigrationSource) -> Result<MigrationPlan> {
+    let home =
+        dirs::home_dir().ok_or_else(|| WorkflowError::Config("No home directory".to_string()))?;
+    let config = WorkflowConfig::load().unwrap_or_default();
+    plan_into(source.home_dir()?, &home, &config)
+}
+
+/// [`plan`] for copying into `home`
+fn plan_into(source_home: PathBuf, home: &Path, config: &WorkflowConfig) -> Result<MigrationPlan> {
+    let mut items = Vec::new();
+    for known in KNOWN_CONFIGS {
+        let Some(found) = known
+            .sources
+            .iter()
+            .map(|s| source_home.join(s))
+            .find(|p| p.exists())
+        else {
+            continue;
+        };
+        let target = target_path(known, config, home);
+        // Pointed at this system's own home directory
+        if same_file(&found, &target) {
+            continue;
+        }
+        items.extend(plan_item(known, found, target)?);
+    }
+
+    Ok(MigrationPlan { source_home, items })
+}
+
+/// Copy every known app config in `source`, backing up files it replaces
+pub fn import_from(source: MigrationSource) -> Result<MigrationReport> {
+    Ok(plan(&source)?.apply(ConflictPolicy::default()))
+}
+
+impl MigrationPlan {
+    pub fn is_empty(&self) -> bool {
+        self.items.is_empty()
+    }
+
+    /// What would be copied, one line per config, then its conflicts
+    pub fn listing(&self) -> Vec<String> {
+        let mut lines = Vec::new();
+        for item in &self.items {
+            lines.push(format!(
+                "{} {}: {} -> {} ({} file{}, {}{})",
+                item.app,
+                item.kind,
+                item.source.display(),
+                item.target.display(),
+                item.file_count(),
+                if item.file_count() == 1 { "" } else { "s" },
+                format_bytes(item.bytes),
+                match item.unchanged {
+                    0 => String::new(),
+                    n if n == item.file_count() => ", already here".to_string(),
+                    n => format!(", {} already here", n),
+                },
+            ));
+            for conflict in &item.conflicts {
+                lines.push(format!("  exists: {}", conflict.display()));
+            }
+        }
+        lines
+    }
+
+    pub fn conflict_count(&self) -> usize {
+        self.items.iter().map(|i| i.conflicts.len()).sum()
+    }
+
+    /// Copy the planned configs. A file that fails doesn't stop the rest.
+    pub fn apply(&self, policy: ConflictPolicy) -> MigrationReport {
+        let mut report = MigrationReport::default();
+        for item in &self.items {
+            if item.files.is_empty() {
+                copy_file(&item.source, &item.target, policy, &mut report);
+            } else {
+                for file in &item.files {
+                    copy_file(
+                        &item.source.join(file),
+                        &item.target.join(file),
+                        policy,
+                        &mut report,
+                    );
+                }
+            }
+        }
+        report
+    }
+}
+
+/// Previous systems on mounted drives, for offering as a source
+pub fn find_sources() -> Vec<MigrationSource> {
+    let Ok(mounts) = fs::read_to_string("/proc/self/mounts") else {
+        return Vec::new();
+    };
+    let mut roots: Vec<PathBuf> = mounts
+        .lines()
+        .filter_map(|line| {
+            let mut fields = line.split_whitespace();
+            let _device = fields.next()?;
+            let mount_point = fields.next()?.replace("\\040", " ");
+            let fs_type = fields.next()?;
+            let virtual_fs = VIRTUAL_FILESYSTEMS.contains(&fs_type);
+            let system = SYSTEM_MOUNTS.contains(&mount_point.as_str())
+                || SYSTEM_MOUNT_PREFIXES
+                    .iter()
+                    .any(|prefix| mount_point.starts_with(prefix));
+            (!virtual_fs && !system).then(|| PathBuf::from(mount_point))
+        })
+        .collect();
+    roots.sort();
+    roots.dedup();
+
+    roots
+        .into_iter()
+        .map(|root| MigrationSource::MountedSystem { root, user: None })
+        .filter(|source| source.home_dir().is_ok())
+        .collect()
+}
+
+/// `None` for a directory with nothing in it to copy
+fn plan_item(
+    known: &KnownConfig,
+    source: PathBuf,
+    target: PathBuf,
+) -> Result<Option<MigrationItem>> {
+    let mut item = MigrationItem {
+        app: known.app.to_string(),
+        kind: known.kind.to_string(),
+        source,
+        target,
+        files: Vec::new(),
+        bytes: 0,
+        conflicts: Vec::new(),
+        unchanged: 0,
+    };
+
+    if item.source.is_dir() {
+        let mut files = Vec::new();
+        collect_files(&item.source, Path::new(""), &mut files)?;
+        if files.is_empty() {
+            return Ok(None);
+        }
+        files.sort();
+        item.files = files;
+    }
+
+    let pairs: Vec<(PathBuf, PathBuf)> = if item.files.is_empty() {
+        vec![(item.source.clone(), item.target.clone())]
+    } else {
+        item.files
+            .iter()
+            .map(|f| (item.source.join(f), item.target.join(f)))
+            .collect()
+    };
+    for (from, to) in pairs {
+        item.bytes += fs::metadata(&from).map(|m| m.len()).unwrap_or(0);
+        if !to.exists() {
+            continue;
+        }
+        if same_contents(&from, &to) {
+            item.unchanged += 1;
+        } else {
+            item.conflicts.push(to);
+        }
+    }
+
+    Ok(Some(item))
+}
+
+/// Regular files under `dir`, relative to where the walk started. Symlinks
+/// are left out: they usually point into the old system, and may loop.
+fn collect_files(dir: &Path, relative: &Path, files: &mut Vec<PathBuf>) -> Result<()> {
+    for entry in fs::read_dir(dir)?.flatten() {
+        let Ok(file_type) = entry.file_type() else {
+            continue;
+        };
+        let name = entry.file_name();
+        let path = relative.join(&name);
+        if file_type.is_dir() {
+            collect_files(&entry.path(), &path, files)?;
+        } else if file_type.is_file() && !is_lock_file(&path) {
+            files.push(path);
+        }
+    }
+    Ok(())
+}
+
+fn is_lock_file(path: &Path) -> bool {
+    path.extension().is_some_and(|ext| ext == "lock")
+        || path.file_name().is_some_and(|name| name == "lock")
+}
+
+fn copy_file(from: &Path, to: &Path, policy: ConflictPolicy, report: &mut MigrationReport) {
+    if to.exists() {
+        if same_contents(from, to) {
+            return;
+        }
+        match policy {
+            ConflictPolicy::Skip => {
+                report.skipped.push(to.to_path_buf());
+                return;
+            }
+            ConflictPolicy::Overwrite => {}
+            ConflictPolicy::Backup => {
+                let mut backup = to.as_os_str().to_owned();
+                backup.push(BACKUP_SUFFIX);
+                let backup = PathBuf::from(backup);
+                if !backup.exists() {
+                    if let Err(e) = fs::copy(to, &backup) {
+                        report.failed.push((to.to_path_buf(), e.to_string()));
+                        return;
+                    }
+                    report.backed_up.push(backup);
+                }
+            }
+        }
+    }
+
+    let copied = to
+        .parent()
+        .map_or(Ok(()), fs::create_dir_all)
+        .and_then(|_| fs::copy(from, to));
+    match copied {
+        Ok(_) => report.copied.push(to.to_path_buf()),
+        Err(e) => report.failed.push((to.to_path_buf(), e.to_string())),
+    }
+}
+
+fn target_path(known: &KnownConfig, config: &WorkflowConfig, home: &Path) -> PathBuf {
+    match known.target {
+        Target::ConfigPath(fallback) => {
+            let mut profiles: Vec<_> = config.profiles.iter().collect();
+            profiles.sort_by(|a, b| a.0.cmp(b.0));
+            profiles
+                .into_iter()
+                .flat_map(|(_, profile)| &profile.applications)
+                .filter(|app| app.name.eq_ignore_ascii_case(known.app))
+                .find_map(|app| app.config_path.as_deref())
+                .map(|path| match path.strip_prefix("~") {
+                    Ok(rest) => home.join(rest),
+                    Err(_) => path.to_path_buf(),
+                })
+                .unwrap_or_else(|| home.join(fallback))
+        }
+        Target::Home(path) => home.join(path),
+    }
+}
+
+/// Home directory of `user` under the root of a system, or of the current
+/// user, or the only one there
+fn home_under(root: &Path, user: Option<&str>) -> Result<PathBuf> {
+    let current = dirs::home_dir()
+        .and_then(|h| h.file_name().map(|n| n.to_string_lossy().into_owned()))
+        .or_else(|| std::env::var("USER").ok());
+    let wanted = user.map(str::to_string).or(current);
+
+    // Linux, then Windows and macOS
+    let bases = [root.join("home"), root.join("Users")];
+    if let Some(name) = wanted {
+        if let Some(home) = bases
+            .iter()
+            .map(|b| b.join(&name))
+            .find(|h| looks_like_home(h))
+        {
+            return Ok(home);
+        }
+        if user.is_some() {
+            return Err(WorkflowError::Config(format!(
+                "No home directory for {} in {}",
+                name,
+                root.display()
+            )));
+        }
+    }
+
+    let homes: Vec<PathBuf> = bases
+        .iter()
+        .filter_map(|b| fs::read_dir(b).ok())
+        .flat_map(|entries| entries.flatten().map(|e| e.path()))
+        .filter(|h| looks_like_home(h))
+        .collect();
+    match homes.as_slice() {
+        [home] => Ok(home.clone()),
+        [] => Err(WorkflowError::Config(format!(
+            "No home directory found in {}",
+            root.display()
+        ))),
+        _ => Err(WorkflowError::Config(format!(
+            "{} has several home directories; choose the user",
+            root.display()
+        ))),
+    }
+}
+
+fn looks_like_home(dir: &Path) -> bool {
+    [".config", ".local", "AppData", "Library"]
+        .iter()
+        .any(|d| dir.join(d).is_dir())
+}
+
+fn same_file(a: &Path, b: &Path) -> bool {
+    match (fs::canonicalize(a), fs::canonicalize(b)) {
+        (Ok(a), Ok(b)) => a == b,
+        _ => false,
+    }
+}
+
+fn same_contents(a: &Path, b: &Path) -> bool {
+    let (Ok(meta_a), Ok(meta_b)) = (fs::metadata(a), fs::metadata(b)) else {
+        return false;
+    };
+    if meta_a.len() != meta_b.len() || meta_a.is_dir() != meta_b.is_dir() {
+        return false;
+    }
+    matches!((fs::read(a), fs::read(b)), (Ok(a), Ok(b)) if a == b)
+}
+
+fn format_bytes(bytes: u64) -> String {
+    const UNITS: [&str; 4] = ["B", "KB", "MB", "GB"];
+    let mut size = bytes as f64;
+    let mut unit = 0;
+    while size >= 1024.0 && unit < UNITS.len() - 1 {
+        size /= 1024.0;
+        unit += 1;
+    }
+    if unit == 0 {
+        format!("{} B", bytes)
+    } else {
+        format!("{:.1} {}", size, UNITS[unit])
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn write(path: &Path, content: &str) {
+        fs::create_dir_all(path.parent().unwrap()).unwrap();
+        fs::write(path, content).unwrap();
+    }
+
+    #[test]
+    fn test_home_under() {
+        let tmp = tempfile::tempdir().unwrap();
+        let root = tmp.path();
+        fs::create_dir_all(root.join("home/alice/.config")).unwrap();
+        fs::create_dir_all(root.join("Users/bob/AppData")).unwrap();
+        // Not a home directory: nothing an app keeps settings in
+        fs::create_dir_all(root.join("home/lost+found")).unwrap();
+
+        assert_eq!(
+            home_under(root, Some("alice")).unwrap(),
+            root.join("home/alice")
+        );
+        assert_eq!(
+            home_under(root, Some("bob")).unwrap(),
+            root.join("Users/bob")
+        );
+        assert!(home_under(root, Some("carol")).is_err());
+        assert!(home_under(root, Some("lost+found")).is_err());
+
+        // Without a user only one home will do
+        let tmp_single = tempfile::tempdir().unwrap();
+        let single = tmp_single.path();
+        fs::create_dir_all(single.join("home/rururu-only-user/.local")).unwrap();
+        assert_eq!(
+            home_under(single, None).unwrap(),
+            single.join("home/rururu-only-user")
+        );
+    }
+
+    #[test]
+    fn test_plan_finds_configs_and_conflicts() {
+        let tmp = tempfile::tempdir().unwrap();
+        let dir = tmp.path();
+        let (old, home) = (dir.join("old"), dir.join("new"));
+        // Flatpak Krita settings, and a Windows Blender with a lock file
+        write(
+            &old.join(".var/app/org.kde.krita/config/kritarc"),
+            "[General]\n",
+        );
+        write(
+            &old.join("AppData/Roaming/Blender Foundation/Blender/4.1/config/userpref.blend"),
+            "prefs",
+        );
+        write(
+            &old.join("AppData/Roaming/Blender Foundation/Blender/4.1/config/startup.blend"),
+            "startup",
+        );
+        write(
+            &old.join("AppData/Roaming/Blender Foundation/Blender/session.lock"),
+            "",
+        );
+        // Already here: one the same, one different
+        write(
+            &home.join(".config/blender/4.1/config/userpref.blend"),
+            "prefs",
+        );
+        write(
+            &home.join(".config/blender/4.1/config/startup.blend"),
+            "mine",
+        );
+        // An empty directory has nothing to copy
+        fs::create_dir_all(old.join(".config/GIMP")).unwrap();
+
+        let plan = plan_into(old.clone(), &home, &WorkflowConfig::default()).unwrap();
+        let apps: Vec<_> = plan
+            .items
+            .iter()
+            .map(|i| (i.app.as_str(), i.kind.as_str()))
+            .collect();
+        assert_eq!(apps, [("Blender", "preferences"), ("Krita", "settings")]);
+
+        let blender = &plan.items[0];
+        assert_eq!(blender.target, home.join(".config/blender"));
+        assert_eq!(
+            blender.files,
+            [
+                PathBuf::from("4.1/config/startup.blend"),
+                PathBuf::from("4.1/config/userpref.blend"),
+            ]
+        );
+        assert_eq!(blender.unchanged, 1);
+        assert_eq!(
+            blender.conflicts,
+            [home.join(".config/blender/4.1/config/startup.blend")]
+        );
+
+        let krita = &plan.items[1];
+        assert_eq!(
+            krita.source,
+            old.join(".var/app/org.kde.krita/config/kritarc")
+        );
+        assert_eq!(krita.target, home.join(".config/kritarc"));
+        assert!(krita.files.is_empty());
+        assert_eq!(krita.file_count(), 1);
+        assert_eq!(plan.conflict_count(), 1);
+
+        // Nothing is written by planning
+        assert!(!home.join(".config/kritarc").exists());
+    }
+
+    #[test]
+    fn test_apply_keeps_existing_files_unless_told() {
+        let tmp = tempfile::tempdir().unwrap();
+        let dir = tmp.path();
+        let (old, home) = (dir.join("old"), dir.join("new"));
+        write(&old.join(".config/GIMP/2.10/gimprc"), "old gimprc");
+        write(&old.join(".config/GIMP/2.10/sessionrc"), "old sessionrc");
+        write(&home.join(".config/GIMP/2.10/gimprc"), "my gimprc");
+        let gimprc = home.join(".config/GIMP/2.10/gimprc");
+        let sessionrc = home.join(".config/GIMP/2.10/sessionrc");
+        let plan = plan_into(old, &home, &WorkflowConfig::default()).unwrap();
+
+        let report = plan.apply(ConflictPolicy::Skip);
+        assert_eq!(report.copied, vec![sessionrc.clone()]);
+        assert_eq!(report.skipped, vec![gimprc.clone()]);
+        assert_eq!(fs::read_to_string(&gimprc).unwrap(), "my gimprc");
+        assert_eq!(fs::read_to_string(&sessionrc).unwrap(), "old sessionrc");
+
+        let report = plan.apply(ConflictPolicy::Backup);
+        let backup = home.join(".config/GIMP/2.10/gimprc.rururu-backup");
+        // The copy already there is identical, so only the conflict is copied
+        assert_eq!(report.copied, vec![gimprc.clone()]);
+        assert_eq!(report.backed_up, vec![backup.clone()]);
+        assert_eq!(fs::read_to_string(&gimprc).unwrap(), "old gimprc");
+        assert_eq!(fs::read_to_string(&backup).unwrap(), "my gimprc");
+    }
+
+    #[test]
+    fn test_copy_file_policies() {
+        let tmp = tempfile::tempdir().unwrap();
+        let dir = tmp.path();
+        let from = dir.join("from/rc");
+        let to = dir.join("to/nested/rc");
+        let backup = dir.join("to/nested/rc.rururu-backup");
+        write(&from, "new");
+
+        // Missing parents are created
+        let mut report = MigrationReport::default();
+        copy_file(&from, &to, ConflictPolicy::Skip, &mut report);
+        assert_eq!(report.copied, vec![to.clone()]);
+        assert_eq!(fs::read_to_string(&to).unwrap(), "new");
+
+        // The same contents are left as they are under any policy
+        let mut report = MigrationReport::default();
+        copy_file(&from, &to, ConflictPolicy::Overwrite, &mut report);
+        assert!(report.copied.is_empty() && report.skipped.is_empty());
+
+        fs::write(&to, "mine").unwrap();
+        let mut report = MigrationReport::default();
+        copy_file(&from, &to, ConflictPolicy::Skip, &mut report);
+        assert_eq!(report.skipped, vec![to.clone()]);
+        assert_eq!(fs::read_to_string(&to).unwrap(), "mine");
+
+        let mut report = MigrationReport::default();
+        copy_file(&from, &to, ConflictPolicy::Backup, &mut report);
+        assert_eq!(report.backed_up, vec![backup.clone()]);
+        assert_eq!(fs::read_to_string(&to).unwrap(), "new");
+        assert_eq!(fs::read_to_string(&backup).unwrap(), "mine");
+
+        // The first backup is the one kept
+        fs::write(&to, "edited").unwrap();
+        let mut report = MigrationReport::default();
+        copy_file(&from, &to, ConflictPolicy::Backup, &mut report);
+        assert!(report.backed_up.is_empty());
+        assert_eq!(fs::read_to_string(&backup).unwrap(), "mine");
+
+        fs::write(&to, "edited").unwrap();
+        let mut report = MigrationReport::default();
+        copy_file(&from, &to, ConflictPolicy::Overwrite, &mut report);
+        assert_eq!(report.copied, vec![to.clone()]);
+        assert_eq!(fs::read_to_string(&to).unwrap(), "new");
+
+        let mut report = MigrationReport::default();
+        copy_file(
+            &dir.join("missing"),
+            &dir.join("to/other"),
+            ConflictPolicy::Skip,
+            &mut report,
+        );
+        assert_eq!(report.failed.len(), 1);
+    }
+}
@@ -0,0 +1,118 @@
+//! What activating a profile would do, without doing it.
+//!
+//! The steps mirror `rururu-workflow activate` so the interactive selector
+//! and `activate --dry-run` can show them before anything is changed.
+
+use crate::apps::is_app_installed;
+use crate::cgroups;
+use crate::environment;
+use crate::profiles::WorkflowProfile;
+use crate::scratch;
+use crate::system::{realtime_audio_config_path, HIGH_PRIORITY_NICE};
+
+impl WorkflowProfile {
+    /// Activation steps in the order they run, one line each
+    pub fn activation_plan(&self) -> Vec<String> {
+        let settings = &self.system_settings;
+        let mut steps = vec![
+            format!(
+                "Set the CPU governor to {} (sudo)",
+                settings.cpu_governor.as_str()
+            ),
+            format!(
+                "Set vm.swappiness to {} (sudo)",
+                settings.swap_usage.swappiness()
+            ),
+        ];
+
+        if settings.realtime_audio {
+            if let Some(path) = realtime_audio_config_path() {
+                steps.push(format!(
+                    "Write a low-latency PipeWire drop-in to {}",
+                    path.display()
+                ));
+            }
+            steps.push("Add you to the audio group with realtime limits (sudo)".to_string());
+        }
+
+        match settings.resource_limits {
+            Some(ref limits) if cgroups::cgroup_v2_available() => {
+                for line in limits.summary() {
+                    steps.push(format!("Limit {}", line));
+                }
+                for process in &settings.high_priority_processes {
+                    steps.push(format!(
+                        "Move running {} processes into {}",
+                        process,
+                        cgroups::WORKFLOW_SLICE
+                    ));
+                }
+            }
+            _ => {
+                steps.push("Reset any workflow resource limits".to_string());
+                for process in &settings.high_priority_processes {
+                    steps.push(format!(
+                        "Renice running {} processes to {}",
+                        process, HIGH_PRIORITY_NICE
+                    ));
+                }
+            }
+        }
+
+        if let Some(ref config) = self.scratch_config {
+            match scratch::scratch_dir(config) {
+                Some(dir) => {
+                    steps.push(format!(
+                        "Create the scratch directory {} for caches and temp files",
+                        dir.display()
+                    ));
+                    let mut apps: Vec<&String> = config.app_cache_dirs.keys().collect();
+                    apps.sort();
+                    for app in apps {
+                        if app == scratch::OCIO_CACHE {
+                            if self.color_config.ocio_config.is_some() {
+                                steps.push("Copy the OCIO config there".to_string());
+                            }
+                        } else if scratch::cache_setting_known(self, app) {
+                            steps.push(format!("Point the {} cache there in its config", app));
+                        } else {
+                            steps.push(format!("Create a cache directory there for {}", app));
+                        }
+                    }
+                }
+                None => steps.push("Find no local drive for scratch storage".to_string()),
+            }
+        }
+
+        // `apply_app_settings` skips apps without settings or a config path
+        for app in self
+            .applications
+            .iter()
+            .filter(|a| !a.settings.is_empty() && a.config_path.is_some())
+            .filter(|a| is_app_installed(a))
+        {
+            steps.push(format!(
+                "Write {} setting(s) into the {} config",
+                app.settings.len(),
+                app.name
+            ));
+        }
+
+        match self.color_config.rendering_intent() {
+            Ok(intent) => steps.push(format!("Convert colors with the {} intent", intent)),
+            Err(e) => steps.push(format!("Skip the color settings: {}", e)),
+        }
+
+        let vars = environment::profile_environment(self);
+        if vars.is_empty() {
+            steps.push("Remove any workflow environment from the login session".to_string());
+        } else {
+            for (key, value) in vars {
+                steps.push(format!("Set {} = {} for the next login", key, value));
+            }
+        }
+
+        steps.push(format!("Record {} as the active workflow", self.name));
+        steps
+    }
+}
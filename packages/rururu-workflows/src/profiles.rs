@@ -1,3 +1,4 @@
+use crate::scratch;
 use serde::{Deserialize, Serialize};
 use std::collections::HashMap;
 use std::path::PathBuf;
@@ -53,6 +54,10 @@ impl WorkflowType {
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct WorkflowProfile {
+    /// Profile (or built-in workflow) this one was written against; see
+    /// [`crate::inheritance`]
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub extends: Option<String>,
     pub workflow_type: WorkflowType,
     pub name: String,
     pub description: String,
@@ -62,6 +67,10 @@ pub struct WorkflowProfile {
     pub keyboard_shortcuts: Vec<KeyboardShortcut>,
     pub startup_apps: Vec<String>,
     pub environment: HashMap<String, String>,
+    /// Fast storage for caches and temp files; `None` leaves them where
+    /// the apps put them
+    #[serde(default)]
+    pub scratch_config: Option<ScratchConfig>,
 }
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
@@ -71,6 +80,11 @@ pub struct AppConfig {
     pub package: String,
     pub flatpak_id: Option<String>,
     pub config_path: Option<PathBuf>,
+    /// `config_path` is the app's config directory, created when missing,
+    /// rather than its config file. A path that already is a directory is
+    /// taken as one either way.
+    #[serde(default)]
+    pub config_dir: bool,
     pub priority: AppPriority,
     pub settings: HashMap<String, String>,
 }
@@ -91,6 +105,41 @@ pub struct SystemSettings {
     pub realtime_audio: bool,
     pub high_priority_processes: Vec<String>,
     pub memory_pressure_threshold: u8,
+    /// cgroup limits applied through systemd slices; `None` renices
+    /// `high_priority_processes` instead
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub resource_limits: Option<ResourceLimits>,
+}
+
+/// cgroup v2 limits for the workflow's processes and for background work,
+/// applied by [`crate::cgroups`]
+#[derive(Debug, Clone, Default, Serialize, Deserialize, PartialEq, Eq)]
+pub struct ResourceLimits {
+    /// `rururu-workflow.slice`, which `high_priority_processes` move into
+    #[serde(default)]
+    pub workflow: SliceLimits,
+    /// The user manager's `background.slice`, where desktops run indexers,
+    /// sync clients and other background services
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub background: Option<SliceLimits>,
+}
+
+/// Unset fields leave systemd's default
+#[derive(Debug, Clone, Copy, Default, Serialize, Deserialize, PartialEq, Eq)]
+#[serde(default)]
+pub struct SliceLimits {
+    /// `CPUWeight`, 1–10000; systemd's default is 100
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub cpu_weight: Option<u32>,
+    /// `MemoryHigh`: reclaimed and throttled above this
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub memory_high_mb: Option<u64>,
+    /// `MemoryMax`: OOM-killed above this
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub memory_max_mb: Option<u64>,
+    /// `IOWeight`, 1–10000; systemd's default is 100
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub io_weight: Option<u32>,
 }
 
 #[derive(Debug, Clone, Copy, Serialize, Deserialize, PartialEq, Eq)]
@@ -101,6 +150,18 @@ pub enum CpuGovernor {
     Ondemand,
 }
 
+impl CpuGovernor {
+    /// Name used by cpufreq's `scaling_governor`
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            CpuGovernor::Performance => "performance",
+            CpuGovernor::Powersave => "powersave",
+            CpuGovernor::Schedutil => "schedutil",
+            CpuGovernor::Ondemand => "ondemand",
+        }
+    }
+}
+
 #[derive(Debug, Clone, Copy, Serialize, Deserialize, PartialEq, Eq)]
 pub enum SwapUsage {
     Minimal,    // swappiness = 10
@@ -108,6 +169,16 @@ pub enum SwapUsage {
     Aggressive, // swappiness = 100
 }
 
+impl SwapUsage {
+    pub fn swappiness(&self) -> u32 {
+        match self {
+            SwapUsage::Minimal => 10,
+            SwapUsage::Balanced => 60,
+            SwapUsage::Aggressive => 100,
+        }
+    }
+}
+
 #[derive(Debug, Clone, Copy, Serialize, Deserialize, PartialEq, Eq)]
 pub enum IoScheduler {
     Bfq,
@@ -124,6 +195,20 @@ pub struct ColorWorkflowConfig {
     pub default_intent: String,
 }
 
+/// Scratch space for render caches, proxies and temp files
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct ScratchConfig {
+    /// Scratch directory; unset picks one on the fastest local drive
+    pub path: Option<PathBuf>,
+    /// Variables pointed at subdirectories of the scratch directory,
+    /// e.g. `TMPDIR` → `tmp`
+    pub environment: HashMap<String, String>,
+    /// Cache subdirectories for apps that keep the cache location in their
+    /// own preferences, keyed by app name; `OCIO` gets a copy of the OCIO
+    /// config
+    pub app_cache_dirs: HashMap<String, String>,
+}
+
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct KeyboardShortcut {
     pub action: String,
@@ -134,6 +219,7 @@ pub struct KeyboardShortcut {
 impl WorkflowProfile {
     pub fn video_editor() -> Self {
         Self {
+            extends: None,
             workflow_type: WorkflowType::VideoEditor,
             name: "Video Editor".to_string(),
             description: "Optimized for video editing with DaVinci Resolve, Kdenlive".to_string(),
@@ -143,7 +229,10 @@ impl WorkflowProfile {
                     executable: "resolve".to_string(),
                     package: "davinci-resolve".to_string(),
                     flatpak_id: None,
-                    config_path: None,
+                    config_path: Some(PathBuf::from(
+                        "~/.local/share/DaVinciResolve/configs/config.dat",
+                    )),
+                    config_dir: false,
                     priority: AppPriority::Primary,
                     settings: HashMap::new(),
                 },
@@ -153,6 +242,7 @@ impl WorkflowProfile {
                     package: "kdenlive".to_string(),
                     flatpak_id: Some("org.kde.kdenlive".to_string()),
                     config_path: None,
+                    config_dir: false,
                     priority: AppPriority::Secondary,
                     settings: HashMap::new(),
                 },
@@ -162,6 +252,7 @@ impl WorkflowProfile {
                     package: "handbrake".to_string(),
                     flatpak_id: Some("fr.handbrake.ghb".to_string()),
                     config_path: None,
+                    config_dir: false,
                     priority: AppPriority::Optional,
                     settings: HashMap::new(),
                 },
@@ -174,6 +265,18 @@ impl WorkflowProfile {
                 realtime_audio: false,
                 high_priority_processes: vec!["resolve".to_string(), "kdenlive".to_string()],
                 memory_pressure_threshold: 90,
+                resource_limits: Some(ResourceLimits {
+                    workflow: SliceLimits {
+                        cpu_weight: Some(400),
+                        io_weight: Some(400),
+                        ..SliceLimits::default()
+                    },
+                    background: Some(SliceLimits {
+                        cpu_weight: Some(50),
+                        io_weight: Some(50),
+                        ..SliceLimits::default()
+                    }),
+                }),
             },
             color_config: ColorWorkflowConfig {
                 working_space: "Rec.709".to_string(),
@@ -193,11 +296,25 @@ impl WorkflowProfile {
             )]
             .into_iter()
             .collect(),
+            scratch_config: Some(ScratchConfig {
+                path: None,
+                environment: [("TMPDIR".to_string(), "tmp".to_string())]
+                    .into_iter()
+                    .collect(),
+                app_cache_dirs: [
+                    ("DaVinci Resolve".to_string(), "resolve-cache".to_string()),
+                    ("Kdenlive".to_string(), "kdenlive-proxies".to_string()),
+                    (scratch::OCIO_CACHE.to_string(), "ocio".to_string()),
+                ]
+                .into_iter()
+                .collect(),
+            }),
         }
     }
 
     pub fn three_d_artist() -> Self {
         Self {
+            extends: None,
             workflow_type: WorkflowType::ThreeDArtist,
             name: "3D Artist".to_string(),
             description: "Optimized for 3D modeling and rendering with Blender".to_string(),
@@ -208,6 +325,7 @@ impl WorkflowProfile {
                     package: "blender".to_string(),
                     flatpak_id: Some("org.blender.Blender".to_string()),
                     config_path: Some(PathBuf::from("~/.config/blender")),
+                    config_dir: true,
                     priority: AppPriority::Primary,
                     settings: [("cycles.device".to_string(), "GPU".to_string())]
                         .into_iter()
@@ -219,6 +337,7 @@ impl WorkflowProfile {
                     package: "freecad".to_string(),
                     flatpak_id: Some("org.freecadweb.FreeCAD".to_string()),
                     config_path: None,
+                    config_dir: false,
                     priority: AppPriority::Secondary,
                     settings: HashMap::new(),
                 },
@@ -231,6 +350,17 @@ impl WorkflowProfile {
                 realtime_audio: false,
                 high_priority_processes: vec!["blender".to_string()],
                 memory_pressure_threshold: 95,
+                resource_limits: Some(ResourceLimits {
+                    workflow: SliceLimits {
+                        cpu_weight: Some(400),
+                        ..SliceLimits::default()
+                    },
+                    background: Some(SliceLimits {
+                        cpu_weight: Some(50),
+                        io_weight: Some(50),
+                        ..SliceLimits::default()
+                    }),
+                }),
             },
             color_config: ColorWorkflowConfig {
                 working_space: "ACEScg".to_string(),
@@ -256,11 +386,25 @@ impl WorkflowProfile {
             ]
             .into_iter()
             .collect(),
+            scratch_config: Some(ScratchConfig {
+                path: None,
+                environment: [("TMPDIR".to_string(), "tmp".to_string())]
+                    .into_iter()
+                    .collect(),
+                // Blender's temp dir holds its render cache
+                app_cache_dirs: [
+                    ("Blender".to_string(), "blender-temp".to_string()),
+                    (scratch::OCIO_CACHE.to_string(), "ocio".to_string()),
+                ]
+                .into_iter()
+                .collect(),
+            }),
         }
     }
 
     pub fn two_d_designer() -> Self {
         Self {
+            extends: None,
             workflow_type: WorkflowType::TwoDDesigner,
             name: "2D Designer".to_string(),
             description: "Optimized for 2D graphics with Krita, GIMP, Inkscape".to_string(),
@@ -271,6 +415,7 @@ impl WorkflowProfile {
                     package: "krita".to_string(),
                     flatpak_id: Some("org.kde.krita".to_string()),
                     config_path: Some(PathBuf::from("~/.config/krita")),
+                    config_dir: true,
                     priority: AppPriority::Primary,
                     settings: HashMap::new(),
                 },
@@ -280,6 +425,7 @@ impl WorkflowProfile {
                     package: "gimp".to_string(),
                     flatpak_id: Some("org.gimp.GIMP".to_string()),
                     config_path: None,
+                    config_dir: false,
                     priority: AppPriority::Secondary,
                     settings: HashMap::new(),
                 },
@@ -289,6 +435,7 @@ impl WorkflowProfile {
                     package: "inkscape".to_string(),
                     flatpak_id: Some("org.inkscape.Inkscape".to_string()),
                     config_path: None,
+                    config_dir: false,
                     priority: AppPriority::Secondary,
                     settings: HashMap::new(),
                 },
@@ -301,6 +448,7 @@ impl WorkflowProfile {
                 realtime_audio: false,
                 high_priority_processes: vec!["krita".to_string()],
                 memory_pressure_threshold: 85,
+                resource_limits: None,
             },
             color_config: ColorWorkflowConfig {
                 working_space: "Adobe RGB".to_string(),
@@ -315,11 +463,13 @@ impl WorkflowProfile {
             }],
             startup_apps: vec![],
             environment: HashMap::new(),
+            scratch_config: None,
         }
     }
 
     pub fn audio_producer() -> Self {
         Self {
+            extends: None,
             workflow_type: WorkflowType::AudioProducer,
             name: "Audio Producer".to_string(),
             description: "Optimized for audio production with Ardour, Bitwig".to_string(),
@@ -330,6 +480,7 @@ impl WorkflowProfile {
                     package: "ardour".to_string(),
                     flatpak_id: None,
                     config_path: Some(PathBuf::from("~/.config/ardour8")),
+                    config_dir: true,
                     priority: AppPriority::Primary,
                     settings: HashMap::new(),
                 },
@@ -339,6 +490,7 @@ impl WorkflowProfile {
                     package: "bitwig-studio".to_string(),
                     flatpak_id: Some("com.bitwig.BitwigStudio".to_string()),
                     config_path: None,
+                    config_dir: false,
                     priority: AppPriority::Primary,
                     settings: HashMap::new(),
                 },
@@ -348,9 +500,25 @@ impl WorkflowProfile {
                     package: "audacity".to_string(),
                     flatpak_id: Some("org.audacityteam.Audacity".to_string()),
                     config_path: None,
+                    config_dir: false,
                     priority: AppPriority::Secondary,
                     settings: HashMap::new(),
                 },
+                AppConfig {
+                    name: "PipeWire".to_string(),
+                    executable: "pipewire".to_string(),
+                    package: "pipewire".to_string(),
+                    flatpak_id: None,
+                    config_path: Some(PathBuf::from("~/.config/pipewire")),
+                    config_dir: true,
+                    priority: AppPriority::Optional,
+                    settings: [
+                        ("default.clock.quantum".to_string(), "256".to_string()),
+                        ("default.clock.min-quantum".to_string(), "64".to_string()),
+                    ]
+                    .into_iter()
+                    .collect(),
+                },
             ],
             system_settings: SystemSettings {
                 cpu_governor: CpuGovernor::Performance,
@@ -365,6 +533,18 @@ impl WorkflowProfile {
                     "wireplumber".to_string(),
                 ],
                 memory_pressure_threshold: 80,
+                resource_limits: Some(ResourceLimits {
+                    workflow: SliceLimits {
+                        cpu_weight: Some(1000),
+                        io_weight: Some(1000),
+                        ..SliceLimits::default()
+                    },
+                    background: Some(SliceLimits {
+                        cpu_weight: Some(20),
+                        io_weight: Some(20),
+                        ..SliceLimits::default()
+                    }),
+                }),
             },
             color_config: ColorWorkflowConfig {
                 working_space: "sRGB".to_string(),
@@ -384,11 +564,13 @@ impl WorkflowProfile {
             ]
             .into_iter()
             .collect(),
+            scratch_config: None,
         }
     }
 
     pub fn photographer() -> Self {
         Self {
+            extends: None,
             workflow_type: WorkflowType::Photographer,
             name: "Photographer".to_string(),
             description: "Optimized for photo editing with Darktable, RawTherapee".to_string(),
@@ -399,6 +581,7 @@ impl WorkflowProfile {
                     package: "darktable".to_string(),
                     flatpak_id: Some("org.darktable.Darktable".to_string()),
                     config_path: Some(PathBuf::from("~/.config/darktable")),
+                    config_dir: true,
                     priority: AppPriority::Primary,
                     settings: HashMap::new(),
                 },
@@ -408,6 +591,7 @@ impl WorkflowProfile {
                     package: "rawtherapee".to_string(),
                     flatpak_id: Some("com.rawtherapee.RawTherapee".to_string()),
                     config_path: None,
+                    config_dir: false,
                     priority: AppPriority::Secondary,
                     settings: HashMap::new(),
                 },
@@ -417,6 +601,7 @@ impl WorkflowProfile {
                     package: "digikam".to_string(),
                     flatpak_id: Some("org.kde.digikam".to_string()),
                     config_path: None,
+                    config_dir: false,
                     priority: AppPriority::Secondary,
                     settings: HashMap::new(),
                 },
@@ -429,6 +614,7 @@ impl WorkflowProfile {
                 realtime_audio: false,
                 high_priority_processes: vec!["darktable".to_string()],
                 memory_pressure_threshold: 85,
+                resource_limits: None,
             },
             color_config: ColorWorkflowConfig {
                 working_space: "ProPhoto RGB".to_string(),
@@ -443,6 +629,7 @@ impl WorkflowProfile {
             }],
             startup_apps: vec![],
             environment: HashMap::new(),
+            scratch_config: None,
         }
     }
 
@@ -459,6 +646,7 @@ impl WorkflowProfile {
 
     fn general() -> Self {
         Self {
+            extends: None,
             workflow_type: WorkflowType::General,
             name: "General".to_string(),
             description: "Balanced settings for general creative work".to_string(),
@@ -471,6 +659,7 @@ impl WorkflowProfile {
                 realtime_audio: false,
                 high_priority_processes: vec![],
                 memory_pressure_threshold: 80,
+                resource_limits: None,
             },
             color_config: ColorWorkflowConfig {
                 working_space: "sRGB".to_string(),
@@ -481,6 +670,7 @@ impl WorkflowProfile {
             keyboard_shortcuts: vec![],
             startup_apps: vec![],
             environment: HashMap::new(),
+            scratch_config: None,
         }
     }
 }
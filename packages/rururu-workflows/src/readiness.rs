@@ -0,0 +1,126 @@
+//! How well the machine suits a workflow.
+//!
+//! The score is a rough guide for picking a workflow, not a benchmark: it
+//! weighs memory, CPU cores and a dedicated GPU against what the profile's
+//! applications are comfortable with, plus how many primary apps are
+//! installed.
+
+use crate::apps::is_app_installed;
+use crate::profiles::{AppPriority, WorkflowProfile, WorkflowType};
+use crate::system::SystemInfo;
+
+const MEMORY_WEIGHT: f32 = 30.0;
+const CPU_WEIGHT: f32 = 20.0;
+const GPU_WEIGHT: f32 = 20.0;
+const APPS_WEIGHT: f32 = 30.0;
+
+/// Share of the GPU points left without a dedicated GPU when one is wanted
+const INTEGRATED_GPU_CREDIT: f32 = 0.25;
+
+struct Requirements {
+    memory_gb: u64,
+    cpu_cores: usize,
+    dedicated_gpu: bool,
+}
+
+fn requirements(workflow_type: WorkflowType) -> Requirements {
+    let (memory_gb, cpu_cores, dedicated_gpu) = match workflow_type {
+        WorkflowType::VideoEditor => (32, 8, true),
+        WorkflowType::ThreeDArtist => (32, 8, true),
+        WorkflowType::Photographer => (16, 6, false),
+        WorkflowType::TwoDDesigner => (16, 4, false),
+        WorkflowType::AudioProducer => (16, 4, false),
+        WorkflowType::Developer => (16, 4, false),
+        WorkflowType::General => (8, 2, false),
+    };
+    Requirements {
+        memory_gb,
+        cpu_cores,
+        dedicated_gpu,
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Readiness {
+    /// 0-100
+    pub score: u8,
+    /// What keeps the score down, if anything
+    pub shortfalls: Vec<String>,
+}
+
+impl WorkflowProfile {
+    pub fn readiness(&self, info: &SystemInfo) -> Readiness {
+        let wanted = requirements(self.workflow_type);
+        let mut shortfalls = Vec::new();
+
+        // `memory_total_gb` rounds down, so 32 GB of RAM reports 31
+        let memory = ((info.memory_total_gb + 1) as f32 / wanted.memory_gb as f32).min(1.0);
+        if memory < 1.0 {
+            shortfalls.push(format!(
+                "{} GB of memory, {} GB recommended",
+                info.memory_total_gb, wanted.memory_gb
+            ));
+        }
+
+        let cpu = (info.cpu_count as f32 / wanted.cpu_cores as f32).min(1.0);
+        if cpu < 1.0 {
+            shortfalls.push(format!(
+                "{} CPU threads, {} recommended",
+                info.cpu_count, wanted.cpu_cores
+            ));
+        }
+
+        let gpu = if !wanted.dedicated_gpu || has_dedicated_gpu(info) {
+            1.0
+        } else {
+            shortfalls.push("No dedicated GPU detected".to_string());
+            INTEGRATED_GPU_CREDIT
+        };
+
+        let apps = self.installed_share(&mut shortfalls);
+
+        let score =
+            memory * MEMORY_WEIGHT + cpu * CPU_WEIGHT + gpu * GPU_WEIGHT + apps * APPS_WEIGHT;
+        Readiness {
+            score: score.round().clamp(0.0, 100.0) as u8,
+            shortfalls,
+        }
+    }
+
+    /// Share of primary apps installed, or of all apps if none is primary
+    fn installed_share(&self, shortfalls: &mut Vec<String>) -> f32 {
+        let primary: Vec<_> = self
+            .applications
+            .iter()
+            .filter(|a| a.priority == AppPriority::Primary)
+            .collect();
+        let counted = if primary.is_empty() {
+            self.applications.iter().collect()
+        } else {
+            primary
+        };
+        if counted.is_empty() {
+            return 1.0;
+        }
+
+        let missing: Vec<&str> = counted
+            .iter()
+            .filter(|a| !is_app_installed(a))
+            .map(|a| a.name.as_str())
+            .collect();
+        if !missing.is_empty() {
+            shortfalls.push(format!("Not installed: {}", missing.join(", ")));
+        }
+
+        1.0 - missing.len() as f32 / counted.len() as f32
+    }
+}
+
+/// NVIDIA, AMD or Intel Arc graphics, going by the driver and `lspci`
+fn has_dedicated_gpu(info: &SystemInfo) -> bool {
+    let gpu = info.gpu.to_lowercase();
+    info.has_nvidia
+        || ["nvidia", "radeon", "amd/ati", " arc "]
+            .iter()
+            .any(|vendor| gpu.contains(vendor))
+}
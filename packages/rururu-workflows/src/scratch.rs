@@ -0,0 +1,387 @@
+//! Scratch storage for render caches, proxies and temp files.
+//!
+//! Without a configured path the scratch directory goes on the fastest
+//! fixed local drive: NVMe before SATA SSDs before disks,
+//! never removable media. When that drive holds the home directory it is
+//! `~/.cache/rururu-scratch`; otherwise a `rururu-scratch-<uid>` directory at
+//! the root of the first mount on it the user can write to. Either way only
+//! its owner can get in. Variables pointing into it reach apps through the
+//! workflow's login environment, apps that keep their cache location in
+//! their own preferences get it written there, and the OCIO config can be
+//! copied onto it.
+
+use crate::profiles::{AppConfig, ScratchConfig, WorkflowProfile};
+use crate::{expand_home, Result, WorkflowError};
+use nix::unistd::{access, getuid, AccessFlags};
+use rururu_utils::SystemInfo;
+use std::fs::{self, DirBuilder};
+use std::os::unix::fs::{DirBuilderExt, MetadataExt, PermissionsExt};
+use std::path::{Path, PathBuf};
+
+const SCRATCH_DIR: &str = "rururu-scratch";
+
+/// `app_cache_dirs` entry the profile's OCIO config is copied into, so its
+/// LUTs load from the scratch drive
+pub const OCIO_CACHE: &str = "OCIO";
+
+/// Settings holding an app's cache or temp location, by executable, in the
+/// form `apply_app_settings` writes them
+const CACHE_SETTINGS: &[(&str, &str)] = &[
+    ("blender", "preferences.filepaths.temporary_directory"),
+    ("resolve", "CacheClipDir"),
+];
+
+/// Mounts that never hold scratch data even on a fast drive
+const SYSTEM_MOUNTS: &[&str] = &["/boot", "/efi", "/boot/efi"];
+
+/// The scratch directory with its app cache directories, once created
+#[derive(Debug, Clone)]
+pub struct PreparedScratch {
+    pub dir: PathBuf,
+    /// App name and the cache directory to point it at
+    pub app_dirs: Vec<(String, PathBuf)>,
+}
+
+/// Where `config` puts scratch data, if anywhere
+pub fn scratch_dir(config: &ScratchConfig) -> Option<PathBuf> {
+    match config.path {
+        Some(ref path) => expand_home(path),
+        None => suggest_scratch_dir(),
+    }
+}
+
+/// Variables the scratch config sets, as full paths
+pub fn scratch_environment(config: &ScratchConfig) -> Vec<(String, String)> {
+    let Some(dir) = scratch_dir(config) else {
+        return Vec::new();
+    };
+    config
+        .environment
+        .iter()
+        .map(|(key, sub)| (key.clone(), dir.join(sub).display().to_string()))
+        .collect()
+}
+
+/// Create the scratch directory and every subdirectory the config names
+pub fn prepare(config: &ScratchConfig) -> Result<Option<PreparedScratch>> {
+    let Some(dir) = scratch_dir(config) else {
+        return Ok(None);
+    };
+
+    create_private_dir(&dir)?;
+    for sub in config.environment.values() {
+        fs::create_dir_all(dir.join(sub))?;
+    }
+    let mut app_dirs: Vec<(String, PathBuf)> = config
+        .app_cache_dirs
+        .iter()
+        .map(|(app, sub)| (app.clone(), dir.join(sub)))
+        .collect();
+    app_dirs.sort();
+    for (_, app_dir) in &app_dirs {
+        fs::create_dir_all(app_dir)?;
+    }
+
+    Ok(Some(PreparedScratch { dir, app_dirs }))
+}
+
+/// Create `dir` readable by its owner only. An existing directory has to
+/// belong to us and not be a symlink, since at a mount root someone else
+/// could have put it there first; its mode is tightened if needed.
+fn create_private_dir(dir: &Path) -> Result<()> {
+    if let Some(parent) = dir.parent() {
+        fs::create_dir_all(parent)?;
+    }
+    match DirBuilder::new().mode(0o700).create(dir) {
+        Ok(()) => return Ok(()),
+        Err(e) if e.kind() == std::io::ErrorKind::AlreadyExists => {}
+        Err(e) => return Err(e.into()),
+    }
+
+    let meta = fs::symlink_metadata(dir)?;
+    if !meta.is_dir() || meta.uid() != getuid().as_raw() {
+        return Err(WorkflowError::Config(format!(
+            "{} exists but is not a directory of ours",
+            dir.display()
+        )));
+    }
+    if meta.permissions().mode() & 0o077 != 0 {
+        fs::set_permissions(dir, fs::Permissions::from_mode(0o700))?;
+    }
+    Ok(())
+}
+
+/// `app` with its scratch cache directory added to its settings, when the
+/// profile gives it one and we know which setting holds it. Apps without a
+/// config path are left alone, since there is nowhere to write it.
+pub fn with_cache_setting(app: &AppConfig, prepared: &PreparedScratch) -> AppConfig {
+    let mut app = app.clone();
+    let setting = CACHE_SETTINGS
+        .iter()
+        .find(|(exe, _)| app.executable.eq_ignore_ascii_case(exe))
+        .map(|(_, key)| key.to_string());
+    let dir = prepared
+        .app_dirs
+        .iter()
+        .find(|(name, _)| *name == app.name)
+        .map(|(_, dir)| dir.display().to_string());
+    if let (Some(key), Some(dir), Some(_)) = (setting, dir, &app.config_path) {
+        app.settings.insert(key, dir);
+    }
+    app
+}
+
+/// Whether [`with_cache_setting`] writes the cache location for the app
+/// named `name` in `profile`
+pub fn cache_setting_known(profile: &WorkflowProfile, name: &str) -> bool {
+    profile
+        .applications
+        .iter()
+        .filter(|app| app.name == name && app.config_path.is_some())
+        .any(|app| {
+            CACHE_SETTINGS
+                .iter()
+                .any(|(exe, _)| app.executable.eq_ignore_ascii_case(exe))
+        })
+}
+
+/// The OCIO config apps should load: the copy on scratch when there is
+/// one, otherwise the profile's own if it exists
+pub fn ocio_config(profile: &WorkflowProfile) -> Option<PathBuf> {
+    let config = profile.color_config.ocio_config.as_ref()?;
+    let cached = profile
+        .scratch_config
+        .as_ref()
+        .and_then(|scratch| {
+            let sub = scratch.app_cache_dirs.get(OCIO_CACHE)?;
+            Some(scratch_dir(scratch)?.join(sub).join(config.file_name()?))
+        })
+        .filter(|copy| copy.is_file());
+    cached.or_else(|| config.exists().then(|| config.clone()))
+}
+
+/// Copy the directory holding the OCIO `config`, LUTs included, into
+/// `cache`. Files that haven't changed since the last copy are skipped, so
+/// this is cheap on every activation after the first. Returns the copied
+/// config file.
+pub fn cache_ocio_config(config: &Path, cache: &Path) -> Result<PathBuf> {
+    let (Some(source), Some(name)) = (config.parent(), config.file_name()) else {
+        return Err(WorkflowError::Config(format!(
+            "{} is not an OCIO config file",
+            config.display()
+        )));
+    };
+    if !config.is_file() {
+        return Err(WorkflowError::Config(format!(
+            "OCIO config {} not found",
+            config.display()
+        )));
+    }
+    copy_changed(source, cache, 0)?;
+    Ok(cache.join(name))
+}
+
+fn copy_changed(from: &Path, to: &Path, depth: usize) -> Result<()> {
+    // Symlinks are followed, so a link back up the tree must not recurse
+    // forever; real configs are two or three levels deep
+    if depth > 8 {
+        return Ok(());
+    }
+
+    fs::create_dir_all(to)?;
+    for entry in fs::read_dir(from)? {
+        let entry = entry?;
+        let target = to.join(entry.file_name());
+        let meta = fs::metadata(entry.path())?;
+        if meta.is_dir() {
+            copy_changed(&entry.path(), &target, depth + 1)?;
+        } else if meta.is_file() {
+            let current = fs::metadata(&target).is_ok_and(|t| {
+                t.len() == meta.len()
+                    && matches!((t.modified(), meta.modified()), (Ok(t), Ok(m)) if t >= m)
+            });
+            if !current {
+                fs::copy(entry.path(), &target)?;
+            }
+        }
+    }
+    Ok(())
+}
+
+/// A scratch directory on the fastest local drive
+pub fn suggest_scratch_dir() -> Option<PathBuf> {
+    let drives = SystemInfo::local_drives();
+    let fastest = drives.first()?.speed;
+    let fast_disks: Vec<&String> = drives
+        .iter()
+        .filter(|d| d.speed == fastest)
+        .map(|d| &d.name)
+        .collect();
+
+    let mounts = mounts();
+    let home = dirs::home_dir()?;
+    let home_disk = mounts
+        .iter()
+        .filter(|(_, mount)| home.starts_with(mount))
+        .max_by_key(|(_, mount)| mount.as_os_str().len())
+        .and_then(|(device, _)| disk_of(device));
+    if home_disk.as_ref().is_some_and(|d| fast_disks.contains(&d)) {
+        return dirs::cache_dir().map(|cache| cache.join(SCRATCH_DIR));
+    }
+
+    mounts
+        .iter()
+        .filter(|(_, mount)| !SYSTEM_MOUNTS.iter().any(|m| mount == Path::new(m)))
+        .filter(|(device, _)| disk_of(device).is_some_and(|d| fast_disks.contains(&&d)))
+        .find(|(_, mount)| access(mount, AccessFlags::W_OK).is_ok())
+        .map(|(_, mount)| mount.join(format!("{}-{}", SCRATCH_DIR, getuid())))
+}
+
+/// Writable block-device mounts from `/proc/mounts`: device and mount point
+fn mounts() -> Vec<(PathBuf, PathBuf)> {
+    fs::read_to_string("/proc/mounts")
+        .unwrap_or_default()
+        .lines()
+        .filter_map(|line| {
+            let mut fields = line.split_whitespace();
+            let device = fields.next()?;
+            let mount = fields.next()?;
+            let options = fields.nth(1)?;
+            (device.starts_with("/dev/") && !options.split(',').any(|o| o == "ro"))
+                .then(|| (PathBuf::from(device), PathBuf::from(unescape(mount))))
+        })
+        .collect()
+}
+
+/// `/proc/mounts` writes spaces and tabs in paths as octal escapes
+fn unescape(field: &str) -> String {
+    field
+        .replace("\\040", " ")
+        .replace("\\011", "\t")
+        .replace("\\134", "\\")
+}
+
+/// The whole disk a device lives on, as named in `/sys/block`: partitions
+/// map to their disk, and device-mapper volumes (LUKS, LVM) to the disk of
+/// the device underneath
+fn disk_of(device: &Path) -> Option<String> {
+    let name = fs::canonicalize(device)
+        .ok()?
+        .file_name()?
+        .to_string_lossy()
+        .to_string();
+    disk_of_block(&name, 0)
+}
+
+fn disk_of_block(name: &str, depth: usize) -> Option<String> {
+    // Stacks deeper than LUKS on LVM on a partition don't occur in practice
+    if depth > 4 {
+        return None;
+    }
+
+    let sys = PathBuf::from("/sys/class/block").join(name);
+    let slave = fs::read_dir(sys.join("slaves"))
+        .ok()
+        .and_then(|mut entries| entries.next())
+        .and_then(|entry| entry.ok());
+    if let Some(slave) = slave {
+        return disk_of_block(&slave.file_name().to_string_lossy(), depth + 1);
+    }
+
+    if sys.join("partition").exists() {
+        // .../block/nvme0n1/nvme0n1p2
+        let real = fs::canonicalize(&sys).ok()?;
+        return Some(real.parent()?.file_name()?.to_string_lossy().to_string());
+    }
+    Some(name.to_string())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::profiles::WorkflowType;
+
+    #[test]
+    fn test_private_dir_is_owner_only() {
+        let tmp = tempfile::tempdir().unwrap();
+        let dir = tmp.path().join("private");
+
+        create_private_dir(&dir).unwrap();
+        assert_eq!(
+            fs::metadata(&dir).unwrap().permissions().mode() & 0o777,
+            0o700
+        );
+
+        // A directory left open by an earlier run is tightened
+        fs::set_permissions(&dir, fs::Permissions::from_mode(0o777)).unwrap();
+        create_private_dir(&dir).unwrap();
+        assert_eq!(
+            fs::metadata(&dir).unwrap().permissions().mode() & 0o777,
+            0o700
+        );
+
+        let link = tmp.path().join("private-link");
+        std::os::unix::fs::symlink(&dir, &link).unwrap();
+        assert!(create_private_dir(&link).is_err());
+    }
+
+    #[test]
+    fn test_cache_setting_for_known_apps() {
+        let profile = WorkflowProfile::get_profile(WorkflowType::ThreeDArtist);
+        let prepared = PreparedScratch {
+            dir: PathBuf::from("/scratch"),
+            app_dirs: vec![(
+                "Blender".to_string(),
+                PathBuf::from("/scratch/blender-temp"),
+            )],
+        };
+
+        let blender = &profile.applications[0];
+        let configured = with_cache_setting(blender, &prepared);
+        assert_eq!(
+            configured.settings["preferences.filepaths.temporary_directory"],
+            "/scratch/blender-temp"
+        );
+        assert!(cache_setting_known(&profile, "Blender"));
+
+        // FreeCAD has no scratch directory, and no known setting either
+        let freecad = &profile.applications[1];
+        assert_eq!(
+            with_cache_setting(freecad, &prepared).settings,
+            freecad.settings
+        );
+        assert!(!cache_setting_known(&profile, "FreeCAD"));
+    }
+
+    #[test]
+    fn test_ocio_config_copy_skips_unchanged_files() {
+        let tmp = tempfile::tempdir().unwrap();
+        let source = tmp.path().join("ocio-source");
+        let cache = tmp.path().join("ocio-cache");
+        fs::create_dir_all(source.join("luts")).unwrap();
+        fs::write(source.join("config.ocio"), "ocio_profile_version: 1\n").unwrap();
+        fs::write(source.join("luts/lg10.spi1d"), "Version 1\n").unwrap();
+
+        let copy = cache_ocio_config(&source.join("config.ocio"), &cache).unwrap();
+        assert_eq!(copy, cache.join("config.ocio"));
+        assert_eq!(
+            fs::read_to_string(cache.join("luts/lg10.spi1d")).unwrap(),
+            "Version 1\n"
+        );
+
+        // An unchanged file is left as it is, a changed one copied again
+        fs::write(cache.join("luts/lg10.spi1d"), "Version 0\n").unwrap();
+        fs::write(source.join("config.ocio"), "ocio_profile_version: 2\n").unwrap();
+        cache_ocio_config(&source.join("config.ocio"), &cache).unwrap();
+        assert_eq!(
+            fs::read_to_string(cache.join("luts/lg10.spi1d")).unwrap(),
+            "Version 0\n"
+        );
+        assert_eq!(
+            fs::read_to_string(&copy).unwrap(),
+            "ocio_profile_version: 2\n"
+        );
+
+        assert!(cache_ocio_config(&source.join("missing.ocio"), &cache).is_err());
+    }
+}
@@ -1,7 +1,14 @@
-use crate::profiles::{CpuGovernor, IoScheduler, SwapUsage, SystemSettings};
-use crate::Result;
+use crate::profiles::{
+    CpuGovernor, IoScheduler, SwapUsage, SystemSettings, WorkflowProfile, WorkflowType,
+};
+use crate::{cgroups, Result};
 use std::fs;
-use std::path::Path;
+use std::path::{Path, PathBuf};
+
+const AMD_PERFORMANCE_LEVEL: &str = "/sys/class/drm/card0/device/power_dpm_force_performance_level";
+
+/// Nice value given to a profile's `high_priority_processes`
+pub const HIGH_PRIORITY_NICE: i32 = -10;
 
 pub fn apply_system_settings(settings: &SystemSettings) -> Result<()> {
     set_cpu_governor(settings.cpu_governor)?;
@@ -11,20 +18,14 @@ pub fn apply_system_settings(settings: &SystemSettings) -> Result<()> {
         configure_realtime_audio()?;
     }
 
-    for process in &settings.high_priority_processes {
-        set_process_priority(process, -10)?;
-    }
+    // Moves high_priority_processes into the workflow slice, or renices them
+    cgroups::apply(settings)?;
 
     Ok(())
 }
 
 pub fn set_cpu_governor(governor: CpuGovernor) -> Result<()> {
-    let governor_str = match governor {
-        CpuGovernor::Performance => "performance",
-        CpuGovernor::Powersave => "powersave",
-        CpuGovernor::Schedutil => "schedutil",
-        CpuGovernor::Ondemand => "ondemand",
-    };
+    let governor_str = governor.as_str();
 
     // Find all CPU cores
     let cpufreq_path = Path::new("/sys/devices/system/cpu/cpufreq");
@@ -51,11 +52,7 @@ pub fn set_cpu_governor(governor: CpuGovernor) -> Result<()> {
 }
 
 pub fn set_swap_usage(usage: SwapUsage) -> Result<()> {
-    let swappiness = match usage {
-        SwapUsage::Minimal => 10,
-        SwapUsage::Balanced => 60,
-        SwapUsage::Aggressive => 100,
-    };
+    let swappiness = usage.swappiness();
 
     // Try sysctl
     let _ = std::process::Command::new("sudo")
@@ -79,11 +76,45 @@ pub fn set_io_scheduler(scheduler: IoScheduler, device: &str) -> Result<()> {
     Ok(())
 }
 
+/// Governors currently set on each cpufreq policy, deduplicated
+pub fn current_cpu_governors() -> Vec<String> {
+    let mut governors = Vec::new();
+
+    if let Ok(entries) = fs::read_dir("/sys/devices/system/cpu/cpufreq") {
+        for entry in entries.flatten() {
+            if let Ok(governor) = fs::read_to_string(entry.path().join("scaling_governor")) {
+                let governor = governor.trim().to_string();
+                if !governors.contains(&governor) {
+                    governors.push(governor);
+                }
+            }
+        }
+    }
+
+    governors
+}
+
+pub fn current_swappiness() -> Option<u32> {
+    fs::read_to_string("/proc/sys/vm/swappiness")
+        .ok()
+        .and_then(|s| s.trim().parse().ok())
+}
+
+/// AMD `power_dpm_force_performance_level`, if the GPU exposes it
+pub fn current_amd_performance_level() -> Option<String> {
+    fs::read_to_string(AMD_PERFORMANCE_LEVEL)
+        .ok()
+        .map(|s| s.trim().to_string())
+}
+
+/// PipeWire drop-in written by [`configure_realtime_audio`]
+pub fn realtime_audio_config_path() -> Option<PathBuf> {
+    dirs::config_dir().map(|dir| dir.join("pipewire/pipewire.conf.d/10-realtime.conf"))
+}
+
 pub fn configure_realtime_audio() -> Result<()> {
     // Set PipeWire for low latency
-    if let Some(config_dir) = dirs::config_dir() {
-        let pipewire_conf = config_dir.join("pipewire/pipewire.conf.d/10-realtime.conf");
-
+    if let Some(pipewire_conf) = realtime_audio_config_path() {
         if let Some(parent) = pipewire_conf.parent() {
             let _ = fs::create_dir_all(parent);
         }
@@ -162,10 +193,9 @@ pub fn set_gpu_performance_mode(enabled: bool) -> Result<()> {
     }
 
     // AMD
-    let amd_perf = "/sys/class/drm/card0/device/power_dpm_force_performance_level";
-    if Path::new(amd_perf).exists() {
+    if Path::new(AMD_PERFORMANCE_LEVEL).exists() {
         let mode = if enabled { "high" } else { "auto" };
-        let _ = fs::write(amd_perf, mode);
+        let _ = fs::write(AMD_PERFORMANCE_LEVEL, mode);
     }
 
     Ok(())
@@ -228,3 +258,378 @@ fn detect_gpu() -> String {
     }
     "Unknown".to_string()
 }
+
+const NVIDIA_VENDOR: &str = "0x10de";
+const AMD_VENDOR: &str = "0x1002";
+const INTEL_VENDOR: &str = "0x8086";
+
+/// Where distributions and the vendor SDKs put compute runtimes
+const LIBRARY_DIRS: &[&str] = &[
+    "/usr/lib",
+    "/usr/lib64",
+    "/usr/lib/x86_64-linux-gnu",
+    "/opt/rocm/lib",
+    "/opt/cuda/lib64",
+    "/usr/local/cuda/lib64",
+];
+
+const CUDA_TOOLKIT_DIRS: &[&str] = &["/opt/cuda", "/usr/local/cuda"];
+
+/// Oldest driver each CUDA major version runs on
+const CUDA_MIN_DRIVER: &[(u32, &str)] = &[(12, "525.60.13"), (11, "450.80.02")];
+
+/// App settings that switch rendering to the GPU, with the value that
+/// renders on the CPU
+const GPU_RENDER_SETTINGS: &[(&str, &str, &str)] = &[("cycles.device", "GPU", "CPU")];
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ComputeBackend {
+    Cuda,
+    Optix,
+    Hip,
+    OpenCl,
+}
+
+impl ComputeBackend {
+    pub fn name(&self) -> &'static str {
+        match self {
+            ComputeBackend::Cuda => "CUDA",
+            ComputeBackend::Optix => "OptiX",
+            ComputeBackend::Hip => "HIP",
+            ComputeBackend::OpenCl => "OpenCL",
+        }
+    }
+}
+
+#[derive(Debug, Clone, Default)]
+pub struct GpuComputeStatus {
+    /// Backends with a runtime library and usable device nodes
+    pub available: Vec<ComputeBackend>,
+    /// NVIDIA kernel module version
+    pub nvidia_driver: Option<String>,
+    /// Installed CUDA toolkit version, if any
+    pub cuda_toolkit: Option<String>,
+    /// What keeps GPU compute from working, one line each
+    pub problems: Vec<String>,
+    /// Packages that fix `problems`
+    pub packages: Vec<String>,
+}
+
+impl GpuComputeStatus {
+    /// Whether Cycles can render on the GPU; it dropped OpenCL in Blender 3.0
+    pub fn supports_gpu_rendering(&self) -> bool {
+        self.available.iter().any(|b| {
+            matches!(
+                b,
+                ComputeBackend::Cuda | ComputeBackend::Optix | ComputeBackend::Hip
+            )
+        })
+    }
+
+    /// Command installing the missing packages, if any are missing
+    pub fn recommendation(&self) -> Option<String> {
+        (!self.packages.is_empty()).then(|| format!("sudo pacman -S {}", self.packages.join(" ")))
+    }
+
+    fn problem(&mut self, problem: impl Into<String>, packages: &[&str]) {
+        self.problems.push(problem.into());
+        for package in packages {
+            if !self.packages.iter().any(|p| p == package) {
+                self.packages.push(package.to_string());
+            }
+        }
+    }
+}
+
+/// Check that CUDA/OptiX (NVIDIA), HIP (AMD) and OpenCL can actually run:
+/// runtime libraries present, device nodes accessible, and for NVIDIA the
+/// kernel module, userspace driver and CUDA toolkit agreeing on versions.
+pub fn validate_gpu_compute() -> GpuComputeStatus {
+    let mut status = GpuComputeStatus::default();
+    let vendors = gpu_vendors();
+
+    let nvidia = vendors.iter().any(|v| v == NVIDIA_VENDOR);
+    let amd = vendors.iter().any(|v| v == AMD_VENDOR);
+    if nvidia {
+        check_nvidia(&mut status);
+    }
+    if amd {
+        check_amd(&mut status);
+    }
+    if !nvidia && !amd {
+        // Cycles' oneAPI backend only covers Intel Arc and isn't checked here
+        status.problem("No NVIDIA or AMD GPU for CUDA, OptiX or HIP", &[]);
+    }
+    if !vendors.is_empty() {
+        check_opencl(&vendors, &mut status);
+    }
+
+    status
+}
+
+fn check_nvidia(status: &mut GpuComputeStatus) {
+    let Some(driver) = nvidia_driver_version() else {
+        status.problem(
+            "NVIDIA GPU found but the nvidia kernel module isn't loaded",
+            &["nvidia", "nvidia-utils"],
+        );
+        return;
+    };
+    status.nvidia_driver = Some(driver.clone());
+
+    let mut usable = true;
+    if !Path::new("/dev/nvidiactl").exists() || !Path::new("/dev/nvidia0").exists() {
+        status.problem(
+            "/dev/nvidia0 is missing; reboot after the driver install",
+            &[],
+        );
+        usable = false;
+    }
+    // CUDA contexts fail to initialize without the unified memory module
+    if !Path::new("/dev/nvidia-uvm").exists() {
+        status.problem(
+            "/dev/nvidia-uvm is missing; run `sudo modprobe nvidia-uvm`",
+            &[],
+        );
+        usable = false;
+    }
+
+    match find_library("libcuda.so") {
+        None => {
+            status.problem("libcuda is missing", &["nvidia-utils"]);
+            usable = false;
+        }
+        Some(lib) => {
+            // libcuda.so.1 links to libcuda.so.<driver version>
+            let userspace = fs::canonicalize(&lib).ok().and_then(|path| {
+                let name = path.file_name()?.to_str()?.to_string();
+                name.strip_prefix("libcuda.so.").map(str::to_string)
+            });
+            if let Some(userspace) = userspace.filter(|v| v.contains('.') && *v != driver) {
+                status.problem(
+                    format!(
+                        "NVIDIA userspace driver {} doesn't match kernel module {}; reboot to load the new module",
+                        userspace, driver
+                    ),
+                    &[],
+                );
+                usable = false;
+            }
+        }
+    }
+
+    if let Some(toolkit) = cuda_toolkit_version() {
+        let major = parse_version(&toolkit).first().copied().unwrap_or(0);
+        let min_driver = CUDA_MIN_DRIVER
+            .iter()
+            .find(|(cuda, _)| *cuda == major)
+            .map(|(_, driver)| *driver);
+        if let Some(min_driver) = min_driver {
+            if parse_version(&driver) < parse_version(min_driver) {
+                status.problem(
+                    format!(
+                        "CUDA {} needs NVIDIA driver {} or newer, {} is loaded",
+                        toolkit, min_driver, driver
+                    ),
+                    &["nvidia", "nvidia-utils"],
+                );
+            }
+        }
+        status.cuda_toolkit = Some(toolkit);
+    }
+
+    if usable {
+        status.available.push(ComputeBackend::Cuda);
+        if find_library("libnvoptix.so").is_some() {
+            status.available.push(ComputeBackend::Optix);
+        } else {
+            status.problem(
+                "libnvoptix is missing, so OptiX is unavailable",
+                &["nvidia-utils"],
+            );
+        }
+    }
+}
+
+fn check_amd(status: &mut GpuComputeStatus) {
+    let kfd = Path::new("/dev/kfd");
+    if !kfd.exists() {
+        status.problem(
+            "AMD GPU found but /dev/kfd is missing; compute needs the amdgpu driver",
+            &[],
+        );
+        return;
+    }
+    if let Err(e) = fs::OpenOptions::new().read(true).write(true).open(kfd) {
+        if e.kind() == std::io::ErrorKind::PermissionDenied {
+            status.problem(
+                format!(
+                    "No access to /dev/kfd; run `sudo usermod -aG render,video {}` and log in again",
+                    whoami()
+                ),
+                &[],
+            );
+            return;
+        }
+    }
+
+    if find_library("libamdhip64.so").is_some() {
+        status.available.push(ComputeBackend::Hip);
+    } else {
+        status.problem("The HIP runtime is missing", &["hip-runtime-amd"]);
+    }
+}
+
+fn check_opencl(vendors: &[String], status: &mut GpuComputeStatus) {
+    if find_library("libOpenCL.so").is_none() {
+        status.problem("The OpenCL loader is missing", &["ocl-icd"]);
+    }
+
+    let icds: Vec<String> = fs::read_dir("/etc/OpenCL/vendors")
+        .map(|entries| {
+            entries
+                .flatten()
+                .map(|e| e.file_name().to_string_lossy().to_lowercase())
+                .filter(|name| name.ends_with(".icd"))
+                .collect()
+        })
+        .unwrap_or_default();
+
+    // Vendor, ICD file name fragment, package providing it
+    let drivers = [
+        (NVIDIA_VENDOR, "NVIDIA", "nvidia", "opencl-nvidia"),
+        (AMD_VENDOR, "AMD", "amd", "rocm-opencl-runtime"),
+        (INTEL_VENDOR, "Intel", "intel", "intel-compute-runtime"),
+    ];
+    for (vendor, name, icd, package) in drivers {
+        if vendors.iter().any(|v| v == vendor) && !icds.iter().any(|f| f.contains(icd)) {
+            status.problem(format!("No OpenCL driver for the {} GPU", name), &[package]);
+        }
+    }
+
+    if find_library("libOpenCL.so").is_some() && !icds.is_empty() {
+        status.available.push(ComputeBackend::OpenCl);
+    }
+}
+
+/// PCI vendor IDs of the DRM cards, e.g. "0x10de"
+fn gpu_vendors() -> Vec<String> {
+    let mut vendors = Vec::new();
+    if let Ok(entries) = fs::read_dir("/sys/class/drm") {
+        for entry in entries.flatten() {
+            let name = entry.file_name().to_string_lossy().to_string();
+            // Skip connectors like card0-DP-1
+            if !name.starts_with("card") || name.contains('-') {
+                continue;
+            }
+            if let Ok(vendor) = fs::read_to_string(entry.path().join("device/vendor")) {
+                let vendor = vendor.trim().to_lowercase();
+                if !vendors.contains(&vendor) {
+                    vendors.push(vendor);
+                }
+            }
+        }
+    }
+    vendors
+}
+
+/// "NVRM version: NVIDIA UNIX x86_64 Kernel Module  550.54.14  Thu Feb 22 ..."
+fn nvidia_driver_version() -> Option<String> {
+    let content = fs::read_to_string("/proc/driver/nvidia/version").ok()?;
+    content
+        .lines()
+        .next()?
+        .split_whitespace()
+        .find(|word| word.contains('.') && word.chars().all(|c| c.is_ascii_digit() || c == '.'))
+        .map(str::to_string)
+}
+
+/// From `version.json` (CUDA 11.1+) or `version.txt`
+fn cuda_toolkit_version() -> Option<String> {
+    for dir in CUDA_TOOLKIT_DIRS {
+        let dir = Path::new(dir);
+        let json = fs::read_to_string(dir.join("version.json"))
+            .ok()
+            .and_then(|content| serde_json::from_str::<serde_json::Value>(&content).ok());
+        if let Some(version) = json.as_ref().and_then(|j| j["cuda"]["version"].as_str()) {
+            return Some(version.to_string());
+        }
+        if let Ok(content) = fs::read_to_string(dir.join("version.txt")) {
+            if let Some(version) = content.trim().strip_prefix("CUDA Version ") {
+                return Some(version.to_string());
+            }
+        }
+    }
+    None
+}
+
+/// First file in the library path named `name` or `name.<version>`
+fn find_library(name: &str) -> Option<PathBuf> {
+    let extra = env_library_dirs();
+    LIBRARY_DIRS
+        .iter()
+        .map(PathBuf::from)
+        .chain(extra)
+        .find_map(|dir| {
+            let exact = dir.join(name);
+            if exact.exists() {
+                return Some(exact);
+            }
+            fs::read_dir(&dir).ok()?.flatten().find_map(|entry| {
+                let file = entry.file_name();
+                let file = file.to_str()?;
+                file.strip_prefix(name)
+                    .is_some_and(|rest| rest.starts_with('.'))
+                    .then(|| entry.path())
+            })
+        })
+}
+
+fn env_library_dirs() -> Vec<PathBuf> {
+    std::env::var_os("LD_LIBRARY_PATH")
+        .map(|paths| std::env::split_paths(&paths).collect())
+        .unwrap_or_default()
+}
+
+fn parse_version(version: &str) -> Vec<u32> {
+    version
+        .split('.')
+        .map(|part| part.parse().unwrap_or(0))
+        .collect()
+}
+
+impl WorkflowProfile {
+    /// The video and 3D workflows count on GPU compute for rendering,
+    /// effects and encoding
+    pub fn uses_gpu_compute(&self) -> bool {
+        matches!(
+            self.workflow_type,
+            WorkflowType::VideoEditor | WorkflowType::ThreeDArtist
+        )
+    }
+
+    /// Switch app settings that render on the GPU to the CPU instead of
+    /// letting apps silently fall back. The CPU value is set rather than the
+    /// setting dropped, so config written by an earlier activation is
+    /// overwritten too. Returns the apps that were changed.
+    pub fn disable_gpu_rendering(&mut self) -> Vec<String> {
+        let mut changed = Vec::new();
+        for app in &mut self.applications {
+            let mut switched = false;
+            for (key, value) in app.settings.iter_mut() {
+                let cpu = GPU_RENDER_SETTINGS
+                    .iter()
+                    .find(|(k, gpu, _)| k == key && gpu == value)
+                    .map(|(_, _, cpu)| cpu);
+                if let Some(cpu) = cpu {
+                    *value = cpu.to_string();
+                    switched = true;
+                }
+            }
+            if switched {
+                changed.push(app.name.clone());
+            }
+        }
+        changed
+    }
+}
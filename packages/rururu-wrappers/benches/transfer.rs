@@ -0,0 +1,67 @@
+//! Exact vs LUT sRGB transfer on a 4K RGBA buffer. Timings include
+//! refilling the buffer between iterations.
+//!
+//! Run with `cargo bench -p rururu-wrappers --bench transfer`.
+
+use rururu_wrappers::color::ColorSpace;
+use rururu_wrappers::{ColorManager, TransferMode};
+use std::hint::black_box;
+use std::time::{Duration, Instant};
+
+const WIDTH: usize = 3840;
+const HEIGHT: usize = 2160;
+const ITERATIONS: u32 = 5;
+
+fn time(mut f: impl FnMut()) -> Duration {
+    // Warm up (and build the tables) before timing
+    f();
+    let start = Instant::now();
+    for _ in 0..ITERATIONS {
+        f();
+    }
+    start.elapsed() / ITERATIONS
+}
+
+fn main() {
+    let source: Vec<f32> = (0..WIDTH * HEIGHT * 4)
+        .map(|i| (i % 1021) as f32 / 1020.0)
+        .collect();
+    let mut cm = ColorManager::new();
+
+    for (from, to) in [
+        (ColorSpace::SRGB, ColorSpace::Linear),
+        (ColorSpace::Linear, ColorSpace::SRGB),
+    ] {
+        let mut results = Vec::new();
+        for mode in [TransferMode::Exact, TransferMode::Lut] {
+            cm.set_transfer_mode(mode);
+            let mut pixels = source.clone();
+            let elapsed = time(|| {
+                pixels.copy_from_slice(&source);
+                cm.transform_buffer(black_box(&mut pixels), 4, from, to)
+                    .unwrap();
+            });
+            results.push(elapsed);
+            println!(
+                "{} -> {} {:?}: {:.1} ms",
+                from.name(),
+                to.name(),
+                mode,
+                elapsed.as_secs_f64() * 1000.0
+            );
+        }
+        println!(
+            "  speedup: {:.1}x",
+            results[0].as_secs_f64() / results[1].as_secs_f64()
+        );
+    }
+
+    let bytes: Vec<u8> = source.iter().map(|&c| (c * 255.0) as u8).collect();
+    let elapsed = time(|| {
+        black_box(cm.decode_srgb_u8(&bytes, 4));
+    });
+    println!(
+        "8-bit sRGB -> Linear table: {:.1} ms",
+        elapsed.as_secs_f64() * 1000.0
+    );
+}
@@ -1,3 +1,4 @@
+use crate::transfer::{self, TransferLut, TransferMode};
 use std::path::Path;
 use thiserror::Error;
 use tracing::{debug, warn};
@@ -22,10 +23,15 @@ pub enum ColorSpace {
     ACES2065_1,
     Rec709,
     Rec2020,
+    /// BT.2020 primaries with the PQ curve, as in HDR10
+    Rec2100PQ,
+    /// BT.2020 primaries with the HLG curve, as in broadcast HDR
+    Rec2100HLG,
     DCI_P3,
     DisplayP3,
     AdobeRGB,
     ProPhotoRGB,
+    /// CIE XYZ relative to a D65 white, the space generic transforms meet in
     XYZ,
     Raw,
     Custom,
@@ -40,6 +46,8 @@ impl ColorSpace {
             ColorSpace::ACES2065_1 => "ACES2065-1",
             ColorSpace::Rec709 => "Rec.709",
             ColorSpace::Rec2020 => "Rec.2020",
+            ColorSpace::Rec2100PQ => "Rec.2100 PQ",
+            ColorSpace::Rec2100HLG => "Rec.2100 HLG",
             ColorSpace::DCI_P3 => "DCI-P3",
             ColorSpace::DisplayP3 => "Display P3",
             ColorSpace::AdobeRGB => "Adobe RGB",
@@ -58,6 +66,8 @@ impl ColorSpace {
             "aces2065-1" | "aces" => Some(ColorSpace::ACES2065_1),
             "rec709" | "rec.709" => Some(ColorSpace::Rec709),
             "rec2020" | "rec.2020" => Some(ColorSpace::Rec2020),
+            "rec2100 pq" | "rec.2100 pq" | "pq" => Some(ColorSpace::Rec2100PQ),
+            "rec2100 hlg" | "rec.2100 hlg" | "hlg" => Some(ColorSpace::Rec2100HLG),
             "dci-p3" | "dcip3" => Some(ColorSpace::DCI_P3),
             "display p3" | "displayp3" => Some(ColorSpace::DisplayP3),
             "adobe rgb" | "adobergb" => Some(ColorSpace::AdobeRGB),
@@ -69,9 +79,128 @@ impl ColorSpace {
     }
 }
 
+/// Color vision deficiency to simulate
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum CvdType {
+    /// Missing or shifted L (red) cones
+    Protanopia,
+    /// Missing or shifted M (green) cones
+    Deuteranopia,
+    /// Missing or shifted S (blue) cones
+    Tritanopia,
+}
+
+impl CvdType {
+    pub const ALL: [CvdType; 3] = [
+        CvdType::Protanopia,
+        CvdType::Deuteranopia,
+        CvdType::Tritanopia,
+    ];
+
+    pub fn name(&self) -> &'static str {
+        match self {
+            CvdType::Protanopia => "Protanopia",
+            CvdType::Deuteranopia => "Deuteranopia",
+            CvdType::Tritanopia => "Tritanopia",
+        }
+    }
+
+    /// Machado et al. (2009) matrix at severity 1.0, for linear sRGB
+    fn matrix(&self) -> [[f32; 3]; 3] {
+        match self {
+            CvdType::Protanopia => [
+                [0.152286, 1.052583, -0.204868],
+                [0.114503, 0.786281, 0.099216],
+                [-0.003882, -0.048116, 1.051998],
+            ],
+            CvdType::Deuteranopia => [
+                [0.367322, 0.860646, -0.227968],
+                [0.280085, 0.672501, 0.047413],
+                [-0.011820, 0.042940, 0.968881],
+            ],
+            CvdType::Tritanopia => [
+                [1.255528, -0.076749, -0.178779],
+                [-0.078411, 0.930809, 0.147602],
+                [0.004733, 0.691367, 0.303900],
+            ],
+        }
+    }
+}
+
+/// How colors are carried between spaces with different white points, such
+/// as a D50 ProPhoto image on a D65 display. Each method scales responses
+/// to the two whites in its own cone space.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum ChromaticAdaptation {
+    /// The ICC's choice, and what most color-managed apps use
+    #[default]
+    Bradford,
+    /// CIECAM02's transform
+    Cat02,
+    /// Hunt-Pointer-Estevez cone responses
+    VonKries,
+    /// X, Y and Z scaled directly; the least accurate, for matching software
+    /// that uses it
+    XyzScaling,
+}
+
+impl ChromaticAdaptation {
+    pub const ALL: [ChromaticAdaptation; 4] = [
+        ChromaticAdaptation::Bradford,
+        ChromaticAdaptation::Cat02,
+        ChromaticAdaptation::VonKries,
+        ChromaticAdaptation::XyzScaling,
+    ];
+
+    pub fn name(&self) -> &'static str {
+        match self {
+            ChromaticAdaptation::Bradford => "Bradford",
+            ChromaticAdaptation::Cat02 => "CAT02",
+            ChromaticAdaptation::VonKries => "Von Kries",
+            ChromaticAdaptation::XyzScaling => "XYZ Scaling",
+        }
+    }
+
+    fn cone_matrix(&self) -> [[f32; 3]; 3] {
+        match self {
+            ChromaticAdaptation::Bradford => [
+                [0.8951, 0.2664, -0.1614],
+                [-0.7502, 1.7135, 0.0367],
+                [0.0389, -0.0685, 1.0296],
+            ],
+            ChromaticAdaptation::Cat02 => [
+                [0.7328, 0.4296, -0.1624],
+                [-0.7036, 1.6975, 0.0061],
+                [0.0030, 0.0136, 0.9834],
+            ],
+            ChromaticAdaptation::VonKries => [
+                [0.40024, 0.70760, -0.08081],
+                [-0.22630, 1.16532, 0.04570],
+                [0.0, 0.0, 0.91822],
+            ],
+            ChromaticAdaptation::XyzScaling => [[1.0, 0.0, 0.0], [0.0, 1.0, 0.0], [0.0, 0.0, 1.0]],
+        }
+    }
+
+    /// Matrix taking XYZ seen under white `from` to XYZ under white `to`,
+    /// both with Y = 1
+    pub fn matrix(&self, from: [f32; 3], to: [f32; 3]) -> [[f32; 3]; 3] {
+        let cone = self.cone_matrix();
+        let (src, dst) = (mat_vec(cone, from), mat_vec(cone, to));
+        let mut scaled = cone;
+        for (row, (d, s)) in scaled.iter_mut().zip(dst.iter().zip(src)) {
+            *row = row.map(|c| c * d / s);
+        }
+        mat_mul(invert(cone), scaled)
+    }
+}
+
 pub struct ColorManager {
     config_path: Option<String>,
     working_space: ColorSpace,
+    transfer_mode: TransferMode,
+    reference_white_nits: f32,
+    adaptation: ChromaticAdaptation,
 }
 
 impl ColorManager {
@@ -79,6 +208,9 @@ impl ColorManager {
         Self {
             config_path: None,
             working_space: ColorSpace::Linear,
+            transfer_mode: TransferMode::default(),
+            reference_white_nits: transfer::REFERENCE_WHITE_NITS,
+            adaptation: ChromaticAdaptation::default(),
         }
     }
 
@@ -96,6 +228,9 @@ impl ColorManager {
         Ok(Self {
             config_path: Some(path_str),
             working_space: ColorSpace::Linear,
+            transfer_mode: TransferMode::default(),
+            reference_white_nits: transfer::REFERENCE_WHITE_NITS,
+            adaptation: ChromaticAdaptation::default(),
         })
     }
 
@@ -107,11 +242,132 @@ impl ColorManager {
         self.working_space
     }
 
+    /// Use `TransferMode::Exact` when buffer transforms must match
+    /// `transform_rgb` bit for bit rather than to within 2e-5.
+    pub fn set_transfer_mode(&mut self, mode: TransferMode) {
+        self.transfer_mode = mode;
+    }
+
+    pub fn transfer_mode(&self) -> TransferMode {
+        self.transfer_mode
+    }
+
+    /// Luminance in cd/m² that linear 1.0 stands for when converting to or
+    /// from PQ and HLG. Defaults to the BT.2408 reference white of 203, so
+    /// SDR white lands where HDR masters put it.
+    pub fn set_reference_white(&mut self, nits: f32) {
+        self.reference_white_nits = nits.max(f32::MIN_POSITIVE);
+    }
+
+    pub fn reference_white(&self) -> f32 {
+        self.reference_white_nits
+    }
+
+    /// Method for transforms between spaces whose whites differ, such as
+    /// ProPhoto's D50 and sRGB's D65. Defaults to Bradford.
+    pub fn set_chromatic_adaptation(&mut self, adaptation: ChromaticAdaptation) {
+        self.adaptation = adaptation;
+    }
+
+    pub fn chromatic_adaptation(&self) -> ChromaticAdaptation {
+        self.adaptation
+    }
+
     pub fn transform_rgb(
         &self,
         rgb: [f32; 3],
         from: ColorSpace,
         to: ColorSpace,
+    ) -> Result<[f32; 3], ColorError> {
+        if is_approximated(from, to) {
+            warn!(
+                "Transform from {} to {} using approximation",
+                from.name(),
+                to.name()
+            );
+        }
+        self.convert(rgb, from, to)
+    }
+
+    /// Transform interleaved pixels in place. Channels beyond the first
+    /// three (alpha) are untouched. sRGB decoding and encoding follow the
+    /// transfer mode; other transforms are exact.
+    pub fn transform_buffer(
+        &self,
+        pixels: &mut [f32],
+        channels: usize,
+        from: ColorSpace,
+        to: ColorSpace,
+    ) -> Result<(), ColorError> {
+        if from == to || channels < 3 {
+            return Ok(());
+        }
+
+        match (from, to) {
+            (ColorSpace::SRGB, ColorSpace::Linear) => {
+                self.apply_transfer(
+                    pixels,
+                    channels,
+                    transfer::srgb_decode,
+                    transfer::srgb_decode_lut(),
+                );
+            }
+            (ColorSpace::Linear, ColorSpace::SRGB) => {
+                self.apply_transfer(
+                    pixels,
+                    channels,
+                    transfer::srgb_encode,
+                    transfer::srgb_encode_lut(),
+                );
+            }
+            _ => {
+                // Fail before touching the buffer, and warn once rather than per pixel
+                let _ = self.transform_rgb([0.0; 3], from, to)?;
+                for_each_rgb(pixels, channels, |rgb| {
+                    self.convert(rgb, from, to).unwrap_or(rgb)
+                });
+            }
+        }
+
+        Ok(())
+    }
+
+    fn apply_transfer(
+        &self,
+        pixels: &mut [f32],
+        channels: usize,
+        exact: fn(f32) -> f32,
+        lut: &TransferLut,
+    ) {
+        match self.transfer_mode {
+            TransferMode::Exact => for_each_rgb(pixels, channels, |rgb| rgb.map(exact)),
+            TransferMode::Lut => for_each_rgb(pixels, channels, |rgb| rgb.map(|c| lut.apply(c))),
+        }
+    }
+
+    /// Decode 8-bit sRGB pixels to linear floats through an exact 256-entry
+    /// table, the fast path for image loaders. Alpha is scaled to 0..=1.
+    pub fn decode_srgb_u8(&self, pixels: &[u8], channels: usize) -> Vec<f32> {
+        let table = transfer::srgb_u8_table();
+        let mut linear: Vec<f32> = pixels.iter().map(|&v| table[v as usize]).collect();
+        if channels > 3 {
+            for (out, px) in linear
+                .chunks_exact_mut(channels)
+                .zip(pixels.chunks_exact(channels))
+            {
+                for (o, &v) in out[3..].iter_mut().zip(&px[3..]) {
+                    *o = v as f32 / 255.0;
+                }
+            }
+        }
+        linear
+    }
+
+    fn convert(
+        &self,
+        rgb: [f32; 3],
+        from: ColorSpace,
+        to: ColorSpace,
     ) -> Result<[f32; 3], ColorError> {
         if from == to {
             return Ok(rgb);
@@ -124,11 +380,6 @@ impl ColorManager {
             (ColorSpace::Linear, ColorSpace::ACEScg) => Ok(self.linear_to_acescg(rgb)),
             (ColorSpace::ACEScg, ColorSpace::Linear) => Ok(self.acescg_to_linear(rgb)),
             _ => {
-                warn!(
-                    "Transform from {} to {} using approximation",
-                    from.name(),
-                    to.name()
-                );
                 // Generic transform via XYZ
                 let xyz = self.to_xyz(rgb, from)?;
                 self.from_xyz(xyz, to)
@@ -137,23 +388,11 @@ impl ColorManager {
     }
 
     fn srgb_to_linear(&self, rgb: [f32; 3]) -> [f32; 3] {
-        rgb.map(|c| {
-            if c <= 0.04045 {
-                c / 12.92
-            } else {
-                ((c + 0.055) / 1.055).powf(2.4)
-            }
-        })
+        rgb.map(transfer::srgb_decode)
     }
 
     fn linear_to_srgb(&self, rgb: [f32; 3]) -> [f32; 3] {
-        rgb.map(|c| {
-            if c <= 0.0031308 {
-                c * 12.92
-            } else {
-                1.055 * c.powf(1.0 / 2.4) - 0.055
-            }
-        })
+        rgb.map(transfer::srgb_encode)
     }
 
     fn linear_to_acescg(&self, rgb: [f32; 3]) -> [f32; 3] {
@@ -176,45 +415,71 @@ impl ColorManager {
         self.matrix_multiply(rgb, m)
     }
 
+    /// Linear BT.2020 light relative to the reference white
+    fn hdr_to_linear(&self, rgb: [f32; 3], from: ColorSpace) -> [f32; 3] {
+        let nits = match from {
+            ColorSpace::Rec2100PQ => rgb.map(transfer::pq_eotf),
+            _ => transfer::hlg_ootf(rgb.map(transfer::hlg_inverse_oetf), transfer::HLG_PEAK_NITS),
+        };
+        nits.map(|n| n / self.reference_white_nits)
+    }
+
+    fn linear_to_hdr(&self, linear: [f32; 3], to: ColorSpace) -> [f32; 3] {
+        // Out-of-gamut components have no negative light to encode
+        let nits = linear.map(|c| c.max(0.0) * self.reference_white_nits);
+        match to {
+            ColorSpace::Rec2100PQ => nits.map(transfer::pq_inverse_eotf),
+            _ => transfer::hlg_inverse_ootf(nits, transfer::HLG_PEAK_NITS).map(transfer::hlg_oetf),
+        }
+    }
+
+    /// XYZ relative to D65, adapted from the white of `from`
     fn to_xyz(&self, rgb: [f32; 3], from: ColorSpace) -> Result<[f32; 3], ColorError> {
-        let linear = match from {
-            ColorSpace::SRGB => self.srgb_to_linear(rgb),
-            ColorSpace::Linear => rgb,
+        let (linear, m) = match from {
+            ColorSpace::SRGB => (self.srgb_to_linear(rgb), SRGB_TO_XYZ),
+            ColorSpace::Linear => (rgb, SRGB_TO_XYZ),
             ColorSpace::XYZ => return Ok(rgb),
+            ColorSpace::Rec2100PQ | ColorSpace::Rec2100HLG => {
+                (self.hdr_to_linear(rgb, from), BT2020_TO_XYZ)
+            }
+            ColorSpace::ProPhotoRGB => (rgb.map(transfer::prophoto_decode), PROPHOTO_TO_XYZ),
+            ColorSpace::ACES2065_1 => (rgb, AP0_TO_XYZ),
             _ => {
                 return Err(ColorError::UnsupportedColorSpace(from.name().to_string()));
             }
         };
 
-        // sRGB/Linear to XYZ
-        let m = [
-            [0.4124564, 0.3575761, 0.1804375],
-            [0.2126729, 0.7151522, 0.0721750],
-            [0.0193339, 0.1191920, 0.9503041],
-        ];
-        Ok(self.matrix_multiply(linear, m))
+        let xyz = self.matrix_multiply(linear, m);
+        Ok(self.adapt(xyz, white_xyz(from), D65_XYZ))
     }
 
     fn from_xyz(&self, xyz: [f32; 3], to: ColorSpace) -> Result<[f32; 3], ColorError> {
         if to == ColorSpace::XYZ {
             return Ok(xyz);
         }
-
-        // XYZ to sRGB/Linear
-        let m = [
-            [3.2404542, -1.5371385, -0.4985314],
-            [-0.9692660, 1.8760108, 0.0415560],
-            [0.0556434, -0.2040259, 1.0572252],
-        ];
-        let linear = self.matrix_multiply(xyz, m);
+        let adapted = self.adapt(xyz, D65_XYZ, white_xyz(to));
+        let linear = |m| self.matrix_multiply(adapted, m);
 
         match to {
-            ColorSpace::Linear => Ok(linear),
-            ColorSpace::SRGB => Ok(self.linear_to_srgb(linear)),
+            ColorSpace::Rec2100PQ | ColorSpace::Rec2100HLG => {
+                Ok(self.linear_to_hdr(linear(XYZ_TO_BT2020), to))
+            }
+            ColorSpace::Linear => Ok(linear(XYZ_TO_SRGB)),
+            ColorSpace::SRGB => Ok(self.linear_to_srgb(linear(XYZ_TO_SRGB))),
+            ColorSpace::ProPhotoRGB => Ok(linear(XYZ_TO_PROPHOTO).map(transfer::prophoto_encode)),
+            ColorSpace::ACES2065_1 => Ok(linear(XYZ_TO_AP0)),
             _ => Err(ColorError::UnsupportedColorSpace(to.name().to_string())),
         }
     }
 
+    /// XYZ seen under white `from` as it would be under `to`
+    fn adapt(&self, xyz: [f32; 3], from: [f32; 3], to: [f32; 3]) -> [f32; 3] {
+        if from == to {
+            return xyz;
+        }
+        self.matrix_multiply(xyz, self.adaptation.matrix(from, to))
+    }
+
     fn matrix_multiply(&self, v: [f32; 3], m: [[f32; 3]; 3]) -> [f32; 3] {
         [
             m[0][0] * v[0] + m[0][1] * v[1] + m[0][2] * v[2],
@@ -223,6 +488,179 @@ impl ColorManager {
         ]
     }
 
+    /// Correct pixels lit by `temp_k` to the working space's white.
+    ///
+    /// `tint` runs from -1.0 (green) to 1.0 (magenta). Gains are computed in
+    /// linear light; sRGB-encoded buffers are decoded and re-encoded around
+    /// the adjustment. Channels beyond the first three (alpha) are untouched.
+    pub fn apply_white_balance(&self, pixels: &mut [f32], channels: usize, temp_k: f32, tint: f32) {
+        if channels < 3 {
+            return;
+        }
+
+        let reference = self.srgb_white_for(white_point_kelvin(self.working_space));
+        let source = self.srgb_white_for(temp_k);
+        let mut gains = [
+            reference[0] / source[0],
+            reference[1] / source[1],
+            reference[2] / source[2],
+        ];
+
+        // Keep green at unity so overall brightness stays put
+        let green = gains[1];
+        gains = gains.map(|g| g / green);
+        gains[1] *= 1.0 - tint.clamp(-1.0, 1.0) * 0.3;
+
+        let encoded = self.working_space == ColorSpace::SRGB;
+
+        for px in pixels.chunks_mut(channels) {
+            if px.len() < 3 {
+                break;
+            }
+            let mut rgb = [px[0], px[1], px[2]];
+            if encoded {
+                rgb = self.srgb_to_linear(rgb);
+            }
+            rgb = [rgb[0] * gains[0], rgb[1] * gains[1], rgb[2] * gains[2]];
+            if encoded {
+                rgb = self.linear_to_srgb(rgb);
+            }
+            px[..3].copy_from_slice(&rgb);
+        }
+    }
+
+    /// Show pixels as someone with `kind` sees them (Machado et al. 2009).
+    ///
+    /// `severity` runs from 0.0 (normal vision) to 1.0 (dichromacy); partial
+    /// severities blend towards the full-severity matrix. The model works in
+    /// linear light with sRGB primaries: sRGB-encoded buffers are decoded and
+    /// re-encoded around it, other working spaces are taken as linear.
+    /// Channels beyond the first three (alpha) are untouched.
+    pub fn simulate_cvd(&self, pixels: &mut [f32], channels: usize, kind: CvdType, severity: f32) {
+        let severity = severity.clamp(0.0, 1.0);
+        if channels < 3 || severity == 0.0 {
+            return;
+        }
+
+        let full = kind.matrix();
+        let mut m = [[0.0; 3]; 3];
+        for (i, row) in m.iter_mut().enumerate() {
+            for (j, v) in row.iter_mut().enumerate() {
+                let identity = if i == j { 1.0 } else { 0.0 };
+                *v = identity + (full[i][j] - identity) * severity;
+            }
+        }
+
+        let encoded = self.working_space == ColorSpace::SRGB;
+        let mode = self.transfer_mode;
+
+        for_each_rgb(pixels, channels, |mut rgb| {
+            if encoded {
+                rgb = rgb.map(|c| transfer::decode(c, mode));
+            }
+            rgb = self.matrix_multiply(rgb, m);
+            if encoded {
+                rgb = rgb.map(|c| transfer::encode(c, mode));
+            }
+            rgb
+        });
+    }
+
+    /// Hue in degrees (0..360), saturation and value in 0..=1. Works on the
+    /// values as given; pickers want sRGB-encoded input, since hue and
+    /// saturation steps look even there and not in linear light.
+    pub fn rgb_to_hsv(&self, rgb: [f32; 3]) -> [f32; 3] {
+        let (hue, max, min) = hue_max_min(rgb);
+        let saturation = if max > 0.0 { (max - min) / max } else { 0.0 };
+        [hue, saturation, max]
+    }
+
+    /// Inverse of `rgb_to_hsv`; any hue is accepted and wrapped into 0..360
+    pub fn hsv_to_rgb(&self, hsv: [f32; 3]) -> [f32; 3] {
+        let [hue, saturation, value] = hsv;
+        let chroma = value * saturation;
+        from_hue_chroma(hue, chroma, value - chroma)
+    }
+
+    /// Hue in degrees (0..360), saturation and lightness in 0..=1
+    pub fn rgb_to_hsl(&self, rgb: [f32; 3]) -> [f32; 3] {
+        let (hue, max, min) = hue_max_min(rgb);
+        let lightness = (max + min) / 2.0;
+        let divisor = 1.0 - (2.0 * lightness - 1.0).abs();
+        let saturation = if divisor > 0.0 {
+            (max - min) / divisor
+        } else {
+            0.0
+        };
+        [hue, saturation, lightness]
+    }
+
+    /// Inverse of `rgb_to_hsl`; any hue is accepted and wrapped into 0..360
+    pub fn hsl_to_rgb(&self, hsl: [f32; 3]) -> [f32; 3] {
+        let [hue, saturation, lightness] = hsl;
+        let chroma = (1.0 - (2.0 * lightness - 1.0).abs()) * saturation;
+        from_hue_chroma(hue, chroma, lightness - chroma / 2.0)
+    }
+
+    /// Rotate hues by `degrees`
+    pub fn adjust_hue(&self, pixels: &mut [f32], channels: usize, degrees: f32) {
+        if degrees.rem_euclid(360.0) == 0.0 {
+            return;
+        }
+        self.adjust_hsv(pixels, channels, |[h, s, v]| [h + degrees, s, v]);
+    }
+
+    /// Scale saturation by `factor`; 0.0 turns everything gray
+    pub fn adjust_saturation(&self, pixels: &mut [f32], channels: usize, factor: f32) {
+        if factor == 1.0 {
+            return;
+        }
+        self.adjust_hsv(pixels, channels, |[h, s, v]| {
+            [h, (s * factor.max(0.0)).min(1.0), v]
+        });
+    }
+
+    /// Scale HSV value (brightness of the strongest channel) by `factor`
+    pub fn adjust_value(&self, pixels: &mut [f32], channels: usize, factor: f32) {
+        if factor == 1.0 {
+            return;
+        }
+        self.adjust_hsv(pixels, channels, |[h, s, v]| [h, s, v * factor.max(0.0)]);
+    }
+
+    /// Run `f` on the HSV form of every pixel. Like the other adjustments,
+    /// sRGB working-space buffers are used as they are, and linear ones are
+    /// encoded with the sRGB curve around the change so steps are even.
+    /// HSV has no place for negative components, so those are clipped.
+    fn adjust_hsv(&self, pixels: &mut [f32], channels: usize, f: impl Fn([f32; 3]) -> [f32; 3]) {
+        if channels < 3 {
+            return;
+        }
+        let linear = self.working_space != ColorSpace::SRGB;
+        let mode = self.transfer_mode;
+
+        for_each_rgb(pixels, channels, |mut rgb| {
+            rgb = rgb.map(|c| c.max(0.0));
+            if linear {
+                rgb = rgb.map(|c| transfer::encode(c, mode));
+            }
+            rgb = self.hsv_to_rgb(f(self.rgb_to_hsv(rgb)));
+            if linear {
+                rgb = rgb.map(|c| transfer::decode(c, mode));
+            }
+            rgb
+        });
+    }
+
+    /// Linear sRGB value of a Planckian white at `kelvin`, with Y = 1.
+    fn srgb_white_for(&self, kelvin: f32) -> [f32; 3] {
+        let (x, y) = planckian_xy(kelvin);
+        let xyz = [x / y, 1.0, (1.0 - x - y) / y];
+        self.from_xyz(xyz, ColorSpace::Linear)
+            .unwrap_or([1.0, 1.0, 1.0])
+            .map(|c| c.max(1e-6))
+    }
+
     pub fn list_color_spaces(&self) -> Vec<ColorSpace> {
         vec![
             ColorSpace::SRGB,
@@ -231,6 +669,8 @@ impl ColorManager {
             ColorSpace::ACES2065_1,
             ColorSpace::Rec709,
             ColorSpace::Rec2020,
+            ColorSpace::Rec2100PQ,
+            ColorSpace::Rec2100HLG,
             ColorSpace::DCI_P3,
             ColorSpace::DisplayP3,
             ColorSpace::AdobeRGB,
@@ -240,6 +680,202 @@ impl ColorManager {
     }
 }
 
+/// White points as XYZ with Y = 1
+const D65_XYZ: [f32; 3] = [0.95047, 1.0, 1.08883];
+const D50_XYZ: [f32; 3] = [0.96422, 1.0, 0.82521];
+/// The ACES white, near D60
+const ACES_WHITE_XYZ: [f32; 3] = [0.952646, 1.0, 1.008825];
+
+/// Linear sRGB (D65) to XYZ
+const SRGB_TO_XYZ: [[f32; 3]; 3] = [
+    [0.4124564, 0.3575761, 0.1804375],
+    [0.2126729, 0.7151522, 0.0721750],
+    [0.0193339, 0.1191920, 0.9503041],
+];
+
+const XYZ_TO_SRGB: [[f32; 3]; 3] = [
+    [3.2404542, -1.5371385, -0.4985314],
+    [-0.9692660, 1.8760108, 0.0415560],
+    [0.0556434, -0.2040259, 1.0572252],
+];
+
+/// Linear ProPhoto RGB (D50) to XYZ
+const PROPHOTO_TO_XYZ: [[f32; 3]; 3] = [
+    [0.7976749, 0.1351917, 0.0313534],
+    [0.2880402, 0.7118741, 0.0000857],
+    [0.0000000, 0.0000000, 0.82521],
+];
+
+const XYZ_TO_PROPHOTO: [[f32; 3]; 3] = [
+    [1.3459434, -0.2556075, -0.0511118],
+    [-0.5445988, 1.5081673, 0.0205351],
+    [0.0000000, 0.0000000, 1.2118128],
+];
+
+/// ACES2065-1 (AP0, ACES white) to XYZ
+const AP0_TO_XYZ: [[f32; 3]; 3] = [
+    [0.9525524, 0.0000000, 0.0000937],
+    [0.3439664, 0.7281661, -0.0721325],
+    [0.0000000, 0.0000000, 1.0088252],
+];
+
+const XYZ_TO_AP0: [[f32; 3]; 3] = [
+    [1.049811, 0.0000000, -0.0000975],
+    [-0.495903, 1.373313, 0.09824],
+    [0.0000000, 0.0000000, 0.991252],
+];
+
+/// Linear BT.2020 (D65) to XYZ
+const BT2020_TO_XYZ: [[f32; 3]; 3] = [
+    [0.636958, 0.1446169, 0.168881],
+    [0.2627002, 0.6779981, 0.0593017],
+    [0.0000000, 0.0280727, 1.0609851],
+];
+
+const XYZ_TO_BT2020: [[f32; 3]; 3] = [
+    [1.7166512, -0.3556708, -0.2533663],
+    [-0.6666844, 1.6164812, 0.0157685],
+    [0.0176399, -0.0427706, 0.9421031],
+];
+
+/// White of the RGB values in `space`, which `to_xyz` and `from_xyz` adapt
+/// from and to
+fn white_xyz(space: ColorSpace) -> [f32; 3] {
+    match space {
+        ColorSpace::ProPhotoRGB => D50_XYZ,
+        ColorSpace::ACEScg | ColorSpace::ACES2065_1 => ACES_WHITE_XYZ,
+        _ => D65_XYZ,
+    }
+}
+
+fn mat_vec(m: [[f32; 3]; 3], v: [f32; 3]) -> [f32; 3] {
+    m.map(|row| row[0] * v[0] + row[1] * v[1] + row[2] * v[2])
+}
+
+fn mat_mul(a: [[f32; 3]; 3], b: [[f32; 3]; 3]) -> [[f32; 3]; 3] {
+    let mut out = [[0.0; 3]; 3];
+    for (r, row) in out.iter_mut().enumerate() {
+        for (c, value) in row.iter_mut().enumerate() {
+            *value = (0..3).map(|k| a[r][k] * b[k][c]).sum();
+        }
+    }
+    out
+}
+
+/// Inverse of a cone matrix, all of which are well conditioned
+fn invert(m: [[f32; 3]; 3]) -> [[f32; 3]; 3] {
+    let det = m[0][0] * (m[1][1] * m[2][2] - m[1][2] * m[2][1])
+        - m[0][1] * (m[1][0] * m[2][2] - m[1][2] * m[2][0])
+        + m[0][2] * (m[1][0] * m[2][1] - m[1][1] * m[2][0]);
+    let mut out = [[0.0; 3]; 3];
+    for (r, row) in out.iter_mut().enumerate() {
+        for (c, value) in row.iter_mut().enumerate() {
+            // Cofactor of the transposed element
+            let (r1, r2) = ((c + 1) % 3, (c + 2) % 3);
+            let (c1, c2) = ((r + 1) % 3, (r + 2) % 3);
+            *value = (m[r1][c1] * m[r2][c2] - m[r1][c2] * m[r2][c1]) / det;
+        }
+    }
+    out
+}
+
+/// Pairs `convert` handles through the generic XYZ path
+fn is_approximated(from: ColorSpace, to: ColorSpace) -> bool {
+    from != to
+        && !matches!(
+            (from, to),
+            (ColorSpace::SRGB, ColorSpace::Linear)
+                | (ColorSpace::Linear, ColorSpace::SRGB)
+                | (ColorSpace::Linear, ColorSpace::ACEScg)
+                | (ColorSpace::ACEScg, ColorSpace::Linear)
+        )
+}
+
+fn for_each_rgb(pixels: &mut [f32], channels: usize, mut f: impl FnMut([f32; 3]) -> [f32; 3]) {
+    for px in pixels.chunks_exact_mut(channels) {
+        let rgb = f([px[0], px[1], px[2]]);
+        px[..3].copy_from_slice(&rgb);
+    }
+}
+
+/// Hue in degrees shared by HSV and HSL, with the largest and smallest
+/// component; gray gets hue 0
+fn hue_max_min(rgb: [f32; 3]) -> (f32, f32, f32) {
+    let [r, g, b] = rgb;
+    let max = r.max(g).max(b);
+    let min = r.min(g).min(b);
+    let delta = max - min;
+
+    let hue = if delta <= 0.0 {
+        0.0
+    } else if max == r {
+        60.0 * ((g - b) / delta).rem_euclid(6.0)
+    } else if max == g {
+        60.0 * ((b - r) / delta + 2.0)
+    } else {
+        60.0 * ((r - g) / delta + 4.0)
+    };
+    (wrap_hue(hue), max, min)
+}
+
+/// RGB from a hue, chroma and the amount `m` added to every channel
+fn from_hue_chroma(hue: f32, chroma: f32, m: f32) -> [f32; 3] {
+    let sector = wrap_hue(hue) / 60.0;
+    let x = chroma * (1.0 - (sector % 2.0 - 1.0).abs());
+    let (r, g, b) = match sector as u32 {
+        0 => (chroma, x, 0.0),
+        1 => (x, chroma, 0.0),
+        2 => (0.0, chroma, x),
+        3 => (0.0, x, chroma),
+        4 => (x, 0.0, chroma),
+        _ => (chroma, 0.0, x),
+    };
+    [r + m, g + m, b + m]
+}
+
+/// `hue` in 0..360; `rem_euclid` rounds tiny negative hues up to 360 itself
+fn wrap_hue(hue: f32) -> f32 {
+    let wrapped = hue.rem_euclid(360.0);
+    if wrapped >= 360.0 {
+        0.0
+    } else {
+        wrapped
+    }
+}
+
+/// Correlated color temperature of each space's reference white.
+fn white_point_kelvin(space: ColorSpace) -> f32 {
+    match space {
+        ColorSpace::ACEScg | ColorSpace::ACES2065_1 => 6000.0,
+        ColorSpace::DCI_P3 => 6300.0,
+        ColorSpace::ProPhotoRGB => 5003.0,
+        _ => 6504.0,
+    }
+}
+
+/// Chromaticity of a blackbody at `kelvin` (Kim et al. cubic spline,
+/// valid from 1667K to 25000K).
+fn planckian_xy(kelvin: f32) -> (f32, f32) {
+    let t = kelvin.clamp(1667.0, 25000.0) as f64;
+    let (t2, t3) = (t * t, t * t * t);
+
+    let x = if t <= 4000.0 {
+        -0.2661239e9 / t3 - 0.2343589e6 / t2 + 0.8776956e3 / t + 0.179910
+    } else {
+        -3.0258469e9 / t3 + 2.1070379e6 / t2 + 0.2226347e3 / t + 0.240390
+    };
+
+    let y = if t <= 2222.0 {
+        -1.1063814 * x * x * x - 1.34811020 * x * x + 2.18555832 * x - 0.20219683
+    } else if t <= 4000.0 {
+        -0.9549476 * x * x * x - 1.37418593 * x * x + 2.09137015 * x - 0.16748867
+    } else {
+        3.0817580 * x * x * x - 5.87338670 * x * x + 3.75112997 * x - 0.37001483
+    };
+
+    (x as f32, y as f32)
+}
+
 impl Default for ColorManager {
     fn default() -> Self {
         Self::new()
@@ -270,6 +906,114 @@ mod tests {
         assert_eq!(ColorSpace::from_name("unknown"), None);
     }
 
+    #[test]
+    fn test_white_balance_reference_is_noop() {
+        let cm = ColorManager::new();
+        let original = vec![0.5, 0.3, 0.8, 1.0, 0.1, 0.9, 0.2, 0.5];
+        let mut pixels = original.clone();
+
+        cm.apply_white_balance(&mut pixels, 4, white_point_kelvin(cm.working_space()), 0.0);
+
+        for (a, b) in original.iter().zip(&pixels) {
+            assert!((a - b).abs() < 1e-6);
+        }
+    }
+
+    #[test]
+    fn test_white_balance_warm_light_cools_image() {
+        let cm = ColorManager::new();
+        let mut pixels = vec![0.5, 0.5, 0.5];
+
+        // Correcting for tungsten light pulls red down and blue up
+        cm.apply_white_balance(&mut pixels, 3, 3200.0, 0.0);
+        assert!(pixels[0] < pixels[1]);
+        assert!(pixels[2] > pixels[1]);
+    }
+
+    /// rg chromaticity, i.e. the hue/saturation left once brightness is removed
+    fn chromaticity(rgb: &[f32]) -> [f32; 2] {
+        let sum = rgb[0] + rgb[1] + rgb[2];
+        [rgb[0] / sum, rgb[1] / sum]
+    }
+
+    #[test]
+    fn test_deuteranopia_collapses_red_green() {
+        let mut cm = ColorManager::new();
+        cm.set_working_space(ColorSpace::Linear);
+        let mut pixels = vec![1.0, 0.0, 0.0, 0.0, 1.0, 0.0, 1.0, 1.0, 1.0];
+
+        cm.simulate_cvd(&mut pixels, 3, CvdType::Deuteranopia, 1.0);
+
+        // Red and green land on the same hue, differing only in brightness
+        let (red, green) = (chromaticity(&pixels[0..3]), chromaticity(&pixels[3..6]));
+        assert!((red[0] - green[0]).abs() < 0.05);
+        assert!((red[1] - green[1]).abs() < 0.05);
+        // Blue barely responds to either
+        assert!(pixels[2].abs() < 0.05 && pixels[5].abs() < 0.05);
+        // White stays white
+        for c in &pixels[6..9] {
+            assert!((c - 1.0).abs() < 1e-4);
+        }
+    }
+
+    #[test]
+    fn test_cvd_zero_severity_is_noop() {
+        let cm = ColorManager::new();
+        let original = vec![0.5, 0.3, 0.8, 1.0, 0.9, 0.1, 0.2, 0.5];
+
+        for kind in CvdType::ALL {
+            let mut pixels = original.clone();
+            cm.simulate_cvd(&mut pixels, 4, kind, 0.0);
+            assert_eq!(pixels, original);
+
+            // Alpha is left alone at any severity
+            cm.simulate_cvd(&mut pixels, 4, kind, 1.0);
+            assert_eq!((pixels[3], pixels[7]), (1.0, 0.5));
+        }
+    }
+
+    #[test]
+    fn test_transform_buffer_matches_transform_rgb() {
+        let mut cm = ColorManager::new();
+        let original = vec![0.5, 0.3, 0.8, 1.0, 0.0, 0.04, 1.0, 0.5];
+
+        for mode in [TransferMode::Exact, TransferMode::Lut] {
+            cm.set_transfer_mode(mode);
+            let mut pixels = original.clone();
+            cm.transform_buffer(&mut pixels, 4, ColorSpace::SRGB, ColorSpace::Linear)
+                .unwrap();
+
+            for (px, out) in original.chunks(4).zip(pixels.chunks(4)) {
+                let expected = cm
+                    .transform_rgb([px[0], px[1], px[2]], ColorSpace::SRGB, ColorSpace::Linear)
+                    .unwrap();
+                for i in 0..3 {
+                    assert!((out[i] - expected[i]).abs() < 2e-5);
+                }
+                assert_eq!(out[3], px[3]);
+            }
+        }
+    }
+
+    #[test]
+    fn test_transform_buffer_unsupported_leaves_pixels() {
+        let cm = ColorManager::new();
+        let mut pixels = vec![0.5, 0.3, 0.8];
+        let result = cm.transform_buffer(&mut pixels, 3, ColorSpace::Rec2020, ColorSpace::SRGB);
+        assert!(result.is_err());
+        assert_eq!(pixels, vec![0.5, 0.3, 0.8]);
+    }
+
+    #[test]
+    fn test_decode_srgb_u8() {
+        let cm = ColorManager::new();
+        let linear = cm.decode_srgb_u8(&[255, 128, 0, 255], 4);
+        assert!((linear[0] - 1.0).abs() < 1e-6);
+        assert!((linear[1] - cm.srgb_to_linear([128.0 / 255.0; 3])[0]).abs() < 1e-6);
+        assert_eq!(linear[2], 0.0);
+        assert_eq!(linear[3], 1.0);
+    }
+
     #[test]
     fn test_transform_same_space() {
         let cm = ColorManager::new();
@@ -279,4 +1023,184 @@ mod tests {
             .unwrap();
         assert_eq!(rgb, result);
     }
+
+    fn assert_rgb_close(a: [f32; 3], b: [f32; 3]) {
+        for (x, y) in a.iter().zip(b.iter()) {
+            assert!((x - y).abs() < 1e-4, "{:?} != {:?}", a, b);
+        }
+    }
+
+    #[test]
+    fn test_hsv_hsl_roundtrip_at_hue_wraparound() {
+        let cm = ColorManager::new();
+
+        // Reds either side of 0°, plus each primary and secondary sector
+        for rgb in [
+            [1.0, 0.0, 0.001],
+            [1.0, 0.001, 0.0],
+            [0.8, 0.2, 0.25],
+            [0.2, 0.9, 0.4],
+            [0.1, 0.3, 0.7],
+            [0.6, 0.1, 0.9],
+            [0.5, 0.5, 0.5],
+            [0.0, 0.0, 0.0],
+        ] {
+            let hsv = cm.rgb_to_hsv(rgb);
+            assert!((0.0..360.0).contains(&hsv[0]), "hue {}", hsv[0]);
+            assert_rgb_close(cm.hsv_to_rgb(hsv), rgb);
+
+            let hsl = cm.rgb_to_hsl(rgb);
+            assert!((0.0..360.0).contains(&hsl[0]), "hue {}", hsl[0]);
+            assert_rgb_close(cm.hsl_to_rgb(hsl), rgb);
+        }
+
+        assert!(cm.rgb_to_hsv([1.0, 0.0, 0.001])[0] > 359.9);
+        assert!(cm.rgb_to_hsv([1.0, 0.001, 0.0])[0] < 0.1);
+
+        // Hues outside 0..360 wrap
+        let red = cm.hsv_to_rgb([0.0, 1.0, 1.0]);
+        assert_rgb_close(cm.hsv_to_rgb([360.0, 1.0, 1.0]), red);
+        assert_rgb_close(cm.hsv_to_rgb([-1e-6, 1.0, 1.0]), red);
+        assert_rgb_close(
+            cm.hsl_to_rgb([-30.0, 1.0, 0.5]),
+            cm.hsl_to_rgb([330.0, 1.0, 0.5]),
+        );
+    }
+
+    #[test]
+    fn test_adjust_hue_crosses_zero() {
+        let mut cm = ColorManager::new();
+        cm.set_working_space(ColorSpace::SRGB);
+
+        let mut pixels = cm.hsv_to_rgb([350.0, 0.8, 0.9]).to_vec();
+        pixels.push(0.5);
+        cm.adjust_hue(&mut pixels, 4, 20.0);
+
+        let hsv = cm.rgb_to_hsv([pixels[0], pixels[1], pixels[2]]);
+        assert!((hsv[0] - 10.0).abs() < 1e-3, "hue {}", hsv[0]);
+        assert!((hsv[1] - 0.8).abs() < 1e-4);
+        assert_eq!(pixels[3], 0.5);
+    }
+
+    #[test]
+    fn test_adjustments_in_linear_working_space() {
+        let cm = ColorManager::new();
+        let original = [0.2, 0.05, 0.6];
+
+        let mut pixels = original.to_vec();
+        cm.adjust_hue(&mut pixels, 3, 360.0);
+        cm.adjust_value(&mut pixels, 3, 1.0);
+        assert_eq!(pixels, original);
+
+        // Adjusted on the sRGB-encoded values, not the linear ones
+        let mut pixels = original.to_vec();
+        cm.adjust_saturation(&mut pixels, 3, 0.5);
+        let encoded = cm.linear_to_srgb(original);
+        let [h, s, v] = cm.rgb_to_hsv(encoded);
+        let expected = cm.srgb_to_linear(cm.hsv_to_rgb([h, s * 0.5, v]));
+        assert_rgb_close([pixels[0], pixels[1], pixels[2]], expected);
+
+        let mut gray = original.to_vec();
+        cm.adjust_saturation(&mut gray, 3, 0.0);
+        assert!((gray[0] - gray[1]).abs() < 1e-5 && (gray[1] - gray[2]).abs() < 1e-5);
+    }
+
+    #[test]
+    fn test_sdr_white_in_hdr_spaces() {
+        let cm = ColorManager::new();
+        // Published code values are rounded, and 75% HLG is 202.9 cd/m²
+        let close = |a: [f32; 3], b: f32| {
+            assert!(a.iter().all(|c| (c - b).abs() < 1e-3), "{:?} != {}", a, b);
+        };
+
+        // BT.2408 places SDR white at 203 cd/m²: PQ 58%, HLG 75%
+        let pq = cm
+            .transform_rgb([1.0; 3], ColorSpace::SRGB, ColorSpace::Rec2100PQ)
+            .unwrap();
+        close(pq, 0.5806);
+        let hlg = cm
+            .transform_rgb([1.0; 3], ColorSpace::Linear, ColorSpace::Rec2100HLG)
+            .unwrap();
+        close(hlg, 0.75);
+
+        let mut cm = ColorManager::new();
+        cm.set_reference_white(100.0);
+        let pq = cm
+            .transform_rgb([1.0; 3], ColorSpace::Linear, ColorSpace::Rec2100PQ)
+            .unwrap();
+        close(pq, 0.5081);
+    }
+
+    #[test]
+    fn test_hdr_buffer_roundtrip() {
+        let cm = ColorManager::new();
+        // Includes a highlight five times brighter than SDR white
+        let original = vec![0.5, 0.3, 0.8, 1.0, 5.0, 4.0, 2.0, 0.5];
+
+        for space in [ColorSpace::Rec2100PQ, ColorSpace::Rec2100HLG] {
+            let mut pixels = original.clone();
+            cm.transform_buffer(&mut pixels, 4, ColorSpace::Linear, space)
+                .unwrap();
+            assert!(pixels[..3].iter().all(|c| (0.0..=1.0).contains(c)));
+            assert_eq!((pixels[3], pixels[7]), (1.0, 0.5));
+
+            cm.transform_buffer(&mut pixels, 4, space, ColorSpace::Linear)
+                .unwrap();
+            for (a, b) in pixels.iter().zip(&original) {
+                assert!(
+                    (a - b).abs() / b.max(1.0) < 1e-3,
+                    "{:?}: {} != {}",
+                    space,
+                    a,
+                    b
+                );
+            }
+        }
+    }
+
+    #[test]
+    fn test_bradford_and_xyz_scaling_adaptation() {
+        let mut cm = ColorManager::new();
+        // Linear light 0.5, 0.2, 0.1 in D50 ProPhoto, shown on D65 sRGB
+        let prophoto = [0.5, 0.2, 0.1].map(transfer::prophoto_encode);
+        let close = |a: [f32; 3], b: [f32; 3]| {
+            assert!(
+                a.iter().zip(&b).all(|(x, y)| (x - y).abs() < 1e-3),
+                "{:?} != {:?}",
+                a,
+                b
+            );
+        };
+
+        // Reference through Lindbloom's published Bradford D50 to D65 matrix
+        let bradford = cm
+            .transform_rgb(prophoto, ColorSpace::ProPhotoRGB, ColorSpace::Linear)
+            .unwrap();
+        close(bradford, [0.8409, 0.1316, 0.0812]);
+
+        // Scaling XYZ directly pushes the red noticeably further
+        cm.set_chromatic_adaptation(ChromaticAdaptation::XyzScaling);
+        let scaled = cm
+            .transform_rgb(prophoto, ColorSpace::ProPhotoRGB, ColorSpace::Linear)
+            .unwrap();
+        close(scaled, [0.8758, 0.1319, 0.0802]);
+        assert!(scaled[0] - bradford[0] > 0.03);
+
+        // Every method carries white to white and round-trips
+        for adaptation in ChromaticAdaptation::ALL {
+            cm.set_chromatic_adaptation(adaptation);
+            let white = cm
+                .transform_rgb([1.0; 3], ColorSpace::ProPhotoRGB, ColorSpace::SRGB)
+                .unwrap();
+            close(white, [1.0; 3]);
+
+            let linear = cm
+                .transform_rgb(prophoto, ColorSpace::ProPhotoRGB, ColorSpace::Linear)
+                .unwrap();
+            let back = cm
+                .transform_rgb(linear, ColorSpace::Linear, ColorSpace::ProPhotoRGB)
+                .unwrap();
+            assert_rgb_close(back, prophoto);
+        }
+    }
 }
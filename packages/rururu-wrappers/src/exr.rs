@@ -1,6 +1,17 @@
+use std::io::{BufReader, Read};
 use std::path::Path;
 use thiserror::Error;
-use tracing::debug;
+use tracing::{debug, warn};
+
+const EXR_MAGIC: [u8; 4] = [0x76, 0x2f, 0x31, 0x01];
+// Version field flag set when any part holds deep (non-image) data
+const NON_IMAGE_FLAG: u32 = 0x800;
+/// Version flag of files with several parts, each with its own header
+const MULTIPART_FLAG: u32 = 0x1000;
+// Headers are small; reading this much covers every attribute we look at
+const HEADER_PROBE_SIZE: u64 = 64 * 1024;
+// Attributes larger than this (e.g. embedded previews) are skipped unread
+const MAX_FORMATTED_ATTRIBUTE: usize = 64 * 1024;
 
 #[derive(Error, Debug)]
 pub enum ExrError {
@@ -12,6 +23,10 @@ pub enum ExrError {
     WriteError(String),
     #[error("Unsupported EXR feature: {0}")]
     UnsupportedFeature(String),
+    #[error("Not a tiled multi-resolution EXR: {0}")]
+    NotMultiResolution(String),
+    #[error("Resolution level {level} does not exist; the file has {count} levels")]
+    LevelOutOfRange { level: usize, count: usize },
     #[error("IO error: {0}")]
     IoError(#[from] std::io::Error),
 }
@@ -25,7 +40,52 @@ pub struct ExrMetadata {
     pub data_window: (i32, i32, i32, i32),
     pub display_window: (i32, i32, i32, i32),
     pub pixel_aspect_ratio: f32,
+    /// Attributes without a typed field, as text. `save` writes them as
+    /// string attributes; standard names other than `owner`, `comments`,
+    /// `capDate` and `software` are not written back.
     pub attributes: Vec<(String, String)>,
+    /// CIE xy primaries and white point of the pixel values
+    pub chromaticities: Option<Chromaticities>,
+    /// Capture time, `YYYY:MM:DD hh:mm:ss` local time per the EXR spec
+    pub capture_date: Option<String>,
+    /// Tile layout; `None` for scanline files
+    pub tiles: Option<TileDescription>,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Chromaticities {
+    pub red: [f32; 2],
+    pub green: [f32; 2],
+    pub blue: [f32; 2],
+    pub white: [f32; 2],
+}
+
+impl Chromaticities {
+    /// Rec. 709 primaries with a D65 white point, assumed when a file has none
+    pub const REC709: Chromaticities = Chromaticities {
+        red: [0.64, 0.33],
+        green: [0.30, 0.60],
+        blue: [0.15, 0.06],
+        white: [0.3127, 0.3290],
+    };
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct TileDescription {
+    pub tile_width: u32,
+    pub tile_height: u32,
+    pub level_mode: LevelMode,
+    /// Level sizes are rounded up rather than down when halving
+    pub round_up: bool,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum LevelMode {
+    One,
+    /// Each level halves both width and height
+    Mipmap,
+    /// Width and height are halved independently
+    Ripmap,
 }
 
 #[derive(Debug, Clone)]
@@ -57,6 +117,361 @@ pub enum Compression {
     Dwab,
 }
 
+impl ExrMetadata {
+    /// Read the header of the first part without touching any pixel data.
+    ///
+    /// Windows are the file's inclusive `(x_min, y_min, x_max, y_max)` boxes.
+    /// Attributes that aren't mapped to a field are kept in `attributes` when
+    /// they have a textual form (strings, numbers, vectors, boxes).
+    pub fn read<P: AsRef<Path>>(path: P) -> Result<Self, ExrError> {
+        let path = path.as_ref();
+        debug!("Reading EXR header: {:?}", path);
+
+        let file = std::fs::File::open(path)
+            .map_err(|e| ExrError::OpenError(format!("{:?}: {}", path, e)))?;
+        let mut reader = BufReader::new(file);
+
+        let mut preamble = [0u8; 8];
+        reader.read_exact(&mut preamble)?;
+        if preamble[..4] != EXR_MAGIC {
+            return Err(ExrError::OpenError(format!(
+                "{:?} is not an OpenEXR file",
+                path
+            )));
+        }
+
+        let mut metadata = ExrMetadata {
+            width: 0,
+            height: 0,
+            channels: Vec::new(),
+            compression: Compression::None,
+            data_window: (0, 0, 0, 0),
+            display_window: (0, 0, 0, 0),
+            pixel_aspect_ratio: 1.0,
+            attributes: Vec::new(),
+            chromaticities: None,
+            capture_date: None,
+            tiles: None,
+        };
+        let mut has_data_window = false;
+
+        while let Some((name, kind, size)) = read_attribute_header(&mut reader)? {
+            if size > MAX_FORMATTED_ATTRIBUTE {
+                // Skip without buffering
+                std::io::copy(&mut (&mut reader).take(size as u64), &mut std::io::sink())?;
+                continue;
+            }
+
+            let mut value = vec![0u8; size];
+            reader.read_exact(&mut value)?;
+
+            match (name.as_str(), kind.as_str()) {
+                ("channels", "chlist") => metadata.channels = parse_channels(&value)?,
+                ("compression", "compression") => metadata.compression = parse_compression(&value)?,
+                ("dataWindow", "box2i") => {
+                    metadata.data_window = parse_box2i(&value)?;
+                    has_data_window = true;
+                }
+                ("displayWindow", "box2i") => metadata.display_window = parse_box2i(&value)?,
+                ("pixelAspectRatio", "float") => {
+                    metadata.pixel_aspect_ratio = f32::from_le_bytes(fixed(&value)?)
+                }
+                ("chromaticities", "chromaticities") => {
+                    let [rx, ry, gx, gy, bx, by, wx, wy] = parse_f32s::<8>(&value)?;
+                    metadata.chromaticities = Some(Chromaticities {
+                        red: [rx, ry],
+                        green: [gx, gy],
+                        blue: [bx, by],
+                        white: [wx, wy],
+                    });
+                }
+                ("capDate", "string") => {
+                    metadata.capture_date = Some(String::from_utf8_lossy(&value).into_owned())
+                }
+                ("tiles", "tiledesc") => metadata.tiles = Some(parse_tiles(&value)?),
+                _ => {
+                    if let Some(text) = format_attribute(&kind, &value) {
+                        metadata.attributes.push((name, text));
+                    }
+                }
+            }
+        }
+
+        if !has_data_window {
+            return Err(ExrError::ReadError("header has no dataWindow".into()));
+        }
+
+        let (x_min, y_min, x_max, y_max) = metadata.data_window;
+        metadata.width = (x_max as i64 - x_min as i64 + 1).max(0) as u32;
+        metadata.height = (y_max as i64 - y_min as i64 + 1).max(0) as u32;
+
+        Ok(metadata)
+    }
+
+    /// Number of resolution levels in the file; 1 for scanline and
+    /// single-level tiled files
+    pub fn level_count<P: AsRef<Path>>(path: P) -> Result<usize, ExrError> {
+        Ok(Self::read(path)?.level_sizes().len())
+    }
+
+    /// Width and height of each resolution level, full size first. For
+    /// ripmaps these are the levels reduced equally in both directions.
+    pub fn level_sizes(&self) -> Vec<(u32, u32)> {
+        let Some(tiles) = self.tiles.filter(|t| t.level_mode != LevelMode::One) else {
+            return vec![(self.width, self.height)];
+        };
+
+        let count = match tiles.level_mode {
+            LevelMode::Ripmap => levels_along(self.width, tiles.round_up)
+                .min(levels_along(self.height, tiles.round_up)),
+            _ => levels_along(self.width.max(self.height), tiles.round_up),
+        };
+        (0..count)
+            .map(|level| {
+                (
+                    level_size(self.width, level, tiles.round_up),
+                    level_size(self.height, level, tiles.round_up),
+                )
+            })
+            .collect()
+    }
+
+    /// Smallest level that is still at least `min_size` pixels on its longer
+    /// side, e.g. for decoding a thumbnail; 0 when only full size is large
+    /// enough
+    pub fn level_for_size(&self, min_size: u32) -> usize {
+        self.level_sizes()
+            .iter()
+            .rposition(|&(w, h)| w.max(h) >= min_size)
+            .unwrap_or(0)
+    }
+}
+
+/// Levels a mipmap of a `size` pixel axis has, down to a single pixel
+fn levels_along(size: u32, round_up: bool) -> usize {
+    let size = size.max(1);
+    let log2 = 31 - size.leading_zeros();
+    let log2 = if round_up && !size.is_power_of_two() {
+        log2 + 1
+    } else {
+        log2
+    };
+    log2 as usize + 1
+}
+
+fn level_size(size: u32, level: usize, round_up: bool) -> u32 {
+    let divisor = 1u64 << level;
+    let size = size as u64;
+    let reduced = if round_up {
+        size.div_ceil(divisor)
+    } else {
+        size / divisor
+    };
+    reduced.max(1) as u32
+}
+
+/// Next `name\0 type\0 size` triple, or `None` at the end of the header
+fn read_attribute_header<R: Read>(
+    reader: &mut R,
+) -> Result<Option<(String, String, usize)>, ExrError> {
+    let name = read_null_terminated(reader)?;
+    if name.is_empty() {
+        return Ok(None);
+    }
+    let kind = read_null_terminated(reader)?;
+
+    let mut size = [0u8; 4];
+    reader.read_exact(&mut size)?;
+    let size = i32::from_le_bytes(size);
+    if size < 0 {
+        return Err(ExrError::ReadError(format!(
+            "attribute {} has negative size",
+            name
+        )));
+    }
+
+    Ok(Some((name, kind, size as usize)))
+}
+
+fn read_null_terminated<R: Read>(reader: &mut R) -> Result<String, ExrError> {
+    let mut bytes = Vec::new();
+    let mut byte = [0u8; 1];
+    loop {
+        reader.read_exact(&mut byte)?;
+        if byte[0] == 0 {
+            break;
+        }
+        // Names are limited to 255 bytes by the spec
+        if bytes.len() >= 255 {
+            return Err(ExrError::ReadError("attribute name too long".into()));
+        }
+        bytes.push(byte[0]);
+    }
+    Ok(String::from_utf8_lossy(&bytes).into_owned())
+}
+
+fn fixed<const N: usize>(value: &[u8]) -> Result<[u8; N], ExrError> {
+    value
+        .get(..N)
+        .and_then(|v| v.try_into().ok())
+        .ok_or_else(|| ExrError::ReadError("truncated attribute".into()))
+}
+
+fn parse_i32s<const N: usize>(value: &[u8]) -> Result<[i32; N], ExrError> {
+    let mut out = [0i32; N];
+    for (i, v) in out.iter_mut().enumerate() {
+        *v = i32::from_le_bytes(fixed(value.get(i * 4..).unwrap_or_default())?);
+    }
+    Ok(out)
+}
+
+fn parse_f32s<const N: usize>(value: &[u8]) -> Result<[f32; N], ExrError> {
+    let mut out = [0f32; N];
+    for (i, v) in out.iter_mut().enumerate() {
+        *v = f32::from_le_bytes(fixed(value.get(i * 4..).unwrap_or_default())?);
+    }
+    Ok(out)
+}
+
+fn parse_box2i(value: &[u8]) -> Result<(i32, i32, i32, i32), ExrError> {
+    let [x_min, y_min, x_max, y_max] = parse_i32s::<4>(value)?;
+    Ok((x_min, y_min, x_max, y_max))
+}
+
+fn parse_compression(value: &[u8]) -> Result<Compression, ExrError> {
+    match value.first() {
+        Some(0) => Ok(Compression::None),
+        Some(1) => Ok(Compression::Rle),
+        Some(2) => Ok(Compression::ZipsS),
+        Some(3) => Ok(Compression::Zip),
+        Some(4) => Ok(Compression::Piz),
+        Some(5) => Ok(Compression::Pxr24),
+        Some(6) => Ok(Compression::B44),
+        Some(7) => Ok(Compression::B44a),
+        Some(8) => Ok(Compression::Dwaa),
+        Some(9) => Ok(Compression::Dwab),
+        Some(other) => Err(ExrError::UnsupportedFeature(format!(
+            "compression method {}",
+            other
+        ))),
+        None => Err(ExrError::ReadError("empty compression attribute".into())),
+    }
+}
+
+/// `tiledesc`: `x_size(u32) y_size(u32) mode(u8)`, the level mode in the
+/// low nibble of `mode` and the rounding mode in the high one
+fn parse_tiles(value: &[u8]) -> Result<TileDescription, ExrError> {
+    let [tile_width, tile_height] = parse_i32s::<2>(value)?;
+    let mode = *value
+        .get(8)
+        .ok_or_else(|| ExrError::ReadError("truncated attribute".into()))?;
+
+    let level_mode = match mode & 0x0f {
+        0 => LevelMode::One,
+        1 => LevelMode::Mipmap,
+        2 => LevelMode::Ripmap,
+        other => {
+            return Err(ExrError::UnsupportedFeature(format!(
+                "tile level mode {}",
+                other
+            )))
+        }
+    };
+
+    Ok(TileDescription {
+        tile_width: tile_width as u32,
+        tile_height: tile_height as u32,
+        level_mode,
+        round_up: mode >> 4 == 1,
+    })
+}
+
+/// `chlist`: per channel `name\0 pixel_type(i32) pLinear(u8) reserved[3]
+/// x_sampling(i32) y_sampling(i32)`, ended by an empty name
+fn parse_channels(value: &[u8]) -> Result<Vec<ChannelInfo>, ExrError> {
+    let mut channels = Vec::new();
+    let mut reader = value;
+
+    loop {
+        let name = read_null_terminated(&mut reader)?;
+        if name.is_empty() {
+            break;
+        }
+
+        let [pixel_type, _linear_and_reserved, x_sampling, y_sampling] = parse_i32s::<4>(reader)?;
+        reader = &reader[16..];
+
+        let pixel_type = match pixel_type {
+            0 => PixelType::Uint,
+            1 => PixelType::Half,
+            2 => PixelType::Float,
+            other => {
+                return Err(ExrError::ReadError(format!(
+                    "channel {} has unknown pixel type {}",
+                    name, other
+                )))
+            }
+        };
+
+        channels.push(ChannelInfo {
+            name,
+            pixel_type,
+            x_sampling: x_sampling.max(1) as u32,
+            y_sampling: y_sampling.max(1) as u32,
+        });
+    }
+
+    Ok(channels)
+}
+
+/// Text form of attribute types worth showing; `None` for the rest
+fn format_attribute(kind: &str, value: &[u8]) -> Option<String> {
+    match kind {
+        "string" => Some(String::from_utf8_lossy(value).into_owned()),
+        "stringvector" => {
+            // Each entry is size(i32) followed by the bytes
+            let mut entries = Vec::new();
+            let mut rest = value;
+            while rest.len() >= 4 {
+                let len = i32::from_le_bytes(fixed(rest).ok()?).max(0) as usize;
+                let entry = rest.get(4..4 + len)?;
+                entries.push(String::from_utf8_lossy(entry).into_owned());
+                rest = &rest[4 + len..];
+            }
+            Some(entries.join(", "))
+        }
+        "int" => parse_i32s::<1>(value).ok().map(|[v]| v.to_string()),
+        "float" => parse_f32s::<1>(value).ok().map(|[v]| v.to_string()),
+        "double" => fixed::<8>(value)
+            .ok()
+            .map(|b| f64::from_le_bytes(b).to_string()),
+        "rational" => {
+            let [num] = parse_i32s::<1>(value).ok()?;
+            let den = u32::from_le_bytes(fixed(value.get(4..)?).ok()?);
+            Some(format!("{}/{}", num, den))
+        }
+        "v2i" => parse_i32s::<2>(value)
+            .ok()
+            .map(|[x, y]| format!("({}, {})", x, y)),
+        "v2f" => parse_f32s::<2>(value)
+            .ok()
+            .map(|[x, y]| format!("({}, {})", x, y)),
+        "v3f" => parse_f32s::<3>(value)
+            .ok()
+            .map(|[x, y, z]| format!("({}, {}, {})", x, y, z)),
+        "box2i" => parse_box2i(value)
+            .ok()
+            .map(|(a, b, c, d)| format!("({}, {}) - ({}, {})", a, b, c, d)),
+        "lineOrder" => match value.first()? {
+            0 => Some("increasing Y".to_string()),
+            1 => Some("decreasing Y".to_string()),
+            2 => Some("random Y".to_string()),
+            _ => None,
+        },
+        _ => None,
+    }
+}
+
 pub struct ExrImage {
     pub metadata: ExrMetadata,
     pub pixels: Vec<f32>,
@@ -70,6 +485,14 @@ impl ExrImage {
         let path = path.as_ref();
         debug!("Opening EXR file: {:?}", path);
 
+        if Self::is_deep(path) {
+            warn!(
+                "{:?} contains deep data; flatten it (e.g. DeepToImage) before opening",
+                path
+            );
+            return Err(ExrError::UnsupportedFeature("deep data".into()));
+        }
+
         let reader = read()
             .no_deep_data()
             .largest_resolution_level()
@@ -85,42 +508,27 @@ impl ExrImage {
         let layer = reader.layer_data;
         let size = reader.attributes.layer_size;
 
-        let metadata = ExrMetadata {
+        let mut metadata = ExrMetadata {
             width: size.width() as u32,
             height: size.height() as u32,
-            channels: vec![
-                ChannelInfo {
-                    name: "R".to_string(),
-                    pixel_type: PixelType::Float,
-                    x_sampling: 1,
-                    y_sampling: 1,
-                },
-                ChannelInfo {
-                    name: "G".to_string(),
-                    pixel_type: PixelType::Float,
-                    x_sampling: 1,
-                    y_sampling: 1,
-                },
-                ChannelInfo {
-                    name: "B".to_string(),
-                    pixel_type: PixelType::Float,
-                    x_sampling: 1,
-                    y_sampling: 1,
-                },
-                ChannelInfo {
-                    name: "A".to_string(),
-                    pixel_type: PixelType::Float,
-                    x_sampling: 1,
-                    y_sampling: 1,
-                },
-            ],
+            channels: rgba_float_channels(),
             compression: Compression::Zip,
             data_window: (0, 0, size.width() as i32, size.height() as i32),
             display_window: (0, 0, size.width() as i32, size.height() as i32),
             pixel_aspect_ratio: 1.0,
             attributes: Vec::new(),
+            chromaticities: None,
+            capture_date: None,
+            tiles: None,
         };
 
+        // The RGBA reader drops custom attributes; take them from the header
+        if let Ok(header) = ExrMetadata::read(path) {
+            metadata.attributes = header.attributes;
+            metadata.chromaticities = header.chromaticities;
+            metadata.capture_date = header.capture_date;
+        }
+
         let mut pixels = Vec::with_capacity(size.width() * size.height() * 4);
         for (r, g, b, a) in layer.channel_data.pixels.iter() {
             pixels.push(*r);
@@ -137,6 +545,111 @@ impl ExrImage {
         Err(ExrError::UnsupportedFeature("OpenEXR not enabled".into()))
     }
 
+    /// Decode one resolution level of a tiled mipmap or ripmap, 0 being full
+    /// size. Only that level's tiles are read, so a small level is a cheap
+    /// way to get a thumbnail; see [`ExrMetadata::level_for_size`].
+    ///
+    /// `metadata` is the file's header with the level's width and height.
+    #[cfg(feature = "openexr")]
+    pub fn open_level<P: AsRef<Path>>(path: P, level: usize) -> Result<Self, ExrError> {
+        use openexr::block::reader::ChunksReader;
+        use openexr::prelude::*;
+
+        let path = path.as_ref();
+        let header = ExrMetadata::read(path)?;
+        let (width, height) = level_dimensions(path, &header, level)?;
+        if level == 0 {
+            return Self::open(path);
+        }
+        debug!(
+            "Opening level {} ({}x{}) of {:?}",
+            level, width, height, path
+        );
+
+        if Self::is_deep(path) {
+            return Err(ExrError::UnsupportedFeature("deep data".into()));
+        }
+
+        let file = std::fs::File::open(path)
+            .map_err(|e| ExrError::OpenError(format!("{:?}: {}", path, e)))?;
+        let reader = openexr::block::read(BufReader::new(file), false)
+            .map_err(|e| ExrError::OpenError(e.to_string()))?;
+
+        // Where each of the first part's channels goes in an RGBA pixel
+        let channels = &reader.headers()[0].channels.list;
+        let slots: Vec<Option<usize>> = channels
+            .iter()
+            .map(|c| ["R", "G", "B", "A"].iter().position(|n| c.name.eq(n)))
+            .collect();
+        if (0..3).any(|slot| !slots.contains(&Some(slot))) {
+            return Err(ExrError::UnsupportedFeature(
+                "first part has no RGB channels".into(),
+            ));
+        }
+        if channels.iter().any(|c| c.sampling != Vec2(1, 1)) {
+            return Err(ExrError::UnsupportedFeature("subsampled channels".into()));
+        }
+
+        let (w, h) = (width as usize, height as usize);
+        let mut pixels = [0.0, 0.0, 0.0, 1.0].repeat(w * h);
+        let index = Vec2(level, level);
+
+        reader
+            .filter_chunks(false, |_, tile, block| {
+                block.layer == 0 && tile.level_index == index
+            })
+            .map_err(|e| ExrError::ReadError(e.to_string()))?
+            .decompress_sequential(false, |meta, block| {
+                let channels = &meta.headers[0].channels;
+                for line in block.lines(channels) {
+                    let location = line.location;
+                    let Some(slot) = slots[location.channel] else {
+                        continue;
+                    };
+                    let start = (location.position.y() * w + location.position.x()) * 4 + slot;
+                    let Some(row) = pixels.get_mut(start..) else {
+                        continue;
+                    };
+                    let out = row.iter_mut().step_by(4);
+
+                    match channels.list[location.channel].sample_type {
+                        SampleType::F16 => {
+                            for (out, value) in out.zip(line.read_samples::<f16>()) {
+                                *out = value?.to_f32();
+                            }
+                        }
+                        SampleType::F32 => {
+                            for (out, value) in out.zip(line.read_samples::<f32>()) {
+                                *out = value?;
+                            }
+                        }
+                        SampleType::U32 => {
+                            for (out, value) in out.zip(line.read_samples::<u32>()) {
+                                *out = value? as f32;
+                            }
+                        }
+                    }
+                }
+                Ok(())
+            })
+            .map_err(|e| ExrError::ReadError(e.to_string()))?;
+
+        let metadata = ExrMetadata {
+            width,
+            height,
+            channels: rgba_float_channels(),
+            ..header
+        };
+        Ok(Self { metadata, pixels })
+    }
+
+    #[cfg(not(feature = "openexr"))]
+    pub fn open_level<P: AsRef<Path>>(path: P, level: usize) -> Result<Self, ExrError> {
+        let path = path.as_ref();
+        level_dimensions(path, &ExrMetadata::read(path)?, level)?;
+        Err(ExrError::UnsupportedFeature("OpenEXR not enabled".into()))
+    }
+
     #[cfg(feature = "openexr")]
     pub fn save<P: AsRef<Path>>(&self, path: P) -> Result<(), ExrError> {
         use openexr::prelude::*;
@@ -155,12 +668,23 @@ impl ExrImage {
 
         let layer = Layer::new(
             size,
-            LayerAttributes::named("main"),
+            self.layer_attributes()?,
             Encoding::SMALL_LOSSLESS,
             SpecificChannels::rgba(|pos: Vec2<usize>| rgba_data[pos.y() * size.0 + pos.x()]),
         );
 
-        layer
+        let mut image = Image::from_layer(layer);
+        image.attributes.chromaticities =
+            self.metadata
+                .chromaticities
+                .map(|c| openexr::meta::attribute::Chromaticities {
+                    red: Vec2(c.red[0], c.red[1]),
+                    green: Vec2(c.green[0], c.green[1]),
+                    blue: Vec2(c.blue[0], c.blue[1]),
+                    white: Vec2(c.white[0], c.white[1]),
+                });
+
+        image
             .write()
             .to_file(path)
             .map_err(|e| ExrError::WriteError(e.to_string()))?;
@@ -168,11 +692,66 @@ impl ExrImage {
         Ok(())
     }
 
+    /// Header attributes of the saved layer, built from `metadata`
+    #[cfg(feature = "openexr")]
+    fn layer_attributes(&self) -> Result<openexr::prelude::LayerAttributes, ExrError> {
+        use openexr::meta::header::standard_names;
+        use openexr::prelude::*;
+
+        // Header strings are Latin-1
+        let text = |name: &str, value: &str| {
+            Text::new_or_none(value).ok_or_else(|| {
+                ExrError::WriteError(format!("attribute {} has characters outside Latin-1", name))
+            })
+        };
+
+        let mut attributes = LayerAttributes::named("main");
+        if let Some(date) = &self.metadata.capture_date {
+            attributes.capture_date = Some(text("capDate", date)?);
+        }
+
+        for (name, value) in &self.metadata.attributes {
+            match name.as_str() {
+                "owner" => attributes.owner = Some(text(name, value)?),
+                "comments" => attributes.comments = Some(text(name, value)?),
+                "software" => attributes.software_name = Some(text(name, value)?),
+                "capDate" => {
+                    if attributes.capture_date.is_none() {
+                        attributes.capture_date = Some(text(name, value)?);
+                    }
+                }
+                _ if standard_names::ALL.contains(&name.as_bytes()) => {
+                    // Typed in the file; the text form can't be written back
+                    debug!("Not writing standard EXR attribute {} as text", name);
+                }
+                _ => {
+                    attributes
+                        .other
+                        .insert(text(name, name)?, AttributeValue::Text(text(name, value)?));
+                }
+            }
+        }
+
+        Ok(attributes)
+    }
+
     #[cfg(not(feature = "openexr"))]
     pub fn save<P: AsRef<Path>>(&self, _path: P) -> Result<(), ExrError> {
         Err(ExrError::UnsupportedFeature("OpenEXR not enabled".into()))
     }
 
+    /// Returns true if the file is a deep scanline or deep tiled EXR.
+    ///
+    /// Only the header is inspected, so this is cheap enough to call before
+    /// deciding how to preview a file. Unreadable or non-EXR files are not deep.
+    pub fn is_deep<P: AsRef<Path>>(path: P) -> bool {
+        let mut header = Vec::new();
+        let read = std::fs::File::open(path.as_ref())
+            .and_then(|f| f.take(HEADER_PROBE_SIZE).read_to_end(&mut header));
+
+        read.is_ok() && header_is_deep(&header)
+    }
+
     pub fn width(&self) -> u32 {
         self.metadata.width
     }
@@ -208,37 +787,15 @@ impl ExrImage {
         let metadata = ExrMetadata {
             width,
             height,
-            channels: vec![
-                ChannelInfo {
-                    name: "R".to_string(),
-                    pixel_type: PixelType::Float,
-                    x_sampling: 1,
-                    y_sampling: 1,
-                },
-                ChannelInfo {
-                    name: "G".to_string(),
-                    pixel_type: PixelType::Float,
-                    x_sampling: 1,
-                    y_sampling: 1,
-                },
-                ChannelInfo {
-                    name: "B".to_string(),
-                    pixel_type: PixelType::Float,
-                    x_sampling: 1,
-                    y_sampling: 1,
-                },
-                ChannelInfo {
-                    name: "A".to_string(),
-                    pixel_type: PixelType::Float,
-                    x_sampling: 1,
-                    y_sampling: 1,
-                },
-            ],
+            channels: rgba_float_channels(),
             compression: Compression::Zip,
             data_window: (0, 0, width as i32, height as i32),
             display_window: (0, 0, width as i32, height as i32),
             pixel_aspect_ratio: 1.0,
             attributes: Vec::new(),
+            chromaticities: None,
+            capture_date: None,
+            tiles: None,
         };
 
         Self {
@@ -274,6 +831,93 @@ impl ExrImage {
     }
 }
 
+/// Size of `level`, or why the file doesn't have it
+fn level_dimensions(
+    path: &Path,
+    header: &ExrMetadata,
+    level: usize,
+) -> Result<(u32, u32), ExrError> {
+    let sizes = header.level_sizes();
+    if level > 0 && sizes.len() == 1 {
+        return Err(ExrError::NotMultiResolution(format!("{:?}", path)));
+    }
+    sizes.get(level).copied().ok_or(ExrError::LevelOutOfRange {
+        level,
+        count: sizes.len(),
+    })
+}
+
+/// What `ExrImage` holds in `pixels`, whatever the file stores
+fn rgba_float_channels() -> Vec<ChannelInfo> {
+    ["R", "G", "B", "A"]
+        .iter()
+        .map(|name| ChannelInfo {
+            name: name.to_string(),
+            pixel_type: PixelType::Float,
+            x_sampling: 1,
+            y_sampling: 1,
+        })
+        .collect()
+}
+
+fn header_is_deep(data: &[u8]) -> bool {
+    if data.len() < 8 || data[..4] != EXR_MAGIC {
+        return false;
+    }
+
+    let version = u32::from_le_bytes([data[4], data[5], data[6], data[7]]);
+    if version & NON_IMAGE_FLAG != 0 {
+        return true;
+    }
+
+    // Writers are supposed to set the flag, but check each part's `type`
+    // attribute as well: name\0 type\0 size(i32) value. A NUL ends a header;
+    // what follows is another part's header only in multi-part files, which
+    // end the list with an empty one. Otherwise it's the offset table and
+    // pixel data, which can hold anything.
+    let multipart = version & MULTIPART_FLAG != 0;
+    let mut pos = 8;
+    while pos < data.len() {
+        if data[pos] == 0 {
+            if !multipart || data.get(pos + 1) == Some(&0) {
+                break;
+            }
+            pos += 1;
+            continue;
+        }
+
+        let Some(name_end) = data[pos..].iter().position(|&b| b == 0) else {
+            break;
+        };
+        let name = &data[pos..pos + name_end];
+        pos += name_end + 1;
+
+        let Some(type_end) = data.get(pos..).and_then(|d| d.iter().position(|&b| b == 0)) else {
+            break;
+        };
+        pos += type_end + 1;
+
+        let Some(size_bytes) = data.get(pos..pos + 4) else {
+            break;
+        };
+        let size = i32::from_le_bytes([size_bytes[0], size_bytes[1], size_bytes[2], size_bytes[3]]);
+        pos += 4;
+        if size < 0 {
+            break;
+        }
+
+        let Some(value) = data.get(pos..pos + size as usize) else {
+            break;
+        };
+        if name == b"type" && value.starts_with(b"deep") {
+            return true;
+        }
+        pos += size as usize;
+    }
+
+    false
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
@@ -297,6 +941,236 @@ mod tests {
         assert_eq!(pixel[3], 1.0);
     }
 
+    fn write_header(version: u32, part_type: &str) -> tempfile::NamedTempFile {
+        use std::io::Write;
+
+        let mut data = EXR_MAGIC.to_vec();
+        data.extend_from_slice(&version.to_le_bytes());
+        data.extend_from_slice(b"type\0string\0");
+        data.extend_from_slice(&(part_type.len() as i32).to_le_bytes());
+        data.extend_from_slice(part_type.as_bytes());
+        data.push(0);
+
+        let mut file = tempfile::NamedTempFile::new().unwrap();
+        file.write_all(&data).unwrap();
+        file
+    }
+
+    #[test]
+    fn test_deep_detection() {
+        let deep = write_header(2 | NON_IMAGE_FLAG, "deepscanline");
+        assert!(ExrImage::is_deep(deep.path()));
+
+        // Missing version flag still caught by the type attribute
+        let unflagged = write_header(2, "deeptile");
+        assert!(ExrImage::is_deep(unflagged.path()));
+
+        let flat = write_header(2, "scanlineimage");
+        assert!(!ExrImage::is_deep(flat.path()));
+
+        // Pixel data after a single part's header isn't parsed as attributes
+        let mut data = std::fs::read(flat.path()).unwrap();
+        data.extend_from_slice(&[0; 8]);
+        attribute(&mut data, "type", "string", b"deepscanline");
+        std::fs::write(flat.path(), &data).unwrap();
+        assert!(!ExrImage::is_deep(flat.path()));
+
+        // Every part of a multi-part file is checked
+        let multipart = write_header(2 | MULTIPART_FLAG, "scanlineimage");
+        let mut data = std::fs::read(multipart.path()).unwrap();
+        attribute(&mut data, "type", "string", b"deeptile");
+        data.extend_from_slice(&[0, 0]);
+        std::fs::write(multipart.path(), &data).unwrap();
+        assert!(ExrImage::is_deep(multipart.path()));
+
+        match ExrImage::open(deep.path()) {
+            Err(ExrError::UnsupportedFeature(feature)) => assert_eq!(feature, "deep data"),
+            _ => panic!("deep EXR should be rejected"),
+        }
+    }
+
+    fn attribute(data: &mut Vec<u8>, name: &str, kind: &str, value: &[u8]) {
+        data.extend_from_slice(name.as_bytes());
+        data.push(0);
+        data.extend_from_slice(kind.as_bytes());
+        data.push(0);
+        data.extend_from_slice(&(value.len() as i32).to_le_bytes());
+        data.extend_from_slice(value);
+    }
+
+    #[test]
+    fn test_metadata_read() {
+        use std::io::Write;
+
+        let mut channels = Vec::new();
+        for (name, pixel_type) in [("B", 1i32), ("G", 1), ("R", 1), ("Z", 2)] {
+            channels.extend_from_slice(name.as_bytes());
+            channels.push(0);
+            channels.extend_from_slice(&pixel_type.to_le_bytes());
+            channels.extend_from_slice(&[0, 0, 0, 0]);
+            channels.extend_from_slice(&1i32.to_le_bytes());
+            channels.extend_from_slice(&1i32.to_le_bytes());
+        }
+        channels.push(0);
+
+        let window: Vec<u8> = [0i32, 0, 1919, 1079]
+            .iter()
+            .flat_map(|v| v.to_le_bytes())
+            .collect();
+
+        let mut data = EXR_MAGIC.to_vec();
+        data.extend_from_slice(&2u32.to_le_bytes());
+        attribute(&mut data, "channels", "chlist", &channels);
+        attribute(&mut data, "compression", "compression", &[4]);
+        attribute(&mut data, "dataWindow", "box2i", &window);
+        attribute(&mut data, "displayWindow", "box2i", &window);
+        attribute(
+            &mut data,
+            "pixelAspectRatio",
+            "float",
+            &1.0f32.to_le_bytes(),
+        );
+        attribute(&mut data, "owner", "string", b"Lighting Dept");
+        attribute(
+            &mut data,
+            "framesPerSecond",
+            "rational",
+            &[24, 0, 0, 0, 1, 0, 0, 0],
+        );
+        data.push(0);
+        // Offset table and scanlines are never looked at
+        data.extend_from_slice(&[0xff; 64]);
+
+        let mut file = tempfile::NamedTempFile::new().unwrap();
+        file.write_all(&data).unwrap();
+
+        let meta = ExrMetadata::read(file.path()).unwrap();
+        assert_eq!((meta.width, meta.height), (1920, 1080));
+        assert_eq!(meta.compression, Compression::Piz);
+        assert_eq!(meta.channels.len(), 4);
+        assert_eq!(meta.channels[3].name, "Z");
+        assert_eq!(meta.channels[3].pixel_type, PixelType::Float);
+        assert_eq!(meta.display_window, (0, 0, 1919, 1079));
+        assert!(meta
+            .attributes
+            .contains(&("owner".to_string(), "Lighting Dept".to_string())));
+        assert!(meta
+            .attributes
+            .contains(&("framesPerSecond".to_string(), "24/1".to_string())));
+
+        let not_exr = write_header(2, "scanlineimage");
+        std::fs::write(not_exr.path(), b"not an exr file").unwrap();
+        assert!(matches!(
+            ExrMetadata::read(not_exr.path()),
+            Err(ExrError::OpenError(_))
+        ));
+    }
+
+    fn write_tiled_header(width: i32, height: i32, mode: u8) -> tempfile::NamedTempFile {
+        use std::io::Write;
+
+        let window: Vec<u8> = [0, 0, width - 1, height - 1]
+            .iter()
+            .flat_map(|v| v.to_le_bytes())
+            .collect();
+        let mut tiles = [64u32.to_le_bytes(), 64u32.to_le_bytes()].concat();
+        tiles.push(mode);
+
+        let mut data = EXR_MAGIC.to_vec();
+        data.extend_from_slice(&0x202u32.to_le_bytes());
+        attribute(&mut data, "dataWindow", "box2i", &window);
+        attribute(&mut data, "tiles", "tiledesc", &tiles);
+        data.push(0);
+
+        let mut file = tempfile::NamedTempFile::new().unwrap();
+        file.write_all(&data).unwrap();
+        file
+    }
+
+    #[test]
+    fn test_resolution_levels() {
+        let mipmap = write_tiled_header(1920, 1080, 0x01);
+        let meta = ExrMetadata::read(mipmap.path()).unwrap();
+        assert_eq!(meta.tiles.unwrap().level_mode, LevelMode::Mipmap);
+        assert_eq!(ExrMetadata::level_count(mipmap.path()).unwrap(), 11);
+        let sizes = meta.level_sizes();
+        assert_eq!(sizes[1], (960, 540));
+        assert_eq!(sizes[3], (240, 135));
+        assert_eq!(sizes[4], (120, 67));
+        assert_eq!(sizes[10], (1, 1));
+        assert_eq!(meta.level_for_size(256), 2);
+        assert_eq!(meta.level_for_size(4096), 0);
+
+        // Rounding up keeps odd sizes from losing a pixel
+        let round_up = write_tiled_header(1920, 1080, 0x11);
+        let meta = ExrMetadata::read(round_up.path()).unwrap();
+        assert_eq!(meta.level_sizes()[4], (120, 68));
+        assert_eq!(meta.level_sizes().len(), 12);
+
+        // Ripmaps only expose the levels halved in both directions
+        let ripmap = write_tiled_header(512, 128, 0x02);
+        assert_eq!(ExrMetadata::level_count(ripmap.path()).unwrap(), 8);
+
+        assert!(matches!(
+            ExrImage::open_level(mipmap.path(), 11),
+            Err(ExrError::LevelOutOfRange {
+                level: 11,
+                count: 11
+            })
+        ));
+
+        let single = write_tiled_header(256, 256, 0x00);
+        assert_eq!(ExrMetadata::level_count(single.path()).unwrap(), 1);
+        assert!(matches!(
+            ExrImage::open_level(single.path(), 1),
+            Err(ExrError::NotMultiResolution(_))
+        ));
+    }
+
+    #[test]
+    fn test_attributes_round_trip() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("shot.exr");
+
+        let aces = Chromaticities {
+            red: [0.713, 0.293],
+            green: [0.165, 0.830],
+            blue: [0.128, 0.044],
+            white: [0.32168, 0.33767],
+        };
+
+        let mut img = ExrImage::new(4, 2);
+        img.set_pixel(1, 1, [0.5, 0.25, 2.0, 1.0]);
+        img.metadata.attributes = vec![
+            ("software".to_string(), "rururu-render 1.2".to_string()),
+            ("shot".to_string(), "sq010_sh0040".to_string()),
+        ];
+        img.metadata.chromaticities = Some(aces);
+        img.metadata.capture_date = Some("2026:10:14 09:30:00".to_string());
+        img.save(&path).unwrap();
+
+        let reopened = ExrImage::open(&path).unwrap();
+        let meta = &reopened.metadata;
+        for attribute in &img.metadata.attributes {
+            assert!(meta.attributes.contains(attribute), "{:?}", attribute);
+        }
+        assert_eq!(meta.chromaticities, Some(aces));
+        assert_eq!(meta.capture_date.as_deref(), Some("2026:10:14 09:30:00"));
+        assert_eq!(reopened.get_pixel(1, 1), Some([0.5, 0.25, 2.0, 1.0]));
+
+        // Saving again doesn't duplicate the header's standard attributes
+        reopened.save(&path).unwrap();
+        let again = ExrMetadata::read(&path).unwrap();
+        let names: Vec<_> = again.attributes.iter().map(|(n, _)| n).collect();
+        let unique: std::collections::HashSet<_> = names.iter().collect();
+        assert_eq!(names.len(), unique.len(), "{:?}", names);
+
+        img.metadata
+            .attributes
+            .push(("artist".to_string(), "Łukasz".to_string()));
+        assert!(matches!(img.save(&path), Err(ExrError::WriteError(_))));
+    }
+
     #[test]
     fn test_tonemap() {
         let mut img = ExrImage::new(2, 2);
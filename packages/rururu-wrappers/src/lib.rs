@@ -1,4 +1,5 @@
 pub mod color;
+pub mod transfer;
 
 #[cfg(feature = "openexr")]
 pub mod exr;
@@ -6,7 +7,8 @@ pub mod exr;
 #[cfg(feature = "assimp")]
 pub mod model3d;
 
-pub use color::ColorManager;
+pub use color::{ChromaticAdaptation, ColorManager, CvdType};
+pub use transfer::TransferMode;
 
 #[cfg(feature = "openexr")]
 pub use exr::{ExrImage, ExrMetadata};
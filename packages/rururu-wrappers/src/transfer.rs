@@ -0,0 +1,288 @@
+//! Transfer functions: sRGB, with lookup tables, ProPhoto's ROMM curve and
+//! the BT.2100 HDR curves.
+//!
+//! `powf` per component dominates buffer transforms, so sRGB decoding and
+//! encoding go through a 4096-entry table with linear interpolation instead.
+//! Values outside 0..=1 (HDR highlights, negative out-of-gamut components)
+//! fall back to the exact formula. 8-bit inputs have an exact 256-entry
+//! decode table.
+//!
+//! PQ (SMPTE ST 2084) and HLG (ARIB STD-B67) work in absolute luminance:
+//! PQ codes map to 0-10000 cd/m², and HLG scene light reaches the display
+//! through the BT.2100 OOTF for a given peak.
+
+use std::sync::OnceLock;
+
+/// Intervals in each interpolated table
+pub const LUT_SIZE: usize = 4096;
+
+/// Exact formula or table lookup
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum TransferMode {
+    Exact,
+    /// Interpolated tables; within 2e-5 of exact on 0..=1
+    #[default]
+    Lut,
+}
+
+/// Luminance of PQ code 1.0, in cd/m²
+pub const PQ_MAX_NITS: f32 = 10000.0;
+/// Peak of the BT.2100 HLG reference display, in cd/m²
+pub const HLG_PEAK_NITS: f32 = 1000.0;
+/// BT.2408 diffuse white, where PQ and HLG place SDR white, in cd/m²
+pub const REFERENCE_WHITE_NITS: f32 = 203.0;
+
+const PQ_M1: f64 = 2610.0 / 16384.0;
+const PQ_M2: f64 = 2523.0 / 4096.0 * 128.0;
+const PQ_C1: f64 = 3424.0 / 4096.0;
+const PQ_C2: f64 = 2413.0 / 4096.0 * 32.0;
+const PQ_C3: f64 = 2392.0 / 4096.0 * 32.0;
+
+const HLG_A: f64 = 0.17883277;
+const HLG_B: f64 = 1.0 - 4.0 * HLG_A;
+// 0.5 - a·ln(4a), which isn't const
+const HLG_C: f64 = 0.55991073;
+
+/// BT.2020 luminance weights
+const BT2020_LUMA: [f64; 3] = [0.2627, 0.6780, 0.0593];
+
+pub fn srgb_decode(c: f32) -> f32 {
+    if c <= 0.04045 {
+        c / 12.92
+    } else {
+        ((c + 0.055) / 1.055).powf(2.4)
+    }
+}
+
+pub fn srgb_encode(c: f32) -> f32 {
+    if c <= 0.0031308 {
+        c * 12.92
+    } else {
+        1.055 * c.powf(1.0 / 2.4) - 0.055
+    }
+}
+
+/// ROMM RGB (ProPhoto) decoding: gamma 1.8 with a linear toe below 16/512
+pub fn prophoto_decode(c: f32) -> f32 {
+    if c < 16.0 / 512.0 {
+        c / 16.0
+    } else {
+        c.powf(1.8)
+    }
+}
+
+pub fn prophoto_encode(c: f32) -> f32 {
+    if c < 1.0 / 512.0 {
+        c * 16.0
+    } else {
+        c.powf(1.0 / 1.8)
+    }
+}
+
+/// Display luminance in cd/m² of a PQ code (the ST 2084 EOTF)
+pub fn pq_eotf(signal: f32) -> f32 {
+    let e = (signal.max(0.0) as f64).powf(1.0 / PQ_M2);
+    let y = ((e - PQ_C1).max(0.0) / (PQ_C2 - PQ_C3 * e)).powf(1.0 / PQ_M1);
+    (y * PQ_MAX_NITS as f64) as f32
+}
+
+/// PQ code of a display luminance in cd/m²; negative luminance is black
+pub fn pq_inverse_eotf(nits: f32) -> f32 {
+    let y = (nits.max(0.0) as f64 / PQ_MAX_NITS as f64).powf(PQ_M1);
+    ((PQ_C1 + PQ_C2 * y) / (1.0 + PQ_C3 * y)).powf(PQ_M2) as f32
+}
+
+/// HLG signal of normalized scene light, 1.0 being the brightest the
+/// camera records (the BT.2100 OETF)
+pub fn hlg_oetf(e: f32) -> f32 {
+    let e = e.max(0.0) as f64;
+    if e <= 1.0 / 12.0 {
+        (3.0 * e).sqrt() as f32
+    } else {
+        (HLG_A * (12.0 * e - HLG_B).ln() + HLG_C) as f32
+    }
+}
+
+pub fn hlg_inverse_oetf(signal: f32) -> f32 {
+    let signal = signal.max(0.0) as f64;
+    if signal <= 0.5 {
+        (signal * signal / 3.0) as f32
+    } else {
+        ((((signal - HLG_C) / HLG_A).exp() + HLG_B) / 12.0) as f32
+    }
+}
+
+/// Exponent the HLG OOTF applies for a display peaking at `peak_nits`,
+/// 1.2 on the 1000 cd/m² reference display
+pub fn hlg_system_gamma(peak_nits: f32) -> f32 {
+    1.2 + 0.42 * (peak_nits / 1000.0).log10()
+}
+
+/// Display light in cd/m² for BT.2020 scene light from `hlg_inverse_oetf`.
+/// The OOTF scales all three channels by a function of luminance, so hues
+/// survive.
+pub fn hlg_ootf(scene: [f32; 3], peak_nits: f32) -> [f32; 3] {
+    let gamma = hlg_system_gamma(peak_nits) as f64;
+    let luminance = luma(scene);
+    if luminance <= 0.0 {
+        return [0.0; 3];
+    }
+    let scale = peak_nits as f64 * luminance.powf(gamma - 1.0);
+    scene.map(|e| (e as f64 * scale) as f32)
+}
+
+/// Scene light for display light in cd/m², the inverse of `hlg_ootf`
+pub fn hlg_inverse_ootf(display: [f32; 3], peak_nits: f32) -> [f32; 3] {
+    let gamma = hlg_system_gamma(peak_nits) as f64;
+    let luminance = luma(display) / peak_nits as f64;
+    if luminance <= 0.0 {
+        return [0.0; 3];
+    }
+    let scene_luminance = luminance.powf(1.0 / gamma);
+    let scale = 1.0 / (peak_nits as f64 * scene_luminance.powf(gamma - 1.0));
+    display.map(|f| (f as f64 * scale) as f32)
+}
+
+fn luma(rgb: [f32; 3]) -> f64 {
+    rgb.iter()
+        .zip(BT2020_LUMA)
+        .map(|(&c, w)| c as f64 * w)
+        .sum()
+}
+
+/// A transfer function sampled at `LUT_SIZE + 1` evenly spaced points on
+/// 0..=1, stored as (value, slope) per interval so a lookup is one load.
+pub struct TransferLut {
+    table: Box<[[f32; 2]; LUT_SIZE]>,
+    exact: fn(f32) -> f32,
+}
+
+impl TransferLut {
+    pub fn new(exact: fn(f32) -> f32) -> Self {
+        let sample = |i: usize| exact(i as f32 / LUT_SIZE as f32);
+        let table = Box::new(std::array::from_fn(|i| {
+            let value = sample(i);
+            [value, sample(i + 1) - value]
+        }));
+        Self { table, exact }
+    }
+
+    #[inline]
+    pub fn apply(&self, c: f32) -> f32 {
+        if !(0.0..=1.0).contains(&c) {
+            return (self.exact)(c);
+        }
+
+        let pos = c * LUT_SIZE as f32;
+        let i = (pos as usize).min(LUT_SIZE - 1);
+        let [value, slope] = self.table[i];
+        value + slope * (pos - i as f32)
+    }
+}
+
+pub fn srgb_decode_lut() -> &'static TransferLut {
+    static LUT: OnceLock<TransferLut> = OnceLock::new();
+    LUT.get_or_init(|| TransferLut::new(srgb_decode))
+}
+
+pub fn srgb_encode_lut() -> &'static TransferLut {
+    static LUT: OnceLock<TransferLut> = OnceLock::new();
+    LUT.get_or_init(|| TransferLut::new(srgb_encode))
+}
+
+/// Linear value of every 8-bit sRGB code, computed exactly
+pub fn srgb_u8_table() -> &'static [f32; 256] {
+    static TABLE: OnceLock<[f32; 256]> = OnceLock::new();
+    TABLE.get_or_init(|| std::array::from_fn(|i| srgb_decode(i as f32 / 255.0)))
+}
+
+pub fn decode(c: f32, mode: TransferMode) -> f32 {
+    match mode {
+        TransferMode::Exact => srgb_decode(c),
+        TransferMode::Lut => srgb_decode_lut().apply(c),
+    }
+}
+
+pub fn encode(c: f32, mode: TransferMode) -> f32 {
+    match mode {
+        TransferMode::Exact => srgb_encode(c),
+        TransferMode::Lut => srgb_encode_lut().apply(c),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_lut_matches_exact() {
+        for i in 0..=10_000 {
+            let c = i as f32 / 10_000.0;
+            assert!((decode(c, TransferMode::Lut) - srgb_decode(c)).abs() < 2e-5);
+            assert!((encode(c, TransferMode::Lut) - srgb_encode(c)).abs() < 2e-5);
+        }
+    }
+
+    #[test]
+    fn test_lut_out_of_range_is_exact() {
+        for c in [-0.5, 1.5, 16.0] {
+            assert_eq!(decode(c, TransferMode::Lut), srgb_decode(c));
+            assert_eq!(encode(c, TransferMode::Lut), srgb_encode(c));
+        }
+    }
+
+    #[test]
+    fn test_u8_table() {
+        let table = srgb_u8_table();
+        assert_eq!(table[0], 0.0);
+        assert!((table[255] - 1.0).abs() < 1e-6);
+        assert_eq!(table[128], srgb_decode(128.0 / 255.0));
+    }
+
+    #[test]
+    fn test_pq_reference_points() {
+        // ST 2084 codes for 0, 100, the 203 cd/m² reference white and 10000
+        assert_eq!(pq_inverse_eotf(0.0), pq_inverse_eotf(-5.0));
+        assert!(pq_inverse_eotf(0.0) < 1e-6);
+        assert!((pq_inverse_eotf(100.0) - 0.5081).abs() < 1e-4);
+        assert!((pq_inverse_eotf(REFERENCE_WHITE_NITS) - 0.5806).abs() < 1e-4);
+        assert!((pq_inverse_eotf(PQ_MAX_NITS) - 1.0).abs() < 1e-6);
+
+        assert!((pq_eotf(0.5081) - 100.0).abs() < 0.05);
+        assert!((pq_eotf(1.0) - PQ_MAX_NITS).abs() < 0.5);
+        assert_eq!(pq_eotf(0.0), 0.0);
+    }
+
+    #[test]
+    fn test_pq_roundtrip() {
+        for nits in [0.005, 0.1, 1.0, 48.0, 203.0, 1000.0, 4000.0, 10000.0] {
+            let back = pq_eotf(pq_inverse_eotf(nits));
+            assert!((back - nits).abs() / nits < 1e-4, "{} -> {}", nits, back);
+        }
+    }
+
+    #[test]
+    fn test_hlg_reference_points() {
+        assert_eq!(hlg_oetf(0.0), 0.0);
+        assert!((hlg_oetf(1.0 / 12.0) - 0.5).abs() < 1e-6);
+        assert!((hlg_oetf(1.0) - 1.0).abs() < 1e-5);
+        assert!((hlg_system_gamma(HLG_PEAK_NITS) - 1.2).abs() < 1e-6);
+
+        for e in [0.001, 0.05, 1.0 / 12.0, 0.3, 0.8, 1.0] {
+            let back = hlg_inverse_oetf(hlg_oetf(e));
+            assert!((back - e).abs() < 1e-5, "{} -> {}", e, back);
+        }
+
+        // BT.2408: 75% HLG is the 203 cd/m² reference white on a 1000 cd/m²
+        // display
+        let scene = [hlg_inverse_oetf(0.75); 3];
+        let display = hlg_ootf(scene, HLG_PEAK_NITS);
+        for c in display {
+            assert!((c - REFERENCE_WHITE_NITS).abs() < 0.5, "{}", c);
+        }
+        let back = hlg_inverse_ootf(display, HLG_PEAK_NITS);
+        for (a, b) in back.iter().zip(&scene) {
+            assert!((a - b).abs() < 1e-5);
+        }
+    }
+}